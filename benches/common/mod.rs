@@ -0,0 +1,54 @@
+//! Shared setup for the QilbeeDB benchmarks
+
+// Each bench binary uses a different subset of these helpers
+#![allow(dead_code)]
+
+use criterion::Criterion;
+use qilbeedb::core::dataset::Dataset;
+use qilbeedb::{Database, Graph, GraphId, StorageEngine, StorageOptions};
+use std::time::Duration;
+use tempfile::TempDir;
+
+/// Seed for every generated dataset, so runs are comparable
+pub const SEED: u64 = 0x5EED;
+
+/// Criterion settings shared by all benchmarks
+///
+/// A fixed sample count and measurement window keep results comparable
+/// between machines and CI runs.
+pub fn criterion() -> Criterion {
+    Criterion::default()
+        .sample_size(50)
+        .warm_up_time(Duration::from_secs(1))
+        .measurement_time(Duration::from_secs(5))
+        .noise_threshold(0.05)
+}
+
+/// Open an empty storage engine in a temporary directory
+pub fn empty_engine() -> (StorageEngine, GraphId, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let engine = StorageEngine::open(StorageOptions::for_testing(dir.path())).unwrap();
+    (engine, GraphId::from_name("bench"), dir)
+}
+
+/// Open a storage engine holding a seeded social graph
+pub fn seeded_engine(node_count: usize, degree: usize) -> (StorageEngine, GraphId, TempDir) {
+    let (engine, graph_id, dir) = empty_engine();
+    let dataset = Dataset::social(SEED, node_count, degree);
+    engine
+        .bulk_seed(graph_id, &dataset.nodes, &dataset.relationships)
+        .unwrap();
+    (engine, graph_id, dir)
+}
+
+/// Open a database whose `bench` graph holds a seeded social graph
+pub fn seeded_graph(node_count: usize, degree: usize) -> (Database, Graph, TempDir) {
+    let dir = TempDir::new().unwrap();
+    let db = Database::open_for_testing(dir.path()).unwrap();
+    let graph = db.graph("bench").unwrap();
+    let dataset = Dataset::social(SEED, node_count, degree);
+    db.storage()
+        .bulk_seed(graph.id(), &dataset.nodes, &dataset.relationships)
+        .unwrap();
+    (db, graph, dir)
+}
@@ -0,0 +1,72 @@
+//! Query execution benchmarks over a seeded social graph
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use qilbeedb::query::{parse_simple, QueryExecutor, QueryPlanner};
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::sync::Arc;
+
+/// People in the seeded graph
+const NODES: usize = 10_000;
+
+/// Outgoing `KNOWS` relationships per person
+const DEGREE: usize = 5;
+
+/// Queries measured, by benchmark name
+const QUERIES: &[(&str, &str)] = &[
+    ("label_scan", "MATCH (p:Person) RETURN p.name AS name"),
+    (
+        "filter_equality",
+        "MATCH (p:Person) WHERE p.city = 'Lisbon' RETURN p.name AS name",
+    ),
+    (
+        "filter_range",
+        "MATCH (p:Person) WHERE p.age > 60 RETURN p.name AS name",
+    ),
+    (
+        "order_limit",
+        "MATCH (p:Person) RETURN p.name AS name ORDER BY p.age LIMIT 10",
+    ),
+    (
+        "aggregate",
+        "MATCH (p:Person) RETURN p.city, count(*) AS total",
+    ),
+    (
+        "expand_two_hops",
+        "MATCH (a:Person)-[:KNOWS*1..2]->(b) WHERE a.name = 'Person42' RETURN b.name AS name",
+    ),
+];
+
+fn query_execution(c: &mut Criterion) {
+    let (_db, graph, _dir) = common::seeded_graph(NODES, DEGREE);
+    let planner = QueryPlanner::new().with_graph_schema(&graph).unwrap();
+    let executor = QueryExecutor::new(Arc::new(graph));
+    let params = HashMap::new();
+
+    let mut group = c.benchmark_group("query");
+    for (name, cypher) in QUERIES {
+        let plan = planner.plan(&parse_simple(cypher).unwrap()).unwrap();
+        group.bench_function(*name, |b| {
+            b.iter(|| black_box(executor.execute(&plan, &params).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn query_planning(c: &mut Criterion) {
+    let planner = QueryPlanner::new();
+    let (_, cypher) = QUERIES[1];
+
+    c.bench_function("query/parse_and_plan", |b| {
+        b.iter(|| black_box(planner.plan(&parse_simple(cypher).unwrap()).unwrap()))
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = query_execution, query_planning
+}
+criterion_main!(benches);
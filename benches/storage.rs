@@ -0,0 +1,71 @@
+//! Storage engine benchmarks: node writes and reads, label scans and
+//! property index lookups
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use qilbeedb::core::dataset::{Dataset, SeededRng};
+use qilbeedb::{EntityId, NodeId, PropertyValue};
+use std::hint::black_box;
+
+/// Nodes in the seeded graph
+const NODES: usize = 10_000;
+
+fn node_put(c: &mut Criterion) {
+    let (engine, graph_id, _dir) = common::empty_engine();
+    let dataset = Dataset::social(common::SEED, NODES, 0);
+
+    let mut nodes = dataset.nodes.iter().cycle();
+    c.bench_function("storage/node_put", |b| {
+        b.iter(|| engine.put_node(graph_id, nodes.next().unwrap()).unwrap())
+    });
+}
+
+fn node_get(c: &mut Criterion) {
+    let (engine, graph_id, _dir) = common::seeded_engine(NODES, 0);
+
+    let mut rng = SeededRng::new(common::SEED);
+    c.bench_function("storage/node_get", |b| {
+        b.iter(|| {
+            let id = NodeId::from_internal(1 + rng.below(NODES as u64));
+            black_box(engine.get_node(graph_id, id).unwrap())
+        })
+    });
+}
+
+fn label_scan(c: &mut Criterion) {
+    let (engine, graph_id, _dir) = common::seeded_engine(NODES, 0);
+
+    let mut group = c.benchmark_group("storage/label_scan");
+    group.throughput(Throughput::Elements(NODES as u64));
+    group.bench_function("nodes", |b| {
+        b.iter(|| black_box(engine.get_nodes_by_label(graph_id, "Person").unwrap()))
+    });
+    group.bench_function("count", |b| {
+        b.iter(|| black_box(engine.count_nodes_by_label(graph_id, "Person").unwrap()))
+    });
+    group.finish();
+}
+
+fn property_lookup(c: &mut Criterion) {
+    let (engine, graph_id, _dir) = common::seeded_engine(NODES, 0);
+
+    let mut rng = SeededRng::new(common::SEED);
+    c.bench_function("storage/property_lookup", |b| {
+        b.iter(|| {
+            let name = PropertyValue::String(format!("Person{}", rng.below(NODES as u64)));
+            black_box(
+                engine
+                    .get_nodes_by_property(graph_id, "Person", "name", &name)
+                    .unwrap(),
+            )
+        })
+    });
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = node_put, node_get, label_scan, property_lookup
+}
+criterion_main!(benches);
@@ -0,0 +1,100 @@
+//! HNSW vector index benchmarks: inserts, k-nearest-neighbour search and
+//! the similarity functions behind them
+//!
+//! Run with `--features simd` to compare the SIMD similarity functions with
+//! the scalar ones.
+
+mod common;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use qilbeedb::core::dataset::random_vectors;
+use qilbeedb::memory::embeddings::{self, scalar};
+use qilbeedb::memory::{HnswConfig, HnswIndex};
+use std::hint::black_box;
+
+/// Embedding dimension used throughout
+const DIMENSION: usize = 128;
+
+/// Vectors inserted per measured insert iteration
+const INSERT_BATCH: usize = 1_000;
+
+/// Vectors in the index searched by `hnsw_search`
+const INDEXED: usize = 10_000;
+
+fn hnsw_insert(c: &mut Criterion) {
+    let vectors: Vec<(String, Vec<f32>)> = random_vectors(common::SEED, INSERT_BATCH, DIMENSION)
+        .into_iter()
+        .enumerate()
+        .map(|(i, v)| (format!("v{}", i), v))
+        .collect();
+
+    let mut group = c.benchmark_group("hnsw/insert");
+    group.throughput(Throughput::Elements(INSERT_BATCH as u64));
+    group.sample_size(10);
+    group.bench_function(BenchmarkId::from_parameter(INSERT_BATCH), |b| {
+        b.iter_batched(
+            || (HnswIndex::new(HnswConfig::medium()), vectors.clone()),
+            |(mut index, vectors)| {
+                for (id, vector) in vectors {
+                    index.insert(id, vector).unwrap();
+                }
+                index
+            },
+            BatchSize::PerIteration,
+        )
+    });
+    group.finish();
+}
+
+fn hnsw_search(c: &mut Criterion) {
+    let mut index = HnswIndex::new(HnswConfig::medium());
+    for (i, vector) in random_vectors(common::SEED, INDEXED, DIMENSION).into_iter().enumerate() {
+        index.insert(format!("v{}", i), vector).unwrap();
+    }
+    let queries = random_vectors(common::SEED + 1, 256, DIMENSION);
+
+    // Compare the configured ef_search with wider per-query overrides
+    let mut group = c.benchmark_group("hnsw/search_k10");
+    for ef_search in [None, Some(100), Some(400)] {
+        let label = ef_search.map_or("default".to_string(), |ef| ef.to_string());
+        let mut queries = queries.iter().cycle();
+        group.bench_function(BenchmarkId::new("ef", label), |b| {
+            b.iter(|| black_box(index.search(queries.next().unwrap(), 10, ef_search).unwrap()))
+        });
+    }
+    group.finish();
+}
+
+fn similarity(c: &mut Criterion) {
+    // OpenAI text-embedding-3-small dimension
+    let vectors = random_vectors(common::SEED, 2, 1536);
+    let (a, b) = (&vectors[0], &vectors[1]);
+
+    let mut group = c.benchmark_group("similarity/1536");
+    group.bench_function("cosine/scalar", |bench| {
+        bench.iter(|| scalar::cosine_similarity(black_box(a), black_box(b)))
+    });
+    group.bench_function("cosine", |bench| {
+        bench.iter(|| embeddings::cosine_similarity(black_box(a), black_box(b)))
+    });
+    group.bench_function("dot/scalar", |bench| {
+        bench.iter(|| scalar::dot_product(black_box(a), black_box(b)))
+    });
+    group.bench_function("dot", |bench| {
+        bench.iter(|| embeddings::dot_product(black_box(a), black_box(b)))
+    });
+    group.bench_function("euclidean/scalar", |bench| {
+        bench.iter(|| scalar::euclidean_distance_squared(black_box(a), black_box(b)))
+    });
+    group.bench_function("euclidean", |bench| {
+        bench.iter(|| embeddings::euclidean_distance_squared(black_box(a), black_box(b)))
+    });
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = hnsw_insert, hnsw_search, similarity
+}
+criterion_main!(benches);
@@ -0,0 +1,178 @@
+//! Deterministic synthetic datasets
+//!
+//! Generates reproducible graphs and vectors from a seed, so benchmarks and
+//! tests can work with realistic volumes without fixture files. The same seed
+//! always produces the same entities, IDs and property values.
+
+use crate::id::{EntityId, NodeId, RelationshipId};
+use crate::property::Property;
+use crate::types::{Node, Relationship};
+
+/// Cities assigned to generated `Person` nodes
+pub const CITIES: &[&str] = &[
+    "Lisbon", "Paris", "Berlin", "Madrid", "Rome", "Vienna", "Prague", "Dublin",
+];
+
+/// Small, fast pseudo-random number generator (SplitMix64)
+///
+/// Not suitable for anything security related; it only exists to make
+/// generated data reproducible.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    /// Create a generator from a seed
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    /// Next 64 random bits
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Random integer in `0..bound`
+    ///
+    /// Panics if `bound` is zero.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be positive");
+        ((self.next_u64() as u128 * bound as u128) >> 64) as u64
+    }
+
+    /// Random float in `[0, 1)`
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
+/// A generated graph with sequential node and relationship IDs starting at 1
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    /// Generated nodes
+    pub nodes: Vec<Node>,
+
+    /// Generated relationships between `nodes`
+    pub relationships: Vec<Relationship>,
+}
+
+impl Dataset {
+    /// Generate a social graph
+    ///
+    /// Creates `node_count` `Person` nodes with `name`, `age` and `city`
+    /// properties, each with `degree` outgoing `KNOWS` relationships (carrying
+    /// a `since` year) to other randomly chosen people.
+    pub fn social(seed: u64, node_count: usize, degree: usize) -> Self {
+        let mut rng = SeededRng::new(seed);
+
+        let nodes: Vec<Node> = (0..node_count)
+            .map(|i| {
+                let mut props = Property::new();
+                props.set("name", format!("Person{}", i));
+                props.set("age", 18 + rng.below(62) as i64);
+                props.set("city", CITIES[rng.below(CITIES.len() as u64) as usize]);
+                Node::with_labels_and_properties(
+                    NodeId::from_internal(i as u64 + 1),
+                    ["Person"],
+                    props,
+                )
+            })
+            .collect();
+
+        let mut relationships = Vec::new();
+        if node_count > 1 {
+            for source in &nodes {
+                for _ in 0..degree {
+                    // Pick any other node, never the source itself
+                    let offset = 1 + rng.below(node_count as u64 - 1);
+                    let target = (source.id.as_internal() - 1 + offset) % node_count as u64 + 1;
+
+                    let mut props = Property::new();
+                    props.set("since", 2000 + rng.below(25) as i64);
+                    relationships.push(Relationship::with_properties(
+                        RelationshipId::from_internal(relationships.len() as u64 + 1),
+                        "KNOWS",
+                        source.id,
+                        NodeId::from_internal(target),
+                        props,
+                    ));
+                }
+            }
+        }
+
+        Self {
+            nodes,
+            relationships,
+        }
+    }
+}
+
+/// Generate `count` vectors of `dimension` components in `[-1, 1)`
+pub fn random_vectors(seed: u64, count: usize, dimension: usize) -> Vec<Vec<f32>> {
+    let mut rng = SeededRng::new(seed);
+    (0..count)
+        .map(|_| (0..dimension).map(|_| rng.next_f32() * 2.0 - 1.0).collect())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_seeded_rng_is_deterministic() {
+        let mut a = SeededRng::new(7);
+        let mut b = SeededRng::new(7);
+        let mut c = SeededRng::new(8);
+        let first: Vec<u64> = (0..10).map(|_| a.next_u64()).collect();
+        assert_eq!(first, (0..10).map(|_| b.next_u64()).collect::<Vec<_>>());
+        assert_ne!(first, (0..10).map(|_| c.next_u64()).collect::<Vec<_>>());
+
+        for _ in 0..1000 {
+            assert!(a.below(10) < 10);
+            let f = a.next_f32();
+            assert!((0.0..1.0).contains(&f));
+        }
+    }
+
+    #[test]
+    fn test_social_dataset() {
+        let dataset = Dataset::social(42, 100, 3);
+        assert_eq!(dataset.nodes.len(), 100);
+        assert_eq!(dataset.relationships.len(), 300);
+
+        for rel in &dataset.relationships {
+            assert_ne!(rel.source, rel.target);
+            assert!((1..=100).contains(&rel.target.as_internal()));
+        }
+        let node = &dataset.nodes[0];
+        assert_eq!(node.id.as_internal(), 1);
+        assert!(node.has_label_name("Person"));
+        assert_eq!(node.get_property("name"), Some(&"Person0".into()));
+
+        // The same seed reproduces the same graph
+        let again = Dataset::social(42, 100, 3);
+        for (a, b) in dataset.nodes.iter().zip(&again.nodes) {
+            assert_eq!((a.id, &a.properties), (b.id, &b.properties));
+        }
+        for (a, b) in dataset.relationships.iter().zip(&again.relationships) {
+            assert_eq!((a.id, a.source, a.target), (b.id, b.source, b.target));
+        }
+
+        assert!(Dataset::social(1, 1, 5).relationships.is_empty());
+    }
+
+    #[test]
+    fn test_random_vectors() {
+        let vectors = random_vectors(3, 50, 16);
+        assert_eq!(vectors.len(), 50);
+        assert!(vectors.iter().all(|v| v.len() == 16));
+        assert!(vectors.iter().flatten().all(|x| (-1.0..1.0).contains(x)));
+        assert_eq!(vectors, random_vectors(3, 50, 16));
+    }
+}
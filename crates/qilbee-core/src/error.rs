@@ -17,6 +17,9 @@ pub enum Error {
     #[error("Data corruption detected: {0}")]
     DataCorruption(String),
 
+    #[error("Conflict: {0}")]
+    Conflict(String),
+
     // ========== Graph Errors ==========
     #[error("Node not found: {0}")]
     NodeNotFound(String),
@@ -30,6 +33,9 @@ pub enum Error {
     #[error("Invalid graph operation: {0}")]
     InvalidGraphOperation(String),
 
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
     // ========== Query Errors ==========
     #[error("Query parse error: {0}")]
     QueryParse(String),
@@ -149,9 +149,21 @@ impl fmt::Display for RelationshipId {
     }
 }
 
-/// Identifier generator for sequential IDs within a graph
+/// Strategy used to allocate node and relationship IDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum IdStrategy {
+    /// Monotonically increasing IDs (the high-water mark is persisted by the graph)
+    #[default]
+    Sequential,
+
+    /// Random UUID-derived IDs that need no coordination between processes
+    Random,
+}
+
+/// Identifier generator for IDs within a graph
 #[derive(Debug)]
 pub struct IdGenerator {
+    strategy: IdStrategy,
     next_node_id: std::sync::atomic::AtomicU64,
     next_rel_id: std::sync::atomic::AtomicU64,
 }
@@ -159,34 +171,55 @@ pub struct IdGenerator {
 impl IdGenerator {
     /// Create a new ID generator
     pub fn new() -> Self {
-        Self {
-            next_node_id: std::sync::atomic::AtomicU64::new(1),
-            next_rel_id: std::sync::atomic::AtomicU64::new(1),
-        }
+        Self::with_start(1, 1)
     }
 
     /// Create with starting values (for recovery)
     pub fn with_start(node_start: u64, rel_start: u64) -> Self {
         Self {
+            strategy: IdStrategy::Sequential,
             next_node_id: std::sync::atomic::AtomicU64::new(node_start),
             next_rel_id: std::sync::atomic::AtomicU64::new(rel_start),
         }
     }
 
+    /// Create a generator producing random IDs
+    pub fn random() -> Self {
+        Self {
+            strategy: IdStrategy::Random,
+            ..Self::new()
+        }
+    }
+
+    /// Get the allocation strategy
+    pub fn strategy(&self) -> IdStrategy {
+        self.strategy
+    }
+
     /// Generate the next node ID
     pub fn next_node_id(&self) -> NodeId {
-        let id = self
-            .next_node_id
-            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        NodeId::from_internal(id)
+        match self.strategy {
+            IdStrategy::Sequential => {
+                let id = self
+                    .next_node_id
+                    .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                NodeId::from_internal(id)
+            }
+            IdStrategy::Random => NodeId::new(),
+        }
     }
 
     /// Generate the next relationship ID
     pub fn next_relationship_id(&self) -> RelationshipId {
-        let id = self
-            .next_rel_id
-            .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
-        RelationshipId::from_internal(id)
+        match self.strategy {
+            IdStrategy::Sequential => {
+                let id = self
+                    .next_rel_id
+                    .fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                RelationshipId::from_internal(id)
+            }
+            IdStrategy::Random => RelationshipId::new(),
+        }
     }
 
     /// Get current node ID counter value
@@ -254,4 +287,17 @@ mod tests {
         assert_eq!(id_gen.next_node_id().as_internal(), 100);
         assert_eq!(id_gen.next_relationship_id().as_internal(), 200);
     }
+
+    #[test]
+    fn test_id_generator_random() {
+        let id_gen = IdGenerator::random();
+        assert_eq!(id_gen.strategy(), IdStrategy::Random);
+
+        let n1 = id_gen.next_node_id();
+        let n2 = id_gen.next_node_id();
+        assert_ne!(n1, n2);
+
+        // Random allocation leaves the sequential counters untouched
+        assert_eq!(id_gen.current_node_id(), 1);
+    }
 }
@@ -14,7 +14,9 @@
 //! - `error` - Error types and result aliases
 //! - `id` - Entity identification and generation
 //! - `temporal` - Bi-temporal data handling
+//! - `dataset` - Deterministic synthetic graphs for benchmarks and tests
 
+pub mod dataset;
 pub mod error;
 pub mod id;
 pub mod property;
@@ -22,7 +24,7 @@ pub mod temporal;
 pub mod types;
 
 pub use error::{Error, Result};
-pub use id::{EntityId, GraphId, IdGenerator, NodeId, RelationshipId};
-pub use property::{Property, PropertyValue};
+pub use id::{EntityId, GraphId, IdGenerator, IdStrategy, NodeId, RelationshipId};
+pub use property::{compare_floats, MergeMode, Property, PropertyValue};
 pub use temporal::{BiTemporal, EventTime, TransactionTime};
 pub use types::{Direction, Label, Node, Relationship};
@@ -3,6 +3,7 @@
 //! Provides property values and collections for nodes and relationships.
 
 use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 /// A property value that can be stored on nodes and relationships
@@ -120,6 +121,33 @@ impl PropertyValue {
         }
     }
 
+    /// Whether two values are equal, treating numbers within `float_epsilon`
+    /// of each other as equal
+    ///
+    /// With no epsilon this is exact equality. With one, an integer compared
+    /// with a float is compared as a float, and lists and maps are compared
+    /// element by element.
+    pub fn equals_within(&self, other: &PropertyValue, float_epsilon: Option<f64>) -> bool {
+        let Some(epsilon) = float_epsilon else {
+            return self == other;
+        };
+        match (self, other) {
+            (PropertyValue::Float(_), PropertyValue::Float(_) | PropertyValue::Integer(_))
+            | (PropertyValue::Integer(_), PropertyValue::Float(_)) => {
+                let (a, b) = (self.as_float().unwrap_or(f64::NAN), other.as_float().unwrap_or(f64::NAN));
+                compare_floats(a, b, Some(epsilon)) == Some(Ordering::Equal)
+            }
+            (PropertyValue::Array(a), PropertyValue::Array(b)) => {
+                a.len() == b.len() && a.iter().zip(b).all(|(a, b)| a.equals_within(b, float_epsilon))
+            }
+            (PropertyValue::Map(a), PropertyValue::Map(b)) => {
+                a.len() == b.len()
+                    && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| a.equals_within(b, float_epsilon)))
+            }
+            _ => self == other,
+        }
+    }
+
     /// Try to get as array reference
     pub fn as_array(&self) -> Option<&Vec<PropertyValue>> {
         match self {
@@ -214,6 +242,44 @@ impl<T: Into<PropertyValue>> From<Vec<T>> for PropertyValue {
     }
 }
 
+/// Compare two floats, treating values within `epsilon` of each other as equal
+///
+/// Returns `None` if either is NaN.
+pub fn compare_floats(a: f64, b: f64, epsilon: Option<f64>) -> Option<Ordering> {
+    if epsilon.is_some_and(|epsilon| (a - b).abs() <= epsilon) {
+        return Some(Ordering::Equal);
+    }
+    a.partial_cmp(&b)
+}
+
+/// How merging property maps (`SET n += {map}`) treats nested maps
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MergeMode {
+    /// Nested maps merge key by key; any other value is overwritten
+    #[default]
+    Deep,
+    /// Each updated key is overwritten as a whole, nested maps included
+    Shallow,
+}
+
+/// Merge `update` into `target` key by key
+fn merge_maps(
+    target: &mut HashMap<String, PropertyValue>,
+    update: HashMap<String, PropertyValue>,
+    mode: MergeMode,
+) {
+    for (key, value) in update {
+        match (mode, target.get_mut(&key), value) {
+            (MergeMode::Deep, Some(PropertyValue::Map(existing)), PropertyValue::Map(nested)) => {
+                merge_maps(existing, nested, mode);
+            }
+            (_, _, value) => {
+                target.insert(key, value);
+            }
+        }
+    }
+}
+
 /// A collection of properties
 #[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Property {
@@ -280,6 +346,14 @@ impl Property {
         self.inner.extend(other.inner);
     }
 
+    /// Merge with another property collection, as `SET n += {map}` does
+    ///
+    /// `other` takes precedence. In [`MergeMode::Deep`], a map value merges
+    /// into an existing map under the same key instead of replacing it.
+    pub fn merge_with(&mut self, other: Property, mode: MergeMode) {
+        merge_maps(&mut self.inner, other.inner, mode);
+    }
+
     /// Convert to HashMap
     pub fn into_inner(self) -> HashMap<String, PropertyValue> {
         self.inner
@@ -328,6 +402,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_equals_within_float_epsilon() {
+        let sum = PropertyValue::Float(0.1 + 0.2);
+        let expected = PropertyValue::Float(0.3);
+        assert!(!sum.equals_within(&expected, None));
+        assert!(sum.equals_within(&expected, Some(1e-9)));
+        assert!(!PropertyValue::Float(0.31).equals_within(&expected, Some(1e-9)));
+
+        // Numbers compare across types only with a tolerance
+        assert!(!PropertyValue::Integer(1).equals_within(&PropertyValue::Float(1.0), None));
+        assert!(PropertyValue::Integer(1).equals_within(&PropertyValue::Float(1.0 + 1e-12), Some(1e-9)));
+
+        let list = |x: f64| PropertyValue::Array(vec![PropertyValue::String("a".into()), PropertyValue::Float(x)]);
+        assert!(list(0.1 + 0.2).equals_within(&list(0.3), Some(1e-9)));
+        assert!(!list(0.1 + 0.2).equals_within(&list(0.3), None));
+        assert!(!PropertyValue::String("a".into()).equals_within(&PropertyValue::Float(0.3), Some(1e-9)));
+
+        assert_eq!(compare_floats(0.1 + 0.2, 0.3, None), Some(Ordering::Greater));
+        assert_eq!(compare_floats(0.1 + 0.2, 0.3, Some(1e-9)), Some(Ordering::Equal));
+        assert_eq!(compare_floats(f64::NAN, 0.3, Some(1e-9)), None);
+    }
+
     #[test]
     fn test_property_from_impls() {
         let _: PropertyValue = true.into();
@@ -368,4 +464,68 @@ mod tests {
         assert_eq!(props1.get("a").and_then(|v| v.as_str()), Some("overwritten"));
         assert_eq!(props1.get("b").and_then(|v| v.as_str()), Some("2"));
     }
+
+    fn nested(entries: &[(&str, PropertyValue)]) -> PropertyValue {
+        PropertyValue::Map(entries.iter().map(|(k, v)| (k.to_string(), v.clone())).collect())
+    }
+
+    #[test]
+    fn test_deep_merge_preserves_untouched_nested_keys() {
+        let mut props = Property::new();
+        props.set("name", "Alice");
+        props.set(
+            "address",
+            nested(&[
+                ("city", "Lisbon".into()),
+                ("zip", "1000".into()),
+                ("geo", nested(&[("lat", 38.7.into()), ("lon", (-9.1).into())])),
+            ]),
+        );
+
+        let mut update = Property::new();
+        update.set("age", 30);
+        update.set(
+            "address",
+            nested(&[("city", "Porto".into()), ("geo", nested(&[("lat", 41.1.into())]))]),
+        );
+        props.merge_with(update, MergeMode::Deep);
+
+        assert_eq!(props.get("name"), Some(&"Alice".into()));
+        assert_eq!(props.get("age"), Some(&30.into()));
+        let address = props.get("address").unwrap().as_map().unwrap();
+        assert_eq!(address.get("city"), Some(&"Porto".into()));
+        assert_eq!(address.get("zip"), Some(&"1000".into()));
+        let geo = address.get("geo").unwrap().as_map().unwrap();
+        assert_eq!(geo.get("lat"), Some(&41.1.into()));
+        assert_eq!(geo.get("lon"), Some(&(-9.1).into()));
+    }
+
+    #[test]
+    fn test_deep_merge_overwrites_scalars_and_mismatched_types() {
+        let mut props = Property::new();
+        props.set("tags", nested(&[("a", 1.into())]));
+        props.set("score", nested(&[("value", 1.into())]));
+
+        let mut update = Property::new();
+        update.set("tags", "none");
+        update.set("score", 5);
+        props.merge_with(update, MergeMode::Deep);
+
+        assert_eq!(props.get("tags"), Some(&"none".into()));
+        assert_eq!(props.get("score"), Some(&5.into()));
+    }
+
+    #[test]
+    fn test_shallow_merge_replaces_nested_maps() {
+        let mut props = Property::new();
+        props.set("address", nested(&[("city", "Lisbon".into()), ("zip", "1000".into())]));
+
+        let mut update = Property::new();
+        update.set("address", nested(&[("city", "Porto".into())]));
+        props.merge_with(update, MergeMode::Shallow);
+
+        let address = props.get("address").unwrap().as_map().unwrap();
+        assert_eq!(address.get("city"), Some(&"Porto".into()));
+        assert!(address.get("zip").is_none());
+    }
 }
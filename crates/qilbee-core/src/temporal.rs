@@ -1,9 +1,15 @@
 //! Bi-temporal data handling for QilbeeDB
 //!
 //! Implements event time and transaction time tracking for agent memory systems.
+//!
+//! All times are held in UTC. Times built from other offsets are normalized
+//! to UTC on construction, so millisecond and RFC 3339 conversions always
+//! describe the same instant.
 
-use chrono::{DateTime, Utc};
+use crate::error::{Error, Result};
+use chrono::{DateTime, SecondsFormat, TimeZone, Utc};
 use serde::{Deserialize, Serialize};
+use std::ops::{Bound, RangeBounds};
 
 /// Event time - when the event actually occurred in the real world
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
@@ -15,9 +21,9 @@ impl EventTime {
         Self(Utc::now())
     }
 
-    /// Create from a DateTime
-    pub fn from_datetime(dt: DateTime<Utc>) -> Self {
-        Self(dt)
+    /// Create from a DateTime in any time zone, normalized to UTC
+    pub fn from_datetime<Tz: TimeZone>(dt: DateTime<Tz>) -> Self {
+        Self(dt.with_timezone(&Utc))
     }
 
     /// Create from milliseconds since Unix epoch
@@ -25,6 +31,13 @@ impl EventTime {
         Self(DateTime::from_timestamp_millis(millis).unwrap_or_else(Utc::now))
     }
 
+    /// Parse an RFC 3339 timestamp, normalizing any offset to UTC
+    pub fn from_rfc3339(s: &str) -> Result<Self> {
+        DateTime::parse_from_rfc3339(s)
+            .map(Self::from_datetime)
+            .map_err(|e| Error::ValidationError(format!("Invalid event time '{}': {}", s, e)))
+    }
+
     /// Get as DateTime
     pub fn as_datetime(&self) -> DateTime<Utc> {
         self.0
@@ -35,6 +48,11 @@ impl EventTime {
         self.0.timestamp_millis()
     }
 
+    /// Format as RFC 3339 in UTC with a `Z` suffix
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    }
+
     /// Represents the beginning of time (for queries)
     pub fn min() -> Self {
         Self(DateTime::from_timestamp_millis(0).unwrap())
@@ -62,9 +80,9 @@ impl TransactionTime {
         Self(Utc::now())
     }
 
-    /// Create from a DateTime
-    pub fn from_datetime(dt: DateTime<Utc>) -> Self {
-        Self(dt)
+    /// Create from a DateTime in any time zone, normalized to UTC
+    pub fn from_datetime<Tz: TimeZone>(dt: DateTime<Tz>) -> Self {
+        Self(dt.with_timezone(&Utc))
     }
 
     /// Create from milliseconds since Unix epoch
@@ -72,6 +90,13 @@ impl TransactionTime {
         Self(DateTime::from_timestamp_millis(millis).unwrap_or_else(Utc::now))
     }
 
+    /// Parse an RFC 3339 timestamp, normalizing any offset to UTC
+    pub fn from_rfc3339(s: &str) -> Result<Self> {
+        DateTime::parse_from_rfc3339(s)
+            .map(Self::from_datetime)
+            .map_err(|e| Error::ValidationError(format!("Invalid transaction time '{}': {}", s, e)))
+    }
+
     /// Get as DateTime
     pub fn as_datetime(&self) -> DateTime<Utc> {
         self.0
@@ -82,6 +107,11 @@ impl TransactionTime {
         self.0.timestamp_millis()
     }
 
+    /// Format as RFC 3339 in UTC with a `Z` suffix
+    pub fn to_rfc3339(&self) -> String {
+        self.0.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+    }
+
     /// Represents the beginning of time (for queries)
     pub fn min() -> Self {
         Self(DateTime::from_timestamp_millis(0).unwrap())
@@ -193,43 +223,81 @@ impl<T> BiTemporal<T> {
 }
 
 /// Temporal range for queries
+///
+/// Each end is a [`Bound`]: `Included` and `Excluded` compare against the
+/// event time exactly, and `Unbounded` leaves that side of the range open.
+/// Ranges built with [`TemporalRange::new`] include both ends.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TemporalRange {
-    pub start: EventTime,
-    pub end: EventTime,
+    pub start: Bound<EventTime>,
+    pub end: Bound<EventTime>,
 }
 
 impl TemporalRange {
-    /// Create a new temporal range
+    /// Create a new temporal range including both `start` and `end`
     pub fn new(start: EventTime, end: EventTime) -> Self {
+        Self::with_bounds(Bound::Included(start), Bound::Included(end))
+    }
+
+    /// Create a range with explicit bounds on each end
+    pub fn with_bounds(start: Bound<EventTime>, end: Bound<EventTime>) -> Self {
         Self { start, end }
     }
 
+    /// Create a range including `start` and excluding `end`
+    pub fn half_open(start: EventTime, end: EventTime) -> Self {
+        Self::with_bounds(Bound::Included(start), Bound::Excluded(end))
+    }
+
+    /// Create a range from `start` (inclusive) with no end
+    pub fn since(start: EventTime) -> Self {
+        Self::with_bounds(Bound::Included(start), Bound::Unbounded)
+    }
+
+    /// Create a range with no start, up to `end` (inclusive)
+    pub fn until(end: EventTime) -> Self {
+        Self::with_bounds(Bound::Unbounded, Bound::Included(end))
+    }
+
     /// Create a range from now going back a duration
     pub fn last_days(days: i64) -> Self {
         let end = EventTime::now();
         let start = EventTime::from_millis(end.as_millis() - days * 24 * 60 * 60 * 1000);
-        Self { start, end }
+        Self::new(start, end)
     }
 
     /// Create a range from now going back hours
     pub fn last_hours(hours: i64) -> Self {
         let end = EventTime::now();
         let start = EventTime::from_millis(end.as_millis() - hours * 60 * 60 * 1000);
-        Self { start, end }
+        Self::new(start, end)
     }
 
     /// Create an unbounded range (all time)
     pub fn all() -> Self {
-        Self {
-            start: EventTime::min(),
-            end: EventTime::max(),
-        }
+        Self::with_bounds(Bound::Unbounded, Bound::Unbounded)
     }
 
     /// Check if a time is within this range
     pub fn contains(&self, time: EventTime) -> bool {
-        time >= self.start && time <= self.end
+        (self.start, self.end).contains(&time)
+    }
+
+    /// Inclusive millisecond bounds covering every time in this range
+    ///
+    /// Open ends map to `i64::MIN` and `i64::MAX`. Exclusive bounds are kept
+    /// as their own millisecond, since times carry sub-millisecond precision,
+    /// so callers filtering by millis should still check [`contains`](Self::contains).
+    pub fn millis_bounds(&self) -> (i64, i64) {
+        let start = match self.start {
+            Bound::Included(t) | Bound::Excluded(t) => t.as_millis(),
+            Bound::Unbounded => i64::MIN,
+        };
+        let end = match self.end {
+            Bound::Included(t) | Bound::Excluded(t) => t.as_millis(),
+            Bound::Unbounded => i64::MAX,
+        };
+        (start, end)
     }
 }
 
@@ -300,6 +368,61 @@ mod tests {
         assert_eq!(time.as_millis(), millis);
     }
 
+    #[test]
+    fn test_epoch_conversions() {
+        let epoch = EventTime::from_millis(0);
+        assert_eq!(epoch.as_millis(), 0);
+        assert_eq!(epoch.to_rfc3339(), "1970-01-01T00:00:00Z");
+        assert_eq!(EventTime::from_rfc3339("1970-01-01T00:00:00Z").unwrap(), epoch);
+
+        let before_epoch = TransactionTime::from_millis(-1);
+        assert_eq!(before_epoch.to_rfc3339(), "1969-12-31T23:59:59.999Z");
+        assert_eq!(TransactionTime::from_rfc3339(&before_epoch.to_rfc3339()).unwrap(), before_epoch);
+    }
+
+    #[test]
+    fn test_rfc3339_round_trip_preserves_instant() {
+        let event = EventTime::now();
+        assert_eq!(EventTime::from_rfc3339(&event.to_rfc3339()).unwrap(), event);
+
+        let transaction = TransactionTime::from_millis(1700000000123);
+        assert_eq!(transaction.to_rfc3339(), "2023-11-14T22:13:20.123Z");
+        let parsed = TransactionTime::from_rfc3339(&transaction.to_rfc3339()).unwrap();
+        assert_eq!(parsed.as_millis(), 1700000000123);
+    }
+
+    #[test]
+    fn test_rfc3339_offsets_normalize_to_utc() {
+        let time = EventTime::from_rfc3339("2024-06-01T12:00:00+02:00").unwrap();
+        assert_eq!(time.to_rfc3339(), "2024-06-01T10:00:00Z");
+        assert_eq!(time.as_datetime().timezone(), Utc);
+
+        let offset = chrono::FixedOffset::west_opt(5 * 3600).unwrap();
+        let local = offset.with_ymd_and_hms(2024, 1, 1, 19, 0, 0).unwrap();
+        assert_eq!(TransactionTime::from_datetime(local).to_rfc3339(), "2024-01-02T00:00:00Z");
+    }
+
+    #[test]
+    fn test_rfc3339_around_dst_transitions() {
+        // US spring-forward: 01:59:59 EST is one second before 03:00:00 EDT
+        let before = EventTime::from_rfc3339("2024-03-10T01:59:59-05:00").unwrap();
+        let after = EventTime::from_rfc3339("2024-03-10T03:00:00-04:00").unwrap();
+        assert_eq!(after.as_millis() - before.as_millis(), 1000);
+        assert_eq!(after.to_rfc3339(), "2024-03-10T07:00:00Z");
+
+        // US fall-back: the repeated 01:30 wall-clock times are an hour apart
+        let first = EventTime::from_rfc3339("2024-11-03T01:30:00-04:00").unwrap();
+        let second = EventTime::from_rfc3339("2024-11-03T01:30:00-05:00").unwrap();
+        assert_eq!(second.as_millis() - first.as_millis(), 60 * 60 * 1000);
+        assert_eq!(first.to_rfc3339(), "2024-11-03T05:30:00Z");
+    }
+
+    #[test]
+    fn test_from_rfc3339_rejects_invalid_input() {
+        assert!(EventTime::from_rfc3339("2024-03-10 03:00:00").is_err());
+        assert!(TransactionTime::from_rfc3339("not a time").is_err());
+    }
+
     #[test]
     fn test_bi_temporal_creation() {
         let data = "test data".to_string();
@@ -361,6 +484,50 @@ mod tests {
         assert!(!range.contains(two_weeks_ago));
     }
 
+    #[test]
+    fn test_temporal_range_inclusive_and_exclusive_bounds() {
+        let start = EventTime::from_millis(1_000);
+        let end = EventTime::from_millis(2_000);
+
+        let inclusive = TemporalRange::new(start, end);
+        assert!(inclusive.contains(start));
+        assert!(inclusive.contains(end));
+        assert!(!inclusive.contains(EventTime::from_millis(999)));
+        assert!(!inclusive.contains(EventTime::from_millis(2_001)));
+
+        let half_open = TemporalRange::half_open(start, end);
+        assert!(half_open.contains(start));
+        assert!(!half_open.contains(end));
+        assert!(half_open.contains(EventTime::from_millis(1_999)));
+
+        let exclusive = TemporalRange::with_bounds(Bound::Excluded(start), Bound::Excluded(end));
+        assert!(!exclusive.contains(start));
+        assert!(exclusive.contains(EventTime::from_millis(1_001)));
+        assert!(!exclusive.contains(end));
+        assert_eq!(exclusive.millis_bounds(), (1_000, 2_000));
+    }
+
+    #[test]
+    fn test_temporal_range_open_ends() {
+        let pivot = EventTime::from_millis(1_000);
+
+        let since = TemporalRange::since(pivot);
+        assert!(since.contains(pivot));
+        assert!(since.contains(EventTime::from_rfc3339("9999-12-31T23:59:59Z").unwrap()));
+        assert!(!since.contains(EventTime::from_millis(999)));
+        assert_eq!(since.millis_bounds(), (1_000, i64::MAX));
+
+        let until = TemporalRange::until(pivot);
+        assert!(until.contains(pivot));
+        assert!(until.contains(EventTime::from_millis(-5_000)));
+        assert!(!until.contains(EventTime::from_millis(1_001)));
+        assert_eq!(until.millis_bounds(), (i64::MIN, 1_000));
+
+        let all = TemporalRange::all();
+        assert!(all.contains(EventTime::from_millis(-5_000)));
+        assert!(all.contains(EventTime::now()));
+    }
+
     #[test]
     fn test_as_of_queries() {
         let now = AsOf::now();
@@ -0,0 +1,763 @@
+//! Graph algorithms
+//!
+//! Path finding and other whole-graph computations on top of `Graph`.
+
+use crate::graph::Graph;
+use qilbee_core::{Direction, EntityId, Error, NodeId, RelationshipId, Result};
+use std::collections::HashMap;
+
+/// Default PageRank damping factor
+pub const DEFAULT_PAGERANK_DAMPING: f64 = 0.85;
+
+/// Default PageRank iteration cap
+pub const DEFAULT_PAGERANK_ITERATIONS: usize = 20;
+
+/// Default PageRank convergence tolerance (L1 distance between iterations)
+pub const DEFAULT_PAGERANK_TOLERANCE: f64 = 1e-6;
+
+/// A path through the graph
+///
+/// `nodes` always holds one more entry than `relationships`; the
+/// relationship at index `i` connects `nodes[i]` and `nodes[i + 1]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path {
+    /// Nodes along the path, starting at the source
+    pub nodes: Vec<NodeId>,
+
+    /// Relationships traversed, in order
+    pub relationships: Vec<RelationshipId>,
+}
+
+impl Path {
+    /// Number of relationships in the path
+    pub fn len(&self) -> usize {
+        self.relationships.len()
+    }
+
+    /// Check if the path has no relationships (source equals target)
+    pub fn is_empty(&self) -> bool {
+        self.relationships.is_empty()
+    }
+
+    /// First node of the path
+    pub fn start(&self) -> NodeId {
+        self.nodes[0]
+    }
+
+    /// Last node of the path
+    pub fn end(&self) -> NodeId {
+        self.nodes[self.nodes.len() - 1]
+    }
+}
+
+/// Constraints applied while searching for paths
+#[derive(Debug, Clone, Default)]
+pub struct PathOptions {
+    /// Only traverse relationships of these types (empty means any type)
+    pub rel_types: Vec<String>,
+
+    /// Maximum number of relationships in a path (`None` means unbounded)
+    pub max_hops: Option<u32>,
+}
+
+/// One side of a bidirectional breadth-first search
+struct SearchFrontier {
+    /// Traversal direction for this side
+    direction: Direction,
+
+    /// Distance of every visited node from this side's origin
+    depth: HashMap<NodeId, u32>,
+
+    /// Predecessors of each node on shortest paths from the origin
+    parents: HashMap<NodeId, Vec<(NodeId, RelationshipId)>>,
+
+    /// Nodes discovered at the current depth
+    frontier: Vec<NodeId>,
+
+    /// Depth fully expanded so far
+    level: u32,
+}
+
+impl SearchFrontier {
+    fn new(origin: NodeId, direction: Direction) -> Self {
+        Self {
+            direction,
+            depth: HashMap::from([(origin, 0)]),
+            parents: HashMap::new(),
+            frontier: vec![origin],
+            level: 0,
+        }
+    }
+
+    /// Expand the frontier by one level, recording every shortest predecessor
+    fn expand(&mut self, graph: &Graph, options: &PathOptions) -> Result<()> {
+        let next_level = self.level + 1;
+        let mut next = Vec::new();
+
+        for node in std::mem::take(&mut self.frontier) {
+            for rel in graph.get_relationships(node, self.direction)? {
+                if !options.rel_types.is_empty()
+                    && !options.rel_types.iter().any(|t| t == rel.rel_type.name())
+                {
+                    continue;
+                }
+
+                let neighbor = match self.direction {
+                    Direction::Outgoing => rel.target,
+                    Direction::Incoming => rel.source,
+                    Direction::Both => rel.other(node).unwrap_or(rel.target),
+                };
+
+                match self.depth.get(&neighbor) {
+                    None => {
+                        self.depth.insert(neighbor, next_level);
+                        self.parents.insert(neighbor, vec![(node, rel.id)]);
+                        next.push(neighbor);
+                    }
+                    Some(&d) if d == next_level => {
+                        let parents = self.parents.entry(neighbor).or_default();
+                        if !parents.contains(&(node, rel.id)) {
+                            parents.push((node, rel.id));
+                        }
+                    }
+                    // Already reached by a shorter route; this also stops cycles
+                    Some(_) => {}
+                }
+            }
+        }
+
+        self.frontier = next;
+        self.level = next_level;
+        Ok(())
+    }
+
+    /// All shortest routes from the origin to `node`, as (nodes, relationships)
+    /// listed from the origin outwards
+    fn routes_to(&self, node: NodeId) -> Vec<(Vec<NodeId>, Vec<RelationshipId>)> {
+        match self.parents.get(&node) {
+            None => vec![(vec![node], Vec::new())],
+            Some(parents) => {
+                let mut routes = Vec::new();
+                for (parent, rel) in parents {
+                    for (mut nodes, mut rels) in self.routes_to(*parent) {
+                        nodes.push(node);
+                        rels.push(*rel);
+                        routes.push((nodes, rels));
+                    }
+                }
+                routes
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Find a shortest path between two nodes
+    ///
+    /// Runs a bidirectional breadth-first search over the adjacency indices.
+    /// With a directed `direction`, only paths following relationship
+    /// direction from `source` to `target` are considered.
+    pub fn shortest_path(
+        &self,
+        source: NodeId,
+        target: NodeId,
+        direction: Direction,
+        options: &PathOptions,
+    ) -> Result<Option<Path>> {
+        Ok(self
+            .search_shortest_paths(source, target, direction, options, false)?
+            .into_iter()
+            .next())
+    }
+
+    /// Find every path of minimal length between two nodes
+    pub fn all_shortest_paths(
+        &self,
+        source: NodeId,
+        target: NodeId,
+        direction: Direction,
+        options: &PathOptions,
+    ) -> Result<Vec<Path>> {
+        self.search_shortest_paths(source, target, direction, options, true)
+    }
+
+    fn search_shortest_paths(
+        &self,
+        source: NodeId,
+        target: NodeId,
+        direction: Direction,
+        options: &PathOptions,
+        all: bool,
+    ) -> Result<Vec<Path>> {
+        if self.get_node(source)?.is_none() || self.get_node(target)?.is_none() {
+            return Ok(Vec::new());
+        }
+
+        if source == target {
+            return Ok(vec![Path {
+                nodes: vec![source],
+                relationships: Vec::new(),
+            }]);
+        }
+
+        let mut forward = SearchFrontier::new(source, direction);
+        let mut backward = SearchFrontier::new(target, direction.reverse());
+
+        loop {
+            if forward.frontier.is_empty() || backward.frontier.is_empty() {
+                return Ok(Vec::new());
+            }
+            if options
+                .max_hops
+                .is_some_and(|max| forward.level + backward.level >= max)
+            {
+                return Ok(Vec::new());
+            }
+
+            // Expand the cheaper side first
+            if forward.frontier.len() <= backward.frontier.len() {
+                forward.expand(self, options)?;
+            } else {
+                backward.expand(self, options)?;
+            }
+
+            let shortest = forward
+                .depth
+                .iter()
+                .filter_map(|(node, df)| backward.depth.get(node).map(|db| df + db))
+                .min();
+
+            if let Some(length) = shortest {
+                return Ok(self.join_routes(&forward, &backward, length, all));
+            }
+        }
+    }
+
+    /// Stitch forward and backward routes together through the nodes that sit
+    /// at a fixed position on every shortest path, so each path is built once
+    fn join_routes(
+        &self,
+        forward: &SearchFrontier,
+        backward: &SearchFrontier,
+        length: u32,
+        all: bool,
+    ) -> Vec<Path> {
+        let position = forward.level.min(length);
+
+        let mut meeting: Vec<NodeId> = forward
+            .depth
+            .iter()
+            .filter(|(node, df)| {
+                **df == position && backward.depth.get(*node) == Some(&(length - position))
+            })
+            .map(|(node, _)| *node)
+            .collect();
+        meeting.sort_by_key(|n| n.as_internal());
+
+        let mut paths = Vec::new();
+        for node in meeting {
+            for (head_nodes, head_rels) in forward.routes_to(node) {
+                for (tail_nodes, tail_rels) in backward.routes_to(node) {
+                    let mut nodes = head_nodes.clone();
+                    nodes.extend(tail_nodes.iter().rev().skip(1));
+                    let mut relationships = head_rels.clone();
+                    relationships.extend(tail_rels.iter().rev());
+
+                    paths.push(Path {
+                        nodes,
+                        relationships,
+                    });
+                    if !all {
+                        return paths;
+                    }
+                }
+            }
+        }
+
+        paths
+    }
+}
+
+impl Graph {
+    /// Compute PageRank scores for every node
+    ///
+    /// Iterates over the outgoing adjacency lists until the L1 change between
+    /// two iterations drops below `tolerance` or `iterations` is reached.
+    /// Rank held by dangling nodes (no outgoing relationships) is spread
+    /// uniformly across all nodes, so scores always sum to 1.
+    pub fn pagerank(
+        &self,
+        damping: f64,
+        iterations: usize,
+        tolerance: f64,
+    ) -> Result<HashMap<NodeId, f64>> {
+        if !(0.0..=1.0).contains(&damping) {
+            return Err(Error::InvalidGraphOperation(format!(
+                "PageRank damping factor must be between 0 and 1, got {}",
+                damping
+            )));
+        }
+        if tolerance.is_nan() || tolerance < 0.0 {
+            return Err(Error::InvalidGraphOperation(format!(
+                "PageRank tolerance must be non-negative, got {}",
+                tolerance
+            )));
+        }
+
+        let nodes: Vec<NodeId> = self.get_all_nodes()?.into_iter().map(|n| n.id).collect();
+        if nodes.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let index: HashMap<NodeId, usize> =
+            nodes.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        let mut out_edges: Vec<Vec<usize>> = Vec::with_capacity(nodes.len());
+        for node in &nodes {
+            let targets = self
+                .get_relationships(*node, Direction::Outgoing)?
+                .into_iter()
+                .filter_map(|rel| index.get(&rel.target).copied())
+                .collect();
+            out_edges.push(targets);
+        }
+
+        let count = nodes.len() as f64;
+        let mut ranks = vec![1.0 / count; nodes.len()];
+
+        for _ in 0..iterations {
+            let dangling: f64 = out_edges
+                .iter()
+                .zip(&ranks)
+                .filter(|(targets, _)| targets.is_empty())
+                .map(|(_, rank)| rank)
+                .sum();
+
+            let base = (1.0 - damping) / count + damping * dangling / count;
+            let mut next = vec![base; nodes.len()];
+            for (targets, rank) in out_edges.iter().zip(&ranks) {
+                if targets.is_empty() {
+                    continue;
+                }
+                let share = damping * rank / targets.len() as f64;
+                for target in targets {
+                    next[*target] += share;
+                }
+            }
+
+            let delta: f64 = next.iter().zip(&ranks).map(|(a, b)| (a - b).abs()).sum();
+            ranks = next;
+            if delta < tolerance {
+                break;
+            }
+        }
+
+        Ok(nodes.into_iter().zip(ranks).collect())
+    }
+}
+
+impl Graph {
+    /// Partition the graph into connected components
+    ///
+    /// `Direction::Both` yields weakly connected components, computed with
+    /// union-find over the relationships. A directed `direction` yields
+    /// strongly connected components using Tarjan's algorithm. Adjacency is
+    /// read one node at a time, so only node IDs are held for the whole graph.
+    ///
+    /// Nodes within a component are sorted by ID, and components are ordered
+    /// by their smallest node ID.
+    pub fn connected_components(&self, direction: Direction) -> Result<Vec<Vec<NodeId>>> {
+        let nodes = self.get_all_node_ids()?;
+        let index: HashMap<NodeId, usize> =
+            nodes.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+
+        let mut components = match direction {
+            Direction::Both => self.weakly_connected_components(&nodes, &index)?,
+            _ => self.strongly_connected_components(&nodes, &index, direction)?,
+        };
+
+        for component in &mut components {
+            component.sort_by_key(|n| n.as_internal());
+        }
+        components.sort_by_key(|c| c[0].as_internal());
+        Ok(components)
+    }
+
+    fn weakly_connected_components(
+        &self,
+        nodes: &[NodeId],
+        index: &HashMap<NodeId, usize>,
+    ) -> Result<Vec<Vec<NodeId>>> {
+        let mut sets = DisjointSet::new(nodes.len());
+
+        // Every relationship is outgoing from exactly one node
+        for (i, node) in nodes.iter().enumerate() {
+            for rel in self.get_relationships(*node, Direction::Outgoing)? {
+                if let Some(&j) = index.get(&rel.target) {
+                    sets.union(i, j);
+                }
+            }
+        }
+
+        let mut groups: HashMap<usize, Vec<NodeId>> = HashMap::new();
+        for (i, node) in nodes.iter().enumerate() {
+            groups.entry(sets.find(i)).or_default().push(*node);
+        }
+        Ok(groups.into_values().collect())
+    }
+
+    /// Iterative Tarjan's algorithm, so deep graphs cannot overflow the stack
+    fn strongly_connected_components(
+        &self,
+        nodes: &[NodeId],
+        index: &HashMap<NodeId, usize>,
+        direction: Direction,
+    ) -> Result<Vec<Vec<NodeId>>> {
+        let neighbors = |i: usize| -> Result<Vec<usize>> {
+            Ok(self
+                .get_relationships(nodes[i], direction)?
+                .into_iter()
+                .filter_map(|rel| {
+                    let neighbor = match direction {
+                        Direction::Incoming => rel.source,
+                        _ => rel.target,
+                    };
+                    index.get(&neighbor).copied()
+                })
+                .collect())
+        };
+
+        let mut order: Vec<Option<usize>> = vec![None; nodes.len()];
+        let mut lowlink = vec![0; nodes.len()];
+        let mut on_stack = vec![false; nodes.len()];
+        let mut stack = Vec::new();
+        let mut next_order = 0;
+        let mut components = Vec::new();
+
+        for root in 0..nodes.len() {
+            if order[root].is_some() {
+                continue;
+            }
+
+            // Each frame is (node, its neighbors, next neighbor to visit)
+            order[root] = Some(next_order);
+            lowlink[root] = next_order;
+            next_order += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            let mut frames = vec![(root, neighbors(root)?, 0)];
+
+            while let Some((v, edges, pos)) = frames.last_mut() {
+                let v = *v;
+                if let Some(&w) = edges.get(*pos) {
+                    *pos += 1;
+                    match order[w] {
+                        None => {
+                            order[w] = Some(next_order);
+                            lowlink[w] = next_order;
+                            next_order += 1;
+                            stack.push(w);
+                            on_stack[w] = true;
+                            frames.push((w, neighbors(w)?, 0));
+                        }
+                        Some(w_order) if on_stack[w] => {
+                            lowlink[v] = lowlink[v].min(w_order);
+                        }
+                        Some(_) => {}
+                    }
+                    continue;
+                }
+
+                frames.pop();
+                if let Some((parent, _, _)) = frames.last() {
+                    lowlink[*parent] = lowlink[*parent].min(lowlink[v]);
+                }
+
+                if Some(lowlink[v]) == order[v] {
+                    let mut component = Vec::new();
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component.push(nodes[w]);
+                        if w == v {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+
+        Ok(components)
+    }
+}
+
+/// Union-find with path halving and union by size
+struct DisjointSet {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(count: usize) -> Self {
+        Self {
+            parent: (0..count).collect(),
+            size: vec![1; count],
+        }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Database;
+    use tempfile::TempDir;
+
+    fn create_test_graph() -> (Graph, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        (graph, temp_dir)
+    }
+
+    fn create_nodes(graph: &Graph, count: usize) -> Vec<NodeId> {
+        (0..count)
+            .map(|_| graph.create_node(["Person"]).unwrap().id)
+            .collect()
+    }
+
+    #[test]
+    fn test_shortest_path_line() {
+        let (graph, _dir) = create_test_graph();
+        let n = create_nodes(&graph, 4);
+        let r1 = graph.create_relationship(n[0], "KNOWS", n[1]).unwrap();
+        let r2 = graph.create_relationship(n[1], "KNOWS", n[2]).unwrap();
+        let r3 = graph.create_relationship(n[2], "KNOWS", n[3]).unwrap();
+
+        let path = graph
+            .shortest_path(n[0], n[3], Direction::Outgoing, &PathOptions::default())
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.nodes, n);
+        assert_eq!(path.relationships, vec![r1.id, r2.id, r3.id]);
+        assert_eq!(path.len(), 3);
+    }
+
+    #[test]
+    fn test_shortest_path_respects_direction() {
+        let (graph, _dir) = create_test_graph();
+        let n = create_nodes(&graph, 2);
+        graph.create_relationship(n[1], "KNOWS", n[0]).unwrap();
+
+        let options = PathOptions::default();
+        assert!(
+            graph
+                .shortest_path(n[0], n[1], Direction::Outgoing, &options)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            graph
+                .shortest_path(n[0], n[1], Direction::Incoming, &options)
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            graph
+                .shortest_path(n[0], n[1], Direction::Both, &options)
+                .unwrap()
+                .is_some()
+        );
+    }
+
+    #[test]
+    fn test_all_shortest_paths_with_cycles() {
+        let (graph, _dir) = create_test_graph();
+        // Diamond 0 -> {1, 2} -> 3 plus a cycle back to the start and a longer detour
+        let n = create_nodes(&graph, 5);
+        graph.create_relationship(n[0], "KNOWS", n[1]).unwrap();
+        graph.create_relationship(n[0], "KNOWS", n[2]).unwrap();
+        graph.create_relationship(n[1], "KNOWS", n[3]).unwrap();
+        graph.create_relationship(n[2], "KNOWS", n[3]).unwrap();
+        graph.create_relationship(n[3], "KNOWS", n[0]).unwrap();
+        graph.create_relationship(n[1], "KNOWS", n[4]).unwrap();
+        graph.create_relationship(n[4], "KNOWS", n[3]).unwrap();
+
+        let paths = graph
+            .all_shortest_paths(n[0], n[3], Direction::Outgoing, &PathOptions::default())
+            .unwrap();
+        assert_eq!(paths.len(), 2);
+        assert!(
+            paths
+                .iter()
+                .all(|p| p.len() == 2 && p.start() == n[0] && p.end() == n[3])
+        );
+        assert_ne!(paths[0], paths[1]);
+    }
+
+    #[test]
+    fn test_shortest_path_options() {
+        let (graph, _dir) = create_test_graph();
+        let n = create_nodes(&graph, 3);
+        graph.create_relationship(n[0], "KNOWS", n[1]).unwrap();
+        graph.create_relationship(n[1], "KNOWS", n[2]).unwrap();
+        graph.create_relationship(n[0], "BLOCKS", n[2]).unwrap();
+
+        let knows = PathOptions {
+            rel_types: vec!["KNOWS".to_string()],
+            max_hops: None,
+        };
+        let path = graph
+            .shortest_path(n[0], n[2], Direction::Outgoing, &knows)
+            .unwrap()
+            .unwrap();
+        assert_eq!(path.len(), 2);
+
+        let bounded = PathOptions {
+            rel_types: vec!["KNOWS".to_string()],
+            max_hops: Some(1),
+        };
+        assert!(
+            graph
+                .shortest_path(n[0], n[2], Direction::Outgoing, &bounded)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_shortest_path_unreachable() {
+        let (graph, _dir) = create_test_graph();
+        let n = create_nodes(&graph, 3);
+        graph.create_relationship(n[0], "KNOWS", n[1]).unwrap();
+        graph.create_relationship(n[1], "KNOWS", n[0]).unwrap();
+
+        assert!(
+            graph
+                .all_shortest_paths(n[0], n[2], Direction::Both, &PathOptions::default())
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_pagerank_cycle_is_uniform() {
+        let (graph, _dir) = create_test_graph();
+        let n = create_nodes(&graph, 3);
+        graph.create_relationship(n[0], "LINKS", n[1]).unwrap();
+        graph.create_relationship(n[1], "LINKS", n[2]).unwrap();
+        graph.create_relationship(n[2], "LINKS", n[0]).unwrap();
+
+        let ranks = graph.pagerank(0.85, 100, 1e-9).unwrap();
+        assert_eq!(ranks.len(), 3);
+        for id in &n {
+            assert!((ranks[id] - 1.0 / 3.0).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_pagerank_dangling_nodes() {
+        let (graph, _dir) = create_test_graph();
+        // 0 -> 2 and 1 -> 2, node 2 has no outgoing relationships
+        let n = create_nodes(&graph, 3);
+        graph.create_relationship(n[0], "LINKS", n[2]).unwrap();
+        graph.create_relationship(n[1], "LINKS", n[2]).unwrap();
+
+        let ranks = graph
+            .pagerank(
+                DEFAULT_PAGERANK_DAMPING,
+                DEFAULT_PAGERANK_ITERATIONS,
+                DEFAULT_PAGERANK_TOLERANCE,
+            )
+            .unwrap();
+        let total: f64 = ranks.values().sum();
+        assert!((total - 1.0).abs() < 1e-9);
+        assert!(ranks[&n[2]] > ranks[&n[0]]);
+        assert!((ranks[&n[0]] - ranks[&n[1]]).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_pagerank_validates_arguments() {
+        let (graph, _dir) = create_test_graph();
+        assert!(graph.pagerank(0.85, 20, 1e-6).unwrap().is_empty());
+        assert!(matches!(
+            graph.pagerank(1.5, 20, 1e-6),
+            Err(Error::InvalidGraphOperation(_))
+        ));
+        assert!(graph.pagerank(0.85, 20, -1.0).is_err());
+    }
+
+    #[test]
+    fn test_connected_components() {
+        let (graph, _dir) = create_test_graph();
+        // Cluster one: 0 -> 1 -> 2 -> 0 plus 2 -> 3
+        // Cluster two: 4 -> 5, 5 -> 4
+        // Node 6 is isolated
+        let n = create_nodes(&graph, 7);
+        graph.create_relationship(n[0], "KNOWS", n[1]).unwrap();
+        graph.create_relationship(n[1], "KNOWS", n[2]).unwrap();
+        graph.create_relationship(n[2], "KNOWS", n[0]).unwrap();
+        graph.create_relationship(n[2], "KNOWS", n[3]).unwrap();
+        graph.create_relationship(n[4], "KNOWS", n[5]).unwrap();
+        graph.create_relationship(n[5], "KNOWS", n[4]).unwrap();
+
+        let weak = graph.connected_components(Direction::Both).unwrap();
+        assert_eq!(
+            weak,
+            vec![vec![n[0], n[1], n[2], n[3]], vec![n[4], n[5]], vec![n[6]]]
+        );
+
+        // Node 3 has no path back into the cycle, so it is its own component
+        let strong = graph.connected_components(Direction::Outgoing).unwrap();
+        assert_eq!(
+            strong,
+            vec![
+                vec![n[0], n[1], n[2]],
+                vec![n[3]],
+                vec![n[4], n[5]],
+                vec![n[6]]
+            ]
+        );
+        assert_eq!(
+            graph.connected_components(Direction::Incoming).unwrap(),
+            strong
+        );
+    }
+
+    #[test]
+    fn test_connected_components_long_chain() {
+        let (graph, _dir) = create_test_graph();
+        let n = create_nodes(&graph, 500);
+        for pair in n.windows(2) {
+            graph.create_relationship(pair[0], "NEXT", pair[1]).unwrap();
+        }
+        graph.create_relationship(n[499], "NEXT", n[0]).unwrap();
+
+        assert_eq!(
+            graph.connected_components(Direction::Outgoing).unwrap(),
+            vec![n.clone()]
+        );
+        assert_eq!(
+            graph.connected_components(Direction::Both).unwrap(),
+            vec![n]
+        );
+    }
+}
@@ -1,13 +1,16 @@
 //! Database management for QilbeeDB
 
-use crate::graph::Graph;
-use qilbee_core::{Error, GraphId, Result};
+use crate::graph::{Graph, GraphQuotas};
+use qilbee_core::{Error, GraphId, IdStrategy, Result};
 use qilbee_storage::{StorageEngine, StorageOptions};
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::{Arc, RwLock};
 use tracing::{info, warn};
 
+/// File in a checkpoint directory naming the checkpointed graph
+const CHECKPOINT_MANIFEST: &str = "QILBEE_CHECKPOINT";
+
 /// The main database instance for QilbeeDB
 ///
 /// Manages multiple graphs and provides access to the storage engine.
@@ -30,6 +33,36 @@ pub struct DatabaseConfig {
 
     /// Default graph name
     pub default_graph: String,
+
+    /// Node and relationship ID allocation strategy
+    pub id_strategy: IdStrategy,
+
+    /// Maximum number of nodes per graph (`None` for unlimited)
+    pub max_nodes_per_graph: Option<u64>,
+
+    /// Maximum number of relationships per graph (`None` for unlimited)
+    pub max_relationships_per_graph: Option<u64>,
+
+    /// Relationship property that orders adjacency scans (`None` for ID order)
+    pub adjacency_sort_property: Option<String>,
+
+    /// Maximum number of properties on a node or relationship (`None` for unlimited)
+    pub max_properties_per_entity: Option<usize>,
+
+    /// Maximum serialized size of a property value in bytes (`None` for unlimited)
+    pub max_property_value_bytes: Option<usize>,
+
+    /// Distance within which floats compare equal (`None` for exact comparison)
+    pub float_epsilon: Option<f64>,
+}
+
+impl DatabaseConfig {
+    fn graph_quotas(&self) -> GraphQuotas {
+        GraphQuotas {
+            max_nodes: self.max_nodes_per_graph,
+            max_relationships: self.max_relationships_per_graph,
+        }
+    }
 }
 
 impl Default for DatabaseConfig {
@@ -37,6 +70,13 @@ impl Default for DatabaseConfig {
         Self {
             max_graphs: 10000,
             default_graph: "default".to_string(),
+            id_strategy: IdStrategy::default(),
+            max_nodes_per_graph: None,
+            max_relationships_per_graph: None,
+            adjacency_sort_property: None,
+            max_properties_per_entity: None,
+            max_property_value_bytes: None,
+            float_epsilon: None,
         }
     }
 }
@@ -63,7 +103,11 @@ impl Database {
 
     /// Open or create a database with custom configuration
     pub fn open_with_config<P: AsRef<Path>>(path: P, config: DatabaseConfig) -> Result<Self> {
-        let storage_opts = StorageOptions::new(path.as_ref());
+        let mut storage_opts = StorageOptions::new(path.as_ref());
+        storage_opts.adjacency_sort_property = config.adjacency_sort_property.clone();
+        storage_opts.max_properties_per_entity = config.max_properties_per_entity;
+        storage_opts.max_property_value_bytes = config.max_property_value_bytes;
+        storage_opts.float_epsilon = config.float_epsilon;
         let storage = StorageEngine::open(storage_opts)?;
 
         info!("Opened database at {:?}", path.as_ref());
@@ -85,11 +129,15 @@ impl Database {
         let storage_opts = StorageOptions::for_testing(path.as_ref());
         let storage = StorageEngine::open(storage_opts)?;
 
-        Ok(Self {
+        let db = Self {
             storage,
             graphs: Arc::new(RwLock::new(HashMap::new())),
             config: DatabaseConfig::default(),
-        })
+        };
+
+        db.load_graphs()?;
+
+        Ok(db)
     }
 
     /// Create or get a graph by name
@@ -125,13 +173,18 @@ impl Database {
 
         // Check max graphs limit
         if graphs.len() >= self.config.max_graphs {
-            return Err(Error::InvalidGraphOperation(format!(
+            return Err(Error::QuotaExceeded(format!(
                 "Maximum number of graphs ({}) reached",
                 self.config.max_graphs
             )));
         }
 
-        let graph = Graph::new(name.to_string(), self.storage.clone());
+        let graph = Graph::new(
+            name.to_string(),
+            self.storage.clone(),
+            self.config.id_strategy,
+            self.config.graph_quotas(),
+        )?;
         graphs.insert(name.to_string(), graph.clone());
 
         // Store graph metadata (collect names while holding lock)
@@ -200,6 +253,44 @@ impl Database {
         Ok(graphs.len())
     }
 
+    /// Create a point-in-time checkpoint of a graph in the new directory `path`
+    ///
+    /// The database stays open for reads and writes meanwhile. The checkpoint
+    /// holds every write to the graph committed before it was taken and none
+    /// committed after. It hard-links the storage files rather than copying
+    /// them, so it is cheap however large the database. It is a checkpoint of
+    /// the whole database: it also holds every other graph and the other
+    /// column families, such as user accounts with their password hashes,
+    /// but only `graph_name` is restored from it.
+    pub fn checkpoint<P: AsRef<Path>>(&self, graph_name: &str, path: P) -> Result<()> {
+        let graph = self
+            .graphs
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire graphs lock".to_string()))?
+            .get(graph_name)
+            .cloned()
+            .ok_or_else(|| Error::GraphNotFound(graph_name.to_string()))?;
+
+        let path = path.as_ref();
+        graph.checkpoint(path)?;
+        std::fs::write(path.join(CHECKPOINT_MANIFEST), graph_name)?;
+
+        info!("Checkpointed graph '{}' to {:?}", graph_name, path);
+        Ok(())
+    }
+
+    /// Open the graph of a checkpoint created by [`Self::checkpoint`]
+    ///
+    /// The checkpoint is opened read-only, so it can be restored from again.
+    pub fn restore_from<P: AsRef<Path>>(path: P) -> Result<Graph> {
+        let path = path.as_ref();
+        let name = std::fs::read_to_string(path.join(CHECKPOINT_MANIFEST)).map_err(|e| {
+            Error::ValidationError(format!("{:?} is not a graph checkpoint: {}", path, e))
+        })?;
+        let storage = StorageEngine::open_read_only(StorageOptions::new(path))?;
+        Graph::new(name, storage, IdStrategy::default(), GraphQuotas::default())
+    }
+
     /// Flush all data to disk
     pub fn flush(&self) -> Result<()> {
         self.storage.flush()
@@ -210,6 +301,11 @@ impl Database {
         self.storage.compact()
     }
 
+    /// Get the applied schema version
+    pub fn schema_version(&self) -> Result<u64> {
+        self.storage.get_schema_version()
+    }
+
     /// Get storage statistics
     pub fn stats(&self) -> String {
         self.storage.stats()
@@ -228,7 +324,12 @@ impl Database {
             })?;
 
             for name in graph_names {
-                let graph = Graph::new(name.clone(), self.storage.clone());
+                let graph = Graph::new(
+                    name.clone(),
+                    self.storage.clone(),
+                    self.config.id_strategy,
+                    self.config.graph_quotas(),
+                )?;
                 graphs.insert(name, graph);
             }
         }
@@ -250,6 +351,7 @@ impl Clone for Database {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use qilbee_core::EntityId;
     use tempfile::TempDir;
 
     fn create_test_db() -> (Database, TempDir) {
@@ -313,6 +415,32 @@ mod tests {
         assert!(!db.delete_graph("test").unwrap());
     }
 
+    #[test]
+    fn test_checkpoint_and_restore() {
+        let (db, dir) = create_test_db();
+        let graph = db.graph("social").unwrap();
+        let alice = graph.create_node(["Person"]).unwrap().id;
+        let bob = graph.create_node(["Person"]).unwrap().id;
+        graph.create_relationship(alice, "KNOWS", bob).unwrap();
+        db.graph("other").unwrap().create_node(["Company"]).unwrap();
+
+        let path = dir.path().join("backup");
+        db.checkpoint("social", &path).unwrap();
+        graph.create_node(["Person"]).unwrap();
+        graph.detach_delete_node(bob).unwrap();
+
+        let restored = Database::restore_from(&path).unwrap();
+        assert_eq!(restored.name(), "social");
+        assert_eq!(restored.node_count().unwrap(), 2);
+        assert_eq!(restored.relationship_count().unwrap(), 1);
+        assert!(restored.get_node(bob).unwrap().is_some());
+        assert_eq!(graph.node_count().unwrap(), 2);
+        assert_eq!(graph.relationship_count().unwrap(), 0);
+
+        assert!(matches!(db.checkpoint("missing", dir.path().join("none")), Err(Error::GraphNotFound(_))));
+        assert!(Database::restore_from(dir.path()).is_err());
+    }
+
     #[test]
     fn test_graph_count() {
         let (db, _dir) = create_test_db();
@@ -343,4 +471,94 @@ mod tests {
         let graph = db.default_graph().unwrap();
         assert_eq!(graph.name(), "default");
     }
+
+    #[test]
+    fn test_ids_not_reused_after_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+
+        let mut first_ids = Vec::new();
+        {
+            let db = Database::open_for_testing(temp_dir.path()).unwrap();
+            let graph = db.graph("test").unwrap();
+            for _ in 0..5 {
+                first_ids.push(graph.create_node(["Person"]).unwrap().id);
+            }
+        }
+
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        let max_first = first_ids.iter().map(|id| id.as_internal()).max().unwrap();
+
+        for _ in 0..5 {
+            let node = graph.create_node(["Person"]).unwrap();
+            assert!(!first_ids.contains(&node.id));
+            assert!(node.id.as_internal() > max_first);
+        }
+
+        // Nodes from the first session are untouched
+        for id in &first_ids {
+            assert!(graph.get_node(*id).unwrap().is_some());
+        }
+    }
+
+    fn open_with_quotas(path: &Path, config: DatabaseConfig) -> Database {
+        let storage = StorageEngine::open(StorageOptions::for_testing(path)).unwrap();
+        let db = Database {
+            storage,
+            graphs: Arc::new(RwLock::new(HashMap::new())),
+            config,
+        };
+        db.load_graphs().unwrap();
+        db
+    }
+
+    #[test]
+    fn test_max_graphs_quota() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DatabaseConfig {
+            max_graphs: 2,
+            ..Default::default()
+        };
+        let db = open_with_quotas(temp_dir.path(), config);
+
+        db.create_graph("one").unwrap();
+        db.graph("two").unwrap();
+        assert!(matches!(db.create_graph("three"), Err(Error::QuotaExceeded(_))));
+        assert!(matches!(db.graph("three"), Err(Error::QuotaExceeded(_))));
+
+        // Existing graphs stay reachable and deleting one frees a slot
+        assert!(db.graph("one").is_ok());
+        assert!(db.delete_graph("one").unwrap());
+        db.create_graph("three").unwrap();
+    }
+
+    #[test]
+    fn test_graph_quotas_survive_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DatabaseConfig {
+            max_nodes_per_graph: Some(3),
+            max_relationships_per_graph: Some(1),
+            ..Default::default()
+        };
+
+        {
+            let db = open_with_quotas(temp_dir.path(), config.clone());
+            let graph = db.graph("tenant").unwrap();
+            let a = graph.create_node(["Person"]).unwrap();
+            let b = graph.create_node(["Person"]).unwrap();
+            graph.create_relationship(a.id, "KNOWS", b.id).unwrap();
+        }
+
+        let db = open_with_quotas(temp_dir.path(), config);
+        let graph = db.graph("tenant").unwrap();
+        assert_eq!(graph.node_count().unwrap(), 2);
+        assert_eq!(graph.relationship_count().unwrap(), 1);
+
+        let c = graph.create_node(["Person"]).unwrap();
+        assert!(matches!(graph.create_node(["Person"]), Err(Error::QuotaExceeded(_))));
+        assert!(matches!(
+            graph.create_relationship(c.id, "KNOWS", c.id),
+            Err(Error::QuotaExceeded(_))
+        ));
+    }
 }
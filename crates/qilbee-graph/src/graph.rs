@@ -1,14 +1,156 @@
 //! Graph instance implementation
 
-use crate::schema::Schema;
+use crate::schema::{Constraint, Index, IndexType, Schema};
 use qilbee_core::{
-    Direction, EntityId, Error, GraphId, IdGenerator, Label, Node, NodeId, Property,
+    Direction, EntityId, Error, GraphId, IdGenerator, IdStrategy, Label, Node, NodeId, Property,
     PropertyValue, Relationship, RelationshipId, Result,
 };
-use qilbee_storage::{StorageEngine, Transaction};
-use std::sync::{Arc, RwLock};
+use qilbee_storage::keys::sortable_property_value;
+use qilbee_core::temporal::TransactionTime;
+use qilbee_storage::{
+    CompositeIndex, GraphDiff, IndexBuildProgress, StorageEngine, StorageSnapshot, Transaction, TypeCounts,
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex, PoisonError, RwLock};
 use tracing::{debug, info};
 
+/// Number of sequential IDs reserved per high-water mark write
+const ID_RESERVATION_BLOCK: u64 = 1000;
+
+/// Schema type under which constraints are persisted
+const CONSTRAINT_SCHEMA: &str = "constraint";
+
+/// Schema type under which non-composite index definitions are persisted
+///
+/// Composite indexes keep their definition with their storage entries.
+const INDEX_SCHEMA: &str = "index";
+
+/// Per-graph limits on stored entities
+///
+/// `None` leaves the corresponding entity count unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GraphQuotas {
+    /// Maximum number of nodes in the graph
+    pub max_nodes: Option<u64>,
+
+    /// Maximum number of relationships in the graph
+    pub max_relationships: Option<u64>,
+}
+
+/// Order of nodes returned by the `*_ordered` listing methods
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum NodeOrder {
+    /// Storage iteration order, with no sorting
+    #[default]
+    Unordered,
+
+    /// Ascending internal ID
+    Id,
+
+    /// Ascending value of a property, ties broken by ID
+    ///
+    /// Values are grouped by type as for ordered adjacency (null, booleans,
+    /// integers, floats, strings, then temporal types). Nodes without the
+    /// property come last.
+    Property(String),
+}
+
+impl NodeOrder {
+    /// Sort `nodes` into this order
+    pub fn sort(&self, nodes: &mut [Node]) {
+        match self {
+            NodeOrder::Unordered => {}
+            // Storage keys are ID-ordered, so this is usually a linear pass
+            NodeOrder::Id => nodes.sort_by_key(|node| node.id.as_internal()),
+            NodeOrder::Property(property) => nodes.sort_by_cached_key(|node| {
+                (sortable_property_value(node.get_property(property)), node.id.as_internal())
+            }),
+        }
+    }
+}
+
+/// What a graph handle reads from instead of the latest state
+enum GraphView {
+    /// One committed state, see [`Graph::snapshot`]
+    Snapshot(Box<StorageSnapshot>),
+    /// A transaction's snapshot and pending writes, see [`Graph::transaction`]
+    Transaction(GraphTransaction),
+}
+
+/// The storage transaction behind a [`Graph::transaction`] handle
+struct GraphTransaction {
+    /// `None` once committed or rolled back
+    tx: Mutex<Option<Box<Transaction>>>,
+    /// Keeps merges out until the transaction ends
+    merge_guard: Mutex<Option<MergeGuard>>,
+}
+
+impl GraphTransaction {
+    /// Run `f` on the transaction, failing if it has ended
+    fn with<T>(&self, f: impl FnOnce(&mut Transaction) -> Result<T>) -> Result<T> {
+        let mut tx = self
+            .tx
+            .lock()
+            .map_err(|_| Error::Internal("Failed to acquire transaction lock".to_string()))?;
+        let tx = tx
+            .as_mut()
+            .ok_or_else(|| Error::TransactionAborted("Transaction is no longer active".to_string()))?;
+        f(tx)
+    }
+
+    /// End the transaction, returning it unless it had already ended
+    fn finish(&self) -> Result<Box<Transaction>> {
+        let tx = self
+            .tx
+            .lock()
+            .map_err(|_| Error::Internal("Failed to acquire transaction lock".to_string()))?
+            .take()
+            .ok_or_else(|| Error::TransactionAborted("Transaction is no longer active".to_string()))?;
+        if let Ok(mut guard) = self.merge_guard.lock() {
+            guard.take();
+        }
+        Ok(tx)
+    }
+}
+
+/// Serializes merges, see [`Graph::merge_node`]
+///
+/// Unlike a `MutexGuard`, a [`MergeGuard`] owns its lock, so a transaction
+/// handle can hold it until the transaction ends.
+#[derive(Default)]
+struct MergeLock {
+    held: Mutex<bool>,
+    released: Condvar,
+}
+
+impl MergeLock {
+    /// Wait until no merge or transaction holds the lock, then take it
+    fn acquire(self: &Arc<Self>) -> Result<MergeGuard> {
+        let mut held = self
+            .held
+            .lock()
+            .map_err(|_| Error::Internal("Failed to acquire merge lock".to_string()))?;
+        while *held {
+            held = self
+                .released
+                .wait(held)
+                .map_err(|_| Error::Internal("Failed to acquire merge lock".to_string()))?;
+        }
+        *held = true;
+        Ok(MergeGuard(Arc::clone(self)))
+    }
+}
+
+/// Holds a [`MergeLock`] until dropped
+struct MergeGuard(Arc<MergeLock>);
+
+impl Drop for MergeGuard {
+    fn drop(&mut self) {
+        *self.0.held.lock().unwrap_or_else(PoisonError::into_inner) = false;
+        self.0.released.notify_one();
+    }
+}
+
 /// A graph instance in QilbeeDB
 pub struct Graph {
     /// Graph identifier
@@ -23,21 +165,85 @@ pub struct Graph {
     /// ID generator for this graph
     id_gen: Arc<IdGenerator>,
 
+    /// Persisted upper bound of reserved node IDs
+    node_id_ceiling: Arc<Mutex<u64>>,
+
+    /// Persisted upper bound of reserved relationship IDs
+    rel_id_ceiling: Arc<Mutex<u64>>,
+
+    /// Number of nodes currently stored, persisted on every change
+    node_count: Arc<Mutex<u64>>,
+
+    /// Number of relationships currently stored, persisted on every change
+    rel_count: Arc<Mutex<u64>>,
+
+    /// Entity limits enforced at create time
+    quotas: GraphQuotas,
+
+    /// Serializes merges so concurrent ones cannot both create a node
+    merge_lock: Arc<MergeLock>,
+
     /// Schema for this graph
     schema: Arc<RwLock<Schema>>,
+
+    /// Where entities are read from, `None` for the latest state
+    view: Option<Arc<GraphView>>,
 }
 
 impl Graph {
     /// Create a new graph instance (internal use)
-    pub(crate) fn new(name: String, storage: StorageEngine) -> Self {
+    ///
+    /// Sequential ID allocation resumes from the high-water mark persisted
+    /// in the metadata column family, so IDs are never reused after a restart.
+    /// Entity counts are likewise loaded from metadata; graphs written before
+    /// counts were tracked are scanned once to initialize them. Indexes and
+    /// constraints created earlier are added to the schema.
+    pub(crate) fn new(
+        name: String,
+        storage: StorageEngine,
+        id_strategy: IdStrategy,
+        quotas: GraphQuotas,
+    ) -> Result<Self> {
         let id = GraphId::from_name(&name);
-        Self {
+        let node_ceiling = Self::load_id_watermark(&storage, id, "node")?;
+        let rel_ceiling = Self::load_id_watermark(&storage, id, "relationship")?;
+        let (node_count, rel_count) = Self::load_entity_counts(&storage, id)?;
+
+        let mut schema = Schema::new();
+        for index in storage.composite_indexes(id)? {
+            let properties = index.properties.iter().map(String::as_str).collect();
+            schema.add_index(Index::composite(&index.name, index.label.as_str(), properties));
+        }
+        for (_, data) in storage.schema_objects(id, INDEX_SCHEMA)? {
+            let index: Index = serde_json::from_slice(&data)
+                .map_err(|e| Error::Deserialization(e.to_string()))?;
+            schema.add_index(index);
+        }
+        for (_, data) in storage.schema_objects(id, CONSTRAINT_SCHEMA)? {
+            let constraint: Constraint = serde_json::from_slice(&data)
+                .map_err(|e| Error::Deserialization(e.to_string()))?;
+            schema.add_constraint(constraint);
+        }
+
+        let id_gen = match id_strategy {
+            IdStrategy::Sequential => IdGenerator::with_start(node_ceiling.max(1), rel_ceiling.max(1)),
+            IdStrategy::Random => IdGenerator::random(),
+        };
+
+        Ok(Self {
             id,
             name,
             storage,
-            id_gen: Arc::new(IdGenerator::new()),
-            schema: Arc::new(RwLock::new(Schema::new())),
-        }
+            id_gen: Arc::new(id_gen),
+            node_id_ceiling: Arc::new(Mutex::new(node_ceiling)),
+            rel_id_ceiling: Arc::new(Mutex::new(rel_ceiling)),
+            node_count: Arc::new(Mutex::new(node_count)),
+            rel_count: Arc::new(Mutex::new(rel_count)),
+            quotas,
+            merge_lock: Arc::new(MergeLock::default()),
+            schema: Arc::new(RwLock::new(schema)),
+            view: None,
+        })
     }
 
     /// Get the graph ID
@@ -55,11 +261,256 @@ impl Graph {
         &self.schema
     }
 
+    /// Add an index to the schema, persisting it across restarts
+    ///
+    /// Composite indexes are also built in storage, covering the nodes
+    /// already stored. Range indexes fill in property index entries missing
+    /// for stored nodes. Returns `false` if an index with the same name
+    /// exists.
+    pub fn create_index(&self, index: Index) -> Result<bool> {
+        Ok(self.create_index_with_progress(index, |_| {})?.is_some())
+    }
+
+    /// Like [`Self::create_index`], reporting the progress of a range index
+    /// build and returning the number of nodes indexed, or `None` if an index
+    /// with the same name exists
+    ///
+    /// A range index is built online: the graph can be read and written
+    /// while its nodes are indexed in batches, and nodes written meanwhile
+    /// end up indexed by their latest value.
+    pub fn create_index_with_progress(
+        &self,
+        index: Index,
+        mut progress: impl FnMut(IndexBuildProgress),
+    ) -> Result<Option<u64>> {
+        // The schema is not locked during the build, so queries can still
+        // be planned against it
+        let exists = self
+            .schema
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire schema lock".to_string()))?
+            .get_index(&index.name)
+            .is_some();
+        if exists {
+            return Ok(None);
+        }
+
+        let indexed = match index.index_type {
+            IndexType::Range => {
+                let mut indexed = 0;
+                for property in &index.properties {
+                    indexed += self
+                        .storage
+                        .build_property_index(self.id, index.label.name(), property, &mut progress)?;
+                }
+                indexed
+            }
+            IndexType::Composite => {
+                let definition = CompositeIndex::new(&index.name, index.label.name(), index.properties.clone());
+                if !self.storage.create_composite_index(self.id, definition)? {
+                    return Ok(None);
+                }
+                // Nodes with the label and the first property are indexed
+                self.storage
+                    .get_nodes_with_property(self.id, index.label.name(), &index.properties[0])?
+                    .len() as u64
+            }
+            IndexType::FullText | IndexType::Vector => 0,
+        };
+
+        let mut schema = self.schema.write().map_err(|_| {
+            Error::Internal("Failed to acquire schema lock".to_string())
+        })?;
+        if schema.get_index(&index.name).is_some() {
+            return Ok(None);
+        }
+        if index.index_type != IndexType::Composite {
+            let data = serde_json::to_vec(&index).map_err(|e| Error::Serialization(e.to_string()))?;
+            self.storage.put_schema_object(self.id, INDEX_SCHEMA, &index.name, &data)?;
+        }
+        schema.add_index(index);
+        Ok(Some(indexed))
+    }
+
+    /// Remove an index from the schema and from storage
+    pub fn drop_index(&self, name: &str) -> Result<Option<Index>> {
+        let mut schema = self.schema.write().map_err(|_| {
+            Error::Internal("Failed to acquire schema lock".to_string())
+        })?;
+        let index = schema.remove_index(name);
+        match index.as_ref().map(|index| &index.index_type) {
+            Some(IndexType::Composite) => {
+                self.storage.drop_composite_index(self.id, name)?;
+            }
+            Some(_) => self.storage.delete_schema_object(self.id, INDEX_SCHEMA, name)?,
+            None => {}
+        }
+        Ok(index)
+    }
+
+    /// Add a constraint to the schema, persisting it across restarts
+    ///
+    /// The constraint applies to nodes created or updated from now on; nodes
+    /// already stored are not checked until they are next updated. Returns
+    /// `false` if a constraint with the same name exists.
+    pub fn create_constraint(&self, constraint: Constraint) -> Result<bool> {
+        let mut schema = self.schema.write().map_err(|_| {
+            Error::Internal("Failed to acquire schema lock".to_string())
+        })?;
+        if schema.get_constraint(&constraint.name).is_some() {
+            return Ok(false);
+        }
+
+        let data = serde_json::to_vec(&constraint).map_err(|e| Error::Serialization(e.to_string()))?;
+        self.storage
+            .put_schema_object(self.id, CONSTRAINT_SCHEMA, &constraint.name, &data)?;
+        Ok(schema.add_constraint(constraint))
+    }
+
+    /// Remove a constraint from the schema
+    pub fn drop_constraint(&self, name: &str) -> Result<Option<Constraint>> {
+        let mut schema = self.schema.write().map_err(|_| {
+            Error::Internal("Failed to acquire schema lock".to_string())
+        })?;
+        let constraint = schema.remove_constraint(name);
+        if constraint.is_some() {
+            self.storage.delete_schema_object(self.id, CONSTRAINT_SCHEMA, name)?;
+        }
+        Ok(constraint)
+    }
+
+    /// Get a read-only handle on this graph as it is now
+    ///
+    /// Nodes, relationships and index lookups read through the handle all
+    /// come from one storage snapshot, so a query run against it sees a
+    /// single committed state however many writes land meanwhile: never a
+    /// relationship whose endpoint was deleted after the snapshot was taken.
+    /// Counts, the version and the schema are shared with this graph and
+    /// stay current.
+    ///
+    /// Creating, updating or deleting entities through the handle fails with
+    /// [`Error::InvalidGraphOperation`]. Taking a snapshot of a snapshot or
+    /// transaction handle returns the same view.
+    pub fn snapshot(&self) -> Graph {
+        let mut graph = self.clone();
+        if graph.view.is_none() {
+            graph.view = Some(Arc::new(GraphView::Snapshot(Box::new(self.storage.snapshot()))));
+        }
+        graph
+    }
+
+    /// Get a handle on this graph whose writes are held back until
+    /// [`Self::commit`]
+    ///
+    /// Reads through the handle see a snapshot taken when it is created plus
+    /// the handle's own writes. Nothing is stored before `commit`, which
+    /// writes everything in one batch, so other readers see all of the
+    /// handle's writes or none of them. [`Self::rollback`], or dropping every
+    /// clone of the handle, discards the writes.
+    ///
+    /// Constraints, relationship endpoints and quotas are checked as writes
+    /// are made and again at commit, against whatever was committed
+    /// meanwhile. The handle holds the lock that serializes merges until it
+    /// ends, so its merges never duplicate a node merged concurrently; this
+    /// call waits while another transaction handle on the graph is open.
+    /// Schema changes through the handle are not transactional.
+    pub fn transaction(&self) -> Result<Graph> {
+        if self.view.is_some() {
+            return Err(Error::InvalidGraphOperation(format!(
+                "Graph '{}' is already a snapshot or transaction handle",
+                self.name
+            )));
+        }
+        // Taken first, so the snapshot includes every merge committed before
+        let merge_guard = self.merge_lock.acquire()?;
+        let mut graph = self.clone();
+        graph.view = Some(Arc::new(GraphView::Transaction(GraphTransaction {
+            tx: Mutex::new(Some(Box::new(Transaction::new(self.storage.clone(), self.id)))),
+            merge_guard: Mutex::new(Some(merge_guard)),
+        })));
+        Ok(graph)
+    }
+
+    /// Commit the writes of a [`Self::transaction`] handle
+    ///
+    /// Fails without writing anything if a write would now violate a
+    /// constraint or a quota, would leave a relationship without an
+    /// endpoint, or if a node it deletes gained relationships meanwhile. The
+    /// handle cannot be used afterwards, whether the commit succeeded or not.
+    pub fn commit(&self) -> Result<()> {
+        let tx = self.transaction_view()?.finish()?;
+        let live = self.live();
+
+        let mut node_count = Self::lock_count(&self.node_count)?;
+        let mut rel_count = Self::lock_count(&self.rel_count)?;
+        let mut deltas = (0, 0);
+        tx.commit_checked(|nodes, rels| {
+            deltas = live.check_commit(nodes, rels, (*node_count, *rel_count))?;
+            Ok(())
+        })?;
+
+        let (nodes, relationships) = deltas;
+        *node_count = node_count.saturating_add_signed(nodes);
+        self.storage
+            .put_meta(&Self::entity_count_key(self.id, "node"), &node_count.to_be_bytes())?;
+        *rel_count = rel_count.saturating_add_signed(relationships);
+        self.storage
+            .put_meta(&Self::entity_count_key(self.id, "relationship"), &rel_count.to_be_bytes())?;
+        debug!("Committed transaction on graph {}", self.name);
+        Ok(())
+    }
+
+    /// Discard the writes of a [`Self::transaction`] handle
+    pub fn rollback(&self) -> Result<()> {
+        self.transaction_view()?.finish()?.rollback()
+    }
+
     /// Get a reference to the storage engine
     pub fn storage(&self) -> StorageEngine {
         self.storage.clone()
     }
 
+    /// Get the entity quotas of this graph
+    pub fn quotas(&self) -> GraphQuotas {
+        self.quotas
+    }
+
+    /// Get the number of nodes in this graph
+    pub fn node_count(&self) -> Result<u64> {
+        Self::lock_count(&self.node_count).map(|count| *count)
+    }
+
+    /// Get the number of relationships in this graph
+    pub fn relationship_count(&self) -> Result<u64> {
+        Self::lock_count(&self.rel_count).map(|count| *count)
+    }
+
+    /// Get the number of nodes per label and relationships per type
+    ///
+    /// See [`StorageEngine::type_counts`].
+    pub fn type_counts(&self) -> Result<TypeCounts> {
+        self.storage.type_counts(self.id)
+    }
+
+    /// Get the version of this graph's data, bumped by every write
+    ///
+    /// See [`StorageEngine::graph_version`].
+    pub fn version(&self) -> Result<u64> {
+        self.storage.graph_version(self.id)
+    }
+
+    /// Create a checkpoint of the storage holding this graph in the new
+    /// directory `path`
+    ///
+    /// Nodes and relationships are neither created nor deleted in this graph
+    /// while it is taken, so the stored entity counts in the checkpoint match
+    /// its entities.
+    pub fn checkpoint<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        let _nodes = Self::lock_count(&self.node_count)?;
+        let _relationships = Self::lock_count(&self.rel_count)?;
+        self.storage.checkpoint(path)
+    }
+
     // ========== Node Operations ==========
 
     /// Create a new node with the given labels
@@ -68,13 +519,18 @@ impl Graph {
         I: IntoIterator<Item = L>,
         L: Into<Label>,
     {
-        let node = Node::with_labels(self.id_gen.next_node_id(), labels);
-        self.storage.put_node(self.id, &node)?;
-        debug!("Created node {:?} in graph {}", node.id, self.name);
+        self.check_writable()?;
+        let node = Node::with_labels(self.next_node_id()?, labels);
+        self.insert_node(&node)?;
         Ok(node)
     }
 
     /// Create a new node with labels and properties
+    ///
+    /// Fails with [`Error::UniqueViolation`] if a unique constraint on one of
+    /// its labels already has a node with the same property value, or with
+    /// [`Error::ConstraintViolation`] if it lacks a property that an
+    /// existence constraint requires.
     pub fn create_node_with_properties<I, L>(
         &self,
         labels: I,
@@ -84,79 +540,387 @@ impl Graph {
         I: IntoIterator<Item = L>,
         L: Into<Label>,
     {
-        let node = Node::with_labels_and_properties(self.id_gen.next_node_id(), labels, properties);
+        self.check_writable()?;
+        let node = Node::with_labels_and_properties(self.next_node_id()?, labels, properties);
+        self.insert_node(&node)?;
+        Ok(node)
+    }
+
+    /// Create many nodes in a single atomic write
+    ///
+    /// Nodes with a constrained label are created one at a time instead, so
+    /// each is checked against the nodes created before it: one that
+    /// violates a constraint gets its error in the returned list and is
+    /// skipped. The whole batch fails if it would exceed the node quota.
+    pub fn create_nodes_with_properties<I, L>(
+        &self,
+        rows: impl IntoIterator<Item = (I, Property)>,
+    ) -> Result<Vec<Result<Node>>>
+    where
+        I: IntoIterator<Item = L>,
+        L: Into<Label>,
+    {
+        self.check_writable()?;
+        let nodes = rows
+            .into_iter()
+            .map(|(labels, properties)| {
+                Ok(Node::with_labels_and_properties(self.next_node_id()?, labels, properties))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let constrained = {
+            let schema = self.schema.read().map_err(|_| {
+                Error::Internal("Failed to acquire schema lock".to_string())
+            })?;
+            nodes.iter().any(|node| {
+                node.labels.iter().any(|label| !schema.constraints_for_label(label).is_empty())
+            })
+        };
+        if constrained || self.pending().is_some() {
+            let mut results = Vec::with_capacity(nodes.len());
+            for node in nodes {
+                match self.insert_node(&node) {
+                    Ok(()) => results.push(Ok(node)),
+                    Err(e @ Error::QuotaExceeded(_)) => return Err(e),
+                    Err(e) => results.push(Err(e)),
+                }
+            }
+            return Ok(results);
+        }
 
-        // Check unique constraints
-        self.check_node_constraints(&node)?;
+        if !nodes.is_empty() {
+            self.with_quota_for(
+                &self.node_count,
+                "node",
+                self.quotas.max_nodes,
+                nodes.len() as u64,
+                || self.storage.create_nodes(self.id, &nodes),
+            )?;
+        }
 
-        self.storage.put_node(self.id, &node)?;
-        debug!("Created node {:?} in graph {}", node.id, self.name);
-        Ok(node)
+        debug!("Created {} nodes in graph {}", nodes.len(), self.name);
+        Ok(nodes.into_iter().map(Ok).collect())
+    }
+
+    /// Find the node with all of `labels` and `properties`, or create it
+    ///
+    /// `update` receives the matched node, or the new node before it is
+    /// stored, together with whether it is being created; any changes it
+    /// makes are written back. Returns the node and whether it was created.
+    ///
+    /// Merges on the same graph are serialized, so concurrent merges for the
+    /// same key never create duplicates. Nodes created through other calls
+    /// are not covered by this guarantee.
+    pub fn merge_node<F>(&self, labels: &[String], properties: Property, update: F) -> Result<(Node, bool)>
+    where
+        F: FnOnce(&mut Node, bool) -> Result<()>,
+    {
+        self.check_writable()?;
+        // A transaction handle holds the merge lock until it ends
+        let _guard = match self.pending() {
+            Some(_) => None,
+            None => Some(self.merge_lock.acquire()?),
+        };
+
+        match self.find_merge_candidate(labels, &properties)? {
+            Some(mut node) => {
+                let original = node.clone();
+                update(&mut node, false)?;
+                if node != original {
+                    self.update_node(&node)?;
+                }
+                Ok((node, false))
+            }
+            None => {
+                let labels = labels.iter().map(String::as_str);
+                let mut node = Node::with_labels_and_properties(self.next_node_id()?, labels, properties);
+                update(&mut node, true)?;
+                self.insert_node(&node)?;
+                Ok((node, true))
+            }
+        }
     }
 
     /// Get a node by ID
     pub fn get_node(&self, node_id: NodeId) -> Result<Option<Node>> {
-        self.storage.get_node(self.id, node_id)
+        match self.view.as_deref() {
+            None => self.storage.get_node(self.id, node_id),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_node(self.id, node_id),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_node(node_id)),
+        }
     }
 
     /// Update a node
     pub fn update_node(&self, node: &Node) -> Result<()> {
+        self.check_writable()?;
         // Verify node exists
-        if self.storage.get_node(self.id, node.id)?.is_none() {
+        if self.get_node(node.id)?.is_none() {
             return Err(Error::NodeNotFound(format!("{:?}", node.id)));
         }
 
-        // Check constraints
-        self.check_node_constraints(node)?;
-
-        self.storage.put_node(self.id, node)?;
+        if let Some(tx) = self.pending() {
+            self.check_node_constraints(node)?;
+            return tx.with(|tx| tx.put_node(node.clone()));
+        }
+        self.storage
+            .put_node_checked(self.id, node, || self.check_node_constraints(node))?;
         debug!("Updated node {:?} in graph {}", node.id, self.name);
         Ok(())
     }
 
     /// Delete a node (must have no relationships)
+    ///
+    /// The relationship check runs under the storage write lock, so a
+    /// relationship created concurrently cannot be left without its node.
     pub fn delete_node(&self, node_id: NodeId) -> Result<bool> {
-        // Check for relationships
-        let outgoing = self.storage.get_outgoing_relationships(self.id, node_id)?;
-        let incoming = self.storage.get_incoming_relationships(self.id, node_id)?;
+        self.check_writable()?;
+        if let Some(tx) = self.pending() {
+            if self.get_node(node_id)?.is_none() {
+                return Ok(false);
+            }
+            self.check_unattached(node_id)?;
+            tx.with(|tx| tx.delete_node(node_id))?;
+            return Ok(true);
+        }
+        self.delete_counted(&self.node_count, "node", || {
+            self.storage
+                .delete_node_checked(self.id, node_id, || self.check_unattached(node_id))
+        })
+    }
 
-        if !outgoing.is_empty() || !incoming.is_empty() {
-            return Err(Error::InvalidGraphOperation(format!(
-                "Cannot delete node {:?}: has {} outgoing and {} incoming relationships. Use detach_delete_node instead.",
-                node_id,
-                outgoing.len(),
-                incoming.len()
-            )));
+    /// Delete a node and all its relationships
+    ///
+    /// The node and its relationships are deleted in one write. Returns the
+    /// number of relationships deleted with the node, or `None` if the node
+    /// did not exist.
+    pub fn detach_delete_node(&self, node_id: NodeId) -> Result<Option<u64>> {
+        self.check_writable()?;
+        if let Some(tx) = self.pending() {
+            if self.get_node(node_id)?.is_none() {
+                return Ok(None);
+            }
+            let rels = self.get_relationships(node_id, Direction::Both)?;
+            tx.with(|tx| {
+                for rel in &rels {
+                    tx.delete_relationship(rel.id)?;
+                }
+                tx.delete_node(node_id)
+            })?;
+            return Ok(Some(rels.len() as u64));
+        }
+        let mut node_count = Self::lock_count(&self.node_count)?;
+        let mut rel_count = Self::lock_count(&self.rel_count)?;
+        let Some(relationships) = self.storage.detach_delete_node(self.id, node_id)? else {
+            return Ok(None);
+        };
+
+        *node_count = node_count.saturating_sub(1);
+        self.storage
+            .put_meta(&Self::entity_count_key(self.id, "node"), &node_count.to_be_bytes())?;
+        *rel_count = rel_count.saturating_sub(relationships);
+        self.storage
+            .put_meta(&Self::entity_count_key(self.id, "relationship"), &rel_count.to_be_bytes())?;
+        Ok(Some(relationships))
+    }
+
+    /// Delete every node with a label and all their relationships
+    ///
+    /// Nodes are deleted `batch_size` at a time, each batch with its
+    /// relationships in one write. Returns the number of nodes and
+    /// relationships deleted.
+    pub fn detach_delete_nodes_by_label(&self, label: &str, batch_size: usize) -> Result<(u64, u64)> {
+        self.check_writable()?;
+        if batch_size == 0 {
+            return Err(Error::InvalidGraphOperation("Batch size must be at least 1".to_string()));
+        }
+        if self.pending().is_some() {
+            let (mut nodes_deleted, mut relationships_deleted) = (0, 0);
+            for node in self.find_nodes_by_label(label)? {
+                if let Some(relationships) = self.detach_delete_node(node.id)? {
+                    nodes_deleted += 1;
+                    relationships_deleted += relationships;
+                }
+            }
+            return Ok((nodes_deleted, relationships_deleted));
+        }
+
+        let (mut nodes_deleted, mut relationships_deleted) = (0, 0);
+        loop {
+            let mut node_count = Self::lock_count(&self.node_count)?;
+            let mut rel_count = Self::lock_count(&self.rel_count)?;
+            let (nodes, relationships) = self.storage.detach_delete_nodes_with_label(self.id, label, batch_size)?;
+            if nodes == 0 {
+                break;
+            }
+
+            *node_count = node_count.saturating_sub(nodes);
+            self.storage
+                .put_meta(&Self::entity_count_key(self.id, "node"), &node_count.to_be_bytes())?;
+            *rel_count = rel_count.saturating_sub(relationships);
+            self.storage
+                .put_meta(&Self::entity_count_key(self.id, "relationship"), &rel_count.to_be_bytes())?;
+            nodes_deleted += nodes;
+            relationships_deleted += relationships;
         }
 
-        self.storage.delete_node(self.id, node_id)
+        debug!(
+            "Deleted {} nodes with label {} and {} relationships from graph {}",
+            nodes_deleted, label, relationships_deleted, self.name
+        );
+        Ok((nodes_deleted, relationships_deleted))
     }
 
-    /// Delete a node and all its relationships
-    pub fn detach_delete_node(&self, node_id: NodeId) -> Result<bool> {
-        // Delete all relationships first
-        let outgoing = self.storage.get_outgoing_relationships(self.id, node_id)?;
-        for rel in outgoing {
-            self.storage.delete_relationship(self.id, rel.id)?;
+    /// Delete every node, relationship, index and constraint of the graph
+    ///
+    /// Nodes are deleted `batch_size` at a time, each with its
+    /// relationships, so concurrent readers may see the graph partly
+    /// cleared. Deleting the graph from its [`crate::Database`] alone keeps
+    /// its data, which a graph created later with the same name would see.
+    pub fn clear(&self, batch_size: usize) -> Result<()> {
+        self.check_writable()?;
+        if batch_size == 0 {
+            return Err(Error::InvalidGraphOperation("Batch size must be at least 1".to_string()));
+        }
+        loop {
+            let node_ids = self.node_ids_after(None, None, batch_size)?;
+            if node_ids.is_empty() {
+                break;
+            }
+            for node_id in node_ids {
+                self.detach_delete_node(node_id)?;
+            }
         }
 
-        let incoming = self.storage.get_incoming_relationships(self.id, node_id)?;
-        for rel in incoming {
-            self.storage.delete_relationship(self.id, rel.id)?;
+        let (indexes, constraints): (Vec<_>, Vec<_>) = {
+            let schema = self
+                .schema
+                .read()
+                .map_err(|_| Error::Internal("Failed to acquire schema lock".to_string()))?;
+            (schema.indexes.keys().cloned().collect(), schema.constraints.keys().cloned().collect())
+        };
+        for name in indexes {
+            self.drop_index(&name)?;
+        }
+        for name in constraints {
+            self.drop_constraint(&name)?;
         }
 
-        // Now delete the node
-        self.storage.delete_node(self.id, node_id)
+        debug!("Cleared graph {}", self.name);
+        Ok(())
     }
 
     /// Find nodes by label
     pub fn find_nodes_by_label(&self, label: &str) -> Result<Vec<Node>> {
-        self.storage.get_nodes_by_label(self.id, label)
+        match self.view.as_deref() {
+            None => self.storage.get_nodes_by_label(self.id, label),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_nodes_by_label(self.id, label),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_nodes_by_label(label)),
+        }
+    }
+
+    /// Find nodes by label in a reproducible order
+    pub fn find_nodes_by_label_ordered(&self, label: &str, order: &NodeOrder) -> Result<Vec<Node>> {
+        let mut nodes = self.find_nodes_by_label(label)?;
+        order.sort(&mut nodes);
+        Ok(nodes)
+    }
+
+    /// Count the nodes carrying a label
+    pub fn count_nodes_by_label(&self, label: &str) -> Result<u64> {
+        self.storage.label_count(self.id, label)
+    }
+
+    /// Count the relationships of a type
+    pub fn count_relationships_by_type(&self, rel_type: &str) -> Result<u64> {
+        self.storage.relationship_type_count(self.id, rel_type)
     }
 
     /// Get all nodes in this graph
     pub fn get_all_nodes(&self) -> Result<Vec<Node>> {
-        self.storage.get_all_nodes(self.id)
+        match self.view.as_deref() {
+            None => self.storage.get_all_nodes(self.id),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.nodes(self.id)?.collect(),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.nodes()),
+        }
+    }
+
+    /// Get all nodes in this graph in a reproducible order
+    pub fn get_all_nodes_ordered(&self, order: &NodeOrder) -> Result<Vec<Node>> {
+        let mut nodes = self.get_all_nodes()?;
+        order.sort(&mut nodes);
+        Ok(nodes)
+    }
+
+    /// Get all relationships in this graph
+    pub fn get_all_relationships(&self) -> Result<Vec<Relationship>> {
+        match self.view.as_deref() {
+            None => self.storage.get_all_relationships(self.id),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.relationships(self.id)?.collect(),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.relationships()),
+        }
+    }
+
+    /// Find relationships by type across the whole graph
+    pub fn find_relationships_by_type(&self, rel_type: &str) -> Result<Vec<Relationship>> {
+        match self.view {
+            None => self.storage.get_relationships_by_type(self.id, rel_type),
+            Some(_) => {
+                let mut rels = self.get_all_relationships()?;
+                rels.retain(|rel| rel.rel_type.name() == rel_type);
+                Ok(rels)
+            }
+        }
+    }
+
+    /// Find relationships of a type by property value using the relationship property index
+    ///
+    /// Snapshot and transaction handles scan their relationships instead.
+    pub fn find_relationships_by_property(
+        &self,
+        rel_type: &str,
+        property: &str,
+        value: &PropertyValue,
+    ) -> Result<Vec<Relationship>> {
+        match self.view {
+            None => self.storage.get_relationships_by_property(self.id, rel_type, property, value),
+            Some(_) => {
+                let epsilon = self.storage.float_epsilon();
+                let mut rels = self.find_relationships_by_type(rel_type)?;
+                rels.retain(|rel| rel.properties.get(property).is_some_and(|actual| actual.equals_within(value, epsilon)));
+                Ok(rels)
+            }
+        }
+    }
+
+    /// Compare this graph at two transaction times
+    ///
+    /// Reports the nodes and relationships created, updated or deleted by
+    /// writes committed after `from` and up to and including `to`.
+    pub fn diff(&self, from: TransactionTime, to: TransactionTime) -> Result<GraphDiff> {
+        self.storage.diff(self.id, from, to)
+    }
+
+    /// Get the IDs of all nodes in this graph
+    pub fn get_all_node_ids(&self) -> Result<Vec<NodeId>> {
+        match self.view.as_deref() {
+            None => self.storage.get_all_node_ids(self.id),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.node_ids_after(self.id, None, None, usize::MAX),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.node_ids_after(None, None, usize::MAX)),
+        }
+    }
+
+    /// Get up to `limit` IDs of the nodes, or of the nodes with `label`, that
+    /// come after `after`, in ID order
+    ///
+    /// Pages through the graph without holding it in memory: pass the last
+    /// ID of a page as `after` to get the next one. Nodes created meanwhile
+    /// may or may not be included unless this is a snapshot handle.
+    pub fn node_ids_after(&self, label: Option<&str>, after: Option<NodeId>, limit: usize) -> Result<Vec<NodeId>> {
+        match self.view.as_deref() {
+            None => self.storage.node_ids_after(self.id, label, after, limit),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.node_ids_after(self.id, label, after, limit),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.node_ids_after(label, after, limit)),
+        }
     }
 
     /// Find nodes by label and property value using property index
@@ -168,7 +932,27 @@ impl Graph {
         value: &PropertyValue,
     ) -> Result<Vec<Node>> {
         // Use property index for efficient lookup
-        self.storage.get_nodes_by_property(self.id, label, property, value)
+        match self.view.as_deref() {
+            None => self.storage.get_nodes_by_property(self.id, label, property, value),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_nodes_by_property(self.id, label, property, value),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_nodes_by_property(label, property, value)),
+        }
+    }
+
+    /// Find nodes by values for the leading properties of a composite index
+    ///
+    /// `values` follow the order of the index's properties and may stop
+    /// before the last one.
+    pub fn find_nodes_by_composite_index(
+        &self,
+        index: &str,
+        values: &[PropertyValue],
+    ) -> Result<Vec<Node>> {
+        match self.view.as_deref() {
+            None => self.storage.get_nodes_by_composite_property(self.id, index, values),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_nodes_by_composite_property(self.id, index, values),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_nodes_by_composite_property(index, values)),
+        }
     }
 
     /// Find nodes by label and property range
@@ -180,7 +964,15 @@ impl Graph {
         min_value: Option<&PropertyValue>,
         max_value: Option<&PropertyValue>,
     ) -> Result<Vec<Node>> {
-        self.storage.get_nodes_by_property_range(self.id, label, property, min_value, max_value)
+        match self.view.as_deref() {
+            None => self.storage.get_nodes_by_property_range(self.id, label, property, min_value, max_value),
+            Some(GraphView::Snapshot(snapshot)) => {
+                snapshot.get_nodes_by_property_range(self.id, label, property, min_value, max_value)
+            }
+            Some(GraphView::Transaction(tx)) => {
+                tx.with(|tx| tx.get_nodes_by_property_range(label, property, min_value, max_value))
+            }
+        }
     }
 
     /// Find nodes that have a specific property (any value)
@@ -189,7 +981,11 @@ impl Graph {
         label: &str,
         property: &str,
     ) -> Result<Vec<Node>> {
-        self.storage.get_nodes_with_property(self.id, label, property)
+        match self.view.as_deref() {
+            None => self.storage.get_nodes_with_property(self.id, label, property),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_nodes_with_property(self.id, label, property),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_nodes_with_property(label, property)),
+        }
     }
 
     // ========== Relationship Operations ==========
@@ -201,16 +997,12 @@ impl Graph {
         rel_type: L,
         target: NodeId,
     ) -> Result<Relationship> {
-        // Verify both nodes exist
-        if self.storage.get_node(self.id, source)?.is_none() {
-            return Err(Error::NodeNotFound(format!("{:?}", source)));
-        }
-        if self.storage.get_node(self.id, target)?.is_none() {
-            return Err(Error::NodeNotFound(format!("{:?}", target)));
-        }
+        self.check_writable()?;
+        // Fail early before allocating an ID; checked again under the write lock
+        self.check_endpoints(source, target)?;
 
-        let rel = Relationship::new(self.id_gen.next_relationship_id(), rel_type, source, target);
-        self.storage.put_relationship(self.id, &rel)?;
+        let rel = Relationship::new(self.next_relationship_id()?, rel_type, source, target);
+        self.insert_relationship(&rel)?;
 
         debug!(
             "Created relationship {:?} in graph {}",
@@ -227,22 +1019,18 @@ impl Graph {
         target: NodeId,
         properties: Property,
     ) -> Result<Relationship> {
-        // Verify both nodes exist
-        if self.storage.get_node(self.id, source)?.is_none() {
-            return Err(Error::NodeNotFound(format!("{:?}", source)));
-        }
-        if self.storage.get_node(self.id, target)?.is_none() {
-            return Err(Error::NodeNotFound(format!("{:?}", target)));
-        }
+        self.check_writable()?;
+        // Fail early before allocating an ID; checked again under the write lock
+        self.check_endpoints(source, target)?;
 
         let rel = Relationship::with_properties(
-            self.id_gen.next_relationship_id(),
+            self.next_relationship_id()?,
             rel_type,
             source,
             target,
             properties,
         );
-        self.storage.put_relationship(self.id, &rel)?;
+        self.insert_relationship(&rel)?;
 
         debug!(
             "Created relationship {:?} in graph {}",
@@ -251,26 +1039,110 @@ impl Graph {
         Ok(rel)
     }
 
+    /// Create many relationships in a single atomic write
+    ///
+    /// Each row is checked on its own: rows whose endpoints do not exist get a
+    /// `NodeNotFound` error in the returned list and are skipped, while all
+    /// valid rows are written together with their adjacency entries. The
+    /// whole batch fails if it would exceed the relationship quota, or if an
+    /// endpoint of a valid row is deleted before the write.
+    pub fn create_relationships_with_properties<L: Into<Label>>(
+        &self,
+        rows: impl IntoIterator<Item = (NodeId, L, NodeId, Property)>,
+    ) -> Result<Vec<Result<Relationship>>> {
+        self.check_writable()?;
+        let mut results = Vec::new();
+        let mut valid = Vec::new();
+        for (source, rel_type, target, properties) in rows {
+            let missing = [source, target]
+                .into_iter()
+                .find_map(|id| match self.get_node(id) {
+                    Ok(Some(_)) => None,
+                    Ok(None) => Some(Error::NodeNotFound(format!("{:?}", id))),
+                    Err(e) => Some(e),
+                });
+            match missing {
+                Some(error) => results.push(Err(error)),
+                None => {
+                    let rel = Relationship::with_properties(
+                        self.next_relationship_id()?,
+                        rel_type,
+                        source,
+                        target,
+                        properties,
+                    );
+                    results.push(Ok(rel.clone()));
+                    valid.push(rel);
+                }
+            }
+        }
+
+        if let Some(tx) = self.pending() {
+            tx.with(|tx| valid.iter().try_for_each(|rel| tx.put_relationship(rel.clone())))?;
+        } else if !valid.is_empty() {
+            self.with_quota_for(
+                &self.rel_count,
+                "relationship",
+                self.quotas.max_relationships,
+                valid.len() as u64,
+                || {
+                    self.storage.put_relationships_checked(self.id, &valid, || {
+                        valid
+                            .iter()
+                            .try_for_each(|rel| self.check_endpoints(rel.source, rel.target))
+                    })
+                },
+            )?;
+        }
+
+        debug!(
+            "Created {} relationships in graph {}",
+            valid.len(),
+            self.name
+        );
+        Ok(results)
+    }
+
     /// Get a relationship by ID
     pub fn get_relationship(&self, rel_id: RelationshipId) -> Result<Option<Relationship>> {
-        self.storage.get_relationship(self.id, rel_id)
+        match self.view.as_deref() {
+            None => self.storage.get_relationship(self.id, rel_id),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_relationship(self.id, rel_id),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_relationship(rel_id)),
+        }
     }
 
     /// Update a relationship
     pub fn update_relationship(&self, rel: &Relationship) -> Result<()> {
+        self.check_writable()?;
         // Verify relationship exists
-        if self.storage.get_relationship(self.id, rel.id)?.is_none() {
+        if self.get_relationship(rel.id)?.is_none() {
             return Err(Error::RelationshipNotFound(format!("{:?}", rel.id)));
         }
 
-        self.storage.put_relationship(self.id, rel)?;
+        if let Some(tx) = self.pending() {
+            self.check_endpoints(rel.source, rel.target)?;
+            return tx.with(|tx| tx.put_relationship(rel.clone()));
+        }
+        self.storage
+            .put_relationship_checked(self.id, rel, || self.check_endpoints(rel.source, rel.target))?;
         debug!("Updated relationship {:?} in graph {}", rel.id, self.name);
         Ok(())
     }
 
     /// Delete a relationship
     pub fn delete_relationship(&self, rel_id: RelationshipId) -> Result<bool> {
-        self.storage.delete_relationship(self.id, rel_id)
+        self.check_writable()?;
+        if let Some(tx) = self.pending() {
+            if self.get_relationship(rel_id)?.is_none() {
+                return Ok(false);
+            }
+            tx.with(|tx| tx.delete_relationship(rel_id))?;
+            return Ok(true);
+        }
+        self.delete_counted(&self.rel_count, "relationship", || {
+            self.storage.delete_relationship(self.id, rel_id)
+        })
     }
 
     /// Get relationships from a node
@@ -280,11 +1152,16 @@ impl Graph {
         direction: Direction,
     ) -> Result<Vec<Relationship>> {
         match direction {
-            Direction::Outgoing => self.storage.get_outgoing_relationships(self.id, node_id),
-            Direction::Incoming => self.storage.get_incoming_relationships(self.id, node_id),
+            Direction::Outgoing => self.outgoing_relationships(node_id),
+            Direction::Incoming => self.incoming_relationships(node_id),
             Direction::Both => {
-                let mut rels = self.storage.get_outgoing_relationships(self.id, node_id)?;
-                rels.extend(self.storage.get_incoming_relationships(self.id, node_id)?);
+                let mut rels = self.outgoing_relationships(node_id)?;
+                // Self-loops are both outgoing and incoming; report them once
+                rels.extend(
+                    self.incoming_relationships(node_id)?
+                        .into_iter()
+                        .filter(|rel| rel.source != node_id),
+                );
                 Ok(rels)
             }
         }
@@ -316,7 +1193,7 @@ impl Graph {
                 Direction::Both => rel.other(node_id).unwrap_or(rel.target),
             };
 
-            if let Some(node) = self.storage.get_node(self.id, neighbor_id)? {
+            if let Some(node) = self.get_node(neighbor_id)? {
                 neighbors.push(node);
             }
         }
@@ -331,70 +1208,419 @@ impl Graph {
         Transaction::new(self.storage.clone(), self.id)
     }
 
-    // ========== Private Helpers ==========
+    // ========== Private Helpers ==========
+
+    /// Fail if this handle cannot create, update or delete entities
+    fn check_writable(&self) -> Result<()> {
+        match self.view.as_deref() {
+            None => Ok(()),
+            Some(GraphView::Snapshot(_)) => Err(Error::InvalidGraphOperation(format!(
+                "Graph '{}' is a read-only snapshot",
+                self.name
+            ))),
+            Some(GraphView::Transaction(_)) => Ok(()),
+        }
+    }
+
+    fn outgoing_relationships(&self, node_id: NodeId) -> Result<Vec<Relationship>> {
+        match self.view.as_deref() {
+            None => self.storage.get_outgoing_relationships(self.id, node_id),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_outgoing_relationships(self.id, node_id),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_outgoing_relationships(node_id)),
+        }
+    }
+
+    fn incoming_relationships(&self, node_id: NodeId) -> Result<Vec<Relationship>> {
+        match self.view.as_deref() {
+            None => self.storage.get_incoming_relationships(self.id, node_id),
+            Some(GraphView::Snapshot(snapshot)) => snapshot.get_incoming_relationships(self.id, node_id),
+            Some(GraphView::Transaction(tx)) => tx.with(|tx| tx.get_incoming_relationships(node_id)),
+        }
+    }
+
+    /// Store a new node, enforcing quotas and constraints
+    ///
+    /// Constraints are checked under the storage write lock, so two nodes
+    /// created concurrently cannot both pass a unique constraint.
+    fn insert_node(&self, node: &Node) -> Result<()> {
+        // Quotas of a transaction are checked when it commits
+        if let Some(tx) = self.pending() {
+            self.check_node_constraints(node)?;
+            return tx.with(|tx| tx.put_node(node.clone()));
+        }
+        self.with_quota(&self.node_count, "node", self.quotas.max_nodes, || {
+            self.storage
+                .create_node_checked(self.id, node, || self.check_node_constraints(node))
+        })?;
+        debug!("Created node {:?} in graph {}", node.id, self.name);
+        Ok(())
+    }
+
+    /// Find an existing node for [`Self::merge_node`]
+    ///
+    /// Uses the property index when the pattern has a label and a property.
+    fn find_merge_candidate(&self, labels: &[String], properties: &Property) -> Result<Option<Node>> {
+        let candidates = match (labels.first(), properties.iter().next()) {
+            (Some(label), Some((key, value))) => self.find_nodes_by_label_and_property(label, key, value)?,
+            (Some(label), None) => self.find_nodes_by_label(label)?,
+            (None, _) => self.get_all_nodes()?,
+        };
+
+        Ok(candidates.into_iter().find(|node| {
+            labels.iter().all(|label| node.has_label_name(label))
+                && properties.iter().all(|(key, value)| node.get_property(key) == Some(value))
+        }))
+    }
+
+    fn check_node_constraints(&self, node: &Node) -> Result<()> {
+        self.check_node_constraints_except(node, |_| false)
+    }
+
+    /// Like [`Self::check_node_constraints`], ignoring conflicts with the
+    /// nodes for which `skip` holds
+    fn check_node_constraints_except(&self, node: &Node, skip: impl Fn(NodeId) -> bool) -> Result<()> {
+        let schema = self.schema.read().map_err(|_| {
+            Error::Internal("Failed to acquire schema lock".to_string())
+        })?;
+
+        for label in &node.labels {
+            for constraint in schema.constraints_for_label(label) {
+                match constraint.constraint_type {
+                    crate::schema::ConstraintType::Unique => {
+                        // Check uniqueness for each property in the constraint
+                        for prop_name in &constraint.properties {
+                            if let Some(value) = node.get_property(prop_name) {
+                                let existing = self.find_nodes_by_label_and_property(
+                                    label.name(),
+                                    prop_name,
+                                    value,
+                                )?;
+
+                                // Allow if only match is the node itself
+                                let conflicts: Vec<_> = existing
+                                    .into_iter()
+                                    .filter(|n| n.id != node.id && !skip(n.id))
+                                    .collect();
+
+                                if !conflicts.is_empty() {
+                                    return Err(Error::UniqueViolation {
+                                        label: label.name().to_string(),
+                                        property: prop_name.clone(),
+                                    });
+                                }
+                            }
+                        }
+                    }
+                    crate::schema::ConstraintType::Exists => {
+                        // A null value counts as missing
+                        for prop_name in &constraint.properties {
+                            if node.get_property(prop_name).is_none_or(PropertyValue::is_null) {
+                                return Err(Error::ConstraintViolation(format!(
+                                    "Property '{}' is required for label '{}'",
+                                    prop_name,
+                                    label.name()
+                                )));
+                            }
+                        }
+                    }
+                    crate::schema::ConstraintType::NodeKey => {
+                        // Node key = Exists + Unique for all properties combined
+                        for prop_name in &constraint.properties {
+                            if !node.properties.contains(prop_name) {
+                                return Err(Error::NodeKeyViolation(format!(
+                                    "Property '{}' is required for node key on label '{}'",
+                                    prop_name,
+                                    label.name()
+                                )));
+                            }
+                        }
+                        // TODO: Check uniqueness of combined properties
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn next_node_id(&self) -> Result<NodeId> {
+        let id = self.id_gen.next_node_id();
+        if self.id_gen.strategy() == IdStrategy::Sequential {
+            self.reserve_id(&self.node_id_ceiling, "node", id.as_internal())?;
+        }
+        Ok(id)
+    }
+
+    fn next_relationship_id(&self) -> Result<RelationshipId> {
+        let id = self.id_gen.next_relationship_id();
+        if self.id_gen.strategy() == IdStrategy::Sequential {
+            self.reserve_id(&self.rel_id_ceiling, "relationship", id.as_internal())?;
+        }
+        Ok(id)
+    }
+
+    /// Make sure `id` is covered by the persisted high-water mark, reserving
+    /// a new block of IDs when the current one is exhausted
+    fn reserve_id(&self, ceiling: &Mutex<u64>, kind: &str, id: u64) -> Result<()> {
+        let mut ceiling = ceiling.lock().map_err(|_| {
+            Error::Internal("Failed to acquire id reservation lock".to_string())
+        })?;
+
+        if id >= *ceiling {
+            let new_ceiling = id + ID_RESERVATION_BLOCK;
+            self.storage
+                .put_meta(&Self::id_watermark_key(self.id, kind), &new_ceiling.to_be_bytes())?;
+            *ceiling = new_ceiling;
+        }
+
+        Ok(())
+    }
+
+    fn load_id_watermark(storage: &StorageEngine, graph_id: GraphId, kind: &str) -> Result<u64> {
+        match storage.get_meta(&Self::id_watermark_key(graph_id, kind))? {
+            Some(data) => {
+                let bytes: [u8; 8] = data.as_slice().try_into().map_err(|_| {
+                    Error::DataCorruption(format!("Invalid {} id watermark", kind))
+                })?;
+                Ok(u64::from_be_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    fn id_watermark_key(graph_id: GraphId, kind: &str) -> String {
+        format!("id_watermark:{}:{}", graph_id, kind)
+    }
+
+    /// Fail with `NodeNotFound` unless both relationship endpoints exist
+    fn check_endpoints(&self, source: NodeId, target: NodeId) -> Result<()> {
+        for id in [source, target] {
+            if self.get_node(id)?.is_none() {
+                return Err(Error::NodeNotFound(format!("{:?}", id)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Fail unless a node has no relationships, so it can be deleted
+    fn check_unattached(&self, node_id: NodeId) -> Result<()> {
+        let outgoing = self.outgoing_relationships(node_id)?;
+        let incoming = self.incoming_relationships(node_id)?;
+
+        if !outgoing.is_empty() || !incoming.is_empty() {
+            return Err(Error::InvalidGraphOperation(format!(
+                "Cannot delete node {:?}: has {} outgoing and {} incoming relationships. Use detach_delete_node instead.",
+                node_id,
+                outgoing.len(),
+                incoming.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Store a new relationship, enforcing quotas
+    ///
+    /// The endpoints are checked under the storage write lock, so neither
+    /// can be deleted before the relationship is written.
+    fn insert_relationship(&self, rel: &Relationship) -> Result<()> {
+        // Quotas of a transaction are checked when it commits
+        if let Some(tx) = self.pending() {
+            return tx.with(|tx| tx.put_relationship(rel.clone()));
+        }
+        self.with_quota(&self.rel_count, "relationship", self.quotas.max_relationships, || {
+            self.storage
+                .put_relationship_checked(self.id, rel, || self.check_endpoints(rel.source, rel.target))
+        })
+    }
+
+    /// The transaction of a [`Self::transaction`] handle
+    fn pending(&self) -> Option<&GraphTransaction> {
+        match self.view.as_deref() {
+            Some(GraphView::Transaction(tx)) => Some(tx),
+            _ => None,
+        }
+    }
+
+    fn transaction_view(&self) -> Result<&GraphTransaction> {
+        self.pending().ok_or_else(|| {
+            Error::InvalidGraphOperation(format!("Graph '{}' is not a transaction handle", self.name))
+        })
+    }
+
+    /// A handle on the latest state of this graph
+    fn live(&self) -> Graph {
+        let mut graph = self.clone();
+        graph.view = None;
+        graph
+    }
+
+    /// Check the writes of a transaction against the latest state, returning
+    /// how many nodes and relationships they add
+    ///
+    /// Runs on a handle without a view while the storage write lock is held,
+    /// with the entity counts as they are before the commit.
+    fn check_commit(
+        &self,
+        nodes: &HashMap<NodeId, Option<Node>>,
+        rels: &HashMap<RelationshipId, Option<Relationship>>,
+        (node_count, rel_count): (u64, u64),
+    ) -> Result<(i64, i64)> {
+        let mut node_delta = 0;
+        for (&id, node) in nodes {
+            let exists = self.storage.get_node(self.id, id)?.is_some();
+            match node {
+                Some(node) => {
+                    node_delta += i64::from(!exists);
+                    // The transaction's nodes were checked against each other as they were written
+                    self.check_node_constraints_except(node, |other| nodes.contains_key(&other))?;
+                }
+                None => {
+                    node_delta -= i64::from(exists);
+                    let attached = self
+                        .get_relationships(id, Direction::Both)?
+                        .iter()
+                        .any(|rel| !matches!(rels.get(&rel.id), Some(None)));
+                    if attached {
+                        return Err(Error::InvalidGraphOperation(format!(
+                            "Cannot delete node {:?}: relationships were added to it meanwhile",
+                            id
+                        )));
+                    }
+                }
+            }
+        }
+
+        let mut rel_delta = 0;
+        for (&id, rel) in rels {
+            let exists = self.storage.get_relationship(self.id, id)?.is_some();
+            match rel {
+                Some(rel) => {
+                    rel_delta += i64::from(!exists);
+                    for endpoint in [rel.source, rel.target] {
+                        let found = match nodes.get(&endpoint) {
+                            Some(pending) => pending.is_some(),
+                            None => self.storage.get_node(self.id, endpoint)?.is_some(),
+                        };
+                        if !found {
+                            return Err(Error::NodeNotFound(format!("{:?}", endpoint)));
+                        }
+                    }
+                }
+                None => rel_delta -= i64::from(exists),
+            }
+        }
+
+        let limits = [
+            (node_count, node_delta, self.quotas.max_nodes, "node"),
+            (rel_count, rel_delta, self.quotas.max_relationships, "relationship"),
+        ];
+        for (count, delta, limit, kind) in limits {
+            if let Some(limit) = limit.filter(|limit| delta > 0 && count.saturating_add_signed(delta) > *limit) {
+                return Err(Error::QuotaExceeded(format!(
+                    "Graph '{}' has reached its limit of {} {}s",
+                    self.name, limit, kind
+                )));
+            }
+        }
+        Ok((node_delta, rel_delta))
+    }
+
+    /// Run `create` only if the entity count is below `limit`, then record
+    /// the new entity. The count lock is held throughout so concurrent
+    /// creates cannot overshoot the quota.
+    fn with_quota(
+        &self,
+        counter: &Mutex<u64>,
+        kind: &str,
+        limit: Option<u64>,
+        create: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        self.with_quota_for(counter, kind, limit, 1, create)
+    }
+
+    /// Like [`Self::with_quota`], for `create` adding `amount` entities at once
+    fn with_quota_for(
+        &self,
+        counter: &Mutex<u64>,
+        kind: &str,
+        limit: Option<u64>,
+        amount: u64,
+        create: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let mut count = Self::lock_count(counter)?;
+
+        if let Some(limit) = limit.filter(|limit| count.saturating_add(amount) > *limit) {
+            return Err(Error::QuotaExceeded(format!(
+                "Graph '{}' has reached its limit of {} {}s",
+                self.name, limit, kind
+            )));
+        }
 
-    fn check_node_constraints(&self, node: &Node) -> Result<()> {
-        let schema = self.schema.read().map_err(|_| {
-            Error::Internal("Failed to acquire schema lock".to_string())
-        })?;
+        create()?;
+        *count += amount;
+        self.storage
+            .put_meta(&Self::entity_count_key(self.id, kind), &count.to_be_bytes())
+    }
 
-        for label in &node.labels {
-            for constraint in schema.constraints_for_label(label) {
-                match constraint.constraint_type {
-                    crate::schema::ConstraintType::Unique => {
-                        // Check uniqueness for each property in the constraint
-                        for prop_name in &constraint.properties {
-                            if let Some(value) = node.get_property(prop_name) {
-                                let existing = self.find_nodes_by_label_and_property(
-                                    label.name(),
-                                    prop_name,
-                                    value,
-                                )?;
+    /// Run `delete` and record the removal if it deleted anything
+    fn delete_counted(
+        &self,
+        counter: &Mutex<u64>,
+        kind: &str,
+        delete: impl FnOnce() -> Result<bool>,
+    ) -> Result<bool> {
+        let mut count = Self::lock_count(counter)?;
+
+        let deleted = delete()?;
+        if deleted {
+            *count = count.saturating_sub(1);
+            self.storage
+                .put_meta(&Self::entity_count_key(self.id, kind), &count.to_be_bytes())?;
+        }
+        Ok(deleted)
+    }
 
-                                // Allow if only match is the node itself
-                                let conflicts: Vec<_> = existing
-                                    .into_iter()
-                                    .filter(|n| n.id != node.id)
-                                    .collect();
+    fn lock_count(counter: &Mutex<u64>) -> Result<std::sync::MutexGuard<'_, u64>> {
+        counter
+            .lock()
+            .map_err(|_| Error::Internal("Failed to acquire entity count lock".to_string()))
+    }
 
-                                if !conflicts.is_empty() {
-                                    return Err(Error::UniqueViolation {
-                                        label: label.name().to_string(),
-                                        property: prop_name.clone(),
-                                    });
-                                }
-                            }
-                        }
-                    }
-                    crate::schema::ConstraintType::Exists => {
-                        for prop_name in &constraint.properties {
-                            if !node.properties.contains(prop_name) {
-                                return Err(Error::ConstraintViolation(format!(
-                                    "Property '{}' is required for label '{}'",
-                                    prop_name,
-                                    label.name()
-                                )));
-                            }
-                        }
-                    }
-                    crate::schema::ConstraintType::NodeKey => {
-                        // Node key = Exists + Unique for all properties combined
-                        for prop_name in &constraint.properties {
-                            if !node.properties.contains(prop_name) {
-                                return Err(Error::NodeKeyViolation(format!(
-                                    "Property '{}' is required for node key on label '{}'",
-                                    prop_name,
-                                    label.name()
-                                )));
-                            }
-                        }
-                        // TODO: Check uniqueness of combined properties
-                    }
+    fn load_entity_counts(storage: &StorageEngine, graph_id: GraphId) -> Result<(u64, u64)> {
+        let stored = |kind: &str| -> Result<Option<u64>> {
+            match storage.get_meta(&Self::entity_count_key(graph_id, kind))? {
+                Some(data) => {
+                    let bytes: [u8; 8] = data.as_slice().try_into().map_err(|_| {
+                        Error::DataCorruption(format!("Invalid {} count", kind))
+                    })?;
+                    Ok(Some(u64::from_be_bytes(bytes)))
                 }
+                None => Ok(None),
             }
+        };
+
+        if let (Some(nodes), Some(relationships)) = (stored("node")?, stored("relationship")?) {
+            return Ok((nodes, relationships));
         }
 
-        Ok(())
+        // Counts were never recorded for this graph; scan once
+        let nodes = storage.get_all_nodes(graph_id)?;
+        let mut relationships = 0u64;
+        for node in &nodes {
+            relationships += storage.get_outgoing_relationships(graph_id, node.id)?.len() as u64;
+        }
+        let nodes = nodes.len() as u64;
+
+        storage.put_meta(&Self::entity_count_key(graph_id, "node"), &nodes.to_be_bytes())?;
+        storage.put_meta(
+            &Self::entity_count_key(graph_id, "relationship"),
+            &relationships.to_be_bytes(),
+        )?;
+        Ok((nodes, relationships))
+    }
+
+    fn entity_count_key(graph_id: GraphId, kind: &str) -> String {
+        format!("entity_count:{}:{}", graph_id, kind)
     }
 }
 
@@ -405,7 +1631,14 @@ impl Clone for Graph {
             name: self.name.clone(),
             storage: self.storage.clone(),
             id_gen: Arc::clone(&self.id_gen),
+            node_id_ceiling: Arc::clone(&self.node_id_ceiling),
+            rel_id_ceiling: Arc::clone(&self.rel_id_ceiling),
+            node_count: Arc::clone(&self.node_count),
+            rel_count: Arc::clone(&self.rel_count),
+            quotas: self.quotas,
+            merge_lock: Arc::clone(&self.merge_lock),
             schema: Arc::clone(&self.schema),
+            view: self.view.clone(),
         }
     }
 }
@@ -413,6 +1646,7 @@ impl Clone for Graph {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::database::Database;
     use crate::schema::Constraint;
     use qilbee_storage::StorageOptions;
     use tempfile::TempDir;
@@ -421,7 +1655,7 @@ mod tests {
         let temp_dir = TempDir::new().unwrap();
         let options = StorageOptions::for_testing(temp_dir.path());
         let storage = StorageEngine::open(options).unwrap();
-        let graph = Graph::new("test".to_string(), storage);
+        let graph = Graph::new("test".to_string(), storage, IdStrategy::Sequential, GraphQuotas::default()).unwrap();
         (graph, temp_dir)
     }
 
@@ -483,6 +1717,109 @@ mod tests {
         assert_eq!(rel.rel_type.name(), "KNOWS");
     }
 
+    #[test]
+    fn test_find_relationships_by_property() {
+        let (graph, _dir) = create_test_graph();
+
+        let alice = graph.create_node(["Person"]).unwrap();
+        let movie = graph.create_node(["Movie"]).unwrap();
+        let mut props = Property::new();
+        props.set("score", 5i64);
+        let rated = graph
+            .create_relationship_with_properties(alice.id, "RATED", movie.id, props)
+            .unwrap();
+        graph.create_relationship(alice.id, "RATED", movie.id).unwrap();
+
+        let found = graph
+            .find_relationships_by_property("RATED", "score", &PropertyValue::Integer(5))
+            .unwrap();
+        assert_eq!(found, vec![rated]);
+    }
+
+    #[test]
+    fn test_composite_index_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let string = |s: &str| PropertyValue::String(s.to_string());
+        let ada = {
+            let db = Database::open_for_testing(temp_dir.path()).unwrap();
+            let graph = db.graph("people").unwrap();
+            let mut props = Property::new();
+            props.set("lastName", "Lovelace");
+            props.set("firstName", "Ada");
+            let ada = graph.create_node_with_properties(["Person"], props).unwrap();
+
+            let index = Index::composite("person_name", "Person", vec!["lastName", "firstName"]);
+            assert!(graph.create_index(index.clone()).unwrap());
+            assert!(!graph.create_index(index).unwrap());
+            ada
+        };
+
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("people").unwrap();
+        let index = graph.schema().read().unwrap().get_index("person_name").cloned().unwrap();
+        assert_eq!(index.index_type, IndexType::Composite);
+        assert_eq!(index.properties, vec!["lastName", "firstName"]);
+        let found = graph
+            .find_nodes_by_composite_index("person_name", &[string("Lovelace"), string("Ada")])
+            .unwrap();
+        assert_eq!(found, vec![ada]);
+
+        assert!(graph.drop_index("person_name").unwrap().is_some());
+        assert!(graph.schema().read().unwrap().get_index("person_name").is_none());
+        assert!(graph
+            .find_nodes_by_composite_index("person_name", &[string("Lovelace")])
+            .is_err());
+    }
+
+    #[test]
+    fn test_range_index_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let db = Database::open_for_testing(temp_dir.path()).unwrap();
+            let graph = db.graph("people").unwrap();
+            assert!(graph.create_index(Index::range("person_city", "Person", "city")).unwrap());
+            assert!(graph.create_index(Index::range("person_age", "Person", "age")).unwrap());
+            assert!(graph.drop_index("person_age").unwrap().is_some());
+        }
+
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("people").unwrap();
+        let index = graph.schema().read().unwrap().get_index("person_city").cloned().unwrap();
+        assert_eq!(index, Index::range("person_city", "Person", "city"));
+        assert!(graph.schema().read().unwrap().get_index("person_age").is_none());
+        assert!(!graph.create_index(Index::range("person_city", "Person", "city")).unwrap());
+
+        // Dropped indexes stay dropped after the next restart too
+        assert!(graph.drop_index("person_city").unwrap().is_some());
+        drop((graph, db));
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        assert!(db.graph("people").unwrap().schema().read().unwrap().indexes.is_empty());
+    }
+
+    #[test]
+    fn test_create_index_reports_nodes_indexed() {
+        let (graph, _dir) = create_test_graph();
+        for city in ["Paris", "Paris", "Rome"] {
+            let mut props = Property::new();
+            props.set("city", city);
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        graph.create_node(["Person"]).unwrap();
+
+        let mut reports = Vec::new();
+        let index = Index::range("person_city", "Person", "city");
+        let indexed = graph
+            .create_index_with_progress(index.clone(), |progress| reports.push(progress))
+            .unwrap();
+        assert_eq!(indexed, Some(3));
+        assert_eq!(reports, vec![IndexBuildProgress { scanned: 4, total: 4, indexed: 3 }]);
+        assert_eq!(graph.create_index_with_progress(index, |_| {}).unwrap(), None);
+        assert!(graph.schema().read().unwrap().get_index("person_city").is_some());
+
+        let paris = PropertyValue::String("Paris".to_string());
+        assert_eq!(graph.find_nodes_by_label_and_property("Person", "city", &paris).unwrap().len(), 2);
+    }
+
     #[test]
     fn test_get_relationships() {
         let (graph, _dir) = create_test_graph();
@@ -503,6 +1840,26 @@ mod tests {
         assert_eq!(incoming.len(), 1);
     }
 
+    #[test]
+    fn test_get_relationships_both_directions() {
+        let (graph, _dir) = create_test_graph();
+
+        let alice = graph.create_node(["Person"]).unwrap();
+        let bob = graph.create_node(["Person"]).unwrap();
+
+        graph.create_relationship(alice.id, "KNOWS", bob.id).unwrap();
+        graph.create_relationship(bob.id, "KNOWS", alice.id).unwrap();
+        let self_loop = graph.create_relationship(alice.id, "KNOWS", alice.id).unwrap();
+
+        let both = graph.get_relationships(alice.id, Direction::Both).unwrap();
+        assert_eq!(both.len(), 3);
+        assert_eq!(both.iter().filter(|r| r.id == self_loop.id).count(), 1);
+
+        let neighbors = graph.get_neighbors(bob.id, Direction::Both).unwrap();
+        assert_eq!(neighbors.len(), 2);
+        assert!(neighbors.iter().all(|n| n.id == alice.id));
+    }
+
     #[test]
     fn test_get_neighbors() {
         let (graph, _dir) = create_test_graph();
@@ -532,10 +1889,259 @@ mod tests {
         assert!(graph.delete_node(alice.id).is_err());
 
         // Should succeed with detach
-        assert!(graph.detach_delete_node(alice.id).unwrap());
+        assert_eq!(graph.detach_delete_node(alice.id).unwrap(), Some(1));
         assert!(graph.get_node(alice.id).unwrap().is_none());
     }
 
+    #[test]
+    fn test_snapshot_handle() {
+        let (graph, _dir) = create_test_graph();
+
+        let alice = graph.create_node_with_properties(["Person"], Property::with("name", "Alice")).unwrap();
+        let snapshot = graph.snapshot();
+
+        let bob = graph.create_node(["Person"]).unwrap();
+        graph.create_relationship(alice.id, "KNOWS", bob.id).unwrap();
+        let mut renamed = alice.clone();
+        renamed.set_property("name", "Alicia");
+        graph.update_node(&renamed).unwrap();
+
+        assert_eq!(snapshot.get_node(alice.id).unwrap(), Some(alice.clone()));
+        assert!(snapshot.get_node(bob.id).unwrap().is_none());
+        assert_eq!(snapshot.get_all_node_ids().unwrap(), vec![alice.id]);
+        assert!(snapshot.get_relationships(alice.id, Direction::Both).unwrap().is_empty());
+        let name = PropertyValue::String("Alice".to_string());
+        assert_eq!(snapshot.find_nodes_by_label_and_property("Person", "name", &name).unwrap(), vec![alice]);
+        assert!(graph.find_nodes_by_label_and_property("Person", "name", &name).unwrap().is_empty());
+
+        // Entities cannot be written through a snapshot
+        assert!(matches!(snapshot.create_node(["Person"]), Err(Error::InvalidGraphOperation(_))));
+        assert!(matches!(snapshot.delete_relationship(RelationshipId::from_internal(1)), Err(Error::InvalidGraphOperation(_))));
+        assert_eq!(graph.node_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_transaction_handle() {
+        let (graph, _dir) = create_test_graph();
+        let alice = graph.create_node_with_properties(["Person"], Property::with("name", "Alice")).unwrap();
+
+        let tx = graph.transaction().unwrap();
+        let bob = tx.create_node_with_properties(["Person"], Property::with("name", "Bob")).unwrap();
+        tx.create_relationship(alice.id, "KNOWS", bob.id).unwrap();
+        let mut renamed = alice.clone();
+        renamed.set_property("name", "Alicia");
+        tx.update_node(&renamed).unwrap();
+
+        // The handle reads its own writes; nobody else sees them yet
+        assert_eq!(tx.find_nodes_by_label("Person").unwrap().len(), 2);
+        assert_eq!(tx.get_relationships(alice.id, Direction::Outgoing).unwrap().len(), 1);
+        let name = PropertyValue::String("Alicia".to_string());
+        assert_eq!(tx.find_nodes_by_label_and_property("Person", "name", &name).unwrap(), vec![renamed.clone()]);
+        assert!(graph.get_node(bob.id).unwrap().is_none());
+        assert_eq!(graph.get_node(alice.id).unwrap(), Some(alice.clone()));
+
+        tx.commit().unwrap();
+        assert!(matches!(tx.create_node(["Person"]), Err(Error::TransactionAborted(_))));
+        assert_eq!(graph.get_node(alice.id).unwrap(), Some(renamed));
+        assert_eq!(graph.get_relationships(bob.id, Direction::Incoming).unwrap().len(), 1);
+        assert_eq!((graph.node_count().unwrap(), graph.relationship_count().unwrap()), (2, 1));
+
+        // Dropping the handle discards its writes
+        {
+            let tx = graph.transaction().unwrap();
+            assert_eq!(tx.detach_delete_node(bob.id).unwrap(), Some(1));
+            tx.create_node(["Person"]).unwrap();
+        }
+        assert!(graph.get_node(bob.id).unwrap().is_some());
+        assert_eq!((graph.node_count().unwrap(), graph.relationship_count().unwrap()), (2, 1));
+
+        let tx = graph.transaction().unwrap();
+        assert_eq!(tx.detach_delete_node(bob.id).unwrap(), Some(1));
+        tx.commit().unwrap();
+        assert_eq!((graph.node_count().unwrap(), graph.relationship_count().unwrap()), (1, 0));
+    }
+
+    #[test]
+    fn test_transaction_commit_rechecks_writes() {
+        let (graph, _dir) = create_test_graph();
+        graph.schema.write().unwrap().add_constraint(Constraint::unique("uniq_email", "User", "email"));
+        let email = || Property::with("email", "alice@example.com");
+
+        // A conflict within the transaction fails as it is written
+        let tx = graph.transaction().unwrap();
+        tx.create_node_with_properties(["User"], email()).unwrap();
+        assert!(matches!(tx.create_node_with_properties(["User"], email()), Err(Error::UniqueViolation { .. })));
+        drop(tx);
+
+        // A conflict committed meanwhile fails the commit and stores nothing
+        let node = Node::with_labels_and_properties(graph.next_node_id().unwrap(), ["User"], email());
+        let tx = graph.transaction().unwrap();
+        tx.create_node_with_properties(["User"], email()).unwrap();
+        let target = tx.create_node(["User"]).unwrap();
+        graph.storage.create_node_checked(graph.id, &node, || Ok(())).unwrap();
+        assert!(matches!(tx.commit(), Err(Error::UniqueViolation { .. })));
+        assert!(graph.get_node(target.id).unwrap().is_none());
+
+        // So does a relationship whose endpoint was deleted meanwhile
+        let tx = graph.transaction().unwrap();
+        tx.create_relationship(node.id, "KNOWS", node.id).unwrap();
+        graph.storage.delete_node(graph.id, node.id).unwrap();
+        assert!(matches!(tx.commit(), Err(Error::NodeNotFound(_))));
+        assert!(graph.get_all_relationships().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_node_ids_after() {
+        let (graph, _dir) = create_test_graph();
+        let ids: Vec<NodeId> = (0..5)
+            .map(|i| graph.create_node([if i % 2 == 0 { "Even" } else { "Odd" }]).unwrap().id)
+            .collect();
+
+        assert_eq!(graph.node_ids_after(None, None, 2).unwrap(), ids[..2]);
+        assert_eq!(graph.node_ids_after(None, Some(ids[1]), 10).unwrap(), ids[2..]);
+        assert_eq!(graph.node_ids_after(Some("Even"), Some(ids[0]), 10).unwrap(), vec![ids[2], ids[4]]);
+        assert!(graph.node_ids_after(Some("Odd"), Some(ids[3]), 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_snapshot_readers_never_see_dangling_relationships() {
+        let (graph, _dir) = create_test_graph();
+        let writing = std::sync::atomic::AtomicBool::new(true);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..1000 {
+                    // The relationship is created after its endpoints and
+                    // deleted before them, one write at a time
+                    let source = graph.create_node(["Person"]).unwrap();
+                    let target = graph.create_node(["Person"]).unwrap();
+                    graph.create_relationship(source.id, "KNOWS", target.id).unwrap();
+                    graph.detach_delete_node(source.id).unwrap();
+                    graph.delete_node(target.id).unwrap();
+                }
+                writing.store(false, std::sync::atomic::Ordering::SeqCst);
+            });
+
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while writing.load(std::sync::atomic::Ordering::SeqCst) {
+                        let snapshot = graph.snapshot();
+                        for node in snapshot.find_nodes_by_label("Person").unwrap() {
+                            for rel in snapshot.get_relationships(node.id, Direction::Both).unwrap() {
+                                let other = rel.other(node.id).unwrap();
+                                assert!(
+                                    snapshot.get_node(other).unwrap().is_some(),
+                                    "relationship {:?} read without its endpoint",
+                                    rel.id
+                                );
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        assert_eq!(graph.node_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_detach_delete_nodes_by_label() {
+        let (graph, _dir) = create_test_graph();
+
+        let alice = graph.create_node(["Person"]).unwrap();
+        let temps: Vec<_> = (0..5).map(|_| graph.create_node(["Temp"]).unwrap()).collect();
+        graph.create_relationship(alice.id, "SEES", temps[0].id).unwrap();
+        graph.create_relationship(temps[1].id, "NEXT", temps[2].id).unwrap();
+        graph.create_relationship(alice.id, "KNOWS", alice.id).unwrap();
+
+        assert_eq!(graph.detach_delete_nodes_by_label("Temp", 2).unwrap(), (5, 2));
+        assert!(graph.find_nodes_by_label("Temp").unwrap().is_empty());
+        assert_eq!(graph.node_count().unwrap(), 1);
+        assert_eq!(graph.relationship_count().unwrap(), 1);
+
+        assert_eq!(graph.detach_delete_nodes_by_label("Temp", 2).unwrap(), (0, 0));
+        assert!(graph.detach_delete_nodes_by_label("Person", 0).is_err());
+    }
+
+    #[test]
+    fn test_clear() {
+        let (graph, _dir) = create_test_graph();
+
+        let nodes: Vec<_> = (0..5).map(|_| graph.create_node(["Person"]).unwrap()).collect();
+        graph.create_relationship(nodes[0].id, "KNOWS", nodes[1].id).unwrap();
+        graph.create_relationship(nodes[3].id, "KNOWS", nodes[4].id).unwrap();
+        graph.create_index(Index::range("person_age", "Person", "age")).unwrap();
+        graph
+            .create_constraint(Constraint::unique("uniq_name", "Person", "name"))
+            .unwrap();
+
+        assert!(graph.clear(0).is_err());
+        graph.clear(2).unwrap();
+        assert!(graph.get_all_node_ids().unwrap().is_empty());
+        assert!(graph.get_all_relationships().unwrap().is_empty());
+        assert_eq!(graph.node_count().unwrap(), 0);
+        assert_eq!(graph.relationship_count().unwrap(), 0);
+
+        // Nothing is left for a graph reopened with the same name
+        let reopened = Graph::new("test".to_string(), graph.storage().clone(), IdStrategy::Sequential, GraphQuotas::default())
+            .unwrap();
+        let schema = reopened.schema().read().unwrap();
+        assert!(schema.indexes.is_empty());
+        assert!(schema.constraints.is_empty());
+        assert_eq!(reopened.node_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_merge_node() {
+        let (graph, _dir) = create_test_graph();
+        let labels = vec!["User".to_string()];
+        let key = Property::with("email", "alice@example.com");
+
+        let (created, was_created) = graph
+            .merge_node(&labels, key.clone(), |node, created| {
+                node.set_property("visits", if created { 1i64 } else { 100 });
+                Ok(())
+            })
+            .unwrap();
+        assert!(was_created);
+        assert_eq!(created.get_property("visits"), Some(&PropertyValue::Integer(1)));
+
+        let (matched, was_created) = graph
+            .merge_node(&labels, key, |node, _| {
+                node.set_property("visits", 2i64);
+                Ok(())
+            })
+            .unwrap();
+        assert!(!was_created);
+        assert_eq!(matched.id, created.id);
+        assert_eq!(graph.node_count().unwrap(), 1);
+        let stored = graph.get_node(created.id).unwrap().unwrap();
+        assert_eq!(stored.get_property("visits"), Some(&PropertyValue::Integer(2)));
+
+        // A failing update leaves nothing behind
+        let other = Property::with("email", "bob@example.com");
+        let result = graph.merge_node(&labels, other, |_, _| Err(Error::InvalidQuery("nope".to_string())));
+        assert!(result.is_err());
+        assert_eq!(graph.node_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_merge_creates_one_node() {
+        let (graph, _dir) = create_test_graph();
+        let labels = vec!["User".to_string()];
+
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let key = Property::with("email", "alice@example.com");
+                    graph.merge_node(&labels, key, |_, _| Ok(())).unwrap();
+                });
+            }
+        });
+
+        assert_eq!(graph.find_nodes_by_label("User").unwrap().len(), 1);
+    }
+
     #[test]
     fn test_unique_constraint() {
         let (graph, _dir) = create_test_graph();
@@ -557,7 +2163,100 @@ mod tests {
         let mut props2 = Property::new();
         props2.set("email", "alice@example.com");
         let result = graph.create_node_with_properties(["User"], props2);
-        assert!(result.is_err());
+        assert!(matches!(
+            result,
+            Err(Error::UniqueViolation { ref label, ref property }) if label == "User" && property == "email"
+        ));
+        assert_eq!(graph.find_nodes_by_label("User").unwrap().len(), 1);
+
+        // Updating another user to the taken value fails too
+        let mut props3 = Property::new();
+        props3.set("email", "bob@example.com");
+        let mut bob = graph.create_node_with_properties(["User"], props3).unwrap();
+        bob.set_property("email", "alice@example.com");
+        assert!(matches!(graph.update_node(&bob), Err(Error::UniqueViolation { .. })));
+    }
+
+    #[test]
+    fn test_exists_constraint() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing_email = |result: Result<Node>| {
+            matches!(result, Err(Error::ConstraintViolation(msg)) if msg.contains("'email'") && msg.contains("'User'"))
+        };
+        let user = |email: Option<PropertyValue>| {
+            let mut props = Property::new();
+            props.set("name", "Alice");
+            if let Some(email) = email {
+                props.set("email", email);
+            }
+            props
+        };
+
+        let legacy = {
+            let db = Database::open_for_testing(temp_dir.path()).unwrap();
+            let graph = db.graph("app").unwrap();
+            let legacy = graph.create_node_with_properties(["User"], user(None)).unwrap();
+
+            let constraint = Constraint::exists("user_email", "User", "email");
+            assert!(graph.create_constraint(constraint.clone()).unwrap());
+            assert!(!graph.create_constraint(constraint).unwrap());
+            legacy
+        };
+
+        // The constraint is loaded again when the graph is opened
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("app").unwrap();
+        assert!(graph.schema().read().unwrap().get_constraint("user_email").is_some());
+
+        assert!(missing_email(graph.create_node_with_properties(["User"], user(None))));
+        assert!(missing_email(graph.create_node_with_properties(["User"], user(Some(PropertyValue::Null)))));
+        assert!(graph.create_node_with_properties(["Guest"], user(None)).is_ok());
+        let mut alice = graph
+            .create_node_with_properties(["User"], user(Some("alice@example.com".into())))
+            .unwrap();
+
+        // Removing the property is rejected on update
+        alice.properties.remove("email");
+        assert!(missing_email(graph.update_node(&alice).map(|_| alice.clone())));
+
+        // Nodes stored before the constraint stay until they are next updated
+        assert_eq!(graph.get_node(legacy.id).unwrap(), Some(legacy.clone()));
+        assert!(missing_email(graph.update_node(&legacy).map(|_| legacy.clone())));
+
+        assert!(graph.drop_constraint("user_email").unwrap().is_some());
+        assert!(graph.create_node_with_properties(["User"], user(None)).is_ok());
+        drop((graph, db));
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        assert!(db.graph("app").unwrap().schema().read().unwrap().get_constraint("user_email").is_none());
+    }
+
+    #[test]
+    fn test_unique_constraint_under_concurrent_creates() {
+        let (graph, _dir) = create_test_graph();
+        graph
+            .schema
+            .write()
+            .unwrap()
+            .add_constraint(Constraint::unique("uniq_email", "User", "email"));
+
+        let created = std::sync::atomic::AtomicUsize::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let mut props = Property::new();
+                    props.set("email", "alice@example.com");
+                    match graph.create_node_with_properties(["User"], props) {
+                        Ok(_) => {
+                            created.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                        }
+                        Err(e) => assert!(matches!(e, Error::UniqueViolation { .. }), "{e}"),
+                    }
+                });
+            }
+        });
+
+        assert_eq!(created.into_inner(), 1);
+        assert_eq!(graph.find_nodes_by_label("User").unwrap().len(), 1);
     }
 
     #[test]
@@ -648,6 +2347,51 @@ mod tests {
         assert_eq!(at_least_35.len(), 2); // ages 40 and 50
     }
 
+    #[test]
+    fn test_ordered_node_listing() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+        let graph = Graph::new("test".to_string(), storage, IdStrategy::Random, GraphQuotas::default()).unwrap();
+
+        for (name, age) in [("Dan", 41i64), ("Ann", 29), ("Cat", 35), ("Bea", 29)] {
+            let mut props = Property::new();
+            props.set("name", name);
+            props.set("age", age);
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        graph.create_node_with_properties(["Person"], Property::new()).unwrap();
+
+        // ID order is sorted and identical on every call
+        let by_id = graph.get_all_nodes_ordered(&NodeOrder::Id).unwrap();
+        let ids: Vec<u64> = by_id.iter().map(|n| n.id.as_internal()).collect();
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        for _ in 0..3 {
+            let again = graph.find_nodes_by_label_ordered("Person", &NodeOrder::Id).unwrap();
+            assert_eq!(again.iter().map(|n| n.id.as_internal()).collect::<Vec<_>>(), ids);
+        }
+
+        // Property order; equal ages fall back to ID and missing values come last
+        let by_age = graph
+            .find_nodes_by_label_ordered("Person", &NodeOrder::Property("age".to_string()))
+            .unwrap();
+        let ages: Vec<_> = by_age.iter().map(|n| n.get_property("age").cloned()).collect();
+        assert_eq!(
+            ages,
+            vec![
+                Some(PropertyValue::Integer(29)),
+                Some(PropertyValue::Integer(29)),
+                Some(PropertyValue::Integer(35)),
+                Some(PropertyValue::Integer(41)),
+                None,
+            ]
+        );
+        assert!(by_age[0].id.as_internal() < by_age[1].id.as_internal());
+
+        let by_name = graph.get_all_nodes_ordered(&NodeOrder::Property("name".to_string())).unwrap();
+        let names: Vec<_> = by_name.iter().filter_map(|n| n.get_property("name")?.as_str()).collect();
+        assert_eq!(names, ["Ann", "Bea", "Cat", "Dan"]);
+    }
+
     #[test]
     fn test_property_index_after_update() {
         let (graph, _dir) = create_test_graph();
@@ -704,4 +2448,160 @@ mod tests {
             .unwrap();
         assert_eq!(alices.len(), 0);
     }
+
+    #[test]
+    fn test_random_id_strategy() {
+        let temp_dir = TempDir::new().unwrap();
+        let options = StorageOptions::for_testing(temp_dir.path());
+        let storage = StorageEngine::open(options).unwrap();
+        let graph = Graph::new("test".to_string(), storage, IdStrategy::Random, GraphQuotas::default()).unwrap();
+
+        let n1 = graph.create_node(["Person"]).unwrap();
+        let n2 = graph.create_node(["Person"]).unwrap();
+        assert_ne!(n1.id, n2.id);
+
+        // Random ids never touch the sequential watermark
+        assert!(graph.storage().get_meta(&Graph::id_watermark_key(graph.id(), "node")).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_entity_counts() {
+        let (graph, _dir) = create_test_graph();
+
+        let alice = graph.create_node(["Person"]).unwrap();
+        let bob = graph.create_node(["Person"]).unwrap();
+        graph.create_relationship(alice.id, "KNOWS", bob.id).unwrap();
+        graph.create_relationship(bob.id, "KNOWS", bob.id).unwrap();
+        assert_eq!(graph.node_count().unwrap(), 2);
+        assert_eq!(graph.relationship_count().unwrap(), 2);
+
+        // Self-loops appear in both adjacency lists but are only counted once
+        assert_eq!(graph.detach_delete_node(bob.id).unwrap(), Some(2));
+        assert_eq!(graph.node_count().unwrap(), 1);
+        assert_eq!(graph.relationship_count().unwrap(), 0);
+
+        assert!(!graph.delete_node(bob.id).unwrap());
+        assert_eq!(graph.node_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_create_relationships_batch() {
+        let (graph, _dir) = create_test_graph();
+
+        let hub = graph.create_node(["Person"]).unwrap();
+        let others: Vec<_> = (0..100)
+            .map(|_| graph.create_node(["Person"]).unwrap().id)
+            .collect();
+        let missing = NodeId::from_internal(999_999);
+
+        let mut rows: Vec<_> = others
+            .iter()
+            .map(|id| (hub.id, "KNOWS", *id, Property::new()))
+            .collect();
+        rows.insert(10, (hub.id, "KNOWS", missing, Property::new()));
+        rows.push((missing, "KNOWS", hub.id, Property::new()));
+
+        let results = graph.create_relationships_with_properties(rows).unwrap();
+        assert_eq!(results.len(), 102);
+        assert!(matches!(results[10], Err(Error::NodeNotFound(_))));
+        assert!(matches!(results[101], Err(Error::NodeNotFound(_))));
+        assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 100);
+        assert_eq!(graph.relationship_count().unwrap(), 100);
+
+        // Adjacency indices cover every created relationship
+        let outgoing = graph.get_relationships(hub.id, Direction::Outgoing).unwrap();
+        assert_eq!(outgoing.len(), 100);
+        for id in &others {
+            let incoming = graph.get_relationships(*id, Direction::Incoming).unwrap();
+            assert_eq!(incoming.len(), 1);
+            assert_eq!(incoming[0].source, hub.id);
+        }
+    }
+
+    #[test]
+    fn test_create_nodes_batch() {
+        let (graph, _dir) = create_test_graph();
+
+        let rows = (0..100).map(|i| {
+            let mut props = Property::new();
+            props.set("n", i as i64);
+            (vec!["Person"], props)
+        });
+        let nodes: Vec<_> = graph
+            .create_nodes_with_properties(rows)
+            .unwrap()
+            .into_iter()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(nodes.len(), 100);
+        assert_eq!(graph.node_count().unwrap(), 100);
+        assert_eq!(
+            graph.find_nodes_by_label_and_property("Person", "n", &PropertyValue::Integer(42)).unwrap(),
+            vec![nodes[42].clone()]
+        );
+
+        // Constrained nodes are checked against each other
+        graph
+            .create_constraint(Constraint::unique("uniq_email", "User", "email"))
+            .unwrap();
+        let user = |email: &str| {
+            let mut props = Property::new();
+            props.set("email", email);
+            (vec!["User"], props)
+        };
+        let results = graph
+            .create_nodes_with_properties([user("a@example.com"), user("a@example.com"), user("b@example.com")])
+            .unwrap();
+        assert!(results[0].is_ok());
+        assert!(matches!(results[1], Err(Error::UniqueViolation { .. })));
+        assert!(results[2].is_ok());
+        assert_eq!(graph.find_nodes_by_label("User").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_node_and_relationship_quotas() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+        let quotas = GraphQuotas {
+            max_nodes: Some(2),
+            max_relationships: Some(1),
+        };
+        let graph = Graph::new("test".to_string(), storage, IdStrategy::Sequential, quotas).unwrap();
+
+        let a = graph.create_node(["Person"]).unwrap();
+        let b = graph.create_node_with_properties(["Person"], Property::new()).unwrap();
+        assert!(matches!(graph.create_node(["Person"]), Err(Error::QuotaExceeded(_))));
+        assert!(matches!(
+            graph.create_node_with_properties(["Person"], Property::new()),
+            Err(Error::QuotaExceeded(_))
+        ));
+
+        let rel = graph.create_relationship(a.id, "KNOWS", b.id).unwrap();
+        assert!(matches!(
+            graph.create_relationship(b.id, "KNOWS", a.id),
+            Err(Error::QuotaExceeded(_))
+        ));
+        assert_eq!(graph.node_count().unwrap(), 2);
+        assert_eq!(graph.relationship_count().unwrap(), 1);
+
+        // A batch that would overshoot the quota writes nothing
+        graph.delete_relationship(rel.id).unwrap();
+        let batch = vec![
+            (a.id, "KNOWS", b.id, Property::new()),
+            (b.id, "KNOWS", a.id, Property::new()),
+        ];
+        assert!(matches!(
+            graph.create_relationships_with_properties(batch),
+            Err(Error::QuotaExceeded(_))
+        ));
+        assert_eq!(graph.relationship_count().unwrap(), 0);
+        assert!(matches!(
+            graph.create_nodes_with_properties([(["Person"], Property::new())]),
+            Err(Error::QuotaExceeded(_))
+        ));
+        assert_eq!(graph.node_count().unwrap(), 2);
+
+        // Deleting frees up quota again
+        graph.create_relationship(b.id, "KNOWS", a.id).unwrap();
+    }
 }
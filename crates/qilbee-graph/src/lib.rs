@@ -7,13 +7,19 @@
 //! The graph engine provides:
 //! - Graph instance management (create, open, delete graphs)
 //! - High-level node and relationship operations
-//! - Schema management (constraints, indices)
+//! - Schema management (constraints, indices, migrations)
 //! - Graph algorithms
 
+pub mod algorithms;
 pub mod database;
 pub mod graph;
+pub mod migration;
 pub mod schema;
 
-pub use database::Database;
-pub use graph::Graph;
+pub use algorithms::{
+    Path, PathOptions, DEFAULT_PAGERANK_DAMPING, DEFAULT_PAGERANK_ITERATIONS, DEFAULT_PAGERANK_TOLERANCE,
+};
+pub use database::{Database, DatabaseConfig};
+pub use graph::{Graph, GraphQuotas, NodeOrder};
+pub use migration::{Migration, MigrationRunner};
 pub use schema::{Constraint, ConstraintType, Index, IndexType, Schema};
@@ -0,0 +1,208 @@
+//! Schema versioning and migrations
+//!
+//! Migrations are ordered steps (build an index, backfill data, ...) that
+//! run at startup. The highest applied version is recorded in the schema
+//! column family, so each step runs exactly once across restarts.
+
+use crate::database::Database;
+use qilbee_core::{Error, Result};
+use tracing::info;
+
+/// Function applying a migration step
+type MigrationFn = Box<dyn Fn(&Database) -> Result<()> + Send + Sync>;
+
+/// A single schema migration step
+pub struct Migration {
+    /// Version reached once this step is applied (must be > 0)
+    version: u64,
+
+    /// Human-readable description
+    description: String,
+
+    /// The step itself
+    apply: MigrationFn,
+}
+
+impl Migration {
+    /// Create a new migration step
+    pub fn new<F>(version: u64, description: &str, apply: F) -> Self
+    where
+        F: Fn(&Database) -> Result<()> + Send + Sync + 'static,
+    {
+        Self {
+            version,
+            description: description.to_string(),
+            apply: Box::new(apply),
+        }
+    }
+
+    /// Get the version of this migration
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Get the description of this migration
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+impl std::fmt::Debug for Migration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// Applies pending migrations in version order
+#[derive(Debug, Default)]
+pub struct MigrationRunner {
+    migrations: Vec<Migration>,
+}
+
+impl MigrationRunner {
+    /// Create an empty migration runner
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Migrations shipped with QilbeeDB
+    ///
+    /// New steps are appended here with increasing versions.
+    pub fn builtin() -> Self {
+        Self::new()
+    }
+
+    /// Add a migration step
+    pub fn with_migration(mut self, migration: Migration) -> Self {
+        self.migrations.push(migration);
+        self
+    }
+
+    /// Highest version known to this runner
+    pub fn latest_version(&self) -> u64 {
+        self.migrations.iter().map(|m| m.version).max().unwrap_or(0)
+    }
+
+    /// Apply every migration newer than the recorded schema version
+    ///
+    /// The version is recorded after each step, so a failure leaves the
+    /// already applied steps in place and the run can simply be retried.
+    /// Returns the number of steps applied.
+    pub fn run(&self, db: &Database) -> Result<usize> {
+        let mut migrations: Vec<&Migration> = self.migrations.iter().collect();
+        migrations.sort_by_key(|m| m.version);
+
+        if let Some(pair) = migrations.windows(2).find(|w| w[0].version == w[1].version) {
+            return Err(Error::Configuration(format!(
+                "Duplicate migration version {}",
+                pair[0].version
+            )));
+        }
+        if migrations.first().is_some_and(|m| m.version == 0) {
+            return Err(Error::Configuration(
+                "Migration versions must start at 1".to_string(),
+            ));
+        }
+
+        let current = db.schema_version()?;
+        let mut applied = 0;
+
+        for migration in migrations.into_iter().filter(|m| m.version > current) {
+            info!(
+                "Applying schema migration {}: {}",
+                migration.version, migration.description
+            );
+            (migration.apply)(db)?;
+            db.storage().set_schema_version(migration.version)?;
+            applied += 1;
+        }
+
+        Ok(applied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schema::Index;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    fn index_migration(runs: Arc<AtomicUsize>) -> MigrationRunner {
+        MigrationRunner::new().with_migration(Migration::new(1, "Add Person(name) index", move |db| {
+            runs.fetch_add(1, Ordering::SeqCst);
+            let graph = db.graph("social")?;
+            graph
+                .schema()
+                .write()
+                .unwrap()
+                .add_index(Index::range("person_name", "Person", "name"));
+            Ok(())
+        }))
+    }
+
+    #[test]
+    fn test_migration_applied_once_across_restarts() {
+        let temp_dir = TempDir::new().unwrap();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        {
+            let db = Database::open_for_testing(temp_dir.path()).unwrap();
+            assert_eq!(db.schema_version().unwrap(), 0);
+
+            let runner = index_migration(Arc::clone(&runs));
+            assert_eq!(runner.run(&db).unwrap(), 1);
+            assert_eq!(db.schema_version().unwrap(), 1);
+
+            let graph = db.graph("social").unwrap();
+            assert!(graph.schema().read().unwrap().get_index("person_name").is_some());
+
+            // Running again in the same process is a no-op
+            assert_eq!(runner.run(&db).unwrap(), 0);
+        }
+
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        assert_eq!(index_migration(Arc::clone(&runs)).run(&db).unwrap(), 0);
+        assert_eq!(db.schema_version().unwrap(), 1);
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_migrations_run_in_version_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let order = Arc::new(std::sync::Mutex::new(Vec::new()));
+
+        let (o1, o2) = (Arc::clone(&order), Arc::clone(&order));
+        let runner = MigrationRunner::new()
+            .with_migration(Migration::new(2, "second", move |_| {
+                o2.lock().unwrap().push(2);
+                Ok(())
+            }))
+            .with_migration(Migration::new(1, "first", move |_| {
+                o1.lock().unwrap().push(1);
+                Ok(())
+            }));
+
+        assert_eq!(runner.latest_version(), 2);
+        assert_eq!(runner.run(&db).unwrap(), 2);
+        assert_eq!(*order.lock().unwrap(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_duplicate_migration_versions_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+
+        let runner = MigrationRunner::new()
+            .with_migration(Migration::new(1, "a", |_| Ok(())))
+            .with_migration(Migration::new(1, "b", |_| Ok(())));
+
+        assert!(runner.run(&db).is_err());
+        assert_eq!(db.schema_version().unwrap(), 0);
+    }
+}
@@ -1,13 +1,16 @@
 //! Agent memory manager
 
 use crate::embeddings::{
-    create_provider, similarity, EmbeddingConfig, EmbeddingProvider, SimilarityMetric,
+    create_provider, similarity, EmbeddingConfig, EmbeddingError, EmbeddingProvider,
+    SimilarityMetric,
 };
 use crate::episode::{Episode, EpisodeId, EpisodeType};
-use crate::storage::{InMemoryStorage, MemoryStorage, MemoryStorageConfig, RocksDbMemoryStorage};
-use crate::types::{MemoryConfig, MemoryType, Relevance};
+use crate::storage::{
+    EpisodeCursor, InMemoryStorage, MemoryStorage, MemoryStorageConfig, RocksDbMemoryStorage,
+};
+use crate::types::{ForgetCriteria, MemoryConfig, Relevance};
 use crate::vector_index::{HnswConfig, HnswIndex};
-use qilbee_core::temporal::{EventTime, TemporalRange};
+use qilbee_core::temporal::{EventTime, TemporalRange, TransactionTime};
 use qilbee_core::{Error, Result};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
@@ -29,6 +32,73 @@ pub struct MemoryStatistics {
     pub avg_relevance: f64,
 }
 
+/// Outcome of a decay pass with an eviction threshold
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DecayReport {
+    /// Episodes whose relevance was reduced and which were kept
+    pub decayed: usize,
+
+    /// Episodes removed because their relevance fell below the threshold
+    pub evicted: usize,
+}
+
+/// Outcome of forgetting episodes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForgetReport {
+    /// Number of episodes forgotten, per episode type
+    pub by_type: HashMap<EpisodeType, usize>,
+}
+
+impl ForgetReport {
+    fn record(&mut self, episode_type: EpisodeType) {
+        *self.by_type.entry(episode_type).or_insert(0) += 1;
+    }
+
+    /// Total number of episodes forgotten
+    pub fn total(&self) -> usize {
+        self.by_type.values().sum()
+    }
+}
+
+/// One page of episodes, newest first
+#[derive(Debug, Clone, Default)]
+pub struct EpisodePage {
+    /// Episodes in descending event time order
+    pub episodes: Vec<Episode>,
+    /// Cursor for the next (older) page, if there is one
+    pub next_cursor: Option<EpisodeCursor>,
+}
+
+impl EpisodePage {
+    /// Take the page of up to `limit` episodes that come before `before`
+    ///
+    /// Episodes are ordered by event time, then ID, so episodes sharing a
+    /// timestamp are neither skipped nor repeated across pages, and episodes
+    /// stored after paging began never shift earlier pages.
+    pub fn before(mut episodes: Vec<Episode>, before: Option<EpisodeCursor>, limit: usize) -> Self {
+        episodes.retain(|e| before.is_none_or(|cursor| EpisodeCursor::at(e).order() < cursor.order()));
+        episodes.sort_by_key(|e| std::cmp::Reverse(EpisodeCursor::at(e).order()));
+
+        let has_more = episodes.len() > limit;
+        episodes.truncate(limit);
+        let next_cursor = episodes.last().filter(|_| has_more).map(EpisodeCursor::at);
+        Self {
+            episodes,
+            next_cursor,
+        }
+    }
+}
+
+/// Candidates fetched per requested result when re-ranking by relevance
+const RERANK_CANDIDATES: usize = 3;
+
+/// Parse an episode ID from its string form
+fn parse_episode_id(id: &str) -> Result<EpisodeId> {
+    uuid::Uuid::parse_str(id)
+        .map(EpisodeId::from_uuid)
+        .map_err(|_| Error::ValidationError(format!("Invalid episode ID: {}", id)))
+}
+
 /// Agent memory manager
 ///
 /// Provides memory operations for a single AI agent.
@@ -107,6 +177,13 @@ impl AgentMemory {
         }
     }
 
+    /// Get an episode by the string form of its ID
+    ///
+    /// Fails with a validation error if `id` is not a valid episode ID.
+    pub fn get_episode_by_id_str(&self, id: &str) -> Result<Option<Episode>> {
+        self.get_episode(parse_episode_id(id)?)
+    }
+
     /// Get episodes by type
     pub fn get_episodes_by_type(&self, episode_type: &EpisodeType) -> Result<Vec<Episode>> {
         let episodes = self.episodes.read().map_err(|_| {
@@ -121,6 +198,8 @@ impl AgentMemory {
     }
 
     /// Get episodes in a time range
+    ///
+    /// See [`TemporalRange`] for how inclusive, exclusive and open bounds apply.
     pub fn get_episodes_in_range(&self, range: &TemporalRange) -> Result<Vec<Episode>> {
         let episodes = self.episodes.read().map_err(|_| {
             Error::Internal("Failed to acquire episodes lock".to_string())
@@ -133,6 +212,24 @@ impl AgentMemory {
             .collect())
     }
 
+    /// Get the episodes the agent knew at a past transaction time
+    ///
+    /// Includes episodes invalidated since then, sorted by event time.
+    pub fn get_episodes_as_of(&self, transaction_time: TransactionTime) -> Result<Vec<Episode>> {
+        let episodes = self.episodes.read().map_err(|_| {
+            Error::Internal("Failed to acquire episodes lock".to_string())
+        })?;
+
+        let mut known: Vec<_> = episodes
+            .values()
+            .filter(|e| e.was_valid_at(transaction_time))
+            .cloned()
+            .collect();
+        known.sort_by_key(|e| e.event_time);
+
+        Ok(known)
+    }
+
     /// Get recent episodes (last N)
     pub fn get_recent_episodes(&self, limit: usize) -> Result<Vec<Episode>> {
         let episodes = self.episodes.read().map_err(|_| {
@@ -147,6 +244,20 @@ impl AgentMemory {
         Ok(valid.into_iter().take(limit).collect())
     }
 
+    /// Get a page of up to `limit` valid episodes before `before`, newest first
+    pub fn get_episodes_page(
+        &self,
+        before: Option<EpisodeCursor>,
+        limit: usize,
+    ) -> Result<EpisodePage> {
+        let episodes = self.episodes.read().map_err(|_| {
+            Error::Internal("Failed to acquire episodes lock".to_string())
+        })?;
+
+        let valid = episodes.values().filter(|e| e.is_valid()).cloned().collect();
+        Ok(EpisodePage::before(valid, before, limit))
+    }
+
     /// Search episodes by content (simple substring match)
     pub fn search_episodes(&self, query: &str) -> Result<Vec<Episode>> {
         let query_lower = query.to_lowercase();
@@ -199,10 +310,10 @@ impl AgentMemory {
 
     // ========== Memory Operations ==========
 
-    /// Apply relevance decay to all episodes
-    pub fn apply_decay(&self) -> Result<()> {
-        let decay_rate = MemoryType::Episodic.default_decay_rate();
-
+    /// Apply time-based relevance decay to all episodes
+    ///
+    /// See [`MemoryType::default_decay_rate`](crate::types::MemoryType::default_decay_rate) for typical rates.
+    pub fn apply_decay(&self, decay_rate: f64) -> Result<()> {
         let mut episodes = self.episodes.write().map_err(|_| {
             Error::Internal("Failed to acquire episodes lock".to_string())
         })?;
@@ -220,39 +331,76 @@ impl AgentMemory {
         Ok(())
     }
 
-    /// Forget low-relevance episodes
-    pub fn forget(&self) -> Result<usize> {
-        if !self.config.auto_forget {
-            return Ok(0);
+    /// Scale every episode's relevance by `decay_factor`, then evict
+    /// episodes whose relevance is below `min_relevance`
+    ///
+    /// A factor near 1.0 barely changes relevance; a factor near 0.0 is
+    /// aggressive. Eviction happens regardless of `auto_forget`.
+    pub fn apply_decay_with_threshold(
+        &self,
+        decay_factor: f64,
+        min_relevance: f64,
+    ) -> Result<DecayReport> {
+        let mut episodes = self.episodes.write().map_err(|_| {
+            Error::Internal("Failed to acquire episodes lock".to_string())
+        })?;
+
+        let mut report = DecayReport::default();
+        let mut to_evict = Vec::new();
+        for (id, episode) in episodes.iter_mut().filter(|(_, e)| e.is_valid()) {
+            let changed = episode.relevance.decay_by_factor(decay_factor);
+            if episode.relevance.should_forget(min_relevance) {
+                to_evict.push(*id);
+            } else if changed {
+                report.decayed += 1;
+            }
         }
 
+        for id in to_evict {
+            if let Some(mut episode) = episodes.remove(&id) {
+                episode.invalidate();
+                report.evicted += 1;
+            }
+        }
+
+        info!(
+            "Decayed {} and evicted {} episodes for agent {}",
+            report.decayed, report.evicted, self.config.agent_id
+        );
+
+        Ok(report)
+    }
+
+    /// Forget episodes matching all supplied criteria
+    pub fn forget(&self, criteria: &ForgetCriteria) -> Result<ForgetReport> {
         let mut episodes = self.episodes.write().map_err(|_| {
             Error::Internal("Failed to acquire episodes lock".to_string())
         })?;
 
-        let min_relevance = self.config.min_relevance;
+        let now = chrono::Utc::now().timestamp_millis();
         let to_forget: Vec<_> = episodes
             .iter()
-            .filter(|(_, e)| e.relevance.should_forget(min_relevance))
+            .filter(|(_, e)| e.is_valid() && criteria.matches(e, now))
             .map(|(id, _)| *id)
             .collect();
 
-        let count = to_forget.len();
-
+        let mut report = ForgetReport::default();
         for id in to_forget {
             if let Some(mut episode) = episodes.remove(&id) {
                 episode.invalidate();
+                report.record(episode.episode_type);
             }
         }
 
-        if count > 0 {
+        if report.total() > 0 {
             info!(
                 "Forgot {} episodes for agent {}",
-                count, self.config.agent_id
+                report.total(),
+                self.config.agent_id
             );
         }
 
-        Ok(count)
+        Ok(report)
     }
 
     /// Clear all episodes
@@ -377,8 +525,11 @@ impl Clone for AgentMemory {
 pub struct SemanticSearchResult {
     /// The matched episode
     pub episode: Episode,
-    /// Similarity score (0.0 to 1.0, higher is more similar)
+    /// Ranking score: the similarity, blended with the episode's relevance
+    /// when a relevance weight is set
     pub score: f32,
+    /// Similarity score under the index's metric, higher is more similar
+    pub similarity: f32,
 }
 
 /// Hybrid search result containing episode and combined score
@@ -403,6 +554,8 @@ pub struct SemanticSearchConfig {
     pub hnsw_config: HnswConfig,
     /// Whether to auto-generate embeddings on store
     pub auto_embed: bool,
+    /// Weight (0.0 to 1.0) of episode relevance in semantic search ranking
+    pub relevance_weight: f32,
 }
 
 impl Default for SemanticSearchConfig {
@@ -411,26 +564,54 @@ impl Default for SemanticSearchConfig {
             embedding_config: EmbeddingConfig::default(),
             hnsw_config: HnswConfig::small(),
             auto_embed: true,
+            relevance_weight: 0.0,
         }
     }
 }
 
 impl SemanticSearchConfig {
+    /// Create config for the given embedding provider
+    pub fn new(embedding_config: EmbeddingConfig) -> Self {
+        let dimensions = embedding_config.dimensions;
+        Self {
+            embedding_config,
+            hnsw_config: HnswConfig::medium().with_dimension(dimensions),
+            auto_embed: true,
+            relevance_weight: 0.0,
+        }
+    }
+
     /// Create config with mock embeddings (for testing)
     pub fn mock(dimensions: usize) -> Self {
         Self {
             embedding_config: EmbeddingConfig::mock(dimensions),
             hnsw_config: HnswConfig::small().with_dimension(dimensions),
             auto_embed: true,
+            relevance_weight: 0.0,
         }
     }
 
+    /// Builder: weight episode relevance in semantic search ranking
+    pub fn with_relevance_weight(mut self, weight: f32) -> Self {
+        self.relevance_weight = weight.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Builder: similarity metric used by the vector index
+    ///
+    /// Scores follow [`SimilarityMetric::score_from_distance`].
+    pub fn with_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.hnsw_config = self.hnsw_config.with_metric(metric);
+        self
+    }
+
     /// Create config for OpenAI embeddings
     pub fn openai(api_key: &str) -> Self {
         Self {
             embedding_config: EmbeddingConfig::openai_small(api_key),
             hnsw_config: HnswConfig::medium().with_dimension(1536),
             auto_embed: true,
+            relevance_weight: 0.0,
         }
     }
 }
@@ -460,7 +641,11 @@ pub struct PersistentAgentMemory {
 
 impl PersistentAgentMemory {
     /// Create a new persistent agent memory with RocksDB storage
+    ///
+    /// With `per_agent_directories` set, the agent's memory lives in its own
+    /// directory (see [`MemoryStorageConfig::agent_path`]).
     pub fn new(config: MemoryConfig, storage_config: MemoryStorageConfig) -> Result<Self> {
+        let storage_config = storage_config.for_agent(&config.agent_id);
         let storage_path = storage_config.path.clone();
         let storage = RocksDbMemoryStorage::open(storage_config).map_err(|e| {
             Error::Storage(format!("Failed to create RocksDB storage: {}", e))
@@ -512,10 +697,15 @@ impl PersistentAgentMemory {
     }
 
     /// Enable semantic search with the given configuration
-    pub fn with_semantic_search(mut self, semantic_config: SemanticSearchConfig) -> Result<Self> {
+    pub fn with_semantic_search(mut self, mut semantic_config: SemanticSearchConfig) -> Result<Self> {
         let provider = create_provider(semantic_config.embedding_config.clone())
             .map_err(|e| Error::Internal(format!("Failed to create embedding provider: {}", e)))?;
 
+        // A local model knows its real output size, which wins over the config
+        if let Some(dimension) = semantic_config.hnsw_config.dimension.as_mut() {
+            *dimension = provider.dimensions();
+        }
+
         let index = HnswIndex::new(semantic_config.hnsw_config.clone());
 
         info!(
@@ -593,6 +783,39 @@ impl PersistentAgentMemory {
         Ok(id)
     }
 
+    /// Store several episodes with a single storage write
+    ///
+    /// Returns the episode IDs in the order given.
+    pub async fn store_episodes(&self, episodes: &[Episode]) -> Result<Vec<EpisodeId>> {
+        if !self.config.enable_episodic {
+            return Err(Error::MemoryOperation(
+                "Episodic memory is disabled".to_string(),
+            ));
+        }
+
+        // Make room for the whole batch under the max episodes limit
+        let count = self.storage.episode_count(&self.config.agent_id).await.map_err(|e| {
+            Error::Storage(format!("Failed to get episode count: {}", e))
+        })?;
+        let overflow = (count + episodes.len()).saturating_sub(self.config.max_episodes);
+        for _ in 0..overflow.min(count) {
+            self.evict_low_relevance_episode().await?;
+        }
+
+        self.storage
+            .store_episodes(&self.config.agent_id, episodes)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to store episodes: {}", e)))?;
+
+        debug!(
+            "Stored {} episodes for agent {}",
+            episodes.len(),
+            self.config.agent_id
+        );
+
+        Ok(episodes.iter().map(|e| e.id).collect())
+    }
+
     /// Get an episode by ID
     pub async fn get_episode(&self, id: EpisodeId) -> Result<Option<Episode>> {
         let mut episode = self
@@ -612,6 +835,13 @@ impl PersistentAgentMemory {
         Ok(episode)
     }
 
+    /// Get an episode by the string form of its ID
+    ///
+    /// Fails with a validation error if `id` is not a valid episode ID.
+    pub async fn get_episode_by_id_str(&self, id: &str) -> Result<Option<Episode>> {
+        self.get_episode(parse_episode_id(id)?).await
+    }
+
     /// Get episodes by type
     pub async fn get_episodes_by_type(&self, episode_type: &EpisodeType) -> Result<Vec<Episode>> {
         let all_episodes = self
@@ -627,18 +857,40 @@ impl PersistentAgentMemory {
     }
 
     /// Get episodes in a time range
+    ///
+    /// See [`TemporalRange`] for how inclusive, exclusive and open bounds apply.
     pub async fn get_episodes_in_range(&self, range: &TemporalRange) -> Result<Vec<Episode>> {
+        let (start_millis, end_millis) = range.millis_bounds();
         let episodes = self
             .storage
-            .get_episodes_in_range(
-                &self.config.agent_id,
-                range.start.as_millis(),
-                range.end.as_millis(),
-            )
+            .get_episodes_in_range(&self.config.agent_id, start_millis, end_millis)
             .await
             .map_err(|e| Error::Storage(format!("Failed to get episodes in range: {}", e)))?;
 
-        Ok(episodes.into_iter().filter(|e| e.is_valid()).collect())
+        // Storage filters by whole milliseconds; exclusive bounds need the exact check
+        Ok(episodes
+            .into_iter()
+            .filter(|e| e.is_valid() && range.contains(e.event_time))
+            .collect())
+    }
+
+    /// Get the episodes the agent knew at a past transaction time
+    ///
+    /// Includes episodes invalidated since then, sorted by event time.
+    pub async fn get_episodes_as_of(&self, transaction_time: TransactionTime) -> Result<Vec<Episode>> {
+        let all_episodes = self
+            .storage
+            .get_all_episodes(&self.config.agent_id)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to get episodes: {}", e)))?;
+
+        let mut known: Vec<_> = all_episodes
+            .into_iter()
+            .filter(|e| e.was_valid_at(transaction_time))
+            .collect();
+        known.sort_by_key(|e| e.event_time);
+
+        Ok(known)
     }
 
     /// Get recent episodes (last N)
@@ -657,6 +909,22 @@ impl PersistentAgentMemory {
         Ok(valid.into_iter().take(limit).collect())
     }
 
+    /// Get a page of up to `limit` valid episodes before `before`, newest first
+    pub async fn get_episodes_page(
+        &self,
+        before: Option<EpisodeCursor>,
+        limit: usize,
+    ) -> Result<EpisodePage> {
+        let all_episodes = self
+            .storage
+            .get_all_episodes(&self.config.agent_id)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to get episodes: {}", e)))?;
+
+        let valid = all_episodes.into_iter().filter(|e| e.is_valid()).collect();
+        Ok(EpisodePage::before(valid, before, limit))
+    }
+
     /// Search episodes by content (simple substring match)
     pub async fn search_episodes(&self, query: &str) -> Result<Vec<Episode>> {
         let query_lower = query.to_lowercase();
@@ -706,6 +974,43 @@ impl PersistentAgentMemory {
         }
     }
 
+    /// Mark an episode as consolidated
+    pub async fn mark_consolidated(&self, id: EpisodeId) -> Result<bool> {
+        let episode = self
+            .storage
+            .get_episode(&self.config.agent_id, id)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to get episode: {}", e)))?;
+
+        if let Some(mut ep) = episode {
+            ep.mark_consolidated();
+            self.storage
+                .update_episode(&self.config.agent_id, &ep)
+                .await
+                .map_err(|e| Error::Storage(format!("Failed to update episode: {}", e)))?;
+
+            debug!(
+                "Marked episode {} as consolidated for agent {}",
+                id, self.config.agent_id
+            );
+            Ok(true)
+        } else {
+            Ok(false)
+        }
+    }
+
+    /// Get valid episodes by consolidation status, sorted by event time
+    pub async fn get_episodes_by_consolidation(&self, consolidated: bool) -> Result<Vec<Episode>> {
+        let mut episodes = self
+            .storage
+            .get_episodes_by_consolidation(&self.config.agent_id, consolidated)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to get episodes: {}", e)))?;
+
+        episodes.sort_by_key(|e| e.event_time);
+        Ok(episodes)
+    }
+
     /// Get episode count
     pub async fn episode_count(&self) -> Result<usize> {
         self.storage
@@ -716,10 +1021,10 @@ impl PersistentAgentMemory {
 
     // ========== Memory Operations ==========
 
-    /// Apply relevance decay to all episodes
-    pub async fn apply_decay(&self) -> Result<()> {
-        let decay_rate = MemoryType::Episodic.default_decay_rate();
-
+    /// Apply time-based relevance decay to all episodes
+    ///
+    /// See [`MemoryType::default_decay_rate`](crate::types::MemoryType::default_decay_rate) for typical rates.
+    pub async fn apply_decay(&self, decay_rate: f64) -> Result<()> {
         let all_episodes = self
             .storage
             .get_all_episodes(&self.config.agent_id)
@@ -742,41 +1047,86 @@ impl PersistentAgentMemory {
         Ok(())
     }
 
-    /// Forget low-relevance episodes
-    pub async fn forget(&self) -> Result<usize> {
-        if !self.config.auto_forget {
-            return Ok(0);
+    /// Scale every episode's relevance by `decay_factor`, then evict
+    /// episodes whose relevance is below `min_relevance`
+    ///
+    /// A factor near 1.0 barely changes relevance; a factor near 0.0 is
+    /// aggressive. Eviction happens regardless of `auto_forget`.
+    pub async fn apply_decay_with_threshold(
+        &self,
+        decay_factor: f64,
+        min_relevance: f64,
+    ) -> Result<DecayReport> {
+        let all_episodes = self
+            .storage
+            .get_all_episodes(&self.config.agent_id)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to get episodes: {}", e)))?;
+
+        let mut report = DecayReport::default();
+        for mut episode in all_episodes.into_iter().filter(|e| e.is_valid()) {
+            let changed = episode.relevance.decay_by_factor(decay_factor);
+            if episode.relevance.should_forget(min_relevance) {
+                self.storage
+                    .delete_episode(&self.config.agent_id, episode.id)
+                    .await
+                    .map_err(|e| Error::Storage(format!("Failed to delete episode: {}", e)))?;
+                if self.has_semantic_search() {
+                    self.unindex_episode(episode.id).await?;
+                }
+                report.evicted += 1;
+            } else if changed {
+                self.storage
+                    .update_episode(&self.config.agent_id, &episode)
+                    .await
+                    .map_err(|e| Error::Storage(format!("Failed to update episode: {}", e)))?;
+                report.decayed += 1;
+            }
         }
 
+        info!(
+            "Decayed {} and evicted {} episodes for agent {}",
+            report.decayed, report.evicted, self.config.agent_id
+        );
+
+        Ok(report)
+    }
+
+    /// Forget episodes matching all supplied criteria
+    pub async fn forget(&self, criteria: &ForgetCriteria) -> Result<ForgetReport> {
         let all_episodes = self
             .storage
             .get_all_episodes(&self.config.agent_id)
             .await
             .map_err(|e| Error::Storage(format!("Failed to get episodes: {}", e)))?;
 
-        let min_relevance = self.config.min_relevance;
+        let now = chrono::Utc::now().timestamp_millis();
         let to_forget: Vec<_> = all_episodes
             .into_iter()
-            .filter(|e| e.relevance.should_forget(min_relevance))
+            .filter(|e| e.is_valid() && criteria.matches(e, now))
             .collect();
 
-        let count = to_forget.len();
-
+        let mut report = ForgetReport::default();
         for episode in to_forget {
             self.storage
                 .delete_episode(&self.config.agent_id, episode.id)
                 .await
                 .map_err(|e| Error::Storage(format!("Failed to delete episode: {}", e)))?;
+            if self.has_semantic_search() {
+                self.unindex_episode(episode.id).await?;
+            }
+            report.record(episode.episode_type);
         }
 
-        if count > 0 {
+        if report.total() > 0 {
             info!(
                 "Forgot {} episodes for agent {}",
-                count, self.config.agent_id
+                report.total(),
+                self.config.agent_id
             );
         }
 
-        Ok(count)
+        Ok(report)
     }
 
     /// Clear all episodes
@@ -838,7 +1188,10 @@ impl PersistentAgentMemory {
     }
 
     /// Flush storage to disk
+    ///
+    /// Also saves the vector index, if semantic search is enabled.
     pub async fn flush(&self) -> Result<()> {
+        self.save_vector_index().await?;
         self.storage
             .flush()
             .await
@@ -859,6 +1212,14 @@ impl PersistentAgentMemory {
             .map_err(|e| Error::Internal(format!("Failed to generate embedding: {}", e)))
     }
 
+    /// Text embedded for an episode: its primary and secondary content
+    fn embedding_text(episode: &Episode) -> String {
+        match &episode.content.secondary {
+            Some(secondary) => format!("{} {}", episode.content.primary, secondary),
+            None => episode.content.primary.clone(),
+        }
+    }
+
     /// Index an episode in the vector index
     pub async fn index_episode(&self, episode: &Episode) -> Result<()> {
         let index = self.vector_index.as_ref().ok_or_else(|| {
@@ -866,19 +1227,19 @@ impl PersistentAgentMemory {
         })?;
 
         // Generate embedding for the episode content
-        let text = format!(
-            "{} {}",
-            episode.content.primary,
-            episode.content.secondary.as_deref().unwrap_or("")
-        );
-        let embedding = self.generate_embedding(&text).await?;
+        let embedding = self.generate_embedding(&Self::embedding_text(episode)).await?;
 
         // Add to HNSW index
         let mut index_guard = index.write().map_err(|_| {
             Error::Internal("Failed to acquire vector index lock".to_string())
         })?;
 
-        index_guard.insert(episode.id.to_string(), embedding).map_err(|e| {
+        // Re-indexing an episode replaces its previous embedding
+        let key = episode.id.to_string();
+        index_guard.remove(&key).map_err(|e| {
+            Error::Internal(format!("Failed to remove from vector index: {}", e))
+        })?;
+        index_guard.insert(key, embedding).map_err(|e| {
             Error::Internal(format!("Failed to insert into vector index: {}", e))
         })?;
 
@@ -890,6 +1251,93 @@ impl PersistentAgentMemory {
         Ok(())
     }
 
+    /// Index several episodes in the vector index in a single pass
+    ///
+    /// Embeddings are generated in batches of the provider's maximum batch
+    /// size. When a batch fails, its episodes are embedded one at a time so
+    /// a single bad episode does not keep the rest out of the index; episodes
+    /// that still fail are skipped. Returns the number of episodes indexed,
+    /// or the first error if none could be.
+    pub async fn index_episodes(&self, episodes: &[Episode]) -> Result<usize> {
+        let index = self.vector_index.as_ref().ok_or_else(|| {
+            Error::MemoryOperation("Semantic search is not enabled".to_string())
+        })?;
+        let provider = self.embedding_provider.as_ref().ok_or_else(|| {
+            Error::MemoryOperation("Semantic search is not enabled".to_string())
+        })?;
+        let batch_size = self
+            .semantic_config
+            .as_ref()
+            .map_or(1, |c| c.embedding_config.max_batch_size.max(1));
+
+        let mut embedded = Vec::with_capacity(episodes.len());
+        let mut first_error = None;
+        for chunk in episodes.chunks(batch_size) {
+            let texts: Vec<String> = chunk.iter().map(Self::embedding_text).collect();
+            let texts: Vec<&str> = texts.iter().map(String::as_str).collect();
+            let result = provider.embed_batch(&texts).await.and_then(|embeddings| {
+                if embeddings.len() == texts.len() {
+                    Ok(embeddings)
+                } else {
+                    Err(EmbeddingError::ApiError(format!(
+                        "Expected {} embeddings, got {}",
+                        texts.len(),
+                        embeddings.len()
+                    )))
+                }
+            });
+            match result {
+                Ok(embeddings) => embedded.extend(chunk.iter().zip(embeddings)),
+                Err(e) if chunk.len() > 1 => {
+                    warn!(
+                        "Failed to embed a batch of {} episodes, retrying one at a time: {}",
+                        chunk.len(),
+                        e
+                    );
+                    for (episode, text) in chunk.iter().zip(texts) {
+                        match provider.embed(text).await {
+                            Ok(embedding) => embedded.push((episode, embedding)),
+                            Err(e) => {
+                                warn!("Failed to embed episode {}: {}", episode.id, e);
+                                first_error.get_or_insert(e);
+                            }
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("Failed to embed episode {}: {}", chunk[0].id, e);
+                    first_error.get_or_insert(e);
+                }
+            }
+        }
+        if let (true, Some(e)) = (embedded.is_empty(), first_error) {
+            return Err(Error::Internal(format!("Failed to generate embeddings: {}", e)));
+        }
+
+        let mut index_guard = index.write().map_err(|_| {
+            Error::Internal("Failed to acquire vector index lock".to_string())
+        })?;
+        for (episode, embedding) in &embedded {
+            // Re-indexing an episode replaces its previous embedding
+            let key = episode.id.to_string();
+            index_guard.remove(&key).map_err(|e| {
+                Error::Internal(format!("Failed to remove from vector index: {}", e))
+            })?;
+            index_guard.insert(key, embedding.clone()).map_err(|e| {
+                Error::Internal(format!("Failed to insert into vector index: {}", e))
+            })?;
+        }
+
+        debug!(
+            "Indexed {} of {} episodes for agent {}",
+            embedded.len(),
+            episodes.len(),
+            self.config.agent_id
+        );
+
+        Ok(embedded.len())
+    }
+
     /// Remove an episode from the vector index
     pub async fn unindex_episode(&self, episode_id: EpisodeId) -> Result<bool> {
         let index = self.vector_index.as_ref().ok_or_else(|| {
@@ -908,16 +1356,59 @@ impl PersistentAgentMemory {
     /// Search for semantically similar episodes using a text query
     ///
     /// Returns episodes ranked by semantic similarity to the query.
+    /// `ef_search` overrides the index's configured search breadth for this
+    /// query, trading latency for recall.
     pub async fn semantic_search(
         &self,
         query: &str,
         limit: usize,
+        ef_search: Option<usize>,
+    ) -> Result<Vec<SemanticSearchResult>> {
+        let relevance_weight = self
+            .semantic_config
+            .as_ref()
+            .map_or(0.0, |c| c.relevance_weight);
+        self.semantic_search_with_relevance(query, limit, relevance_weight, ef_search)
+            .await
+    }
+
+    /// Search for semantically similar episodes, blending in episode relevance
+    ///
+    /// Each result is scored `(1 - w) * similarity + w * relevance` for
+    /// relevance weight `w`, so important memories outrank equally similar
+    /// but less relevant ones. A weight of 0.0 ranks by similarity alone.
+    pub async fn semantic_search_with_relevance(
+        &self,
+        query: &str,
+        limit: usize,
+        relevance_weight: f32,
+        ef_search: Option<usize>,
     ) -> Result<Vec<SemanticSearchResult>> {
         // Generate embedding for the query
         let query_embedding = self.generate_embedding(query).await?;
 
-        // Search the HNSW index
-        self.search_by_embedding(&query_embedding, limit).await
+        let relevance_weight = relevance_weight.clamp(0.0, 1.0);
+        if relevance_weight == 0.0 {
+            return self.search_index(&query_embedding, limit, ef_search).await;
+        }
+
+        // Fetch extra candidates so re-ranking can promote relevant episodes
+        let mut results = self
+            .search_index(
+                &query_embedding,
+                limit.saturating_mul(RERANK_CANDIDATES),
+                ef_search,
+            )
+            .await?;
+        for result in &mut results {
+            let relevance = result.episode.relevance.score as f32;
+            result.score =
+                (1.0 - relevance_weight) * result.similarity + relevance_weight * relevance;
+        }
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(limit);
+
+        Ok(results)
     }
 
     /// Search for similar episodes using a pre-computed embedding vector
@@ -925,19 +1416,37 @@ impl PersistentAgentMemory {
         &self,
         embedding: &[f32],
         limit: usize,
+    ) -> Result<Vec<SemanticSearchResult>> {
+        self.search_index(embedding, limit, None).await
+    }
+
+    /// Search the vector index, optionally overriding its `ef_search`
+    async fn search_index(
+        &self,
+        embedding: &[f32],
+        limit: usize,
+        ef_search: Option<usize>,
     ) -> Result<Vec<SemanticSearchResult>> {
         let index = self.vector_index.as_ref().ok_or_else(|| {
             Error::MemoryOperation("Semantic search is not enabled".to_string())
         })?;
 
-        // Search the HNSW index
-        let index_guard = index.read().map_err(|_| {
-            Error::Internal("Failed to acquire vector index lock".to_string())
-        })?;
+        // Search the HNSW index, releasing the lock before fetching episodes
+        let search_results = {
+            let index_guard = index.read().map_err(|_| {
+                Error::Internal("Failed to acquire vector index lock".to_string())
+            })?;
 
-        let search_results = index_guard.search(embedding, limit).map_err(|e| {
-            Error::Internal(format!("Failed to search vector index: {}", e))
-        })?;
+            index_guard.search(embedding, limit, ef_search).map_err(|e| {
+                Error::Internal(format!("Failed to search vector index: {}", e))
+            })?
+        };
+
+        let metric = self
+            .semantic_config
+            .as_ref()
+            .map(|c| c.hnsw_config.metric)
+            .unwrap_or_default();
 
         // Convert search results to SemanticSearchResult
         let mut results = Vec::new();
@@ -956,10 +1465,12 @@ impl PersistentAgentMemory {
                 .map_err(|e| Error::Storage(format!("Failed to get episode: {}", e)))?
             {
                 if episode.is_valid() {
-                    // Convert distance to similarity score
-                    // For cosine distance: distance = 1 - similarity, so similarity = 1 - distance
-                    let score = 1.0 - result.distance;
-                    results.push(SemanticSearchResult { episode, score });
+                    let similarity = metric.score_from_distance(result.distance);
+                    results.push(SemanticSearchResult {
+                        episode,
+                        score: similarity,
+                        similarity,
+                    });
                 }
             }
         }
@@ -980,12 +1491,7 @@ impl PersistentAgentMemory {
             .ok_or_else(|| Error::MemoryOperation("Episode not found".to_string()))?;
 
         // Generate embedding for the episode content
-        let text = format!(
-            "{} {}",
-            episode.content.primary,
-            episode.content.secondary.as_deref().unwrap_or("")
-        );
-        let embedding = self.generate_embedding(&text).await?;
+        let embedding = self.generate_embedding(&Self::embedding_text(&episode)).await?;
 
         // Search for similar episodes (limit + 1 to exclude the source episode)
         let mut results = self.search_by_embedding(&embedding, limit + 1).await?;
@@ -997,7 +1503,7 @@ impl PersistentAgentMemory {
         Ok(results)
     }
 
-    /// Rebuild the vector index from all stored episodes
+    /// Rebuild the vector index from all stored episodes, then save it
     pub async fn rebuild_vector_index(&self) -> Result<usize> {
         let index = self.vector_index.as_ref().ok_or_else(|| {
             Error::MemoryOperation("Semantic search is not enabled".to_string())
@@ -1013,26 +1519,61 @@ impl PersistentAgentMemory {
             })?;
         }
 
-        // Get all episodes
+        // Index all episodes in batches
         let episodes = self.get_all_episodes().await?;
-        let mut indexed_count = 0;
-
-        // Index each episode
-        for episode in episodes {
-            if let Err(e) = self.index_episode(&episode).await {
-                warn!(
-                    "Failed to index episode {} during rebuild: {}",
-                    episode.id, e
-                );
-            } else {
-                indexed_count += 1;
+        let indexed_count = match self.index_episodes(&episodes).await {
+            Ok(count) => count,
+            Err(e) => {
+                warn!("Failed to index episodes during rebuild: {}", e);
+                0
             }
-        }
+        };
 
         info!(
             "Rebuilt vector index for agent {}: {} episodes indexed",
             self.config.agent_id, indexed_count
         );
+        self.save_vector_index().await?;
+
+        Ok(indexed_count)
+    }
+
+    /// Index episodes that are missing from the vector index
+    ///
+    /// Unlike [`rebuild_vector_index`](Self::rebuild_vector_index), episodes
+    /// already in the index are not re-embedded. Returns the number of
+    /// episodes newly indexed.
+    pub async fn index_missing_episodes(&self) -> Result<usize> {
+        let index = self.vector_index.as_ref().ok_or_else(|| {
+            Error::MemoryOperation("Semantic search is not enabled".to_string())
+        })?;
+
+        let episodes = self.get_all_episodes().await?;
+        let missing: Vec<_> = {
+            let index_guard = index.read().map_err(|_| {
+                Error::Internal("Failed to acquire vector index lock".to_string())
+            })?;
+            episodes
+                .into_iter()
+                .filter(|e| e.is_valid() && !index_guard.contains(&e.id.to_string()))
+                .collect()
+        };
+
+        let indexed_count = match self.index_episodes(&missing).await {
+            Ok(count) => count,
+            Err(e) => {
+                warn!("Failed to index missing episodes: {}", e);
+                0
+            }
+        };
+
+        info!(
+            "Indexed {} missing episodes for agent {}",
+            indexed_count, self.config.agent_id
+        );
+        if indexed_count > 0 {
+            self.save_vector_index().await?;
+        }
 
         Ok(indexed_count)
     }
@@ -1050,6 +1591,65 @@ impl PersistentAgentMemory {
         Ok(index_guard.len())
     }
 
+    /// Save the vector index to storage so it survives restarts
+    ///
+    /// Does nothing without semantic search.
+    pub async fn save_vector_index(&self) -> Result<()> {
+        let Some(index) = &self.vector_index else {
+            return Ok(());
+        };
+
+        // Encode under the lock, but release it before writing to storage
+        let bytes = {
+            let index_guard = index.read().map_err(|_| {
+                Error::Internal("Failed to acquire vector index lock".to_string())
+            })?;
+            index_guard.to_saved_bytes().map_err(|e| {
+                Error::Internal(format!("Failed to encode vector index: {}", e))
+            })?
+        };
+
+        self.storage
+            .save_vector_index(&self.config.agent_id, &bytes)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to save vector index: {}", e)))
+    }
+
+    /// Replace the vector index with the one saved in storage
+    ///
+    /// Returns `false`, leaving the index unchanged, when no index was saved.
+    /// Fails if the saved index does not match the configured embedding
+    /// dimension or metric. Episodes stored since the index was saved are
+    /// not in it; [`index_missing_episodes`](Self::index_missing_episodes)
+    /// adds them.
+    pub async fn load_vector_index(&self) -> Result<bool> {
+        let (Some(index), Some(semantic_config)) = (&self.vector_index, &self.semantic_config) else {
+            return Err(Error::MemoryOperation("Semantic search is not enabled".to_string()));
+        };
+
+        let loaded = HnswIndex::load_from(
+            self.storage.as_ref(),
+            &self.config.agent_id,
+            &semantic_config.hnsw_config,
+        )
+        .await
+        .map_err(|e| Error::Storage(format!("Failed to load vector index: {}", e)))?;
+        let Some(loaded) = loaded else {
+            return Ok(false);
+        };
+
+        let mut index_guard = index.write().map_err(|_| {
+            Error::Internal("Failed to acquire vector index lock".to_string())
+        })?;
+        info!(
+            "Loaded vector index for agent {}: {} episodes",
+            self.config.agent_id,
+            loaded.len()
+        );
+        *index_guard = loaded;
+        Ok(true)
+    }
+
     // ========== Hybrid Search Methods ==========
 
     /// Perform hybrid search combining keyword and semantic search
@@ -1094,7 +1694,7 @@ impl PersistentAgentMemory {
         }
 
         // Perform semantic search
-        let semantic_results = self.semantic_search(query, limit * 2).await?;
+        let semantic_results = self.semantic_search(query, limit * 2, None).await?;
 
         // Apply Reciprocal Rank Fusion
         self.reciprocal_rank_fusion(
@@ -1220,6 +1820,8 @@ impl Clone for PersistentAgentMemory {
 mod tests {
     use super::*;
     use crate::embeddings::EmbeddingProviderType;
+    use crate::episode::EpisodeContent;
+    use crate::storage::DatabaseMemoryStorage;
 
     // ==================== Basic AgentMemory Tests ====================
 
@@ -1231,6 +1833,80 @@ mod tests {
 
     // ==================== PersistentAgentMemory Tests ====================
 
+    #[tokio::test]
+    async fn test_vector_index_survives_restart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage: Arc<dyn MemoryStorage> = Arc::new(
+            RocksDbMemoryStorage::open(MemoryStorageConfig::for_testing(temp_dir.path())).unwrap(),
+        );
+        let open = |dimensions: usize| {
+            PersistentAgentMemory::with_storage(MemoryConfig::new("test-agent"), storage.clone())
+                .with_mock_semantic_search(dimensions)
+                .unwrap()
+        };
+
+        let memory = open(64);
+        assert!(!memory.load_vector_index().await.unwrap());
+        for text in ["deploy on friday", "lunch on thursday"] {
+            let episode = Episode::observation("test-agent", text);
+            memory.store_episode(episode.clone()).await.unwrap();
+            memory.index_episode(&episode).await.unwrap();
+        }
+        memory.flush().await.unwrap();
+
+        // An episode stored after the save is picked up without a rebuild
+        let late = Episode::observation("test-agent", "retro on monday");
+        memory.store_episode(late).await.unwrap();
+
+        let restarted = open(64);
+        assert!(restarted.load_vector_index().await.unwrap());
+        assert_eq!(restarted.vector_index_size().unwrap(), 2);
+        assert_eq!(restarted.index_missing_episodes().await.unwrap(), 1);
+        assert_eq!(restarted.vector_index_size().unwrap(), 3);
+
+        // A saved index of another dimension is rejected
+        assert!(open(32).load_vector_index().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_per_agent_storage_directories() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage_config =
+            MemoryStorageConfig::for_testing(temp_dir.path()).per_agent_directories();
+        let open = |agent_id: &str| {
+            PersistentAgentMemory::new(MemoryConfig::new(agent_id), storage_config.clone()).unwrap()
+        };
+
+        {
+            let alice = open("alice");
+            let bob = open("bob");
+            alice
+                .store_episode(Episode::observation("alice", "Alice's secret"))
+                .await
+                .unwrap();
+            bob.store_episode(Episode::observation("bob", "Bob's note"))
+                .await
+                .unwrap();
+        }
+
+        // Each agent's directory holds only its own episodes
+        let alice_path = storage_config.agent_path("alice");
+        let bob_path = storage_config.agent_path("bob");
+        assert_ne!(alice_path, bob_path);
+        {
+            let raw = RocksDbMemoryStorage::open(MemoryStorageConfig::for_testing(&alice_path)).unwrap();
+            let episodes = raw.get_all_episodes("alice").await.unwrap();
+            assert_eq!(episodes.len(), 1);
+            assert_eq!(episodes[0].content.primary, "Alice's secret");
+            assert_eq!(raw.episode_count("bob").await.unwrap(), 0);
+        }
+
+        // Dropping an agent is deleting its directory
+        std::fs::remove_dir_all(&bob_path).unwrap();
+        assert_eq!(open("bob").episode_count().await.unwrap(), 0);
+        assert_eq!(open("alice").episode_count().await.unwrap(), 1);
+    }
+
     #[tokio::test]
     async fn test_persistent_memory_in_memory_backend() {
         let config = MemoryConfig::new("test-agent");
@@ -1250,6 +1926,143 @@ mod tests {
         assert_eq!(retrieved.content.primary, "Hello");
     }
 
+    #[tokio::test]
+    async fn test_persistent_episodes_page_visits_each_episode_once() {
+        let memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"));
+        // Ten episodes share each timestamp, so pages split same-millisecond runs
+        for i in 0..1000 {
+            memory
+                .store_episode(Episode::with_event_time(
+                    "test-agent",
+                    EpisodeType::Observation,
+                    EpisodeContent::new(&format!("Event {}", i)),
+                    EventTime::from_millis(1_700_000_000_000 + i / 10),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        let mut cursor = None;
+        let mut previous: Option<EpisodeCursor> = None;
+        loop {
+            let page = memory.get_episodes_page(cursor, 33).await.unwrap();
+            for episode in &page.episodes {
+                assert!(seen.insert(episode.id), "episode visited twice");
+                let position = EpisodeCursor::at(episode);
+                assert!(previous.is_none_or(|p| position.order() < p.order()));
+                previous = Some(position);
+            }
+            match page.next_cursor {
+                Some(next) => cursor = Some(next),
+                None => break,
+            }
+        }
+        assert_eq!(seen.len(), 1000);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_episodes_page_ignores_newer_inserts() {
+        let memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"));
+        for i in 0..5 {
+            memory
+                .store_episode(Episode::with_event_time(
+                    "test-agent",
+                    EpisodeType::Observation,
+                    EpisodeContent::new(&format!("Event {}", i)),
+                    EventTime::from_millis(1_000 + i),
+                ))
+                .await
+                .unwrap();
+        }
+
+        let first = memory.get_episodes_page(None, 2).await.unwrap();
+        assert_eq!(first.episodes[0].content.primary, "Event 4");
+        memory
+            .store_episode(Episode::with_event_time(
+                "test-agent",
+                EpisodeType::Observation,
+                EpisodeContent::new("Newest"),
+                EventTime::from_millis(2_000),
+            ))
+            .await
+            .unwrap();
+
+        let second = memory.get_episodes_page(first.next_cursor, 2).await.unwrap();
+        let contents: Vec<_> = second.episodes.iter().map(|e| e.content.primary.as_str()).collect();
+        assert_eq!(contents, vec!["Event 2", "Event 1"]);
+
+        let last = memory.get_episodes_page(second.next_cursor, 2).await.unwrap();
+        assert_eq!(last.episodes.len(), 1);
+        assert!(last.next_cursor.is_none());
+
+        let before_time = memory
+            .get_episodes_page(Some(EpisodeCursor::before_time(1_003)), 10)
+            .await
+            .unwrap();
+        assert_eq!(before_time.episodes.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_episodes_by_consolidation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let storage = Arc::new(DatabaseMemoryStorage::new(db.storage().clone()));
+        let memory = PersistentAgentMemory::with_storage(MemoryConfig::new("test-agent"), storage);
+
+        let mut ids = Vec::new();
+        for i in 0..5 {
+            let id = memory
+                .store_episode(Episode::with_event_time(
+                    "test-agent",
+                    EpisodeType::Observation,
+                    EpisodeContent::new(&format!("Event {}", i)),
+                    EventTime::from_millis(1_000 + i),
+                ))
+                .await
+                .unwrap();
+            ids.push(id);
+        }
+
+        for id in [ids[3], ids[1]] {
+            assert!(memory.mark_consolidated(id).await.unwrap());
+        }
+        assert!(!memory.mark_consolidated(EpisodeId::new()).await.unwrap());
+
+        let consolidated = memory.get_episodes_by_consolidation(true).await.unwrap();
+        let consolidated_ids: Vec<_> = consolidated.iter().map(|e| e.id).collect();
+        assert_eq!(consolidated_ids, vec![ids[1], ids[3]]);
+        assert!(consolidated.iter().all(|e| e.consolidated));
+
+        let pending = memory.get_episodes_by_consolidation(false).await.unwrap();
+        let pending_ids: Vec<_> = pending.iter().map(|e| e.id).collect();
+        assert_eq!(pending_ids, vec![ids[0], ids[2], ids[4]]);
+
+        // Invalidated episodes are left out of both lists
+        memory.invalidate_episode(ids[1]).await.unwrap();
+        assert_eq!(memory.get_episodes_by_consolidation(true).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_persistent_decay_with_threshold() {
+        let memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"));
+        for i in 0..3 {
+            memory
+                .store_episode(Episode::observation("test-agent", &format!("Event {}", i)))
+                .await
+                .unwrap();
+        }
+
+        let report = memory.apply_decay_with_threshold(0.99, 0.3).await.unwrap();
+        assert_eq!(report, DecayReport { decayed: 3, evicted: 0 });
+        let stats = memory.get_statistics().await.unwrap();
+        assert!((stats.avg_relevance - 0.99).abs() < 1e-9);
+
+        let report = memory.apply_decay_with_threshold(0.1, 0.3).await.unwrap();
+        assert_eq!(report, DecayReport { decayed: 0, evicted: 3 });
+        assert_eq!(memory.episode_count().await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_persistent_memory_search() {
         let config = MemoryConfig::new("test-agent");
@@ -1312,7 +2125,7 @@ mod tests {
         assert_eq!(memory.embedding_dimensions(), None);
 
         // Should return error when trying to do semantic search
-        let result = memory.semantic_search("test query", 10).await;
+        let result = memory.semantic_search("test query", 10, None).await;
         assert!(result.is_err());
     }
 
@@ -1352,6 +2165,32 @@ mod tests {
         assert_eq!(memory.vector_index_size().unwrap(), 1);
     }
 
+    #[tokio::test]
+    async fn test_reindex_episode() {
+        let memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"))
+            .with_mock_semantic_search(64)
+            .unwrap();
+
+        let episode = Episode::conversation("test-agent", "Tell me about graphs", "Nodes and edges");
+        let other = Episode::observation("test-agent", "It is raining");
+        for ep in [&episode, &other] {
+            memory.store_episode(ep.clone()).await.unwrap();
+            memory.index_episode(ep).await.unwrap();
+        }
+
+        // Indexing an episode again replaces its entry
+        memory.index_episode(&episode).await.unwrap();
+        assert_eq!(memory.vector_index_size().unwrap(), 2);
+
+        // Identical queries get identical scores, and an exact match scores highest
+        let first = memory.semantic_search("Tell me about graphs Nodes and edges", 2, None).await.unwrap();
+        let second = memory.semantic_search("Tell me about graphs Nodes and edges", 2, None).await.unwrap();
+        assert_eq!(first[0].episode.id, episode.id);
+        assert!((first[0].score - 1.0).abs() < 1e-5);
+        let scores = |results: &[SemanticSearchResult]| results.iter().map(|r| r.score).collect::<Vec<_>>();
+        assert_eq!(scores(&first), scores(&second));
+    }
+
     #[tokio::test]
     async fn test_semantic_search_basic() {
         let config = MemoryConfig::new("test-agent");
@@ -1386,11 +2225,79 @@ mod tests {
         memory.index_episode(&ep3).await.unwrap();
 
         // Search for AI-related content
-        let results = memory.semantic_search("artificial intelligence", 3).await.unwrap();
+        let results = memory.semantic_search("artificial intelligence", 3, None).await.unwrap();
         assert!(!results.is_empty());
         assert!(results.len() <= 3);
     }
 
+    #[tokio::test]
+    async fn test_relevance_weighted_semantic_search() {
+        let semantic_config = SemanticSearchConfig::mock(64).with_relevance_weight(0.5);
+        let memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"))
+            .with_semantic_search(semantic_config)
+            .unwrap();
+
+        // Two episodes with identical embeddings but different relevance
+        let mut important = Episode::observation("test-agent", "deploy the release on friday");
+        let mut trivial = Episode::observation("test-agent", "deploy the release on friday");
+        important.relevance.score = 0.9;
+        trivial.relevance.score = 0.2;
+        let unrelated = Episode::observation("test-agent", "the cafeteria serves soup");
+        for episode in [&trivial, &important, &unrelated] {
+            memory.store_episode(episode.clone()).await.unwrap();
+            memory.index_episode(episode).await.unwrap();
+        }
+
+        let results = memory.semantic_search("deploy the release on friday", 2, None).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].episode.id, important.id);
+        assert_eq!(results[1].episode.id, trivial.id);
+        assert!((results[0].similarity - results[1].similarity).abs() < 1e-5);
+        assert!((results[0].score - (0.5 * results[0].similarity + 0.45)).abs() < 1e-5);
+        assert!(results[0].score > results[1].score);
+
+        // Without a relevance weight the score is the similarity alone
+        let results = memory
+            .semantic_search_with_relevance("deploy the release on friday", 2, 0.0, None)
+            .await
+            .unwrap();
+        assert!(results.iter().all(|r| r.score == r.similarity));
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_ranking_is_metric_independent() {
+        // Mock embeddings are normalized, so every metric ranks them alike
+        let episodes: Vec<_> = [
+            "deploy the release on friday",
+            "release notes for the friday deploy",
+            "the cafeteria serves soup",
+            "rollback plan for the release",
+            "team lunch on thursday",
+        ]
+        .iter()
+        .map(|text| Episode::observation("test-agent", text))
+        .collect();
+
+        let mut rankings = Vec::new();
+        for metric in [SimilarityMetric::Cosine, SimilarityMetric::DotProduct, SimilarityMetric::Euclidean] {
+            let memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"))
+                .with_semantic_search(SemanticSearchConfig::mock(64).with_metric(metric))
+                .unwrap();
+            for episode in &episodes {
+                memory.store_episode(episode.clone()).await.unwrap();
+                memory.index_episode(episode).await.unwrap();
+            }
+
+            let results = memory.semantic_search("friday release deploy", episodes.len(), None).await.unwrap();
+            assert_eq!(results.len(), episodes.len());
+            assert!(results.windows(2).all(|w| w[0].similarity >= w[1].similarity));
+            rankings.push(results.iter().map(|r| r.episode.id).collect::<Vec<_>>());
+        }
+
+        assert_eq!(rankings[0], rankings[1]);
+        assert_eq!(rankings[0], rankings[2]);
+    }
+
     #[tokio::test]
     async fn test_search_by_embedding() {
         let config = MemoryConfig::new("test-agent");
@@ -1568,6 +2475,151 @@ mod tests {
         assert_eq!(memory.vector_index_size().unwrap(), 5);
     }
 
+    /// Mock provider that counts the texts it embeds and the requests made
+    struct CountingProvider {
+        inner: crate::embeddings::MockEmbeddingProvider,
+        calls: std::sync::atomic::AtomicUsize,
+        requests: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for CountingProvider {
+        fn dimensions(&self) -> usize {
+            self.inner.dimensions()
+        }
+
+        fn model_name(&self) -> &str {
+            self.inner.model_name()
+        }
+
+        async fn embed(&self, text: &str) -> crate::embeddings::EmbeddingResult<Vec<f32>> {
+            self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.embed(text).await
+        }
+
+        async fn embed_batch(
+            &self,
+            texts: &[&str],
+        ) -> crate::embeddings::EmbeddingResult<Vec<Vec<f32>>> {
+            self.calls.fetch_add(texts.len(), std::sync::atomic::Ordering::SeqCst);
+            self.requests.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+            self.inner.embed_batch(texts).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_missing_episodes() {
+        let provider = Arc::new(CountingProvider {
+            inner: crate::embeddings::MockEmbeddingProvider::new(64),
+            calls: std::sync::atomic::AtomicUsize::new(0),
+            requests: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let mut memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"))
+            .with_mock_semantic_search(64)
+            .unwrap();
+        memory.embedding_provider = Some(provider.clone());
+
+        let mut episodes = Vec::new();
+        for i in 0..5 {
+            let episode = Episode::observation("test-agent", &format!("Event {}", i));
+            memory.store_episode(episode.clone()).await.unwrap();
+            episodes.push(episode);
+        }
+
+        // Partially index the agent
+        for episode in &episodes[..2] {
+            memory.index_episode(episode).await.unwrap();
+        }
+        let calls = || provider.calls.load(std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(calls(), 2);
+
+        // Only the three missing episodes get embedded, in one request
+        let requests = || provider.requests.load(std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(requests(), 2);
+        assert_eq!(memory.index_missing_episodes().await.unwrap(), 3);
+        assert_eq!(calls(), 5);
+        assert_eq!(requests(), 3);
+        assert_eq!(memory.vector_index_size().unwrap(), 5);
+
+        // Nothing left to index
+        assert_eq!(memory.index_missing_episodes().await.unwrap(), 0);
+        assert_eq!(calls(), 5);
+    }
+
+    /// Mock provider that rejects texts mentioning "poison"
+    struct PoisonProvider(crate::embeddings::MockEmbeddingProvider);
+
+    #[async_trait::async_trait]
+    impl EmbeddingProvider for PoisonProvider {
+        fn dimensions(&self) -> usize {
+            self.0.dimensions()
+        }
+
+        fn model_name(&self) -> &str {
+            self.0.model_name()
+        }
+
+        async fn embed(&self, text: &str) -> crate::embeddings::EmbeddingResult<Vec<f32>> {
+            if text.contains("poison") {
+                return Err(EmbeddingError::InvalidInput("poisoned".to_string()));
+            }
+            self.0.embed(text).await
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_episodes_partial_failure() {
+        let mut memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent"))
+            .with_mock_semantic_search(64)
+            .unwrap();
+        memory.embedding_provider = Some(Arc::new(PoisonProvider(
+            crate::embeddings::MockEmbeddingProvider::new(64),
+        )));
+
+        let episodes: Vec<_> = ["alpha", "poison pill", "gamma", "delta"]
+            .iter()
+            .map(|text| Episode::observation("test-agent", text))
+            .collect();
+        memory.store_episodes(&episodes).await.unwrap();
+
+        // The batch fails as a whole, but the good episodes are still indexed
+        assert_eq!(memory.index_episodes(&episodes).await.unwrap(), 3);
+        assert_eq!(memory.vector_index_size().unwrap(), 3);
+        let results = memory.semantic_search("gamma", 1, None).await.unwrap();
+        assert_eq!(results[0].episode.id, episodes[2].id);
+
+        assert_eq!(memory.rebuild_vector_index().await.unwrap(), 3);
+
+        // Nothing could be indexed
+        assert!(memory.index_episodes(&episodes[1..2]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_store_and_index_episodes() {
+        let memory = PersistentAgentMemory::in_memory(MemoryConfig::new("test-agent").max_episodes(4))
+            .with_mock_semantic_search(64)
+            .unwrap();
+        memory
+            .store_episode(Episode::observation("test-agent", "Existing"))
+            .await
+            .unwrap();
+
+        let episodes: Vec<_> = (0..4)
+            .map(|i| Episode::observation("test-agent", &format!("Batch {}", i)))
+            .collect();
+        let ids = memory.store_episodes(&episodes).await.unwrap();
+        assert_eq!(ids, episodes.iter().map(|e| e.id).collect::<Vec<_>>());
+
+        // The existing episode was evicted to stay within the limit
+        assert_eq!(memory.episode_count().await.unwrap(), 4);
+
+        assert_eq!(memory.index_episodes(&episodes).await.unwrap(), 4);
+        assert_eq!(memory.vector_index_size().unwrap(), 4);
+        let results = memory.semantic_search("Batch 2", 1, None).await.unwrap();
+        assert_eq!(results[0].episode.id, episodes[2].id);
+    }
+
     #[tokio::test]
     async fn test_unindex_episode() {
         let config = MemoryConfig::new("test-agent");
@@ -1678,6 +2730,30 @@ mod tests {
         assert!(!retrieved.is_valid());
     }
 
+    #[test]
+    fn test_get_episodes_as_of() {
+        let memory = AgentMemory::for_agent("test-agent");
+
+        let mut early = Episode::observation("test-agent", "Early");
+        early.transaction_time = TransactionTime::from_millis(1_000);
+        early.invalidated_at = Some(TransactionTime::from_millis(3_000));
+        let mut late = Episode::observation("test-agent", "Late");
+        late.transaction_time = TransactionTime::from_millis(2_000);
+        memory.store_episode(early).unwrap();
+        memory.store_episode(late).unwrap();
+
+        assert!(memory.get_episodes_as_of(TransactionTime::from_millis(500)).unwrap().is_empty());
+        assert_eq!(memory.get_episodes_as_of(TransactionTime::from_millis(1_500)).unwrap().len(), 1);
+
+        // The invalidated episode is still visible before its invalidation
+        let known = memory.get_episodes_as_of(TransactionTime::from_millis(2_500)).unwrap();
+        assert_eq!(known.len(), 2);
+
+        let known = memory.get_episodes_as_of(TransactionTime::from_millis(3_000)).unwrap();
+        assert_eq!(known.len(), 1);
+        assert_eq!(known[0].content.primary, "Late");
+    }
+
     #[test]
     fn test_episode_count() {
         let memory = AgentMemory::for_agent("test-agent");
@@ -1694,6 +2770,105 @@ mod tests {
         assert_eq!(memory.episode_count().unwrap(), 2);
     }
 
+    #[test]
+    fn test_apply_decay_with_threshold() {
+        let memory = AgentMemory::for_agent("test-agent");
+        let kept = Episode::observation("test-agent", "Important event");
+        let kept_id = kept.id;
+        memory.store_episode(kept).unwrap();
+        memory
+            .store_episode(Episode::observation("test-agent", "Minor event"))
+            .unwrap();
+
+        // A factor near 1.0 barely changes relevance
+        let report = memory.apply_decay_with_threshold(0.95, 0.3).unwrap();
+        assert_eq!(report, DecayReport { decayed: 2, evicted: 0 });
+        let stats = memory.get_statistics().unwrap();
+        assert!((stats.avg_relevance - 0.95).abs() < 1e-9);
+
+        // Factor 1.0 leaves relevance untouched
+        let report = memory.apply_decay_with_threshold(1.0, 0.3).unwrap();
+        assert_eq!(report, DecayReport::default());
+
+        // Boost one episode so only the other falls below the threshold
+        memory.get_episode(kept_id).unwrap();
+        let report = memory.apply_decay_with_threshold(0.32, 0.31).unwrap();
+        assert_eq!(report, DecayReport { decayed: 1, evicted: 1 });
+        assert_eq!(memory.episode_count().unwrap(), 1);
+
+        // A factor near 0.0 evicts everything
+        let report = memory.apply_decay_with_threshold(0.01, 0.3).unwrap();
+        assert_eq!(report, DecayReport { decayed: 0, evicted: 1 });
+        assert_eq!(memory.episode_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_forget_with_criteria() {
+        let memory = AgentMemory::for_agent("test-agent");
+        let now = chrono::Utc::now().timestamp_millis();
+        for (i, episode_type) in [
+            EpisodeType::Conversation,
+            EpisodeType::Observation,
+            EpisodeType::Observation,
+            EpisodeType::Decision,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            // The first three episodes are two hours old
+            let age = if i < 3 { 7_200_000 } else { 0 };
+            let mut episode = Episode::with_event_time(
+                "test-agent",
+                episode_type,
+                crate::episode::EpisodeContent::new(&format!("Event {}", i)),
+                EventTime::from_millis(now - age),
+            );
+            episode.relevance.score = 0.05;
+            memory.store_episode(episode).unwrap();
+        }
+
+        // Only old, low-relevance observations and conversations are forgotten
+        let criteria = ForgetCriteria::new()
+            .min_relevance(0.1)
+            .max_age(std::time::Duration::from_secs(3600))
+            .episode_types([EpisodeType::Observation, EpisodeType::Conversation]);
+        let report = memory.forget(&criteria).unwrap();
+        assert_eq!(report.total(), 3);
+        assert_eq!(report.by_type[&EpisodeType::Observation], 2);
+        assert_eq!(report.by_type[&EpisodeType::Conversation], 1);
+
+        // The recent decision is preserved despite its low relevance
+        let report = memory
+            .forget(&ForgetCriteria::new().max_age(std::time::Duration::from_secs(3600)))
+            .unwrap();
+        assert_eq!(report.total(), 0);
+        assert_eq!(memory.episode_count().unwrap(), 1);
+
+        assert_eq!(memory.forget(&ForgetCriteria::new()).unwrap().total(), 0);
+        let report = memory.forget(&ForgetCriteria::new().min_relevance(0.1)).unwrap();
+        assert_eq!(report.by_type[&EpisodeType::Decision], 1);
+        assert_eq!(memory.episode_count().unwrap(), 0);
+    }
+
+    #[test]
+    fn test_get_episode_by_id_str() {
+        let memory = AgentMemory::for_agent("test-agent");
+        let episode = Episode::observation("test-agent", "Event");
+        let id = episode.id;
+        memory.store_episode(episode).unwrap();
+
+        let found = memory.get_episode_by_id_str(&id.to_string()).unwrap();
+        assert_eq!(found.unwrap().id, id);
+
+        let absent = EpisodeId::new().to_string();
+        assert!(memory.get_episode_by_id_str(&absent).unwrap().is_none());
+
+        assert!(matches!(
+            memory.get_episode_by_id_str("not-a-uuid"),
+            Err(Error::ValidationError(_))
+        ));
+    }
+
     #[test]
     fn test_clear() {
         let memory = AgentMemory::for_agent("test-agent");
@@ -0,0 +1,241 @@
+//! Circuit breaker for calls to external providers
+//!
+//! After a configurable number of consecutive failures the breaker opens and
+//! calls fail fast instead of waiting on a provider that is down. Once the
+//! open period has elapsed a single probe call is let through (half-open): a
+//! successful probe closes the breaker again, a failed probe re-opens it.
+
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+/// Circuit breaker configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CircuitBreakerConfig {
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+
+    /// Seconds the circuit stays open before a recovery probe is allowed
+    pub open_duration_secs: u64,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            open_duration_secs: 30,
+        }
+    }
+}
+
+/// State of a circuit breaker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitState {
+    /// Calls pass through
+    Closed,
+    /// Calls are rejected without reaching the provider
+    Open,
+    /// A single recovery probe is in flight
+    HalfOpen,
+}
+
+/// Snapshot of a circuit breaker for status reporting
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerStatus {
+    /// Current state
+    pub state: CircuitState,
+
+    /// Consecutive failures recorded
+    pub consecutive_failures: u32,
+
+    /// Consecutive failures that open the circuit
+    pub failure_threshold: u32,
+
+    /// Seconds until a recovery probe is allowed (open circuits only)
+    pub retry_after_secs: Option<u64>,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: u32,
+    /// When the circuit opened, or when the current probe was let through
+    opened_at: Option<Instant>,
+}
+
+/// Circuit breaker guarding calls to an external provider
+#[derive(Debug)]
+pub struct CircuitBreaker {
+    failure_threshold: u32,
+    open_duration: Duration,
+    inner: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    /// Create a closed circuit breaker
+    pub fn new(config: &CircuitBreakerConfig) -> Self {
+        Self::with_open_duration(
+            config.failure_threshold,
+            Duration::from_secs(config.open_duration_secs),
+        )
+    }
+
+    /// Create a closed circuit breaker with an explicit open period
+    pub fn with_open_duration(failure_threshold: u32, open_duration: Duration) -> Self {
+        Self {
+            failure_threshold: failure_threshold.max(1),
+            open_duration,
+            inner: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+            }),
+        }
+    }
+
+    /// Ask permission for a call
+    ///
+    /// Returns the time left until a recovery probe is allowed when the call
+    /// must fail fast. Every permitted call must be followed by
+    /// [`record_success`](Self::record_success) or
+    /// [`record_failure`](Self::record_failure).
+    pub fn try_acquire(&self) -> Result<(), Duration> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state == CircuitState::Closed {
+            return Ok(());
+        }
+
+        // A probe whose outcome was never recorded (e.g. a cancelled call)
+        // is replaced by a new one after another open period
+        let elapsed = inner.opened_at.map(|t| t.elapsed()).unwrap_or_default();
+        if elapsed >= self.open_duration {
+            inner.state = CircuitState::HalfOpen;
+            inner.opened_at = Some(Instant::now());
+            info!("Circuit breaker half-open, sending recovery probe");
+            Ok(())
+        } else {
+            Err(self.open_duration - elapsed)
+        }
+    }
+
+    /// Record a successful call, closing the circuit
+    pub fn record_success(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.state != CircuitState::Closed {
+            info!("Circuit breaker closed after successful probe");
+        }
+        inner.state = CircuitState::Closed;
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+    }
+
+    /// Record a failed call, opening the circuit at the failure threshold
+    pub fn record_failure(&self) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.consecutive_failures = inner.consecutive_failures.saturating_add(1);
+        let trips = inner.state == CircuitState::HalfOpen
+            || inner.consecutive_failures >= self.failure_threshold;
+        if trips {
+            if inner.state != CircuitState::Open {
+                warn!(
+                    "Circuit breaker opened after {} consecutive failures",
+                    inner.consecutive_failures
+                );
+            }
+            inner.state = CircuitState::Open;
+            inner.opened_at = Some(Instant::now());
+        }
+    }
+
+    /// Current state
+    ///
+    /// An open circuit whose open period has elapsed is reported as half-open,
+    /// since the next call will be a recovery probe.
+    pub fn state(&self) -> CircuitState {
+        self.status().state
+    }
+
+    /// Snapshot of the breaker for status reporting
+    pub fn status(&self) -> CircuitBreakerStatus {
+        let inner = self.inner.lock().unwrap();
+        let remaining = inner
+            .opened_at
+            .filter(|_| inner.state == CircuitState::Open)
+            .map(|t| self.open_duration.saturating_sub(t.elapsed()));
+        let state = match remaining {
+            Some(remaining) if remaining.is_zero() => CircuitState::HalfOpen,
+            _ => inner.state,
+        };
+
+        CircuitBreakerStatus {
+            state,
+            consecutive_failures: inner.consecutive_failures,
+            failure_threshold: self.failure_threshold,
+            retry_after_secs: remaining
+                .filter(|r| !r.is_zero())
+                .map(|r| r.as_secs_f64().ceil() as u64),
+        }
+    }
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self::new(&CircuitBreakerConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consecutive_failures_open_circuit() {
+        let breaker = CircuitBreaker::with_open_duration(3, Duration::from_secs(60));
+
+        for _ in 0..2 {
+            assert!(breaker.try_acquire().is_ok());
+            breaker.record_failure();
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // A success resets the failure count
+        breaker.record_success();
+        for _ in 0..3 {
+            assert!(breaker.try_acquire().is_ok());
+            breaker.record_failure();
+        }
+
+        let status = breaker.status();
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.consecutive_failures, 3);
+        assert_eq!(status.retry_after_secs, Some(60));
+        assert!(breaker.try_acquire().is_err());
+    }
+
+    #[test]
+    fn test_half_open_probe() {
+        let breaker = CircuitBreaker::with_open_duration(1, Duration::from_millis(20));
+        breaker.record_failure();
+        assert!(breaker.try_acquire().is_err());
+
+        std::thread::sleep(Duration::from_millis(30));
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // Only one probe is let through; a failed probe re-opens the circuit
+        assert!(breaker.try_acquire().is_ok());
+        assert!(breaker.try_acquire().is_err());
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // A successful probe closes it
+        std::thread::sleep(Duration::from_millis(30));
+        assert!(breaker.try_acquire().is_ok());
+        breaker.record_success();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+        assert_eq!(breaker.status().consecutive_failures, 0);
+        assert!(breaker.try_acquire().is_ok());
+    }
+}
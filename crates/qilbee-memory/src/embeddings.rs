@@ -3,11 +3,13 @@
 //! This module provides abstraction for embedding providers and vector operations
 //! to enable semantic search over agent memories.
 
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerStatus};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
-use tracing::{debug, info, warn};
+use tracing::{debug, info};
 
 /// Errors that can occur during embedding operations
 #[derive(Debug, Error)]
@@ -39,6 +41,30 @@ pub enum EmbeddingError {
     /// Network error
     #[error("Network error: {0}")]
     Network(String),
+
+    /// Circuit breaker is open after repeated provider failures
+    #[error("Provider unavailable, circuit breaker open (retry in {retry_after_secs}s)")]
+    CircuitOpen { retry_after_secs: u64 },
+
+    /// Local model failed to load or run
+    #[error("Model error: {0}")]
+    Model(String),
+}
+
+impl EmbeddingError {
+    /// Whether the error indicates an unhealthy provider (counted by the circuit breaker)
+    pub fn is_provider_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::ApiError(_) | Self::RateLimitExceeded | Self::Network(_)
+        )
+    }
+
+    fn circuit_open(retry_after: Duration) -> Self {
+        Self::CircuitOpen {
+            retry_after_secs: retry_after.as_secs_f64().ceil() as u64,
+        }
+    }
 }
 
 /// Result type for embedding operations
@@ -62,11 +88,22 @@ pub struct EmbeddingConfig {
     /// API base URL (for custom endpoints)
     pub api_base_url: Option<String>,
 
+    /// Path to a local ONNX model (for the local provider)
+    ///
+    /// Either the `.onnx` file or a directory containing `model.onnx`; the
+    /// model's `tokenizer.json` must sit next to it.
+    #[serde(default)]
+    pub model_path: Option<String>,
+
     /// Maximum batch size for embedding requests
     pub max_batch_size: usize,
 
     /// Timeout in seconds for API requests
     pub timeout_secs: u64,
+
+    /// Circuit breaker around provider calls
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
 }
 
 impl Default for EmbeddingConfig {
@@ -77,8 +114,10 @@ impl Default for EmbeddingConfig {
             dimensions: 384,
             api_key: None,
             api_base_url: None,
+            model_path: None,
             max_batch_size: 100,
             timeout_secs: 30,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 }
@@ -92,8 +131,10 @@ impl EmbeddingConfig {
             dimensions: 1536,
             api_key: Some(api_key.to_string()),
             api_base_url: Some("https://api.openai.com/v1".to_string()),
+            model_path: None,
             max_batch_size: 100,
             timeout_secs: 30,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 
@@ -105,8 +146,10 @@ impl EmbeddingConfig {
             dimensions: 3072,
             api_key: Some(api_key.to_string()),
             api_base_url: Some("https://api.openai.com/v1".to_string()),
+            model_path: None,
             max_batch_size: 100,
             timeout_secs: 30,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 
@@ -118,21 +161,28 @@ impl EmbeddingConfig {
             dimensions,
             api_key: None,
             api_base_url: None,
+            model_path: None,
             max_batch_size: 100,
             timeout_secs: 30,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 
-    /// Create config for local embedding server (e.g., Ollama, local transformer)
-    pub fn local(base_url: &str, model: &str, dimensions: usize) -> Self {
+    /// Create config for a local ONNX sentence-transformers model
+    ///
+    /// Defaults match all-MiniLM-L6-v2; the provider reports the dimensions
+    /// of the model actually loaded from `model_path`.
+    pub fn local(model_path: &str) -> Self {
         Self {
             provider: EmbeddingProviderType::Local,
-            model: model.to_string(),
-            dimensions,
+            model: "all-MiniLM-L6-v2".to_string(),
+            dimensions: 384,
             api_key: None,
-            api_base_url: Some(base_url.to_string()),
+            api_base_url: None,
+            model_path: Some(model_path.to_string()),
             max_batch_size: 32,
             timeout_secs: 60,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 }
@@ -142,7 +192,7 @@ impl EmbeddingConfig {
 pub enum EmbeddingProviderType {
     /// OpenAI embeddings API
     OpenAI,
-    /// Local embedding server (e.g., Ollama)
+    /// Local ONNX model (e.g., all-MiniLM), no network required
     Local,
     /// Mock provider for testing
     Mock,
@@ -161,7 +211,17 @@ pub trait EmbeddingProvider: Send + Sync {
     async fn embed(&self, text: &str) -> EmbeddingResult<Vec<f32>>;
 
     /// Generate embeddings for multiple texts (batch)
-    async fn embed_batch(&self, texts: &[String]) -> EmbeddingResult<Vec<Vec<f32>>>;
+    ///
+    /// Returns one embedding per text, in input order. The default
+    /// implementation embeds the texts one at a time; providers whose API
+    /// accepts several inputs per request override it.
+    async fn embed_batch(&self, texts: &[&str]) -> EmbeddingResult<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for text in texts {
+            embeddings.push(self.embed(text).await?);
+        }
+        Ok(embeddings)
+    }
 }
 
 /// Mock embedding provider for testing
@@ -222,21 +282,6 @@ impl EmbeddingProvider for MockEmbeddingProvider {
         debug!("Mock embedding for text of length {}", text.len());
         Ok(self.hash_to_embedding(text))
     }
-
-    async fn embed_batch(&self, texts: &[String]) -> EmbeddingResult<Vec<Vec<f32>>> {
-        if texts.is_empty() {
-            return Ok(vec![]);
-        }
-
-        let embeddings: Vec<Vec<f32>> = texts
-            .iter()
-            .filter(|t| !t.is_empty())
-            .map(|t| self.hash_to_embedding(t))
-            .collect();
-
-        debug!("Mock batch embedding for {} texts", embeddings.len());
-        Ok(embeddings)
-    }
 }
 
 /// OpenAI embedding provider
@@ -284,14 +329,27 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
     }
 
     async fn embed(&self, text: &str) -> EmbeddingResult<Vec<f32>> {
-        let embeddings = self.embed_batch(&[text.to_string()]).await?;
+        let embeddings = self.embed_batch(&[text]).await?;
         embeddings
             .into_iter()
             .next()
             .ok_or_else(|| EmbeddingError::ApiError("Empty response".to_string()))
     }
 
-    async fn embed_batch(&self, texts: &[String]) -> EmbeddingResult<Vec<Vec<f32>>> {
+    /// Embed the texts with one API request per `max_batch_size` inputs
+    async fn embed_batch(&self, texts: &[&str]) -> EmbeddingResult<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for chunk in texts.chunks(self.config.max_batch_size.max(1)) {
+            embeddings.extend(self.request_embeddings(chunk).await?);
+        }
+        Ok(embeddings)
+    }
+}
+
+#[cfg(feature = "openai")]
+impl OpenAIEmbeddingProvider {
+    /// Embed the texts in a single API request
+    async fn request_embeddings(&self, texts: &[&str]) -> EmbeddingResult<Vec<Vec<f32>>> {
         if texts.is_empty() {
             return Ok(vec![]);
         }
@@ -314,7 +372,7 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         #[derive(Serialize)]
         struct EmbeddingRequest<'a> {
             model: &'a str,
-            input: &'a [String],
+            input: &'a [&'a str],
         }
 
         #[derive(Deserialize)]
@@ -363,12 +421,306 @@ impl EmbeddingProvider for OpenAIEmbeddingProvider {
         // Sort by index to ensure correct order
         let mut data = embedding_response.data;
         data.sort_by_key(|d| d.index);
+        if data.len() != texts.len() || data.iter().enumerate().any(|(i, d)| d.index != i) {
+            return Err(EmbeddingError::ApiError(format!(
+                "Expected {} embeddings, got {}",
+                texts.len(),
+                data.len()
+            )));
+        }
 
         Ok(data.into_iter().map(|d| d.embedding).collect())
     }
 }
 
+/// Longest input, in tokens, passed to a local model
+#[cfg(feature = "onnx")]
+const ONNX_MAX_TOKENS: usize = 256;
+
+/// Local embedding provider
+///
+/// Runs a sentence-transformers model exported to ONNX (e.g. all-MiniLM) on
+/// the ONNX runtime, so embeddings need no network access. Token embeddings
+/// are mean-pooled over the attention mask and normalized.
+#[cfg(feature = "onnx")]
+pub struct OnnxEmbeddingProvider {
+    model: Arc<OnnxModel>,
+    model_name: String,
+    max_batch_size: usize,
+}
+
+#[cfg(feature = "onnx")]
+struct OnnxModel {
+    session: ort::session::Session,
+    tokenizer: tokenizers::Tokenizer,
+    output_name: String,
+    token_type_ids: bool,
+    dimensions: usize,
+}
+
+#[cfg(feature = "onnx")]
+fn model_error(e: impl std::fmt::Display) -> EmbeddingError {
+    EmbeddingError::Model(e.to_string())
+}
+
+#[cfg(feature = "onnx")]
+impl OnnxEmbeddingProvider {
+    /// Load the model and tokenizer from `config.model_path`
+    pub fn new(config: EmbeddingConfig) -> EmbeddingResult<Self> {
+        let path = config.model_path.as_deref().ok_or_else(|| {
+            EmbeddingError::NotConfigured("Local provider requires a model path".to_string())
+        })?;
+        let path = std::path::Path::new(path);
+        let (model_file, model_dir) = if path.is_dir() {
+            (path.join("model.onnx"), path.to_path_buf())
+        } else {
+            let dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+            (path.to_path_buf(), dir)
+        };
+
+        let mut tokenizer = tokenizers::Tokenizer::from_file(model_dir.join("tokenizer.json"))
+            .map_err(|e| EmbeddingError::Model(format!("Failed to load tokenizer: {}", e)))?;
+        tokenizer.with_padding(Some(tokenizers::PaddingParams::default()));
+        tokenizer
+            .with_truncation(Some(tokenizers::TruncationParams {
+                max_length: ONNX_MAX_TOKENS,
+                ..Default::default()
+            }))
+            .map_err(model_error)?;
+
+        let session = ort::session::Session::builder()
+            .and_then(|builder| builder.commit_from_file(&model_file))
+            .map_err(|e| {
+                EmbeddingError::Model(format!(
+                    "Failed to load model {}: {}",
+                    model_file.display(),
+                    e
+                ))
+            })?;
+
+        let output = session
+            .outputs
+            .first()
+            .ok_or_else(|| EmbeddingError::Model("Model has no outputs".to_string()))?;
+        let output_name = output.name.clone();
+        let hidden_size = output
+            .output_type
+            .tensor_dimensions()
+            .and_then(|dims| dims.last().copied());
+        let token_type_ids = session.inputs.iter().any(|input| input.name == "token_type_ids");
+
+        let mut model = OnnxModel {
+            session,
+            tokenizer,
+            output_name,
+            token_type_ids,
+            dimensions: 0,
+        };
+
+        // Models exported with a dynamic hidden size are probed instead
+        model.dimensions = match hidden_size {
+            Some(size) if size > 0 => size as usize,
+            _ => model
+                .embed(&["dimension probe".to_string()])?
+                .first()
+                .map_or(0, Vec::len),
+        };
+        if model.dimensions == 0 {
+            return Err(EmbeddingError::Model(
+                "Model produces empty embeddings".to_string(),
+            ));
+        }
+        if model.dimensions != config.dimensions {
+            tracing::warn!(
+                "Model {} produces {} dimensional embeddings, not the configured {}",
+                model_file.display(),
+                model.dimensions,
+                config.dimensions
+            );
+        }
+
+        info!(
+            "Created local embedding provider with model {} ({} dimensions)",
+            config.model, model.dimensions
+        );
+
+        Ok(Self {
+            model: Arc::new(model),
+            model_name: config.model,
+            max_batch_size: config.max_batch_size.max(1),
+        })
+    }
+}
+
+#[cfg(feature = "onnx")]
+impl OnnxModel {
+    /// Embed one batch of texts (blocking)
+    fn embed(&self, texts: &[String]) -> EmbeddingResult<Vec<Vec<f32>>> {
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| EmbeddingError::InvalidInput(e.to_string()))?;
+        let batch = encodings.len();
+        let seq_len = encodings.first().map_or(0, |e| e.len());
+
+        let column = |field: fn(&tokenizers::Encoding) -> &[u32]| -> Vec<i64> {
+            encodings
+                .iter()
+                .flat_map(|e| field(e).iter().map(|&v| v as i64))
+                .collect()
+        };
+        let tensor = |data: Vec<i64>| {
+            ort::value::Tensor::from_array(([batch, seq_len], data))
+                .map(|t| t.into_dyn())
+                .map_err(model_error)
+        };
+
+        let attention_mask = column(tokenizers::Encoding::get_attention_mask);
+        let mut inputs = vec![
+            ("input_ids", tensor(column(tokenizers::Encoding::get_ids))?),
+            ("attention_mask", tensor(attention_mask.clone())?),
+        ];
+        if self.token_type_ids {
+            inputs.push((
+                "token_type_ids",
+                tensor(column(tokenizers::Encoding::get_type_ids))?,
+            ));
+        }
+
+        let outputs = self.session.run(inputs).map_err(model_error)?;
+        let (shape, hidden) = outputs[self.output_name.as_str()]
+            .try_extract_raw_tensor::<f32>()
+            .map_err(model_error)?;
+        if shape.len() != 3 || shape[0] as usize != batch || shape[1] as usize != seq_len {
+            return Err(EmbeddingError::Model(format!(
+                "Unexpected model output shape {:?}",
+                shape
+            )));
+        }
+        let dim = shape[2] as usize;
+
+        // Mean pooling over the tokens that are not padding
+        let mut embeddings = Vec::with_capacity(batch);
+        for b in 0..batch {
+            let mut pooled = vec![0.0f32; dim];
+            let mut tokens = 0.0f32;
+            for t in 0..seq_len {
+                let token = b * seq_len + t;
+                if attention_mask[token] == 0 {
+                    continue;
+                }
+                let offset = token * dim;
+                for (acc, x) in pooled.iter_mut().zip(&hidden[offset..offset + dim]) {
+                    *acc += x;
+                }
+                tokens += 1.0;
+            }
+            if tokens > 0.0 {
+                pooled.iter_mut().for_each(|x| *x /= tokens);
+            }
+            normalize_vector(&mut pooled);
+            embeddings.push(pooled);
+        }
+        Ok(embeddings)
+    }
+}
+
+#[cfg(feature = "onnx")]
+#[async_trait]
+impl EmbeddingProvider for OnnxEmbeddingProvider {
+    fn dimensions(&self) -> usize {
+        self.model.dimensions
+    }
+
+    fn model_name(&self) -> &str {
+        &self.model_name
+    }
+
+    async fn embed(&self, text: &str) -> EmbeddingResult<Vec<f32>> {
+        let embeddings = self.embed_batch(&[text]).await?;
+        embeddings
+            .into_iter()
+            .next()
+            .ok_or_else(|| EmbeddingError::Model("Empty model output".to_string()))
+    }
+
+    async fn embed_batch(&self, texts: &[&str]) -> EmbeddingResult<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(vec![]);
+        }
+
+        // Inference is CPU bound, keep it off the async workers
+        let model = Arc::clone(&self.model);
+        let texts: Vec<String> = texts.iter().map(|t| t.to_string()).collect();
+        let batch_size = self.max_batch_size;
+        tokio::task::spawn_blocking(move || {
+            let mut embeddings = Vec::with_capacity(texts.len());
+            for chunk in texts.chunks(batch_size) {
+                embeddings.extend(model.embed(chunk)?);
+            }
+            Ok(embeddings)
+        })
+        .await
+        .map_err(model_error)?
+    }
+}
+
+/// Embedding provider guarded by a circuit breaker
+///
+/// After repeated provider failures calls fail fast with
+/// [`EmbeddingError::CircuitOpen`] until a recovery probe succeeds.
+pub struct CircuitBreakerEmbeddingProvider {
+    inner: Arc<dyn EmbeddingProvider>,
+    breaker: CircuitBreaker,
+}
+
+impl CircuitBreakerEmbeddingProvider {
+    /// Wrap a provider in a circuit breaker
+    pub fn new(inner: Arc<dyn EmbeddingProvider>, config: &CircuitBreakerConfig) -> Self {
+        Self {
+            inner,
+            breaker: CircuitBreaker::new(config),
+        }
+    }
+
+    /// Current state of the circuit breaker
+    pub fn circuit_breaker_status(&self) -> CircuitBreakerStatus {
+        self.breaker.status()
+    }
+
+    fn record<T>(&self, result: EmbeddingResult<T>) -> EmbeddingResult<T> {
+        match &result {
+            Err(e) if e.is_provider_failure() => self.breaker.record_failure(),
+            _ => self.breaker.record_success(),
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl EmbeddingProvider for CircuitBreakerEmbeddingProvider {
+    fn dimensions(&self) -> usize {
+        self.inner.dimensions()
+    }
+
+    fn model_name(&self) -> &str {
+        self.inner.model_name()
+    }
+
+    async fn embed(&self, text: &str) -> EmbeddingResult<Vec<f32>> {
+        self.breaker.try_acquire().map_err(EmbeddingError::circuit_open)?;
+        self.record(self.inner.embed(text).await)
+    }
+
+    async fn embed_batch(&self, texts: &[&str]) -> EmbeddingResult<Vec<Vec<f32>>> {
+        self.breaker.try_acquire().map_err(EmbeddingError::circuit_open)?;
+        self.record(self.inner.embed_batch(texts).await)
+    }
+}
+
 /// Create an embedding provider from configuration
+///
+/// Providers backed by a remote API are wrapped in a circuit breaker.
 pub fn create_provider(config: EmbeddingConfig) -> EmbeddingResult<Arc<dyn EmbeddingProvider>> {
     match config.provider {
         EmbeddingProviderType::Mock => {
@@ -376,7 +728,9 @@ pub fn create_provider(config: EmbeddingConfig) -> EmbeddingResult<Arc<dyn Embed
         }
         #[cfg(feature = "openai")]
         EmbeddingProviderType::OpenAI => {
-            Ok(Arc::new(OpenAIEmbeddingProvider::new(config)?))
+            let breaker = config.circuit_breaker.clone();
+            let provider = Arc::new(OpenAIEmbeddingProvider::new(config)?);
+            Ok(Arc::new(CircuitBreakerEmbeddingProvider::new(provider, &breaker)))
         }
         #[cfg(not(feature = "openai"))]
         EmbeddingProviderType::OpenAI => {
@@ -384,10 +738,13 @@ pub fn create_provider(config: EmbeddingConfig) -> EmbeddingResult<Arc<dyn Embed
                 "OpenAI feature not enabled. Compile with --features openai".to_string(),
             ))
         }
+        #[cfg(feature = "onnx")]
+        EmbeddingProviderType::Local => Ok(Arc::new(OnnxEmbeddingProvider::new(config)?)),
+        #[cfg(not(feature = "onnx"))]
         EmbeddingProviderType::Local => {
-            // For now, return mock provider; actual local provider would need implementation
-            warn!("Local provider not fully implemented, using mock");
-            Ok(Arc::new(MockEmbeddingProvider::new(config.dimensions)))
+            Err(EmbeddingError::NotConfigured(
+                "ONNX feature not enabled. Compile with --features onnx".to_string(),
+            ))
         }
     }
 }
@@ -415,15 +772,7 @@ pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         return 0.0;
     }
 
-    let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
-    let mag_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
-    let mag_b: f32 = b.iter().map(|x| x * x).sum::<f32>().sqrt();
-
-    if mag_a == 0.0 || mag_b == 0.0 {
-        return 0.0;
-    }
-
-    dot / (mag_a * mag_b)
+    kernels::cosine_similarity(a, b)
 }
 
 /// Calculate dot product between two vectors
@@ -434,7 +783,7 @@ pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
         return 0.0;
     }
 
-    a.iter().zip(b.iter()).map(|(x, y)| x * y).sum()
+    kernels::dot_product(a, b)
 }
 
 /// Calculate Euclidean distance between two vectors
@@ -445,11 +794,7 @@ pub fn euclidean_distance(a: &[f32], b: &[f32]) -> f32 {
         return f32::MAX;
     }
 
-    a.iter()
-        .zip(b.iter())
-        .map(|(x, y)| (x - y).powi(2))
-        .sum::<f32>()
-        .sqrt()
+    kernels::euclidean_distance_squared(a, b).sqrt()
 }
 
 /// Calculate squared Euclidean distance (faster, avoids sqrt)
@@ -458,10 +803,113 @@ pub fn euclidean_distance_squared(a: &[f32], b: &[f32]) -> f32 {
         return f32::MAX;
     }
 
-    a.iter()
-        .zip(b.iter())
-        .map(|(x, y)| (x - y).powi(2))
-        .sum()
+    kernels::euclidean_distance_squared(a, b)
+}
+
+#[cfg(not(feature = "simd"))]
+use scalar as kernels;
+#[cfg(feature = "simd")]
+use simd as kernels;
+
+/// Scalar similarity kernels for vectors of equal length
+///
+/// The similarity functions use these unless the `simd` feature is
+/// enabled; they stay available as the reference the SIMD versions are
+/// tested and benchmarked against.
+pub mod scalar {
+    /// Dot product
+    pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    /// Cosine similarity, 0 if either vector is zero
+    pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+        let dot = dot_product(a, b);
+        let mag_a = dot_product(a, a).sqrt();
+        let mag_b = dot_product(b, b).sqrt();
+
+        if mag_a == 0.0 || mag_b == 0.0 {
+            return 0.0;
+        }
+
+        dot / (mag_a * mag_b)
+    }
+
+    /// Squared Euclidean distance
+    pub fn euclidean_distance_squared(a: &[f32], b: &[f32]) -> f32 {
+        a.iter().zip(b).map(|(x, y)| (x - y) * (x - y)).sum()
+    }
+}
+
+/// SIMD similarity kernels for vectors of equal length
+///
+/// Process eight lanes at a time, finishing any remainder with scalar code.
+/// Results differ from [`scalar`] only by floating point rounding.
+#[cfg(feature = "simd")]
+pub mod simd {
+    use wide::f32x8;
+
+    const LANES: usize = 8;
+
+    fn load(chunk: &[f32]) -> f32x8 {
+        let mut lanes = [0.0; LANES];
+        lanes.copy_from_slice(chunk);
+        f32x8::from(lanes)
+    }
+
+    /// Dot product
+    pub fn dot_product(a: &[f32], b: &[f32]) -> f32 {
+        let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+        let tail = super::scalar::dot_product(a_chunks.remainder(), b_chunks.remainder());
+
+        let mut sum = f32x8::ZERO;
+        for (x, y) in a_chunks.zip(b_chunks) {
+            sum = load(x).mul_add(load(y), sum);
+        }
+        sum.reduce_add() + tail
+    }
+
+    /// Cosine similarity, 0 if either vector is zero
+    ///
+    /// Computes the dot product and both magnitudes in a single pass.
+    pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+        let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+        let (a_tail, b_tail) = (a_chunks.remainder(), b_chunks.remainder());
+
+        let (mut dot, mut norm_a, mut norm_b) = (f32x8::ZERO, f32x8::ZERO, f32x8::ZERO);
+        for (x, y) in a_chunks.zip(b_chunks) {
+            let (x, y) = (load(x), load(y));
+            dot = x.mul_add(y, dot);
+            norm_a = x.mul_add(x, norm_a);
+            norm_b = y.mul_add(y, norm_b);
+        }
+
+        let dot = dot.reduce_add() + super::scalar::dot_product(a_tail, b_tail);
+        let mag_a = (norm_a.reduce_add() + super::scalar::dot_product(a_tail, a_tail)).sqrt();
+        let mag_b = (norm_b.reduce_add() + super::scalar::dot_product(b_tail, b_tail)).sqrt();
+
+        if mag_a == 0.0 || mag_b == 0.0 {
+            return 0.0;
+        }
+
+        dot / (mag_a * mag_b)
+    }
+
+    /// Squared Euclidean distance
+    pub fn euclidean_distance_squared(a: &[f32], b: &[f32]) -> f32 {
+        let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+        let tail = super::scalar::euclidean_distance_squared(
+            a_chunks.remainder(),
+            b_chunks.remainder(),
+        );
+
+        let mut sum = f32x8::ZERO;
+        for (x, y) in a_chunks.zip(b_chunks) {
+            let diff = load(x) - load(y);
+            sum = diff.mul_add(diff, sum);
+        }
+        sum.reduce_add() + tail
+    }
 }
 
 /// Similarity metric types
@@ -481,6 +929,24 @@ impl Default for SimilarityMetric {
     }
 }
 
+impl SimilarityMetric {
+    /// Convert a vector index distance under this metric into a similarity score
+    ///
+    /// Matches [`similarity`] for the same vectors, and always decreases as
+    /// distance grows, so ranking by score is ranking by distance:
+    /// - `Cosine`: `1 - distance`, the cosine similarity (-1.0 to 1.0)
+    /// - `DotProduct`: `-distance`, the dot product (unbounded; equals cosine
+    ///   similarity for normalized vectors)
+    /// - `Euclidean`: `1 / (1 + distance)` (0.0 to 1.0)
+    pub fn score_from_distance(&self, distance: f32) -> f32 {
+        match self {
+            SimilarityMetric::Cosine => 1.0 - distance,
+            SimilarityMetric::DotProduct => -distance,
+            SimilarityMetric::Euclidean => 1.0 / (1.0 + distance),
+        }
+    }
+}
+
 /// Calculate similarity between two vectors using the specified metric
 ///
 /// Returns a value where higher = more similar.
@@ -585,6 +1051,52 @@ mod tests {
         assert!((euclidean_distance(&a, &b) - 5.0).abs() < 0.0001);
     }
 
+    #[test]
+    fn test_score_from_distance_matches_similarity() {
+        let a = vec![0.6, 0.8, 0.0];
+        let b = vec![0.0, 0.6, 0.8];
+
+        let cosine = SimilarityMetric::Cosine.score_from_distance(1.0 - cosine_similarity(&a, &b));
+        assert!((cosine - similarity(&a, &b, SimilarityMetric::Cosine)).abs() < 1e-6);
+
+        let dot = SimilarityMetric::DotProduct.score_from_distance(-dot_product(&a, &b));
+        assert!((dot - similarity(&a, &b, SimilarityMetric::DotProduct)).abs() < 1e-6);
+
+        let euclidean = SimilarityMetric::Euclidean.score_from_distance(euclidean_distance(&a, &b));
+        assert!((euclidean - similarity(&a, &b, SimilarityMetric::Euclidean)).abs() < 1e-6);
+
+        for metric in [SimilarityMetric::Cosine, SimilarityMetric::DotProduct, SimilarityMetric::Euclidean] {
+            assert!(metric.score_from_distance(0.1) > metric.score_from_distance(0.5));
+        }
+    }
+
+    #[test]
+    fn test_similarity_matches_scalar() {
+        use qilbee_core::dataset::random_vectors;
+
+        // Lengths cover empty, remainder-only, exact and mixed lane counts
+        for (seed, dim) in [0usize, 3, 8, 13, 384, 1536].into_iter().enumerate() {
+            let vectors = random_vectors(seed as u64, 20, dim);
+            for pair in vectors.chunks_exact(2) {
+                let (a, b) = (&pair[0], &pair[1]);
+                // Rounding error grows with the number and size of the terms
+                let scale: f32 = a.iter().zip(b).map(|(x, y)| (x * y).abs() + (x - y).powi(2)).sum();
+                let tolerance = f32::EPSILON * (dim as f32 + 1.0) * (scale + 1.0);
+
+                let close = |fast: f32, reference: f32| (fast - reference).abs() <= tolerance;
+                assert!(close(dot_product(a, b), scalar::dot_product(a, b)));
+                assert!(close(
+                    euclidean_distance_squared(a, b),
+                    scalar::euclidean_distance_squared(a, b)
+                ));
+                assert!(close(cosine_similarity(a, b), scalar::cosine_similarity(a, b)));
+            }
+        }
+
+        let zero = vec![0.0; 20];
+        assert_eq!(cosine_similarity(&zero, &zero), 0.0);
+    }
+
     #[test]
     fn test_normalize_vector() {
         let mut v = vec![3.0, 4.0];
@@ -613,18 +1125,67 @@ mod tests {
     async fn test_mock_provider_batch() {
         let provider = MockEmbeddingProvider::new(384);
 
-        let texts = vec![
-            "First text".to_string(),
-            "Second text".to_string(),
-            "Third text".to_string(),
-        ];
+        let texts = ["First text", "Second text", "Third text"];
 
         let embeddings = provider.embed_batch(&texts).await.unwrap();
         assert_eq!(embeddings.len(), 3);
 
-        for emb in &embeddings {
+        // Embeddings come back in input order
+        for (text, emb) in texts.iter().zip(&embeddings) {
             assert_eq!(emb.len(), 384);
+            assert_eq!(emb, &provider.embed(text).await.unwrap());
         }
+
+        assert!(provider.embed_batch(&[]).await.unwrap().is_empty());
+        assert!(matches!(
+            provider.embed_batch(&["First text", ""]).await,
+            Err(EmbeddingError::InvalidInput(_))
+        ));
+    }
+
+    /// Provider whose API is unreachable
+    struct UnreachableProvider;
+
+    #[async_trait]
+    impl EmbeddingProvider for UnreachableProvider {
+        fn dimensions(&self) -> usize {
+            8
+        }
+
+        fn model_name(&self) -> &str {
+            "unreachable"
+        }
+
+        async fn embed(&self, _text: &str) -> EmbeddingResult<Vec<f32>> {
+            Err(EmbeddingError::Network("connection refused".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_circuit_breaker_provider() {
+        use crate::circuit_breaker::CircuitState;
+
+        let config = CircuitBreakerConfig {
+            failure_threshold: 2,
+            open_duration_secs: 60,
+        };
+        let provider = CircuitBreakerEmbeddingProvider::new(Arc::new(UnreachableProvider), &config);
+
+        for _ in 0..2 {
+            assert!(matches!(provider.embed("text").await, Err(EmbeddingError::Network(_))));
+        }
+        assert_eq!(provider.circuit_breaker_status().state, CircuitState::Open);
+        assert!(matches!(
+            provider.embed_batch(&["text"]).await,
+            Err(EmbeddingError::CircuitOpen { retry_after_secs: 60 })
+        ));
+
+        // Input errors don't count as provider failures
+        let mock = CircuitBreakerEmbeddingProvider::new(Arc::new(MockEmbeddingProvider::new(8)), &config);
+        for _ in 0..3 {
+            assert!(matches!(mock.embed("").await, Err(EmbeddingError::InvalidInput(_))));
+        }
+        assert_eq!(mock.circuit_breaker_status().state, CircuitState::Closed);
     }
 
     #[test]
@@ -658,6 +1219,43 @@ mod tests {
         assert_eq!(config.dimensions, 384);
     }
 
+    #[test]
+    fn test_embedding_config_local() {
+        let config = EmbeddingConfig::local("/models/all-MiniLM-L6-v2");
+        assert_eq!(config.provider, EmbeddingProviderType::Local);
+        assert_eq!(config.model_path.as_deref(), Some("/models/all-MiniLM-L6-v2"));
+        assert_eq!(config.dimensions, 384);
+
+        // Configs saved before the field existed still load
+        let mut json = serde_json::to_value(EmbeddingConfig::mock(8)).unwrap();
+        json.as_object_mut().unwrap().remove("model_path");
+        let config: EmbeddingConfig = serde_json::from_value(json).unwrap();
+        assert!(config.model_path.is_none());
+    }
+
+    #[cfg(not(feature = "onnx"))]
+    #[test]
+    fn test_local_provider_requires_onnx_feature() {
+        let result = create_provider(EmbeddingConfig::local("/models/all-MiniLM-L6-v2"));
+        assert!(matches!(result, Err(EmbeddingError::NotConfigured(_))));
+    }
+
+    #[cfg(feature = "onnx")]
+    #[test]
+    fn test_local_provider_missing_model() {
+        let mut config = EmbeddingConfig::local("/nonexistent/model.onnx");
+        assert!(matches!(
+            create_provider(config.clone()),
+            Err(EmbeddingError::Model(_))
+        ));
+
+        config.model_path = None;
+        assert!(matches!(
+            create_provider(config),
+            Err(EmbeddingError::NotConfigured(_))
+        ));
+    }
+
     #[test]
     fn test_embedding_config_openai() {
         let config = EmbeddingConfig::openai_small("test-key");
@@ -76,7 +76,7 @@ impl std::fmt::Display for EpisodeId {
 }
 
 /// Type of episode
-#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum EpisodeType {
     /// User-agent conversation
     Conversation,
@@ -209,6 +209,11 @@ impl Episode {
         self.invalidated_at.is_none()
     }
 
+    /// Check if this episode was recorded and not yet invalidated at a transaction time
+    pub fn was_valid_at(&self, at: TransactionTime) -> bool {
+        self.transaction_time <= at && self.invalidated_at.is_none_or(|invalidated| invalidated > at)
+    }
+
     /// Invalidate this episode
     pub fn invalidate(&mut self) {
         self.invalidated_at = Some(TransactionTime::now());
@@ -219,6 +224,16 @@ impl Episode {
         self.relevance.access();
     }
 
+    /// Number of times this episode has been accessed
+    pub fn access_count(&self) -> u32 {
+        self.relevance.access_count
+    }
+
+    /// When this episode was last accessed (millis since epoch)
+    pub fn last_accessed(&self) -> i64 {
+        self.relevance.last_accessed
+    }
+
     /// Mark as consolidated
     pub fn mark_consolidated(&mut self) {
         self.consolidated = true;
@@ -351,13 +366,26 @@ mod tests {
         assert!(!episode.is_valid());
     }
 
+    #[test]
+    fn test_episode_was_valid_at() {
+        let mut episode = Episode::observation("agent-1", "User logged in");
+        let recorded = episode.transaction_time;
+        episode.invalidated_at = Some(TransactionTime::from_millis(recorded.as_millis() + 1000));
+
+        assert!(!episode.was_valid_at(TransactionTime::from_millis(recorded.as_millis() - 1)));
+        assert!(episode.was_valid_at(recorded));
+        assert!(episode.was_valid_at(TransactionTime::from_millis(recorded.as_millis() + 999)));
+        assert!(!episode.was_valid_at(TransactionTime::from_millis(recorded.as_millis() + 1000)));
+    }
+
     #[test]
     fn test_episode_access() {
         let mut episode = Episode::observation("agent-1", "Event");
         let initial_count = episode.relevance.access_count;
 
         episode.access();
-        assert_eq!(episode.relevance.access_count, initial_count + 1);
+        assert_eq!(episode.access_count(), initial_count + 1);
+        assert_eq!(episode.last_accessed(), episode.relevance.last_accessed);
     }
 
     #[test]
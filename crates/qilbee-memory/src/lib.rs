@@ -19,6 +19,7 @@
 //! - Write-ahead logging for durability
 
 pub mod agent;
+pub mod circuit_breaker;
 pub mod consolidation;
 pub mod embeddings;
 pub mod episode;
@@ -28,8 +29,11 @@ pub mod types;
 pub mod vector_index;
 
 pub use agent::{
-    AgentMemory, HybridSearchResult, MemoryStatistics, PersistentAgentMemory, SemanticSearchConfig,
-    SemanticSearchResult,
+    AgentMemory, DecayReport, EpisodePage, ForgetReport, HybridSearchResult, MemoryStatistics,
+    PersistentAgentMemory, SemanticSearchConfig, SemanticSearchResult,
+};
+pub use circuit_breaker::{
+    CircuitBreaker, CircuitBreakerConfig, CircuitBreakerStatus, CircuitState,
 };
 pub use consolidation::{
     ConsolidationConfig, ConsolidationResult, ConsolidationService, ConsolidationStrategy,
@@ -37,9 +41,9 @@ pub use consolidation::{
 };
 pub use embeddings::{
     cosine_similarity, create_provider as create_embedding_provider, dot_product,
-    euclidean_distance, find_top_k, normalize_vector, similarity, EmbeddingConfig, EmbeddingError,
-    EmbeddingProvider, EmbeddingProviderType, EmbeddingResult, MockEmbeddingProvider,
-    SimilarityMetric, SimilarityResult,
+    euclidean_distance, find_top_k, normalize_vector, similarity, CircuitBreakerEmbeddingProvider,
+    EmbeddingConfig, EmbeddingError, EmbeddingProvider, EmbeddingProviderType, EmbeddingResult,
+    MockEmbeddingProvider, SimilarityMetric, SimilarityResult,
 };
 pub use episode::{Episode, EpisodeContent, EpisodeType};
 pub use llm::{
@@ -47,6 +51,9 @@ pub use llm::{
     LLMProviderType, LLMResponse, LLMResult, LLMService, LLMStatus, MessageRole, MockLLMProvider,
     TokenUsage,
 };
-pub use storage::{InMemoryStorage, MemoryStorage, MemoryStorageConfig, RocksDbMemoryStorage};
-pub use types::{MemoryConfig, MemoryType};
+pub use storage::{
+    AgentStorageUsage, DatabaseMemoryStorage, EpisodeCursor, InMemoryStorage, MemoryStorage,
+    MemoryStorageConfig, RocksDbMemoryStorage,
+};
+pub use types::{ForgetCriteria, MemoryConfig, MemoryType};
 pub use vector_index::{HnswConfig, HnswError, HnswIndex, HnswResult, SearchResult};
@@ -3,9 +3,11 @@
 //! This module provides abstraction for LLM providers to enable
 //! memory consolidation strategies like summarization and fact extraction.
 
+use crate::circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakerStatus};
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 use tracing::{debug, info, warn};
 
@@ -32,6 +34,10 @@ pub enum LLMError {
     #[error("Network error: {0}")]
     Network(String),
 
+    /// Circuit breaker is open after repeated provider failures
+    #[error("Provider unavailable, circuit breaker open (retry in {retry_after_secs}s)")]
+    CircuitOpen { retry_after_secs: u64 },
+
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(String),
@@ -41,6 +47,22 @@ pub enum LLMError {
     ParseError(String),
 }
 
+impl LLMError {
+    /// Whether the error indicates an unhealthy provider (counted by the circuit breaker)
+    pub fn is_provider_failure(&self) -> bool {
+        matches!(
+            self,
+            Self::ApiError(_) | Self::RateLimitExceeded | Self::Network(_) | Self::ParseError(_)
+        )
+    }
+
+    fn circuit_open(retry_after: Duration) -> Self {
+        Self::CircuitOpen {
+            retry_after_secs: retry_after.as_secs_f64().ceil() as u64,
+        }
+    }
+}
+
 /// Result type for LLM operations
 pub type LLMResult<T> = Result<T, LLMError>;
 
@@ -67,6 +89,10 @@ pub struct LLMConfig {
 
     /// Timeout in seconds for API requests
     pub timeout_secs: u64,
+
+    /// Circuit breaker around provider calls
+    #[serde(default)]
+    pub circuit_breaker: CircuitBreakerConfig,
 }
 
 impl Default for LLMConfig {
@@ -79,6 +105,7 @@ impl Default for LLMConfig {
             max_tokens: 1024,
             temperature: 0.3,
             timeout_secs: 60,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 }
@@ -94,6 +121,7 @@ impl LLMConfig {
             max_tokens: 1024,
             temperature: 0.3,
             timeout_secs: 60,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 
@@ -107,6 +135,7 @@ impl LLMConfig {
             max_tokens: 2048,
             temperature: 0.3,
             timeout_secs: 120,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 
@@ -120,6 +149,7 @@ impl LLMConfig {
             max_tokens: 1024,
             temperature: 0.3,
             timeout_secs: 10,
+            circuit_breaker: CircuitBreakerConfig::default(),
         }
     }
 }
@@ -457,9 +487,12 @@ pub fn create_provider(config: LLMConfig) -> LLMResult<Arc<dyn LLMProvider>> {
 /// Runtime-configurable LLM service
 ///
 /// Allows changing LLM configuration at runtime without server restart.
+/// Provider calls go through a circuit breaker, so a provider that keeps
+/// failing is rejected fast until a recovery probe succeeds.
 pub struct LLMService {
     provider: tokio::sync::RwLock<Arc<dyn LLMProvider>>,
     config: tokio::sync::RwLock<LLMConfig>,
+    breaker: tokio::sync::RwLock<Arc<CircuitBreaker>>,
 }
 
 impl LLMService {
@@ -467,30 +500,37 @@ impl LLMService {
     pub fn new(config: LLMConfig) -> LLMResult<Self> {
         let provider = create_provider(config.clone())?;
         info!("Created LLM service with {} provider", config.model);
-        Ok(Self {
-            provider: tokio::sync::RwLock::new(provider),
-            config: tokio::sync::RwLock::new(config),
-        })
+        Ok(Self::with_provider(config, provider))
     }
 
     /// Create a new LLM service with mock provider (for testing)
     pub fn mock() -> Self {
-        let config = LLMConfig::mock();
+        Self::with_provider(LLMConfig::mock(), Arc::new(MockLLMProvider::new()))
+    }
+
+    /// Create a new LLM service around an existing provider
+    pub fn with_provider(config: LLMConfig, provider: Arc<dyn LLMProvider>) -> Self {
+        let breaker = CircuitBreaker::new(&config.circuit_breaker);
         Self {
-            provider: tokio::sync::RwLock::new(Arc::new(MockLLMProvider::new())),
+            provider: tokio::sync::RwLock::new(provider),
             config: tokio::sync::RwLock::new(config),
+            breaker: tokio::sync::RwLock::new(Arc::new(breaker)),
         }
     }
 
     /// Update the LLM configuration at runtime
+    ///
+    /// The circuit breaker starts closed for the new provider.
     pub async fn update_config(&self, new_config: LLMConfig) -> LLMResult<()> {
         let new_provider = create_provider(new_config.clone())?;
 
         let mut config = self.config.write().await;
         let mut provider = self.provider.write().await;
+        let mut breaker = self.breaker.write().await;
 
         *config = new_config.clone();
         *provider = new_provider;
+        *breaker = Arc::new(CircuitBreaker::new(&new_config.circuit_breaker));
 
         info!("Updated LLM service to use {} provider", new_config.model);
         Ok(())
@@ -512,16 +552,21 @@ impl LLMService {
         config.provider != LLMProviderType::Mock && config.api_key.is_some()
     }
 
+    /// Current state of the circuit breaker around the provider
+    pub async fn circuit_breaker_status(&self) -> CircuitBreakerStatus {
+        self.breaker.read().await.status()
+    }
+
     /// Generate a chat completion
     pub async fn chat(&self, messages: &[ChatMessage]) -> LLMResult<LLMResponse> {
-        let provider = self.provider.read().await;
-        provider.chat(messages).await
+        let (provider, breaker) = self.acquire().await?;
+        Self::record(&breaker, provider.chat(messages).await)
     }
 
     /// Generate a completion for a single prompt
     pub async fn complete(&self, prompt: &str) -> LLMResult<String> {
-        let provider = self.provider.read().await;
-        provider.complete(prompt).await
+        let (provider, breaker) = self.acquire().await?;
+        Self::record(&breaker, provider.complete(prompt).await)
     }
 
     /// Generate a completion with a system prompt
@@ -530,8 +575,27 @@ impl LLMService {
         system_prompt: &str,
         user_prompt: &str,
     ) -> LLMResult<String> {
-        let provider = self.provider.read().await;
-        provider.complete_with_system(system_prompt, user_prompt).await
+        let (provider, breaker) = self.acquire().await?;
+        Self::record(
+            &breaker,
+            provider.complete_with_system(system_prompt, user_prompt).await,
+        )
+    }
+
+    /// Get the provider for a call, failing fast while the circuit is open
+    async fn acquire(&self) -> LLMResult<(Arc<dyn LLMProvider>, Arc<CircuitBreaker>)> {
+        let breaker = self.breaker.read().await.clone();
+        breaker.try_acquire().map_err(LLMError::circuit_open)?;
+        Ok((self.provider.read().await.clone(), breaker))
+    }
+
+    /// Record the outcome of a provider call with the circuit breaker
+    fn record<T>(breaker: &CircuitBreaker, result: LLMResult<T>) -> LLMResult<T> {
+        match &result {
+            Err(e) if e.is_provider_failure() => breaker.record_failure(),
+            _ => breaker.record_success(),
+        }
+        result
     }
 }
 
@@ -682,6 +746,72 @@ mod tests {
         assert_eq!(config.model, "mock-llm-v2");
     }
 
+    /// Provider that fails until told to recover
+    struct FlakyProvider {
+        healthy: std::sync::atomic::AtomicBool,
+        calls: std::sync::atomic::AtomicUsize,
+    }
+
+    #[async_trait]
+    impl LLMProvider for FlakyProvider {
+        fn model_name(&self) -> &str {
+            "flaky"
+        }
+
+        async fn chat(&self, _messages: &[ChatMessage]) -> LLMResult<LLMResponse> {
+            use std::sync::atomic::Ordering;
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            if !self.healthy.load(Ordering::SeqCst) {
+                return Err(LLMError::Network("connection refused".to_string()));
+            }
+            Ok(LLMResponse {
+                content: "ok".to_string(),
+                model: "flaky".to_string(),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_llm_service_circuit_breaker() {
+        use crate::circuit_breaker::CircuitState;
+        use std::sync::atomic::Ordering;
+
+        let provider = Arc::new(FlakyProvider {
+            healthy: std::sync::atomic::AtomicBool::new(false),
+            calls: std::sync::atomic::AtomicUsize::new(0),
+        });
+        let mut config = LLMConfig::mock();
+        config.circuit_breaker = CircuitBreakerConfig {
+            failure_threshold: 3,
+            open_duration_secs: 60,
+        };
+        let service = LLMService::with_provider(config.clone(), provider.clone());
+
+        // Consecutive failures trip the breaker
+        for _ in 0..3 {
+            assert!(matches!(service.complete("hi").await, Err(LLMError::Network(_))));
+        }
+        let status = service.circuit_breaker_status().await;
+        assert_eq!(status.state, CircuitState::Open);
+        assert_eq!(status.consecutive_failures, 3);
+
+        // Further calls fail fast without reaching the provider
+        assert!(matches!(service.complete("hi").await, Err(LLMError::CircuitOpen { .. })));
+        assert_eq!(provider.calls.load(Ordering::SeqCst), 3);
+
+        // Once the open period has elapsed, a successful probe closes the breaker
+        config.circuit_breaker.open_duration_secs = 0;
+        let service = LLMService::with_provider(config, provider.clone());
+        for _ in 0..3 {
+            assert!(service.complete("hi").await.is_err());
+        }
+        assert_eq!(service.circuit_breaker_status().await.state, CircuitState::HalfOpen);
+        provider.healthy.store(true, Ordering::SeqCst);
+        assert_eq!(service.complete("hi").await.unwrap(), "ok");
+        assert_eq!(service.circuit_breaker_status().await.state, CircuitState::Closed);
+    }
+
     #[test]
     fn test_llm_status_from_config() {
         let config = LLMConfig::mock();
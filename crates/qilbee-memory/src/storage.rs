@@ -5,9 +5,11 @@
 use crate::episode::{Episode, EpisodeId};
 use async_trait::async_trait;
 use qilbee_core::{Error, Result};
+use qilbee_storage::StorageEngine;
+use qilbee_storage::keys::{push_segment, read_segment};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
@@ -31,6 +33,10 @@ pub struct MemoryStorageConfig {
 
     /// Cache size for frequently accessed episodes (in bytes)
     pub cache_size: usize,
+
+    /// Store each agent's memory in its own RocksDB directory under `path`
+    #[serde(default)]
+    pub per_agent_directories: bool,
 }
 
 impl Default for MemoryStorageConfig {
@@ -42,6 +48,7 @@ impl Default for MemoryStorageConfig {
             write_buffer_size: 64 * 1024 * 1024, // 64MB
             enable_compression: true,
             cache_size: 128 * 1024 * 1024, // 128MB
+            per_agent_directories: false,
         }
     }
 }
@@ -56,8 +63,107 @@ impl MemoryStorageConfig {
             write_buffer_size: 4 * 1024 * 1024, // 4MB for tests
             enable_compression: false,
             cache_size: 16 * 1024 * 1024, // 16MB for tests
+            per_agent_directories: false,
         }
     }
+
+    /// Builder: store each agent's memory in its own directory
+    pub fn per_agent_directories(mut self) -> Self {
+        self.per_agent_directories = true;
+        self
+    }
+
+    /// Directory holding an agent's memory when stored per agent
+    ///
+    /// Bytes of the agent ID outside `[A-Za-z0-9_-]` are escaped as `%XX`, so
+    /// every agent gets a distinct directory directly under `path`.
+    pub fn agent_path(&self, agent_id: &str) -> PathBuf {
+        let mut name = String::with_capacity(agent_id.len());
+        for byte in agent_id.bytes() {
+            if byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-' {
+                name.push(byte as char);
+            } else {
+                name.push_str(&format!("%{:02X}", byte));
+            }
+        }
+        Path::new(&self.path).join("agents").join(name)
+    }
+
+    /// Storage configuration to open for an agent
+    ///
+    /// Routes to the agent's own directory when `per_agent_directories` is set.
+    pub fn for_agent(&self, agent_id: &str) -> Self {
+        let mut config = self.clone();
+        if self.per_agent_directories {
+            config.path = self.agent_path(agent_id).to_string_lossy().to_string();
+        }
+        config
+    }
+}
+
+/// Position in an agent's episodes, ordered by event time and episode ID
+///
+/// Identifies the last episode of a page; the next page starts after it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EpisodeCursor {
+    /// Event time of the episode in milliseconds
+    pub event_time_millis: i64,
+    /// Episode ID
+    pub episode_id: EpisodeId,
+}
+
+impl EpisodeCursor {
+    /// Cursor positioned at an episode
+    pub fn at(episode: &Episode) -> Self {
+        Self {
+            event_time_millis: episode.event_time.as_millis(),
+            episode_id: episode.id,
+        }
+    }
+
+    /// Cursor positioned just before every episode at `millis`
+    ///
+    /// Paging backward from it starts with the episodes strictly earlier than `millis`.
+    pub fn before_time(millis: i64) -> Self {
+        Self {
+            event_time_millis: millis,
+            episode_id: EpisodeId::from_uuid(uuid::Uuid::nil()),
+        }
+    }
+
+    pub(crate) fn order(&self) -> (i64, uuid::Uuid) {
+        (self.event_time_millis, self.episode_id.as_uuid())
+    }
+}
+
+impl std::fmt::Display for EpisodeCursor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}_{}", self.event_time_millis, self.episode_id)
+    }
+}
+
+impl std::str::FromStr for EpisodeCursor {
+    type Err = Error;
+
+    /// Parse `<millis>_<episode id>`, or plain `<millis>` for [`EpisodeCursor::before_time`]
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::ValidationError(format!("Invalid episode cursor: {}", s));
+        let (millis, episode_id) = match s.split_once('_') {
+            Some((millis, id)) => {
+                let id = uuid::Uuid::parse_str(id).map_err(|_| invalid())?;
+                (millis, Some(EpisodeId::from_uuid(id)))
+            }
+            None => (s, None),
+        };
+        let millis = millis.parse().map_err(|_| invalid())?;
+        Ok(match episode_id {
+            Some(episode_id) => Self {
+                event_time_millis: millis,
+                episode_id,
+            },
+            None => Self::before_time(millis),
+        })
+    }
 }
 
 /// Trait for memory storage backends
@@ -71,6 +177,16 @@ pub trait MemoryStorage: Send + Sync {
     /// Store an episode
     async fn store_episode(&self, agent_id: &str, episode: &Episode) -> Result<()>;
 
+    /// Store several episodes at once
+    ///
+    /// The default implementation stores them one by one.
+    async fn store_episodes(&self, agent_id: &str, episodes: &[Episode]) -> Result<()> {
+        for episode in episodes {
+            self.store_episode(agent_id, episode).await?;
+        }
+        Ok(())
+    }
+
     /// Get an episode by ID
     async fn get_episode(&self, agent_id: &str, episode_id: EpisodeId) -> Result<Option<Episode>>;
 
@@ -85,6 +201,30 @@ pub trait MemoryStorage: Send + Sync {
         end_time_millis: i64,
     ) -> Result<Vec<Episode>>;
 
+    /// Get up to `limit` episodes in a time range, ordered by event time,
+    /// starting after `after`
+    ///
+    /// Lets callers walk through an agent's episodes page by page. The default
+    /// implementation loads the whole range.
+    async fn get_episodes_page(
+        &self,
+        agent_id: &str,
+        start_time_millis: i64,
+        end_time_millis: i64,
+        after: Option<EpisodeCursor>,
+        limit: usize,
+    ) -> Result<Vec<Episode>> {
+        let mut episodes = self
+            .get_episodes_in_range(agent_id, start_time_millis, end_time_millis)
+            .await?;
+        episodes.sort_by_key(|e| EpisodeCursor::at(e).order());
+        Ok(episodes
+            .into_iter()
+            .filter(|e| after.is_none_or(|cursor| EpisodeCursor::at(e).order() > cursor.order()))
+            .take(limit)
+            .collect())
+    }
+
     /// Delete an episode
     async fn delete_episode(&self, agent_id: &str, episode_id: EpisodeId) -> Result<bool>;
 
@@ -97,6 +237,35 @@ pub trait MemoryStorage: Send + Sync {
     /// Update an episode (for relevance decay, access tracking, etc.)
     async fn update_episode(&self, agent_id: &str, episode: &Episode) -> Result<()>;
 
+    /// Get the valid episodes of an agent that are, or are not, consolidated
+    ///
+    /// The default implementation filters all of the agent's episodes.
+    async fn get_episodes_by_consolidation(
+        &self,
+        agent_id: &str,
+        consolidated: bool,
+    ) -> Result<Vec<Episode>> {
+        Ok(self
+            .get_all_episodes(agent_id)
+            .await?
+            .into_iter()
+            .filter(|episode| episode.consolidated == consolidated)
+            .collect())
+    }
+
+    /// Get the saved vector index of an agent, if any
+    ///
+    /// The default implementation never saves an index, so semantic search
+    /// rebuilds it from the agent's episodes.
+    async fn load_vector_index(&self, _agent_id: &str) -> Result<Option<Vec<u8>>> {
+        Ok(None)
+    }
+
+    /// Save the vector index of an agent, replacing any saved one
+    async fn save_vector_index(&self, _agent_id: &str, _index: &[u8]) -> Result<()> {
+        Ok(())
+    }
+
     /// Flush any pending writes to disk
     async fn flush(&self) -> Result<()>;
 
@@ -112,7 +281,7 @@ mod cf {
     /// Episode lookup by ID: episode_id -> agent_id + timestamp
     pub const EPISODE_INDEX: &str = "memory_episode_index";
 
-    /// Agent metadata (episode counts, etc.)
+    /// Agent metadata (episode counts, saved vector indexes, etc.)
     pub const AGENT_META: &str = "memory_agent_meta";
 }
 
@@ -121,6 +290,39 @@ mod prefix {
     pub const EPISODE: u8 = 0x01;
     pub const EPISODE_INDEX: u8 = 0x02;
     pub const AGENT_META: u8 = 0x03;
+    pub const VECTOR_INDEX: u8 = 0x04;
+    pub const CONSOLIDATED: u8 = 0x05;
+}
+
+/// Encode an episode index value: agent_id + timestamp
+fn encode_episode_location(agent_id: &str, event_time_millis: i64) -> Vec<u8> {
+    let mut value = Vec::with_capacity(2 + agent_id.len() + 8);
+    push_segment(&mut value, agent_id.as_bytes());
+    value.extend_from_slice(&event_time_millis.to_be_bytes());
+    value
+}
+
+/// Decode the timestamp from an episode index value
+fn decode_episode_timestamp(index_value: &[u8]) -> Result<i64> {
+    let (_, rest) = read_segment(index_value)
+        .ok_or_else(|| Error::Internal("Invalid index value".to_string()))?;
+    let timestamp_bytes: [u8; 8] = rest
+        .get(..8)
+        .ok_or_else(|| Error::Internal("Invalid index value".to_string()))?
+        .try_into()
+        .map_err(|_| Error::Internal("Invalid timestamp bytes".to_string()))?;
+    Ok(i64::from_be_bytes(timestamp_bytes))
+}
+
+/// Decode the length-prefixed agent ID at the start of `bytes`
+fn decode_agent_id(bytes: &[u8]) -> Option<&str> {
+    let (agent, _) = read_segment(bytes)?;
+    std::str::from_utf8(agent).ok()
+}
+
+fn deserialize_episode(value: &[u8]) -> Result<Episode> {
+    bincode::deserialize(value)
+        .map_err(|e| Error::Deserialization(format!("Failed to deserialize episode: {}", e)))
 }
 
 /// RocksDB-backed memory storage implementation
@@ -133,6 +335,7 @@ impl RocksDbMemoryStorage {
     /// Open or create a RocksDB-backed memory storage
     pub fn open(config: MemoryStorageConfig) -> Result<Self> {
         info!("Opening memory storage at {}", config.path);
+        std::fs::create_dir_all(&config.path)?;
 
         let mut db_opts = rocksdb::Options::default();
         db_opts.create_if_missing(true);
@@ -180,9 +383,7 @@ impl RocksDbMemoryStorage {
         let mut key = Vec::with_capacity(1 + 2 + agent_id.len() + 8 + 16);
         key.push(prefix::EPISODE);
         // Length-prefixed agent_id
-        let agent_bytes = agent_id.as_bytes();
-        key.extend_from_slice(&(agent_bytes.len() as u16).to_be_bytes());
-        key.extend_from_slice(agent_bytes);
+        push_segment(&mut key, agent_id.as_bytes());
         // Timestamp for time-ordered iteration
         key.extend_from_slice(&event_time_millis.to_be_bytes());
         // Episode UUID
@@ -194,9 +395,24 @@ impl RocksDbMemoryStorage {
     fn episode_prefix(agent_id: &str) -> Vec<u8> {
         let mut key = Vec::with_capacity(1 + 2 + agent_id.len());
         key.push(prefix::EPISODE);
-        let agent_bytes = agent_id.as_bytes();
-        key.extend_from_slice(&(agent_bytes.len() as u16).to_be_bytes());
-        key.extend_from_slice(agent_bytes);
+        push_segment(&mut key, agent_id.as_bytes());
+        key
+    }
+
+    /// Build consolidated marker key for an episode key
+    ///
+    /// Markers share the episode key layout under their own prefix, so an
+    /// agent's consolidated episodes can be listed in event time order.
+    fn consolidated_key(episode_key: &[u8]) -> Vec<u8> {
+        let mut key = episode_key.to_vec();
+        key[0] = prefix::CONSOLIDATED;
+        key
+    }
+
+    /// Build consolidated marker prefix for scanning an agent's consolidated episodes
+    fn consolidated_prefix(agent_id: &str) -> Vec<u8> {
+        let mut key = Self::episode_prefix(agent_id);
+        key[0] = prefix::CONSOLIDATED;
         key
     }
 
@@ -212,9 +428,15 @@ impl RocksDbMemoryStorage {
     fn agent_meta_key(agent_id: &str) -> Vec<u8> {
         let mut key = Vec::with_capacity(1 + 2 + agent_id.len());
         key.push(prefix::AGENT_META);
-        let agent_bytes = agent_id.as_bytes();
-        key.extend_from_slice(&(agent_bytes.len() as u16).to_be_bytes());
-        key.extend_from_slice(agent_bytes);
+        push_segment(&mut key, agent_id.as_bytes());
+        key
+    }
+
+    /// Build saved vector index key
+    fn vector_index_key(agent_id: &str) -> Vec<u8> {
+        let mut key = Vec::with_capacity(1 + 2 + agent_id.len());
+        key.push(prefix::VECTOR_INDEX);
+        push_segment(&mut key, agent_id.as_bytes());
         key
     }
 
@@ -242,11 +464,7 @@ impl MemoryStorage for RocksDbMemoryStorage {
         let index_key = Self::episode_index_key(episode.id);
 
         // Index value: agent_id + timestamp for lookups
-        let mut index_value = Vec::new();
-        let agent_bytes = agent_id.as_bytes();
-        index_value.extend_from_slice(&(agent_bytes.len() as u16).to_be_bytes());
-        index_value.extend_from_slice(agent_bytes);
-        index_value.extend_from_slice(&episode.event_time.as_millis().to_be_bytes());
+        let index_value = encode_episode_location(agent_id, episode.event_time.as_millis());
 
         // Write batch for atomicity
         let mut batch = rocksdb::WriteBatch::default();
@@ -281,18 +499,7 @@ impl MemoryStorage for RocksDbMemoryStorage {
         };
 
         // Parse index value to get timestamp
-        if index_value.len() < 10 {
-            return Err(Error::Internal("Invalid index value".to_string()));
-        }
-        let agent_len = u16::from_be_bytes([index_value[0], index_value[1]]) as usize;
-        let timestamp_start = 2 + agent_len;
-        if index_value.len() < timestamp_start + 8 {
-            return Err(Error::Internal("Invalid index value".to_string()));
-        }
-        let timestamp_bytes: [u8; 8] = index_value[timestamp_start..timestamp_start + 8]
-            .try_into()
-            .map_err(|_| Error::Internal("Invalid timestamp bytes".to_string()))?;
-        let timestamp = i64::from_be_bytes(timestamp_bytes);
+        let timestamp = decode_episode_timestamp(&index_value)?;
 
         // Now read the actual episode
         let episode_key = Self::episode_key(agent_id, timestamp, episode_id);
@@ -386,15 +593,7 @@ impl MemoryStorage for RocksDbMemoryStorage {
         };
 
         // Parse timestamp from index
-        if index_value.len() < 10 {
-            return Err(Error::Internal("Invalid index value".to_string()));
-        }
-        let agent_len = u16::from_be_bytes([index_value[0], index_value[1]]) as usize;
-        let timestamp_start = 2 + agent_len;
-        let timestamp_bytes: [u8; 8] = index_value[timestamp_start..timestamp_start + 8]
-            .try_into()
-            .map_err(|_| Error::Internal("Invalid timestamp bytes".to_string()))?;
-        let timestamp = i64::from_be_bytes(timestamp_bytes);
+        let timestamp = decode_episode_timestamp(&index_value)?;
 
         // Delete both episode and index
         let episode_key = Self::episode_key(agent_id, timestamp, episode_id);
@@ -484,6 +683,20 @@ impl MemoryStorage for RocksDbMemoryStorage {
         self.store_episode(agent_id, episode).await
     }
 
+    async fn load_vector_index(&self, agent_id: &str) -> Result<Option<Vec<u8>>> {
+        let meta_cf = self.cf(cf::AGENT_META)?;
+        self.db
+            .get_cf(meta_cf, Self::vector_index_key(agent_id))
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    async fn save_vector_index(&self, agent_id: &str, index: &[u8]) -> Result<()> {
+        let meta_cf = self.cf(cf::AGENT_META)?;
+        self.db
+            .put_cf(meta_cf, Self::vector_index_key(agent_id), index)
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
     async fn flush(&self) -> Result<()> {
         self.db
             .flush()
@@ -499,6 +712,359 @@ impl MemoryStorage for RocksDbMemoryStorage {
     }
 }
 
+/// Storage used by one agent's memory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AgentStorageUsage {
+    /// Agent identifier
+    pub agent_id: String,
+
+    /// Number of stored episodes, including invalidated ones
+    pub episodes: usize,
+
+    /// Number of episode index entries
+    pub index_entries: usize,
+
+    /// Bytes of episode keys and values
+    pub episode_bytes: u64,
+
+    /// Bytes of episode index keys and values
+    pub index_bytes: u64,
+}
+
+impl AgentStorageUsage {
+    /// Total bytes used by the agent
+    pub fn total_bytes(&self) -> u64 {
+        self.episode_bytes + self.index_bytes
+    }
+}
+
+/// Entries read per scan when measuring storage usage
+const USAGE_SCAN_PAGE: usize = 1000;
+
+/// Memory storage in the graph database's `memory` column family
+///
+/// Shares the database's RocksDB instance instead of opening a separate one.
+/// Episodes use the same key layout as [`RocksDbMemoryStorage`], keyed by
+/// agent ID and event time, with the episode index stored alongside them.
+#[derive(Clone)]
+pub struct DatabaseMemoryStorage {
+    engine: StorageEngine,
+}
+
+impl DatabaseMemoryStorage {
+    /// Create storage on top of a database's storage engine
+    pub fn new(engine: StorageEngine) -> Self {
+        Self { engine }
+    }
+
+    /// Look up where an episode is stored: its episode key and index key
+    fn locate(&self, agent_id: &str, episode_id: EpisodeId) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let index_key = RocksDbMemoryStorage::episode_index_key(episode_id);
+        let Some(index_value) = self.engine.get_memory(&index_key)? else {
+            return Ok(None);
+        };
+        let timestamp = decode_episode_timestamp(&index_value)?;
+        let episode_key = RocksDbMemoryStorage::episode_key(agent_id, timestamp, episode_id);
+        Ok(Some((episode_key, index_key)))
+    }
+
+    /// Storage used by each agent, largest first
+    ///
+    /// Sizes are the uncompressed bytes of each agent's keys and values, read
+    /// page by page so the whole column family is never held in memory.
+    pub fn usage(&self) -> Result<Vec<AgentStorageUsage>> {
+        let mut usage: HashMap<String, AgentStorageUsage> = HashMap::new();
+        for key_prefix in [prefix::EPISODE, prefix::EPISODE_INDEX] {
+            let mut start = vec![key_prefix];
+            loop {
+                let page = self.engine.scan_memory_from(&[key_prefix], &start, USAGE_SCAN_PAGE)?;
+                for (key, value) in &page {
+                    // Episode keys carry the agent ID; index entries carry it in the value
+                    let agent_id = if key_prefix == prefix::EPISODE {
+                        decode_agent_id(&key[1..])
+                    } else {
+                        decode_agent_id(value)
+                    };
+                    let Some(agent_id) = agent_id else {
+                        warn!("Skipping memory entry with an invalid agent ID");
+                        continue;
+                    };
+                    let entry = usage.entry(agent_id.to_string()).or_insert_with(|| {
+                        AgentStorageUsage {
+                            agent_id: agent_id.to_string(),
+                            ..Default::default()
+                        }
+                    });
+                    let bytes = (key.len() + value.len()) as u64;
+                    if key_prefix == prefix::EPISODE {
+                        entry.episodes += 1;
+                        entry.episode_bytes += bytes;
+                    } else {
+                        entry.index_entries += 1;
+                        entry.index_bytes += bytes;
+                    }
+                }
+
+                match page.last() {
+                    Some((last_key, _)) if page.len() == USAGE_SCAN_PAGE => {
+                        start = last_key.clone();
+                        start.push(0);
+                    }
+                    _ => break,
+                }
+            }
+        }
+
+        let mut usage: Vec<_> = usage.into_values().collect();
+        usage.sort_by(|a, b| {
+            b.total_bytes()
+                .cmp(&a.total_bytes())
+                .then_with(|| a.agent_id.cmp(&b.agent_id))
+        });
+        Ok(usage)
+    }
+
+    /// Puts and deletes that store `episode` with its index entry and consolidated marker
+    fn episode_writes(
+        agent_id: &str,
+        episode: &Episode,
+        puts: &mut Vec<(Vec<u8>, Vec<u8>)>,
+        deletes: &mut Vec<Vec<u8>>,
+    ) -> Result<()> {
+        let value = bincode::serialize(episode)
+            .map_err(|e| Error::Serialization(format!("Failed to serialize episode: {}", e)))?;
+        let event_time = episode.event_time.as_millis();
+        let episode_key = RocksDbMemoryStorage::episode_key(agent_id, event_time, episode.id);
+        let consolidated_key = RocksDbMemoryStorage::consolidated_key(&episode_key);
+
+        puts.push((episode_key, value));
+        puts.push((
+            RocksDbMemoryStorage::episode_index_key(episode.id),
+            encode_episode_location(agent_id, event_time),
+        ));
+        if episode.consolidated {
+            puts.push((consolidated_key, Vec::new()));
+        } else {
+            deletes.push(consolidated_key);
+        }
+        Ok(())
+    }
+
+    /// All stored episodes of an agent, including invalidated ones, in event time order
+    fn scan_episodes(&self, agent_id: &str) -> Result<Vec<(Vec<u8>, Episode)>> {
+        let prefix = RocksDbMemoryStorage::episode_prefix(agent_id);
+        self.engine
+            .scan_memory(&prefix)?
+            .into_iter()
+            .map(|(key, value)| Ok((key, deserialize_episode(&value)?)))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl MemoryStorage for DatabaseMemoryStorage {
+    async fn store_episode(&self, agent_id: &str, episode: &Episode) -> Result<()> {
+        let mut puts = Vec::with_capacity(3);
+        let mut deletes = Vec::with_capacity(1);
+        Self::episode_writes(agent_id, episode, &mut puts, &mut deletes)?;
+        self.engine.write_memory(&puts, &deletes)?;
+
+        debug!("Stored episode {} for agent {}", episode.id, agent_id);
+        Ok(())
+    }
+
+    async fn store_episodes(&self, agent_id: &str, episodes: &[Episode]) -> Result<()> {
+        let mut puts = Vec::with_capacity(episodes.len() * 2);
+        let mut deletes = Vec::new();
+        for episode in episodes {
+            Self::episode_writes(agent_id, episode, &mut puts, &mut deletes)?;
+        }
+
+        self.engine.write_memory(&puts, &deletes)?;
+
+        debug!("Stored {} episodes for agent {}", episodes.len(), agent_id);
+        Ok(())
+    }
+
+    async fn get_episode(&self, agent_id: &str, episode_id: EpisodeId) -> Result<Option<Episode>> {
+        let Some((episode_key, _)) = self.locate(agent_id, episode_id)? else {
+            return Ok(None);
+        };
+        self.engine
+            .get_memory(&episode_key)?
+            .map(|value| deserialize_episode(&value))
+            .transpose()
+    }
+
+    async fn get_all_episodes(&self, agent_id: &str) -> Result<Vec<Episode>> {
+        Ok(self
+            .scan_episodes(agent_id)?
+            .into_iter()
+            .map(|(_, episode)| episode)
+            .filter(|episode| episode.is_valid())
+            .collect())
+    }
+
+    async fn get_episodes_in_range(
+        &self,
+        agent_id: &str,
+        start_time_millis: i64,
+        end_time_millis: i64,
+    ) -> Result<Vec<Episode>> {
+        Ok(self
+            .get_all_episodes(agent_id)
+            .await?
+            .into_iter()
+            .filter(|episode| {
+                let event_millis = episode.event_time.as_millis();
+                event_millis >= start_time_millis && event_millis <= end_time_millis
+            })
+            .collect())
+    }
+
+    async fn get_episodes_page(
+        &self,
+        agent_id: &str,
+        start_time_millis: i64,
+        end_time_millis: i64,
+        after: Option<EpisodeCursor>,
+        limit: usize,
+    ) -> Result<Vec<Episode>> {
+        let prefix = RocksDbMemoryStorage::episode_prefix(agent_id);
+        // Keys order episodes by event time, then ID, so a page starts right
+        // after the cursor's key (or at the start of the range). Big-endian
+        // timestamps only sort correctly when non-negative, so a range
+        // reaching before the epoch is scanned from the agent's first key.
+        let mut start = match after {
+            Some(cursor) => {
+                let mut key = RocksDbMemoryStorage::episode_key(
+                    agent_id,
+                    cursor.event_time_millis,
+                    cursor.episode_id,
+                );
+                key.push(0);
+                key
+            }
+            None if start_time_millis <= 0 => prefix.clone(),
+            None => {
+                let mut key = prefix.clone();
+                key.extend_from_slice(&start_time_millis.to_be_bytes());
+                key
+            }
+        };
+
+        let mut episodes = Vec::new();
+        while episodes.len() < limit {
+            let batch = limit - episodes.len();
+            let entries = self.engine.scan_memory_from(&prefix, &start, batch)?;
+            let exhausted = entries.len() < batch;
+
+            for (key, value) in entries {
+                let episode = deserialize_episode(&value)?;
+                let event_time = episode.event_time.as_millis();
+                if event_time > end_time_millis {
+                    return Ok(episodes);
+                }
+                // Invalidated episodes are skipped, like in get_all_episodes
+                if event_time >= start_time_millis && episode.is_valid() {
+                    episodes.push(episode);
+                }
+                start = key;
+                start.push(0);
+            }
+            if exhausted {
+                break;
+            }
+        }
+        Ok(episodes)
+    }
+
+    async fn delete_episode(&self, agent_id: &str, episode_id: EpisodeId) -> Result<bool> {
+        let Some((episode_key, index_key)) = self.locate(agent_id, episode_id)? else {
+            return Ok(false);
+        };
+        let consolidated_key = RocksDbMemoryStorage::consolidated_key(&episode_key);
+        self.engine.write_memory(&[], &[episode_key, index_key, consolidated_key])?;
+
+        debug!("Deleted episode {} for agent {}", episode_id, agent_id);
+        Ok(true)
+    }
+
+    async fn delete_all_episodes(&self, agent_id: &str) -> Result<usize> {
+        let mut deletes = Vec::new();
+        for (key, episode) in self.scan_episodes(agent_id)? {
+            deletes.push(RocksDbMemoryStorage::consolidated_key(&key));
+            deletes.push(key);
+            deletes.push(RocksDbMemoryStorage::episode_index_key(episode.id));
+        }
+
+        let count = deletes.len() / 3;
+        if count > 0 {
+            // The saved vector index only refers to the deleted episodes
+            deletes.push(RocksDbMemoryStorage::vector_index_key(agent_id));
+            self.engine.write_memory(&[], &deletes)?;
+            info!("Deleted {} episodes for agent {}", count, agent_id);
+        }
+        Ok(count)
+    }
+
+    async fn episode_count(&self, agent_id: &str) -> Result<usize> {
+        Ok(self.get_all_episodes(agent_id).await?.len())
+    }
+
+    async fn update_episode(&self, agent_id: &str, episode: &Episode) -> Result<()> {
+        self.store_episode(agent_id, episode).await
+    }
+
+    async fn get_episodes_by_consolidation(
+        &self,
+        agent_id: &str,
+        consolidated: bool,
+    ) -> Result<Vec<Episode>> {
+        if !consolidated {
+            return Ok(self
+                .get_all_episodes(agent_id)
+                .await?
+                .into_iter()
+                .filter(|episode| !episode.consolidated)
+                .collect());
+        }
+
+        // Consolidated episodes are found through their markers
+        let marker_prefix = RocksDbMemoryStorage::consolidated_prefix(agent_id);
+        let mut episodes = Vec::new();
+        for (key, _) in self.engine.scan_memory(&marker_prefix)? {
+            let mut episode_key = key;
+            episode_key[0] = prefix::EPISODE;
+            if let Some(value) = self.engine.get_memory(&episode_key)? {
+                let episode = deserialize_episode(&value)?;
+                if episode.is_valid() {
+                    episodes.push(episode);
+                }
+            }
+        }
+        Ok(episodes)
+    }
+
+    async fn load_vector_index(&self, agent_id: &str) -> Result<Option<Vec<u8>>> {
+        self.engine.get_memory(&RocksDbMemoryStorage::vector_index_key(agent_id))
+    }
+
+    async fn save_vector_index(&self, agent_id: &str, index: &[u8]) -> Result<()> {
+        let key = RocksDbMemoryStorage::vector_index_key(agent_id);
+        self.engine.write_memory(&[(key, index.to_vec())], &[])
+    }
+
+    async fn flush(&self) -> Result<()> {
+        self.engine.flush()
+    }
+
+    async fn close(&self) -> Result<()> {
+        // The storage engine belongs to the database, which closes it
+        self.flush().await
+    }
+}
+
 /// In-memory storage implementation for testing
 pub struct InMemoryStorage {
     episodes: tokio::sync::RwLock<HashMap<String, HashMap<EpisodeId, Episode>>>,
@@ -528,6 +1094,15 @@ impl MemoryStorage for InMemoryStorage {
         Ok(())
     }
 
+    async fn store_episodes(&self, agent_id: &str, episodes: &[Episode]) -> Result<()> {
+        let mut all_episodes = self.episodes.write().await;
+        let agent_episodes = all_episodes.entry(agent_id.to_string()).or_default();
+        for episode in episodes {
+            agent_episodes.insert(episode.id, episode.clone());
+        }
+        Ok(())
+    }
+
     async fn get_episode(&self, agent_id: &str, episode_id: EpisodeId) -> Result<Option<Episode>> {
         let episodes = self.episodes.read().await;
         Ok(episodes
@@ -632,6 +1207,17 @@ mod tests {
         assert_eq!(retrieved.content.primary, "Hello");
     }
 
+    #[test]
+    fn test_episode_cursor_round_trip() {
+        let episode = Episode::observation("agent-1", "Event");
+        let cursor = EpisodeCursor::at(&episode);
+        assert_eq!(cursor.to_string().parse::<EpisodeCursor>().unwrap(), cursor);
+
+        assert_eq!("1700000000000".parse::<EpisodeCursor>().unwrap(), EpisodeCursor::before_time(1700000000000));
+        assert!("yesterday".parse::<EpisodeCursor>().is_err());
+        assert!("1700000000000_not-a-uuid".parse::<EpisodeCursor>().is_err());
+    }
+
     #[tokio::test]
     async fn test_get_all_episodes() {
         let (storage, _dir) = create_test_storage().await;
@@ -697,6 +1283,197 @@ mod tests {
         assert_eq!(storage.episode_count("agent-1").await.unwrap(), 3);
     }
 
+    #[test]
+    fn test_agent_path() {
+        let config = MemoryStorageConfig::for_testing(Path::new("/data/memory"));
+        assert_eq!(config.for_agent("agent-1").path, "/data/memory");
+
+        let config = config.per_agent_directories();
+        assert_eq!(config.agent_path("agent_1"), Path::new("/data/memory/agents/agent_1"));
+        assert_eq!(config.agent_path("../x"), Path::new("/data/memory/agents/%2E%2E%2Fx"));
+        assert_ne!(config.agent_path("a.b"), config.agent_path("a%2Eb"));
+        assert_eq!(config.for_agent("bot").path, "/data/memory/agents/bot");
+    }
+
+    #[tokio::test]
+    async fn test_database_memory_storage() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+
+        let first = Episode::conversation("agent-1", "Hello", "Hi there!");
+        let second = Episode::observation("agent-1", "User seems happy");
+        let other = Episode::conversation("agent-2", "Bonjour", "Salut");
+        for (agent, episode) in [("agent-1", &first), ("agent-1", &second), ("agent-2", &other)] {
+            storage.store_episode(agent, episode).await.unwrap();
+        }
+
+        assert_eq!(storage.episode_count("agent-1").await.unwrap(), 2);
+        assert_eq!(storage.episode_count("agent-2").await.unwrap(), 1);
+        let retrieved = storage.get_episode("agent-1", first.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.content.primary, "Hello");
+
+        assert!(storage.delete_episode("agent-1", first.id).await.unwrap());
+        assert!(storage.get_episode("agent-1", first.id).await.unwrap().is_none());
+        assert!(!storage.delete_episode("agent-1", first.id).await.unwrap());
+
+        assert_eq!(storage.delete_all_episodes("agent-1").await.unwrap(), 1);
+        assert_eq!(storage.episode_count("agent-1").await.unwrap(), 0);
+        assert_eq!(storage.episode_count("agent-2").await.unwrap(), 1);
+
+        let batch = [
+            Episode::observation("agent-3", "First of a burst"),
+            Episode::observation("agent-3", "Second of a burst"),
+        ];
+        storage.store_episodes("agent-3", &batch).await.unwrap();
+        assert_eq!(storage.episode_count("agent-3").await.unwrap(), 2);
+        let retrieved = storage.get_episode("agent-3", batch[1].id).await.unwrap().unwrap();
+        assert_eq!(retrieved.content.primary, "Second of a burst");
+    }
+
+    #[tokio::test]
+    async fn test_database_consolidated_markers() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+
+        let mut kept = Episode::observation("agent-1", "Kept short-term");
+        let mut promoted = Episode::observation("agent-1", "Promoted");
+        promoted.mark_consolidated();
+        let mut other = Episode::observation("agent-2", "Another agent");
+        other.mark_consolidated();
+        storage.store_episodes("agent-1", &[kept.clone(), promoted.clone()]).await.unwrap();
+        storage.store_episode("agent-2", &other).await.unwrap();
+
+        let consolidated = storage.get_episodes_by_consolidation("agent-1", true).await.unwrap();
+        assert_eq!(consolidated.len(), 1);
+        assert_eq!(consolidated[0].id, promoted.id);
+        let pending = storage.get_episodes_by_consolidation("agent-1", false).await.unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].id, kept.id);
+
+        // Updates move an episode between the two sets
+        kept.mark_consolidated();
+        storage.update_episode("agent-1", &kept).await.unwrap();
+        promoted.consolidated = false;
+        storage.update_episode("agent-1", &promoted).await.unwrap();
+        let consolidated = storage.get_episodes_by_consolidation("agent-1", true).await.unwrap();
+        assert_eq!(consolidated.len(), 1);
+        assert_eq!(consolidated[0].id, kept.id);
+
+        // Deleting an episode removes its marker
+        storage.delete_episode("agent-1", kept.id).await.unwrap();
+        assert!(storage.get_episodes_by_consolidation("agent-1", true).await.unwrap().is_empty());
+        storage.delete_all_episodes("agent-2").await.unwrap();
+        let marker_prefix = RocksDbMemoryStorage::consolidated_prefix("agent-2");
+        assert!(db.storage().scan_memory(&marker_prefix).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_database_memory_usage() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+        assert!(storage.usage().unwrap().is_empty());
+
+        // More than a scan page of episodes for one agent
+        let episodes: Vec<_> = (0..USAGE_SCAN_PAGE + 5)
+            .map(|i| Episode::observation("busy", &format!("Event {}", i)))
+            .collect();
+        storage.store_episodes("busy", &episodes).await.unwrap();
+        storage
+            .store_episode("quiet", &Episode::observation("quiet", "Hello"))
+            .await
+            .unwrap();
+
+        let usage = storage.usage().unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].agent_id, "busy");
+        assert_eq!(usage[0].episodes, USAGE_SCAN_PAGE + 5);
+        assert_eq!(usage[0].index_entries, USAGE_SCAN_PAGE + 5);
+        assert_eq!(usage[1].agent_id, "quiet");
+        assert_eq!(usage[1].episodes, 1);
+        assert!(usage[0].total_bytes() > usage[1].total_bytes());
+        assert!(usage[1].episode_bytes > usage[1].index_bytes);
+    }
+
+    #[tokio::test]
+    async fn test_database_memory_storage_survives_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let episode = Episode::conversation("agent-1", "Remember me", "Noted");
+        {
+            let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+            let storage = DatabaseMemoryStorage::new(db.storage().clone());
+            storage.store_episode("agent-1", &episode).await.unwrap();
+            storage.close().await.unwrap();
+        }
+
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+        let episodes = storage.get_all_episodes("agent-1").await.unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].id, episode.id);
+    }
+
+    /// Walk through episodes 2 at a time and collect their event times
+    async fn page_through(storage: &dyn MemoryStorage, start: i64, end: i64) -> Vec<i64> {
+        let mut times = Vec::new();
+        let mut after = None;
+        loop {
+            let page = storage.get_episodes_page("agent-1", start, end, after, 2).await.unwrap();
+            times.extend(page.iter().map(|e| e.event_time.as_millis()));
+            match page.last() {
+                Some(last) if page.len() == 2 => after = Some(EpisodeCursor::at(last)),
+                _ => return times,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_episode_pages() {
+        use crate::episode::{EpisodeContent, EpisodeType};
+        use qilbee_core::temporal::EventTime;
+
+        let temp_dir = TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let storages: Vec<Box<dyn MemoryStorage>> = vec![
+            Box::new(DatabaseMemoryStorage::new(db.storage().clone())),
+            Box::new(InMemoryStorage::new()),
+        ];
+
+        for storage in &storages {
+            // Two episodes share a timestamp; one is invalidated
+            for millis in [5000, 1000, 3000, 3000, 4000, 2000] {
+                let episode = Episode::with_event_time(
+                    "agent-1",
+                    EpisodeType::Observation,
+                    EpisodeContent::new("event"),
+                    EventTime::from_millis(millis),
+                );
+                storage.store_episode("agent-1", &episode).await.unwrap();
+            }
+            let mut invalid = Episode::with_event_time(
+                "agent-1",
+                EpisodeType::Observation,
+                EpisodeContent::new("retracted"),
+                EventTime::from_millis(2500),
+            );
+            invalid.invalidate();
+            storage.store_episode("agent-1", &invalid).await.unwrap();
+            storage
+                .store_episode("agent-2", &Episode::observation("agent-2", "other agent"))
+                .await
+                .unwrap();
+
+            assert_eq!(
+                page_through(storage.as_ref(), 0, i64::MAX).await,
+                vec![1000, 2000, 3000, 3000, 4000, 5000]
+            );
+            assert_eq!(page_through(storage.as_ref(), 2000, 4000).await, vec![2000, 3000, 3000, 4000]);
+            assert!(page_through(storage.as_ref(), 6000, 7000).await.is_empty());
+        }
+    }
+
     #[tokio::test]
     async fn test_in_memory_storage() {
         let storage = InMemoryStorage::new();
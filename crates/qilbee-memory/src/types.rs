@@ -1,6 +1,8 @@
 //! Memory types and configuration
 
+use crate::episode::{Episode, EpisodeType};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::time::Duration;
 
 /// Type of memory
@@ -141,6 +143,68 @@ impl MemoryConfig {
     }
 }
 
+/// Criteria selecting episodes to forget
+///
+/// An episode is forgotten only if it matches every supplied criterion.
+/// Criteria with nothing supplied match no episodes.
+#[derive(Debug, Clone, Default)]
+pub struct ForgetCriteria {
+    /// Forget episodes with relevance below this score
+    pub min_relevance: Option<f64>,
+
+    /// Forget episodes whose event time is older than this
+    pub max_age: Option<Duration>,
+
+    /// Forget only episodes of these types (empty means all types)
+    pub episode_types: HashSet<EpisodeType>,
+}
+
+impl ForgetCriteria {
+    /// Create empty criteria
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builder: set relevance threshold
+    pub fn min_relevance(mut self, relevance: f64) -> Self {
+        self.min_relevance = Some(relevance);
+        self
+    }
+
+    /// Builder: set maximum age
+    pub fn max_age(mut self, age: Duration) -> Self {
+        self.max_age = Some(age);
+        self
+    }
+
+    /// Builder: restrict to episode types
+    pub fn episode_types(mut self, types: impl IntoIterator<Item = EpisodeType>) -> Self {
+        self.episode_types.extend(types);
+        self
+    }
+
+    /// Check whether any criterion is supplied
+    pub fn is_empty(&self) -> bool {
+        self.min_relevance.is_none() && self.max_age.is_none() && self.episode_types.is_empty()
+    }
+
+    /// Check whether an episode should be forgotten at `now` (millis since epoch)
+    pub fn matches(&self, episode: &Episode, now: i64) -> bool {
+        if self.is_empty() {
+            return false;
+        }
+        let below_relevance = self
+            .min_relevance
+            .is_none_or(|min| episode.relevance.should_forget(min));
+        let too_old = self
+            .max_age
+            .is_none_or(|age| now - episode.event_time.as_millis() > age.as_millis() as i64);
+        let type_selected =
+            self.episode_types.is_empty() || self.episode_types.contains(&episode.episode_type);
+        below_relevance && too_old && type_selected
+    }
+}
+
 /// Relevance score for a memory
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Relevance {
@@ -186,6 +250,15 @@ impl Relevance {
         self.score *= decay;
     }
 
+    /// Scale the score by a fixed factor (0.0 to 1.0), independent of time
+    ///
+    /// Returns true if the score changed.
+    pub fn decay_by_factor(&mut self, factor: f64) -> bool {
+        let before = self.score;
+        self.score *= factor.clamp(0.0, 1.0);
+        self.score < before
+    }
+
     /// Check if this memory should be forgotten
     pub fn should_forget(&self, min_relevance: f64) -> bool {
         self.score < min_relevance
@@ -226,6 +299,47 @@ mod tests {
         assert!(rel.score >= initial_score);
     }
 
+    #[test]
+    fn test_relevance_decay_by_factor() {
+        let mut gentle = Relevance::new();
+        assert!(gentle.decay_by_factor(0.99));
+        assert!((gentle.score - 0.99).abs() < 1e-9);
+
+        let mut aggressive = Relevance::new();
+        aggressive.decay_by_factor(0.05);
+        assert!(aggressive.should_forget(0.3));
+
+        let mut unchanged = Relevance::new();
+        assert!(!unchanged.decay_by_factor(1.0));
+        assert_eq!(unchanged.score, 1.0);
+    }
+
+    #[test]
+    fn test_forget_criteria() {
+        let now = chrono::Utc::now().timestamp_millis();
+        let mut stale = Episode::observation("agent-1", "Stale");
+        stale.event_time = qilbee_core::temporal::EventTime::from_millis(now - 7_200_000);
+        stale.relevance.score = 0.05;
+        let mut recent = Episode::conversation("agent-1", "Recent", "Reply");
+        recent.relevance.score = 0.05;
+
+        // Nothing supplied matches nothing
+        assert!(!ForgetCriteria::new().matches(&stale, now));
+
+        let low = ForgetCriteria::new().min_relevance(0.1);
+        assert!(low.matches(&stale, now));
+        assert!(low.matches(&recent, now));
+
+        // Newer episodes survive even below the relevance threshold
+        let old_and_low = low.clone().max_age(Duration::from_secs(3600));
+        assert!(old_and_low.matches(&stale, now));
+        assert!(!old_and_low.matches(&recent, now));
+
+        let conversations = low.episode_types([EpisodeType::Conversation]);
+        assert!(!conversations.matches(&stale, now));
+        assert!(conversations.matches(&recent, now));
+    }
+
     #[test]
     fn test_relevance_should_forget() {
         let mut rel = Relevance::new();
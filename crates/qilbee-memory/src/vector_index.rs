@@ -7,7 +7,8 @@
 //!
 //! - Configurable index parameters (M, ef_construction, ef_search)
 //! - Multiple similarity metrics (cosine, dot product, euclidean)
-//! - Persistence support via RocksDB
+//! - Persistence through [`MemoryStorage`], in the `memory` column family when
+//!   backed by the database
 //! - Thread-safe concurrent access
 //!
 //! # References
@@ -16,6 +17,7 @@
 //!   search using Hierarchical Navigable Small World graphs.
 
 use crate::embeddings::{cosine_similarity, dot_product, euclidean_distance, SimilarityMetric};
+use crate::storage::MemoryStorage;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
@@ -42,11 +44,20 @@ pub enum HnswError {
 
     #[error("Lock error: {0}")]
     LockError(String),
+
+    #[error("Storage error: {0}")]
+    StorageError(String),
 }
 
 /// Result type for HNSW operations
 pub type HnswResult<T> = Result<T, HnswError>;
 
+/// Version of the saved index format, stored as its first byte
+///
+/// Bump it whenever [`SerializedHnswIndex`] changes incompatibly; saved
+/// indexes of other versions are ignored and rebuilt.
+const SAVED_INDEX_VERSION: u8 = 1;
+
 /// Configuration for the HNSW index
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HnswConfig {
@@ -461,7 +472,16 @@ impl HnswIndex {
     }
 
     /// Search for the k nearest neighbors of a query vector
-    pub fn search(&self, query: &[f32], k: usize) -> HnswResult<Vec<SearchResult>> {
+    ///
+    /// `ef_search` overrides the configured candidate list size for this
+    /// query: larger values improve recall at the cost of latency. It is
+    /// never allowed to drop below `k`.
+    pub fn search(
+        &self,
+        query: &[f32],
+        k: usize,
+        ef_search: Option<usize>,
+    ) -> HnswResult<Vec<SearchResult>> {
         // Validate dimension
         if let Some(dim) = self.config.dimension {
             if query.len() != dim {
@@ -502,7 +522,8 @@ impl HnswIndex {
         }
 
         // Phase 2: Search at layer 0 with ef_search
-        let candidates = self.search_layer(query, &ep, self.config.ef_search, 0)?;
+        let ef = ef_search.unwrap_or(self.config.ef_search).max(k);
+        let candidates = self.search_layer(query, &ep, ef, 0)?;
 
         // Return top k results
         Ok(candidates
@@ -717,59 +738,107 @@ impl HnswIndex {
     }
 
     /// Remove a node from the index
+    ///
+    /// Every neighbor list that pointed at the removed node is repaired by
+    /// reconnecting it to the removed node's own neighbors, so search can
+    /// still reach the nodes that were only linked through it. If the
+    /// removed node was the entry point, the highest-level survivor replaces
+    /// it.
     pub fn remove(&mut self, id: &str) -> HnswResult<bool> {
         let mut nodes = self.nodes.write().map_err(|e| {
             HnswError::LockError(format!("Failed to acquire write lock: {}", e))
         })?;
 
         // Check if node exists
-        let Some(node) = nodes.remove(id) else {
+        let Some(removed) = nodes.remove(id) else {
             return Ok(false);
         };
 
-        // Remove connections from neighbors
-        for level in 0..node.neighbors.len() {
-            for neighbor_id in &node.neighbors[level] {
-                if let Some(neighbor) = nodes.get_mut(neighbor_id) {
-                    if level < neighbor.neighbors.len() {
-                        neighbor.neighbors[level].retain(|n| n != id);
-                    }
-                }
-            }
-        }
+        // Pruning makes links one-directional, so scan every node instead of
+        // only the removed node's neighbors
+        let affected: Vec<(String, usize)> = nodes
+            .values()
+            .flat_map(|node| {
+                node.neighbors
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, layer)| layer.iter().any(|n| n == id))
+                    .map(|(level, _)| (node.id.clone(), level))
+            })
+            .collect();
 
-        drop(nodes);
+        for (node_id, level) in affected {
+            let replacements = removed.neighbors.get(level).map(Vec::as_slice).unwrap_or(&[]);
+            self.reconnect(&mut nodes, &node_id, level, id, replacements);
+        }
 
         // Update entry point if needed
         let mut entry = self.entry_point.write().map_err(|e| {
             HnswError::LockError(format!("Failed to acquire write lock: {}", e))
         })?;
 
-        if entry.as_ref() == Some(&id.to_string()) {
-            let nodes = self.nodes.read().map_err(|e| {
-                HnswError::LockError(format!("Failed to acquire read lock: {}", e))
+        if entry.as_deref() == Some(id) {
+            // Find new entry point (highest level node)
+            let new_entry = nodes.values().max_by_key(|n| n.level);
+
+            let mut max_level = self.current_max_level.write().map_err(|e| {
+                HnswError::LockError(format!("Failed to acquire write lock: {}", e))
             })?;
+            *max_level = new_entry.map(|n| n.level).unwrap_or(0);
+            *entry = new_entry.map(|n| n.id.clone());
+        }
 
-            // Find new entry point (highest level node)
-            let new_entry = nodes
-                .values()
-                .max_by_key(|n| n.level)
-                .map(|n| n.id.clone());
-
-            *entry = new_entry;
-
-            // Update max level
-            if let Some(ref new_entry_id) = *entry {
-                if let Some(new_entry_node) = nodes.get(new_entry_id) {
-                    let mut max_level = self.current_max_level.write().map_err(|e| {
-                        HnswError::LockError(format!("Failed to acquire write lock: {}", e))
-                    })?;
-                    *max_level = new_entry_node.level;
-                }
+        Ok(true)
+    }
+
+    /// Replace `removed_id` in a node's neighbor list at `level` with the
+    /// closest of its remaining neighbors and `replacements`
+    fn reconnect(
+        &self,
+        nodes: &mut HashMap<String, HnswNode>,
+        node_id: &str,
+        level: usize,
+        removed_id: &str,
+        replacements: &[String],
+    ) {
+        let Some(node) = nodes.get(node_id) else {
+            return;
+        };
+
+        let m = if level == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        };
+
+        let mut candidates: Vec<String> = Vec::new();
+        for candidate in node.neighbors[level].iter().chain(replacements) {
+            if candidate != removed_id && candidate != node_id && !candidates.contains(candidate) {
+                candidates.push(candidate.clone());
             }
         }
 
-        Ok(true)
+        let mut connection_distances: Vec<(String, f32)> = candidates
+            .into_iter()
+            .filter_map(|conn_id| {
+                let conn_node = nodes.get(&conn_id)?;
+                (level < conn_node.neighbors.len())
+                    .then(|| self.distance(&node.vector, &conn_node.vector))
+                    .map(|distance| (conn_id, distance))
+            })
+            .collect();
+
+        connection_distances.sort_by(|a, b| {
+            a.1.partial_cmp(&b.1).unwrap_or(Ordering::Equal)
+        });
+
+        if let Some(node) = nodes.get_mut(node_id) {
+            node.neighbors[level] = connection_distances
+                .into_iter()
+                .take(m)
+                .map(|(id, _)| id)
+                .collect();
+        }
     }
 
     /// Clear all nodes from the index
@@ -852,6 +921,74 @@ impl HnswIndex {
             current_max_level: Arc::new(RwLock::new(data.current_max_level)),
         })
     }
+
+    /// Encode the index in the versioned format used by [`save_to`](Self::save_to)
+    pub fn to_saved_bytes(&self) -> HnswResult<Vec<u8>> {
+        let mut bytes = vec![SAVED_INDEX_VERSION];
+        bytes.extend(self.to_bytes()?);
+        Ok(bytes)
+    }
+
+    /// Decode an index saved by [`save_to`](Self::save_to)
+    ///
+    /// Fails if the format version is unknown, or if the index's dimension
+    /// or metric differs from `config`, the configuration it will be used with.
+    pub fn from_saved_bytes(bytes: &[u8], config: &HnswConfig) -> HnswResult<Self> {
+        let (&version, data) = bytes
+            .split_first()
+            .ok_or_else(|| HnswError::SerializationError("Saved index is empty".to_string()))?;
+        if version != SAVED_INDEX_VERSION {
+            return Err(HnswError::SerializationError(format!(
+                "Unsupported saved index version {} (expected {})",
+                version, SAVED_INDEX_VERSION
+            )));
+        }
+
+        let index = Self::from_bytes(data)?;
+        let saved_dimension = index.config.dimension.or_else(|| {
+            let nodes = index.nodes.read().ok()?;
+            nodes.values().next().map(|node| node.vector.len())
+        });
+        if let (Some(expected), Some(got)) = (config.dimension, saved_dimension)
+            && expected != got
+        {
+            return Err(HnswError::DimensionMismatch { expected, got });
+        }
+        if index.config.metric != config.metric {
+            return Err(HnswError::InvalidParameter(format!(
+                "Saved index uses {:?}, configured metric is {:?}",
+                index.config.metric, config.metric
+            )));
+        }
+        Ok(index)
+    }
+
+    /// Save the index of an agent to storage, replacing any saved one
+    pub async fn save_to(&self, storage: &dyn MemoryStorage, agent_id: &str) -> HnswResult<()> {
+        let bytes = self.to_saved_bytes()?;
+        storage
+            .save_vector_index(agent_id, &bytes)
+            .await
+            .map_err(|e| HnswError::StorageError(e.to_string()))
+    }
+
+    /// Load the saved index of an agent from storage
+    ///
+    /// Returns `None` if no index was saved. See
+    /// [`from_saved_bytes`](Self::from_saved_bytes) for validation.
+    pub async fn load_from(
+        storage: &dyn MemoryStorage,
+        agent_id: &str,
+        config: &HnswConfig,
+    ) -> HnswResult<Option<Self>> {
+        let bytes = storage
+            .load_vector_index(agent_id)
+            .await
+            .map_err(|e| HnswError::StorageError(e.to_string()))?;
+        bytes
+            .map(|bytes| Self::from_saved_bytes(&bytes, config))
+            .transpose()
+    }
 }
 
 /// Serializable version of HnswIndex
@@ -956,7 +1093,7 @@ mod tests {
 
         // Search for vectors similar to [1, 0, 0]
         let query = vec![1.0, 0.0, 0.0];
-        let results = index.search(&query, 3).unwrap();
+        let results = index.search(&query, 3, None).unwrap();
 
         assert_eq!(results.len(), 3);
         // The closest should be v1 (exact match)
@@ -967,7 +1104,7 @@ mod tests {
     #[test]
     fn test_search_empty() {
         let index = HnswIndex::with_defaults();
-        let results = index.search(&[1.0, 0.0, 0.0], 5).unwrap();
+        let results = index.search(&[1.0, 0.0, 0.0], 5, None).unwrap();
         assert!(results.is_empty());
     }
 
@@ -987,6 +1124,108 @@ mod tests {
         assert!(index.contains("v2"));
     }
 
+    #[test]
+    fn test_remove_repairs_graph() {
+        let mut index = HnswIndex::new(HnswConfig::small());
+        let vectors: Vec<(String, Vec<f32>)> = (0..100)
+            .map(|i| {
+                let angle = (i as f32) * std::f32::consts::PI / 50.0;
+                let z = ((i * 37) % 100) as f32 / 100.0;
+                (format!("v{}", i), vec![angle.cos(), angle.sin(), z])
+            })
+            .collect();
+        for (id, vector) in &vectors {
+            index.insert(id.clone(), vector.clone()).unwrap();
+        }
+
+        let (removed, survivors): (Vec<_>, Vec<_>) =
+            vectors.iter().enumerate().partition(|(i, _)| i % 2 == 0);
+        for (_, (id, _)) in &removed {
+            assert!(index.remove(id).unwrap());
+        }
+        assert_eq!(index.len(), 50);
+
+        // No neighbor list still points at a removed node
+        {
+            let nodes = index.nodes.read().unwrap();
+            for node in nodes.values() {
+                for layer in &node.neighbors {
+                    assert!(layer.iter().all(|n| nodes.contains_key(n)));
+                }
+            }
+            let entry = index.entry_point.read().unwrap().clone().unwrap();
+            let max_level = nodes.values().map(|n| n.level).max().unwrap();
+            assert_eq!(nodes[&entry].level, max_level);
+            assert_eq!(*index.current_max_level.read().unwrap(), max_level);
+        }
+
+        // Search finds the true nearest survivors
+        for (_, (_, query)) in &removed {
+            let mut expected: Vec<f32> = survivors
+                .iter()
+                .map(|(_, (_, v))| index.distance(query, v))
+                .collect();
+            expected.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+            let results = index.search(query, 5, None).unwrap();
+            assert_eq!(results.len(), 5);
+            for (result, distance) in results.iter().zip(&expected) {
+                assert!((result.distance - distance).abs() < 1e-6);
+            }
+        }
+    }
+
+    #[test]
+    fn test_ef_search_override_improves_recall() {
+        // Deterministic pseudo-random vectors
+        let mut state = 42_u64;
+        let mut next = move || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+        };
+        let dimension = 16;
+        let config = HnswConfig {
+            m: 4,
+            ef_construction: 16,
+            ef_search: 10,
+            ..HnswConfig::small()
+        }
+        .with_metric(SimilarityMetric::Euclidean);
+        let mut index = HnswIndex::new(config);
+        let vectors: Vec<Vec<f32>> = (0..2000)
+            .map(|_| (0..dimension).map(|_| next()).collect())
+            .collect();
+        for (i, vector) in vectors.iter().enumerate() {
+            index.insert(format!("v{}", i), vector.clone()).unwrap();
+        }
+
+        // Plant a query next to every 40th vector and measure recall@10
+        let k = 10;
+        let recall = |ef_search: Option<usize>| {
+            let mut hits = 0;
+            for planted in vectors.iter().step_by(40) {
+                let query: Vec<f32> = planted.iter().map(|x| x + 0.01).collect();
+                let mut exact: Vec<(usize, f32)> = vectors
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| (i, index.distance(&query, v)))
+                    .collect();
+                exact.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap());
+                let expected: HashSet<String> =
+                    exact.iter().take(k).map(|(i, _)| format!("v{}", i)).collect();
+
+                let results = index.search(&query, k, ef_search).unwrap();
+                hits += results.iter().filter(|r| expected.contains(&r.id)).count();
+            }
+            hits as f32 / (50 * k) as f32
+        };
+
+        let narrow = recall(None);
+        let wide = recall(Some(400));
+        assert!(wide > narrow, "recall {} should beat {}", wide, narrow);
+        assert!(wide >= 0.9, "recall with a wide search was {}", wide);
+    }
+
     #[test]
     fn test_get() {
         let mut index = HnswIndex::new(HnswConfig::small());
@@ -1018,11 +1257,60 @@ mod tests {
 
         // Verify search still works
         let query = vec![1.0, 0.0, 0.0];
-        let results = restored.search(&query, 3).unwrap();
+        let results = restored.search(&query, 3, None).unwrap();
         assert_eq!(results.len(), 3);
         assert_eq!(results[0].id, "v1");
     }
 
+    #[tokio::test]
+    async fn test_save_and_load_from_storage() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let storage = crate::storage::RocksDbMemoryStorage::open(
+            crate::storage::MemoryStorageConfig::for_testing(temp_dir.path()),
+        )
+        .unwrap();
+        let config = HnswConfig::small().with_dimension(3);
+
+        assert!(HnswIndex::load_from(&storage, "agent-1", &config).await.unwrap().is_none());
+
+        let mut index = HnswIndex::new(config.clone());
+        for (id, vec) in create_test_vectors() {
+            index.insert(id, vec).unwrap();
+        }
+        index.save_to(&storage, "agent-1").await.unwrap();
+
+        let restored = HnswIndex::load_from(&storage, "agent-1", &config).await.unwrap().unwrap();
+        assert_eq!(restored.len(), index.len());
+        let results = restored.search(&[1.0, 0.0, 0.0], 3, None).unwrap();
+        assert_eq!(results[0].id, "v1");
+
+        // Indexes are saved per agent
+        assert!(HnswIndex::load_from(&storage, "agent-2", &config).await.unwrap().is_none());
+    }
+
+    #[test]
+    fn test_saved_bytes_validation() {
+        let config = HnswConfig::small().with_dimension(3);
+        let mut index = HnswIndex::new(config.clone());
+        index.insert("v1".to_string(), vec![1.0, 0.0, 0.0]).unwrap();
+        let bytes = index.to_saved_bytes().unwrap();
+
+        assert!(HnswIndex::from_saved_bytes(&bytes, &config).is_ok());
+        assert!(matches!(
+            HnswIndex::from_saved_bytes(&bytes, &HnswConfig::small().with_dimension(4)),
+            Err(HnswError::DimensionMismatch { expected: 4, got: 3 })
+        ));
+        assert!(HnswIndex::from_saved_bytes(&bytes, &config.clone().with_metric(SimilarityMetric::Euclidean)).is_err());
+
+        let mut future_version = bytes.clone();
+        future_version[0] = SAVED_INDEX_VERSION + 1;
+        assert!(matches!(
+            HnswIndex::from_saved_bytes(&future_version, &config),
+            Err(HnswError::SerializationError(_))
+        ));
+        assert!(HnswIndex::from_saved_bytes(&[], &config).is_err());
+    }
+
     #[test]
     fn test_different_metrics() {
         // Test with cosine similarity (default)
@@ -1034,7 +1322,7 @@ mod tests {
             .insert("v2".to_string(), vec![0.7071, 0.7071, 0.0])
             .unwrap();
 
-        let results = cosine_index.search(&[1.0, 0.0, 0.0], 2).unwrap();
+        let results = cosine_index.search(&[1.0, 0.0, 0.0], 2, None).unwrap();
         assert_eq!(results[0].id, "v1");
 
         // Test with euclidean distance
@@ -1047,7 +1335,7 @@ mod tests {
             .insert("v2".to_string(), vec![0.9, 0.0, 0.0])
             .unwrap();
 
-        let results = euclidean_index.search(&[1.0, 0.0, 0.0], 2).unwrap();
+        let results = euclidean_index.search(&[1.0, 0.0, 0.0], 2, None).unwrap();
         assert_eq!(results[0].id, "v1");
     }
 
@@ -1091,7 +1379,7 @@ mod tests {
 
         // Search for a specific vector
         let query = vec![1.0, 0.0, 0.0]; // angle = 0
-        let results = index.search(&query, 5).unwrap();
+        let results = index.search(&query, 5, None).unwrap();
 
         // v0 should be the closest (angle = 0)
         assert_eq!(results[0].id, "v0");
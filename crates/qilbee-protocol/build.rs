@@ -0,0 +1,11 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // Use the bundled protoc so builds don't depend on a system install
+    if std::env::var_os("PROTOC").is_none() {
+        // SAFETY: build scripts are single-threaded
+        unsafe { std::env::set_var("PROTOC", protoc_bin_vendored::protoc_bin_path()?) };
+    }
+
+    println!("cargo:rerun-if-changed=proto/qilbee.proto");
+    tonic_build::compile_protos("proto/qilbee.proto")?;
+    Ok(())
+}
@@ -1,13 +1,30 @@
-//! Bolt protocol implementation (placeholder)
+//! Bolt protocol implementation
 //!
 //! The Bolt protocol is a binary protocol developed by Neo4j for efficient
 //! graph database communication.
+//!
+//! [`BoltSession`] implements the message-level state machine: `RUN` opens a
+//! result, `PULL {n}` streams up to `n` records as `RECORD` messages and
+//! `DISCARD {n}` drops them, each batch ending with a `SUCCESS` that either
+//! reports `has_more` or carries the query summary. Records are taken from
+//! the executor's result chunks as they are pulled. A session starts out
+//! waiting for `HELLO`, whose credentials the [`QueryRunner`] checks before
+//! any query runs.
+//!
+//! [`serve_connection`] drives a session over a socket: it answers the
+//! handshake, then reads chunked [`packstream`](crate::packstream) messages
+//! and writes back the session's responses.
 
-use qilbee_core::Result;
+use crate::packstream;
+use qilbee_core::{Error, PropertyValue, Result};
+use std::collections::{HashMap, VecDeque};
 
 /// Bolt protocol version
 pub const BOLT_VERSION: (u8, u8) = (4, 4);
 
+/// Record count in `PULL`/`DISCARD` that selects every remaining record
+pub const ALL_RECORDS: i64 = -1;
+
 /// Bolt protocol version structure
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct BoltVersion {
@@ -21,24 +38,390 @@ impl BoltVersion {
     }
 
     pub const V4_4: Self = Self { major: 4, minor: 4 };
+
+    /// Handshake encoding of this version: `00 00 <minor> <major>`
+    pub fn to_bytes(self) -> [u8; 4] {
+        [0, 0, self.minor, self.major]
+    }
+}
+
+impl PartialOrd for BoltVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BoltVersion {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        (self.major, self.minor).cmp(&(other.major, other.minor))
+    }
+}
+
+/// Preamble every Bolt connection starts with
+pub const BOLT_MAGIC: [u8; 4] = [0x60, 0x60, 0xB0, 0x17];
+
+/// Length of the client handshake: the magic followed by four version offers
+pub const HANDSHAKE_LEN: usize = 20;
+
+/// Handshake response sent when no offered version is supported
+pub const NO_VERSION: [u8; 4] = [0; 4];
+
+/// Protocol versions this server speaks, highest first
+///
+/// Only 4.4: Bolt 5 changes the `HELLO` handshake and the node and
+/// relationship structures, which this server does not implement.
+pub const SUPPORTED_VERSIONS: &[BoltVersion] = &[BoltVersion::V4_4];
+
+/// A version range offered by the client during the handshake
+///
+/// Encoded as `00 <range> <minor> <major>`: the client accepts
+/// `major.minor` and the `range` minor versions below it. Clients before
+/// 4.3 always send a range of zero. Unused slots are all zeros.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VersionOffer {
+    pub major: u8,
+    pub minor: u8,
+    pub range: u8,
+}
+
+impl VersionOffer {
+    /// Decode a 4-byte version offer
+    pub fn from_bytes(bytes: [u8; 4]) -> Self {
+        Self {
+            range: bytes[1],
+            minor: bytes[2],
+            major: bytes[3],
+        }
+    }
+
+    /// Whether this offer includes `version`
+    pub fn accepts(&self, version: BoltVersion) -> bool {
+        self.major != 0
+            && version.major == self.major
+            && version.minor <= self.minor
+            && version.minor >= self.minor.saturating_sub(self.range)
+    }
+}
+
+/// Parse a client handshake into its version offers
+///
+/// Fails if the handshake is not exactly [`HANDSHAKE_LEN`] bytes or does not
+/// start with [`BOLT_MAGIC`].
+pub fn parse_handshake(bytes: &[u8]) -> Result<[VersionOffer; 4]> {
+    if bytes.len() != HANDSHAKE_LEN {
+        return Err(Error::ValidationError(format!(
+            "Bolt handshake must be {} bytes, got {}",
+            HANDSHAKE_LEN,
+            bytes.len()
+        )));
+    }
+    if bytes[..4] != BOLT_MAGIC {
+        return Err(Error::ValidationError("Missing Bolt magic preamble".to_string()));
+    }
+
+    let mut offers = [VersionOffer::from_bytes([0; 4]); 4];
+    for (offer, chunk) in offers.iter_mut().zip(bytes[4..].chunks_exact(4)) {
+        *offer = VersionOffer::from_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]);
+    }
+    Ok(offers)
+}
+
+/// Pick the highest supported version accepted by any offer
+pub fn negotiate_version(offers: &[VersionOffer], supported: &[BoltVersion]) -> Option<BoltVersion> {
+    supported
+        .iter()
+        .copied()
+        .filter(|version| offers.iter().any(|offer| offer.accepts(*version)))
+        .max()
+}
+
+/// Read the client handshake from `stream` and answer it
+///
+/// Returns the negotiated version, or `None` after answering with
+/// [`NO_VERSION`] when nothing matches. A malformed or truncated handshake
+/// shuts the stream down and returns an error; in both failure cases the
+/// caller should drop the connection.
+pub async fn accept_handshake<S>(stream: &mut S) -> Result<Option<BoltVersion>>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let mut buf = [0u8; HANDSHAKE_LEN];
+    let offers = match stream.read_exact(&mut buf).await {
+        Ok(_) => parse_handshake(&buf),
+        Err(e) => Err(Error::Io(e)),
+    };
+    let offers = match offers {
+        Ok(offers) => offers,
+        Err(e) => {
+            let _ = stream.shutdown().await;
+            return Err(e);
+        }
+    };
+
+    let version = negotiate_version(&offers, SUPPORTED_VERSIONS);
+    let response = version.map_or(NO_VERSION, BoltVersion::to_bytes);
+    stream.write_all(&response).await?;
+    stream.flush().await?;
+
+    Ok(version)
 }
 
 /// Bolt message
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum BoltMessage {
-    Hello { metadata: std::collections::HashMap<String, String> },
+    Hello { metadata: HashMap<String, PropertyValue> },
     Goodbye,
     Reset,
-    Run { query: String, parameters: std::collections::HashMap<String, String> },
+    Run {
+        query: String,
+        parameters: HashMap<String, PropertyValue>,
+        metadata: HashMap<String, PropertyValue>,
+    },
     Discard { n: i64 },
     Pull { n: i64 },
-    Begin { metadata: std::collections::HashMap<String, String> },
+    Begin { metadata: HashMap<String, PropertyValue> },
     Commit,
     Rollback,
-    Success { metadata: std::collections::HashMap<String, String> },
-    Record { fields: Vec<String> },
+    Success { metadata: HashMap<String, PropertyValue> },
+    Record { fields: Vec<PropertyValue> },
     Ignored,
-    Failure { metadata: std::collections::HashMap<String, String> },
+    Failure { metadata: HashMap<String, PropertyValue> },
+}
+
+impl BoltMessage {
+    /// Create a `SUCCESS` message
+    pub fn success(metadata: HashMap<String, PropertyValue>) -> Self {
+        Self::Success { metadata }
+    }
+
+    /// Create a `FAILURE` message with a Neo4j-style status code
+    pub fn failure(code: &str, message: &str) -> Self {
+        let mut metadata = HashMap::new();
+        metadata.insert("code".to_string(), PropertyValue::String(code.to_string()));
+        metadata.insert("message".to_string(), PropertyValue::String(message.to_string()));
+        Self::Failure { metadata }
+    }
+
+    /// Wire type of this message
+    pub fn message_type(&self) -> BoltMessageType {
+        match self {
+            Self::Hello { .. } => BoltMessageType::Hello,
+            Self::Goodbye => BoltMessageType::Goodbye,
+            Self::Reset => BoltMessageType::Reset,
+            Self::Run { .. } => BoltMessageType::Run,
+            Self::Discard { .. } => BoltMessageType::Discard,
+            Self::Pull { .. } => BoltMessageType::Pull,
+            Self::Begin { .. } => BoltMessageType::Begin,
+            Self::Commit => BoltMessageType::Commit,
+            Self::Rollback => BoltMessageType::Rollback,
+            Self::Success { .. } => BoltMessageType::Success,
+            Self::Record { .. } => BoltMessageType::Record,
+            Self::Ignored => BoltMessageType::Ignored,
+            Self::Failure { .. } => BoltMessageType::Failure,
+        }
+    }
+
+    /// PackStream encoding of this message, without chunk framing
+    pub fn encode(&self) -> Vec<u8> {
+        let tag = self.message_type() as u8;
+        let mut buf = Vec::new();
+        match self {
+            Self::Hello { metadata } | Self::Begin { metadata } | Self::Success { metadata } | Self::Failure { metadata } => {
+                packstream::encode_struct_header(&mut buf, 1, tag);
+                packstream::encode_map(&mut buf, metadata);
+            }
+            Self::Run { query, parameters, metadata } => {
+                packstream::encode_struct_header(&mut buf, 3, tag);
+                packstream::encode(&mut buf, &PropertyValue::String(query.clone()));
+                packstream::encode_map(&mut buf, parameters);
+                packstream::encode_map(&mut buf, metadata);
+            }
+            Self::Discard { n } | Self::Pull { n } => {
+                let mut extra = HashMap::new();
+                extra.insert("n".to_string(), PropertyValue::Integer(*n));
+                packstream::encode_struct_header(&mut buf, 1, tag);
+                packstream::encode_map(&mut buf, &extra);
+            }
+            Self::Record { fields } => {
+                packstream::encode_struct_header(&mut buf, 1, tag);
+                packstream::encode(&mut buf, &PropertyValue::Array(fields.clone()));
+            }
+            Self::Goodbye | Self::Reset | Self::Commit | Self::Rollback | Self::Ignored => {
+                packstream::encode_struct_header(&mut buf, 0, tag);
+            }
+        }
+        buf
+    }
+
+    /// Decode a message from its PackStream encoding
+    ///
+    /// `PULL` and `DISCARD` without an `n` select every record.
+    pub fn decode(bytes: &[u8]) -> Result<Self> {
+        let mut input = bytes;
+        let (fields, tag) = packstream::decode_struct_header(&mut input)?;
+
+        let message = match (tag, fields) {
+            (0x01, 1) => Self::Hello { metadata: packstream::decode_map(&mut input)? },
+            (0x02, 0) => Self::Goodbye,
+            (0x0F, 0) => Self::Reset,
+            (0x10, 3) => {
+                let query = match packstream::decode(&mut input)? {
+                    PropertyValue::String(query) => query,
+                    other => {
+                        return Err(Error::ValidationError(format!("RUN query must be a string, got {:?}", other)));
+                    }
+                };
+                Self::Run {
+                    query,
+                    parameters: packstream::decode_map(&mut input)?,
+                    metadata: packstream::decode_map(&mut input)?,
+                }
+            }
+            (0x2F | 0x3F, 1) => {
+                let n = match packstream::decode_map(&mut input)?.get("n") {
+                    Some(PropertyValue::Integer(n)) => *n,
+                    None => ALL_RECORDS,
+                    Some(other) => {
+                        return Err(Error::ValidationError(format!("Record count must be an integer, got {:?}", other)));
+                    }
+                };
+                if tag == 0x2F { Self::Discard { n } } else { Self::Pull { n } }
+            }
+            (0x11, 1) => Self::Begin { metadata: packstream::decode_map(&mut input)? },
+            (0x12, 0) => Self::Commit,
+            (0x13, 0) => Self::Rollback,
+            (0x70, 1) => Self::Success { metadata: packstream::decode_map(&mut input)? },
+            (0x71, 1) => match packstream::decode(&mut input)? {
+                PropertyValue::Array(fields) => Self::Record { fields },
+                other => return Err(Error::ValidationError(format!("RECORD fields must be a list, got {:?}", other))),
+            },
+            (0x7E, 0) => Self::Ignored,
+            (0x7F, 1) => Self::Failure { metadata: packstream::decode_map(&mut input)? },
+            _ => {
+                return Err(Error::ValidationError(format!(
+                    "Unknown message 0x{:02X} with {} fields",
+                    tag, fields
+                )));
+            }
+        };
+
+        if !input.is_empty() {
+            return Err(Error::ValidationError("Trailing bytes after message".to_string()));
+        }
+        Ok(message)
+    }
+}
+
+/// Largest message accepted from a client, summed over its chunks
+pub const MAX_MESSAGE_SIZE: usize = 16 * 1024 * 1024;
+
+/// Read one chunked message
+///
+/// Each chunk is a big-endian `u16` length followed by that many bytes; a
+/// zero-length chunk ends the message. Zero-length chunks between messages
+/// are keep-alive no-ops. Returns `None` when the client closes the
+/// connection between messages.
+pub async fn read_message<S>(stream: &mut S) -> Result<Option<Vec<u8>>>
+where
+    S: tokio::io::AsyncRead + Unpin,
+{
+    use tokio::io::AsyncReadExt;
+
+    let mut message = Vec::new();
+    loop {
+        let mut header = [0u8; 2];
+        match stream.read_exact(&mut header).await {
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof && message.is_empty() => return Ok(None),
+            Err(e) => return Err(Error::Io(e)),
+        }
+
+        let len = u16::from_be_bytes(header) as usize;
+        if len == 0 {
+            if message.is_empty() {
+                continue;
+            }
+            return Ok(Some(message));
+        }
+        if message.len() + len > MAX_MESSAGE_SIZE {
+            return Err(Error::ValidationError(format!(
+                "Bolt message exceeds {} bytes",
+                MAX_MESSAGE_SIZE
+            )));
+        }
+
+        let start = message.len();
+        message.resize(start + len, 0);
+        stream.read_exact(&mut message[start..]).await?;
+    }
+}
+
+/// Write one message in chunks of at most `u16::MAX` bytes
+pub async fn write_message<S>(stream: &mut S, message: &BoltMessage) -> Result<()>
+where
+    S: tokio::io::AsyncWrite + Unpin,
+{
+    use tokio::io::AsyncWriteExt;
+
+    let encoded = message.encode();
+    let mut framed = Vec::with_capacity(encoded.len() + 4);
+    for chunk in encoded.chunks(u16::MAX as usize) {
+        framed.extend_from_slice(&(chunk.len() as u16).to_be_bytes());
+        framed.extend_from_slice(chunk);
+    }
+    framed.extend_from_slice(&[0, 0]);
+    stream.write_all(&framed).await?;
+    Ok(())
+}
+
+/// Serve one Bolt connection until the client leaves
+///
+/// Answers the handshake, then handles messages one at a time. Queries run
+/// on the blocking thread pool. A message that cannot be decoded is
+/// answered with a `FAILURE` and closes the connection, as does a failed
+/// `HELLO`.
+pub async fn serve_connection<S, R>(mut stream: S, runner: R) -> Result<()>
+where
+    S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    R: QueryRunner + 'static,
+{
+    use tokio::io::AsyncWriteExt;
+
+    if accept_handshake(&mut stream).await?.is_none() {
+        return Ok(());
+    }
+
+    let mut session = BoltSession::new(runner);
+    while let Some(bytes) = read_message(&mut stream).await? {
+        let message = match BoltMessage::decode(&bytes) {
+            Ok(message) => message,
+            Err(e) => {
+                write_message(&mut stream, &BoltMessage::failure(status_codes::REQUEST_INVALID, &e.to_string())).await?;
+                stream.flush().await?;
+                return Err(e);
+            }
+        };
+
+        let (handled, responses) = tokio::task::spawn_blocking(move || {
+            let responses = session.handle(message);
+            (session, responses)
+        })
+        .await
+        .map_err(|e| Error::Internal(format!("Bolt session task failed: {}", e)))?;
+        session = handled;
+
+        for response in &responses {
+            write_message(&mut stream, response).await?;
+        }
+        stream.flush().await?;
+
+        if session.state() == BoltState::Defunct {
+            break;
+        }
+    }
+    Ok(())
 }
 
 /// Bolt message types
@@ -63,41 +446,754 @@ pub enum BoltMessageType {
     Failure = 0x7F,
 }
 
-/// Bolt protocol handler (placeholder)
-pub struct BoltHandler {
-    // Will hold connection state
+/// Status codes reported in `FAILURE` messages
+pub mod status_codes {
+    pub const REQUEST_INVALID: &str = "Neo.ClientError.Request.Invalid";
+    pub const SYNTAX_ERROR: &str = "Neo.ClientError.Statement.SyntaxError";
+    pub const STATEMENT_INVALID: &str = "Neo.ClientError.Statement.SemanticError";
+    pub const EXECUTION_FAILED: &str = "Neo.DatabaseError.Statement.ExecutionFailed";
+    pub const NOT_SUPPORTED: &str = "Neo.ClientError.Request.NotSupported";
+    pub const UNAUTHORIZED: &str = "Neo.ClientError.Security.Unauthorized";
 }
 
-impl BoltHandler {
-    /// Create a new Bolt handler
-    pub fn new() -> Self {
-        Self {}
+/// Record batches produced by the query executor
+///
+/// A batch fails with the error of the first record that could not be
+/// produced.
+pub type RecordBatches = Box<dyn Iterator<Item = Result<Vec<Vec<PropertyValue>>>> + Send>;
+
+/// An open query result consumed by `PULL` and `DISCARD`
+///
+/// Batches are only taken from the executor when the pending records run
+/// out, so a `PULL` never materializes more than one batch ahead.
+pub struct BoltResult {
+    /// Column names
+    fields: Vec<String>,
+
+    /// Remaining record batches
+    batches: RecordBatches,
+
+    /// Records taken from the current batch but not yet sent
+    pending: VecDeque<Vec<PropertyValue>>,
+
+    /// Metadata of the final `SUCCESS` (statistics, query type, timing)
+    summary: HashMap<String, PropertyValue>,
+}
+
+impl BoltResult {
+    /// Create a result over executor record batches
+    pub fn new(
+        fields: Vec<String>,
+        batches: RecordBatches,
+        summary: HashMap<String, PropertyValue>,
+    ) -> Self {
+        Self {
+            fields,
+            batches,
+            pending: VecDeque::new(),
+            summary,
+        }
     }
 
-    /// Handle a Bolt message (placeholder)
-    pub fn handle_message(&self, _msg_type: BoltMessageType, _data: &[u8]) -> Result<Vec<u8>> {
-        // TODO: Implement Bolt protocol handling
-        Ok(Vec::new())
+    /// Column names
+    pub fn fields(&self) -> &[String] {
+        &self.fields
+    }
+
+    /// Whether records remain to be pulled or discarded
+    pub fn has_more(&mut self) -> Result<bool> {
+        while self.pending.is_empty() {
+            match self.batches.next() {
+                Some(batch) => self.pending.extend(batch?),
+                None => return Ok(false),
+            }
+        }
+        Ok(true)
+    }
+
+    fn next_record(&mut self) -> Result<Option<Vec<PropertyValue>>> {
+        if self.has_more()? {
+            Ok(self.pending.pop_front())
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Stream up to `n` records, followed by the closing `SUCCESS`
+    ///
+    /// `n` is [`ALL_RECORDS`] for every remaining record or a positive count.
+    pub fn pull(&mut self, n: i64) -> Result<Vec<BoltMessage>> {
+        let limit = record_limit(n)?;
+
+        let mut messages = Vec::new();
+        while limit.is_none_or(|limit| messages.len() < limit) {
+            match self.next_record()? {
+                Some(fields) => messages.push(BoltMessage::Record { fields }),
+                None => break,
+            }
+        }
+
+        messages.push(self.batch_success()?);
+        Ok(messages)
+    }
+
+    /// Drop up to `n` records without sending them
+    ///
+    /// `n` is [`ALL_RECORDS`] for every remaining record or a positive count.
+    pub fn discard(&mut self, n: i64) -> Result<BoltMessage> {
+        match record_limit(n)? {
+            None => {
+                self.pending.clear();
+                for batch in self.batches.by_ref() {
+                    batch?;
+                }
+            }
+            Some(limit) => {
+                for _ in 0..limit {
+                    if self.next_record()?.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        self.batch_success()
+    }
+
+    /// `SUCCESS` closing a `PULL`/`DISCARD` batch
+    fn batch_success(&mut self) -> Result<BoltMessage> {
+        if self.has_more()? {
+            let mut metadata = HashMap::new();
+            metadata.insert("has_more".to_string(), PropertyValue::Boolean(true));
+            Ok(BoltMessage::success(metadata))
+        } else {
+            Ok(BoltMessage::success(self.summary.clone()))
+        }
+    }
+}
+
+impl std::fmt::Debug for BoltResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BoltResult")
+            .field("fields", &self.fields)
+            .field("pending", &self.pending.len())
+            .finish_non_exhaustive()
     }
 }
 
-impl Default for BoltHandler {
-    fn default() -> Self {
-        Self::new()
+/// Validate a `PULL`/`DISCARD` record count
+///
+/// Returns `None` for [`ALL_RECORDS`]. Zero and other negative counts are
+/// rejected: the client must ask for at least one record.
+fn record_limit(n: i64) -> Result<Option<usize>> {
+    match n {
+        ALL_RECORDS => Ok(None),
+        n if n > 0 => Ok(Some(usize::try_from(n).unwrap_or(usize::MAX))),
+        n => Err(Error::ValidationError(format!(
+            "Record count must be positive or -1 for all records, got {}",
+            n
+        ))),
+    }
+}
+
+/// Authenticates and executes the queries of a Bolt session
+pub trait QueryRunner: Send {
+    /// Check the credentials of a `HELLO`
+    ///
+    /// `hello` is the `HELLO` extra map, holding the `scheme`, `principal`
+    /// and `credentials` entries. No query runs until this succeeds.
+    fn authenticate(&mut self, hello: &HashMap<String, PropertyValue>) -> Result<()>;
+
+    /// Run a query and open its result
+    ///
+    /// `metadata` is the `RUN` extra map, e.g. the target `db`.
+    fn run(
+        &self,
+        query: &str,
+        parameters: &HashMap<String, PropertyValue>,
+        metadata: &HashMap<String, PropertyValue>,
+    ) -> Result<BoltResult>;
+}
+
+/// Bolt session state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoltState {
+    /// Waiting for an authenticating `HELLO`
+    Connected,
+    /// Waiting for `RUN`
+    Ready,
+    /// A result is open and waiting for `PULL` or `DISCARD`
+    Streaming,
+    /// A request failed; everything but `RESET` is ignored
+    Failed,
+    /// The client said `GOODBYE` or failed to authenticate; the connection
+    /// should be closed
+    Defunct,
+}
+
+/// Message-level Bolt session
+pub struct BoltSession<R: QueryRunner> {
+    runner: R,
+    state: BoltState,
+    result: Option<BoltResult>,
+}
+
+impl<R: QueryRunner> BoltSession<R> {
+    /// Create a session executing queries with `runner`
+    pub fn new(runner: R) -> Self {
+        Self {
+            runner,
+            state: BoltState::Connected,
+            result: None,
+        }
+    }
+
+    /// Current session state
+    pub fn state(&self) -> BoltState {
+        self.state
+    }
+
+    /// Handle a client message and return the responses to send back
+    pub fn handle(&mut self, message: BoltMessage) -> Vec<BoltMessage> {
+        match (self.state, message) {
+            (BoltState::Defunct, _) => Vec::new(),
+            (BoltState::Connected, BoltMessage::Hello { metadata }) => match self.runner.authenticate(&metadata) {
+                Ok(()) => {
+                    let mut metadata = HashMap::new();
+                    metadata.insert(
+                        "server".to_string(),
+                        PropertyValue::String(format!("QilbeeDB/{}", env!("CARGO_PKG_VERSION"))),
+                    );
+                    self.state = BoltState::Ready;
+                    vec![BoltMessage::success(metadata)]
+                }
+                Err(e) => {
+                    self.state = BoltState::Defunct;
+                    vec![BoltMessage::failure(status_codes::UNAUTHORIZED, &e.to_string())]
+                }
+            },
+            (BoltState::Connected, BoltMessage::Goodbye) => {
+                self.state = BoltState::Defunct;
+                Vec::new()
+            }
+            (BoltState::Connected, message) => {
+                self.state = BoltState::Defunct;
+                let message = format!("Expected HELLO, got {:?}", message.message_type());
+                vec![BoltMessage::failure(status_codes::REQUEST_INVALID, &message)]
+            }
+            (_, BoltMessage::Goodbye) => {
+                self.result = None;
+                self.state = BoltState::Defunct;
+                Vec::new()
+            }
+            (_, BoltMessage::Reset) => {
+                self.result = None;
+                self.state = BoltState::Ready;
+                vec![BoltMessage::success(HashMap::new())]
+            }
+            (BoltState::Failed, _) => vec![BoltMessage::Ignored],
+
+            (BoltState::Ready, BoltMessage::Run { query, parameters, metadata }) => {
+                match self.runner.run(&query, &parameters, &metadata) {
+                    Ok(result) => {
+                        let mut metadata = HashMap::new();
+                        metadata.insert(
+                            "fields".to_string(),
+                            PropertyValue::Array(
+                                result.fields().iter().cloned().map(PropertyValue::String).collect(),
+                            ),
+                        );
+                        self.result = Some(result);
+                        self.state = BoltState::Streaming;
+                        vec![BoltMessage::success(metadata)]
+                    }
+                    Err(e) => vec![self.fail(run_error_code(&e), &e.to_string())],
+                }
+            }
+            (BoltState::Streaming, BoltMessage::Pull { n }) => {
+                let Some(result) = self.result.as_mut() else {
+                    return vec![self.fail(status_codes::REQUEST_INVALID, "No open result")];
+                };
+                match result.pull(n) {
+                    Ok(messages) => {
+                        self.close_if_consumed();
+                        messages
+                    }
+                    Err(e) => vec![self.fail(result_error_code(&e), &e.to_string())],
+                }
+            }
+            (BoltState::Streaming, BoltMessage::Discard { n }) => {
+                let Some(result) = self.result.as_mut() else {
+                    return vec![self.fail(status_codes::REQUEST_INVALID, "No open result")];
+                };
+                match result.discard(n) {
+                    Ok(message) => {
+                        self.close_if_consumed();
+                        vec![message]
+                    }
+                    Err(e) => vec![self.fail(result_error_code(&e), &e.to_string())],
+                }
+            }
+
+            (_, BoltMessage::Begin { .. } | BoltMessage::Commit | BoltMessage::Rollback) => {
+                vec![self.fail(
+                    status_codes::NOT_SUPPORTED,
+                    "Explicit transactions are not supported over Bolt",
+                )]
+            }
+            (state, message) => {
+                let message = format!(
+                    "Message {:?} is not allowed in state {:?}",
+                    message.message_type(),
+                    state
+                );
+                vec![self.fail(status_codes::REQUEST_INVALID, &message)]
+            }
+        }
+    }
+
+    /// Return to `Ready` once the open result has no records left
+    fn close_if_consumed(&mut self) {
+        if let Some(result) = self.result.as_mut()
+            && matches!(result.has_more(), Ok(false))
+        {
+            self.result = None;
+            self.state = BoltState::Ready;
+        }
+    }
+
+    /// Enter the failed state and build the `FAILURE` response
+    fn fail(&mut self, code: &str, message: &str) -> BoltMessage {
+        self.result = None;
+        self.state = BoltState::Failed;
+        BoltMessage::failure(code, message)
+    }
+}
+
+/// Failure code for an error raised while running a query
+fn run_error_code(e: &Error) -> &'static str {
+    match e {
+        Error::QueryParse(_) => status_codes::SYNTAX_ERROR,
+        Error::InvalidQuery(_) => status_codes::STATEMENT_INVALID,
+        _ => status_codes::EXECUTION_FAILED,
+    }
+}
+
+/// Failure code for an error raised while pulling or discarding records
+fn result_error_code(e: &Error) -> &'static str {
+    match e {
+        Error::ValidationError(_) => status_codes::REQUEST_INVALID,
+        _ => status_codes::EXECUTION_FAILED,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Runner returning `count` integer records in batches of three
+    struct CountingRunner {
+        count: i64,
+    }
+
+    impl QueryRunner for CountingRunner {
+        fn authenticate(&mut self, hello: &HashMap<String, PropertyValue>) -> Result<()> {
+            match hello.get("credentials") {
+                Some(PropertyValue::String(password)) if password == "secret" => Ok(()),
+                _ => Err(Error::AuthenticationFailed("Invalid credentials".to_string())),
+            }
+        }
+
+        fn run(
+            &self,
+            query: &str,
+            _parameters: &HashMap<String, PropertyValue>,
+            _metadata: &HashMap<String, PropertyValue>,
+        ) -> Result<BoltResult> {
+            if query == "FAIL" {
+                return Err(Error::QueryParse("unexpected input".to_string()));
+            }
+            let records: Vec<Vec<PropertyValue>> =
+                (0..self.count).map(|i| vec![PropertyValue::Integer(i)]).collect();
+            let mut batches: Vec<_> = records.chunks(3).map(|c| Ok(c.to_vec())).collect();
+            if query == "FAIL LATE" {
+                batches.push(Err(Error::QueryExecution("Division by zero".to_string())));
+            }
+            let mut summary = HashMap::new();
+            summary.insert("type".to_string(), PropertyValue::String("r".to_string()));
+            Ok(BoltResult::new(vec!["n".to_string()], Box::new(batches.into_iter()), summary))
+        }
+    }
+
+    fn hello(password: &str) -> BoltMessage {
+        let mut metadata = HashMap::new();
+        metadata.insert("scheme".to_string(), PropertyValue::String("basic".to_string()));
+        metadata.insert("principal".to_string(), PropertyValue::String("alice".to_string()));
+        metadata.insert("credentials".to_string(), PropertyValue::String(password.to_string()));
+        BoltMessage::Hello { metadata }
+    }
+
+    fn authenticated(count: i64) -> BoltSession<CountingRunner> {
+        let mut session = BoltSession::new(CountingRunner { count });
+        assert!(matches!(session.handle(hello("secret")).as_slice(), [BoltMessage::Success { .. }]));
+        assert_eq!(session.state(), BoltState::Ready);
+        session
+    }
+
+    fn session(count: i64) -> BoltSession<CountingRunner> {
+        let mut session = authenticated(count);
+        let responses = session.handle(run("RETURN n"));
+        assert_eq!(responses.len(), 1);
+        assert_eq!(session.state(), BoltState::Streaming);
+        session
+    }
+
+    fn run(query: &str) -> BoltMessage {
+        BoltMessage::Run {
+            query: query.to_string(),
+            parameters: HashMap::new(),
+            metadata: HashMap::new(),
+        }
+    }
+
+    fn records(messages: &[BoltMessage]) -> Vec<i64> {
+        messages
+            .iter()
+            .filter_map(|m| match m {
+                BoltMessage::Record { fields } => fields[0].as_integer(),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn has_more(message: &BoltMessage) -> bool {
+        matches!(message, BoltMessage::Success { metadata }
+            if metadata.get("has_more") == Some(&PropertyValue::Boolean(true)))
+    }
 
     #[test]
     fn test_bolt_version() {
         assert_eq!(BOLT_VERSION, (4, 4));
     }
 
+    fn handshake(offers: [[u8; 4]; 4]) -> Vec<u8> {
+        let mut bytes = BOLT_MAGIC.to_vec();
+        for offer in offers {
+            bytes.extend_from_slice(&offer);
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_negotiate_highest_supported_version() {
+        // Client prefers 5.x, then offers 4.4; Bolt 5 is not spoken
+        let bytes = handshake([[0, 4, 4, 5], [0, 0, 4, 4], [0; 4], [0; 4]]);
+        let offers = parse_handshake(&bytes).unwrap();
+        assert_eq!(offers[0], VersionOffer { major: 5, minor: 4, range: 4 });
+        assert_eq!(negotiate_version(&offers, SUPPORTED_VERSIONS), Some(BoltVersion::V4_4));
+
+        // A version chosen from a wider list still has to be offered
+        let supported = [BoltVersion::new(5, 0), BoltVersion::V4_4];
+        assert_eq!(negotiate_version(&offers, &supported), Some(BoltVersion::new(5, 0)));
+        let offers = parse_handshake(&handshake([[0, 0, 4, 5], [0, 0, 4, 4], [0; 4], [0; 4]])).unwrap();
+        assert_eq!(negotiate_version(&offers, &supported), Some(BoltVersion::V4_4));
+
+        // A 4.x range reaching 4.4
+        let offers = parse_handshake(&handshake([[0, 2, 6, 4], [0; 4], [0; 4], [0; 4]])).unwrap();
+        assert_eq!(negotiate_version(&offers, SUPPORTED_VERSIONS), Some(BoltVersion::V4_4));
+    }
+
+    #[test]
+    fn test_negotiate_no_match() {
+        let offers = parse_handshake(&handshake([[0, 0, 0, 3], [0, 0, 1, 4], [0; 4], [0; 4]])).unwrap();
+        assert_eq!(negotiate_version(&offers, SUPPORTED_VERSIONS), None);
+
+        let offers = parse_handshake(&handshake([[0; 4]; 4])).unwrap();
+        assert_eq!(negotiate_version(&offers, SUPPORTED_VERSIONS), None);
+    }
+
     #[test]
-    fn test_bolt_handler_creation() {
-        let _handler = BoltHandler::new();
+    fn test_malformed_handshake() {
+        let mut bytes = handshake([[0, 0, 4, 4], [0; 4], [0; 4], [0; 4]]);
+        assert!(parse_handshake(&bytes[..12]).is_err());
+        bytes[0] = 0x00;
+        assert!(parse_handshake(&bytes).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_accept_handshake() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client
+            .write_all(&handshake([[0, 4, 4, 5], [0, 0, 4, 4], [0; 4], [0; 4]]))
+            .await
+            .unwrap();
+        assert_eq!(accept_handshake(&mut server).await.unwrap(), Some(BoltVersion::V4_4));
+        let mut response = [0u8; 4];
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(response, [0, 0, 4, 4]);
+
+        // A client offering only Bolt 5 is turned away
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client
+            .write_all(&handshake([[0, 4, 4, 5], [0; 4], [0; 4], [0; 4]]))
+            .await
+            .unwrap();
+        assert_eq!(accept_handshake(&mut server).await.unwrap(), None);
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(response, NO_VERSION);
+
+        // No common version: answered with zeros
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client
+            .write_all(&handshake([[0, 0, 0, 3], [0; 4], [0; 4], [0; 4]]))
+            .await
+            .unwrap();
+        assert_eq!(accept_handshake(&mut server).await.unwrap(), None);
+        client.read_exact(&mut response).await.unwrap();
+        assert_eq!(response, NO_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_accept_handshake_closes_on_malformed_input() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        // Wrong magic
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(&[0u8; HANDSHAKE_LEN]).await.unwrap();
+        assert!(accept_handshake(&mut server).await.is_err());
+        let mut buf = Vec::new();
+        assert_eq!(client.read_to_end(&mut buf).await.unwrap(), 0);
+
+        // Truncated handshake
+        let (mut client, mut server) = tokio::io::duplex(64);
+        client.write_all(&BOLT_MAGIC).await.unwrap();
+        client.shutdown().await.unwrap();
+        assert!(accept_handshake(&mut server).await.is_err());
+    }
+
+    #[test]
+    fn test_pull_in_batches() {
+        let mut session = session(5);
+
+        let first = session.handle(BoltMessage::Pull { n: 2 });
+        assert_eq!(records(&first), vec![0, 1]);
+        assert!(has_more(first.last().unwrap()));
+        assert_eq!(session.state(), BoltState::Streaming);
+
+        // Crosses the executor's batch boundary
+        let rest = session.handle(BoltMessage::Pull { n: 10 });
+        assert_eq!(records(&rest), vec![2, 3, 4]);
+        match rest.last().unwrap() {
+            BoltMessage::Success { metadata } => {
+                assert!(!metadata.contains_key("has_more"));
+                assert_eq!(metadata.get("type"), Some(&PropertyValue::String("r".to_string())));
+            }
+            other => panic!("expected SUCCESS, got {:?}", other),
+        }
+        assert_eq!(session.state(), BoltState::Ready);
+    }
+
+    #[test]
+    fn test_pull_all() {
+        let mut session = session(7);
+        let messages = session.handle(BoltMessage::Pull { n: ALL_RECORDS });
+        assert_eq!(records(&messages), (0..7).collect::<Vec<_>>());
+        assert!(!has_more(messages.last().unwrap()));
+        assert_eq!(session.state(), BoltState::Ready);
+    }
+
+    #[test]
+    fn test_pull_exact_count_reports_completion() {
+        let mut session = session(3);
+        let messages = session.handle(BoltMessage::Pull { n: 3 });
+        assert_eq!(records(&messages), vec![0, 1, 2]);
+        assert!(!has_more(messages.last().unwrap()));
+        assert_eq!(session.state(), BoltState::Ready);
+    }
+
+    #[test]
+    fn test_pull_zero_is_rejected() {
+        let mut session = session(3);
+        let messages = session.handle(BoltMessage::Pull { n: 0 });
+        assert!(matches!(messages.as_slice(), [BoltMessage::Failure { .. }]));
+        assert_eq!(session.state(), BoltState::Failed);
+
+        // Ignored until RESET
+        assert_eq!(session.handle(BoltMessage::Pull { n: 1 }), vec![BoltMessage::Ignored]);
+        session.handle(BoltMessage::Reset);
+        assert_eq!(session.state(), BoltState::Ready);
+    }
+
+    #[test]
+    fn test_discard() {
+        let mut session = session(5);
+
+        let partial = session.handle(BoltMessage::Discard { n: 4 });
+        assert!(has_more(&partial[0]));
+        let last = session.handle(BoltMessage::Pull { n: ALL_RECORDS });
+        assert_eq!(records(&last), vec![4]);
+
+        let mut session = self::session(5);
+        let discarded = session.handle(BoltMessage::Discard { n: ALL_RECORDS });
+        assert_eq!(discarded.len(), 1);
+        assert!(!has_more(&discarded[0]));
+        assert_eq!(session.state(), BoltState::Ready);
+    }
+
+    #[test]
+    fn test_empty_result() {
+        let mut session = session(0);
+        let messages = session.handle(BoltMessage::Pull { n: 10 });
+        assert!(matches!(messages.as_slice(), [BoltMessage::Success { .. }]));
+        assert_eq!(session.state(), BoltState::Ready);
+    }
+
+    #[test]
+    fn test_run_failure_and_state_checks() {
+        let mut session = authenticated(1);
+
+        // PULL without an open result
+        assert!(matches!(
+            session.handle(BoltMessage::Pull { n: 1 }).as_slice(),
+            [BoltMessage::Failure { .. }]
+        ));
+        session.handle(BoltMessage::Reset);
+
+        match session.handle(run("FAIL")).as_slice() {
+            [BoltMessage::Failure { metadata }] => assert_eq!(
+                metadata.get("code"),
+                Some(&PropertyValue::String(status_codes::SYNTAX_ERROR.to_string()))
+            ),
+            other => panic!("expected FAILURE, got {:?}", other),
+        }
+        assert_eq!(session.state(), BoltState::Failed);
+
+        session.handle(BoltMessage::Goodbye);
+        assert_eq!(session.state(), BoltState::Defunct);
+        assert!(session.handle(BoltMessage::Reset).is_empty());
+    }
+    #[test]
+    fn test_pull_failure_after_records() {
+        let mut session = authenticated(3);
+        session.handle(run("FAIL LATE"));
+
+        // Checking for more records after the first batch reaches the error
+        match session.handle(BoltMessage::Pull { n: 3 }).as_slice() {
+            [BoltMessage::Failure { metadata }] => assert_eq!(
+                metadata.get("code"),
+                Some(&PropertyValue::String(status_codes::EXECUTION_FAILED.to_string()))
+            ),
+            other => panic!("expected FAILURE, got {:?}", other),
+        }
+        assert_eq!(session.state(), BoltState::Failed);
+    }
+
+    #[test]
+    fn test_hello_authenticates() {
+        // Nothing but HELLO is accepted first
+        let mut session = BoltSession::new(CountingRunner { count: 1 });
+        assert!(matches!(session.handle(run("RETURN n")).as_slice(), [BoltMessage::Failure { .. }]));
+        assert_eq!(session.state(), BoltState::Defunct);
+
+        let mut session = BoltSession::new(CountingRunner { count: 1 });
+        match session.handle(hello("wrong")).as_slice() {
+            [BoltMessage::Failure { metadata }] => assert_eq!(
+                metadata.get("code"),
+                Some(&PropertyValue::String(status_codes::UNAUTHORIZED.to_string()))
+            ),
+            other => panic!("expected FAILURE, got {:?}", other),
+        }
+        assert_eq!(session.state(), BoltState::Defunct);
+        assert!(session.handle(BoltMessage::Reset).is_empty());
+        assert!(session.handle(run("RETURN n")).is_empty());
+
+        let mut session = authenticated(1);
+        assert!(matches!(session.handle(hello("secret")).as_slice(), [BoltMessage::Failure { .. }]));
+        assert_eq!(session.state(), BoltState::Failed);
+    }
+
+    #[test]
+    fn test_message_round_trip() {
+        let mut parameters = HashMap::new();
+        parameters.insert("name".to_string(), PropertyValue::String("Alice".to_string()));
+        let messages = vec![
+            hello("secret"),
+            BoltMessage::Goodbye,
+            BoltMessage::Reset,
+            BoltMessage::Run { query: "RETURN $name".to_string(), parameters, metadata: HashMap::new() },
+            BoltMessage::Pull { n: 100 },
+            BoltMessage::Discard { n: ALL_RECORDS },
+            BoltMessage::Record { fields: vec![PropertyValue::Integer(1), PropertyValue::Null] },
+            BoltMessage::failure(status_codes::SYNTAX_ERROR, "bad"),
+            BoltMessage::Ignored,
+        ];
+        for message in messages {
+            assert_eq!(BoltMessage::decode(&message.encode()).unwrap(), message);
+        }
+
+        // PULL {} selects every record
+        assert_eq!(BoltMessage::decode(&[0xB1, 0x3F, 0xA0]).unwrap(), BoltMessage::Pull { n: ALL_RECORDS });
+        assert!(BoltMessage::decode(&[0xB0, 0x55]).is_err());
+        assert!(BoltMessage::decode(&[0xB0, 0x02, 0xC0]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_chunked_messages() {
+        let record = BoltMessage::Record { fields: vec![PropertyValue::String("x".repeat(100_000))] };
+        let (mut client, mut server) = tokio::io::duplex(256 * 1024);
+
+        // A keep-alive chunk, then a message split over two chunks
+        client.write_all(&[0, 0]).await.unwrap();
+        write_message(&mut client, &record).await.unwrap();
+        drop(client);
+
+        let bytes = read_message(&mut server).await.unwrap().unwrap();
+        assert_eq!(BoltMessage::decode(&bytes).unwrap(), record);
+        assert!(read_message(&mut server).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_serve_connection() {
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let connection = tokio::spawn(serve_connection(server, CountingRunner { count: 5 }));
+
+        client.write_all(&handshake([[0, 0, 4, 4], [0; 4], [0; 4], [0; 4]])).await.unwrap();
+        let mut version = [0u8; 4];
+        client.read_exact(&mut version).await.unwrap();
+        assert_eq!(version, [0, 0, 4, 4]);
+
+        for message in [hello("secret"), run("RETURN n"), BoltMessage::Pull { n: ALL_RECORDS }, BoltMessage::Goodbye] {
+            write_message(&mut client, &message).await.unwrap();
+        }
+        let mut responses = Vec::new();
+        while let Some(bytes) = read_message(&mut client).await.unwrap() {
+            responses.push(BoltMessage::decode(&bytes).unwrap());
+        }
+        connection.await.unwrap().unwrap();
+
+        // HELLO and RUN succeed, then five records and the summary
+        assert_eq!(responses.len(), 8);
+        assert!(matches!(&responses[0], BoltMessage::Success { metadata } if metadata.contains_key("server")));
+        assert_eq!(records(&responses), vec![0, 1, 2, 3, 4]);
+        assert!(matches!(responses.last(), Some(BoltMessage::Success { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_serve_connection_rejects_bad_credentials() {
+        let (mut client, server) = tokio::io::duplex(64 * 1024);
+        let connection = tokio::spawn(serve_connection(server, CountingRunner { count: 5 }));
+
+        client.write_all(&handshake([[0, 0, 4, 4], [0; 4], [0; 4], [0; 4]])).await.unwrap();
+        client.read_exact(&mut [0u8; 4]).await.unwrap();
+        write_message(&mut client, &hello("wrong")).await.unwrap();
+
+        let bytes = read_message(&mut client).await.unwrap().unwrap();
+        assert!(matches!(BoltMessage::decode(&bytes).unwrap(), BoltMessage::Failure { .. }));
+        // The server hangs up after the failed HELLO
+        assert!(read_message(&mut client).await.unwrap().is_none());
+        connection.await.unwrap().unwrap();
     }
 }
@@ -0,0 +1,235 @@
+//! gRPC protocol implementation
+//!
+//! Message types and the `QilbeeDb` service definition are generated from
+//! `proto/qilbee.proto`. The server crate implements [`QilbeeDb`]; this module
+//! provides the conversions between the generated messages and the core and
+//! protocol-level types.
+
+use crate::message::{QueryResult, QueryStats, Request};
+use qilbee_core::{Property, PropertyValue};
+use std::collections::HashMap;
+
+/// Generated protobuf messages and service stubs
+pub mod proto {
+    tonic::include_proto!("qilbee.v1");
+}
+
+pub use proto::qilbee_db_client::QilbeeDbClient;
+pub use proto::qilbee_db_server::{QilbeeDb, QilbeeDbServer};
+
+/// Default gRPC port
+pub const DEFAULT_GRPC_PORT: u16 = 7688;
+
+/// Rows per streamed chunk when the request doesn't specify a batch size
+pub const DEFAULT_STREAM_BATCH_SIZE: usize = 1000;
+
+impl From<&PropertyValue> for proto::Value {
+    fn from(value: &PropertyValue) -> Self {
+        use proto::value::Kind;
+
+        let kind = match value {
+            PropertyValue::Null => Kind::NullValue(proto::NullValue::NullValue as i32),
+            PropertyValue::Boolean(b) => Kind::BoolValue(*b),
+            PropertyValue::Integer(i) => Kind::IntValue(*i),
+            PropertyValue::Float(f) => Kind::FloatValue(*f),
+            PropertyValue::String(s) => Kind::StringValue(s.clone()),
+            PropertyValue::Bytes(b) => Kind::BytesValue(b.clone()),
+            PropertyValue::Array(list) => Kind::ListValue(proto::ValueList {
+                values: list.iter().map(Into::into).collect(),
+            }),
+            PropertyValue::Map(map) => Kind::MapValue(proto::ValueMap {
+                entries: values_to_proto(map),
+            }),
+            // Temporal values travel as their integer encoding, as in the HTTP API
+            PropertyValue::Date(d) => Kind::IntValue(*d as i64),
+            PropertyValue::Time(t) => Kind::IntValue(*t),
+            PropertyValue::DateTime(dt) => Kind::IntValue(*dt),
+            PropertyValue::Duration(dur) => Kind::IntValue(*dur),
+            PropertyValue::Point2D { x, y, srid } => Kind::MapValue(proto::ValueMap {
+                entries: HashMap::from([
+                    ("x".to_string(), PropertyValue::Float(*x).into()),
+                    ("y".to_string(), PropertyValue::Float(*y).into()),
+                    ("srid".to_string(), PropertyValue::Integer(*srid as i64).into()),
+                ]),
+            }),
+            PropertyValue::Point3D { x, y, z, srid } => Kind::MapValue(proto::ValueMap {
+                entries: HashMap::from([
+                    ("x".to_string(), PropertyValue::Float(*x).into()),
+                    ("y".to_string(), PropertyValue::Float(*y).into()),
+                    ("z".to_string(), PropertyValue::Float(*z).into()),
+                    ("srid".to_string(), PropertyValue::Integer(*srid as i64).into()),
+                ]),
+            }),
+        };
+
+        proto::Value { kind: Some(kind) }
+    }
+}
+
+impl From<PropertyValue> for proto::Value {
+    fn from(value: PropertyValue) -> Self {
+        (&value).into()
+    }
+}
+
+impl From<proto::Value> for PropertyValue {
+    fn from(value: proto::Value) -> Self {
+        use proto::value::Kind;
+
+        match value.kind {
+            None | Some(Kind::NullValue(_)) => PropertyValue::Null,
+            Some(Kind::BoolValue(b)) => PropertyValue::Boolean(b),
+            Some(Kind::IntValue(i)) => PropertyValue::Integer(i),
+            Some(Kind::FloatValue(f)) => PropertyValue::Float(f),
+            Some(Kind::StringValue(s)) => PropertyValue::String(s),
+            Some(Kind::BytesValue(b)) => PropertyValue::Bytes(b),
+            Some(Kind::ListValue(list)) => {
+                PropertyValue::Array(list.values.into_iter().map(Into::into).collect())
+            }
+            Some(Kind::MapValue(map)) => PropertyValue::Map(values_from_proto(map.entries)),
+        }
+    }
+}
+
+/// Convert a map of property values to protobuf values
+pub fn values_to_proto(values: &HashMap<String, PropertyValue>) -> HashMap<String, proto::Value> {
+    values.iter().map(|(k, v)| (k.clone(), v.into())).collect()
+}
+
+/// Convert a map of protobuf values to property values
+pub fn values_from_proto(values: HashMap<String, proto::Value>) -> HashMap<String, PropertyValue> {
+    values.into_iter().map(|(k, v)| (k, v.into())).collect()
+}
+
+/// Convert an entity's properties to protobuf values
+pub fn properties_to_proto(properties: &Property) -> HashMap<String, proto::Value> {
+    properties.iter().map(|(k, v)| (k.clone(), v.into())).collect()
+}
+
+/// Convert protobuf values to an entity property collection
+pub fn properties_from_proto(values: HashMap<String, proto::Value>) -> Property {
+    let mut properties = Property::new();
+    for (k, v) in values {
+        properties.set(k, PropertyValue::from(v));
+    }
+    properties
+}
+
+/// Convert a row of property values to a protobuf row
+pub fn row_to_proto(row: &[PropertyValue]) -> proto::Row {
+    proto::Row {
+        values: row.iter().map(Into::into).collect(),
+    }
+}
+
+impl From<proto::QueryRequest> for Request {
+    fn from(request: proto::QueryRequest) -> Self {
+        Request::Query {
+            statement: request.cypher,
+            parameters: values_from_proto(request.parameters),
+        }
+    }
+}
+
+impl From<&QueryStats> for proto::QueryStats {
+    fn from(stats: &QueryStats) -> Self {
+        proto::QueryStats {
+            nodes_created: stats.nodes_created,
+            nodes_deleted: stats.nodes_deleted,
+            relationships_created: stats.relationships_created,
+            relationships_deleted: stats.relationships_deleted,
+            properties_set: stats.properties_set,
+            labels_added: stats.labels_added,
+            labels_removed: stats.labels_removed,
+            execution_time_ms: stats.execution_time_ms,
+        }
+    }
+}
+
+impl From<&QueryResult> for proto::QueryResponse {
+    fn from(result: &QueryResult) -> Self {
+        proto::QueryResponse {
+            columns: result.columns.clone(),
+            rows: result.rows.iter().map(|row| row_to_proto(row)).collect(),
+            stats: Some((&result.stats).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_round_trip() {
+        let mut map = HashMap::new();
+        map.insert("k".to_string(), PropertyValue::Integer(1));
+        let values = vec![
+            PropertyValue::Null,
+            PropertyValue::Boolean(true),
+            PropertyValue::Integer(42),
+            PropertyValue::Float(1.5),
+            PropertyValue::String("Alice".to_string()),
+            PropertyValue::Bytes(vec![1, 2, 3]),
+            PropertyValue::Array(vec![PropertyValue::Integer(1), PropertyValue::Null]),
+            PropertyValue::Map(map),
+        ];
+
+        for value in values {
+            let encoded = proto::Value::from(&value);
+            assert_eq!(PropertyValue::from(encoded), value);
+        }
+    }
+
+    #[test]
+    fn test_temporal_and_point_values() {
+        assert_eq!(
+            PropertyValue::from(proto::Value::from(PropertyValue::DateTime(1_000))),
+            PropertyValue::Integer(1_000)
+        );
+
+        let point = PropertyValue::from(proto::Value::from(PropertyValue::Point2D {
+            x: 1.0,
+            y: 2.0,
+            srid: 4326,
+        }));
+        let map = point.as_map().unwrap();
+        assert_eq!(map.get("x"), Some(&PropertyValue::Float(1.0)));
+        assert_eq!(map.get("srid"), Some(&PropertyValue::Integer(4326)));
+    }
+
+    #[test]
+    fn test_query_request_into_request() {
+        let request = proto::QueryRequest {
+            graph: "social".to_string(),
+            cypher: "MATCH (n) RETURN n".to_string(),
+            parameters: HashMap::from([("x".to_string(), PropertyValue::Integer(1).into())]),
+            batch_size: 0,
+        };
+
+        match Request::from(request) {
+            Request::Query { statement, parameters } => {
+                assert_eq!(statement, "MATCH (n) RETURN n");
+                assert_eq!(parameters.get("x"), Some(&PropertyValue::Integer(1)));
+            }
+            other => panic!("unexpected request: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_query_result_into_response() {
+        let result = QueryResult::new(
+            vec!["name".to_string()],
+            vec![vec![PropertyValue::String("Alice".to_string())]],
+        );
+
+        let response = proto::QueryResponse::from(&result);
+        assert_eq!(response.columns, vec!["name".to_string()]);
+        assert_eq!(response.rows.len(), 1);
+        assert_eq!(
+            PropertyValue::from(response.rows[0].values[0].clone()),
+            PropertyValue::String("Alice".to_string())
+        );
+        assert!(response.stats.is_some());
+    }
+}
@@ -5,13 +5,17 @@
 //! # Protocols
 //!
 //! - **Bolt**: Neo4j-compatible binary protocol
+//! - **PackStream**: Value serialization used by Bolt messages
 //! - **HTTP**: REST API for web clients
-//! - **gRPC**: High-performance binary protocol (future)
+//! - **gRPC**: High-performance binary protocol with streamed query results
 
 pub mod bolt;
+pub mod grpc;
 pub mod http;
 pub mod message;
+pub mod packstream;
 
-pub use bolt::{BoltMessage, BoltVersion};
+pub use bolt::{BoltMessage, BoltResult, BoltSession, BoltVersion, QueryRunner};
+pub use grpc::{QilbeeDb, QilbeeDbClient, QilbeeDbServer};
 pub use http::{HttpMethod, HttpRequest, HttpResponse, StatusCode};
 pub use message::{Request, Response};
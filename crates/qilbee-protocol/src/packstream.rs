@@ -0,0 +1,435 @@
+//! PackStream serialization
+//!
+//! The binary value format Bolt messages are written in. Every value starts
+//! with a marker byte that carries its type and, for small values, its size.
+//! Bolt messages are structures: a field count, a one-byte tag and the
+//! fields themselves.
+//!
+//! Temporal and spatial values use the Bolt 4.4 structures: dates and
+//! times as `Date`, `LocalTime`, `DateTime` (UTC, tag `F`) and `Duration`,
+//! and points as `Point2D` and `Point3D`.
+
+use qilbee_core::{Error, PropertyValue, Result};
+use std::collections::HashMap;
+
+const NULL: u8 = 0xC0;
+const FLOAT_64: u8 = 0xC1;
+const FALSE: u8 = 0xC2;
+const TRUE: u8 = 0xC3;
+const INT_8: u8 = 0xC8;
+const INT_16: u8 = 0xC9;
+const INT_32: u8 = 0xCA;
+const INT_64: u8 = 0xCB;
+const BYTES_8: u8 = 0xCC;
+const BYTES_16: u8 = 0xCD;
+const BYTES_32: u8 = 0xCE;
+const TINY_STRING: u8 = 0x80;
+const STRING_8: u8 = 0xD0;
+const STRING_16: u8 = 0xD1;
+const STRING_32: u8 = 0xD2;
+const TINY_LIST: u8 = 0x90;
+const LIST_8: u8 = 0xD4;
+const LIST_16: u8 = 0xD5;
+const LIST_32: u8 = 0xD6;
+const TINY_MAP: u8 = 0xA0;
+const MAP_8: u8 = 0xD8;
+const MAP_16: u8 = 0xD9;
+const MAP_32: u8 = 0xDA;
+const TINY_STRUCT: u8 = 0xB0;
+
+/// Structure tags of the values PackStream carries
+mod tags {
+    pub const DATE: u8 = b'D';
+    pub const LOCAL_TIME: u8 = b't';
+    pub const DATE_TIME: u8 = b'F';
+    pub const DURATION: u8 = b'E';
+    pub const POINT_2D: u8 = b'X';
+    pub const POINT_3D: u8 = b'Y';
+}
+
+const NANOS_PER_SECOND: i64 = 1_000_000_000;
+
+/// Deepest nesting of lists, maps and structures accepted when decoding
+pub const MAX_DEPTH: usize = 64;
+
+/// Append the PackStream encoding of `value`
+pub fn encode(buf: &mut Vec<u8>, value: &PropertyValue) {
+    match value {
+        PropertyValue::Null => buf.push(NULL),
+        PropertyValue::Boolean(b) => buf.push(if *b { TRUE } else { FALSE }),
+        PropertyValue::Integer(i) => encode_int(buf, *i),
+        PropertyValue::Float(f) => {
+            buf.push(FLOAT_64);
+            buf.extend_from_slice(&f.to_be_bytes());
+        }
+        PropertyValue::String(s) => encode_string(buf, s),
+        PropertyValue::Array(items) => {
+            encode_size(buf, items.len(), TINY_LIST, [LIST_8, LIST_16, LIST_32]);
+            for item in items {
+                encode(buf, item);
+            }
+        }
+        PropertyValue::Map(map) => encode_map(buf, map),
+        PropertyValue::Bytes(bytes) => {
+            match bytes.len() {
+                len if len <= u8::MAX as usize => buf.extend_from_slice(&[BYTES_8, len as u8]),
+                len if len <= u16::MAX as usize => {
+                    buf.push(BYTES_16);
+                    buf.extend_from_slice(&(len as u16).to_be_bytes());
+                }
+                len => {
+                    buf.push(BYTES_32);
+                    buf.extend_from_slice(&(len as u32).to_be_bytes());
+                }
+            }
+            buf.extend_from_slice(bytes);
+        }
+        PropertyValue::Date(days) => {
+            encode_struct_header(buf, 1, tags::DATE);
+            encode_int(buf, i64::from(*days));
+        }
+        PropertyValue::Time(nanos) => {
+            encode_struct_header(buf, 1, tags::LOCAL_TIME);
+            encode_int(buf, *nanos);
+        }
+        PropertyValue::DateTime(millis) => {
+            encode_struct_header(buf, 3, tags::DATE_TIME);
+            encode_int(buf, millis.div_euclid(1000));
+            encode_int(buf, millis.rem_euclid(1000) * 1_000_000);
+            encode_int(buf, 0);
+        }
+        PropertyValue::Duration(nanos) => {
+            encode_struct_header(buf, 4, tags::DURATION);
+            encode_int(buf, 0);
+            encode_int(buf, 0);
+            encode_int(buf, nanos.div_euclid(NANOS_PER_SECOND));
+            encode_int(buf, nanos.rem_euclid(NANOS_PER_SECOND));
+        }
+        PropertyValue::Point2D { x, y, srid } => {
+            encode_struct_header(buf, 3, tags::POINT_2D);
+            encode_int(buf, i64::from(*srid));
+            encode(buf, &PropertyValue::Float(*x));
+            encode(buf, &PropertyValue::Float(*y));
+        }
+        PropertyValue::Point3D { x, y, z, srid } => {
+            encode_struct_header(buf, 4, tags::POINT_3D);
+            encode_int(buf, i64::from(*srid));
+            encode(buf, &PropertyValue::Float(*x));
+            encode(buf, &PropertyValue::Float(*y));
+            encode(buf, &PropertyValue::Float(*z));
+        }
+    }
+}
+
+/// Append a map with string keys
+pub fn encode_map(buf: &mut Vec<u8>, map: &HashMap<String, PropertyValue>) {
+    encode_size(buf, map.len(), TINY_MAP, [MAP_8, MAP_16, MAP_32]);
+    for (key, value) in map {
+        encode_string(buf, key);
+        encode(buf, value);
+    }
+}
+
+/// Append the header of a structure with `fields` fields
+pub fn encode_struct_header(buf: &mut Vec<u8>, fields: u8, tag: u8) {
+    debug_assert!(fields < 16, "structures have at most 15 fields");
+    buf.extend_from_slice(&[TINY_STRUCT | fields, tag]);
+}
+
+fn encode_int(buf: &mut Vec<u8>, i: i64) {
+    if (-16..=127).contains(&i) {
+        buf.push(i as u8);
+    } else if let Ok(i) = i8::try_from(i) {
+        buf.extend_from_slice(&[INT_8, i as u8]);
+    } else if let Ok(i) = i16::try_from(i) {
+        buf.push(INT_16);
+        buf.extend_from_slice(&i.to_be_bytes());
+    } else if let Ok(i) = i32::try_from(i) {
+        buf.push(INT_32);
+        buf.extend_from_slice(&i.to_be_bytes());
+    } else {
+        buf.push(INT_64);
+        buf.extend_from_slice(&i.to_be_bytes());
+    }
+}
+
+fn encode_string(buf: &mut Vec<u8>, s: &str) {
+    encode_size(buf, s.len(), TINY_STRING, [STRING_8, STRING_16, STRING_32]);
+    buf.extend_from_slice(s.as_bytes());
+}
+
+/// Append the marker of a sized value: tiny below 16, else 8, 16 or 32 bits
+fn encode_size(buf: &mut Vec<u8>, size: usize, tiny: u8, [size_8, size_16, size_32]: [u8; 3]) {
+    if size < 16 {
+        buf.push(tiny | size as u8);
+    } else if size <= u8::MAX as usize {
+        buf.extend_from_slice(&[size_8, size as u8]);
+    } else if size <= u16::MAX as usize {
+        buf.push(size_16);
+        buf.extend_from_slice(&(size as u16).to_be_bytes());
+    } else {
+        buf.push(size_32);
+        buf.extend_from_slice(&(size as u32).to_be_bytes());
+    }
+}
+
+/// Decode one value from the front of `input`, advancing past it
+///
+/// Fails on malformed or truncated input and on values nested deeper than
+/// [`MAX_DEPTH`].
+pub fn decode(input: &mut &[u8]) -> Result<PropertyValue> {
+    decode_nested(input, 0)
+}
+
+fn decode_nested(input: &mut &[u8], depth: usize) -> Result<PropertyValue> {
+    if depth > MAX_DEPTH {
+        return Err(invalid(format!("PackStream values nest deeper than {}", MAX_DEPTH)));
+    }
+    let marker = take_u8(input)?;
+    let value = match marker {
+        NULL => PropertyValue::Null,
+        FALSE => PropertyValue::Boolean(false),
+        TRUE => PropertyValue::Boolean(true),
+        FLOAT_64 => PropertyValue::Float(f64::from_be_bytes(take_array(input)?)),
+        0x00..=0x7F => PropertyValue::Integer(i64::from(marker)),
+        0xF0..=0xFF => PropertyValue::Integer(i64::from(marker as i8)),
+        INT_8 => PropertyValue::Integer(i64::from(take_u8(input)? as i8)),
+        INT_16 => PropertyValue::Integer(i64::from(i16::from_be_bytes(take_array(input)?))),
+        INT_32 => PropertyValue::Integer(i64::from(i32::from_be_bytes(take_array(input)?))),
+        INT_64 => PropertyValue::Integer(i64::from_be_bytes(take_array(input)?)),
+        BYTES_8 | BYTES_16 | BYTES_32 => {
+            let len = decode_size(input, marker - BYTES_8)?;
+            PropertyValue::Bytes(take(input, len)?.to_vec())
+        }
+        0x80..=0x8F | STRING_8 | STRING_16 | STRING_32 => {
+            let len = sized(input, marker, TINY_STRING, STRING_8)?;
+            PropertyValue::String(take_string(input, len)?)
+        }
+        0x90..=0x9F | LIST_8 | LIST_16 | LIST_32 => {
+            let len = sized(input, marker, TINY_LIST, LIST_8)?;
+            let items = (0..len).map(|_| decode_nested(input, depth + 1)).collect::<Result<_>>()?;
+            PropertyValue::Array(items)
+        }
+        0xA0..=0xAF | MAP_8 | MAP_16 | MAP_32 => {
+            let len = sized(input, marker, TINY_MAP, MAP_8)?;
+            PropertyValue::Map(decode_map_entries(input, len, depth + 1)?)
+        }
+        0xB0..=0xBF => decode_struct(input, marker & 0x0F, depth + 1)?,
+        _ => return Err(invalid(format!("Unknown PackStream marker 0x{:02X}", marker))),
+    };
+    Ok(value)
+}
+
+/// Decode a map with string keys
+pub fn decode_map(input: &mut &[u8]) -> Result<HashMap<String, PropertyValue>> {
+    match decode(input)? {
+        PropertyValue::Map(map) => Ok(map),
+        other => Err(invalid(format!("Expected a map, got {:?}", other))),
+    }
+}
+
+/// Decode a structure header, returning its field count and tag
+pub fn decode_struct_header(input: &mut &[u8]) -> Result<(u8, u8)> {
+    let marker = take_u8(input)?;
+    if marker & 0xF0 != TINY_STRUCT {
+        return Err(invalid(format!("Expected a structure, got marker 0x{:02X}", marker)));
+    }
+    Ok((marker & 0x0F, take_u8(input)?))
+}
+
+fn decode_map_entries(input: &mut &[u8], len: usize, depth: usize) -> Result<HashMap<String, PropertyValue>> {
+    let mut map = HashMap::with_capacity(len.min(1024));
+    for _ in 0..len {
+        let key = match decode_nested(input, depth)? {
+            PropertyValue::String(key) => key,
+            other => return Err(invalid(format!("Map keys must be strings, got {:?}", other))),
+        };
+        let value = decode_nested(input, depth)?;
+        map.insert(key, value);
+    }
+    Ok(map)
+}
+
+/// Decode the temporal and spatial structures clients send as parameters
+fn decode_struct(input: &mut &[u8], fields: u8, depth: usize) -> Result<PropertyValue> {
+    let tag = take_u8(input)?;
+    let mut values = Vec::with_capacity(fields as usize);
+    for _ in 0..fields {
+        values.push(decode_nested(input, depth)?);
+    }
+
+    let int = |i: usize| match values.get(i) {
+        Some(PropertyValue::Integer(n)) => Ok(*n),
+        _ => Err(invalid(format!("Structure 0x{:02X} expects an integer field {}", tag, i))),
+    };
+    let float = |i: usize| match values.get(i) {
+        Some(PropertyValue::Float(f)) => Ok(*f),
+        _ => Err(invalid(format!("Structure 0x{:02X} expects a float field {}", tag, i))),
+    };
+    let srid = || u32::try_from(int(0)?).map_err(|_| invalid("Point SRID out of range".to_string()));
+
+    match (tag, fields) {
+        (tags::DATE, 1) => {
+            let days = i32::try_from(int(0)?).map_err(|_| invalid("Date out of range".to_string()))?;
+            Ok(PropertyValue::Date(days))
+        }
+        (tags::LOCAL_TIME, 1) => Ok(PropertyValue::Time(int(0)?)),
+        (tags::DATE_TIME, 3) => {
+            // Seconds are local to the offset; stored date-times are UTC
+            let utc_seconds = int(0)?.saturating_sub(int(2)?);
+            Ok(PropertyValue::DateTime(utc_seconds.saturating_mul(1000).saturating_add(int(1)? / 1_000_000)))
+        }
+        (tags::DURATION, 4) => {
+            if int(0)? != 0 || int(1)? != 0 {
+                return Err(invalid("Durations with months or days are not supported".to_string()));
+            }
+            Ok(PropertyValue::Duration(int(2)?.saturating_mul(NANOS_PER_SECOND).saturating_add(int(3)?)))
+        }
+        (tags::POINT_2D, 3) => Ok(PropertyValue::Point2D { x: float(1)?, y: float(2)?, srid: srid()? }),
+        (tags::POINT_3D, 4) => Ok(PropertyValue::Point3D {
+            x: float(1)?,
+            y: float(2)?,
+            z: float(3)?,
+            srid: srid()?,
+        }),
+        _ => Err(invalid(format!(
+            "Unsupported structure 0x{:02X} with {} fields",
+            tag, fields
+        ))),
+    }
+}
+
+/// Size of a tiny (`marker - tiny`) or 8, 16 or 32-bit sized value
+fn sized(input: &mut &[u8], marker: u8, tiny: u8, size_8: u8) -> Result<usize> {
+    if marker & 0xF0 == tiny {
+        Ok((marker & 0x0F) as usize)
+    } else {
+        decode_size(input, marker - size_8)
+    }
+}
+
+/// Read a size of 1, 2 or 4 bytes for `width` 0, 1 or 2
+fn decode_size(input: &mut &[u8], width: u8) -> Result<usize> {
+    let size = match width {
+        0 => usize::from(take_u8(input)?),
+        1 => usize::from(u16::from_be_bytes(take_array(input)?)),
+        _ => u32::from_be_bytes(take_array(input)?) as usize,
+    };
+    // Every element takes at least one byte
+    if size > input.len() {
+        return Err(invalid("PackStream value is truncated".to_string()));
+    }
+    Ok(size)
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if input.len() < len {
+        return Err(invalid("PackStream value is truncated".to_string()));
+    }
+    let (head, rest) = input.split_at(len);
+    *input = rest;
+    Ok(head)
+}
+
+fn take_u8(input: &mut &[u8]) -> Result<u8> {
+    Ok(take(input, 1)?[0])
+}
+
+fn take_array<const N: usize>(input: &mut &[u8]) -> Result<[u8; N]> {
+    let mut bytes = [0u8; N];
+    bytes.copy_from_slice(take(input, N)?);
+    Ok(bytes)
+}
+
+fn take_string(input: &mut &[u8], len: usize) -> Result<String> {
+    String::from_utf8(take(input, len)?.to_vec()).map_err(|_| invalid("String is not valid UTF-8".to_string()))
+}
+
+fn invalid(message: String) -> Error {
+    Error::ValidationError(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(value: PropertyValue) -> PropertyValue {
+        let mut buf = Vec::new();
+        encode(&mut buf, &value);
+        let mut input = buf.as_slice();
+        let decoded = decode(&mut input).unwrap();
+        assert!(input.is_empty(), "trailing bytes after {:?}", value);
+        decoded
+    }
+
+    #[test]
+    fn test_integer_markers() {
+        let encoded = |i: i64| {
+            let mut buf = Vec::new();
+            encode(&mut buf, &PropertyValue::Integer(i));
+            buf
+        };
+        assert_eq!(encoded(1), vec![0x01]);
+        assert_eq!(encoded(-16), vec![0xF0]);
+        assert_eq!(encoded(-17), vec![INT_8, 0xEF]);
+        assert_eq!(encoded(128), vec![INT_16, 0x00, 0x80]);
+        assert_eq!(encoded(70_000), vec![INT_32, 0x00, 0x01, 0x11, 0x70]);
+        assert_eq!(encoded(i64::MIN)[0], INT_64);
+
+        for i in [0, 127, -16, -17, -128, 129, -32_768, 70_000, i64::MAX, i64::MIN] {
+            assert_eq!(round_trip(PropertyValue::Integer(i)), PropertyValue::Integer(i));
+        }
+    }
+
+    #[test]
+    fn test_round_trip_values() {
+        let mut map = HashMap::new();
+        map.insert("name".to_string(), PropertyValue::String("Alice".to_string()));
+        map.insert("tags".to_string(), PropertyValue::Array(vec![PropertyValue::Null; 20]));
+        let values = vec![
+            PropertyValue::Null,
+            PropertyValue::Boolean(true),
+            PropertyValue::Float(-1.5),
+            PropertyValue::String("x".repeat(300)),
+            PropertyValue::Bytes(vec![7; 70_000]),
+            PropertyValue::Map(map),
+            PropertyValue::Date(-365),
+            PropertyValue::Time(3_600_000_000_000),
+            PropertyValue::DateTime(-1_500),
+            PropertyValue::Duration(-1_500_000_000),
+            PropertyValue::Point2D { x: 1.0, y: 2.0, srid: 7203 },
+            PropertyValue::Point3D { x: 1.0, y: 2.0, z: 3.0, srid: 4979 },
+        ];
+        for value in values {
+            assert_eq!(round_trip(value.clone()), value);
+        }
+    }
+
+    #[test]
+    fn test_date_time_offset_is_applied() {
+        // 01:00 at +01:00 is midnight UTC
+        let mut buf = Vec::new();
+        encode_struct_header(&mut buf, 3, tags::DATE_TIME);
+        for field in [3600, 0, 3600] {
+            encode(&mut buf, &PropertyValue::Integer(field));
+        }
+        assert_eq!(decode(&mut buf.as_slice()).unwrap(), PropertyValue::DateTime(0));
+    }
+
+    #[test]
+    fn test_malformed_input() {
+        // Truncated string, oversized list, unknown marker, non-string key
+        assert!(decode(&mut [0x85, b'a'].as_slice()).is_err());
+        assert!(decode(&mut [LIST_32, 0xFF, 0xFF, 0xFF, 0xFF].as_slice()).is_err());
+        assert!(decode(&mut [0xE0].as_slice()).is_err());
+        assert!(decode(&mut [0xA1, 0x01, 0x01].as_slice()).is_err());
+        assert!(decode(&mut [0xB1, b'Z', 0x01].as_slice()).is_err());
+        assert!(decode(&mut [].as_slice()).is_err());
+
+        // Nesting is bounded
+        let mut nested = vec![0x91; MAX_DEPTH + 1];
+        nested.push(NULL);
+        assert!(decode(&mut nested.as_slice()).is_err());
+        assert!(decode(&mut &nested[1..]).is_ok());
+    }
+}
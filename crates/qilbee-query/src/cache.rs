@@ -0,0 +1,286 @@
+//! Query result cache
+//!
+//! [`QueryCache`] keeps the results of read-only queries, keyed by graph,
+//! query text, parameters and the graph's version. Every write to a graph
+//! bumps its version in the same batch as the write, so a cached result is
+//! only found while no write has landed since it was computed. Entries also
+//! expire after a TTL.
+
+use crate::executor::QueryResult;
+use qilbee_core::{GraphId, PropertyValue};
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+
+/// Default time a cached result stays valid
+pub const DEFAULT_QUERY_CACHE_TTL: Duration = Duration::from_secs(30);
+
+/// Default maximum number of cached results
+pub const DEFAULT_QUERY_CACHE_ENTRIES: usize = 1024;
+
+/// Default maximum number of rows of a cached result
+pub const DEFAULT_QUERY_CACHE_MAX_ROWS: usize = 1000;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    graph_id: GraphId,
+    query: String,
+    /// Parameters in a canonical form, see [`canonical_parameters`]
+    parameters: String,
+    version: u64,
+}
+
+#[derive(Debug)]
+struct CacheEntry {
+    result: QueryResult,
+    expires_at: Instant,
+}
+
+/// Cache of read-only query results, shared by the executors of all graphs
+#[derive(Debug)]
+pub struct QueryCache {
+    ttl: Duration,
+    max_entries: usize,
+    max_rows: usize,
+    entries: Mutex<HashMap<CacheKey, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl Default for QueryCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_QUERY_CACHE_TTL, DEFAULT_QUERY_CACHE_ENTRIES)
+    }
+}
+
+impl QueryCache {
+    /// Create a cache holding up to `max_entries` results for `ttl` each
+    pub fn new(ttl: Duration, max_entries: usize) -> Self {
+        Self {
+            ttl,
+            max_entries,
+            max_rows: DEFAULT_QUERY_CACHE_MAX_ROWS,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Builder: don't cache results with more than `max_rows` rows
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// Results with more rows than this are not cached
+    pub fn max_rows(&self) -> usize {
+        self.max_rows
+    }
+
+    /// Cached result of `query` at `version` of the graph, if still valid
+    pub fn get(
+        &self,
+        graph_id: GraphId,
+        query: &str,
+        params: &HashMap<String, PropertyValue>,
+        version: u64,
+    ) -> Option<QueryResult> {
+        let key = CacheKey {
+            graph_id,
+            query: query.to_string(),
+            parameters: canonical_parameters(params),
+            version,
+        };
+        let result = self.entries.lock().ok().and_then(|entries| {
+            entries
+                .get(&key)
+                .filter(|entry| entry.expires_at > Instant::now())
+                .map(|entry| entry.result.clone())
+        });
+        let counter = if result.is_some() { &self.hits } else { &self.misses };
+        counter.fetch_add(1, Ordering::Relaxed);
+        result
+    }
+
+    /// Cache the result of `query` computed at `version` of the graph
+    ///
+    /// `version` must be read before the query executes, so a write landing
+    /// during execution leaves the entry under a version no reader asks for
+    /// again. Entries of older versions of the graph and expired entries are
+    /// dropped; if the cache is still full, the entry closest to expiring is.
+    pub fn insert(
+        &self,
+        graph_id: GraphId,
+        query: &str,
+        params: &HashMap<String, PropertyValue>,
+        version: u64,
+        result: QueryResult,
+    ) {
+        if self.max_entries == 0 || result.rows.len() > self.max_rows {
+            return;
+        }
+        let Ok(mut entries) = self.entries.lock() else {
+            return;
+        };
+
+        let now = Instant::now();
+        entries.retain(|key, entry| entry.expires_at > now && !(key.graph_id == graph_id && key.version < version));
+        if entries.len() >= self.max_entries {
+            let oldest = entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.expires_at)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                entries.remove(&oldest);
+            }
+        }
+
+        let key = CacheKey {
+            graph_id,
+            query: query.to_string(),
+            parameters: canonical_parameters(params),
+            version,
+        };
+        entries.insert(key, CacheEntry { result, expires_at: now + self.ttl });
+    }
+
+    /// Number of cached results, including expired ones not yet dropped
+    pub fn len(&self) -> usize {
+        self.entries.lock().map(|entries| entries.len()).unwrap_or(0)
+    }
+
+    /// Whether no results are cached
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Drop every cached result
+    pub fn clear(&self) {
+        if let Ok(mut entries) = self.entries.lock() {
+            entries.clear();
+        }
+    }
+
+    /// Lookups answered from the cache
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Lookups that found no valid entry
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+}
+
+/// Parameters in a form that is equal exactly when the parameters are,
+/// with the keys of maps in sorted order
+fn canonical_parameters(params: &HashMap<String, PropertyValue>) -> String {
+    let mut names: Vec<_> = params.keys().collect();
+    names.sort();
+    let mut out = String::new();
+    for name in names {
+        let _ = write!(out, "{:?}=", name);
+        write_canonical(&mut out, &params[name]);
+        out.push(';');
+    }
+    out
+}
+
+fn write_canonical(out: &mut String, value: &PropertyValue) {
+    match value {
+        PropertyValue::Map(map) => {
+            let mut keys: Vec<_> = map.keys().collect();
+            keys.sort();
+            out.push('{');
+            for key in keys {
+                let _ = write!(out, "{:?}:", key);
+                write_canonical(out, &map[key]);
+                out.push(',');
+            }
+            out.push('}');
+        }
+        PropertyValue::Array(items) => {
+            out.push('[');
+            for item in items {
+                write_canonical(out, item);
+                out.push(',');
+            }
+            out.push(']');
+        }
+        other => {
+            let _ = write!(out, "{:?}", other);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::ExecutionStats;
+
+    fn result(value: i64) -> QueryResult {
+        QueryResult {
+            columns: vec!["n".to_string()],
+            rows: vec![vec![PropertyValue::Integer(value)]],
+            stats: ExecutionStats::default(),
+        }
+    }
+
+    #[test]
+    fn test_cache_key() {
+        let cache = QueryCache::default();
+        let graph_id = GraphId::from_name("test");
+        let params = HashMap::from([("name".to_string(), PropertyValue::from("Alice"))]);
+        cache.insert(graph_id, "MATCH (n) RETURN n", &params, 1, result(1));
+
+        assert_eq!(cache.get(graph_id, "MATCH (n) RETURN n", &params, 1).unwrap().rows, result(1).rows);
+        assert!(cache.get(graph_id, "MATCH (n) RETURN n", &params, 2).is_none());
+        assert!(cache.get(graph_id, "MATCH (n) RETURN n", &HashMap::new(), 1).is_none());
+        assert!(cache.get(graph_id, "MATCH (m) RETURN m", &params, 1).is_none());
+        assert!(cache.get(GraphId::from_name("other"), "MATCH (n) RETURN n", &params, 1).is_none());
+        assert_eq!((cache.hits(), cache.misses()), (1, 4));
+
+        // A newer version replaces the entries of older ones
+        cache.insert(graph_id, "MATCH (m) RETURN m", &params, 2, result(2));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_canonical_parameters() {
+        let map = |entries: &[(&str, i64)]| {
+            PropertyValue::Map(entries.iter().map(|(k, v)| (k.to_string(), PropertyValue::Integer(*v))).collect())
+        };
+        let a = HashMap::from([("m".to_string(), map(&[("a", 1), ("b", 2), ("c", 3)]))]);
+        let b = HashMap::from([("m".to_string(), map(&[("c", 3), ("b", 2), ("a", 1)]))]);
+        let c = HashMap::from([("m".to_string(), map(&[("a", 1), ("b", 2), ("c", 4)]))]);
+        assert_eq!(canonical_parameters(&a), canonical_parameters(&b));
+        assert_ne!(canonical_parameters(&a), canonical_parameters(&c));
+
+        let null = HashMap::from([("x".to_string(), PropertyValue::Null)]);
+        let nan = HashMap::from([("x".to_string(), PropertyValue::Float(f64::NAN))]);
+        assert_ne!(canonical_parameters(&null), canonical_parameters(&nan));
+    }
+
+    #[test]
+    fn test_cache_limits() {
+        let graph_id = GraphId::from_name("test");
+        let params = HashMap::new();
+
+        let cache = QueryCache::new(Duration::ZERO, 10);
+        cache.insert(graph_id, "RETURN 1", &params, 0, result(1));
+        assert!(cache.get(graph_id, "RETURN 1", &params, 0).is_none());
+
+        let cache = QueryCache::new(DEFAULT_QUERY_CACHE_TTL, 2);
+        for query in ["RETURN 1", "RETURN 2", "RETURN 3"] {
+            cache.insert(graph_id, query, &params, 0, result(1));
+        }
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(graph_id, "RETURN 3", &params, 0).is_some());
+
+        let cache = QueryCache::default().with_max_rows(0);
+        cache.insert(graph_id, "RETURN 1", &params, 0, result(1));
+        assert!(cache.is_empty());
+    }
+}
@@ -7,12 +7,22 @@
 //! - Index-aware execution
 //! - Cost-based operator selection
 
+use crate::cache::QueryCache;
+use crate::functions::evaluate_function;
 use crate::parser::*;
-use crate::planner::{ExecutionPlan, PhysicalOperator};
-use qilbee_core::{EntityId, Error, Node, NodeId, PropertyValue, Relationship, Result};
-use qilbee_graph::Graph;
-use std::collections::HashMap;
-use std::sync::Arc;
+use crate::planner::{AggregateExpression, AggregateFunction, ExecutionPlan, PhysicalOperator, PlanDescription};
+use qilbee_core::{
+    compare_floats, EntityId, Error, Label, MergeMode, Node, NodeId, Property, PropertyValue, Relationship,
+    RelationshipId, Result,
+};
+use qilbee_graph::{
+    Graph, Index, PathOptions, DEFAULT_PAGERANK_DAMPING, DEFAULT_PAGERANK_ITERATIONS, DEFAULT_PAGERANK_TOLERANCE,
+};
+use regex::Regex;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 /// Query execution result
 #[derive(Debug, Clone)]
@@ -35,6 +45,8 @@ pub struct ExecutionStats {
     pub relationships_created: u64,
     pub relationships_deleted: u64,
     pub properties_set: u64,
+    pub indexes_added: u64,
+    pub indexes_removed: u64,
     pub rows_returned: u64,
     pub execution_time_ms: u64,
     pub nodes_scanned: u64,
@@ -42,24 +54,489 @@ pub struct ExecutionStats {
     pub cache_hits: u64,
 }
 
+impl ExecutionStats {
+    /// Whether the query created, deleted or changed anything
+    pub fn contains_updates(&self) -> bool {
+        self.nodes_created > 0
+            || self.nodes_deleted > 0
+            || self.relationships_created > 0
+            || self.relationships_deleted > 0
+            || self.properties_set > 0
+            || self.indexes_added > 0
+            || self.indexes_removed > 0
+    }
+}
+
+/// Default number of rows per chunk for streamed results
+pub const DEFAULT_STREAM_CHUNK_SIZE: usize = 1000;
+
+/// Streaming query result
+///
+/// Hands out result rows in bounded chunks so callers can serialize and send
+/// large result sets incrementally instead of buffering the whole response.
+/// Rows of a streamed read query are produced as chunks are pulled, so a
+/// chunk fails with the error of the first row that could not be produced.
+#[derive(Debug)]
+pub struct QueryResultStream {
+    /// Column names
+    columns: Vec<String>,
+
+    /// Remaining rows
+    rows: StreamRows,
+
+    /// Maximum rows per chunk
+    chunk_size: usize,
+
+    /// Number of chunks handed out so far
+    chunks_emitted: usize,
+
+    /// Execution statistics
+    stats: ExecutionStats,
+}
+
+/// Where a stream takes its rows from
+#[derive(Debug)]
+enum StreamRows {
+    /// Rows of an executed query
+    Materialized(std::vec::IntoIter<Vec<PropertyValue>>),
+    /// Rows produced on demand by the plan's row-at-a-time operators
+    Pipelined(RowPipeline),
+}
+
+impl QueryResultStream {
+    /// Create a stream over a materialized query result
+    pub fn new(result: QueryResult, chunk_size: usize) -> Self {
+        Self {
+            columns: result.columns,
+            rows: StreamRows::Materialized(result.rows.into_iter()),
+            chunk_size: chunk_size.max(1),
+            chunks_emitted: 0,
+            stats: result.stats,
+        }
+    }
+
+    /// Column names
+    pub fn columns(&self) -> &[String] {
+        &self.columns
+    }
+
+    /// Execution statistics
+    ///
+    /// `rows_returned` and `execution_time_ms` grow as rows are produced and
+    /// are final once the stream is exhausted.
+    pub fn stats(&self) -> &ExecutionStats {
+        &self.stats
+    }
+
+    /// Maximum rows per chunk
+    pub fn chunk_size(&self) -> usize {
+        self.chunk_size
+    }
+
+    /// Number of chunks handed out so far
+    pub fn chunks_emitted(&self) -> usize {
+        self.chunks_emitted
+    }
+
+    /// Number of rows not yet handed out, or `None` while rows are still
+    /// being produced
+    pub fn remaining_rows(&self) -> Option<usize> {
+        match &self.rows {
+            StreamRows::Materialized(rows) => Some(rows.len()),
+            StreamRows::Pipelined(_) => None,
+        }
+    }
+
+    /// Produce the next chunk of a pipelined stream
+    fn next_pipelined(&mut self) -> Option<Result<Vec<Vec<PropertyValue>>>> {
+        let StreamRows::Pipelined(pipeline) = &mut self.rows else {
+            return None;
+        };
+
+        let start = Instant::now();
+        let mut chunk = Vec::new();
+        let mut failed = None;
+        while chunk.len() < self.chunk_size {
+            match pipeline.next() {
+                Some(Ok(row)) => chunk.push(row),
+                Some(Err(e)) => {
+                    failed = Some(e);
+                    break;
+                }
+                None => break,
+            }
+        }
+        pipeline.elapsed += start.elapsed();
+        self.stats.nodes_scanned += std::mem::take(&mut pipeline.nodes_scanned);
+        self.stats.execution_time_ms = pipeline.elapsed.as_millis() as u64;
+        self.stats.rows_returned += chunk.len() as u64;
+
+        // A failed or exhausted pipeline ends the stream
+        if failed.is_some() || chunk.len() < self.chunk_size {
+            self.rows = StreamRows::Materialized(Vec::new().into_iter());
+        }
+        if let Some(e) = failed {
+            return Some(Err(e));
+        }
+        if chunk.is_empty() {
+            return None;
+        }
+        self.chunks_emitted += 1;
+        Some(Ok(chunk))
+    }
+}
+
+impl Iterator for QueryResultStream {
+    type Item = Result<Vec<Vec<PropertyValue>>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let StreamRows::Materialized(rows) = &mut self.rows else {
+            return self.next_pipelined();
+        };
+        if rows.len() == 0 {
+            return None;
+        }
+        let chunk: Vec<_> = rows.by_ref().take(self.chunk_size).collect();
+        self.chunks_emitted += 1;
+        Some(Ok(chunk))
+    }
+}
+
+/// A row-at-a-time operator applied while a stream is pulled
+enum RowStep {
+    /// Keep rows for which `predicate` holds
+    Filter {
+        columns: Vec<String>,
+        predicate: Expression,
+        relationships: HashSet<String>,
+    },
+    /// Replace each row by the values of `expressions`
+    Project {
+        columns: Vec<String>,
+        expressions: Vec<Expression>,
+        relationships: HashSet<String>,
+    },
+    /// Extend each row by the paths leaving its `from_var` node
+    Expand {
+        columns: Vec<String>,
+        from_var: String,
+        rel_var: Option<String>,
+        to_var: String,
+        to_labels: Vec<String>,
+        path_var: Option<String>,
+        rel_types: Vec<String>,
+        direction: qilbee_core::Direction,
+        min_hops: u32,
+        max_hops: u32,
+        /// Expanded rows of the last input row not yet handed on
+        pending: VecDeque<Vec<PropertyValue>>,
+    },
+    /// Rows still to drop
+    Skip(usize),
+    /// Rows still to let through
+    Limit(usize),
+}
+
+impl RowStep {
+    /// An expansion step over rows with `columns`, which become the columns
+    /// of the expanded rows
+    fn expand(
+        executor: &QueryExecutor,
+        columns: &mut Vec<String>,
+        expansion: &Expansion<'_>,
+        min_hops: u32,
+        max_hops: u32,
+    ) -> Result<Self> {
+        // Expanding no rows checks the variables and names the new columns
+        let (expanded, _) = executor.execute_expand(columns.clone(), Vec::new(), expansion, min_hops, max_hops)?;
+        Ok(RowStep::Expand {
+            columns: std::mem::replace(columns, expanded),
+            from_var: expansion.from_var.to_string(),
+            rel_var: expansion.rel_var.clone(),
+            to_var: expansion.to_var.to_string(),
+            to_labels: expansion.to_labels.to_vec(),
+            path_var: expansion.path_var.clone(),
+            rel_types: expansion.rel_types.to_vec(),
+            direction: expansion.direction,
+            min_hops,
+            max_hops,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+/// Where a pipeline takes its rows from
+enum RowSource {
+    /// Rows of operators executed when the stream was opened
+    Rows(std::vec::IntoIter<Vec<PropertyValue>>),
+    /// Node IDs read from storage one page at a time
+    Scan {
+        label: Option<String>,
+        /// Last node ID read, to continue the next page after
+        after: Option<NodeId>,
+        page: std::vec::IntoIter<Vec<PropertyValue>>,
+        page_size: usize,
+        exhausted: bool,
+    },
+}
+
+/// Rows of a read query produced one at a time
+///
+/// A node scan at the bottom of the plan reads node IDs a page at a time,
+/// and the expansions, filters, projections, `SKIP` and `LIMIT` above it run
+/// per row as rows are pulled. Other operators below those run when the
+/// stream is opened.
+struct RowPipeline {
+    executor: QueryExecutor,
+    params: HashMap<String, PropertyValue>,
+    source: RowSource,
+    /// Operators applied to each source row, innermost first
+    steps: Vec<RowStep>,
+    /// Nodes read from storage since the stream last collected them
+    nodes_scanned: u64,
+    /// Time spent executing, including producing the rows pulled so far
+    elapsed: Duration,
+}
+
+impl std::fmt::Debug for RowPipeline {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RowPipeline")
+            .field("scan", &matches!(self.source, RowSource::Scan { .. }))
+            .field("steps", &self.steps.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl RowPipeline {
+    /// Next row of the source, reading the next page of a scan when needed
+    fn next_source_row(&mut self) -> Option<Result<Vec<PropertyValue>>> {
+        let (label, after, page, page_size, exhausted) = match &mut self.source {
+            RowSource::Rows(rows) => return rows.next().map(Ok),
+            RowSource::Scan { label, after, page, page_size, exhausted } => (label, after, page, page_size, exhausted),
+        };
+        if let Some(row) = page.next() {
+            return Some(Ok(row));
+        }
+        if *exhausted {
+            return None;
+        }
+
+        let ids = match self.executor.graph.node_ids_after(label.as_deref(), *after, *page_size) {
+            Ok(ids) => ids,
+            Err(e) => return Some(Err(e)),
+        };
+        *exhausted = ids.len() < *page_size;
+        *after = ids.last().copied().or(*after);
+        self.nodes_scanned += ids.len() as u64;
+        *page = ids
+            .into_iter()
+            .map(|id| vec![PropertyValue::Integer(id.as_internal() as i64)])
+            .collect::<Vec<_>>()
+            .into_iter();
+        page.next().map(Ok)
+    }
+
+    /// Next row out of the first `depth` steps
+    fn pull(&mut self, depth: usize) -> Option<Result<Vec<PropertyValue>>> {
+        let Some(index) = depth.checked_sub(1) else {
+            return self.next_source_row();
+        };
+        loop {
+            match &mut self.steps[index] {
+                // Once a LIMIT is used up no further row can pass it
+                RowStep::Limit(0) => return None,
+                RowStep::Expand { pending, .. } => {
+                    if let Some(row) = pending.pop_front() {
+                        return Some(Ok(row));
+                    }
+                }
+                _ => {}
+            }
+
+            let row = match self.pull(index)? {
+                Ok(row) => row,
+                Err(e) => return Some(Err(e)),
+            };
+            match self.apply(index, row) {
+                Ok(Some(row)) => return Some(Ok(row)),
+                Ok(None) => continue,
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+
+    /// Run step `index` on `row`, returning `None` for a row it drops or
+    /// expands into its pending rows
+    fn apply(&mut self, index: usize, row: Vec<PropertyValue>) -> Result<Option<Vec<PropertyValue>>> {
+        match &mut self.steps[index] {
+            RowStep::Filter { columns, predicate, relationships } => {
+                let bindings: HashMap<String, PropertyValue> =
+                    columns.iter().cloned().zip(row.iter().cloned()).collect();
+                match self.executor.evaluate_expression(predicate, &bindings, relationships, &self.params)? {
+                    PropertyValue::Boolean(true) => Ok(Some(row)),
+                    _ => Ok(None),
+                }
+            }
+            RowStep::Project { columns, expressions, relationships } => {
+                let bindings: HashMap<String, PropertyValue> = columns.iter().cloned().zip(row).collect();
+                expressions
+                    .iter()
+                    .map(|expr| self.executor.evaluate_expression(expr, &bindings, relationships, &self.params))
+                    .collect::<Result<Vec<_>>>()
+                    .map(Some)
+            }
+            RowStep::Expand {
+                columns, from_var, rel_var, to_var, to_labels, path_var, rel_types, direction, min_hops, max_hops, pending,
+            } => {
+                let expansion = Expansion { from_var, rel_var, to_var, to_labels, path_var, rel_types, direction: *direction };
+                let (_, rows) = self.executor.execute_expand(columns.clone(), vec![row], &expansion, *min_hops, *max_hops)?;
+                pending.extend(rows);
+                Ok(None)
+            }
+            RowStep::Skip(remaining) => {
+                if *remaining > 0 {
+                    *remaining -= 1;
+                    return Ok(None);
+                }
+                Ok(Some(row))
+            }
+            RowStep::Limit(remaining) => {
+                *remaining -= 1;
+                Ok(Some(row))
+            }
+        }
+    }
+}
+
+impl Iterator for RowPipeline {
+    type Item = Result<Vec<PropertyValue>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.pull(self.steps.len())
+    }
+}
+
+/// Parameters shared by single-hop and variable-length expansion
+struct Expansion<'a> {
+    from_var: &'a str,
+    rel_var: &'a Option<String>,
+    to_var: &'a str,
+    to_labels: &'a [String],
+    path_var: &'a Option<String>,
+    rel_types: &'a [String],
+    direction: qilbee_core::Direction,
+}
+
+/// Parameters of a shortest path search between bound endpoints
+struct PathSearch<'a> {
+    from_var: &'a str,
+    rel_var: &'a Option<String>,
+    to_var: &'a str,
+    path_var: &'a Option<String>,
+    direction: qilbee_core::Direction,
+    options: PathOptions,
+    min_hops: u32,
+    all: bool,
+}
+
+/// Variables bound in one row, for evaluating SET expressions
+struct RowBindings<'a> {
+    values: HashMap<String, PropertyValue>,
+    /// Variables holding relationship ids
+    relationships: &'a HashSet<String>,
+}
+
+/// Parameters of a MERGE
+struct MergeSpec<'a> {
+    variable: &'a str,
+    labels: &'a [String],
+    properties: &'a [(String, Expression)],
+    on_create: &'a [SetItem],
+    on_match: &'a [SetItem],
+}
+
+/// What one operator did during a profiled execution, including its inputs
+#[derive(Debug, Clone, Copy, Default)]
+struct OperatorProfile {
+    rows: u64,
+    db_hits: u64,
+    elapsed: Duration,
+}
+
 /// Query executor
 pub struct QueryExecutor {
     graph: Arc<Graph>,
+    merge_mode: MergeMode,
+    /// Distance within which floats compare equal in expressions
+    float_epsilon: Option<f64>,
+    /// Per-operator profiles keyed by operator address, while profiling
+    profile: Option<Arc<Mutex<HashMap<usize, OperatorProfile>>>>,
+    /// Results of read-only queries, for [`Self::execute_cached`]
+    cache: Option<Arc<QueryCache>>,
 }
 
 impl QueryExecutor {
     /// Create a new query executor
     pub fn new(graph: Arc<Graph>) -> Self {
-        Self { graph }
+        let float_epsilon = graph.storage().float_epsilon();
+        Self {
+            graph,
+            merge_mode: MergeMode::Deep,
+            float_epsilon,
+            profile: None,
+            cache: None,
+        }
+    }
+
+    /// Builder: how `SET n += {map}` merges nested maps (deep by default)
+    pub fn with_merge_mode(mut self, mode: MergeMode) -> Self {
+        self.merge_mode = mode;
+        self
+    }
+
+    /// Merge a `SET n += {map}` update into an entity's properties
+    pub fn merge_properties(&self, properties: &mut Property, update: Property) {
+        properties.merge_with(update, self.merge_mode);
+    }
+
+    /// An executor with the same settings that works on `graph`
+    fn with_graph(&self, graph: Arc<Graph>) -> Self {
+        Self {
+            graph,
+            merge_mode: self.merge_mode,
+            float_epsilon: self.float_epsilon,
+            profile: self.profile.clone(),
+            cache: self.cache.clone(),
+        }
     }
 
     /// Execute a query from an execution plan
+    ///
+    /// Plans of `EXPLAIN` queries are not executed and return no rows. A
+    /// read-only query reads from a snapshot of the graph taken when it
+    /// starts, so all of its rows reflect one committed state. A query that
+    /// writes runs in a transaction (see [`Graph::transaction`]) committed
+    /// once the whole plan has succeeded; if any part fails, none of its
+    /// writes are stored.
     pub fn execute(&self, plan: &ExecutionPlan, params: &HashMap<String, PropertyValue>) -> Result<QueryResult> {
         let start = std::time::Instant::now();
         let mut stats = ExecutionStats::default();
 
-        // Execute the physical plan
-        let (columns, rows) = self.execute_plan(&plan.root, params, &mut stats)?;
+        if plan.mode == QueryMode::Explain {
+            return Ok(QueryResult { columns: Vec::new(), rows: Vec::new(), stats });
+        }
+
+        // Execute the physical plan; a failed plan drops its transaction,
+        // which discards its writes
+        let graph = match plan.read_only {
+            true => self.graph.snapshot(),
+            false => self.graph.transaction()?,
+        };
+        let executor = self.with_graph(Arc::new(graph));
+        let (columns, rows) = executor.execute_plan(&plan.root, params, &mut stats)?;
+        if !plan.read_only {
+            executor.graph.commit()?;
+        }
 
         stats.execution_time_ms = start.elapsed().as_millis() as u64;
         stats.rows_returned = rows.len() as u64;
@@ -71,12 +548,214 @@ impl QueryExecutor {
         })
     }
 
-    /// Execute a physical operator
+    /// Execute a query, recording rows, database hits and time per operator
+    ///
+    /// Returns the result along with the plan description with its actual
+    /// counts filled in.
+    pub fn profile(
+        &self,
+        plan: &ExecutionPlan,
+        params: &HashMap<String, PropertyValue>,
+    ) -> Result<(QueryResult, PlanDescription)> {
+        let profiler = Self {
+            graph: Arc::clone(&self.graph),
+            merge_mode: self.merge_mode,
+            float_epsilon: self.float_epsilon,
+            profile: Some(Arc::new(Mutex::new(HashMap::new()))),
+            cache: None,
+        };
+        let result = profiler.execute(plan, params)?;
+
+        let profiles = profiler.profile.map(|p| p.lock().unwrap().clone()).unwrap_or_default();
+        let description = profiled_description(&plan.root, &plan.description, &profiles);
+        Ok((result, description))
+    }
+
+    /// Builder: serve repeated read-only queries from `cache`
+    pub fn with_cache(mut self, cache: Arc<QueryCache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Execute a query, answering read-only queries from the cache when one
+    /// is set
+    ///
+    /// `query` is the query text the plan was made from. A result served
+    /// from the cache has `cache_hits` set to 1 in its stats. Queries that
+    /// write, and `EXPLAIN` and `PROFILE`, always execute.
+    pub fn execute_cached(
+        &self,
+        query: &str,
+        plan: &ExecutionPlan,
+        params: &HashMap<String, PropertyValue>,
+    ) -> Result<QueryResult> {
+        let cache = match &self.cache {
+            Some(cache) if plan.read_only && plan.mode == QueryMode::Execute => cache,
+            _ => return self.execute(plan, params),
+        };
+
+        // Read before executing, so a write landing meanwhile leaves the
+        // result under a version that is already stale
+        let version = self.graph.version()?;
+        if let Some(mut result) = cache.get(self.graph.id(), query, params, version) {
+            result.stats.cache_hits = 1;
+            result.stats.execution_time_ms = 0;
+            return Ok(result);
+        }
+        let result = self.execute(plan, params)?;
+        cache.insert(self.graph.id(), query, params, version, result.clone());
+        Ok(result)
+    }
+
+    /// Execute a query and hand out its rows as a chunked stream
+    ///
+    /// For read queries, the expansions, filters, projections, `SKIP` and
+    /// `LIMIT` at the top of the plan run as chunks are pulled, and a node
+    /// scan beneath them reads node IDs from storage one chunk at a time, so
+    /// rows past a chunk are neither read, evaluated nor held in memory until
+    /// they are asked for. They read from the snapshot the query started
+    /// with, however long the stream stays open. Queries that write, and `EXPLAIN` and `PROFILE`,
+    /// execute in full up front.
+    pub fn execute_stream(
+        &self,
+        plan: &ExecutionPlan,
+        params: &HashMap<String, PropertyValue>,
+        chunk_size: usize,
+    ) -> Result<QueryResultStream> {
+        if !plan.read_only || plan.mode != QueryMode::Execute {
+            let result = self.execute(plan, params)?;
+            return Ok(QueryResultStream::new(result, chunk_size));
+        }
+
+        let start = Instant::now();
+        let mut stats = ExecutionStats::default();
+        let executor = Self {
+            graph: Arc::new(self.graph.snapshot()),
+            merge_mode: self.merge_mode,
+            float_epsilon: self.float_epsilon,
+            profile: None,
+            cache: None,
+        };
+
+        // Peel the row-at-a-time operators off the top of the plan
+        let mut pipelined = Vec::new();
+        let mut source = &plan.root;
+        while let PhysicalOperator::Filter { input, .. }
+        | PhysicalOperator::Project { input, .. }
+        | PhysicalOperator::Expand { input, .. }
+        | PhysicalOperator::VarLengthExpand { input, .. }
+        | PhysicalOperator::Skip { input, .. }
+        | PhysicalOperator::Limit { input, .. } = source
+        {
+            pipelined.push(source);
+            source = input;
+        }
+
+        // A node scan reads its IDs as rows are pulled
+        let (mut columns, rows) = match source {
+            PhysicalOperator::NodeScan { variable, labels, .. } => {
+                let scan = RowSource::Scan {
+                    label: labels.first().cloned(),
+                    after: None,
+                    page: Vec::new().into_iter(),
+                    page_size: chunk_size.max(1),
+                    exhausted: false,
+                };
+                (vec![variable.clone()], scan)
+            }
+            _ => {
+                let (columns, rows) = executor.execute_plan(source, params, &mut stats)?;
+                (columns, RowSource::Rows(rows.into_iter()))
+            }
+        };
+
+        let mut steps = Vec::with_capacity(pipelined.len());
+        for operator in pipelined.into_iter().rev() {
+            steps.push(match operator {
+                PhysicalOperator::Filter { input, predicate, .. } => RowStep::Filter {
+                    columns: columns.clone(),
+                    predicate: predicate.clone(),
+                    relationships: input.relationship_variables(),
+                },
+                PhysicalOperator::Project { input, expressions, aliases, .. } => RowStep::Project {
+                    columns: std::mem::replace(&mut columns, aliases.clone()),
+                    expressions: expressions.clone(),
+                    relationships: input.relationship_variables(),
+                },
+                PhysicalOperator::Expand { from_var, rel_var, to_var, to_labels, rel_types, direction, .. } => {
+                    let expansion = Expansion { from_var, rel_var, to_var, to_labels, path_var: &None, rel_types, direction: *direction };
+                    RowStep::expand(&executor, &mut columns, &expansion, 1, 1)?
+                }
+                PhysicalOperator::VarLengthExpand {
+                    from_var, rel_var, to_var, to_labels, path_var, rel_types, direction, min_hops, max_hops, ..
+                } => {
+                    let expansion = Expansion { from_var, rel_var, to_var, to_labels, path_var, rel_types, direction: *direction };
+                    RowStep::expand(&executor, &mut columns, &expansion, *min_hops, *max_hops)?
+                }
+                PhysicalOperator::Skip { count, .. } => RowStep::Skip(*count),
+                PhysicalOperator::Limit { count, .. } => RowStep::Limit(*count),
+                _ => unreachable!("only row-at-a-time operators are pipelined"),
+            });
+        }
+
+        let pipeline = RowPipeline {
+            executor,
+            params: params.clone(),
+            source: rows,
+            steps,
+            nodes_scanned: 0,
+            elapsed: start.elapsed(),
+        };
+        stats.execution_time_ms = pipeline.elapsed.as_millis() as u64;
+
+        Ok(QueryResultStream {
+            columns,
+            rows: StreamRows::Pipelined(pipeline),
+            chunk_size: chunk_size.max(1),
+            chunks_emitted: 0,
+            stats,
+        })
+    }
+
+    /// Execute a physical operator, recording its profile while profiling
     fn execute_plan(
         &self,
         operator: &PhysicalOperator,
         params: &HashMap<String, PropertyValue>,
         stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let Some(profile) = &self.profile else {
+            return self.execute_operator(operator, params, stats);
+        };
+
+        let start = Instant::now();
+        let db_hits_before = stats.nodes_scanned + stats.index_hits;
+        let result = self.execute_operator(operator, params, stats)?;
+        let entry = OperatorProfile {
+            rows: result.1.len() as u64,
+            db_hits: stats.nodes_scanned + stats.index_hits - db_hits_before,
+            elapsed: start.elapsed(),
+        };
+        // Operators under an Apply run once per row; their profiles add up
+        profile
+            .lock()
+            .unwrap()
+            .entry(operator as *const PhysicalOperator as usize)
+            .and_modify(|total| {
+                total.rows += entry.rows;
+                total.db_hits += entry.db_hits;
+                total.elapsed += entry.elapsed;
+            })
+            .or_insert(entry);
+        Ok(result)
+    }
+
+    /// Execute a physical operator
+    fn execute_operator(
+        &self,
+        operator: &PhysicalOperator,
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
     ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
         match operator {
             PhysicalOperator::NodeScan { variable, labels, .. } => {
@@ -87,6 +766,14 @@ impl QueryExecutor {
                 self.execute_index_seek(variable, label, property, value, params, stats)
             }
 
+            PhysicalOperator::CompositeIndexSeek { variable, index, values, .. } => {
+                self.execute_composite_index_seek(variable, index, values, params, stats)
+            }
+
+            PhysicalOperator::IndexScan { variable, label, property, range, .. } => {
+                self.execute_index_scan(variable, label, property, range, params, stats)
+            }
+
             PhysicalOperator::Filter { input, predicate, .. } => {
                 self.execute_filter(input, predicate, params, stats)
             }
@@ -95,18 +782,103 @@ impl QueryExecutor {
                 self.execute_project(input, expressions, aliases, params, stats)
             }
 
-            PhysicalOperator::Expand { input, from_var, rel_var, to_var, rel_types, direction, .. } => {
-                self.execute_expand(input, from_var, rel_var, to_var, rel_types, direction, params, stats)
+            PhysicalOperator::Expand { input, from_var, rel_var, to_var, to_labels, rel_types, direction, .. } => {
+                let (columns, rows) = self.execute_plan(input, params, stats)?;
+                let expansion = Expansion { from_var, rel_var, to_var, to_labels, path_var: &None, rel_types, direction: *direction };
+                self.execute_expand(columns, rows, &expansion, 1, 1)
+            }
+
+            PhysicalOperator::VarLengthExpand {
+                input, from_var, rel_var, to_var, to_labels, path_var, rel_types, direction, min_hops, max_hops, ..
+            } => {
+                let (columns, rows) = self.execute_plan(input, params, stats)?;
+                let expansion = Expansion { from_var, rel_var, to_var, to_labels, path_var, rel_types, direction: *direction };
+                self.execute_expand(columns, rows, &expansion, *min_hops, *max_hops)
+            }
+
+            PhysicalOperator::ShortestPath {
+                left, right, from_var, rel_var, to_var, path_var, rel_types, direction, min_hops, max_hops, all, ..
+            } => {
+                let left = self.execute_plan(left, params, stats)?;
+                let right = self.execute_plan(right, params, stats)?;
+                let search = PathSearch {
+                    from_var,
+                    rel_var,
+                    to_var,
+                    path_var,
+                    direction: *direction,
+                    options: PathOptions { rel_types: rel_types.clone(), max_hops: *max_hops },
+                    min_hops: *min_hops,
+                    all: *all,
+                };
+                self.execute_shortest_path(left, right, &search)
+            }
+
+            PhysicalOperator::ProcedureCall { procedure, args, yield_items, .. } => {
+                self.execute_procedure_call(procedure, args, yield_items, params)
+            }
+
+            PhysicalOperator::Unwind { input, expression, variable, .. } => {
+                self.execute_unwind(input.as_deref(), expression, variable, params, stats)
+            }
+
+            PhysicalOperator::Apply { left, right, .. } => {
+                self.execute_apply(left, right, params, stats)
+            }
+
+            PhysicalOperator::Merge { variable, labels, properties, on_create, on_match, .. } => {
+                let merge = MergeSpec { variable, labels, properties, on_create, on_match };
+                self.execute_merge(&merge, params, stats)
+            }
+
+            PhysicalOperator::CreateIndex { name, label, properties, if_not_exists, .. } => {
+                self.execute_create_index(name, label, properties, *if_not_exists, stats)
+            }
+
+            PhysicalOperator::DropIndex { name, if_exists, .. } => {
+                self.execute_drop_index(name, *if_exists, stats)
+            }
+
+            PhysicalOperator::SetProperty { input, items, .. } => {
+                self.execute_node_updates(input, items, SetItem::variable, params, stats, |item, node, row| {
+                    self.apply_set_item(node, item, row, params)
+                })
+            }
+
+            PhysicalOperator::SetLabels { input, variable, labels, .. } => {
+                let item = SetItem::Labels { variable: variable.clone(), labels: labels.clone() };
+                let items = std::slice::from_ref(&item);
+                self.execute_node_updates(input, items, SetItem::variable, params, stats, |item, node, row| {
+                    self.apply_set_item(node, item, row, params)
+                })
+            }
+
+            PhysicalOperator::Remove { input, items, .. } => {
+                self.execute_node_updates(input, items, RemoveItem::variable, params, stats, |item, node, _| {
+                    Ok(apply_remove_item(node, item))
+                })
+            }
+
+            PhysicalOperator::Delete { input, expressions, detach, .. } => {
+                self.execute_delete(input, expressions, *detach, params, stats)
             }
 
             PhysicalOperator::Limit { input, count, .. } => {
                 self.execute_limit(input, *count, params, stats)
             }
 
+            PhysicalOperator::Skip { input, count, .. } => {
+                self.execute_skip(input, *count, params, stats)
+            }
+
             PhysicalOperator::OrderBy { input, items, .. } => {
                 self.execute_order_by(input, items, params, stats)
             }
 
+            PhysicalOperator::Aggregate { input, group_by, group_by_aliases, aggregates, .. } => {
+                self.execute_aggregate(input, group_by, group_by_aliases, aggregates, params, stats)
+            }
+
             _ => Err(Error::QueryExecution("Unsupported operator".to_string())),
         }
     }
@@ -151,7 +923,7 @@ impl QueryExecutor {
         stats: &mut ExecutionStats,
     ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
         // Evaluate the value expression
-        let prop_value = self.evaluate_expression(value, &HashMap::new(), params)?;
+        let prop_value = self.evaluate_expression(value, &HashMap::new(), &HashSet::new(), params)?;
 
         // For now, use find_nodes_by_label_and_property
         let nodes = self.graph.find_nodes_by_label_and_property(
@@ -174,106 +946,761 @@ impl QueryExecutor {
         Ok((columns, rows))
     }
 
-    /// Execute filter - apply predicate to input
-    fn execute_filter(
+    /// Execute composite index seek - nodes matching values for the leading
+    /// properties of the index
+    fn execute_composite_index_seek(
         &self,
-        input: &PhysicalOperator,
-        predicate: &Expression,
+        variable: &str,
+        index: &str,
+        values: &[Expression],
         params: &HashMap<String, PropertyValue>,
         stats: &mut ExecutionStats,
     ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
-        let (columns, rows) = self.execute_plan(input, params, stats)?;
-
-        // Filter rows based on predicate
-        let mut filtered_rows = Vec::new();
-        for row in rows {
-            // Build variable bindings for this row
-            let mut bindings = HashMap::new();
-            for (i, col) in columns.iter().enumerate() {
-                bindings.insert(col.clone(), row[i].clone());
-            }
+        let values = values
+            .iter()
+            .map(|value| self.evaluate_expression(value, &HashMap::new(), &HashSet::new(), params))
+            .collect::<Result<Vec<_>>>()?;
+        let nodes = self.graph.find_nodes_by_composite_index(index, &values)?;
 
-            // Evaluate predicate
-            if let PropertyValue::Boolean(true) = self.evaluate_expression(predicate, &bindings, params)? {
-                filtered_rows.push(row);
-            }
-        }
+        stats.index_hits += 1;
+        stats.nodes_scanned += nodes.len() as u64;
 
-        Ok((columns, filtered_rows))
+        let rows = nodes
+            .iter()
+            .map(|node| vec![PropertyValue::Integer(node.id.as_internal() as i64)])
+            .collect();
+        Ok((vec![variable.to_string()], rows))
     }
 
-    /// Execute projection - select specific columns/expressions
-    fn execute_project(
+    /// Execute index range scan - nodes whose property lies within inclusive bounds
+    fn execute_index_scan(
         &self,
-        input: &PhysicalOperator,
-        expressions: &[Expression],
-        aliases: &[String],
+        variable: &str,
+        label: &str,
+        property: &str,
+        range: &(Option<Expression>, Option<Expression>),
         params: &HashMap<String, PropertyValue>,
         stats: &mut ExecutionStats,
     ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
-        let (input_columns, input_rows) = self.execute_plan(input, params, stats)?;
+        let bound = |expression: &Option<Expression>| {
+            expression
+                .as_ref()
+                .map(|e| self.evaluate_expression(e, &HashMap::new(), &HashSet::new(), params))
+                .transpose()
+        };
+        let (min, max) = (bound(&range.0)?, bound(&range.1)?);
 
-        let mut output_rows = Vec::new();
-        for row in input_rows {
-            // Build variable bindings
-            let mut bindings = HashMap::new();
-            for (i, col) in input_columns.iter().enumerate() {
-                bindings.insert(col.clone(), row[i].clone());
-            }
+        let nodes = self.graph.find_nodes_by_property_range(label, property, min.as_ref(), max.as_ref())?;
 
-            // Evaluate projection expressions
-            let mut output_row = Vec::new();
-            for expr in expressions {
-                let value = self.evaluate_expression(expr, &bindings, params)?;
-                output_row.push(value);
-            }
-            output_rows.push(output_row);
-        }
+        stats.index_hits += 1;
+        stats.nodes_scanned += nodes.len() as u64;
 
-        Ok((aliases.to_vec(), output_rows))
+        let rows = nodes
+            .iter()
+            .map(|node| vec![PropertyValue::Integer(node.id.as_internal() as i64)])
+            .collect();
+        Ok((vec![variable.to_string()], rows))
     }
 
-    /// Execute expand - traverse relationships
-    fn execute_expand(
+    /// Execute merge - find the node matching the pattern or create it
+    ///
+    /// The lookup, the create and the `ON CREATE`/`ON MATCH` updates run
+    /// under the graph's merge lock, so concurrent merges for the same key
+    /// create a single node.
+    fn execute_merge(
         &self,
-        input: &PhysicalOperator,
-        from_var: &str,
-        _rel_var: &Option<String>,
-        to_var: &str,
-        _rel_types: &[String],
-        direction: &qilbee_core::Direction,
+        merge: &MergeSpec<'_>,
         params: &HashMap<String, PropertyValue>,
         stats: &mut ExecutionStats,
     ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
-        let (mut columns, rows) = self.execute_plan(input, params, stats)?;
+        let mut properties = Property::new();
+        for (key, value) in merge.properties {
+            match self.evaluate_expression(value, &HashMap::new(), &HashSet::new(), params)? {
+                PropertyValue::Null => {
+                    return Err(Error::QueryExecution(format!(
+                        "Cannot merge node using null property value for {}",
+                        key
+                    )))
+                }
+                value => properties.set(key.clone(), value),
+            }
+        }
 
-        // Find the column index for the from variable
-        let from_idx = columns.iter().position(|c| c == from_var)
-            .ok_or_else(|| Error::QueryExecution(format!("Variable {} not found", from_var)))?;
+        let mut properties_set = 0;
+        let (node, created) = self.graph.merge_node(merge.labels, properties, |node, created| {
+            let items = if created { merge.on_create } else { merge.on_match };
+            let no_relationships = HashSet::new();
+            let row = RowBindings { values: HashMap::new(), relationships: &no_relationships };
+            for item in items {
+                if item.variable() != merge.variable {
+                    return Err(Error::QueryExecution(format!("Variable {} not found", item.variable())));
+                }
+                properties_set += self.apply_set_item(node, item, &row, params)?;
+            }
+            Ok(())
+        })?;
 
-        let mut output_rows = Vec::new();
+        if created {
+            stats.nodes_created += 1;
+        }
+        stats.properties_set += properties_set;
+
+        let row = vec![PropertyValue::Integer(node.id.as_internal() as i64)];
+        Ok((vec![merge.variable.to_string()], vec![row]))
+    }
+
+    /// Create an index, building it over the existing nodes
+    ///
+    /// A single property makes a range index, several a composite one.
+    fn execute_create_index(
+        &self,
+        name: &str,
+        label: &str,
+        properties: &[String],
+        if_not_exists: bool,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let index = match properties {
+            [property] => Index::range(name, label, property),
+            _ => Index::composite(name, label, properties.iter().map(String::as_str).collect()),
+        };
+
+        let columns = vec!["index".to_string(), "nodesIndexed".to_string()];
+        match self.graph.create_index_with_progress(index, |_| {})? {
+            Some(indexed) => {
+                stats.indexes_added += 1;
+                let row = vec![PropertyValue::String(name.to_string()), PropertyValue::Integer(indexed as i64)];
+                Ok((columns, vec![row]))
+            }
+            None if if_not_exists => Ok((columns, Vec::new())),
+            None => Err(Error::IndexAlreadyExists(name.to_string())),
+        }
+    }
+
+    /// Drop an index by name
+    fn execute_drop_index(
+        &self,
+        name: &str,
+        if_exists: bool,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let columns = vec!["index".to_string()];
+        match self.graph.drop_index(name)? {
+            Some(_) => {
+                stats.indexes_removed += 1;
+                Ok((columns, vec![vec![PropertyValue::String(name.to_string())]]))
+            }
+            None if if_exists => Ok((columns, Vec::new())),
+            None => Err(Error::IndexNotFound(name.to_string())),
+        }
+    }
+
+    /// Apply per-row node updates for SET and REMOVE
+    ///
+    /// `update` runs for each item on the node bound to the item's variable and
+    /// returns the number of properties it set. Changed nodes are written back
+    /// once per row through `Graph::update_node`, which also re-indexes them.
+    /// Rows pass through unchanged; null bindings are skipped.
+    fn execute_node_updates<T>(
+        &self,
+        input: &PhysicalOperator,
+        items: &[T],
+        target: fn(&T) -> &str,
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
+        update: impl Fn(&T, &mut Node, &RowBindings<'_>) -> Result<u64>,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (columns, rows) = self.execute_plan(input, params, stats)?;
+        let relationships = input.relationship_variables();
+
+        for row in &rows {
+            let bindings = RowBindings {
+                values: columns.iter().cloned().zip(row.iter().cloned()).collect(),
+                relationships: &relationships,
+            };
+
+            // Nodes touched by this row as (variable, stored, updated)
+            let mut pending: Vec<(&str, Node, Node)> = Vec::new();
+            for item in items {
+                let variable = target(item);
+                let index = match pending.iter().position(|(name, ..)| *name == variable) {
+                    Some(index) => index,
+                    None => {
+                        let Some(node) = self.bound_node(variable, &bindings)? else {
+                            continue;
+                        };
+                        pending.push((variable, node.clone(), node));
+                        pending.len() - 1
+                    }
+                };
+                stats.properties_set += update(item, &mut pending[index].2, &bindings)?;
+            }
+
+            for (_, stored, updated) in pending {
+                if updated != stored {
+                    self.graph.update_node(&updated)?;
+                }
+            }
+        }
+
+        Ok((columns, rows))
+    }
+
+    /// Execute `[DETACH] DELETE` over every input row
+    ///
+    /// Deletions are collected across all rows first, so a query deleting a
+    /// node together with its relationships succeeds regardless of row order.
+    /// Without `detach`, nothing is deleted if any node would keep a
+    /// relationship. Rows pass through unchanged.
+    fn execute_delete(
+        &self,
+        input: &PhysicalOperator,
+        expressions: &[Expression],
+        detach: bool,
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (columns, rows) = self.execute_plan(input, params, stats)?;
+        let relationships = input.relationship_variables();
+
+        let mut nodes = Vec::new();
+        let mut rels = Vec::new();
+        for row in &rows {
+            let bindings: HashMap<String, PropertyValue> = columns.iter().cloned().zip(row.iter().cloned()).collect();
+            for expression in expressions {
+                let is_relationship =
+                    matches!(expression, Expression::Variable(var) if relationships.contains(var));
+                match self.evaluate_expression(expression, &bindings, &relationships, params)? {
+                    PropertyValue::Null => {}
+                    PropertyValue::Integer(id) if is_relationship => {
+                        rels.push(RelationshipId::from_internal(id as u64))
+                    }
+                    PropertyValue::Integer(id) => nodes.push(NodeId::from_internal(id as u64)),
+                    other => {
+                        return Err(Error::QueryExecution(format!(
+                            "DELETE expects a node or relationship, got {}",
+                            other.type_name()
+                        )))
+                    }
+                }
+            }
+        }
+        // The same entity can be bound in several rows
+        let mut deleting = HashSet::new();
+        rels.retain(|id| deleting.insert(*id));
+        let mut seen = HashSet::new();
+        nodes.retain(|id| seen.insert(*id));
+
+        if !detach {
+            for &node_id in &nodes {
+                let remaining = self
+                    .graph
+                    .get_relationships(node_id, qilbee_core::Direction::Both)?
+                    .into_iter()
+                    .filter(|rel| !deleting.contains(&rel.id))
+                    .count();
+                if remaining > 0 {
+                    return Err(Error::InvalidGraphOperation(format!(
+                        "Cannot delete node {} because it still has {} relationship(s); \
+                         delete them first or use DETACH DELETE",
+                        node_id.as_internal(),
+                        remaining
+                    )));
+                }
+            }
+        }
+
+        for rel_id in rels {
+            stats.relationships_deleted += self.graph.delete_relationship(rel_id)? as u64;
+        }
+        for node_id in nodes {
+            if detach {
+                if let Some(rels_deleted) = self.graph.detach_delete_node(node_id)? {
+                    stats.nodes_deleted += 1;
+                    stats.relationships_deleted += rels_deleted;
+                }
+            } else {
+                stats.nodes_deleted += self.graph.delete_node(node_id)? as u64;
+            }
+        }
+
+        Ok((columns, rows))
+    }
+
+    /// Load the node bound to `variable`, or `None` if it is bound to null
+    fn bound_node(&self, variable: &str, bindings: &RowBindings<'_>) -> Result<Option<Node>> {
+        if bindings.relationships.contains(variable) {
+            return Err(Error::QueryExecution(format!(
+                "Cannot update {}: SET and REMOVE only support nodes",
+                variable
+            )));
+        }
+        let node_id = match bindings.values.get(variable) {
+            Some(PropertyValue::Null) => return Ok(None),
+            Some(PropertyValue::Integer(id)) => NodeId::from_internal(*id as u64),
+            Some(_) => return Err(Error::QueryExecution(format!("{} is not a node", variable))),
+            None => return Err(Error::QueryExecution(format!("Variable {} not found", variable))),
+        };
+        self.graph
+            .get_node(node_id)?
+            .map(Some)
+            .ok_or_else(|| Error::NodeNotFound(format!("{:?}", node_id)))
+    }
+
+    /// Apply a SET item to a node that may not be stored yet
+    ///
+    /// Returns the number of properties set. The node's own properties are
+    /// bound to the item's variable as a map, so `n.count + 1` reads the
+    /// pending value. Null values remove properties.
+    fn apply_set_item(
+        &self,
+        node: &mut Node,
+        item: &SetItem,
+        row: &RowBindings<'_>,
+        params: &HashMap<String, PropertyValue>,
+    ) -> Result<u64> {
+        let mut bindings = row.values.clone();
+        bindings.insert(
+            item.variable().to_string(),
+            PropertyValue::Map(node.properties.clone().into_inner()),
+        );
+        let evaluate =
+            |value: &Expression| self.evaluate_expression(value, &bindings, row.relationships, params);
+        let evaluate_map = |value: &Expression| match evaluate(value)? {
+            PropertyValue::Map(map) => Ok(map),
+            other => Err(Error::QueryExecution(format!(
+                "Expected a map to set properties of {}, got {}",
+                item.variable(),
+                other.type_name()
+            ))),
+        };
+
+        match item {
+            SetItem::Property { property, value, .. } => {
+                match evaluate(value)? {
+                    PropertyValue::Null => {
+                        node.remove_property(property);
+                    }
+                    value => node.set_property(property.clone(), value),
+                }
+                Ok(1)
+            }
+            SetItem::Labels { labels, .. } => {
+                for label in labels {
+                    node.add_label(label.clone());
+                }
+                Ok(0)
+            }
+            SetItem::AllProperties { value, .. } => {
+                let map = evaluate_map(value)?;
+                let count = map.len() as u64;
+                node.properties = map.into_iter().filter(|(_, v)| !v.is_null()).collect();
+                Ok(count)
+            }
+            SetItem::MergeProperties { value, .. } => {
+                let map = evaluate_map(value)?;
+                let count = map.len() as u64;
+                let (removed, update): (Vec<_>, Vec<_>) = map.into_iter().partition(|(_, v)| v.is_null());
+                for (key, _) in removed {
+                    node.remove_property(&key);
+                }
+                self.merge_properties(&mut node.properties, update.into_iter().collect());
+                Ok(count)
+            }
+        }
+    }
+
+    /// Execute filter - apply predicate to input
+    fn execute_filter(
+        &self,
+        input: &PhysicalOperator,
+        predicate: &Expression,
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (columns, rows) = self.execute_plan(input, params, stats)?;
+        let relationships = input.relationship_variables();
+
+        // Filter rows based on predicate
+        let mut filtered_rows = Vec::new();
+        for row in rows {
+            // Build variable bindings for this row
+            let mut bindings = HashMap::new();
+            for (i, col) in columns.iter().enumerate() {
+                bindings.insert(col.clone(), row[i].clone());
+            }
+
+            // Evaluate predicate
+            if let PropertyValue::Boolean(true) = self.evaluate_expression(predicate, &bindings, &relationships, params)? {
+                filtered_rows.push(row);
+            }
+        }
+
+        Ok((columns, filtered_rows))
+    }
+
+    /// Execute unwind - one row per element of a list, per input row
+    fn execute_unwind(
+        &self,
+        input: Option<&PhysicalOperator>,
+        expression: &Expression,
+        variable: &str,
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (mut columns, rows) = match input {
+            Some(input) => self.execute_plan(input, params, stats)?,
+            None => (Vec::new(), vec![Vec::new()]),
+        };
+        let relationships = input.map(PhysicalOperator::relationship_variables).unwrap_or_default();
+
+        let mut unwound_rows = Vec::new();
+        for row in rows {
+            let bindings: HashMap<String, PropertyValue> = columns.iter().cloned().zip(row.iter().cloned()).collect();
+            let elements = match self.evaluate_expression(expression, &bindings, &relationships, params)? {
+                PropertyValue::Array(items) => items,
+                PropertyValue::Null => Vec::new(),
+                value => vec![value],
+            };
+            for element in elements {
+                let mut unwound = row.clone();
+                unwound.push(element);
+                unwound_rows.push(unwound);
+            }
+        }
+
+        columns.push(variable.to_string());
+        Ok((columns, unwound_rows))
+    }
+
+    /// Execute apply - run `right` once per `left` row, with the row's
+    /// variables passed as arguments
+    fn execute_apply(
+        &self,
+        left: &PhysicalOperator,
+        right: &PhysicalOperator,
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (left_columns, left_rows) = self.execute_plan(left, params, stats)?;
+
+        let mut right_columns = None;
+        let mut rows = Vec::new();
+        for left_row in left_rows {
+            let mut arguments = params.clone();
+            for (column, value) in left_columns.iter().zip(&left_row) {
+                arguments.insert(argument_key(column), value.clone());
+            }
+            let (columns, right_rows) = self.execute_plan(right, &arguments, stats)?;
+            right_columns.get_or_insert(columns);
+            for right_row in right_rows {
+                let mut row = left_row.clone();
+                row.extend(right_row);
+                rows.push(row);
+            }
+        }
+
+        let mut columns = left_columns;
+        columns.extend(right_columns.unwrap_or_default());
+        Ok((columns, rows))
+    }
+
+    /// Execute projection - select specific columns/expressions
+    fn execute_project(
+        &self,
+        input: &PhysicalOperator,
+        expressions: &[Expression],
+        aliases: &[String],
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (input_columns, input_rows) = self.execute_plan(input, params, stats)?;
+        let relationships = input.relationship_variables();
+
+        let mut output_rows = Vec::new();
+        for row in input_rows {
+            // Build variable bindings
+            let mut bindings = HashMap::new();
+            for (i, col) in input_columns.iter().enumerate() {
+                bindings.insert(col.clone(), row[i].clone());
+            }
+
+            // Evaluate projection expressions
+            let mut output_row = Vec::new();
+            for expr in expressions {
+                let value = self.evaluate_expression(expr, &bindings, &relationships, params)?;
+                output_row.push(value);
+            }
+            output_rows.push(output_row);
+        }
+
+        Ok((aliases.to_vec(), output_rows))
+    }
+
+    /// Execute expand - traverse between `min_hops` and `max_hops` relationships
+    ///
+    /// Paths are grown breadth-first one hop at a time; a relationship is
+    /// never traversed twice within the same path, so cycles terminate.
+    fn execute_expand(
+        &self,
+        mut columns: Vec<String>,
+        rows: Vec<Vec<PropertyValue>>,
+        expansion: &Expansion<'_>,
+        min_hops: u32,
+        max_hops: u32,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        // Find the column index for the from variable
+        let from_idx = columns.iter().position(|c| c == expansion.from_var)
+            .ok_or_else(|| Error::QueryExecution(format!("Variable {} not found", expansion.from_var)))?;
+
+        let variable_length = expansion.path_var.is_some() || (min_hops, max_hops) != (1, 1);
+        let mut label_matches: HashMap<NodeId, bool> = HashMap::new();
+        let mut output_rows = Vec::new();
 
         for row in rows {
-            // Get the node ID
-            if let PropertyValue::Integer(node_id) = row[from_idx] {
-                let node_id = qilbee_core::NodeId::from_internal(node_id as u64);
+            let PropertyValue::Integer(start) = row[from_idx] else {
+                continue;
+            };
+
+            // Each partial path is (nodes, relationships)
+            let mut frontier = vec![(vec![NodeId::from_internal(start as u64)], Vec::<RelationshipId>::new())];
 
-                // Get neighbors
-                let neighbors = self.graph.get_neighbors(node_id, *direction)?;
+            for depth in 0..=max_hops {
+                for (nodes, rels) in &frontier {
+                    let end = *nodes.last().unwrap();
+                    if depth < min_hops || !self.node_has_labels(end, expansion.to_labels, &mut label_matches)? {
+                        continue;
+                    }
 
-                // Create a row for each neighbor
-                for neighbor in neighbors {
                     let mut output_row = row.clone();
-                    output_row.push(PropertyValue::Integer(neighbor.id.as_internal() as i64));
+                    output_row.push(PropertyValue::Integer(end.as_internal() as i64));
+                    if expansion.rel_var.is_some() {
+                        output_row.push(if variable_length {
+                            id_list(rels.iter().map(|r| r.as_internal()))
+                        } else {
+                            PropertyValue::Integer(rels[0].as_internal() as i64)
+                        });
+                    }
+                    if expansion.path_var.is_some() {
+                        let mut path = HashMap::new();
+                        path.insert("nodes".to_string(), id_list(nodes.iter().map(|n| n.as_internal())));
+                        path.insert("relationships".to_string(), id_list(rels.iter().map(|r| r.as_internal())));
+                        output_row.push(PropertyValue::Map(path));
+                    }
+                    output_rows.push(output_row);
+                }
+
+                if depth == max_hops {
+                    break;
+                }
+
+                let mut next = Vec::new();
+                for (nodes, rels) in &frontier {
+                    let end = *nodes.last().unwrap();
+
+                    for rel in self.graph.get_relationships(end, expansion.direction)? {
+                        if rels.contains(&rel.id) {
+                            continue;
+                        }
+                        if !expansion.rel_types.is_empty()
+                            && !expansion.rel_types.iter().any(|t| t == rel.rel_type.name())
+                        {
+                            continue;
+                        }
+
+                        let neighbor = match expansion.direction {
+                            qilbee_core::Direction::Outgoing => rel.target,
+                            qilbee_core::Direction::Incoming => rel.source,
+                            qilbee_core::Direction::Both => rel.other(end).unwrap_or(rel.target),
+                        };
+
+                        let mut nodes = nodes.clone();
+                        nodes.push(neighbor);
+                        let mut rels = rels.clone();
+                        rels.push(rel.id);
+                        next.push((nodes, rels));
+                    }
+                }
+
+                if next.is_empty() {
+                    break;
+                }
+                frontier = next;
+            }
+        }
+
+        columns.push(expansion.to_var.to_string());
+        if let Some(rel_var) = expansion.rel_var {
+            columns.push(rel_var.clone());
+        }
+        if let Some(path_var) = expansion.path_var {
+            columns.push(path_var.clone());
+        }
+        Ok((columns, output_rows))
+    }
+
+    /// Execute shortest path search - pair every left row with every right
+    /// row and emit one row per shortest path between their endpoints
+    fn execute_shortest_path(
+        &self,
+        (left_columns, left_rows): (Vec<String>, Vec<Vec<PropertyValue>>),
+        (right_columns, right_rows): (Vec<String>, Vec<Vec<PropertyValue>>),
+        search: &PathSearch<'_>,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let from_idx = left_columns.iter().position(|c| c == search.from_var)
+            .ok_or_else(|| Error::QueryExecution(format!("Variable {} not found", search.from_var)))?;
+        let to_idx = right_columns.iter().position(|c| c == search.to_var)
+            .ok_or_else(|| Error::QueryExecution(format!("Variable {} not found", search.to_var)))?;
+
+        let mut output_rows = Vec::new();
+        for left_row in &left_rows {
+            let PropertyValue::Integer(source) = left_row[from_idx] else {
+                continue;
+            };
+            let source = NodeId::from_internal(source as u64);
+
+            for right_row in &right_rows {
+                let PropertyValue::Integer(target) = right_row[to_idx] else {
+                    continue;
+                };
+                let target = NodeId::from_internal(target as u64);
+
+                let paths = if search.all {
+                    self.graph.all_shortest_paths(source, target, search.direction, &search.options)?
+                } else {
+                    self.graph
+                        .shortest_path(source, target, search.direction, &search.options)?
+                        .into_iter()
+                        .collect()
+                };
+
+                for path in paths.into_iter().filter(|p| p.len() as u32 >= search.min_hops) {
+                    let mut output_row = left_row.clone();
+                    output_row.extend(right_row.iter().cloned());
+                    if search.rel_var.is_some() {
+                        output_row.push(id_list(path.relationships.iter().map(|r| r.as_internal())));
+                    }
+                    if search.path_var.is_some() {
+                        let mut path_map = HashMap::new();
+                        path_map.insert("nodes".to_string(), id_list(path.nodes.iter().map(|n| n.as_internal())));
+                        path_map.insert(
+                            "relationships".to_string(),
+                            id_list(path.relationships.iter().map(|r| r.as_internal())),
+                        );
+                        output_row.push(PropertyValue::Map(path_map));
+                    }
                     output_rows.push(output_row);
                 }
             }
         }
 
-        columns.push(to_var.to_string());
+        let mut columns = left_columns;
+        columns.extend(right_columns);
+        if let Some(rel_var) = search.rel_var {
+            columns.push(rel_var.clone());
+        }
+        if let Some(path_var) = search.path_var {
+            columns.push(path_var.clone());
+        }
         Ok((columns, output_rows))
     }
 
+    /// Execute a procedure call, narrowing the output to the yielded columns
+    fn execute_procedure_call(
+        &self,
+        procedure: &str,
+        args: &[Expression],
+        yield_items: &[String],
+        params: &HashMap<String, PropertyValue>,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let args = args
+            .iter()
+            .map(|arg| self.evaluate_expression(arg, &HashMap::new(), &HashSet::new(), params))
+            .collect::<Result<Vec<_>>>()?;
+
+        let (columns, rows) = match procedure.to_lowercase().as_str() {
+            "algo.pagerank" => self.call_pagerank(&args)?,
+            _ => return Err(Error::QueryExecution(format!("Unknown procedure: {}", procedure))),
+        };
+
+        if yield_items.is_empty() {
+            return Ok((columns, rows));
+        }
+
+        let indices = yield_items
+            .iter()
+            .map(|item| {
+                columns.iter().position(|c| c == item).ok_or_else(|| {
+                    Error::QueryExecution(format!("Procedure {} does not yield {}", procedure, item))
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let rows = rows
+            .into_iter()
+            .map(|row| indices.iter().map(|i| row[*i].clone()).collect())
+            .collect();
+        Ok((yield_items.to_vec(), rows))
+    }
+
+    /// `algo.pagerank([damping [, iterations [, tolerance]]])` yielding
+    /// `nodeId` and `score`, highest score first
+    fn call_pagerank(&self, args: &[PropertyValue]) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        if args.len() > 3 {
+            return Err(Error::QueryExecution(
+                "algo.pagerank() takes at most 3 arguments: damping, iterations, tolerance".to_string(),
+            ));
+        }
+
+        let damping = match args.first() {
+            Some(value) => numeric_argument("damping", value)?,
+            None => DEFAULT_PAGERANK_DAMPING,
+        };
+        let iterations = match args.get(1) {
+            Some(PropertyValue::Integer(n)) if *n >= 0 => *n as usize,
+            Some(_) => {
+                return Err(Error::QueryExecution(
+                    "algo.pagerank() iterations must be a non-negative integer".to_string(),
+                ))
+            }
+            None => DEFAULT_PAGERANK_ITERATIONS,
+        };
+        let tolerance = match args.get(2) {
+            Some(value) => numeric_argument("tolerance", value)?,
+            None => DEFAULT_PAGERANK_TOLERANCE,
+        };
+
+        let mut scores: Vec<(NodeId, f64)> = self.graph.pagerank(damping, iterations, tolerance)?.into_iter().collect();
+        scores.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.as_internal().cmp(&b.0.as_internal())));
+
+        let rows = scores
+            .into_iter()
+            .map(|(node, score)| vec![PropertyValue::Integer(node.as_internal() as i64), PropertyValue::Float(score)])
+            .collect();
+        Ok((vec!["nodeId".to_string(), "score".to_string()], rows))
+    }
+
+    /// Check that a node carries all of the given labels, caching lookups
+    fn node_has_labels(
+        &self,
+        node_id: NodeId,
+        labels: &[String],
+        cache: &mut HashMap<NodeId, bool>,
+    ) -> Result<bool> {
+        if labels.is_empty() {
+            return Ok(true);
+        }
+        if let Some(matches) = cache.get(&node_id) {
+            return Ok(*matches);
+        }
+
+        let matches = self
+            .graph
+            .get_node(node_id)?
+            .is_some_and(|node| labels.iter().all(|l| node.has_label_name(l)));
+        cache.insert(node_id, matches);
+        Ok(matches)
+    }
+
     /// Execute limit - restrict number of rows
     fn execute_limit(
         &self,
@@ -287,6 +1714,19 @@ impl QueryExecutor {
         Ok((columns, rows))
     }
 
+    /// Execute skip - drop leading results
+    fn execute_skip(
+        &self,
+        input: &PhysicalOperator,
+        count: usize,
+        params: &HashMap<String, PropertyValue>,
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (columns, mut rows) = self.execute_plan(input, params, stats)?;
+        rows.drain(..count.min(rows.len()));
+        Ok((columns, rows))
+    }
+
     /// Execute order by - sort results
     fn execute_order_by(
         &self,
@@ -296,6 +1736,7 @@ impl QueryExecutor {
         stats: &mut ExecutionStats,
     ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
         let (columns, mut rows) = self.execute_plan(input, params, stats)?;
+        let relationships = input.relationship_variables();
 
         // Sort rows based on sort items
         rows.sort_by(|a, b| {
@@ -309,8 +1750,8 @@ impl QueryExecutor {
                 }
 
                 // Evaluate expression for both rows
-                let val_a = self.evaluate_expression(expr, &bindings_a, params).unwrap_or(PropertyValue::Null);
-                let val_b = self.evaluate_expression(expr, &bindings_b, params).unwrap_or(PropertyValue::Null);
+                let val_a = self.evaluate_expression(expr, &bindings_a, &relationships, params).unwrap_or(PropertyValue::Null);
+                let val_b = self.evaluate_expression(expr, &bindings_b, &relationships, params).unwrap_or(PropertyValue::Null);
 
                 // Compare
                 let cmp = compare_property_values(&val_a, &val_b);
@@ -324,18 +1765,86 @@ impl QueryExecutor {
         Ok((columns, rows))
     }
 
-    /// Evaluate an expression
-    fn evaluate_expression(
+    /// Execute aggregate - group rows and compute aggregate functions
+    fn execute_aggregate(
         &self,
-        expr: &Expression,
-        bindings: &HashMap<String, PropertyValue>,
+        input: &PhysicalOperator,
+        group_by: &[Expression],
+        group_by_aliases: &[String],
+        aggregates: &[AggregateExpression],
         params: &HashMap<String, PropertyValue>,
-    ) -> Result<PropertyValue> {
-        match expr {
-            Expression::Literal(lit) => Ok(literal_to_property_value(lit)),
+        stats: &mut ExecutionStats,
+    ) -> Result<(Vec<String>, Vec<Vec<PropertyValue>>)> {
+        let (input_columns, input_rows) = self.execute_plan(input, params, stats)?;
+        let relationships = input.relationship_variables();
+
+        // Groups in first-seen order, each holding the collected argument values
+        let mut groups: Vec<(Vec<PropertyValue>, Vec<Vec<PropertyValue>>)> = Vec::new();
+        let mut group_index: HashMap<String, usize> = HashMap::new();
+
+        for row in input_rows {
+            let mut bindings = HashMap::new();
+            for (i, col) in input_columns.iter().enumerate() {
+                bindings.insert(col.clone(), row[i].clone());
+            }
+
+            let key = group_by
+                .iter()
+                .map(|expr| self.evaluate_expression(expr, &bindings, &relationships, params))
+                .collect::<Result<Vec<_>>>()?;
+
+            let idx = *group_index.entry(format!("{:?}", key)).or_insert_with(|| {
+                groups.push((key, vec![Vec::new(); aggregates.len()]));
+                groups.len() - 1
+            });
+
+            for (agg, values) in aggregates.iter().zip(groups[idx].1.iter_mut()) {
+                let value = match &agg.argument {
+                    // count(*) counts rows, so any non-null marker will do
+                    Expression::Star => PropertyValue::Boolean(true),
+                    expr => self.evaluate_expression(expr, &bindings, &relationships, params)?,
+                };
+                values.push(value);
+            }
+        }
+
+        // Without grouping keys an empty input still yields a single row
+        if groups.is_empty() && group_by.is_empty() {
+            groups.push((Vec::new(), vec![Vec::new(); aggregates.len()]));
+        }
+
+        let mut columns = group_by_aliases.to_vec();
+        columns.extend(aggregates.iter().map(|agg| agg.alias.clone()));
+
+        let mut rows = Vec::with_capacity(groups.len());
+        for (key, values) in groups {
+            let mut row = key;
+            for (agg, values) in aggregates.iter().zip(values) {
+                row.push(compute_aggregate(agg, values)?);
+            }
+            rows.push(row);
+        }
+
+        Ok((columns, rows))
+    }
+
+    /// Evaluate an expression
+    ///
+    /// `relationships` names the bound variables holding relationship ids;
+    /// every other integer binding is treated as a node id.
+    fn evaluate_expression(
+        &self,
+        expr: &Expression,
+        bindings: &HashMap<String, PropertyValue>,
+        relationships: &HashSet<String>,
+        params: &HashMap<String, PropertyValue>,
+    ) -> Result<PropertyValue> {
+        match expr {
+            Expression::Literal(lit) => Ok(literal_to_property_value(lit)),
 
             Expression::Variable(var) => {
                 bindings.get(var)
+                    .or_else(|| params.get(&argument_key(var)))
                     .cloned()
                     .ok_or_else(|| Error::QueryExecution(format!("Variable {} not found", var)))
             }
@@ -348,7 +1857,24 @@ impl QueryExecutor {
 
             Expression::Property(object, property) => {
                 // First evaluate the object
-                let obj_val = self.evaluate_expression(object, bindings, params)?;
+                let obj_val = self.evaluate_expression(object, bindings, relationships, params)?;
+
+                // Relationship variables resolve against the relationship
+                if let (Expression::Variable(var), PropertyValue::Integer(rel_id)) = (object.as_ref(), &obj_val)
+                    && relationships.contains(var)
+                {
+                    let rel_id = RelationshipId::from_internal(*rel_id as u64);
+                    return Ok(self
+                        .graph
+                        .get_relationship(rel_id)?
+                        .and_then(|rel| rel.properties.get(property).cloned())
+                        .unwrap_or(PropertyValue::Null));
+                }
+
+                // Maps, including a MERGE node's pending properties, resolve by key
+                if let PropertyValue::Map(map) = &obj_val {
+                    return Ok(map.get(property).cloned().unwrap_or(PropertyValue::Null));
+                }
 
                 // If it's a node ID, get the node and return the property
                 if let PropertyValue::Integer(node_id) = obj_val {
@@ -360,17 +1886,106 @@ impl QueryExecutor {
                 Ok(PropertyValue::Null)
             }
 
+            Expression::Index(object, index) => {
+                let obj_val = self.evaluate_expression(object, bindings, relationships, params)?;
+                let index_val = self.evaluate_expression(index, bindings, relationships, params)?;
+                evaluate_index(&obj_val, &index_val)
+            }
+
             Expression::Binary { left, op, right } => {
-                let left_val = self.evaluate_expression(left, bindings, params)?;
-                let right_val = self.evaluate_expression(right, bindings, params)?;
-                evaluate_binary_op(&left_val, op, &right_val)
+                let left_val = self.evaluate_expression(left, bindings, relationships, params)?;
+                let right_val = self.evaluate_expression(right, bindings, relationships, params)?;
+                evaluate_binary_op(&left_val, op, &right_val, self.float_epsilon)
+            }
+
+            Expression::Function { name, args, .. } => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.evaluate_expression(arg, bindings, relationships, params))
+                    .collect::<Result<Vec<_>>>()?;
+                evaluate_function(name, &values)
             }
 
+            Expression::List(items) => items
+                .iter()
+                .map(|item| self.evaluate_expression(item, bindings, relationships, params))
+                .collect::<Result<Vec<_>>>()
+                .map(PropertyValue::Array),
+
+            Expression::Map(map) => map
+                .entries
+                .iter()
+                .map(|(key, value)| {
+                    Ok((key.clone(), self.evaluate_expression(value, bindings, relationships, params)?))
+                })
+                .collect::<Result<HashMap<_, _>>>()
+                .map(PropertyValue::Map),
+
             _ => Err(Error::QueryExecution("Unsupported expression type".to_string())),
         }
     }
 }
 
+/// Key under which an Apply passes the variable `variable` of its left row
+/// to its right side, among the parameters; no `$name` can spell it
+fn argument_key(variable: &str) -> String {
+    format!("@{}", variable)
+}
+
+/// Fill in a plan description with the profiles recorded for its operators
+///
+/// Profiles include the operator's inputs, so the inputs' database hits and
+/// time are subtracted. Operators that never ran keep no actual counts.
+fn profiled_description(
+    operator: &PhysicalOperator,
+    description: &PlanDescription,
+    profiles: &HashMap<usize, OperatorProfile>,
+) -> PlanDescription {
+    let children = operator.children();
+    let own = profiles.get(&(operator as *const PhysicalOperator as usize)).map(|profile| {
+        let inputs = children
+            .iter()
+            .filter_map(|child| profiles.get(&(*child as *const PhysicalOperator as usize)))
+            .fold(OperatorProfile::default(), |sum, input| OperatorProfile {
+                rows: 0,
+                db_hits: sum.db_hits + input.db_hits,
+                elapsed: sum.elapsed + input.elapsed,
+            });
+        OperatorProfile {
+            rows: profile.rows,
+            db_hits: profile.db_hits.saturating_sub(inputs.db_hits),
+            elapsed: profile.elapsed.saturating_sub(inputs.elapsed),
+        }
+    });
+
+    PlanDescription {
+        operator: description.operator.clone(),
+        estimated_rows: description.estimated_rows,
+        estimated_cost: description.estimated_cost,
+        rows: own.map(|p| p.rows),
+        db_hits: own.map(|p| p.db_hits),
+        time_ms: own.map(|p| p.elapsed.as_secs_f64() * 1000.0),
+        children: children
+            .into_iter()
+            .zip(&description.children)
+            .map(|(child, child_description)| profiled_description(child, child_description, profiles))
+            .collect(),
+    }
+}
+
+/// Apply a REMOVE item to a node, returning the number of properties removed
+fn apply_remove_item(node: &mut Node, item: &RemoveItem) -> u64 {
+    match item {
+        RemoveItem::Property { property, .. } => node.remove_property(property).is_some() as u64,
+        RemoveItem::Labels { labels, .. } => {
+            for label in labels {
+                node.remove_label(&Label::new(label.as_str()));
+            }
+            0
+        }
+    }
+}
+
 /// Convert a literal to a property value
 fn literal_to_property_value(lit: &Literal) -> PropertyValue {
     match lit {
@@ -382,15 +1997,65 @@ fn literal_to_property_value(lit: &Literal) -> PropertyValue {
     }
 }
 
+/// `list[index]` or `map[key]`; negative indices count from the end of the
+/// list, and out-of-range indices, missing keys and null give null
+fn evaluate_index(value: &PropertyValue, index: &PropertyValue) -> Result<PropertyValue> {
+    match (value, index) {
+        (PropertyValue::Null, _) | (_, PropertyValue::Null) => Ok(PropertyValue::Null),
+        (PropertyValue::Array(items), PropertyValue::Integer(i)) => {
+            let position = if *i < 0 { items.len() as i64 + i } else { *i };
+            Ok(usize::try_from(position)
+                .ok()
+                .and_then(|position| items.get(position))
+                .cloned()
+                .unwrap_or(PropertyValue::Null))
+        }
+        (PropertyValue::Map(map), PropertyValue::String(key)) => {
+            Ok(map.get(key).cloned().unwrap_or(PropertyValue::Null))
+        }
+        (PropertyValue::Array(_), _) => Err(Error::QueryExecution("List index must be an integer".to_string())),
+        (PropertyValue::Map(_), _) => Err(Error::QueryExecution("Map key must be a string".to_string())),
+        _ => Err(Error::QueryExecution("Only lists and maps can be indexed".to_string())),
+    }
+}
+
+/// Read a numeric procedure argument as a float
+fn numeric_argument(name: &str, value: &PropertyValue) -> Result<f64> {
+    match value {
+        PropertyValue::Float(f) => Ok(*f),
+        PropertyValue::Integer(i) => Ok(*i as f64),
+        _ => Err(Error::QueryExecution(format!("Procedure argument {} must be a number", name))),
+    }
+}
+
+/// Build a list value from entity IDs
+fn id_list(ids: impl Iterator<Item = u64>) -> PropertyValue {
+    PropertyValue::Array(ids.map(|id| PropertyValue::Integer(id as i64)).collect())
+}
+
 /// Evaluate a binary operation
-fn evaluate_binary_op(left: &PropertyValue, op: &BinaryOp, right: &PropertyValue) -> Result<PropertyValue> {
+///
+/// Comparisons treat numbers within `float_epsilon` of each other as equal.
+fn evaluate_binary_op(
+    left: &PropertyValue,
+    op: &BinaryOp,
+    right: &PropertyValue,
+    float_epsilon: Option<f64>,
+) -> Result<PropertyValue> {
+    let compare = |left, right| compare_property_values_within(left, right, float_epsilon);
     match op {
-        BinaryOp::Equals => Ok(PropertyValue::Boolean(left == right)),
-        BinaryOp::NotEquals => Ok(PropertyValue::Boolean(left != right)),
-        BinaryOp::LessThan => Ok(PropertyValue::Boolean(compare_property_values(left, right) == std::cmp::Ordering::Less)),
-        BinaryOp::LessEquals => Ok(PropertyValue::Boolean(compare_property_values(left, right) != std::cmp::Ordering::Greater)),
-        BinaryOp::GreaterThan => Ok(PropertyValue::Boolean(compare_property_values(left, right) == std::cmp::Ordering::Greater)),
-        BinaryOp::GreaterEquals => Ok(PropertyValue::Boolean(compare_property_values(left, right) != std::cmp::Ordering::Less)),
+        BinaryOp::Equals => Ok(PropertyValue::Boolean(left.equals_within(right, float_epsilon))),
+        BinaryOp::NotEquals => Ok(PropertyValue::Boolean(!left.equals_within(right, float_epsilon))),
+        // Ordering against null is unknown, so the row is filtered out
+        BinaryOp::LessThan | BinaryOp::LessEquals | BinaryOp::GreaterThan | BinaryOp::GreaterEquals
+            if matches!(left, PropertyValue::Null) || matches!(right, PropertyValue::Null) =>
+        {
+            Ok(PropertyValue::Null)
+        }
+        BinaryOp::LessThan => Ok(PropertyValue::Boolean(compare(left, right) == std::cmp::Ordering::Less)),
+        BinaryOp::LessEquals => Ok(PropertyValue::Boolean(compare(left, right) != std::cmp::Ordering::Greater)),
+        BinaryOp::GreaterThan => Ok(PropertyValue::Boolean(compare(left, right) == std::cmp::Ordering::Greater)),
+        BinaryOp::GreaterEquals => Ok(PropertyValue::Boolean(compare(left, right) != std::cmp::Ordering::Less)),
         BinaryOp::And => {
             let l = if let PropertyValue::Boolean(b) = left { *b } else { false };
             let r = if let PropertyValue::Boolean(b) = right { *b } else { false };
@@ -401,20 +2066,1618 @@ fn evaluate_binary_op(left: &PropertyValue, op: &BinaryOp, right: &PropertyValue
             let r = if let PropertyValue::Boolean(b) = right { *b } else { false };
             Ok(PropertyValue::Boolean(l || r))
         }
+        BinaryOp::Add
+        | BinaryOp::Subtract
+        | BinaryOp::Multiply
+        | BinaryOp::Divide
+        | BinaryOp::Modulo
+        | BinaryOp::Power => evaluate_arithmetic(left, op, right),
+        BinaryOp::In => evaluate_in(left, right, float_epsilon),
+        BinaryOp::StartsWith | BinaryOp::EndsWith | BinaryOp::Contains | BinaryOp::RegexMatch => {
+            evaluate_string_predicate(left, op, right)
+        }
         _ => Err(Error::QueryExecution(format!("Unsupported binary operator: {:?}", op))),
     }
 }
 
+/// Evaluate `value IN list`
+///
+/// Follows Cypher: a null value, or a list without the value but with a null
+/// element, gives null rather than false.
+fn evaluate_in(value: &PropertyValue, list: &PropertyValue, float_epsilon: Option<f64>) -> Result<PropertyValue> {
+    let items = match list {
+        PropertyValue::Array(items) => items,
+        PropertyValue::Null => return Ok(PropertyValue::Null),
+        _ => return Err(Error::QueryExecution("IN expects a list on its right-hand side".to_string())),
+    };
+
+    if matches!(value, PropertyValue::Null) {
+        return Ok(PropertyValue::Null);
+    }
+    if items.iter().any(|item| item.equals_within(value, float_epsilon)) {
+        Ok(PropertyValue::Boolean(true))
+    } else if items.iter().any(|item| matches!(item, PropertyValue::Null)) {
+        Ok(PropertyValue::Null)
+    } else {
+        Ok(PropertyValue::Boolean(false))
+    }
+}
+
+/// Evaluate `STARTS WITH`, `ENDS WITH`, `CONTAINS` or `=~`
+///
+/// Matching is case-sensitive. Unless both operands are strings the result is
+/// null, so the row is filtered out rather than failing the query.
+fn evaluate_string_predicate(left: &PropertyValue, op: &BinaryOp, right: &PropertyValue) -> Result<PropertyValue> {
+    let (PropertyValue::String(text), PropertyValue::String(pattern)) = (left, right) else {
+        return Ok(PropertyValue::Null);
+    };
+
+    let matched = match op {
+        BinaryOp::StartsWith => text.starts_with(pattern.as_str()),
+        BinaryOp::EndsWith => text.ends_with(pattern.as_str()),
+        BinaryOp::Contains => text.contains(pattern.as_str()),
+        _ => regex_matches(pattern, text)?,
+    };
+    Ok(PropertyValue::Boolean(matched))
+}
+
+/// Compile a pattern that must match a whole string, as `=~` requires
+pub(crate) fn compile_regex(pattern: &str) -> std::result::Result<Regex, regex::Error> {
+    // Validate the pattern on its own so that it cannot escape the anchoring group
+    Regex::new(pattern)?;
+    Regex::new(&format!("^(?:{})$", pattern))
+}
+
+thread_local! {
+    /// The most recently compiled `=~` pattern, reused across rows
+    static LAST_REGEX: RefCell<Option<(String, Regex)>> = const { RefCell::new(None) };
+}
+
+/// Check whether `text` matches `pattern` in full
+fn regex_matches(pattern: &str, text: &str) -> Result<bool> {
+    LAST_REGEX.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if let Some((cached, regex)) = cache.as_ref()
+            && cached == pattern
+        {
+            return Ok(regex.is_match(text));
+        }
+
+        let regex = compile_regex(pattern).map_err(|e| {
+            Error::QueryExecution(format!("Invalid regular expression '{}': {}", pattern, e))
+        })?;
+        let matched = regex.is_match(text);
+        *cache = Some((pattern.to_string(), regex));
+        Ok(matched)
+    })
+}
+
+/// Evaluate an arithmetic operation
+///
+/// Follows Cypher: a null operand gives null, an integer and a float give a
+/// float, and `^` always gives a float. Integer arithmetic is checked, so an
+/// overflowing result or an integer division by zero is an error rather than
+/// a wrapped value. `+` also concatenates strings.
+fn evaluate_arithmetic(left: &PropertyValue, op: &BinaryOp, right: &PropertyValue) -> Result<PropertyValue> {
+    use PropertyValue::{Float, Integer, Null};
+
+    let (l, r) = match (left, right) {
+        (Null, _) | (_, Null) => return Ok(Null),
+        (PropertyValue::String(l), PropertyValue::String(r)) if *op == BinaryOp::Add => {
+            return Ok(PropertyValue::String(format!("{}{}", l, r)));
+        }
+        (Integer(l), Integer(r)) if *op != BinaryOp::Power => {
+            let (l, r) = (*l, *r);
+            if r == 0 && matches!(op, BinaryOp::Divide | BinaryOp::Modulo) {
+                return Err(Error::QueryExecution("Division by zero".to_string()));
+            }
+            let result = match op {
+                BinaryOp::Add => l.checked_add(r),
+                BinaryOp::Subtract => l.checked_sub(r),
+                BinaryOp::Multiply => l.checked_mul(r),
+                BinaryOp::Divide => l.checked_div(r),
+                // Only i64::MIN % -1 overflows, and its remainder is 0
+                _ => Some(l.checked_rem(r).unwrap_or(0)),
+            };
+            return result.map(Integer).ok_or_else(|| {
+                Error::QueryExecution(format!("Integer overflow: {:?} of {} and {}", op, l, r))
+            });
+        }
+        (Integer(l), Integer(r)) => (*l as f64, *r as f64),
+        (Integer(l), Float(r)) => (*l as f64, *r),
+        (Float(l), Integer(r)) => (*l, *r as f64),
+        (Float(l), Float(r)) => (*l, *r),
+        _ => {
+            return Err(Error::QueryExecution(format!(
+                "Cannot apply {:?} to {} and {}",
+                op,
+                left.type_name(),
+                right.type_name()
+            )))
+        }
+    };
+
+    Ok(Float(match op {
+        BinaryOp::Add => l + r,
+        BinaryOp::Subtract => l - r,
+        BinaryOp::Multiply => l * r,
+        BinaryOp::Divide => l / r,
+        BinaryOp::Modulo => l % r,
+        _ => l.powf(r),
+    }))
+}
+
+/// Compute a single aggregate over the argument values collected for a group
+///
+/// Nulls are ignored by every function. Sums of integers stay Integer unless
+/// a Float is present, following Cypher's numeric promotion rules.
+fn compute_aggregate(agg: &AggregateExpression, values: Vec<PropertyValue>) -> Result<PropertyValue> {
+    let mut values: Vec<PropertyValue> = values
+        .into_iter()
+        .filter(|v| !matches!(v, PropertyValue::Null))
+        .collect();
+
+    if agg.distinct {
+        let mut seen = std::collections::HashSet::new();
+        values.retain(|v| seen.insert(format!("{:?}", v)));
+    }
+
+    match agg.function {
+        AggregateFunction::Count => Ok(PropertyValue::Integer(values.len() as i64)),
+
+        AggregateFunction::Sum | AggregateFunction::Avg => {
+            let mut int_sum: i64 = 0;
+            let mut float_sum: f64 = 0.0;
+            let mut has_float = false;
+
+            for value in &values {
+                match value {
+                    PropertyValue::Integer(i) => {
+                        int_sum = int_sum.checked_add(*i).ok_or_else(|| {
+                            Error::QueryExecution("Integer overflow in sum()".to_string())
+                        })?;
+                    }
+                    PropertyValue::Float(f) => {
+                        float_sum += f;
+                        has_float = true;
+                    }
+                    other => {
+                        return Err(Error::TypeMismatch {
+                            expected: "number".to_string(),
+                            found: other.type_name().to_string(),
+                        })
+                    }
+                }
+            }
+
+            if agg.function == AggregateFunction::Avg {
+                if values.is_empty() {
+                    return Ok(PropertyValue::Null);
+                }
+                let total = int_sum as f64 + float_sum;
+                return Ok(PropertyValue::Float(total / values.len() as f64));
+            }
+
+            if has_float {
+                Ok(PropertyValue::Float(int_sum as f64 + float_sum))
+            } else {
+                Ok(PropertyValue::Integer(int_sum))
+            }
+        }
+
+        AggregateFunction::Min => Ok(values
+            .into_iter()
+            .min_by(compare_property_values)
+            .unwrap_or(PropertyValue::Null)),
+
+        AggregateFunction::Max => Ok(values
+            .into_iter()
+            .max_by(compare_property_values)
+            .unwrap_or(PropertyValue::Null)),
+
+        AggregateFunction::Collect => Ok(PropertyValue::Array(values)),
+    }
+}
+
 /// Compare two property values
 fn compare_property_values(a: &PropertyValue, b: &PropertyValue) -> std::cmp::Ordering {
+    compare_property_values_within(a, b, None)
+}
+
+/// Compare two property values, treating numbers within `float_epsilon` of
+/// each other as equal
+fn compare_property_values_within(a: &PropertyValue, b: &PropertyValue, float_epsilon: Option<f64>) -> std::cmp::Ordering {
     use std::cmp::Ordering;
     match (a, b) {
         (PropertyValue::Integer(a), PropertyValue::Integer(b)) => a.cmp(b),
         (PropertyValue::Float(a), PropertyValue::Float(b)) => {
-            a.partial_cmp(b).unwrap_or(Ordering::Equal)
+            compare_floats(*a, *b, float_epsilon).unwrap_or(Ordering::Equal)
+        }
+        (PropertyValue::Integer(a), PropertyValue::Float(b)) => {
+            compare_floats(*a as f64, *b, float_epsilon).unwrap_or(Ordering::Equal)
+        }
+        (PropertyValue::Float(a), PropertyValue::Integer(b)) => {
+            compare_floats(*a, *b as f64, float_epsilon).unwrap_or(Ordering::Equal)
         }
         (PropertyValue::String(a), PropertyValue::String(b)) => a.cmp(b),
         (PropertyValue::Boolean(a), PropertyValue::Boolean(b)) => a.cmp(b),
+        (PropertyValue::Date(a), PropertyValue::Date(b)) => a.cmp(b),
+        (PropertyValue::Time(a), PropertyValue::Time(b))
+        | (PropertyValue::DateTime(a), PropertyValue::DateTime(b))
+        | (PropertyValue::Duration(a), PropertyValue::Duration(b)) => a.cmp(b),
         _ => Ordering::Equal,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::planner::QueryPlanner;
+    use crate::simple_parser::parse_simple;
+    use qilbee_core::Property;
+    use qilbee_graph::Database;
+    use tempfile::TempDir;
+
+    fn create_test_graph() -> (Graph, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        (graph, temp_dir)
+    }
+
+    fn run_query(graph: &Graph, cypher: &str) -> QueryResult {
+        let query = parse_simple(cypher).unwrap();
+        let plan = QueryPlanner::new()
+            .with_schema(&graph.schema().read().unwrap())
+            .plan(&query)
+            .unwrap();
+        QueryExecutor::new(Arc::new(graph.clone()))
+            .execute(&plan, &HashMap::new())
+            .unwrap()
+    }
+
+    /// Declare a range index on `label.property`
+    fn add_range_index(graph: &Graph, label: &str, property: &str) {
+        let name = format!("{}_{}", label.to_lowercase(), property);
+        graph
+            .schema()
+            .write()
+            .unwrap()
+            .add_index(qilbee_graph::Index::range(&name, label, property));
+    }
+
+    /// Plan `cypher` against the indexes declared on `graph`
+    fn plan_with_schema(graph: &Graph, cypher: &str) -> ExecutionPlan {
+        QueryPlanner::new()
+            .with_schema(&graph.schema().read().unwrap())
+            .plan(&parse_simple(cypher).unwrap())
+            .unwrap()
+    }
+
+    fn create_named(graph: &Graph, name: &str) -> NodeId {
+        let mut props = Property::new();
+        props.set("name", name);
+        graph.create_node_with_properties(["Person"], props).unwrap().id
+    }
+
+    fn create_person(graph: &Graph, name: &str, city: &str, age: PropertyValue) {
+        let mut props = Property::new();
+        props.set("name", name);
+        props.set("city", city);
+        props.set("age", age);
+        graph.create_node_with_properties(["Person"], props).unwrap();
+    }
+
+    fn integer_result(count: i64) -> QueryResult {
+        QueryResult {
+            columns: vec!["n".to_string()],
+            rows: (0..count).map(|i| vec![PropertyValue::Integer(i)]).collect(),
+            stats: ExecutionStats::default(),
+        }
+    }
+
+    #[test]
+    fn test_merge_properties_follows_merge_mode() {
+        let (graph, _dir) = create_test_graph();
+        let graph = Arc::new(graph);
+        let address = |entries: &[(&str, &str)]| {
+            PropertyValue::Map(entries.iter().map(|(k, v)| (k.to_string(), (*v).into())).collect())
+        };
+        let original = Property::with("address", address(&[("city", "Lisbon"), ("zip", "1000")]));
+        let update = Property::with("address", address(&[("city", "Porto")]));
+
+        let mut deep = original.clone();
+        QueryExecutor::new(Arc::clone(&graph)).merge_properties(&mut deep, update.clone());
+        assert_eq!(deep.get("address"), Some(&address(&[("city", "Porto"), ("zip", "1000")])));
+
+        let mut shallow = original;
+        QueryExecutor::new(graph)
+            .with_merge_mode(MergeMode::Shallow)
+            .merge_properties(&mut shallow, update);
+        assert_eq!(shallow.get("address"), Some(&address(&[("city", "Porto")])));
+    }
+
+    #[test]
+    fn test_result_stream_chunks() {
+        let mut stream = QueryResultStream::new(integer_result(25), 10);
+        assert_eq!(stream.columns(), &["n".to_string()]);
+
+        let sizes: Vec<usize> = stream.by_ref().map(|chunk| chunk.unwrap().len()).collect();
+        assert_eq!(sizes, vec![10, 10, 5]);
+        assert_eq!(stream.chunks_emitted(), 3);
+        assert_eq!(stream.remaining_rows(), Some(0));
+    }
+
+    #[test]
+    fn test_result_stream_empty() {
+        let mut stream = QueryResultStream::new(integer_result(0), 10);
+        assert!(stream.next().is_none());
+        assert_eq!(stream.chunks_emitted(), 0);
+    }
+
+    #[test]
+    fn test_execute_stream_reads_one_snapshot() {
+        let (graph, _dir) = create_test_graph();
+        let ids: Vec<NodeId> = (0..25).map(|i| create_named(&graph, &format!("Person{}", i))).collect();
+
+        let plan = plan_with_schema(&graph, "MATCH (p:Person) RETURN p.name AS name");
+        let mut stream = QueryExecutor::new(Arc::new(graph.clone()))
+            .execute_stream(&plan, &HashMap::new(), 10)
+            .unwrap();
+        assert_eq!(stream.remaining_rows(), None);
+
+        let first = stream.next().unwrap().unwrap();
+        assert_eq!(first.len(), 10);
+        assert_eq!(stream.stats().rows_returned, 10);
+
+        // Writes landing while the stream is open are not seen by later rows
+        for id in &ids {
+            let mut node = graph.get_node(*id).unwrap().unwrap();
+            node.set_property("name", "Renamed");
+            graph.update_node(&node).unwrap();
+        }
+        graph.detach_delete_node(ids[20]).unwrap();
+        let rest: Vec<_> = stream.by_ref().flat_map(Result::unwrap).collect();
+        let names: Vec<_> = (10..25).map(|i| vec![PropertyValue::String(format!("Person{}", i))]).collect();
+        assert_eq!(rest, names);
+        assert_eq!(stream.stats().rows_returned, 25);
+        assert_eq!(stream.remaining_rows(), Some(0));
+    }
+
+    #[test]
+    fn test_execute_stream_reads_storage_per_chunk() {
+        let (graph, _dir) = create_test_graph();
+        let ids: Vec<NodeId> = (0..500).map(|i| create_named(&graph, &format!("Person{}", i))).collect();
+        for pair in ids.windows(2) {
+            graph.create_relationship(pair[0], "KNOWS", pair[1]).unwrap();
+        }
+
+        let query = "MATCH (a:Person)-[:KNOWS]->(b:Person) RETURN a.name AS a, b.name AS b";
+        let plan = plan_with_schema(&graph, query);
+        let mut stream = QueryExecutor::new(Arc::new(graph.clone()))
+            .execute_stream(&plan, &HashMap::new(), 10)
+            .unwrap();
+        assert_eq!(stream.stats().nodes_scanned, 0);
+
+        // Only the nodes behind the first chunk have been read
+        let first = stream.next().unwrap().unwrap();
+        assert_eq!(first.len(), 10);
+        assert!(stream.stats().nodes_scanned <= 20, "read {} nodes", stream.stats().nodes_scanned);
+
+        let rest: Vec<_> = stream.by_ref().flat_map(Result::unwrap).collect();
+        let executed = run_query(&graph, query);
+        assert_eq!([first, rest].concat(), executed.rows);
+        assert_eq!(stream.stats().nodes_scanned, 500);
+    }
+
+    #[test]
+    fn test_plan_max_rows() {
+        let (graph, _dir) = create_test_graph();
+        let max_rows = |cypher: &str| plan_with_schema(&graph, cypher).max_rows();
+
+        assert_eq!(max_rows("MATCH (p:Person) RETURN p.name AS name LIMIT 10"), Some(10));
+        assert_eq!(max_rows("MATCH (p:Person) WHERE p.age > 3 RETURN p.name AS name ORDER BY name LIMIT 5"), Some(5));
+        assert_eq!(max_rows("MATCH (p:Person) RETURN count(p) AS people"), Some(1));
+        assert_eq!(max_rows("MATCH (p:Person) RETURN p.name AS name"), None);
+        assert_eq!(max_rows("MATCH (p:Person)-[:KNOWS]->(f) RETURN f.name AS name"), None);
+    }
+
+    #[test]
+    fn test_execute_stream_pipelines_skip_and_limit() {
+        let (graph, _dir) = create_test_graph();
+        for i in 0..25 {
+            create_named(&graph, &format!("Person{}", i));
+        }
+
+        let plan = plan_with_schema(
+            &graph,
+            "MATCH (p:Person) WHERE p.name <> 'Person3' RETURN p.name AS name SKIP 5 LIMIT 12",
+        );
+        let stream = QueryExecutor::new(Arc::new(graph.clone()))
+            .execute_stream(&plan, &HashMap::new(), 5)
+            .unwrap();
+        let streamed: Vec<_> = stream.flat_map(Result::unwrap).collect();
+
+        let executed = run_query(&graph, "MATCH (p:Person) WHERE p.name <> 'Person3' RETURN p.name AS name SKIP 5 LIMIT 12");
+        assert_eq!(streamed, executed.rows);
+        assert_eq!(streamed.len(), 12);
+    }
+
+    #[test]
+    fn test_execute_stream_fails_at_the_failing_row() {
+        let (graph, _dir) = create_test_graph();
+        for i in 0..25 {
+            let mut props = Property::new();
+            props.set("divisor", if i == 15 { 0 } else { 1 });
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+
+        let plan = plan_with_schema(&graph, "MATCH (p:Person) RETURN 10 / p.divisor AS q");
+        let mut stream = QueryExecutor::new(Arc::new(graph.clone()))
+            .execute_stream(&plan, &HashMap::new(), 10)
+            .unwrap();
+
+        assert_eq!(stream.next().unwrap().unwrap().len(), 10);
+        assert!(matches!(stream.next(), Some(Err(Error::QueryExecution(msg))) if msg == "Division by zero"));
+        assert!(stream.next().is_none());
+    }
+
+    #[test]
+    fn test_count_star_on_empty_match() {
+        let (graph, _dir) = create_test_graph();
+
+        let result = run_query(&graph, "MATCH (p:Person) RETURN count(*) AS total");
+        assert_eq!(result.columns, vec!["total".to_string()]);
+        assert_eq!(result.rows, vec![vec![PropertyValue::Integer(0)]]);
+    }
+
+    #[test]
+    fn test_default_limit_and_skip() {
+        let (graph, _temp) = create_test_graph();
+        for i in 0..20 {
+            create_named(&graph, &format!("Person{:02}", i));
+        }
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        let planner = QueryPlanner::new().with_default_limit(5);
+
+        // Unlimited reads are capped at the default
+        let query = parse_simple("MATCH (p:Person) RETURN p.name AS name ORDER BY p.name").unwrap();
+        let plan = planner.plan(&query).unwrap();
+        assert_eq!(plan.default_limit, Some(5));
+        let result = executor.execute(&plan, &HashMap::new()).unwrap();
+        assert_eq!(result.rows.len(), 5);
+        assert_eq!(result.rows[0][0], PropertyValue::String("Person00".to_string()));
+
+        // An explicit LIMIT overrides the default
+        let query = parse_simple("MATCH (p:Person) RETURN p.name AS name LIMIT 12").unwrap();
+        let plan = planner.plan(&query).unwrap();
+        assert_eq!(plan.default_limit, None);
+        assert_eq!(executor.execute(&plan, &HashMap::new()).unwrap().rows.len(), 12);
+
+        // SKIP pages through the rest
+        let query = parse_simple(
+            "MATCH (p:Person) RETURN p.name AS name ORDER BY p.name SKIP 5 LIMIT 5",
+        )
+        .unwrap();
+        let result = executor.execute(&planner.plan(&query).unwrap(), &HashMap::new()).unwrap();
+        assert_eq!(result.rows.len(), 5);
+        assert_eq!(result.rows[0][0], PropertyValue::String("Person05".to_string()));
+
+        assert_eq!(run_query(&graph, "MATCH (p:Person) RETURN p.name AS name SKIP 18").rows.len(), 2);
+    }
+
+    #[test]
+    fn test_where_equality_uses_index_seek() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Bob", "Paris", PropertyValue::Integer(25));
+        create_person(&graph, "Carol", "Rome", PropertyValue::Integer(30));
+        add_range_index(&graph, "Person", "city");
+
+        let plan_for = |cypher: &str| plan_with_schema(&graph, cypher);
+        assert!(plan_for("MATCH (p:Person) WHERE p.city = 'Paris' RETURN p.name AS name").uses_index());
+        assert!(!plan_for("MATCH (p:Person) WHERE p.city <> 'Rome' RETURN p.name AS name").uses_index());
+        assert!(!plan_for("MATCH (p) WHERE p.city = 'Paris' RETURN p.name AS name").uses_index());
+
+        let result = run_query(
+            &graph,
+            "MATCH (p:Person) WHERE p.city = 'Paris' RETURN p.name AS name ORDER BY p.name",
+        );
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![PropertyValue::String("Alice".to_string())],
+                vec![PropertyValue::String("Bob".to_string())],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_float_epsilon_makes_near_equal_floats_equal() {
+        let names = |graph: &Graph, cypher: &str| {
+            let mut rows = run_query(graph, cypher).rows;
+            rows.sort_by(|a, b| compare_property_values(&a[0], &b[0]));
+            rows.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>()
+        };
+        let seeded = |float_epsilon: Option<f64>| {
+            let temp_dir = TempDir::new().unwrap();
+            let config = qilbee_graph::DatabaseConfig { float_epsilon, ..Default::default() };
+            let db = Database::open_with_config(temp_dir.path(), config).unwrap();
+            let graph = db.graph("test").unwrap();
+            create_person(&graph, "Alice", "Paris", PropertyValue::Float(0.1 + 0.2));
+            create_person(&graph, "Bob", "Paris", PropertyValue::Float(0.31));
+            (graph, temp_dir)
+        };
+        let alice = vec![PropertyValue::String("Alice".to_string())];
+        let bob = vec![PropertyValue::String("Bob".to_string())];
+
+        // Exact by default: 0.1 + 0.2 is not 0.3
+        let (graph, _dir) = seeded(None);
+        assert!(names(&graph, "MATCH (p:Person) WHERE p.age = 0.3 RETURN p.name").is_empty());
+        assert!(names(&graph, "MATCH (p:Person) WHERE p.age * 1 = 0.3 RETURN p.name").is_empty());
+        assert!(names(&graph, "MATCH (p:Person) WHERE p.age <= 0.3 RETURN p.name").is_empty());
+
+        // With a tolerance, index seeks and filters agree that it is
+        let (graph, _dir) = seeded(Some(1e-9));
+        assert_eq!(names(&graph, "MATCH (p:Person) WHERE p.age = 0.3 RETURN p.name"), alice);
+        assert_eq!(names(&graph, "MATCH (p:Person) WHERE p.age * 1 = 0.3 RETURN p.name"), alice);
+        assert_eq!(names(&graph, "MATCH (p:Person) WHERE p.age <= 0.3 RETURN p.name"), alice);
+        assert_eq!(names(&graph, "MATCH (p:Person) WHERE p.age <> 0.3 RETURN p.name"), bob);
+        assert_eq!(names(&graph, "MATCH (p:Person) WHERE p.age IN [0.3, 1.0] RETURN p.name"), alice);
+    }
+
+    #[test]
+    fn test_planner_consults_schema_indexes() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Bob", "Rome", PropertyValue::Integer(25));
+        graph
+            .schema()
+            .write()
+            .unwrap()
+            .add_index(qilbee_graph::Index::range("person_city", "Person", "city"));
+
+        let schema = graph.schema().read().unwrap().clone();
+        let plan_for = |cypher: &str| {
+            QueryPlanner::new()
+                .with_schema(&schema)
+                .plan(&parse_simple(cypher).unwrap())
+                .unwrap()
+        };
+
+        // Indexed property: seek, with no filter left over
+        let plan = plan_for("MATCH (p:Person) WHERE p.city = 'Paris' RETURN p.name AS name");
+        assert!(plan.uses_index());
+        let explain = plan.explain();
+        assert!(explain.contains("IndexSeek p:Person(city)"), "{}", explain);
+        assert!(!explain.contains("Filter"), "{}", explain);
+
+        // Unindexed property: label scan plus filter
+        let plan = plan_for("MATCH (p:Person) WHERE p.name = 'Alice' RETURN p.name AS name");
+        assert!(!plan.uses_index());
+        let explain = plan.explain();
+        let lines: Vec<_> = explain.lines().map(str::trim_start).collect();
+        assert!(lines[0].starts_with("Project name"), "{}", explain);
+        assert!(lines[1].starts_with("Filter"), "{}", explain);
+        assert!(lines[2].starts_with("NodeByLabelScan p:Person"), "{}", explain);
+
+        // The indexed conjunct is chosen even when it is not the first
+        let plan = plan_for("MATCH (p:Person) WHERE p.name = 'Alice' AND p.city = 'Paris' RETURN p");
+        assert!(plan.explain().contains("IndexSeek p:Person(city)"));
+        let plan = plan_for("MATCH (p:Person {name: 'Alice', city: 'Paris'}) RETURN p");
+        assert!(plan.explain().contains("IndexSeek p:Person(city)"));
+
+        // Range predicates and other labels only use declared indexes too
+        assert!(plan_for("MATCH (p:Person) WHERE p.city >= 'P' RETURN p").uses_index());
+        assert!(!plan_for("MATCH (p:Person) WHERE p.age > 26 RETURN p").uses_index());
+        assert!(!plan_for("MATCH (c:City) WHERE c.city = 'Paris' RETURN c").uses_index());
+
+        // Both plans return the same rows
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        for cypher in [
+            "MATCH (p:Person) WHERE p.city = 'Paris' RETURN p.name AS name",
+            "MATCH (p:Person) WHERE p.name = 'Alice' RETURN p.name AS name",
+        ] {
+            let result = executor.execute(&plan_for(cypher), &HashMap::new()).unwrap();
+            assert_eq!(result.rows, vec![vec![PropertyValue::String("Alice".to_string())]]);
+        }
+    }
+
+    #[test]
+    fn test_planner_uses_composite_index_prefix() {
+        let (graph, _dir) = create_test_graph();
+        for (last, first, city) in [
+            ("Lovelace", "Ada", "London"),
+            ("Lovelace", "Byron", "London"),
+            ("Hopper", "Grace", "New York"),
+        ] {
+            let mut props = Property::new();
+            props.set("lastName", last);
+            props.set("firstName", first);
+            props.set("city", city);
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        graph
+            .create_index(qilbee_graph::Index::composite(
+                "person_name",
+                "Person",
+                vec!["lastName", "firstName", "city"],
+            ))
+            .unwrap();
+
+        let schema = graph.schema().read().unwrap().clone();
+        let plan_for = |cypher: &str| {
+            QueryPlanner::new()
+                .with_schema(&schema)
+                .plan(&parse_simple(cypher).unwrap())
+                .unwrap()
+        };
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        let mut params = HashMap::new();
+        params.insert("l".to_string(), PropertyValue::String("Lovelace".to_string()));
+        params.insert("f".to_string(), PropertyValue::String("Ada".to_string()));
+        let names = |plan: &ExecutionPlan| {
+            let mut rows = executor.execute(plan, &params).unwrap().rows;
+            rows.sort_by_key(|row| format!("{:?}", row));
+            rows
+        };
+        let ada = vec![vec![PropertyValue::String("Ada".to_string())]];
+
+        // A constrained prefix, with no filter left over
+        let plan = plan_for("MATCH (p:Person) WHERE p.lastName = $l AND p.firstName = $f RETURN p.firstName AS name");
+        let explain = plan.explain();
+        assert!(explain.contains("CompositeIndexSeek p:Person(lastName, firstName)"), "{}", explain);
+        assert!(!explain.contains("Filter"), "{}", explain);
+        assert_eq!(names(&plan), ada);
+
+        // Inline properties, in any order, and the full index
+        let plan = plan_for("MATCH (p:Person {city: 'London', firstName: $f, lastName: $l}) RETURN p.firstName AS name");
+        let explain = plan.explain();
+        assert!(explain.contains("CompositeIndexSeek p:Person(lastName, firstName, city)"), "{}", explain);
+        assert!(!explain.contains("Filter"), "{}", explain);
+        assert_eq!(names(&plan), ada);
+
+        // Other conjuncts are checked on the seek's output
+        let plan = plan_for(
+            "MATCH (p:Person) WHERE p.firstName = $f AND p.lastName = $l AND p.city <> 'Paris' RETURN p.firstName AS name",
+        );
+        let explain = plan.explain();
+        assert!(explain.contains("CompositeIndexSeek p:Person(lastName, firstName)"), "{}", explain);
+        assert!(explain.contains("Filter"), "{}", explain);
+        assert_eq!(names(&plan), ada);
+
+        // Not a prefix: the leading property alone uses the property index
+        let plan = plan_for("MATCH (p:Person) WHERE p.lastName = 'Lovelace' AND p.city = 'London' RETURN p.firstName AS name");
+        let explain = plan.explain();
+        assert!(!explain.contains("CompositeIndexSeek"), "{}", explain);
+        assert!(explain.contains("IndexSeek p:Person(lastName)"), "{}", explain);
+        assert_eq!(names(&plan).len(), 2);
+
+        // The leading property is not constrained
+        let plan = plan_for("MATCH (p:Person) WHERE p.firstName = $f AND p.city = 'London' RETURN p.firstName AS name");
+        assert!(!plan.uses_index(), "{}", plan.explain());
+        assert_eq!(names(&plan), ada);
+    }
+
+    #[test]
+    fn test_create_and_drop_index() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Bob", "London", PropertyValue::Integer(40));
+        let try_query = |cypher: &str| {
+            let plan = QueryPlanner::new().plan(&parse_simple(cypher).unwrap())?;
+            QueryExecutor::new(Arc::new(graph.clone())).execute(&plan, &HashMap::new())
+        };
+
+        let result = try_query("CREATE INDEX FOR (p:Person) ON (p.city)").unwrap();
+        assert_eq!(result.columns, vec!["index".to_string(), "nodesIndexed".to_string()]);
+        assert_eq!(
+            result.rows,
+            vec![vec![PropertyValue::String("person_city".to_string()), PropertyValue::Integer(2)]]
+        );
+        assert_eq!(result.stats.indexes_added, 1);
+        assert!(result.stats.contains_updates());
+        assert!(graph.schema().read().unwrap().get_index("person_city").is_some());
+
+        // The existing nodes were indexed
+        let schema = graph.schema().read().unwrap().clone();
+        let plan = QueryPlanner::new()
+            .with_schema(&schema)
+            .plan(&parse_simple("MATCH (p:Person) WHERE p.city = 'Paris' RETURN p.name AS name").unwrap())
+            .unwrap();
+        assert!(plan.explain().contains("IndexSeek p:Person(city)"), "{}", plan.explain());
+        let result = QueryExecutor::new(Arc::new(graph.clone())).execute(&plan, &HashMap::new()).unwrap();
+        assert_eq!(result.rows, vec![vec![PropertyValue::String("Alice".to_string())]]);
+
+        // Creating it again is an error, unless it may already exist
+        assert!(matches!(
+            try_query("CREATE INDEX FOR (p:Person) ON (p.city)"),
+            Err(Error::IndexAlreadyExists(name)) if name == "person_city"
+        ));
+        let result = try_query("CREATE INDEX IF NOT EXISTS FOR (p:Person) ON (p.city)").unwrap();
+        assert!(result.rows.is_empty());
+        assert_eq!(result.stats.indexes_added, 0);
+
+        let result = try_query("CREATE INDEX person_name_age FOR (p:Person) ON (p.name, p.age)").unwrap();
+        assert_eq!(result.rows[0][0], PropertyValue::String("person_name_age".to_string()));
+        assert_eq!(
+            graph.schema().read().unwrap().get_index("person_name_age").unwrap().index_type,
+            qilbee_graph::IndexType::Composite
+        );
+
+        let result = try_query("DROP INDEX person_city").unwrap();
+        assert_eq!(result.rows, vec![vec![PropertyValue::String("person_city".to_string())]]);
+        assert_eq!(result.stats.indexes_removed, 1);
+        assert!(graph.schema().read().unwrap().get_index("person_city").is_none());
+        assert!(matches!(try_query("DROP INDEX person_city"), Err(Error::IndexNotFound(_))));
+        assert!(try_query("DROP INDEX person_city IF EXISTS").unwrap().rows.is_empty());
+    }
+
+    #[test]
+    fn test_planner_uses_stored_counts() {
+        let (graph, _dir) = create_test_graph();
+        let mut cities = Vec::new();
+        for name in ["Paris", "Rome"] {
+            let mut props = Property::new();
+            props.set("name", name);
+            cities.push(graph.create_node_with_properties(["City"], props).unwrap().id);
+        }
+        for i in 0..30 {
+            let mut props = Property::new();
+            props.set("name", format!("Person{}", i));
+            let person = graph.create_node_with_properties(["Person"], props).unwrap();
+            graph.create_relationship(person.id, "LIVES_IN", cities[i % 2]).unwrap();
+        }
+        add_range_index(&graph, "Person", "name");
+        add_range_index(&graph, "City", "name");
+
+        let plan_for = |cypher: &str| {
+            let query = parse_simple(cypher).unwrap();
+            QueryPlanner::new()
+                .with_schema(&graph.schema().read().unwrap())
+                .with_graph_statistics(&graph, &query)
+                .unwrap()
+                .plan(&query)
+                .unwrap()
+        };
+
+        // The pattern is expanded from the smaller label
+        let cypher = "MATCH (p:Person)-[:LIVES_IN]->(c:City) WHERE c.name = 'Rome' RETURN p.name AS name";
+        let plan = plan_for(cypher);
+        let explain = plan.explain();
+        assert!(explain.contains("Expand (c)--(p)"), "{}", explain);
+        assert!(explain.contains("NodeByLabelScan c:City"), "{}", explain);
+        // Two cities with 30 LIVES_IN relationships over 32 nodes
+        let expand = &plan.description.children[0].children[0];
+        assert_eq!(expand.estimated_rows, 2);
+
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        let mut reordered = executor.execute(&plan, &HashMap::new()).unwrap().rows;
+        let mut unordered = run_query(&graph, cypher).rows;
+        reordered.sort_by_key(|row| format!("{:?}", row));
+        unordered.sort_by_key(|row| format!("{:?}", row));
+        assert_eq!(reordered.len(), 15);
+        assert_eq!(reordered, unordered);
+
+        // A seek only pays off on labels with more nodes than it costs
+        assert!(plan_for("MATCH (p:Person) WHERE p.name = 'Person3' RETURN p").uses_index());
+        assert!(!plan_for("MATCH (c:City) WHERE c.name = 'Rome' RETURN c").uses_index());
+        assert!(!plan_for("MATCH (c:City {name: 'Rome'}) RETURN c").uses_index());
+    }
+
+    #[test]
+    fn test_explain_and_profile() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Bob", "Paris", PropertyValue::Integer(25));
+        create_person(&graph, "Carol", "Rome", PropertyValue::Integer(40));
+        add_range_index(&graph, "Person", "age");
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        let plan_for = |cypher: &str| plan_with_schema(&graph, cypher);
+
+        // EXPLAIN plans without executing, even for updates
+        let plan = plan_for("EXPLAIN MATCH (p:Person) SET p.seen = true");
+        assert_eq!(plan.mode, QueryMode::Explain);
+        let result = executor.execute(&plan, &HashMap::new()).unwrap();
+        assert!(result.rows.is_empty());
+        assert_eq!(plan.description.operator, "SetProperty");
+        assert_eq!(plan.description.children[0].operator, "NodeByLabelScan p:Person");
+        assert_eq!(plan.description.rows, None);
+        assert_eq!(run_query(&graph, "MATCH (p:Person) WHERE p.seen = true RETURN p").rows.len(), 0);
+
+        // PROFILE executes and reports actual rows per operator
+        let plan = plan_for("PROFILE MATCH (p:Person) WHERE p.age > 26 RETURN p.name AS name ORDER BY name");
+        let (result, profile) = executor.profile(&plan, &HashMap::new()).unwrap();
+        assert_eq!(result.rows.len(), 2);
+
+        let mut operators = Vec::new();
+        let mut next = Some(&profile);
+        while let Some(description) = next {
+            operators.push((description.operator.clone(), description.rows, description.db_hits));
+            next = description.children.first();
+        }
+        assert_eq!(
+            operators,
+            [
+                ("Project name".to_string(), Some(2), Some(0)),
+                ("OrderBy".to_string(), Some(2), Some(0)),
+                ("Filter".to_string(), Some(2), Some(0)),
+                ("IndexScan p:Person(age)".to_string(), Some(2), Some(3)),
+            ]
+        );
+        assert!(profile.time_ms.is_some());
+        assert_eq!(profile.estimated_rows, plan.description.estimated_rows);
+
+        let json = serde_json::to_value(&profile).unwrap();
+        assert_eq!(json["operator"], "Project name");
+        assert_eq!(json["dbHits"], 0);
+        assert_eq!(json["children"][0]["estimatedRows"], plan.description.children[0].estimated_rows);
+        assert!(serde_json::to_value(&plan.description).unwrap().get("rows").is_none());
+    }
+
+    #[test]
+    fn test_nested_property_access() {
+        let (graph, _dir) = create_test_graph();
+        let address = HashMap::from([
+            ("city".to_string(), PropertyValue::from("Lisbon")),
+            ("geo".to_string(), PropertyValue::Map(HashMap::from([("lat".to_string(), PropertyValue::Float(38.7))]))),
+        ]);
+        let mut props = Property::new();
+        props.set("name", "Alice");
+        props.set("address", PropertyValue::Map(address));
+        props.set("tags", PropertyValue::Array(vec!["a".into(), "b".into(), "c".into()]));
+        graph.create_node_with_properties(["Person"], props).unwrap();
+
+        let result = run_query(
+            &graph,
+            "MATCH (p:Person) RETURN p.address.city AS city, p.address.geo.lat AS lat, p.tags[0] AS first, \
+             p.tags[-1] AS last, p.address['city'] AS keyed",
+        );
+        assert_eq!(
+            result.rows,
+            vec![vec!["Lisbon".into(), PropertyValue::Float(38.7), "a".into(), "c".into(), "Lisbon".into()]]
+        );
+
+        // Missing keys, out-of-range indices and missing properties are null
+        let result = run_query(
+            &graph,
+            "MATCH (p:Person) RETURN p.address.zip AS zip, p.tags[3] AS fourth, p.tags[-4] AS before, \
+             p.missing.city AS city, p.missing[0] AS element",
+        );
+        assert_eq!(result.rows, vec![vec![PropertyValue::Null; 5]]);
+
+        let query = parse_simple("MATCH (p:Person) RETURN p.tags['a'] AS bad").unwrap();
+        let plan = QueryPlanner::new().plan(&query).unwrap();
+        assert!(QueryExecutor::new(Arc::new(graph)).execute(&plan, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_scalar_functions() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", "30".into());
+        create_person(&graph, "Bob", "Rome", "unknown".into());
+
+        let result = run_query(
+            &graph,
+            "MATCH (p:Person) WHERE toInteger(p.age) >= 18 \
+             RETURN p.name AS name, toInteger(p.age) + 1 AS next, toFloat(p.age) AS age, toString(toInteger(p.age)) AS text",
+        );
+        assert_eq!(
+            result.rows,
+            vec![vec!["Alice".into(), PropertyValue::Integer(31), PropertyValue::Float(30.0), "30".into()]]
+        );
+
+        let result = run_query(
+            &graph,
+            "MATCH (p:Person) WHERE p.name = 'Bob' \
+             RETURN toInteger(p.age) AS age, coalesce(p.nickname, toInteger(p.age), p.name) AS label",
+        );
+        assert_eq!(result.rows, vec![vec![PropertyValue::Null, "Bob".into()]]);
+    }
+
+    #[test]
+    fn test_temporal_functions() {
+        let (graph, _dir) = create_test_graph();
+        for (name, created) in [("old", "2023-06-01T12:00:00Z"), ("new", "2024-03-01T12:00:00Z")] {
+            run_query(&graph, &format!("MERGE (e:Event {{name: '{}'}}) ON CREATE SET e.created = datetime('{}')", name, created));
+        }
+
+        let result = run_query(&graph, "MATCH (e:Event) WHERE e.created > datetime('2024-01-01') RETURN e.name AS name");
+        assert_eq!(result.rows, vec![vec!["new".into()]]);
+
+        let result = run_query(
+            &graph,
+            "MATCH (e:Event) WHERE e.name = 'new' \
+             RETURN date(e.created) AS day, duration.between(datetime('2024-03-01'), e.created) AS since",
+        );
+        assert_eq!(
+            result.rows,
+            vec![vec![PropertyValue::Date(19_783), PropertyValue::Duration(12 * 3_600_000_000_000)]]
+        );
+
+        let result = run_query(&graph, "MATCH (e:Event) RETURN e.name AS name ORDER BY e.created DESC");
+        assert_eq!(result.rows, vec![vec!["new".into()], vec!["old".into()]]);
+    }
+
+    #[test]
+    fn test_inline_pattern_properties() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Alice", "Rome", PropertyValue::Integer(25));
+
+        let result = run_query(&graph, "MATCH (p:Person {name: 'Alice', city: 'Rome'}) RETURN p.age AS age");
+        assert_eq!(result.rows, vec![vec![PropertyValue::Integer(25)]]);
+        assert_eq!(run_query(&graph, "MATCH (p {city: 'Paris'}) RETURN p.age AS age").rows.len(), 1);
+
+        let query = parse_simple("MATCH (a:Person)-[:KNOWS]->(b:Person {name: 'Bob'}) RETURN b").unwrap();
+        assert!(matches!(QueryPlanner::new().plan(&query), Err(Error::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_merge_clause() {
+        let (graph, _dir) = create_test_graph();
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        let query = parse_simple(
+            "MERGE (p:Person {email: $email}) \
+             ON CREATE SET p.visits = 1, p:New \
+             ON MATCH SET p.visits = p.visits + 1 \
+             RETURN p.visits AS visits",
+        )
+        .unwrap();
+        let plan = QueryPlanner::new().with_default_limit(10).plan(&query).unwrap();
+        let params = HashMap::from([("email".to_string(), PropertyValue::from("alice@example.com"))]);
+
+        let created = executor.execute(&plan, &params).unwrap();
+        assert_eq!(created.rows, vec![vec![PropertyValue::Integer(1)]]);
+        assert_eq!(created.stats.nodes_created, 1);
+        assert_eq!(created.stats.properties_set, 1);
+
+        let matched = executor.execute(&plan, &params).unwrap();
+        assert_eq!(matched.rows, vec![vec![PropertyValue::Integer(2)]]);
+        assert_eq!(matched.stats.nodes_created, 0);
+
+        let people = graph.find_nodes_by_label("Person").unwrap();
+        assert_eq!(people.len(), 1);
+        assert!(people[0].has_label_name("New"));
+
+        // A different key creates a second node
+        let params = HashMap::from([("email".to_string(), PropertyValue::from("bob@example.com"))]);
+        assert_eq!(executor.execute(&plan, &params).unwrap().stats.nodes_created, 1);
+        assert_eq!(graph.find_nodes_by_label("Person").unwrap().len(), 2);
+
+        // Null keys cannot be merged on
+        let params = HashMap::from([("email".to_string(), PropertyValue::Null)]);
+        assert!(executor.execute(&plan, &params).is_err());
+
+        let plan_err = |cypher: &str| QueryPlanner::new().plan(&parse_simple(cypher).unwrap()).unwrap_err();
+        assert!(matches!(plan_err("MERGE (a:Person)-[:KNOWS]->(b:Person)"), Error::InvalidQuery(_)));
+        assert!(matches!(plan_err("MATCH (a:Person) MERGE (b:Person {name: 'x'})"), Error::InvalidQuery(_)));
+    }
+
+    #[test]
+    fn test_unwind_clause() {
+        let (graph, _dir) = create_test_graph();
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        let run = |cypher: &str, params: &HashMap<String, PropertyValue>| {
+            let plan = QueryPlanner::new().plan(&parse_simple(cypher).unwrap()).unwrap();
+            executor.execute(&plan, params).unwrap()
+        };
+
+        let result = run("UNWIND [1, 2, 3] AS x RETURN x", &HashMap::new());
+        assert_eq!(result.rows, (1..=3).map(|x| vec![PropertyValue::Integer(x)]).collect::<Vec<_>>());
+
+        // One MERGE per element, merging repeated elements into one node
+        let names = PropertyValue::Array(vec!["Alice".into(), "Bob".into(), "Alice".into()]);
+        let params = HashMap::from([("names".to_string(), names)]);
+        let result = run("UNWIND $names AS name MERGE (p:Person {name: name})", &params);
+        assert_eq!(result.stats.nodes_created, 2);
+        assert_eq!(graph.find_nodes_by_label("Person").unwrap().len(), 2);
+
+        // Map elements are read by field, by the seek and by SET
+        let pair = |a: &str, b: i64| {
+            PropertyValue::Map(HashMap::from([("a".to_string(), a.into()), ("b".to_string(), PropertyValue::Integer(b))]))
+        };
+        let params = HashMap::from([("pairs".to_string(), PropertyValue::Array(vec![pair("Alice", 1), pair("Bob", 2)]))]);
+        let result = run("UNWIND $pairs AS pair MATCH (p:Person {name: pair.a}) SET p.rank = pair.b", &params);
+        assert_eq!(result.stats.properties_set, 2);
+        let bob = graph.find_nodes_by_label_and_property("Person", "name", &"Bob".into()).unwrap();
+        assert_eq!(bob[0].get_property("rank"), Some(&PropertyValue::Integer(2)));
+
+        let result = run("MATCH (p:Person) WHERE p.name = 'Alice' UNWIND [p.rank, 10] AS r RETURN r", &HashMap::new());
+        assert_eq!(result.rows, vec![vec![PropertyValue::Integer(1)], vec![PropertyValue::Integer(10)]]);
+
+        // A null list unwinds to no rows
+        let params = HashMap::from([("names".to_string(), PropertyValue::Null)]);
+        assert!(run("UNWIND $names AS name RETURN name", &params).rows.is_empty());
+    }
+
+    #[test]
+    fn test_failed_write_query_stores_nothing() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        let execute = |cypher: &str| {
+            let plan = QueryPlanner::new().plan(&parse_simple(cypher).unwrap()).unwrap();
+            executor.execute(&plan, &HashMap::new())
+        };
+
+        // The last element fails after the first two were merged and updated
+        let failed = execute("UNWIND [1, 2, 0] AS x MERGE (n:Item {id: x}) SET n.ratio = 10 / x");
+        assert!(matches!(failed, Err(Error::QueryExecution(msg)) if msg == "Division by zero"));
+        assert!(graph.find_nodes_by_label("Item").unwrap().is_empty());
+        assert_eq!(graph.node_count().unwrap(), 1);
+
+        let failed = execute("MATCH (p:Person) SET p.age = 31, p.ratio = 1 / 0");
+        assert!(failed.is_err());
+        let alice = graph.find_nodes_by_label("Person").unwrap().remove(0);
+        assert_eq!(alice.get_property("age"), Some(&PropertyValue::Integer(30)));
+        assert_eq!(alice.get_property("ratio"), None);
+
+        // Without the failing element every write is stored
+        let result = execute("UNWIND [1, 2] AS x MERGE (n:Item {id: x}) SET n.ratio = 10 / x").unwrap();
+        assert_eq!(result.stats.nodes_created, 2);
+        assert_eq!(graph.find_nodes_by_label("Item").unwrap().len(), 2);
+        assert_eq!(graph.node_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_set_and_remove_clauses() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Bob", "Rome", PropertyValue::Integer(40));
+        let alice = || graph.find_nodes_by_label_and_property("Person", "name", &"Alice".into()).unwrap()[0].clone();
+
+        let result = run_query(&graph, "MATCH (p:Person) WHERE p.name = 'Alice' SET p.age = 31 RETURN p.age AS age");
+        assert_eq!(result.rows, vec![vec![PropertyValue::Integer(31)]]);
+        assert_eq!(result.stats.properties_set, 1);
+
+        // The property index follows the new value
+        assert!(run_query(&graph, "MATCH (p:Person {age: 30}) RETURN p.name AS name").rows.is_empty());
+        assert_eq!(run_query(&graph, "MATCH (p:Person {age: 31}) RETURN p.name AS name").rows.len(), 1);
+
+        let result = run_query(&graph, "MATCH (p:Person {name: 'Alice'}) SET p += {city: 'Lyon', nickname: 'Al'}, p:Admin");
+        assert_eq!(result.stats.properties_set, 2);
+        assert_eq!(alice().get_property("city"), Some(&"Lyon".into()));
+        assert_eq!(graph.find_nodes_by_label("Admin").unwrap().len(), 1);
+
+        // Null removes, as does REMOVE
+        run_query(&graph, "MATCH (p:Person {name: 'Alice'}) SET p.city = null");
+        assert_eq!(alice().get_property("city"), None);
+        let result = run_query(&graph, "MATCH (p:Person {name: 'Alice'}) REMOVE p.nickname, p.missing, p:Admin");
+        assert_eq!(result.stats.properties_set, 1);
+        assert_eq!(alice().get_property("nickname"), None);
+        assert!(graph.find_nodes_by_label("Admin").unwrap().is_empty());
+        assert!(alice().has_label_name("Person"));
+
+        // Replacing drops every property not in the map
+        run_query(&graph, "MATCH (p:Person {name: 'Alice'}) SET p = {name: 'Alice', active: true}");
+        assert_eq!(alice().properties.len(), 2);
+        assert_eq!(alice().get_property("active"), Some(&PropertyValue::Boolean(true)));
+
+        // Each row reads its node's current value
+        let result = run_query(&graph, "MATCH (p:Person) SET p.visits = 1, p.visits = p.visits + 1");
+        assert_eq!(result.stats.properties_set, 4);
+        for person in graph.find_nodes_by_label("Person").unwrap() {
+            assert_eq!(person.get_property("visits"), Some(&PropertyValue::Integer(2)));
+        }
+
+        let plan = QueryPlanner::new().plan(&parse_simple("MATCH (p:Person) SET p = 5").unwrap()).unwrap();
+        let executor = QueryExecutor::new(Arc::new(graph.clone()));
+        assert!(executor.execute(&plan, &HashMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_delete_clauses() {
+        let (graph, _dir) = create_test_graph();
+        let alice = create_named(&graph, "Alice");
+        let bob = create_named(&graph, "Bob");
+        let carol = create_named(&graph, "Carol");
+        let dave = create_named(&graph, "Dave");
+        graph.create_relationship(alice, "KNOWS", bob).unwrap();
+        graph.create_relationship(bob, "KNOWS", carol).unwrap();
+        graph.create_relationship(carol, "KNOWS", dave).unwrap();
+        graph.create_relationship(carol, "KNOWS", carol).unwrap();
+
+        // A node with relationships cannot be deleted on its own
+        let plan = QueryPlanner::new()
+            .plan(&parse_simple("MATCH (p:Person) WHERE p.name = 'Bob' DELETE p").unwrap())
+            .unwrap();
+        let err = QueryExecutor::new(Arc::new(graph.clone())).execute(&plan, &HashMap::new()).unwrap_err();
+        assert!(matches!(err, Error::InvalidGraphOperation(_)));
+        assert!(graph.get_node(bob).unwrap().is_some());
+        assert_eq!(graph.relationship_count().unwrap(), 4);
+
+        let result = run_query(&graph, "MATCH (a:Person {name: 'Alice'})-[r:KNOWS]->(b) DELETE r");
+        assert_eq!(result.stats.relationships_deleted, 1);
+        let result = run_query(&graph, "MATCH (p:Person {name: 'Alice'}) DELETE p");
+        assert_eq!(result.stats.nodes_deleted, 1);
+        assert!(graph.get_node(alice).unwrap().is_none());
+
+        // Deleting a node together with its last relationship succeeds
+        let result = run_query(&graph, "MATCH (p:Person {name: 'Bob'})-[r:KNOWS]->(c) DELETE p, r");
+        assert_eq!((result.stats.nodes_deleted, result.stats.relationships_deleted), (1, 1));
+
+        // DETACH DELETE removes the edges first, self-loops included
+        let result = run_query(&graph, "MATCH (p:Person {name: 'Carol'}) DETACH DELETE p RETURN p.name AS name");
+        assert_eq!((result.stats.nodes_deleted, result.stats.relationships_deleted), (1, 2));
+        assert_eq!(result.rows, vec![vec![PropertyValue::Null]]);
+        assert_eq!(graph.relationship_count().unwrap(), 0);
+        assert_eq!(graph.node_count().unwrap(), 1);
+        assert!(graph.get_node(dave).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_in_and_range_predicates() {
+        let (graph, _dir) = create_test_graph();
+        for (name, age) in [("Ann", 12), ("Bob", 18), ("Cid", 25), ("Dee", 30), ("Eve", 64), ("Fay", 65)] {
+            create_person(&graph, name, "Paris", PropertyValue::Integer(age));
+        }
+        create_person(&graph, "Gus", "Paris", PropertyValue::Null);
+        add_range_index(&graph, "Person", "age");
+
+        let names = |predicate: &str| {
+            let query = format!("MATCH (p:Person) WHERE {} RETURN p.name AS name ORDER BY p.name", predicate);
+            run_query(&graph, &query).rows.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>()
+        };
+        let strings = |values: &[&str]| values.iter().map(|v| PropertyValue::String(v.to_string())).collect::<Vec<_>>();
+
+        assert_eq!(names("p.age IN [25, 30, 35]"), strings(&["Cid", "Dee"]));
+        assert_eq!(names("p.age IN []"), strings(&[]));
+        assert_eq!(names("p.age >= 18 AND p.age < 65"), strings(&["Bob", "Cid", "Dee", "Eve"]));
+        assert_eq!(names("18 < p.age AND p.age <= 30"), strings(&["Cid", "Dee"]));
+        assert_eq!(names("p.age < 18 OR p.age >= 65"), strings(&["Ann", "Fay"]));
+        // A null age is neither inside nor outside a range
+        assert_eq!(names("p.age > 0 AND p.name = 'Gus'"), strings(&[]));
+
+        // The range is read from the label+property index, then filtered exactly
+        let plan = plan_with_schema(&graph, "MATCH (p:Person) WHERE p.age >= 18 AND p.age < 65 RETURN p.name AS name");
+        let PhysicalOperator::Project { input, .. } = &plan.root else {
+            panic!("expected a projection, got {:?}", plan.root);
+        };
+        let PhysicalOperator::Filter { input, .. } = input.as_ref() else {
+            panic!("expected a filter, got {:?}", input);
+        };
+        let PhysicalOperator::IndexScan { label, property, range, .. } = input.as_ref() else {
+            panic!("expected an index range scan, got {:?}", input);
+        };
+        assert_eq!((label.as_str(), property.as_str()), ("Person", "age"));
+        assert_eq!(range.0, Some(Expression::Literal(Literal::Integer(18))));
+        assert_eq!(range.1, Some(Expression::Literal(Literal::Integer(65))));
+        assert!(plan.root.uses_index());
+
+        // Ranges on an unlabelled scan, or across OR, still scan every node
+        let unlabelled = plan_with_schema(&graph, "MATCH (p) WHERE p.age > 18 RETURN p");
+        assert!(!unlabelled.root.uses_index());
+        let either = plan_with_schema(&graph, "MATCH (p:Person) WHERE p.age < 18 OR p.age > 64 RETURN p");
+        assert!(!either.root.uses_index());
+
+        // Parameters work as bounds and as lists
+        let plan = plan_with_schema(&graph, "MATCH (p:Person) WHERE p.age > $min AND p.name IN $names RETURN p.name AS name");
+        assert!(plan.root.uses_index());
+        let params = HashMap::from([
+            ("min".to_string(), PropertyValue::Integer(20)),
+            ("names".to_string(), PropertyValue::Array(vec!["Ann".into(), "Dee".into()])),
+        ]);
+        let result = QueryExecutor::new(Arc::new(graph.clone())).execute(&plan, &params).unwrap();
+        assert_eq!(result.rows, vec![vec![PropertyValue::String("Dee".to_string())]]);
+    }
+
+    #[test]
+    fn test_string_predicates() {
+        let (graph, _dir) = create_test_graph();
+        create_named(&graph, "Alice");
+        create_named(&graph, "Alan");
+        create_named(&graph, "bob");
+        create_person(&graph, "Carol", "Rome", PropertyValue::Integer(20));
+        let mut props = Property::new();
+        props.set("name", 42i64);
+        graph.create_node_with_properties(["Person"], props).unwrap();
+
+        let names = |predicate: &str| {
+            let query = format!("MATCH (p:Person) WHERE {} RETURN p.name AS name ORDER BY p.name", predicate);
+            run_query(&graph, &query).rows.into_iter().map(|row| row[0].clone()).collect::<Vec<_>>()
+        };
+        let strings = |values: &[&str]| values.iter().map(|v| PropertyValue::String(v.to_string())).collect::<Vec<_>>();
+
+        assert_eq!(names("p.name STARTS WITH 'Al'"), strings(&["Alan", "Alice"]));
+        assert_eq!(names("p.name ENDS WITH 'ol'"), strings(&["Carol"]));
+        assert_eq!(names("p.name CONTAINS 'o'"), strings(&["Carol", "bob"]));
+        assert_eq!(names("p.name =~ 'A.*'"), strings(&["Alan", "Alice"]));
+        // Matching is case-sensitive, and =~ must match the whole string
+        assert_eq!(names("p.name STARTS WITH 'al'"), strings(&[]));
+        assert_eq!(names("p.name =~ '(?i)B.B'"), strings(&["bob"]));
+        assert_eq!(names("p.name =~ 'Ali'"), strings(&[]));
+        // Non-string values never match and do not fail the query
+        assert_eq!(names("p.city STARTS WITH 'R'"), strings(&["Carol"]));
+        assert_eq!(names("p.name CONTAINS 4"), strings(&[]));
+
+        // A literal pattern that does not compile is rejected while planning
+        let err = QueryPlanner::new()
+            .plan(&parse_simple("MATCH (p:Person) WHERE p.name =~ 'A(' RETURN p").unwrap())
+            .unwrap_err();
+        assert!(matches!(&err, Error::InvalidQuery(msg) if msg.contains("Invalid regular expression 'A('")));
+
+        // A pattern passed as a parameter fails when the query runs
+        let plan = QueryPlanner::new()
+            .plan(&parse_simple("MATCH (p:Person) WHERE p.name =~ $pattern RETURN p").unwrap())
+            .unwrap();
+        let params = HashMap::from([("pattern".to_string(), PropertyValue::String("a)(b".to_string()))]);
+        let err = QueryExecutor::new(Arc::new(graph.clone())).execute(&plan, &params).unwrap_err();
+        assert!(matches!(err, Error::QueryExecution(_)));
+    }
+
+    #[test]
+    fn test_aggregate_functions() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Bob", "Paris", PropertyValue::Integer(40));
+        create_person(&graph, "Carol", "Rome", PropertyValue::Integer(20));
+
+        let result = run_query(
+            &graph,
+            "MATCH (p:Person) RETURN count(p) AS c, sum(p.age) AS s, avg(p.age) AS a, min(p.age) AS lo, max(p.name) AS hi",
+        );
+        assert_eq!(
+            result.rows,
+            vec![vec![
+                PropertyValue::Integer(3),
+                PropertyValue::Integer(90),
+                PropertyValue::Float(30.0),
+                PropertyValue::Integer(20),
+                PropertyValue::String("Carol".to_string()),
+            ]]
+        );
+    }
+
+    #[test]
+    fn test_aggregate_group_by_and_distinct() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Bob", "Paris", PropertyValue::Integer(30));
+        create_person(&graph, "Carol", "Rome", PropertyValue::Integer(20));
+
+        let result = run_query(
+            &graph,
+            "MATCH (p:Person) RETURN p.city AS city, count(*) AS n, count(DISTINCT p.age) AS ages",
+        );
+        assert_eq!(result.columns, vec!["city".to_string(), "n".to_string(), "ages".to_string()]);
+
+        let mut rows = result.rows;
+        rows.sort_by(|a, b| compare_property_values(&a[0], &b[0]));
+        assert_eq!(
+            rows,
+            vec![
+                vec![PropertyValue::String("Paris".to_string()), PropertyValue::Integer(2), PropertyValue::Integer(1)],
+                vec![PropertyValue::String("Rome".to_string()), PropertyValue::Integer(1), PropertyValue::Integer(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sum_promotes_to_float() {
+        let (graph, _dir) = create_test_graph();
+        create_person(&graph, "Alice", "Paris", PropertyValue::Integer(1));
+        create_person(&graph, "Bob", "Paris", PropertyValue::Float(2.5));
+
+        let result = run_query(&graph, "MATCH (p:Person) RETURN sum(p.age)");
+        assert_eq!(result.rows, vec![vec![PropertyValue::Float(3.5)]]);
+    }
+
+    #[test]
+    fn test_integer_arithmetic_is_checked() {
+        use PropertyValue::{Float, Integer, Null};
+        let eval = |l: PropertyValue, op: BinaryOp, r: PropertyValue| evaluate_binary_op(&l, &op, &r, None);
+        let overflows = |result: Result<PropertyValue>| {
+            matches!(result, Err(Error::QueryExecution(msg)) if msg.contains("Integer overflow"))
+        };
+
+        assert_eq!(eval(Integer(i64::MAX - 1), BinaryOp::Add, Integer(1)).unwrap(), Integer(i64::MAX));
+        assert!(overflows(eval(Integer(i64::MAX), BinaryOp::Add, Integer(1))));
+        assert!(overflows(eval(Integer(i64::MIN), BinaryOp::Subtract, Integer(1))));
+        assert!(overflows(eval(Integer(i64::MAX), BinaryOp::Multiply, Integer(1_000_000))));
+        assert!(overflows(eval(Integer(i64::MIN), BinaryOp::Divide, Integer(-1))));
+        assert_eq!(eval(Integer(i64::MIN), BinaryOp::Modulo, Integer(-1)).unwrap(), Integer(0));
+        assert!(eval(Integer(1), BinaryOp::Divide, Integer(0)).is_err());
+        assert!(eval(Integer(1), BinaryOp::Modulo, Integer(0)).is_err());
+
+        // Floats, and powers, never overflow into an error
+        assert_eq!(eval(Integer(i64::MAX), BinaryOp::Multiply, Float(2.0)).unwrap(), Float(i64::MAX as f64 * 2.0));
+        assert_eq!(eval(Integer(2), BinaryOp::Power, Integer(64)).unwrap(), Float(2f64.powi(64)));
+        assert_eq!(eval(Integer(7), BinaryOp::Divide, Integer(2)).unwrap(), Integer(3));
+        assert_eq!(eval(Null, BinaryOp::Add, Integer(1)).unwrap(), Null);
+        assert_eq!(eval("a".into(), BinaryOp::Add, "b".into()).unwrap(), PropertyValue::String("ab".to_string()));
+        assert!(eval("a".into(), BinaryOp::Multiply, Integer(2)).is_err());
+    }
+
+    #[test]
+    fn test_arithmetic_in_query() {
+        let (graph, _dir) = create_test_graph();
+        let mut props = Property::new();
+        props.set("count", i64::MAX / 10);
+        graph.create_node_with_properties(["Counter"], props).unwrap();
+
+        let result = run_query(&graph, "MATCH (c:Counter) RETURN c.count * 2 + 1 AS doubled");
+        assert_eq!(result.rows[0][0], PropertyValue::Integer(i64::MAX / 10 * 2 + 1));
+
+        // The overflow is reported instead of a wrapped result
+        let query = parse_simple("MATCH (c:Counter) RETURN c.count * 1000000 AS big").unwrap();
+        let plan = QueryPlanner::new().plan(&query).unwrap();
+        let err = QueryExecutor::new(Arc::new(graph))
+            .execute(&plan, &HashMap::new())
+            .unwrap_err();
+        assert!(err.to_string().contains("Integer overflow"));
+    }
+
+    #[test]
+    fn test_variable_length_expand() {
+        let (graph, _dir) = create_test_graph();
+        let a = create_named(&graph, "A");
+        let b = create_named(&graph, "B");
+        let c = create_named(&graph, "C");
+        let d = create_named(&graph, "D");
+        graph.create_relationship(a, "KNOWS", b).unwrap();
+        graph.create_relationship(b, "KNOWS", c).unwrap();
+        graph.create_relationship(c, "KNOWS", d).unwrap();
+        graph.create_relationship(a, "LIKES", d).unwrap();
+
+        let result = run_query(
+            &graph,
+            "MATCH p = (a:Person)-[:KNOWS*1..2]->(b) WHERE a.name = 'A' RETURN b.name AS name, length(p) AS hops",
+        );
+        assert_eq!(result.columns, vec!["name".to_string(), "hops".to_string()]);
+
+        let mut rows = result.rows;
+        rows.sort_by(|x, y| compare_property_values(&x[1], &y[1]));
+        assert_eq!(
+            rows,
+            vec![
+                vec![PropertyValue::String("B".to_string()), PropertyValue::Integer(1)],
+                vec![PropertyValue::String("C".to_string()), PropertyValue::Integer(2)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_project_relationship_properties() {
+        let (graph, _dir) = create_test_graph();
+        let alice = create_named(&graph, "Alice");
+        let mut movies = Vec::new();
+        for title in ["Alien", "Brazil", "Casablanca"] {
+            let mut props = Property::new();
+            props.set("title", title);
+            movies.push(graph.create_node_with_properties(["Movie"], props).unwrap().id);
+        }
+        for (movie, stars) in movies.iter().zip([5, 3]) {
+            let mut props = Property::new();
+            props.set("stars", stars as i64);
+            graph
+                .create_relationship_with_properties(alice, "RATED", *movie, props)
+                .unwrap();
+        }
+        // A rating without stars projects as Null
+        graph.create_relationship(alice, "RATED", movies[2]).unwrap();
+
+        let result = run_query(
+            &graph,
+            "MATCH (a:Person)-[r:RATED]->(m) RETURN r.stars AS stars, m.title AS title ORDER BY m.title",
+        );
+        assert_eq!(result.columns, vec!["stars".to_string(), "title".to_string()]);
+        assert_eq!(
+            result.rows,
+            vec![
+                vec![PropertyValue::Integer(5), PropertyValue::String("Alien".to_string())],
+                vec![PropertyValue::Integer(3), PropertyValue::String("Brazil".to_string())],
+                vec![PropertyValue::Null, PropertyValue::String("Casablanca".to_string())],
+            ]
+        );
+
+        // Relationship properties also drive ordering
+        let result = run_query(
+            &graph,
+            "MATCH (a:Person)-[r:RATED]->(m) RETURN m.title AS title ORDER BY r.stars DESC LIMIT 1",
+        );
+        assert_eq!(result.rows, vec![vec![PropertyValue::String("Alien".to_string())]]);
+    }
+
+    #[test]
+    fn test_undirected_expand() {
+        let (graph, _dir) = create_test_graph();
+        let a = create_named(&graph, "A");
+        let b = create_named(&graph, "B");
+        let c = create_named(&graph, "C");
+        graph.create_relationship(a, "FRIEND", b).unwrap();
+        graph.create_relationship(c, "FRIEND", a).unwrap();
+        graph.create_relationship(a, "FRIEND", a).unwrap();
+        graph.create_relationship(b, "LIKES", c).unwrap();
+
+        let pairs = |cypher: &str| {
+            let mut rows: Vec<(String, String)> = run_query(&graph, cypher)
+                .rows
+                .into_iter()
+                .map(|row| match (&row[0], &row[1]) {
+                    (PropertyValue::String(x), PropertyValue::String(y)) => (x.clone(), y.clone()),
+                    other => panic!("unexpected row {:?}", other),
+                })
+                .collect();
+            rows.sort();
+            rows
+        };
+        let pair = |x: &str, y: &str| (x.to_string(), y.to_string());
+
+        // Each edge matches in both orientations, the self-loop once
+        assert_eq!(
+            pairs("MATCH (x:Person)-[:FRIEND]-(y) RETURN x.name AS x, y.name AS y"),
+            vec![pair("A", "A"), pair("A", "B"), pair("A", "C"), pair("B", "A"), pair("C", "A")]
+        );
+
+        // Directed patterns still follow the arrow
+        assert_eq!(
+            pairs("MATCH (x:Person)-[:FRIEND]->(y) RETURN x.name AS x, y.name AS y"),
+            vec![pair("A", "A"), pair("A", "B"), pair("C", "A")]
+        );
+        assert_eq!(
+            pairs("MATCH (x:Person)<-[:FRIEND]-(y) RETURN x.name AS x, y.name AS y"),
+            vec![pair("A", "A"), pair("A", "C"), pair("B", "A")]
+        );
+    }
+
+    #[test]
+    fn test_variable_length_expand_terminates_on_cycles() {
+        let (graph, _dir) = create_test_graph();
+        let a = create_named(&graph, "A");
+        let b = create_named(&graph, "B");
+        graph.create_relationship(a, "KNOWS", b).unwrap();
+        graph.create_relationship(b, "KNOWS", a).unwrap();
+
+        // Each relationship is used at most once per path: A->B, A->B->A
+        let result = run_query(&graph, "MATCH (a)-[*1..10]->(b) WHERE a.name = 'A' RETURN b.name");
+        assert_eq!(result.rows.len(), 2);
+    }
+
+    #[test]
+    fn test_unbounded_variable_length_rejected() {
+        let query = parse_simple("MATCH (a)-[:KNOWS*]->(b) RETURN b").unwrap();
+        let result = QueryPlanner::new().plan(&query);
+        assert!(matches!(result, Err(Error::InvalidQuery(_))));
+
+        let query = parse_simple("MATCH (a)-[:KNOWS*2..]->(b) RETURN b").unwrap();
+        assert!(QueryPlanner::new().plan(&query).is_err());
+    }
+
+    #[test]
+    fn test_shortest_path_pattern() {
+        let (graph, _dir) = create_test_graph();
+        let a = create_named(&graph, "A");
+        let b = create_named(&graph, "B");
+        let c = create_named(&graph, "C");
+        let d = create_named(&graph, "D");
+        graph.create_relationship(a, "KNOWS", b).unwrap();
+        graph.create_relationship(b, "KNOWS", c).unwrap();
+        graph.create_relationship(c, "KNOWS", d).unwrap();
+        graph.create_relationship(d, "KNOWS", a).unwrap();
+        graph.create_relationship(b, "KNOWS", d).unwrap();
+
+        let result = run_query(
+            &graph,
+            "MATCH p = shortestPath((a:Person)-[:KNOWS*]->(b:Person)) WHERE b.name = 'D' RETURN a.name AS name, length(p) AS hops",
+        );
+        let mut rows = result.rows;
+        rows.sort_by(|x, y| compare_property_values(&x[0], &y[0]));
+        assert_eq!(
+            rows,
+            vec![
+                vec![PropertyValue::String("A".to_string()), PropertyValue::Integer(2)],
+                vec![PropertyValue::String("B".to_string()), PropertyValue::Integer(1)],
+                vec![PropertyValue::String("C".to_string()), PropertyValue::Integer(1)],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_all_shortest_paths_pattern() {
+        let (graph, _dir) = create_test_graph();
+        let a = create_named(&graph, "A");
+        let b = create_named(&graph, "B");
+        let c = create_named(&graph, "C");
+        let d = create_named(&graph, "D");
+        graph.create_relationship(a, "KNOWS", b).unwrap();
+        graph.create_relationship(a, "KNOWS", c).unwrap();
+        graph.create_relationship(b, "KNOWS", d).unwrap();
+        graph.create_relationship(d, "KNOWS", c).unwrap();
+
+        // Undirected: A-B-D and A-C-D; directed only A->B->D
+        let undirected = run_query(&graph, "MATCH p = allShortestPaths((a)-[*]-(b)) WHERE a.name = 'A' RETURN b.name, p");
+        let to_d = |result: &QueryResult| {
+            result.rows.iter().filter(|row| row[0] == PropertyValue::String("D".to_string())).count()
+        };
+        assert_eq!(to_d(&undirected), 2);
+
+        let directed = run_query(&graph, "MATCH p = allShortestPaths((a)-[*]->(b)) WHERE a.name = 'A' RETURN b.name, p");
+        assert_eq!(to_d(&directed), 1);
+    }
+
+    #[test]
+    fn test_shortest_path_requires_single_relationship() {
+        let query = parse_simple("MATCH p = shortestPath((a)-->(b)-->(c)) RETURN p").unwrap();
+        assert!(matches!(QueryPlanner::new().plan(&query), Err(Error::InvalidQuery(_))));
+
+        let query = parse_simple("MATCH p = shortestPath((a)-[*2..5]->(b)) RETURN p").unwrap();
+        assert!(QueryPlanner::new().plan(&query).is_err());
+    }
+
+    #[test]
+    fn test_call_pagerank() {
+        let (graph, _dir) = create_test_graph();
+        let a = create_named(&graph, "A");
+        let b = create_named(&graph, "B");
+        let c = create_named(&graph, "C");
+        graph.create_relationship(a, "LINKS", c).unwrap();
+        graph.create_relationship(b, "LINKS", c).unwrap();
+
+        let result = run_query(&graph, "CALL algo.pagerank()");
+        assert_eq!(result.columns, vec!["nodeId".to_string(), "score".to_string()]);
+        assert_eq!(result.rows.len(), 3);
+        assert_eq!(result.rows[0][0], PropertyValue::Integer(c.as_internal() as i64));
+
+        let total: f64 = result
+            .rows
+            .iter()
+            .map(|row| match row[1] {
+                PropertyValue::Float(score) => score,
+                _ => panic!("Expected float score"),
+            })
+            .sum();
+        assert!((total - 1.0).abs() < 1e-9);
+
+        let result = run_query(&graph, "CALL algo.pagerank(0.5, 50) YIELD score RETURN score AS score LIMIT 1");
+        assert_eq!(result.columns, vec!["score".to_string()]);
+        assert_eq!(result.rows.len(), 1);
+    }
+
+    #[test]
+    fn test_call_unknown_procedure() {
+        let (graph, _dir) = create_test_graph();
+        let query = parse_simple("CALL algo.nothing()").unwrap();
+        let plan = QueryPlanner::new().plan(&query).unwrap();
+        let result = QueryExecutor::new(Arc::new(graph)).execute(&plan, &HashMap::new());
+        assert!(matches!(result, Err(Error::QueryExecution(_))));
+    }
+
+    #[test]
+    fn test_execute_cached() {
+        let (graph, _dir) = create_test_graph();
+        create_named(&graph, "Alice");
+        let cache = Arc::new(QueryCache::default());
+        let executor = QueryExecutor::new(Arc::new(graph.clone())).with_cache(Arc::clone(&cache));
+        let run = |cypher: &str| {
+            let plan = QueryPlanner::new().plan(&parse_simple(cypher).unwrap()).unwrap();
+            executor.execute_cached(cypher, &plan, &HashMap::new()).unwrap()
+        };
+        let names = "MATCH (p:Person) RETURN p.name";
+
+        assert_eq!(run(names).stats.cache_hits, 0);
+        let cached = run(names);
+        assert_eq!(cached.stats.cache_hits, 1);
+        assert_eq!(cached.rows, vec![vec![PropertyValue::from("Alice")]]);
+
+        // Every kind of write invalidates the cached result
+        for (write, expected) in [
+            ("MERGE (p:Person {name: 'Bob'})", vec!["Alice", "Bob"]),
+            ("MATCH (p:Person {name: 'Bob'}) SET p.name = 'Carol'", vec!["Alice", "Carol"]),
+            ("MATCH (p:Person {name: 'Carol'}) DELETE p", vec!["Alice"]),
+        ] {
+            assert_eq!(run(write).stats.cache_hits, 0);
+            let result = run(names);
+            assert_eq!(result.stats.cache_hits, 0, "{}", write);
+            let mut rows: Vec<_> = result.rows.iter().map(|row| row[0].clone()).collect();
+            rows.sort_by_key(|value| format!("{:?}", value));
+            let expected: Vec<_> = expected.into_iter().map(PropertyValue::from).collect();
+            assert_eq!(rows, expected, "{}", write);
+        }
+
+        // So do writes that bypass the query engine
+        create_named(&graph, "Dave");
+        assert_eq!(run(names).rows.len(), 2);
+        assert_eq!(cache.hits(), 1);
+    }
+}
@@ -0,0 +1,296 @@
+//! Scalar functions
+//!
+//! [`evaluate_function`] computes the functions that map one row's argument
+//! values to a single value, such as `coalesce()` or `toInteger()`. Function
+//! names are case-insensitive. Aggregates like `count()` are planned
+//! separately, see [`crate::AggregateFunction`].
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use qilbee_core::{Error, PropertyValue, Result};
+use std::collections::HashMap;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+const NANOS_PER_MILLI: i64 = 1_000_000;
+
+/// Evaluate the scalar function `name` on its evaluated `args`
+pub fn evaluate_function(name: &str, args: &[PropertyValue]) -> Result<PropertyValue> {
+    match name.to_lowercase().as_str() {
+        "length" => length(args),
+        "coalesce" => coalesce(args),
+        "tointeger" => to_integer(single_argument("toInteger", args)?),
+        "tofloat" => to_float(single_argument("toFloat", args)?),
+        "tostring" => to_string(single_argument("toString", args)?),
+        "timestamp" => timestamp(args),
+        "datetime" => datetime(args),
+        "date" => date(args),
+        "duration" => duration(single_argument("duration", args)?),
+        "duration.between" => duration_between(args),
+        _ => Err(Error::QueryExecution(format!("Unknown function: {}", name))),
+    }
+}
+
+fn single_argument<'a>(name: &str, args: &'a [PropertyValue]) -> Result<&'a PropertyValue> {
+    match args {
+        [arg] => Ok(arg),
+        _ => Err(Error::QueryExecution(format!("{}() expects a single argument", name))),
+    }
+}
+
+/// Hops of a path, elements of a list or characters of a string
+fn length(args: &[PropertyValue]) -> Result<PropertyValue> {
+    match args {
+        // Paths are maps of `nodes` and `relationships`; their length is the hop count
+        [PropertyValue::Map(path)] => match path.get("relationships") {
+            Some(PropertyValue::Array(rels)) => Ok(PropertyValue::Integer(rels.len() as i64)),
+            _ => Err(Error::QueryExecution("length() expects a path".to_string())),
+        },
+        [PropertyValue::Array(items)] => Ok(PropertyValue::Integer(items.len() as i64)),
+        [PropertyValue::String(s)] => Ok(PropertyValue::Integer(s.chars().count() as i64)),
+        [PropertyValue::Null] => Ok(PropertyValue::Null),
+        _ => Err(Error::QueryExecution("length() expects a single path argument".to_string())),
+    }
+}
+
+/// The first non-null argument, or null
+fn coalesce(args: &[PropertyValue]) -> Result<PropertyValue> {
+    if args.is_empty() {
+        return Err(Error::QueryExecution("coalesce() expects at least one argument".to_string()));
+    }
+    Ok(args.iter().find(|arg| !arg.is_null()).cloned().unwrap_or(PropertyValue::Null))
+}
+
+/// Integer value of a number, boolean or numeric string
+///
+/// Floats are truncated towards zero. Strings that are not numbers and
+/// floats out of the integer range give null.
+fn to_integer(value: &PropertyValue) -> Result<PropertyValue> {
+    let integer = match value {
+        PropertyValue::Null => None,
+        PropertyValue::Integer(i) => Some(*i),
+        PropertyValue::Boolean(b) => Some(*b as i64),
+        PropertyValue::Float(f) => truncate(*f),
+        PropertyValue::String(s) => {
+            let s = s.trim();
+            s.parse().ok().or_else(|| s.parse().ok().and_then(truncate))
+        }
+        _ => return Err(Error::QueryExecution("toInteger() expects a number, boolean or string".to_string())),
+    };
+    Ok(integer.map_or(PropertyValue::Null, PropertyValue::Integer))
+}
+
+fn truncate(f: f64) -> Option<i64> {
+    // `i64::MAX as f64` rounds up to 2^63, which no i64 holds
+    (f.is_finite() && f >= i64::MIN as f64 && f < i64::MAX as f64).then(|| f.trunc() as i64)
+}
+
+/// Float value of a number or numeric string; other strings give null
+fn to_float(value: &PropertyValue) -> Result<PropertyValue> {
+    let float = match value {
+        PropertyValue::Null => None,
+        PropertyValue::Integer(i) => Some(*i as f64),
+        PropertyValue::Float(f) => Some(*f),
+        PropertyValue::String(s) => s.trim().parse().ok(),
+        _ => return Err(Error::QueryExecution("toFloat() expects a number or string".to_string())),
+    };
+    Ok(float.map_or(PropertyValue::Null, PropertyValue::Float))
+}
+
+/// String form of a number, boolean or string
+fn to_string(value: &PropertyValue) -> Result<PropertyValue> {
+    let string = match value {
+        PropertyValue::Null => return Ok(PropertyValue::Null),
+        PropertyValue::String(s) => s.clone(),
+        PropertyValue::Integer(i) => i.to_string(),
+        // Debug formatting keeps the fraction of whole floats: `1.0`, not `1`
+        PropertyValue::Float(f) => format!("{:?}", f),
+        PropertyValue::Boolean(b) => b.to_string(),
+        _ => return Err(Error::QueryExecution("toString() expects a number, boolean or string".to_string())),
+    };
+    Ok(PropertyValue::String(string))
+}
+
+/// Milliseconds since the Unix epoch, now
+fn timestamp(args: &[PropertyValue]) -> Result<PropertyValue> {
+    if !args.is_empty() {
+        return Err(Error::QueryExecution("timestamp() takes no arguments".to_string()));
+    }
+    Ok(PropertyValue::Integer(Utc::now().timestamp_millis()))
+}
+
+/// The current instant, or the instant a date, datetime or string names
+///
+/// Strings are RFC 3339 timestamps, or `2024-01-01T10:00:00` and
+/// `2024-01-01`, read as UTC.
+fn datetime(args: &[PropertyValue]) -> Result<PropertyValue> {
+    let millis = match args {
+        [] => Utc::now().timestamp_millis(),
+        [PropertyValue::Null] => return Ok(PropertyValue::Null),
+        [PropertyValue::DateTime(millis)] => *millis,
+        [PropertyValue::Date(days)] => *days as i64 * MILLIS_PER_DAY,
+        [PropertyValue::String(s)] => parse_datetime(s)?,
+        _ => return Err(Error::QueryExecution("datetime() expects a string, date or datetime".to_string())),
+    };
+    Ok(PropertyValue::DateTime(millis))
+}
+
+/// Today's date, or the date of a datetime or a `2024-01-01` string
+fn date(args: &[PropertyValue]) -> Result<PropertyValue> {
+    let millis = match args {
+        [] => Utc::now().timestamp_millis(),
+        [PropertyValue::Null] => return Ok(PropertyValue::Null),
+        [PropertyValue::Date(days)] => return Ok(PropertyValue::Date(*days)),
+        [PropertyValue::DateTime(millis)] => *millis,
+        [PropertyValue::String(s)] => parse_datetime(s)?,
+        _ => return Err(Error::QueryExecution("date() expects a string, date or datetime".to_string())),
+    };
+    let days = i32::try_from(millis.div_euclid(MILLIS_PER_DAY))
+        .map_err(|_| Error::QueryExecution("date() is out of range".to_string()))?;
+    Ok(PropertyValue::Date(days))
+}
+
+/// Milliseconds since the Unix epoch of a datetime string
+fn parse_datetime(s: &str) -> Result<i64> {
+    let s = s.trim();
+    if let Ok(datetime) = DateTime::parse_from_rfc3339(s) {
+        return Ok(datetime.timestamp_millis());
+    }
+    if let Ok(datetime) = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f") {
+        return Ok(datetime.and_utc().timestamp_millis());
+    }
+    NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|datetime| datetime.and_utc().timestamp_millis())
+        .ok_or_else(|| Error::QueryExecution(format!("Invalid datetime '{}'", s)))
+}
+
+/// A duration from a map of `days`, `hours`, `minutes`, `seconds`,
+/// `milliseconds` and `nanoseconds`
+fn duration(value: &PropertyValue) -> Result<PropertyValue> {
+    const UNITS: [(&str, i64); 6] = [
+        ("days", MILLIS_PER_DAY * NANOS_PER_MILLI),
+        ("hours", 3_600_000 * NANOS_PER_MILLI),
+        ("minutes", 60_000 * NANOS_PER_MILLI),
+        ("seconds", 1_000 * NANOS_PER_MILLI),
+        ("milliseconds", NANOS_PER_MILLI),
+        ("nanoseconds", 1),
+    ];
+    let components: &HashMap<String, PropertyValue> = match value {
+        PropertyValue::Null => return Ok(PropertyValue::Null),
+        PropertyValue::Map(components) => components,
+        _ => return Err(Error::QueryExecution("duration() expects a map of units".to_string())),
+    };
+    if let Some(unit) = components.keys().find(|key| !UNITS.iter().any(|(name, _)| name == key)) {
+        return Err(Error::QueryExecution(format!("Unknown duration unit: {}", unit)));
+    }
+
+    let overflow = || Error::QueryExecution("duration() is out of range".to_string());
+    let mut nanos: i64 = 0;
+    for (unit, scale) in UNITS {
+        let amount = match components.get(unit) {
+            None | Some(PropertyValue::Null) => continue,
+            Some(PropertyValue::Integer(amount)) => *amount,
+            Some(_) => return Err(Error::QueryExecution(format!("Duration {} must be an integer", unit))),
+        };
+        nanos = amount.checked_mul(scale).and_then(|part| nanos.checked_add(part)).ok_or_else(overflow)?;
+    }
+    Ok(PropertyValue::Duration(nanos))
+}
+
+/// The duration from one date or datetime to another
+fn duration_between(args: &[PropertyValue]) -> Result<PropertyValue> {
+    let millis = |value: &PropertyValue| match value {
+        PropertyValue::DateTime(millis) => Ok(Some(*millis)),
+        PropertyValue::Date(days) => Ok(Some(*days as i64 * MILLIS_PER_DAY)),
+        PropertyValue::Null => Ok(None),
+        _ => Err(Error::QueryExecution("duration.between() expects dates or datetimes".to_string())),
+    };
+    let [from, to] = args else {
+        return Err(Error::QueryExecution("duration.between() expects two arguments".to_string()));
+    };
+    let (Some(from), Some(to)) = (millis(from)?, millis(to)?) else {
+        return Ok(PropertyValue::Null);
+    };
+    to.checked_sub(from)
+        .and_then(|millis| millis.checked_mul(NANOS_PER_MILLI))
+        .map(PropertyValue::Duration)
+        .ok_or_else(|| Error::QueryExecution("duration.between() is out of range".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call(name: &str, args: &[PropertyValue]) -> PropertyValue {
+        evaluate_function(name, args).unwrap()
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let args = [PropertyValue::Null, PropertyValue::Integer(1), PropertyValue::Integer(2)];
+        assert_eq!(call("coalesce", &args), PropertyValue::Integer(1));
+        assert_eq!(call("COALESCE", &[PropertyValue::Null]), PropertyValue::Null);
+        assert!(evaluate_function("coalesce", &[]).is_err());
+    }
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(call("toInteger", &["42".into()]), PropertyValue::Integer(42));
+        assert_eq!(call("toInteger", &[" 4.7 ".into()]), PropertyValue::Integer(4));
+        assert_eq!(call("toInteger", &[PropertyValue::Float(-4.7)]), PropertyValue::Integer(-4));
+        assert_eq!(call("toInteger", &[PropertyValue::Boolean(true)]), PropertyValue::Integer(1));
+        assert_eq!(call("toInteger", &["abc".into()]), PropertyValue::Null);
+        assert_eq!(call("toInteger", &[PropertyValue::Float(f64::NAN)]), PropertyValue::Null);
+        assert_eq!(call("toInteger", &[PropertyValue::Float(1e19)]), PropertyValue::Null);
+
+        assert_eq!(call("toFloat", &["2.5".into()]), PropertyValue::Float(2.5));
+        assert_eq!(call("toFloat", &[PropertyValue::Integer(3)]), PropertyValue::Float(3.0));
+        assert_eq!(call("toFloat", &["abc".into()]), PropertyValue::Null);
+
+        assert_eq!(call("toString", &[PropertyValue::Integer(7)]), "7".into());
+        assert_eq!(call("toString", &[PropertyValue::Float(1.0)]), "1.0".into());
+        assert_eq!(call("toString", &[PropertyValue::Boolean(false)]), "false".into());
+
+        for name in ["toInteger", "toFloat", "toString"] {
+            assert_eq!(call(name, &[PropertyValue::Null]), PropertyValue::Null);
+            assert!(evaluate_function(name, &[PropertyValue::Array(vec![])]).is_err());
+            assert!(evaluate_function(name, &[]).is_err());
+        }
+    }
+
+    #[test]
+    fn test_temporal_functions() {
+        let PropertyValue::Integer(now) = call("timestamp", &[]) else {
+            panic!("Expected an integer timestamp");
+        };
+        assert!(now > 1_700_000_000_000);
+        assert!(matches!(call("datetime", &[]), PropertyValue::DateTime(_)));
+        assert!(matches!(call("date", &[]), PropertyValue::Date(_)));
+
+        let new_year = PropertyValue::DateTime(1_704_067_200_000);
+        assert_eq!(call("datetime", &["2024-01-01".into()]), new_year);
+        assert_eq!(call("datetime", &["2024-01-01T00:00:00".into()]), new_year);
+        assert_eq!(call("datetime", &["2024-01-01T01:00:00+01:00".into()]), new_year);
+        assert_eq!(call("datetime", &[PropertyValue::Date(19_723)]), new_year);
+        assert_eq!(call("date", &["2024-01-01".into()]), PropertyValue::Date(19_723));
+        assert_eq!(call("date", &[PropertyValue::DateTime(1_704_067_200_000 + 3_600_000)]), PropertyValue::Date(19_723));
+        assert_eq!(call("date", &[PropertyValue::DateTime(-1)]), PropertyValue::Date(-1));
+        assert!(evaluate_function("datetime", &["yesterday".into()]).is_err());
+
+        let later = PropertyValue::DateTime(1_704_067_200_000 + 90_000);
+        assert_eq!(call("duration.between", &[new_year.clone(), later]), PropertyValue::Duration(90_000_000_000));
+        assert_eq!(
+            call("duration.between", &[PropertyValue::Date(19_724), new_year.clone()]),
+            PropertyValue::Duration(-MILLIS_PER_DAY * NANOS_PER_MILLI)
+        );
+        assert_eq!(call("duration.between", &[PropertyValue::Null, new_year]), PropertyValue::Null);
+
+        let components = HashMap::from([
+            ("minutes".to_string(), PropertyValue::Integer(1)),
+            ("seconds".to_string(), PropertyValue::Integer(30)),
+        ]);
+        assert_eq!(call("duration", &[PropertyValue::Map(components)]), PropertyValue::Duration(90_000_000_000));
+        let unknown = HashMap::from([("weeks".to_string(), PropertyValue::Integer(1))]);
+        assert!(evaluate_function("duration", &[PropertyValue::Map(unknown)]).is_err());
+    }
+}
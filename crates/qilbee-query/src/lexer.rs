@@ -174,6 +174,9 @@ pub enum Token {
     #[token("=")]
     Equals,
 
+    #[token("=~")]
+    RegexMatch,
+
     #[token("<>")]
     NotEquals,
 
@@ -395,4 +398,15 @@ mod tests {
         assert!(tokens2.contains(&Token::Match));
         assert!(tokens2.contains(&Token::Return));
     }
+
+    #[test]
+    fn test_variable_length_relationship() {
+        let tokens = tokenize("MATCH (a)-[:KNOWS*1..3]->(b)");
+
+        let star = tokens.iter().position(|t| *t == Token::Star).unwrap();
+        assert_eq!(
+            &tokens[star..star + 4],
+            &[Token::Star, Token::Integer(1), Token::DoubleDot, Token::Integer(3)]
+        );
+    }
 }
@@ -16,12 +16,15 @@ pub mod parser;
 pub mod simple_parser;
 pub mod planner;
 pub mod executor;
+pub mod functions;
+pub mod cache;
 
 pub use lexer::{tokenize, Token};
-pub use parser::parse;
-pub use simple_parser::parse_simple;
-pub use planner::{QueryPlanner, ExecutionPlan, PhysicalOperator};
-pub use executor::{QueryExecutor, QueryResult, ExecutionStats};
+pub use parser::{parse, QueryMode};
+pub use simple_parser::{parse_simple, parse_simple_with_limits, ParserLimits};
+pub use planner::{QueryPlanner, ExecutionPlan, PhysicalOperator, PlanDescription, AggregateExpression, AggregateFunction};
+pub use executor::{QueryExecutor, QueryResult, QueryResultStream, ExecutionStats, DEFAULT_STREAM_CHUNK_SIZE};
+pub use cache::{QueryCache, DEFAULT_QUERY_CACHE_ENTRIES, DEFAULT_QUERY_CACHE_TTL};
 
 // Type alias for lexer (uses logos::Lexer)
 pub type CypherLexer<'a> = logos::Lexer<'a, Token>;
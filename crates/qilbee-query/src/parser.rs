@@ -8,6 +8,40 @@ use serde::{Deserialize, Serialize};
 pub struct Query {
     /// Query clauses in order
     pub clauses: Vec<Clause>,
+
+    /// `EXPLAIN` or `PROFILE` prefix
+    #[serde(default)]
+    pub mode: QueryMode,
+}
+
+/// How a query is run, set by an `EXPLAIN` or `PROFILE` prefix
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QueryMode {
+    /// Execute the query
+    #[default]
+    Execute,
+    /// Plan the query without executing it
+    Explain,
+    /// Execute the query and record per-operator statistics
+    Profile,
+}
+
+impl Query {
+    /// Whether the query only reads data
+    pub fn is_read_only(&self) -> bool {
+        !self.clauses.iter().any(|clause| {
+            matches!(
+                clause,
+                Clause::Create(_)
+                    | Clause::Merge(_)
+                    | Clause::Delete(_)
+                    | Clause::Set(_)
+                    | Clause::Remove(_)
+                    | Clause::CreateIndex(_)
+                    | Clause::DropIndex(_)
+            )
+        })
+    }
 }
 
 /// A clause in a Cypher query
@@ -23,10 +57,14 @@ pub enum Clause {
     Return(ReturnClause),
     /// CREATE clause
     Create(CreateClause),
+    /// MERGE clause
+    Merge(MergeClause),
     /// DELETE clause
     Delete(DeleteClause),
     /// SET clause
     Set(SetClause),
+    /// REMOVE clause
+    Remove(RemoveClause),
     /// WITH clause
     With(WithClause),
     /// UNWIND clause
@@ -37,6 +75,12 @@ pub enum Clause {
     Skip(Expression),
     /// LIMIT clause
     Limit(Expression),
+    /// CALL clause (procedure invocation)
+    Call(CallClause),
+    /// CREATE INDEX statement
+    CreateIndex(CreateIndexClause),
+    /// DROP INDEX statement
+    DropIndex(DropIndexClause),
 }
 
 /// MATCH clause
@@ -48,9 +92,22 @@ pub struct MatchClause {
 /// A pattern (node-relationship chain)
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Pattern {
+    /// Path variable (`p = (a)-->(b)`)
+    pub variable: Option<String>,
+    /// `shortestPath(...)` / `allShortestPaths(...)` wrapper
+    pub selector: Option<PathSelector>,
     pub elements: Vec<PatternElement>,
 }
 
+/// Shortest path selector wrapping a pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PathSelector {
+    /// `shortestPath(...)`: a single shortest path per pair of endpoints
+    Shortest,
+    /// `allShortestPaths(...)`: every path of minimal length
+    AllShortest,
+}
+
 /// Element in a pattern
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PatternElement {
@@ -111,6 +168,16 @@ pub struct CreateClause {
     pub patterns: Vec<Pattern>,
 }
 
+/// MERGE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MergeClause {
+    pub pattern: Pattern,
+    /// Items of `ON CREATE SET`, applied when no match exists
+    pub on_create: Vec<SetItem>,
+    /// Items of `ON MATCH SET`, applied to an existing match
+    pub on_match: Vec<SetItem>,
+}
+
 /// DELETE clause
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct DeleteClause {
@@ -140,6 +207,52 @@ pub enum SetItem {
         variable: String,
         value: Expression,
     },
+    /// `n += {map}`: adds to the existing properties
+    MergeProperties {
+        variable: String,
+        value: Expression,
+    },
+}
+
+impl SetItem {
+    /// Variable the item updates
+    pub fn variable(&self) -> &str {
+        match self {
+            SetItem::Property { entity, .. } => entity,
+            SetItem::Labels { variable, .. }
+            | SetItem::AllProperties { variable, .. }
+            | SetItem::MergeProperties { variable, .. } => variable,
+        }
+    }
+}
+
+/// REMOVE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoveClause {
+    pub items: Vec<RemoveItem>,
+}
+
+/// Item in REMOVE clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RemoveItem {
+    Property {
+        entity: String,
+        property: String,
+    },
+    Labels {
+        variable: String,
+        labels: Vec<String>,
+    },
+}
+
+impl RemoveItem {
+    /// Variable the item updates
+    pub fn variable(&self) -> &str {
+        match self {
+            RemoveItem::Property { entity, .. } => entity,
+            RemoveItem::Labels { variable, .. } => variable,
+        }
+    }
 }
 
 /// WITH clause
@@ -156,6 +269,46 @@ pub struct UnwindClause {
     pub variable: String,
 }
 
+/// CALL clause
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallClause {
+    /// Dotted procedure name, e.g. `algo.pagerank`
+    pub procedure: String,
+    pub args: Vec<Expression>,
+    /// Columns listed after YIELD (empty means all procedure columns)
+    pub yield_items: Vec<String>,
+}
+
+/// CREATE INDEX statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CreateIndexClause {
+    /// Index name; derived from the label and properties when omitted
+    pub name: Option<String>,
+    pub label: String,
+    /// Indexed properties, more than one making a composite index
+    pub properties: Vec<String>,
+    /// `IF NOT EXISTS`: creating an index that exists is a no-op
+    pub if_not_exists: bool,
+}
+
+impl CreateIndexClause {
+    /// Name of the index, e.g. `person_email` for an unnamed index on
+    /// `:Person(email)`
+    pub fn index_name(&self) -> String {
+        self.name.clone().unwrap_or_else(|| {
+            format!("{}_{}", self.label.to_lowercase(), self.properties.join("_"))
+        })
+    }
+}
+
+/// DROP INDEX statement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DropIndexClause {
+    pub name: String,
+    /// `IF EXISTS`: dropping a missing index is a no-op
+    pub if_exists: bool,
+}
+
 /// ORDER BY clause
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OrderByClause {
@@ -178,6 +331,8 @@ pub enum Expression {
     Variable(String),
     /// Property access (entity.property)
     Property(Box<Expression>, String),
+    /// Element access (list[index], map[key])
+    Index(Box<Expression>, Box<Expression>),
     /// Parameter ($name)
     Parameter(String),
     /// Binary operation
@@ -255,6 +410,8 @@ pub enum BinaryOp {
     Contains,
     StartsWith,
     EndsWith,
+    /// `=~`, matching the whole string against a regular expression
+    RegexMatch,
     // Other
     In,
     Is,
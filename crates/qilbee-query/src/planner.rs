@@ -7,9 +7,15 @@
 //! - Predicate pushdown
 //! - Common subexpression elimination
 
+use crate::executor::compile_regex;
 use crate::parser::*;
 use qilbee_core::{Direction, Error, Result};
-use std::collections::HashMap;
+use qilbee_graph::{Graph, Index, IndexType, Schema};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// Estimated cost of an index seek, below which a label scan is preferred
+const INDEX_SEEK_COST: f64 = 10.0;
 
 /// Physical execution plan
 #[derive(Debug, Clone)]
@@ -22,6 +28,91 @@ pub struct ExecutionPlan {
 
     /// Estimated cardinality (number of rows)
     pub estimated_rows: u64,
+
+    /// Default LIMIT added because the read query had none
+    pub default_limit: Option<usize>,
+
+    /// Whether the query is executed, explained or profiled
+    pub mode: QueryMode,
+
+    /// Whether the query only reads data, so its result may be cached
+    pub read_only: bool,
+
+    /// Operator tree with the planner's estimates, in the same shape as `root`
+    pub description: PlanDescription,
+}
+
+/// One operator of a plan, as returned for `EXPLAIN` and `PROFILE`
+///
+/// The actual counts are only filled in by
+/// [`QueryExecutor::profile`](crate::QueryExecutor::profile). Database hits
+/// and time are the operator's own, excluding its inputs; database hits count
+/// the nodes read by scans and index lookups.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanDescription {
+    /// Operator name and arguments, e.g. `IndexSeek p:Person(email)`
+    pub operator: String,
+
+    /// Estimated number of rows produced
+    pub estimated_rows: u64,
+
+    /// Estimated cost, including inputs
+    pub estimated_cost: f64,
+
+    /// Rows actually produced
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rows: Option<u64>,
+
+    /// Database hits
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub db_hits: Option<u64>,
+
+    /// Time spent in the operator, in milliseconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time_ms: Option<f64>,
+
+    /// Input operators
+    pub children: Vec<PlanDescription>,
+}
+
+impl ExecutionPlan {
+    /// Whether any operator in the plan reads from an index
+    pub fn uses_index(&self) -> bool {
+        self.root.uses_index()
+    }
+
+    /// Describe the plan as an operator tree, root first
+    ///
+    /// Each line names an operator, e.g. `IndexSeek p:Person(email)` or
+    /// `NodeByLabelScan p:Person`, with its estimated cost. Inputs are
+    /// indented below the operator they feed.
+    pub fn explain(&self) -> String {
+        let mut out = String::new();
+        self.root.explain_into(0, &mut out);
+        out
+    }
+
+    /// Most rows the plan can return, or `None` if nothing bounds them
+    ///
+    /// A `LIMIT` bounds the operators above it that keep, drop or reorder
+    /// rows, and an aggregation without grouping returns one row.
+    pub fn max_rows(&self) -> Option<usize> {
+        let mut operator = &self.root;
+        loop {
+            match operator {
+                PhysicalOperator::Limit { count, .. } => return Some(*count),
+                PhysicalOperator::Aggregate { group_by, .. } if group_by.is_empty() => return Some(1),
+                PhysicalOperator::Project { input, .. }
+                | PhysicalOperator::Filter { input, .. }
+                | PhysicalOperator::Skip { input, .. }
+                | PhysicalOperator::OrderBy { input, .. }
+                | PhysicalOperator::Distinct { input, .. }
+                | PhysicalOperator::Aggregate { input, .. } => operator = input,
+                _ => return None,
+            }
+        }
+    }
 }
 
 /// Physical query operators
@@ -43,6 +134,19 @@ pub enum PhysicalOperator {
         estimated_cost: f64,
     },
 
+    /// Composite index seek on equalities for the leading properties of a
+    /// multi-property index
+    CompositeIndexSeek {
+        variable: String,
+        label: String,
+        index: String,
+        /// The constrained leading properties, in index order
+        properties: Vec<String>,
+        /// One value per entry of `properties`
+        values: Vec<Expression>,
+        estimated_cost: f64,
+    },
+
     /// Index scan (range scan)
     IndexScan {
         variable: String,
@@ -73,11 +177,156 @@ pub enum PhysicalOperator {
         from_var: String,
         rel_var: Option<String>,
         to_var: String,
+        to_labels: Vec<String>,
         rel_types: Vec<String>,
         direction: Direction,
         estimated_cost: f64,
     },
 
+    /// Variable-length relationship expansion (bounded breadth-first search)
+    ///
+    /// Binds `rel_var` to the list of traversed relationship IDs and
+    /// `path_var` to a path map with `nodes` and `relationships` lists.
+    VarLengthExpand {
+        input: Box<PhysicalOperator>,
+        from_var: String,
+        rel_var: Option<String>,
+        to_var: String,
+        to_labels: Vec<String>,
+        path_var: Option<String>,
+        rel_types: Vec<String>,
+        direction: Direction,
+        min_hops: u32,
+        max_hops: u32,
+        estimated_cost: f64,
+    },
+
+    /// Shortest path search between every pair of `left` and `right` rows
+    ///
+    /// Binds `rel_var` to the list of traversed relationship IDs and
+    /// `path_var` to a path map with `nodes` and `relationships` lists.
+    /// With `all` set, one row is produced per path of minimal length.
+    ShortestPath {
+        left: Box<PhysicalOperator>,
+        right: Box<PhysicalOperator>,
+        from_var: String,
+        rel_var: Option<String>,
+        to_var: String,
+        path_var: Option<String>,
+        rel_types: Vec<String>,
+        direction: Direction,
+        min_hops: u32,
+        max_hops: Option<u32>,
+        all: bool,
+        estimated_cost: f64,
+    },
+
+    /// Procedure invocation (`CALL name(args) YIELD ...`)
+    ///
+    /// Produces the procedure's output columns, narrowed to `yield_items`
+    /// when any are given.
+    ProcedureCall {
+        procedure: String,
+        args: Vec<Expression>,
+        yield_items: Vec<String>,
+        estimated_cost: f64,
+    },
+
+    /// Expand a list into one row per element (`UNWIND list AS variable`)
+    ///
+    /// Each input row is repeated once per element of the list `expression`
+    /// evaluates to in it, with the element bound to `variable`. A null list
+    /// produces no rows and any other value a single row. Without an input,
+    /// the list is unwound once.
+    Unwind {
+        input: Option<Box<PhysicalOperator>>,
+        expression: Expression,
+        variable: String,
+        estimated_cost: f64,
+    },
+
+    /// Run `right` once per `left` row
+    ///
+    /// The variables of the `left` row can be read by `right`, e.g. by an
+    /// index seek or MERGE on an unwound value. Produces each `left` row
+    /// followed by each of the rows `right` produced for it.
+    Apply {
+        left: Box<PhysicalOperator>,
+        right: Box<PhysicalOperator>,
+        estimated_cost: f64,
+    },
+
+    /// Find or create a node (`MERGE`)
+    ///
+    /// Produces one row binding `variable` to the matched or created node,
+    /// after applying `on_create` or `on_match` to it.
+    Merge {
+        variable: String,
+        labels: Vec<String>,
+        properties: Vec<(String, Expression)>,
+        on_create: Vec<SetItem>,
+        on_match: Vec<SetItem>,
+        estimated_cost: f64,
+    },
+
+    /// Create an index (`CREATE INDEX`), indexing the existing nodes
+    ///
+    /// Produces one row with the index name and the number of nodes
+    /// indexed, or none if `if_not_exists` and the index exists.
+    CreateIndex {
+        name: String,
+        label: String,
+        properties: Vec<String>,
+        if_not_exists: bool,
+        estimated_cost: f64,
+    },
+
+    /// Drop an index (`DROP INDEX`)
+    ///
+    /// Produces one row with the index name, or none if `if_exists` and the
+    /// index does not exist.
+    DropIndex {
+        name: String,
+        if_exists: bool,
+        estimated_cost: f64,
+    },
+
+    /// Assign node properties (`SET n.key = expr`, `SET n = map`, `SET n += map`)
+    ///
+    /// Passes its input rows through unchanged. Null values remove the
+    /// property.
+    SetProperty {
+        input: Box<PhysicalOperator>,
+        items: Vec<SetItem>,
+        estimated_cost: f64,
+    },
+
+    /// Add labels to a node (`SET n:Label`)
+    SetLabels {
+        input: Box<PhysicalOperator>,
+        variable: String,
+        labels: Vec<String>,
+        estimated_cost: f64,
+    },
+
+    /// Remove node properties and labels (`REMOVE n.key, n:Label`)
+    Remove {
+        input: Box<PhysicalOperator>,
+        items: Vec<RemoveItem>,
+        estimated_cost: f64,
+    },
+
+    /// Delete the nodes and relationships bound in each row (`[DETACH] DELETE`)
+    ///
+    /// Relationships are deleted before nodes. Without `detach`, deleting a
+    /// node that keeps other relationships is an error.
+    Delete {
+        input: Box<PhysicalOperator>,
+        expressions: Vec<Expression>,
+        detach: bool,
+        estimated_cost: f64,
+    },
+
     /// Hash join
     HashJoin {
         left: Box<PhysicalOperator>,
@@ -122,16 +371,191 @@ pub enum PhysicalOperator {
     },
 
     /// Aggregation
+    ///
+    /// Produces one row per distinct combination of `group_by` values
+    /// (columns `group_by_aliases`), followed by one column per aggregate.
     Aggregate {
         input: Box<PhysicalOperator>,
         group_by: Vec<Expression>,
-        aggregates: Vec<(AggregateFunction, Expression, String)>,
+        group_by_aliases: Vec<String>,
+        aggregates: Vec<AggregateExpression>,
         estimated_cost: f64,
     },
 }
 
+impl PhysicalOperator {
+    /// Input operators feeding this one
+    pub fn children(&self) -> Vec<&PhysicalOperator> {
+        match self {
+            PhysicalOperator::NodeScan { .. }
+            | PhysicalOperator::IndexSeek { .. }
+            | PhysicalOperator::CompositeIndexSeek { .. }
+            | PhysicalOperator::IndexScan { .. }
+            | PhysicalOperator::ProcedureCall { .. }
+            | PhysicalOperator::Merge { .. }
+            | PhysicalOperator::CreateIndex { .. }
+            | PhysicalOperator::DropIndex { .. } => Vec::new(),
+            PhysicalOperator::Filter { input, .. }
+            | PhysicalOperator::Project { input, .. }
+            | PhysicalOperator::Expand { input, .. }
+            | PhysicalOperator::VarLengthExpand { input, .. }
+            | PhysicalOperator::OrderBy { input, .. }
+            | PhysicalOperator::Limit { input, .. }
+            | PhysicalOperator::Skip { input, .. }
+            | PhysicalOperator::Distinct { input, .. }
+            | PhysicalOperator::Aggregate { input, .. }
+            | PhysicalOperator::SetProperty { input, .. }
+            | PhysicalOperator::SetLabels { input, .. }
+            | PhysicalOperator::Remove { input, .. }
+            | PhysicalOperator::Delete { input, .. } => vec![input.as_ref()],
+            PhysicalOperator::Unwind { input, .. } => input.iter().map(AsRef::as_ref).collect(),
+            PhysicalOperator::ShortestPath { left, right, .. }
+            | PhysicalOperator::Apply { left, right, .. }
+            | PhysicalOperator::HashJoin { left, right, .. }
+            | PhysicalOperator::NestedLoopJoin { left, right, .. } => {
+                vec![left.as_ref(), right.as_ref()]
+            }
+        }
+    }
+
+    /// Planner's cost estimate for this operator, including its inputs
+    pub fn estimated_cost(&self) -> f64 {
+        match self {
+            PhysicalOperator::NodeScan { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::IndexSeek { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::CompositeIndexSeek { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::IndexScan { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Filter { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Project { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Expand { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::VarLengthExpand { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::ShortestPath { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::ProcedureCall { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Unwind { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Apply { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Merge { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::CreateIndex { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::DropIndex { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::SetProperty { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::SetLabels { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Remove { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Delete { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::HashJoin { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::NestedLoopJoin { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::OrderBy { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Limit { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Skip { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Distinct { estimated_cost, .. } => *estimated_cost,
+            PhysicalOperator::Aggregate { estimated_cost, .. } => *estimated_cost,
+        }
+    }
+
+    /// Operator name and its most relevant arguments, for plan descriptions
+    pub fn describe(&self) -> String {
+        match self {
+            PhysicalOperator::NodeScan { variable, labels, .. } if labels.is_empty() => {
+                format!("AllNodesScan {}", variable)
+            }
+            PhysicalOperator::NodeScan { variable, labels, .. } => {
+                format!("NodeByLabelScan {}", node_description(variable, labels))
+            }
+            PhysicalOperator::IndexSeek { variable, label, property, .. } => {
+                format!("IndexSeek {}:{}({})", variable, label, property)
+            }
+            PhysicalOperator::CompositeIndexSeek { variable, label, properties, .. } => {
+                format!("CompositeIndexSeek {}:{}({})", variable, label, properties.join(", "))
+            }
+            PhysicalOperator::IndexScan { variable, label, property, .. } => {
+                format!("IndexScan {}:{}({})", variable, label, property)
+            }
+            PhysicalOperator::Filter { .. } => "Filter".to_string(),
+            PhysicalOperator::Project { aliases, .. } => format!("Project {}", aliases.join(", ")),
+            PhysicalOperator::Expand { from_var, to_var, .. } => format!("Expand ({})--({})", from_var, to_var),
+            PhysicalOperator::VarLengthExpand { from_var, to_var, min_hops, max_hops, .. } => {
+                format!("VarLengthExpand ({})-[*{}..{}]-({})", from_var, min_hops, max_hops, to_var)
+            }
+            PhysicalOperator::ShortestPath { from_var, to_var, .. } => {
+                format!("ShortestPath ({})--({})", from_var, to_var)
+            }
+            PhysicalOperator::ProcedureCall { procedure, .. } => format!("ProcedureCall {}", procedure),
+            PhysicalOperator::Unwind { variable, .. } => format!("Unwind {}", variable),
+            PhysicalOperator::Apply { .. } => "Apply".to_string(),
+            PhysicalOperator::Merge { variable, labels, .. } => {
+                format!("Merge {}", node_description(variable, labels))
+            }
+            PhysicalOperator::CreateIndex { name, label, properties, .. } => {
+                format!("CreateIndex {} :{}({})", name, label, properties.join(", "))
+            }
+            PhysicalOperator::DropIndex { name, .. } => format!("DropIndex {}", name),
+            PhysicalOperator::SetProperty { .. } => "SetProperty".to_string(),
+            PhysicalOperator::SetLabels { variable, labels, .. } => {
+                format!("SetLabels {}", node_description(variable, labels))
+            }
+            PhysicalOperator::Remove { .. } => "Remove".to_string(),
+            PhysicalOperator::Delete { detach: true, .. } => "DetachDelete".to_string(),
+            PhysicalOperator::Delete { .. } => "Delete".to_string(),
+            PhysicalOperator::HashJoin { .. } => "HashJoin".to_string(),
+            PhysicalOperator::NestedLoopJoin { .. } => "NestedLoopJoin".to_string(),
+            PhysicalOperator::OrderBy { .. } => "OrderBy".to_string(),
+            PhysicalOperator::Limit { count, .. } => format!("Limit {}", count),
+            PhysicalOperator::Skip { count, .. } => format!("Skip {}", count),
+            PhysicalOperator::Distinct { .. } => "Distinct".to_string(),
+            PhysicalOperator::Aggregate { group_by_aliases, .. } if group_by_aliases.is_empty() => {
+                "Aggregate".to_string()
+            }
+            PhysicalOperator::Aggregate { group_by_aliases, .. } => {
+                format!("Aggregate by {}", group_by_aliases.join(", "))
+            }
+        }
+    }
+
+    /// Append this operator and its inputs to `out`, one per line
+    fn explain_into(&self, depth: usize, out: &mut String) {
+        out.push_str(&format!(
+            "{}{} (cost={:.1})\n",
+            "  ".repeat(depth),
+            self.describe(),
+            self.estimated_cost()
+        ));
+        for child in self.children() {
+            child.explain_into(depth + 1, out);
+        }
+    }
+
+    /// Whether this operator or any of its inputs reads from an index
+    pub fn uses_index(&self) -> bool {
+        matches!(
+            self,
+            PhysicalOperator::IndexSeek { .. }
+                | PhysicalOperator::CompositeIndexSeek { .. }
+                | PhysicalOperator::IndexScan { .. }
+        ) || self.children().into_iter().any(PhysicalOperator::uses_index)
+    }
+
+    /// Variables bound to a single relationship by this operator or its inputs
+    ///
+    /// Variable-length expansions bind relationship lists instead, so their
+    /// relationship variables are not included.
+    pub fn relationship_variables(&self) -> HashSet<String> {
+        let mut variables: HashSet<String> = self
+            .children()
+            .into_iter()
+            .flat_map(PhysicalOperator::relationship_variables)
+            .collect();
+        if let PhysicalOperator::Expand { rel_var: Some(rel_var), .. } = self {
+            variables.insert(rel_var.clone());
+        }
+        variables
+    }
+}
+
+/// `variable:Label1:Label2`, as written in a node pattern
+fn node_description(variable: &str, labels: &[String]) -> String {
+    labels.iter().fold(variable.to_string(), |description, label| description + ":" + label)
+}
+
 /// Aggregate functions
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AggregateFunction {
     Count,
     Sum,
@@ -141,10 +565,53 @@ pub enum AggregateFunction {
     Collect,
 }
 
+impl AggregateFunction {
+    /// Look up an aggregate function by its (case-insensitive) Cypher name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "count" => Some(AggregateFunction::Count),
+            "sum" => Some(AggregateFunction::Sum),
+            "avg" => Some(AggregateFunction::Avg),
+            "min" => Some(AggregateFunction::Min),
+            "max" => Some(AggregateFunction::Max),
+            "collect" => Some(AggregateFunction::Collect),
+            _ => None,
+        }
+    }
+}
+
+/// A single aggregate computed by the Aggregate operator
+#[derive(Debug, Clone)]
+pub struct AggregateExpression {
+    /// Aggregate function
+    pub function: AggregateFunction,
+
+    /// Argument expression (`Expression::Star` for `count(*)`)
+    pub argument: Expression,
+
+    /// Only aggregate distinct argument values
+    pub distinct: bool,
+
+    /// Output column name
+    pub alias: String,
+}
+
 /// Query planner
 pub struct QueryPlanner {
     /// Statistics for cost estimation
     stats: PlannerStats,
+
+    /// LIMIT applied to read queries that don't specify one
+    default_limit: Option<usize>,
+
+    /// `(label, property)` pairs with a declared index
+    ///
+    /// Empty until a schema is supplied, so without one every predicate is
+    /// planned as a label scan followed by a filter.
+    indexes: HashSet<(String, String)>,
+
+    /// Composite indexes declared in the schema
+    composite_indexes: Vec<Index>,
 }
 
 /// Statistics for query planning
@@ -156,6 +623,9 @@ struct PlannerStats {
     /// Estimated nodes per label
     nodes_per_label: HashMap<String, u64>,
 
+    /// Estimated relationships per type
+    relationships_per_type: HashMap<String, u64>,
+
     /// Selectivity estimates
     selectivity_estimates: HashMap<String, f64>,
 }
@@ -165,6 +635,7 @@ impl Default for PlannerStats {
         Self {
             total_nodes: 1000, // Default estimate
             nodes_per_label: HashMap::new(),
+            relationships_per_type: HashMap::new(),
             selectivity_estimates: HashMap::new(),
         }
     }
@@ -175,47 +646,258 @@ impl QueryPlanner {
     pub fn new() -> Self {
         Self {
             stats: PlannerStats::default(),
+            default_limit: None,
+            indexes: HashSet::new(),
+            composite_indexes: Vec::new(),
+        }
+    }
+
+    /// Cap read queries without a LIMIT clause at `limit` rows
+    ///
+    /// Queries that create, update or delete data are never capped.
+    pub fn with_default_limit(mut self, limit: usize) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    /// Only plan index seeks and scans on properties indexed in `schema`
+    ///
+    /// Range and unique indexes qualify, as does the leading property of a
+    /// composite index. Equalities on two or more leading properties of a
+    /// composite index are planned as a seek on the composite index itself.
+    /// Predicates on other properties are planned as a label scan followed
+    /// by a filter.
+    pub fn with_schema(mut self, schema: &Schema) -> Self {
+        let indexes = schema
+            .indexes
+            .values()
+            .filter(|index| matches!(index.index_type, IndexType::Range | IndexType::Composite))
+            .filter_map(|index| {
+                let property = index.properties.first()?;
+                Some((index.label.name().to_string(), property.clone()))
+            })
+            .collect();
+        self.indexes = indexes;
+        self.composite_indexes = schema
+            .indexes
+            .values()
+            .filter(|index| index.index_type == IndexType::Composite)
+            .cloned()
+            .collect();
+        self
+    }
+
+    /// Only plan index seeks and scans on properties indexed in `graph`'s
+    /// current schema
+    pub fn with_graph_schema(self, graph: &Graph) -> Result<Self> {
+        let schema = graph
+            .schema()
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire schema lock".to_string()))?;
+        Ok(self.with_schema(&schema))
+    }
+
+    /// Plan a seek on the composite index of `label` with the longest prefix
+    /// of its properties constrained by `equalities`
+    ///
+    /// Only prefixes of two or more properties are considered; a single
+    /// leading property is better served by the property index. Returns the
+    /// seek and the properties it constrains.
+    fn composite_seek(
+        &self,
+        variable: &str,
+        label: &str,
+        equalities: &[(String, Expression)],
+    ) -> Option<(PhysicalOperator, Vec<String>)> {
+        let value_of = |property: &String| {
+            equalities
+                .iter()
+                .find(|(constrained, _)| constrained == property)
+                .map(|(_, value)| value.clone())
+        };
+        let (index, values) = self
+            .composite_indexes
+            .iter()
+            .filter(|index| index.label.name() == label)
+            .map(|index| {
+                let values: Vec<Expression> = index.properties.iter().map_while(value_of).collect();
+                (index, values)
+            })
+            .filter(|(_, values)| values.len() >= 2)
+            // Ties go to the index with the lowest name, so plans are stable
+            .max_by(|(a, a_values), (b, b_values)| {
+                a_values.len().cmp(&b_values.len()).then_with(|| b.name.cmp(&a.name))
+            })?;
+
+        let properties = index.properties[..values.len()].to_vec();
+        let seek = PhysicalOperator::CompositeIndexSeek {
+            variable: variable.to_string(),
+            label: label.to_string(),
+            index: index.name.clone(),
+            properties: properties.clone(),
+            values,
+            estimated_cost: INDEX_SEEK_COST,
+        };
+        Some((seek, properties))
+    }
+
+    /// Whether `label.property` can be read through an index
+    fn has_index(&self, label: &str, property: &str) -> bool {
+        self.indexes.contains(&(label.to_string(), property.to_string()))
+    }
+
+    /// Use the graph's actual node and relationship counts for cost estimation
+    ///
+    /// Reads the stored counts of every label and relationship type
+    /// referenced by the query's MATCH patterns.
+    pub fn with_graph_statistics(mut self, graph: &Graph, query: &Query) -> Result<Self> {
+        self.stats.total_nodes = graph.node_count()?;
+
+        for clause in &query.clauses {
+            let Clause::Match(match_clause) = clause else {
+                continue;
+            };
+            for element in match_clause.patterns.iter().flat_map(|p| &p.elements) {
+                match element {
+                    PatternElement::Node(node) => {
+                        for label in &node.labels {
+                            if !self.stats.nodes_per_label.contains_key(label) {
+                                let count = graph.count_nodes_by_label(label)?;
+                                self.stats.nodes_per_label.insert(label.clone(), count);
+                            }
+                        }
+                    }
+                    PatternElement::Relationship(rel) => {
+                        for rel_type in &rel.rel_types {
+                            if !self.stats.relationships_per_type.contains_key(rel_type) {
+                                let count = graph.count_relationships_by_type(rel_type)?;
+                                self.stats.relationships_per_type.insert(rel_type.clone(), count);
+                            }
+                        }
+                    }
+                }
+            }
         }
+
+        Ok(self)
     }
 
     /// Create an execution plan from a parsed query
     pub fn plan(&self, query: &Query) -> Result<ExecutionPlan> {
-        // Extract clauses
+        if let Some(root) = self.plan_schema_statement(query)? {
+            return Ok(ExecutionPlan {
+                description: self.describe(&root),
+                estimated_cost: root.estimated_cost(),
+                estimated_rows: self.estimate_cardinality(&root),
+                root,
+                default_limit: None,
+                mode: query.mode,
+                read_only: query.is_read_only(),
+            });
+        }
+
+        // Extract clauses. UNWIND clauses before the first MATCH, CALL or
+        // MERGE feed it; later ones unwind over its rows.
+        let mut leading_unwinds = Vec::new();
+        let mut unwind_clauses = Vec::new();
         let mut match_clauses = Vec::new();
+        let mut call_clause = None;
+        let mut merge_clause = None;
         let mut where_clauses = Vec::new();
+        let mut update_clauses = Vec::new();
         let mut return_clause = None;
         let mut order_by_clause = None;
+        let mut skip_clause = None;
         let mut limit_clause = None;
 
         for clause in &query.clauses {
             match clause {
+                Clause::Unwind(u) if match_clauses.is_empty() && call_clause.is_none() && merge_clause.is_none() => {
+                    leading_unwinds.push(u.clone())
+                }
+                Clause::Unwind(u) => unwind_clauses.push(u.clone()),
                 Clause::Match(m) => match_clauses.push(m.clone()),
+                Clause::Call(c) => call_clause = Some(c.clone()),
+                Clause::Merge(m) => merge_clause = Some(m.clone()),
                 Clause::Where(w) => where_clauses.push(w.clone()),
+                Clause::Set(_) | Clause::Remove(_) | Clause::Delete(_) => update_clauses.push(clause),
                 Clause::Return(r) => return_clause = Some(r.clone()),
                 Clause::OrderBy(o) => order_by_clause = Some(o.clone()),
+                Clause::Skip(s) => skip_clause = Some(s.clone()),
                 Clause::Limit(l) => limit_clause = Some(l.clone()),
                 _ => {}
             }
         }
 
-        if match_clauses.is_empty() {
-            return Err(Error::QueryParse("Query must have at least one MATCH clause".to_string()));
-        }
-
         // Build execution plan bottom-up
-        let mut plan = self.plan_match(&match_clauses[0])?;
+        let mut source = leading_unwinds.into_iter().fold(None, |input, unwind| Some(self.plan_unwind(input, unwind)));
+        let mut plan = match (call_clause, match_clauses.first(), merge_clause) {
+            (Some(_), Some(_), _) => {
+                return Err(Error::InvalidQuery(
+                    "CALL cannot be combined with MATCH in the same query".to_string(),
+                ))
+            }
+            (Some(_), _, Some(_)) | (_, Some(_), Some(_)) => {
+                return Err(Error::InvalidQuery(
+                    "MERGE cannot be combined with MATCH or CALL in the same query".to_string(),
+                ))
+            }
+            (None, None, Some(merge)) => self.plan_merge(&merge)?,
+            (Some(call), None, None) => PhysicalOperator::ProcedureCall {
+                procedure: call.procedure,
+                args: call.args,
+                yield_items: call.yield_items,
+                estimated_cost: self.stats.total_nodes as f64 * 10.0,
+            },
+            (None, Some(match_clause), None) => self.plan_match(match_clause)?,
+            (None, None, None) => match source.take() {
+                Some(source) => source,
+                None => {
+                    return Err(Error::QueryParse("Query must have at least one MATCH clause".to_string()));
+                }
+            },
+        };
 
         // Apply WHERE filters (predicate pushdown)
         for where_expr in where_clauses {
+            Self::check_regex_literals(&where_expr)?;
             plan = self.apply_filter(plan, where_expr)?;
         }
 
+        // Unwound rows feed the MATCH, CALL or MERGE, which runs once per
+        // row and can read the row's variables, in its WHERE filters too
+        if let Some(source) = source {
+            let estimated_cost =
+                self.estimate_cost(&source) + self.estimate_cardinality(&source) as f64 * self.estimate_cost(&plan);
+            plan = PhysicalOperator::Apply { left: Box::new(source), right: Box::new(plan), estimated_cost };
+        }
+
+        // Apply UNWIND over the matched rows
+        for unwind in unwind_clauses {
+            plan = self.plan_unwind(Some(plan), unwind);
+        }
+
+        // Apply SET, REMOVE and DELETE to every matched row, before paging
+        for clause in update_clauses {
+            plan = self.apply_update(plan, clause);
+        }
+
         // Apply ORDER BY
         if let Some(order_by) = order_by_clause {
             plan = self.apply_order_by(plan, &order_by)?;
         }
 
-        // Apply LIMIT
+        // Apply SKIP
+        if let Some(Expression::Literal(Literal::Integer(count))) = skip_clause {
+            plan = PhysicalOperator::Skip {
+                input: Box::new(plan),
+                count: count.max(0) as usize,
+                estimated_cost: 1.0,
+            };
+        }
+
+        // Apply LIMIT, falling back to the default for unbounded reads
+        let mut default_limit = None;
         if let Some(limit_expr) = limit_clause {
             if let Expression::Literal(Literal::Integer(count)) = limit_expr {
                 plan = PhysicalOperator::Limit {
@@ -224,6 +906,15 @@ impl QueryPlanner {
                     estimated_cost: 1.0,
                 };
             }
+        } else if let Some(count) = self.default_limit
+            && query.is_read_only()
+        {
+            plan = PhysicalOperator::Limit {
+                input: Box::new(plan),
+                count,
+                estimated_cost: 1.0,
+            };
+            default_limit = Some(count);
         }
 
         // Apply RETURN projection
@@ -235,57 +926,485 @@ impl QueryPlanner {
         let estimated_rows = self.estimate_cardinality(&plan);
 
         Ok(ExecutionPlan {
+            description: self.describe(&plan),
             root: plan,
             estimated_cost,
             estimated_rows,
+            default_limit,
+            mode: query.mode,
+            read_only: query.is_read_only(),
+        })
+    }
+
+    /// Plan a `CREATE INDEX` or `DROP INDEX` statement, `None` for other queries
+    fn plan_schema_statement(&self, query: &Query) -> Result<Option<PhysicalOperator>> {
+        let schema_clauses = query
+            .clauses
+            .iter()
+            .filter(|clause| matches!(clause, Clause::CreateIndex(_) | Clause::DropIndex(_)))
+            .count();
+        if schema_clauses == 0 {
+            return Ok(None);
+        }
+        if query.clauses.len() > 1 {
+            return Err(Error::InvalidQuery(
+                "Index statements cannot be combined with other clauses".to_string(),
+            ));
+        }
+
+        Ok(match &query.clauses[0] {
+            Clause::CreateIndex(create) => Some(PhysicalOperator::CreateIndex {
+                name: create.index_name(),
+                label: create.label.clone(),
+                properties: create.properties.clone(),
+                if_not_exists: create.if_not_exists,
+                // Building the index reads every node with the label
+                estimated_cost: self.stats.total_nodes as f64,
+            }),
+            Clause::DropIndex(drop) => Some(PhysicalOperator::DropIndex {
+                name: drop.name.clone(),
+                if_exists: drop.if_exists,
+                estimated_cost: 1.0,
+            }),
+            _ => None,
         })
     }
 
+    /// Describe an operator tree with the planner's estimates
+    fn describe(&self, operator: &PhysicalOperator) -> PlanDescription {
+        PlanDescription {
+            operator: operator.describe(),
+            estimated_rows: self.estimate_cardinality(operator),
+            estimated_cost: operator.estimated_cost(),
+            rows: None,
+            db_hits: None,
+            time_ms: None,
+            children: operator.children().into_iter().map(|child| self.describe(child)).collect(),
+        }
+    }
+
     /// Plan a MATCH clause
     fn plan_match(&self, match_clause: &MatchClause) -> Result<PhysicalOperator> {
         if match_clause.patterns.is_empty() {
             return Err(Error::QueryParse("MATCH clause must have at least one pattern".to_string()));
         }
 
-        // For now, handle simple single-node patterns
+        // Only the first pattern of a MATCH is planned for now
         let pattern = &match_clause.patterns[0];
-        if pattern.elements.is_empty() {
-            return Err(Error::QueryParse("Pattern must have at least one element".to_string()));
-        }
-
-        // Extract the node pattern
-        if let PatternElement::Node(node_pattern) = &pattern.elements[0] {
-            let variable = node_pattern.variable.clone().unwrap_or_else(|| "n".to_string());
-
-            // Check if we can use an index
-            if let Some(properties) = &node_pattern.properties {
-                // Try to use index seek
-                if let Some((key, value_expr)) = properties.entries.first() {
-                    if !node_pattern.labels.is_empty() {
-                        return Ok(PhysicalOperator::IndexSeek {
-                            variable: variable.clone(),
-                            label: node_pattern.labels[0].clone(),
-                            property: key.clone(),
-                            value: value_expr.clone(),
-                            estimated_cost: 10.0,
-                        });
-                    }
+        let first_node = match pattern.elements.first() {
+            Some(PatternElement::Node(node_pattern)) => node_pattern,
+            Some(_) => return Err(Error::QueryParse("Invalid pattern structure".to_string())),
+            None => {
+                return Err(Error::QueryParse("Pattern must have at least one element".to_string()))
+            }
+        };
+
+        if let Some(selector) = pattern.selector {
+            return self.plan_shortest_path(pattern, selector);
+        }
+
+        let hops = pattern.elements.len() / 2;
+        if pattern.variable.is_some() && hops != 1 {
+            return Err(Error::InvalidQuery(
+                "Path variables are only supported on patterns with a single relationship".to_string(),
+            ));
+        }
+
+        // Expand from whichever end of the pattern is cheaper to scan
+        let reversed = self.reverse_for_cost(pattern);
+        let elements = reversed.as_deref().unwrap_or(&pattern.elements);
+        let first_node = match elements.first() {
+            Some(PatternElement::Node(node_pattern)) => node_pattern,
+            _ => first_node,
+        };
+
+        let mut from_var = first_node.variable.clone().unwrap_or_else(|| "n".to_string());
+        let mut plan = self.plan_node_pattern(first_node, &from_var);
+
+        for (i, pair) in elements[1..].chunks(2).enumerate() {
+            let (rel, node) = match pair {
+                [PatternElement::Relationship(rel), PatternElement::Node(node)] => (rel, node),
+                _ => return Err(Error::QueryParse("Invalid pattern structure".to_string())),
+            };
+
+            if node.properties.is_some() {
+                return Err(Error::InvalidQuery(
+                    "Inline properties are only supported on the first node of a pattern".to_string(),
+                ));
+            }
+
+            let to_var = node.variable.clone().unwrap_or_else(|| format!("__node{}", i + 1));
+            let direction = match rel.direction {
+                RelationshipDirection::Outgoing => Direction::Outgoing,
+                RelationshipDirection::Incoming => Direction::Incoming,
+                RelationshipDirection::Both => Direction::Both,
+            };
+
+            plan = if rel.length.is_some() || pattern.variable.is_some() {
+                let (min_hops, max_hops) = match &rel.length {
+                    Some(length) => Self::hop_bounds(length)?,
+                    None => (1, 1),
+                };
+                let estimated_cost = self.estimate_cost(&plan) * 10.0 * max_hops.max(1) as f64;
+
+                PhysicalOperator::VarLengthExpand {
+                    input: Box::new(plan),
+                    from_var: from_var.clone(),
+                    rel_var: rel.variable.clone(),
+                    to_var: to_var.clone(),
+                    to_labels: node.labels.clone(),
+                    path_var: pattern.variable.clone(),
+                    rel_types: rel.rel_types.clone(),
+                    direction,
+                    min_hops,
+                    max_hops,
+                    estimated_cost,
+                }
+            } else {
+                let estimated_cost = self.estimate_cost(&plan) * 10.0;
+
+                PhysicalOperator::Expand {
+                    input: Box::new(plan),
+                    from_var: from_var.clone(),
+                    rel_var: rel.variable.clone(),
+                    to_var: to_var.clone(),
+                    to_labels: node.labels.clone(),
+                    rel_types: rel.rel_types.clone(),
+                    direction,
+                    estimated_cost,
                 }
+            };
+
+            from_var = to_var;
+        }
+
+        Ok(plan)
+    }
+
+    /// The elements of `pattern` read from the other end, if its last node
+    /// is cheaper to scan than its first
+    ///
+    /// Only fixed-length patterns without a path variable or inline node
+    /// properties are turned around, since those produce the same rows from
+    /// either end.
+    fn reverse_for_cost(&self, pattern: &Pattern) -> Option<Vec<PatternElement>> {
+        let (Some(PatternElement::Node(first)), Some(PatternElement::Node(last))) =
+            (pattern.elements.first(), pattern.elements.last())
+        else {
+            return None;
+        };
+
+        let reversible = pattern.variable.is_none()
+            && pattern.elements.len() > 1
+            && pattern.elements.iter().all(|element| match element {
+                PatternElement::Node(node) => node.properties.is_none(),
+                PatternElement::Relationship(rel) => rel.length.is_none(),
+            });
+        if !reversible || self.estimate_scan_cost(&last.labels) >= self.estimate_scan_cost(&first.labels) {
+            return None;
+        }
+
+        let reversed = pattern
+            .elements
+            .iter()
+            .rev()
+            .map(|element| match element {
+                PatternElement::Relationship(rel) => {
+                    let mut rel = rel.clone();
+                    rel.direction = match rel.direction {
+                        RelationshipDirection::Outgoing => RelationshipDirection::Incoming,
+                        RelationshipDirection::Incoming => RelationshipDirection::Outgoing,
+                        RelationshipDirection::Both => RelationshipDirection::Both,
+                    };
+                    PatternElement::Relationship(rel)
+                }
+                node => node.clone(),
+            })
+            .collect();
+        Some(reversed)
+    }
+
+    /// Plan a `shortestPath(...)` or `allShortestPaths(...)` pattern
+    ///
+    /// Both endpoints are scanned independently and paired up by the
+    /// operator. The upper bound may be omitted since the search stops at
+    /// the first (shortest) matches.
+    fn plan_shortest_path(&self, pattern: &Pattern, selector: PathSelector) -> Result<PhysicalOperator> {
+        let (from_node, rel, to_node) = match pattern.elements.as_slice() {
+            [
+                PatternElement::Node(from_node),
+                PatternElement::Relationship(rel),
+                PatternElement::Node(to_node),
+            ] => (from_node, rel, to_node),
+            _ => {
+                return Err(Error::InvalidQuery(
+                    "Shortest path patterns must contain exactly one relationship".to_string(),
+                ))
+            }
+        };
+
+        let (min_hops, max_hops) = match &rel.length {
+            Some(length) => (length.min.unwrap_or(1), length.max),
+            None => (1, Some(1)),
+        };
+        if min_hops > 1 {
+            return Err(Error::InvalidQuery(
+                "Shortest path patterns only support a minimum length of 0 or 1".to_string(),
+            ));
+        }
+        if max_hops.is_some_and(|max| max < min_hops) {
+            return Err(Error::InvalidQuery(format!(
+                "Invalid relationship length *{}..{}: minimum exceeds maximum",
+                min_hops,
+                max_hops.unwrap_or_default()
+            )));
+        }
+
+        let from_var = from_node.variable.clone().unwrap_or_else(|| "n".to_string());
+        let to_var = to_node.variable.clone().unwrap_or_else(|| "__node1".to_string());
+        let left = self.plan_node_pattern(from_node, &from_var);
+        let right = self.plan_node_pattern(to_node, &to_var);
+        let estimated_cost = self.estimate_cost(&left) * self.estimate_cost(&right).max(1.0) * 10.0;
+
+        Ok(PhysicalOperator::ShortestPath {
+            left: Box::new(left),
+            right: Box::new(right),
+            from_var,
+            rel_var: rel.variable.clone(),
+            to_var,
+            path_var: pattern.variable.clone(),
+            rel_types: rel.rel_types.clone(),
+            direction: match rel.direction {
+                RelationshipDirection::Outgoing => Direction::Outgoing,
+                RelationshipDirection::Incoming => Direction::Incoming,
+                RelationshipDirection::Both => Direction::Both,
+            },
+            min_hops,
+            max_hops,
+            all: selector == PathSelector::AllShortest,
+            estimated_cost,
+        })
+    }
+
+    /// Plan an UNWIND clause over `input`, or over a single empty row
+    fn plan_unwind(&self, input: Option<PhysicalOperator>, unwind: UnwindClause) -> PhysicalOperator {
+        let estimated_cost = input.as_ref().map_or(0.0, |input| self.estimate_cost(input)) + 1.0;
+        PhysicalOperator::Unwind {
+            input: input.map(Box::new),
+            expression: unwind.expression,
+            variable: unwind.variable,
+            estimated_cost,
+        }
+    }
+
+    /// Plan a MERGE clause
+    fn plan_merge(&self, merge_clause: &MergeClause) -> Result<PhysicalOperator> {
+        let node = match merge_clause.pattern.elements.as_slice() {
+            [PatternElement::Node(node)] => node,
+            _ => {
+                return Err(Error::InvalidQuery(
+                    "MERGE only supports a single node pattern".to_string(),
+                ))
+            }
+        };
+
+        Ok(PhysicalOperator::Merge {
+            variable: node.variable.clone().unwrap_or_else(|| "n".to_string()),
+            labels: node.labels.clone(),
+            properties: node.properties.clone().map(|map| map.entries).unwrap_or_default(),
+            on_create: merge_clause.on_create.clone(),
+            on_match: merge_clause.on_match.clone(),
+            estimated_cost: 10.0,
+        })
+    }
+
+    /// Plan the scan for the first node of a pattern
+    fn plan_node_pattern(&self, node_pattern: &NodePattern, variable: &str) -> PhysicalOperator {
+        let entries = node_pattern.properties.as_ref().map_or(&[][..], |map| map.entries.as_slice());
+        // Seeks only pay off when the label has enough nodes
+        let seek_label = node_pattern.labels.first().filter(|label| self.prefers_seek(label));
+
+        // Seek on a composite index when the inline properties cover two or
+        // more of its leading properties, else on the first indexed inline
+        // property of the first label. `consumed` holds the positions of the
+        // entries the seek checks.
+        let equalities: Vec<(String, Expression)> = entries
+            .iter()
+            .filter(|(_, value)| Self::is_seek_value(value))
+            .cloned()
+            .collect();
+        let composite = seek_label.and_then(|label| self.composite_seek(variable, label, &equalities));
+        let seek = match composite {
+            Some((seek, properties)) => {
+                let consumed = properties
+                    .iter()
+                    .filter_map(|property| {
+                        entries
+                            .iter()
+                            .position(|(key, value)| key == property && Self::is_seek_value(value))
+                    })
+                    .collect();
+                Some((seek, consumed))
             }
+            None => seek_label.and_then(|label| {
+                let position = entries.iter().position(|(key, _)| self.has_index(label, key))?;
+                let seek = PhysicalOperator::IndexSeek {
+                    variable: variable.to_string(),
+                    label: label.clone(),
+                    property: entries[position].0.clone(),
+                    value: entries[position].1.clone(),
+                    estimated_cost: INDEX_SEEK_COST,
+                };
+                Some((seek, vec![position]))
+            }),
+        };
+        let (scan, consumed): (PhysicalOperator, Vec<usize>) = match seek {
+            Some(seek) => seek,
+            None => (
+                PhysicalOperator::NodeScan {
+                    variable: variable.to_string(),
+                    labels: node_pattern.labels.clone(),
+                    estimated_cost: self.estimate_scan_cost(&node_pattern.labels),
+                },
+                Vec::new(),
+            ),
+        };
 
-            // Fall back to node scan
-            return Ok(PhysicalOperator::NodeScan {
-                variable,
-                labels: node_pattern.labels.clone(),
-                estimated_cost: self.estimate_scan_cost(&node_pattern.labels),
+        // The other inline properties are checked on the scan's output
+        let predicate = entries
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !consumed.contains(i))
+            .map(|(_, (key, value))| Expression::Binary {
+                left: Box::new(Expression::Property(
+                    Box::new(Expression::Variable(variable.to_string())),
+                    key.clone(),
+                )),
+                op: BinaryOp::Equals,
+                right: Box::new(value.clone()),
+            })
+            .reduce(|left, right| Expression::Binary {
+                left: Box::new(left),
+                op: BinaryOp::And,
+                right: Box::new(right),
             });
+
+        match predicate {
+            Some(predicate) => PhysicalOperator::Filter {
+                estimated_cost: self.estimate_cost(&scan) * 1.1,
+                input: Box::new(scan),
+                predicate,
+            },
+            None => scan,
+        }
+    }
+
+    /// Resolve the hop bounds of a variable-length relationship
+    ///
+    /// An upper bound is mandatory to avoid runaway traversals.
+    fn hop_bounds(length: &RelationshipLength) -> Result<(u32, u32)> {
+        let max = length.max.ok_or_else(|| {
+            Error::InvalidQuery(
+                "Variable-length relationships require an upper bound, e.g. [*1..5]".to_string(),
+            )
+        })?;
+        let min = length.min.unwrap_or(1);
+
+        if min > max {
+            return Err(Error::InvalidQuery(format!(
+                "Invalid relationship length *{}..{}: minimum exceeds maximum",
+                min, max
+            )));
         }
 
-        Err(Error::QueryParse("Invalid pattern structure".to_string()))
+        Ok((min, max))
+    }
+
+    /// Reject `=~` comparisons against a literal pattern that does not compile
+    ///
+    /// Patterns passed as parameters are only checked when the query runs.
+    fn check_regex_literals(expression: &Expression) -> Result<()> {
+        match expression {
+            Expression::Binary { left, op, right } => {
+                if *op == BinaryOp::RegexMatch
+                    && let Expression::Literal(Literal::String(pattern)) = right.as_ref()
+                {
+                    compile_regex(pattern).map_err(|e| {
+                        Error::InvalidQuery(format!("Invalid regular expression '{}': {}", pattern, e))
+                    })?;
+                }
+                Self::check_regex_literals(left)?;
+                Self::check_regex_literals(right)
+            }
+            Expression::Unary { operand, .. } => Self::check_regex_literals(operand),
+            Expression::Function { args, .. } | Expression::List(args) => {
+                args.iter().try_for_each(Self::check_regex_literals)
+            }
+            _ => Ok(()),
+        }
     }
 
     /// Apply a filter operation
     fn apply_filter(&self, input: PhysicalOperator, predicate: Expression) -> Result<PhysicalOperator> {
+        // Equalities on leading properties of a composite index become a composite index seek
+        if let PhysicalOperator::NodeScan { variable, labels, .. } = &input
+            && let [label] = labels.as_slice()
+            && self.prefers_seek(label)
+        {
+            let mut conjuncts = Vec::new();
+            Self::collect_conjuncts(&predicate, &mut conjuncts);
+            let equalities: Vec<(String, Expression)> = conjuncts
+                .iter()
+                .filter_map(|conjunct| Self::seek_equality(variable, conjunct))
+                .collect();
+            if let Some((seek, properties)) = self.composite_seek(variable, label, &equalities) {
+                // The seek checks everything when the predicate is exactly its equalities
+                if conjuncts.len() == properties.len() && equalities.len() == properties.len() {
+                    return Ok(seek);
+                }
+                return self.apply_filter(seek, predicate);
+            }
+        }
+
+        // Property equality on a single-label scan becomes an index seek
+        if let PhysicalOperator::NodeScan { variable, labels, .. } = &input
+            && let [label] = labels.as_slice()
+            && self.prefers_seek(label)
+            && let Some((property, value, whole)) =
+                Self::index_predicate(variable, &predicate, &|property| self.has_index(label, property))
+        {
+            let seek = PhysicalOperator::IndexSeek {
+                variable: variable.clone(),
+                label: label.clone(),
+                property,
+                value,
+                estimated_cost: INDEX_SEEK_COST,
+            };
+            if whole {
+                return Ok(seek);
+            }
+            // The remaining conjuncts are still checked on the seek's output
+            return self.apply_filter(seek, predicate);
+        }
+
+        // Range comparisons on a single-label scan become an index range scan
+        if let PhysicalOperator::NodeScan { variable, labels, .. } = &input
+            && let [label] = labels.as_slice()
+            && let Some((property, lower, upper)) =
+                Self::range_predicate(variable, &predicate, &|property| self.has_index(label, property))
+        {
+            let scan = PhysicalOperator::IndexScan {
+                variable: variable.clone(),
+                label: label.clone(),
+                property,
+                range: (lower, upper),
+                estimated_cost: self.estimate_cost(&input) / 3.0, // Assume a third of the label matches
+            };
+            // The range is inclusive, so strict bounds and other conjuncts are
+            // still checked on the scan's output
+            return self.apply_filter(scan, predicate);
+        }
+
         let estimated_cost = self.estimate_cost(&input) * 1.1; // Filter adds 10% overhead
         Ok(PhysicalOperator::Filter {
             input: Box::new(input),
@@ -294,6 +1413,185 @@ impl QueryPlanner {
         })
     }
 
+    /// Apply a SET, REMOVE or DELETE clause
+    ///
+    /// Property items of a SET share one operator; each label item gets its own.
+    fn apply_update(&self, input: PhysicalOperator, clause: &Clause) -> PhysicalOperator {
+        let estimated_cost = self.estimate_cost(&input) * 1.5; // Writes cost more than reads
+        match clause {
+            Clause::Set(set) => {
+                let (labels, properties): (Vec<_>, Vec<_>) = set
+                    .items
+                    .iter()
+                    .cloned()
+                    .partition(|item| matches!(item, SetItem::Labels { .. }));
+
+                let mut plan = input;
+                if !properties.is_empty() {
+                    plan = PhysicalOperator::SetProperty {
+                        input: Box::new(plan),
+                        items: properties,
+                        estimated_cost,
+                    };
+                }
+                for item in labels {
+                    if let SetItem::Labels { variable, labels } = item {
+                        plan = PhysicalOperator::SetLabels {
+                            input: Box::new(plan),
+                            variable,
+                            labels,
+                            estimated_cost,
+                        };
+                    }
+                }
+                plan
+            }
+            Clause::Remove(remove) => PhysicalOperator::Remove {
+                input: Box::new(input),
+                items: remove.items.clone(),
+                estimated_cost,
+            },
+            Clause::Delete(delete) => PhysicalOperator::Delete {
+                input: Box::new(input),
+                expressions: delete.expressions.clone(),
+                detach: delete.detach,
+                estimated_cost,
+            },
+            _ => input,
+        }
+    }
+
+    /// Find a `variable.property = literal` equality usable for an index seek
+    ///
+    /// Looks through AND conjunctions. Returns the property, the value and
+    /// whether the equality is the entire predicate. Null literals are skipped
+    /// since nodes without the property are not in the index, as are
+    /// properties for which `indexed` is false.
+    fn index_predicate(
+        variable: &str,
+        predicate: &Expression,
+        indexed: &dyn Fn(&str) -> bool,
+    ) -> Option<(String, Expression, bool)> {
+        let Expression::Binary { left, op, right } = predicate else {
+            return None;
+        };
+
+        match op {
+            BinaryOp::Equals => {
+                let (property, value) = match (left.as_ref(), right.as_ref()) {
+                    (Expression::Property(target, property), value @ Expression::Literal(_))
+                    | (value @ Expression::Literal(_), Expression::Property(target, property))
+                        if matches!(target.as_ref(), Expression::Variable(v) if v == variable) =>
+                    {
+                        (property, value)
+                    }
+                    _ => return None,
+                };
+                if matches!(value, Expression::Literal(Literal::Null)) || !indexed(property) {
+                    return None;
+                }
+                Some((property.clone(), value.clone(), true))
+            }
+            BinaryOp::And => Self::index_predicate(variable, left, indexed)
+                .or_else(|| Self::index_predicate(variable, right, indexed))
+                .map(|(property, value, _)| (property, value, false)),
+            _ => None,
+        }
+    }
+
+    /// The operands of an AND conjunction, or the predicate itself
+    fn collect_conjuncts<'a>(predicate: &'a Expression, conjuncts: &mut Vec<&'a Expression>) {
+        match predicate {
+            Expression::Binary { left, op: BinaryOp::And, right } => {
+                Self::collect_conjuncts(left, conjuncts);
+                Self::collect_conjuncts(right, conjuncts);
+            }
+            _ => conjuncts.push(predicate),
+        }
+    }
+
+    /// `variable.property = value` as `(property, value)`, if the value is a
+    /// non-null literal or a parameter
+    fn seek_equality(variable: &str, predicate: &Expression) -> Option<(String, Expression)> {
+        let Expression::Binary { left, op: BinaryOp::Equals, right } = predicate else {
+            return None;
+        };
+        match (left.as_ref(), right.as_ref()) {
+            (Expression::Property(target, property), value) | (value, Expression::Property(target, property))
+                if matches!(target.as_ref(), Expression::Variable(v) if v == variable)
+                    && Self::is_seek_value(value) =>
+            {
+                Some((property.clone(), value.clone()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether `value` can be evaluated before any row is read, and can match
+    fn is_seek_value(value: &Expression) -> bool {
+        matches!(value, Expression::Parameter(_))
+            || matches!(value, Expression::Literal(literal) if *literal != Literal::Null)
+    }
+
+    /// Find bounds on one property usable for an index range scan
+    ///
+    /// Looks through AND conjunctions for `variable.property` compared with a
+    /// literal or parameter by `<`, `<=`, `>` or `>=`, and returns the first
+    /// such property for which `indexed` is true with its lower and upper
+    /// bound.
+    fn range_predicate(
+        variable: &str,
+        predicate: &Expression,
+        indexed: &dyn Fn(&str) -> bool,
+    ) -> Option<(String, Option<Expression>, Option<Expression>)> {
+        let mut bounds = Vec::new();
+        Self::collect_range_bounds(variable, predicate, &mut bounds);
+        bounds.retain(|(property, _, _)| indexed(property));
+
+        let property = bounds.first()?.0.clone();
+        let (mut lower, mut upper) = (None, None);
+        for (bound_property, is_lower, value) in bounds {
+            if bound_property == property {
+                let bound = if is_lower { &mut lower } else { &mut upper };
+                bound.get_or_insert(value);
+            }
+        }
+        Some((property, lower, upper))
+    }
+
+    /// Collect `(property, is_lower_bound, value)` for each range comparison
+    fn collect_range_bounds(variable: &str, predicate: &Expression, bounds: &mut Vec<(String, bool, Expression)>) {
+        let Expression::Binary { left, op, right } = predicate else {
+            return;
+        };
+
+        if *op == BinaryOp::And {
+            Self::collect_range_bounds(variable, left, bounds);
+            Self::collect_range_bounds(variable, right, bounds);
+            return;
+        }
+
+        let is_target = |target: &Expression| matches!(target, Expression::Variable(v) if v == variable);
+        // `value < n.p` bounds `n.p` from below, so the side the property is on decides
+        let (property, value, property_on_left) = match (left.as_ref(), right.as_ref()) {
+            (Expression::Property(target, property), value) if is_target(target) => (property, value, true),
+            (value, Expression::Property(target, property)) if is_target(target) => (property, value, false),
+            _ => return,
+        };
+        if !matches!(value, Expression::Parameter(_))
+            && !matches!(value, Expression::Literal(literal) if *literal != Literal::Null)
+        {
+            return;
+        }
+
+        let is_lower = match op {
+            BinaryOp::GreaterThan | BinaryOp::GreaterEquals => property_on_left,
+            BinaryOp::LessThan | BinaryOp::LessEquals => !property_on_left,
+            _ => return,
+        };
+        bounds.push((property.clone(), is_lower, value.clone()));
+    }
+
     /// Apply ORDER BY
     fn apply_order_by(&self, input: PhysicalOperator, order_by: &OrderByClause) -> Result<PhysicalOperator> {
         let items: Vec<(Expression, bool)> = order_by.items.iter()
@@ -311,6 +1609,15 @@ impl QueryPlanner {
 
     /// Apply RETURN projection
     fn apply_return(&self, input: PhysicalOperator, return_clause: &ReturnClause) -> Result<PhysicalOperator> {
+        let has_aggregates = return_clause
+            .items
+            .iter()
+            .any(|item| Self::as_aggregate(&item.expression).is_some());
+
+        if has_aggregates {
+            return self.apply_aggregate_return(input, return_clause);
+        }
+
         let mut expressions = Vec::new();
         let mut aliases = Vec::new();
 
@@ -329,23 +1636,87 @@ impl QueryPlanner {
         })
     }
 
+    /// Apply a RETURN clause containing aggregate functions
+    ///
+    /// Non-aggregated return items become grouping keys. The Aggregate
+    /// operator emits internal columns which a final projection puts back
+    /// into RETURN order under the user-visible aliases.
+    fn apply_aggregate_return(&self, input: PhysicalOperator, return_clause: &ReturnClause) -> Result<PhysicalOperator> {
+        let mut group_by = Vec::new();
+        let mut group_by_aliases = Vec::new();
+        let mut aggregates = Vec::new();
+        let mut expressions = Vec::new();
+        let mut aliases = Vec::new();
+
+        for item in &return_clause.items {
+            let column = match Self::as_aggregate(&item.expression) {
+                Some((function, args, distinct)) => {
+                    let argument = match args {
+                        [arg] => arg.clone(),
+                        _ => {
+                            return Err(Error::InvalidQuery(format!(
+                                "Aggregate function {:?} takes exactly one argument",
+                                function
+                            )))
+                        }
+                    };
+                    if matches!(argument, Expression::Star) && function != AggregateFunction::Count {
+                        return Err(Error::InvalidQuery(
+                            "Only count() accepts * as its argument".to_string(),
+                        ));
+                    }
+
+                    let column = format!("__agg{}", aggregates.len());
+                    aggregates.push(AggregateExpression {
+                        function,
+                        argument,
+                        distinct,
+                        alias: column.clone(),
+                    });
+                    column
+                }
+                None => {
+                    let column = format!("__group{}", group_by.len());
+                    group_by.push(item.expression.clone());
+                    group_by_aliases.push(column.clone());
+                    column
+                }
+            };
+
+            expressions.push(Expression::Variable(column));
+            aliases.push(item.alias.clone().unwrap_or_else(|| "?column?".to_string()));
+        }
+
+        let estimated_cost = self.estimate_cost(&input) * 1.2;
+        let aggregate = PhysicalOperator::Aggregate {
+            input: Box::new(input),
+            group_by,
+            group_by_aliases,
+            aggregates,
+            estimated_cost,
+        };
+
+        Ok(PhysicalOperator::Project {
+            input: Box::new(aggregate),
+            expressions,
+            aliases,
+            estimated_cost: estimated_cost * 1.05,
+        })
+    }
+
+    /// Split an aggregate function call into its parts
+    fn as_aggregate(expr: &Expression) -> Option<(AggregateFunction, &[Expression], bool)> {
+        match expr {
+            Expression::Function { name, args, distinct } => {
+                AggregateFunction::from_name(name).map(|function| (function, args.as_slice(), *distinct))
+            }
+            _ => None,
+        }
+    }
+
     /// Estimate the cost of a physical operator
     fn estimate_cost(&self, operator: &PhysicalOperator) -> f64 {
-        match operator {
-            PhysicalOperator::NodeScan { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::IndexSeek { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::IndexScan { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::Filter { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::Project { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::Expand { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::HashJoin { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::NestedLoopJoin { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::OrderBy { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::Limit { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::Skip { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::Distinct { estimated_cost, .. } => *estimated_cost,
-            PhysicalOperator::Aggregate { estimated_cost, .. } => *estimated_cost,
-        }
+        operator.estimated_cost()
     }
 
     /// Estimate cardinality (number of rows) for an operator
@@ -358,16 +1729,67 @@ impl QueryPlanner {
                     *self.stats.nodes_per_label.get(&labels[0]).unwrap_or(&1000)
                 }
             }
-            PhysicalOperator::IndexSeek { .. } => 1, // Point lookup
+            PhysicalOperator::IndexSeek { .. }
+            | PhysicalOperator::CompositeIndexSeek { .. }
+            | PhysicalOperator::Merge { .. } => 1, // Point lookup
+            PhysicalOperator::CreateIndex { .. } | PhysicalOperator::DropIndex { .. } => 1,
             PhysicalOperator::Filter { input, .. } => {
                 // Assume 10% selectivity
                 self.estimate_cardinality(input) / 10
             }
-            PhysicalOperator::Limit { count, .. } => *count as u64,
+            PhysicalOperator::Expand { input, rel_types, .. } => {
+                match self.relationship_fanout(rel_types) {
+                    Some(fanout) => (self.estimate_cardinality(input) as f64 * fanout).ceil() as u64,
+                    None => 100,
+                }
+            }
+            PhysicalOperator::Limit { input, count, .. } => {
+                self.estimate_cardinality(input).min(*count as u64)
+            }
+            PhysicalOperator::Unwind { input, expression, .. } => {
+                let input_rows = input.as_ref().map_or(1, |input| self.estimate_cardinality(input));
+                let elements = match expression {
+                    Expression::List(items) => items.len() as u64,
+                    _ => 10, // Assume short lists
+                };
+                input_rows * elements
+            }
+            PhysicalOperator::Apply { left, right, .. } => {
+                self.estimate_cardinality(left) * self.estimate_cardinality(right)
+            }
+            PhysicalOperator::Skip { input, count, .. } => {
+                self.estimate_cardinality(input).saturating_sub(*count as u64)
+            }
+            // Row-preserving operators pass their input's cardinality through
+            PhysicalOperator::Project { input, .. }
+            | PhysicalOperator::OrderBy { input, .. }
+            | PhysicalOperator::Distinct { input, .. }
+            | PhysicalOperator::SetProperty { input, .. }
+            | PhysicalOperator::SetLabels { input, .. }
+            | PhysicalOperator::Remove { input, .. }
+            | PhysicalOperator::Delete { input, .. } => self.estimate_cardinality(input),
             _ => 100, // Default estimate
         }
     }
 
+    /// Whether an index seek on `label` is cheaper than scanning the label
+    fn prefers_seek(&self, label: &str) -> bool {
+        self.estimate_scan_cost(&[label.to_string()]) > INDEX_SEEK_COST
+    }
+
+    /// Average number of relationships of `rel_types` per node, if every
+    /// type's count is known
+    fn relationship_fanout(&self, rel_types: &[String]) -> Option<f64> {
+        if rel_types.is_empty() {
+            return None;
+        }
+        let mut total = 0;
+        for rel_type in rel_types {
+            total += self.stats.relationships_per_type.get(rel_type)?;
+        }
+        Some(total as f64 / self.stats.total_nodes.max(1) as f64)
+    }
+
     /// Estimate scan cost
     fn estimate_scan_cost(&self, labels: &[String]) -> f64 {
         if labels.is_empty() {
@@ -1,45 +1,168 @@
 //! Simple Cypher Parser
 //!
 //! Basic recursive descent parser for common Cypher patterns.
-//! Supports: MATCH, WHERE, RETURN, ORDER BY, LIMIT
-
+//! Supports: MATCH (including relationship chains, variable-length
+//! `*min..max` relationships and `shortestPath`/`allShortestPaths`), WHERE,
+//! RETURN, ORDER BY, LIMIT, standalone `CALL procedure(...) YIELD ...`,
+//! `MERGE` with `ON CREATE SET`/`ON MATCH SET`, `SET`/`REMOVE` and
+//! `[DETACH] DELETE`, `UNWIND list AS x` before the first MATCH, CALL or
+//! MERGE and after WHERE, with an optional `EXPLAIN` or `PROFILE` prefix, as
+//! well as `CREATE INDEX` and `DROP INDEX`. Node patterns may carry inline
+//! properties (`{key: value}`).
+//! Expressions support arithmetic (`+ - * / % ^`), parentheses, map literals
+//! and `null`/`true`/`false`.
+
+use crate::lexer::Token;
 use crate::parser::*;
+use logos::Logos;
 use qilbee_core::{Error, Result};
 
-/// Parse a Cypher query string
+/// Default maximum nesting depth of expressions
+pub const DEFAULT_MAX_PARSE_DEPTH: usize = 64;
+
+/// Default maximum number of tokens in a query
+pub const DEFAULT_MAX_QUERY_TOKENS: usize = 10_000;
+
+/// Limits that keep pathological input from exhausting the parser
+///
+/// Queries exceeding them fail with [`Error::QueryParse`] instead of
+/// overflowing the stack or parsing for an unbounded time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParserLimits {
+    /// Maximum nesting depth of expressions (e.g. nested function calls)
+    pub max_depth: usize,
+
+    /// Maximum number of tokens in the query
+    pub max_tokens: usize,
+}
+
+impl Default for ParserLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_MAX_PARSE_DEPTH,
+            max_tokens: DEFAULT_MAX_QUERY_TOKENS,
+        }
+    }
+}
+
+/// Parse a Cypher query string with the default [`ParserLimits`]
 pub fn parse_simple(query: &str) -> Result<Query> {
-    let mut parser = SimpleParser::new(query);
+    parse_simple_with_limits(query, ParserLimits::default())
+}
+
+/// Parse a Cypher query string, rejecting queries that exceed `limits`
+pub fn parse_simple_with_limits(query: &str, limits: ParserLimits) -> Result<Query> {
+    // Counting stops one past the limit, so huge inputs are not lexed in full
+    let tokens = Token::lexer(query).take(limits.max_tokens + 1).count();
+    if tokens > limits.max_tokens {
+        return Err(Error::QueryParse(format!(
+            "Query exceeds the maximum of {} tokens",
+            limits.max_tokens
+        )));
+    }
+
+    let mut parser = SimpleParser::new(query, limits);
     parser.parse_query()
 }
 
 struct SimpleParser {
     query: String,
+    /// Byte offset into `query`
     pos: usize,
+    limits: ParserLimits,
+    depth: usize,
 }
 
 impl SimpleParser {
-    fn new(query: &str) -> Self {
+    fn new(query: &str, limits: ParserLimits) -> Self {
         Self {
             query: query.trim().to_string(),
             pos: 0,
+            limits,
+            depth: 0,
         }
     }
 
     fn parse_query(&mut self) -> Result<Query> {
         let mut clauses = Vec::new();
 
+        let mode = if self.consume_keyword("EXPLAIN") {
+            QueryMode::Explain
+        } else if self.consume_keyword("PROFILE") {
+            QueryMode::Profile
+        } else {
+            QueryMode::Execute
+        };
+
+        // Index statements stand alone
+        let start = self.pos;
+        if self.consume_keyword("CREATE") && self.consume_keyword("INDEX") {
+            let clause = Clause::CreateIndex(self.parse_create_index()?);
+            return self.finish_schema_statement(clause, mode);
+        }
+        self.pos = start;
+        if self.consume_keyword("DROP") {
+            if !self.consume_keyword("INDEX") {
+                return Err(Error::QueryParse("Expected INDEX after DROP".to_string()));
+            }
+            let clause = Clause::DropIndex(self.parse_drop_index()?);
+            return self.finish_schema_statement(clause, mode);
+        }
+
+        // Parse UNWIND clauses feeding the rest of the query
+        while self.consume_keyword("UNWIND") {
+            clauses.push(Clause::Unwind(self.parse_unwind()?));
+        }
+
         // Parse MATCH clause
         if self.consume_keyword("MATCH") {
             let match_clause = self.parse_match()?;
             clauses.push(Clause::Match(match_clause));
         }
 
+        // Parse CALL clause
+        if self.consume_keyword("CALL") {
+            let call_clause = self.parse_call()?;
+            clauses.push(Clause::Call(call_clause));
+        }
+
+        // Parse MERGE clause
+        if self.consume_keyword("MERGE") {
+            let merge_clause = self.parse_merge()?;
+            clauses.push(Clause::Merge(merge_clause));
+        }
+
         // Parse WHERE clause
         if self.consume_keyword("WHERE") {
             let where_expr = self.parse_where()?;
             clauses.push(Clause::Where(where_expr));
         }
 
+        // Parse UNWIND clauses over the matched rows
+        while self.consume_keyword("UNWIND") {
+            clauses.push(Clause::Unwind(self.parse_unwind()?));
+        }
+
+        // Parse SET, REMOVE and DELETE clauses, in any order
+        loop {
+            if self.consume_keyword("SET") {
+                let items = self.parse_set_items()?;
+                clauses.push(Clause::Set(SetClause { items }));
+            } else if self.consume_keyword("REMOVE") {
+                let items = self.parse_remove_items()?;
+                clauses.push(Clause::Remove(RemoveClause { items }));
+            } else if self.consume_keyword("DETACH") {
+                if !self.consume_keyword("DELETE") {
+                    return Err(Error::QueryParse("Expected DELETE after DETACH".to_string()));
+                }
+                clauses.push(Clause::Delete(self.parse_delete(true)?));
+            } else if self.consume_keyword("DELETE") {
+                clauses.push(Clause::Delete(self.parse_delete(false)?));
+            } else {
+                break;
+            }
+        }
+
         // Parse RETURN clause
         if self.consume_keyword("RETURN") {
             let return_clause = self.parse_return()?;
@@ -53,48 +176,479 @@ impl SimpleParser {
             clauses.push(Clause::OrderBy(order_clause));
         }
 
+        // Parse SKIP clause
+        if self.consume_keyword("SKIP") {
+            let skip_expr = self.parse_limit()?;
+            clauses.push(Clause::Skip(skip_expr));
+        }
+
         // Parse LIMIT clause
         if self.consume_keyword("LIMIT") {
             let limit_expr = self.parse_limit()?;
             clauses.push(Clause::Limit(limit_expr));
         }
 
-        Ok(Query { clauses })
+        Ok(Query { clauses, mode })
+    }
+
+    /// Parse the rest of `CREATE INDEX [name] [IF NOT EXISTS]
+    /// FOR (n:Label) ON (n.prop, ...)`, or of the older form
+    /// `CREATE INDEX ON :Label(prop, ...)`
+    fn parse_create_index(&mut self) -> Result<CreateIndexClause> {
+        let name = if self.peek_keyword("IF") || self.peek_keyword("FOR") || self.peek_keyword("ON") {
+            None
+        } else {
+            Some(self.parse_identifier()?)
+        };
+
+        let if_not_exists = self.consume_keyword("IF");
+        if if_not_exists && !(self.consume_keyword("NOT") && self.consume_keyword("EXISTS")) {
+            return Err(Error::QueryParse("Expected NOT EXISTS after IF".to_string()));
+        }
+
+        let (label, properties) = if self.consume_keyword("FOR") {
+            let node = self.parse_node_pattern()?;
+            let [label] = node.labels.as_slice() else {
+                return Err(Error::QueryParse("Index pattern must have exactly one label".to_string()));
+            };
+            if !self.consume_keyword("ON") || !self.consume_char('(') {
+                return Err(Error::QueryParse("Expected ON (...) after index pattern".to_string()));
+            }
+            let mut properties = Vec::new();
+            loop {
+                let variable = self.parse_identifier()?;
+                if node.variable.as_deref() != Some(variable.as_str()) {
+                    return Err(Error::QueryParse(format!("Variable {} not defined in index pattern", variable)));
+                }
+                if !self.consume_char('.') {
+                    return Err(Error::QueryParse(format!("Expected property of {}", variable)));
+                }
+                properties.push(self.parse_identifier()?);
+                if !self.consume_char(',') {
+                    break;
+                }
+            }
+            (label.clone(), properties)
+        } else if self.consume_keyword("ON") {
+            if !self.consume_char(':') {
+                return Err(Error::QueryParse("Expected :Label after ON".to_string()));
+            }
+            let label = self.parse_identifier()?;
+            if !self.consume_char('(') {
+                return Err(Error::QueryParse("Expected '(' after index label".to_string()));
+            }
+            let mut properties = Vec::new();
+            loop {
+                properties.push(self.parse_identifier()?);
+                if !self.consume_char(',') {
+                    break;
+                }
+            }
+            (label, properties)
+        } else {
+            return Err(Error::QueryParse("Expected FOR or ON in CREATE INDEX".to_string()));
+        };
+        if !self.consume_char(')') {
+            return Err(Error::QueryParse("Expected ')' after indexed properties".to_string()));
+        }
+
+        Ok(CreateIndexClause { name, label, properties, if_not_exists })
+    }
+
+    /// Parse the rest of `DROP INDEX name [IF EXISTS]`
+    fn parse_drop_index(&mut self) -> Result<DropIndexClause> {
+        let name = self.parse_identifier()?;
+        let if_exists = self.consume_keyword("IF");
+        if if_exists && !self.consume_keyword("EXISTS") {
+            return Err(Error::QueryParse("Expected EXISTS after IF".to_string()));
+        }
+        Ok(DropIndexClause { name, if_exists })
+    }
+
+    /// End a schema statement, which cannot be followed by other clauses
+    fn finish_schema_statement(&mut self, clause: Clause, mode: QueryMode) -> Result<Query> {
+        if self.peek_non_whitespace().is_some() {
+            return Err(Error::QueryParse(format!(
+                "Unexpected input after index statement: {}",
+                &self.query[self.pos..]
+            )));
+        }
+        Ok(Query { clauses: vec![clause], mode })
     }
 
     fn parse_match(&mut self) -> Result<MatchClause> {
         self.skip_whitespace();
 
-        // Parse pattern: (variable:Label)
-        if !self.consume_char('(') {
-            return Err(Error::QueryParse("Expected '(' after MATCH".to_string()));
+        // Optional path variable: p = (a)-->(b)
+        let variable = if self.peek_char() != Some('(') {
+            let name = self.parse_identifier()?;
+            if !self.consume_char('=') {
+                return Err(Error::QueryParse("Expected '=' after path variable".to_string()));
+            }
+            Some(name)
+        } else {
+            None
+        };
+
+        self.skip_whitespace();
+        let selector = if self.consume_keyword("shortestPath") {
+            Some(PathSelector::Shortest)
+        } else if self.consume_keyword("allShortestPaths") {
+            Some(PathSelector::AllShortest)
+        } else {
+            None
+        };
+        if selector.is_some() && !self.consume_char('(') {
+            return Err(Error::QueryParse("Expected '(' after shortest path function".to_string()));
+        }
+
+        let elements = self.parse_pattern_elements()?;
+
+        if selector.is_some() && !self.consume_char(')') {
+            return Err(Error::QueryParse("Expected ')' after shortest path pattern".to_string()));
+        }
+
+        Ok(MatchClause {
+            patterns: vec![Pattern { variable, selector, elements }],
+        })
+    }
+
+    /// Parse `(variable:Label)-[rel:TYPE*min..max]->(other)...`
+    fn parse_pattern_elements(&mut self) -> Result<Vec<PatternElement>> {
+        let mut elements = vec![PatternElement::Node(self.parse_node_pattern()?)];
+        while let Some(relationship) = self.parse_relationship_pattern()? {
+            elements.push(PatternElement::Relationship(relationship));
+            elements.push(PatternElement::Node(self.parse_node_pattern()?));
+        }
+        Ok(elements)
+    }
+
+    /// Parse `pattern [ON CREATE SET ...] [ON MATCH SET ...]`
+    fn parse_merge(&mut self) -> Result<MergeClause> {
+        let elements = self.parse_pattern_elements()?;
+        let mut on_create = Vec::new();
+        let mut on_match = Vec::new();
+
+        while self.consume_keyword("ON") {
+            let items = if self.consume_keyword("CREATE") {
+                &mut on_create
+            } else if self.consume_keyword("MATCH") {
+                &mut on_match
+            } else {
+                return Err(Error::QueryParse("Expected CREATE or MATCH after ON".to_string()));
+            };
+            if !self.consume_keyword("SET") {
+                return Err(Error::QueryParse("Expected SET after ON CREATE or ON MATCH".to_string()));
+            }
+            items.extend(self.parse_set_items()?);
+        }
+
+        Ok(MergeClause {
+            pattern: Pattern { variable: None, selector: None, elements },
+            on_create,
+            on_match,
+        })
+    }
+
+    /// Parse comma-separated `n.prop = expr`, `n = map`, `n += map` and
+    /// `n:Label` items
+    fn parse_set_items(&mut self) -> Result<Vec<SetItem>> {
+        let mut items = Vec::new();
+        loop {
+            let variable = self.parse_identifier()?;
+            self.skip_whitespace();
+            if self.consume_str("+=") {
+                let value = self.parse_expression()?;
+                items.push(SetItem::MergeProperties { variable, value });
+            } else if self.consume_char('=') {
+                let value = self.parse_expression()?;
+                items.push(SetItem::AllProperties { variable, value });
+            } else if self.consume_char('.') {
+                let property = self.parse_identifier()?;
+                if !self.consume_char('=') {
+                    return Err(Error::QueryParse("Expected '=' in SET item".to_string()));
+                }
+                let value = self.parse_expression()?;
+                items.push(SetItem::Property { entity: variable, property, value });
+            } else if self.peek_non_whitespace() == Some(':') {
+                let mut labels = Vec::new();
+                while self.consume_char(':') {
+                    labels.push(self.parse_identifier()?);
+                }
+                items.push(SetItem::Labels { variable, labels });
+            } else {
+                return Err(Error::QueryParse("Expected '.', ':', '=' or '+=' in SET item".to_string()));
+            }
+
+            if !self.consume_char(',') {
+                return Ok(items);
+            }
         }
+    }
 
+    /// Parse the rest of `UNWIND expr AS variable`
+    fn parse_unwind(&mut self) -> Result<UnwindClause> {
+        let expression = self.parse_expression()?;
+        if !self.consume_keyword("AS") {
+            return Err(Error::QueryParse("Expected AS after UNWIND expression".to_string()));
+        }
         let variable = self.parse_identifier()?;
+        Ok(UnwindClause { expression, variable })
+    }
+
+    /// Parse the comma-separated expressions of a DELETE clause
+    fn parse_delete(&mut self, detach: bool) -> Result<DeleteClause> {
+        let mut expressions = vec![self.parse_expression()?];
+        while self.consume_char(',') {
+            expressions.push(self.parse_expression()?);
+        }
+        Ok(DeleteClause { detach, expressions })
+    }
+
+    /// Parse comma-separated `n.prop` and `n:Label` items
+    fn parse_remove_items(&mut self) -> Result<Vec<RemoveItem>> {
+        let mut items = Vec::new();
+        loop {
+            let variable = self.parse_identifier()?;
+            if self.consume_char('.') {
+                let property = self.parse_identifier()?;
+                items.push(RemoveItem::Property { entity: variable, property });
+            } else if self.peek_non_whitespace() == Some(':') {
+                let mut labels = Vec::new();
+                while self.consume_char(':') {
+                    labels.push(self.parse_identifier()?);
+                }
+                items.push(RemoveItem::Labels { variable, labels });
+            } else {
+                return Err(Error::QueryParse("Expected '.' or ':' in REMOVE item".to_string()));
+            }
+
+            if !self.consume_char(',') {
+                return Ok(items);
+            }
+        }
+    }
+
+    /// Parse `{key: expr, ...}`
+    fn parse_map(&mut self) -> Result<MapExpression> {
+        if !self.consume_char('{') {
+            return Err(Error::QueryParse("Expected '{'".to_string()));
+        }
+
+        let mut entries = Vec::new();
+        if !self.consume_char('}') {
+            loop {
+                let key = self.parse_identifier()?;
+                if !self.consume_char(':') {
+                    return Err(Error::QueryParse("Expected ':' after map key".to_string()));
+                }
+                entries.push((key, self.parse_expression()?));
+                if !self.consume_char(',') {
+                    break;
+                }
+            }
+            if !self.consume_char('}') {
+                return Err(Error::QueryParse("Expected '}' after map entries".to_string()));
+            }
+        }
+
+        Ok(MapExpression { entries })
+    }
+
+    fn parse_node_pattern(&mut self) -> Result<NodePattern> {
+        if !self.consume_char('(') {
+            return Err(Error::QueryParse("Expected '(' in pattern".to_string()));
+        }
+
+        let variable = self.parse_optional_identifier();
 
         let mut labels = Vec::new();
-        if self.consume_char(':') {
+        while self.consume_char(':') {
             labels.push(self.parse_identifier()?);
         }
 
+        let properties = if self.peek_non_whitespace() == Some('{') {
+            Some(self.parse_map()?)
+        } else {
+            None
+        };
+
         if !self.consume_char(')') {
             return Err(Error::QueryParse("Expected ')' in pattern".to_string()));
         }
 
-        let node_pattern = NodePattern {
-            variable: Some(variable),
+        Ok(NodePattern {
+            variable,
             labels,
-            properties: None,
+            properties,
+        })
+    }
+
+    /// Parse `-[...]->`, `<-[...]-`, `-[...]-` or their bracketless forms
+    fn parse_relationship_pattern(&mut self) -> Result<Option<RelationshipPattern>> {
+        self.skip_whitespace();
+
+        let incoming = if self.consume_str("<-") {
+            true
+        } else if self.consume_char('-') {
+            false
+        } else {
+            return Ok(None);
         };
 
-        Ok(MatchClause {
-            patterns: vec![Pattern {
-                elements: vec![PatternElement::Node(node_pattern)],
-            }],
+        let mut variable = None;
+        let mut rel_types = Vec::new();
+        let mut length = None;
+
+        if self.consume_char('[') {
+            variable = self.parse_optional_identifier();
+
+            if self.consume_char(':') {
+                loop {
+                    rel_types.push(self.parse_identifier()?);
+                    if !self.consume_char('|') {
+                        break;
+                    }
+                    // Both [:A|B] and [:A|:B] are accepted
+                    self.consume_char(':');
+                }
+            }
+
+            if self.consume_char('*') {
+                length = Some(self.parse_relationship_length()?);
+            }
+
+            if !self.consume_char(']') {
+                return Err(Error::QueryParse("Expected ']' in relationship pattern".to_string()));
+            }
+        }
+
+        self.skip_whitespace();
+        let outgoing = if self.consume_str("->") {
+            true
+        } else if self.consume_char('-') {
+            false
+        } else {
+            return Err(Error::QueryParse("Expected '-' or '->' after relationship".to_string()));
+        };
+
+        let direction = match (incoming, outgoing) {
+            (false, true) => RelationshipDirection::Outgoing,
+            (true, false) => RelationshipDirection::Incoming,
+            (false, false) => RelationshipDirection::Both,
+            (true, true) => {
+                return Err(Error::QueryParse(
+                    "Relationship pattern cannot point in both directions".to_string(),
+                ))
+            }
+        };
+
+        Ok(Some(RelationshipPattern {
+            variable,
+            rel_types,
+            direction,
+            properties: None,
+            length,
+        }))
+    }
+
+    /// Parse the part of a variable-length pattern following `*`
+    ///
+    /// `*` and `*2..` leave `max` unset, `*3` means exactly three hops.
+    fn parse_relationship_length(&mut self) -> Result<RelationshipLength> {
+        self.skip_whitespace();
+
+        let min = self.parse_optional_hops()?;
+        if self.consume_str("..") {
+            let max = self.parse_optional_hops()?;
+            Ok(RelationshipLength { min, max })
+        } else {
+            Ok(RelationshipLength { min, max: min })
+        }
+    }
+
+    fn parse_optional_hops(&mut self) -> Result<Option<u32>> {
+        self.skip_whitespace();
+        if !self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+
+        let hops = self.parse_number()?;
+        u32::try_from(hops)
+            .map(Some)
+            .map_err(|_| Error::QueryParse(format!("Invalid relationship length: {}", hops)))
+    }
+
+    fn parse_optional_identifier(&mut self) -> Option<String> {
+        self.skip_whitespace();
+        if self.peek_char().is_some_and(|c| c.is_alphanumeric() || c == '_') {
+            self.parse_identifier().ok()
+        } else {
+            None
+        }
+    }
+
+    /// Parse `procedure.name(args...) [YIELD col, ...]`
+    fn parse_call(&mut self) -> Result<CallClause> {
+        let mut procedure = self.parse_identifier()?;
+        while self.consume_char('.') {
+            procedure.push('.');
+            procedure.push_str(&self.parse_identifier()?);
+        }
+
+        if !self.consume_char('(') {
+            return Err(Error::QueryParse(format!("Expected '(' after procedure {}", procedure)));
+        }
+        let mut args = Vec::new();
+        if self.peek_non_whitespace() != Some(')') {
+            loop {
+                args.push(self.parse_expression()?);
+                if !self.consume_char(',') {
+                    break;
+                }
+            }
+        }
+        if !self.consume_char(')') {
+            return Err(Error::QueryParse(format!("Expected ')' after arguments to {}", procedure)));
+        }
+
+        let mut yield_items = Vec::new();
+        if self.consume_keyword("YIELD") {
+            loop {
+                yield_items.push(self.parse_identifier()?);
+                if !self.consume_char(',') {
+                    break;
+                }
+            }
+        }
+
+        Ok(CallClause {
+            procedure,
+            args,
+            yield_items,
         })
     }
 
+    /// Parse comparisons joined by `AND` and `OR`, with `AND` binding tighter
     fn parse_where(&mut self) -> Result<Expression> {
+        let mut left = self.parse_conjunction()?;
+        while self.consume_keyword("OR") {
+            let right = self.parse_conjunction()?;
+            left = Expression::Binary { left: Box::new(left), op: BinaryOp::Or, right: Box::new(right) };
+        }
+        Ok(left)
+    }
+
+    fn parse_conjunction(&mut self) -> Result<Expression> {
+        let mut left = self.parse_comparison()?;
+        while self.consume_keyword("AND") {
+            let right = self.parse_comparison()?;
+            left = Expression::Binary { left: Box::new(left), op: BinaryOp::And, right: Box::new(right) };
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expression> {
         self.skip_whitespace();
 
         // Parse comparison: variable.property > $parameter
@@ -120,7 +674,11 @@ impl SimpleParser {
 
         loop {
             let expr = self.parse_expression()?;
-            let alias = None; // TODO: parse AS alias
+            let alias = if self.consume_keyword("AS") {
+                Some(self.parse_identifier()?)
+            } else {
+                None
+            };
 
             items.push(ReturnItem {
                 expression: expr,
@@ -162,18 +720,118 @@ impl SimpleParser {
     }
 
     fn parse_expression(&mut self) -> Result<Expression> {
+        if self.depth >= self.limits.max_depth {
+            return Err(Error::QueryParse(format!(
+                "Expression nesting exceeds the maximum depth of {}",
+                self.limits.max_depth
+            )));
+        }
+
+        self.depth += 1;
+        let expression = self.parse_additive();
+        self.depth -= 1;
+        expression
+    }
+
+    /// Parse `a + b - c`, binding looser than `*`, `/`, `%` and `^`
+    fn parse_additive(&mut self) -> Result<Expression> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = if self.consume_char('+') {
+                BinaryOp::Add
+            } else if self.consume_char('-') {
+                BinaryOp::Subtract
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_multiplicative()?;
+            left = Expression::Binary { left: Box::new(left), op, right: Box::new(right) };
+        }
+    }
+
+    /// Parse `a * b / c % d`
+    fn parse_multiplicative(&mut self) -> Result<Expression> {
+        let mut left = self.parse_power()?;
+        loop {
+            let op = if self.consume_char('*') {
+                BinaryOp::Multiply
+            } else if self.consume_char('/') {
+                BinaryOp::Divide
+            } else if self.consume_char('%') {
+                BinaryOp::Modulo
+            } else {
+                return Ok(left);
+            };
+            let right = self.parse_power()?;
+            left = Expression::Binary { left: Box::new(left), op, right: Box::new(right) };
+        }
+    }
+
+    /// Parse `a ^ b`
+    fn parse_power(&mut self) -> Result<Expression> {
+        let mut left = self.parse_primary()?;
+        while self.consume_char('^') {
+            let right = self.parse_primary()?;
+            left = Expression::Binary {
+                left: Box::new(left),
+                op: BinaryOp::Power,
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+
+    /// Parse an atom followed by any number of `.property` and `[index]`
+    /// accesses, e.g. `p.address.city` or `p.tags[0]`
+    fn parse_primary(&mut self) -> Result<Expression> {
+        let mut expression = self.parse_atom()?;
+        loop {
+            if self.consume_char('.') {
+                let property = self.parse_identifier()?;
+                expression = Expression::Property(Box::new(expression), property);
+            } else if self.consume_char('[') {
+                let index = self.parse_expression()?;
+                if !self.consume_char(']') {
+                    return Err(Error::QueryParse("Expected ']' after index".to_string()));
+                }
+                expression = Expression::Index(Box::new(expression), Box::new(index));
+            } else {
+                return Ok(expression);
+            }
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Expression> {
         self.skip_whitespace();
 
+        // Check for a parenthesized expression
+        if self.consume_char('(') {
+            let expression = self.parse_expression()?;
+            if !self.consume_char(')') {
+                return Err(Error::QueryParse("Expected ')' after expression".to_string()));
+            }
+            return Ok(expression);
+        }
+
         // Check for parameter
         if self.consume_char('$') {
             let param_name = self.parse_identifier()?;
             return Ok(Expression::Parameter(param_name));
         }
 
-        // Check for number
+        // Check for number, possibly negative
+        if self.query[self.pos..].starts_with('-')
+            && self.query[self.pos + 1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            self.pos += 1;
+            return match self.parse_numeric_literal()? {
+                Expression::Literal(Literal::Integer(i)) => Ok(Expression::Literal(Literal::Integer(-i))),
+                Expression::Literal(Literal::Float(f)) => Ok(Expression::Literal(Literal::Float(-f))),
+                other => Ok(other),
+            };
+        }
         if self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
-            let num = self.parse_number()?;
-            return Ok(Expression::Literal(Literal::Integer(num)));
+            return self.parse_numeric_literal();
         }
 
         // Check for string
@@ -182,21 +840,79 @@ impl SimpleParser {
             return Ok(Expression::Literal(Literal::String(s)));
         }
 
-        // Parse variable or property access
+        // Check for map literal
+        if self.peek_char() == Some('{') {
+            return Ok(Expression::Map(self.parse_map()?));
+        }
+
+        // Check for list literal
+        if self.consume_char('[') {
+            let mut items = Vec::new();
+            if self.peek_non_whitespace() != Some(']') {
+                loop {
+                    items.push(self.parse_expression()?);
+                    if !self.consume_char(',') {
+                        break;
+                    }
+                }
+            }
+            if !self.consume_char(']') {
+                return Err(Error::QueryParse("Expected ']' after list items".to_string()));
+            }
+            return Ok(Expression::List(items));
+        }
+
+        // Parse keyword literal, variable or function call
         let var = self.parse_identifier()?;
+        if self.peek_non_whitespace() != Some('(') {
+            match var.to_ascii_lowercase().as_str() {
+                "null" => return Ok(Expression::Literal(Literal::Null)),
+                "true" => return Ok(Expression::Literal(Literal::Boolean(true))),
+                "false" => return Ok(Expression::Literal(Literal::Boolean(false))),
+                _ => {}
+            }
+        }
 
-        // Check for property access
-        if self.consume_char('.') {
-            let prop = self.parse_identifier()?;
-            return Ok(Expression::Property(
-                Box::new(Expression::Variable(var)),
-                prop,
-            ));
+        // Check for function call
+        if self.consume_char('(') {
+            return self.parse_function_call(var);
         }
 
+        // Check for a namespaced function call, e.g. `duration.between(a, b)`
+        let start = self.pos;
+        if self.consume_char('.')
+            && let Ok(member) = self.parse_identifier()
+            && self.consume_char('(')
+        {
+            return self.parse_function_call(format!("{}.{}", var, member));
+        }
+        self.pos = start;
+
         Ok(Expression::Variable(var))
     }
 
+    fn parse_function_call(&mut self, name: String) -> Result<Expression> {
+        let distinct = self.consume_keyword("DISTINCT");
+        let mut args = Vec::new();
+
+        if self.consume_char('*') {
+            args.push(Expression::Star);
+        } else if self.peek_non_whitespace() != Some(')') {
+            loop {
+                args.push(self.parse_expression()?);
+                if !self.consume_char(',') {
+                    break;
+                }
+            }
+        }
+
+        if !self.consume_char(')') {
+            return Err(Error::QueryParse(format!("Expected ')' after arguments to {}", name)));
+        }
+
+        Ok(Expression::Function { name, args, distinct })
+    }
+
     fn parse_operator(&mut self) -> Result<BinaryOp> {
         self.skip_whitespace();
 
@@ -210,8 +926,24 @@ impl SimpleParser {
             Ok(BinaryOp::GreaterThan)
         } else if self.consume_char('<') {
             Ok(BinaryOp::LessThan)
+        } else if self.consume_str("=~") {
+            Ok(BinaryOp::RegexMatch)
         } else if self.consume_char('=') {
             Ok(BinaryOp::Equals)
+        } else if self.consume_keyword("IN") {
+            Ok(BinaryOp::In)
+        } else if self.consume_keyword("CONTAINS") {
+            Ok(BinaryOp::Contains)
+        } else if self.consume_keyword("STARTS") {
+            if !self.consume_keyword("WITH") {
+                return Err(Error::QueryParse("Expected WITH after STARTS".to_string()));
+            }
+            Ok(BinaryOp::StartsWith)
+        } else if self.consume_keyword("ENDS") {
+            if !self.consume_keyword("WITH") {
+                return Err(Error::QueryParse("Expected WITH after ENDS".to_string()));
+            }
+            Ok(BinaryOp::EndsWith)
         } else {
             Err(Error::QueryParse("Expected comparison operator".to_string()))
         }
@@ -223,7 +955,7 @@ impl SimpleParser {
         let start = self.pos;
         while let Some(c) = self.peek_char() {
             if c.is_alphanumeric() || c == '_' {
-                self.pos += 1;
+                self.pos += c.len_utf8();
             } else {
                 break;
             }
@@ -257,6 +989,36 @@ impl SimpleParser {
             .map_err(|_| Error::QueryParse("Invalid number".to_string()))
     }
 
+    /// Parse an integer or float literal (`42`, `0.85`, `1e-6`)
+    fn parse_numeric_literal(&mut self) -> Result<Expression> {
+        let start = self.pos;
+        let integer = self.parse_number()?;
+        let integer_end = self.pos;
+
+        if self.query[self.pos..].starts_with('.')
+            && self.query[self.pos + 1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            self.pos += 1;
+            self.parse_number()?;
+        }
+        if self.query[self.pos..].starts_with(['e', 'E']) {
+            self.pos += 1;
+            if self.query[self.pos..].starts_with(['-', '+']) {
+                self.pos += 1;
+            }
+            self.parse_number()?;
+        }
+
+        if self.pos == integer_end {
+            return Ok(Expression::Literal(Literal::Integer(integer)));
+        }
+
+        self.query[start..self.pos]
+            .parse()
+            .map(|f| Expression::Literal(Literal::Float(f)))
+            .map_err(|_| Error::QueryParse("Invalid number".to_string()))
+    }
+
     fn parse_string(&mut self) -> Result<String> {
         self.skip_whitespace();
 
@@ -272,7 +1034,7 @@ impl SimpleParser {
             if c == quote {
                 break;
             }
-            self.pos += 1;
+            self.pos += c.len_utf8();
         }
 
         let s = self.query[start..self.pos].to_string();
@@ -287,24 +1049,33 @@ impl SimpleParser {
     fn consume_keyword(&mut self, keyword: &str) -> bool {
         self.skip_whitespace();
 
-        let upper_keyword = keyword.to_uppercase();
         let remaining = &self.query[self.pos..];
+        let Some(candidate) = remaining.get(..keyword.len()) else {
+            return false;
+        };
 
-        if remaining.len() >= upper_keyword.len() {
-            let candidate = &remaining[..upper_keyword.len()];
-            if candidate.to_uppercase() == upper_keyword {
-                // Check that it's followed by whitespace or end
-                if remaining.len() == upper_keyword.len() ||
-                   remaining.chars().nth(upper_keyword.len()).map_or(false, |c| c.is_whitespace() || "(),".contains(c)) {
-                    self.pos += upper_keyword.len();
-                    return true;
-                }
-            }
+        // Check that it's followed by whitespace or end
+        if candidate.eq_ignore_ascii_case(keyword)
+            && remaining[keyword.len()..]
+                .chars()
+                .next()
+                .is_none_or(|c| c.is_whitespace() || "(),".contains(c))
+        {
+            self.pos += keyword.len();
+            return true;
         }
 
         false
     }
 
+    /// Whether the next word is `keyword`, without consuming it
+    fn peek_keyword(&mut self, keyword: &str) -> bool {
+        let start = self.pos;
+        let found = self.consume_keyword(keyword);
+        self.pos = start;
+        found
+    }
+
     fn consume_str(&mut self, s: &str) -> bool {
         let remaining = &self.query[self.pos..];
         if remaining.starts_with(s) {
@@ -318,7 +1089,7 @@ impl SimpleParser {
     fn consume_char(&mut self, c: char) -> bool {
         self.skip_whitespace();
         if self.peek_char() == Some(c) {
-            self.pos += 1;
+            self.pos += c.len_utf8();
             true
         } else {
             false
@@ -326,23 +1097,24 @@ impl SimpleParser {
     }
 
     fn consume_any_char(&mut self) -> Option<char> {
-        if self.pos < self.query.len() {
-            let c = self.query.chars().nth(self.pos)?;
-            self.pos += 1;
-            Some(c)
-        } else {
-            None
-        }
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        Some(c)
     }
 
     fn peek_char(&self) -> Option<char> {
-        self.query.chars().nth(self.pos)
+        self.query[self.pos..].chars().next()
+    }
+
+    fn peek_non_whitespace(&mut self) -> Option<char> {
+        self.skip_whitespace();
+        self.peek_char()
     }
 
     fn skip_whitespace(&mut self) {
         while let Some(c) = self.peek_char() {
             if c.is_whitespace() {
-                self.pos += 1;
+                self.pos += c.len_utf8();
             } else {
                 break;
             }
@@ -367,4 +1139,471 @@ mod tests {
         let result = parse_simple(query);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_parse_aggregate_functions() {
+        let query = parse_simple("MATCH (p:Person) RETURN p.city, count(*) AS total, count(DISTINCT p.name)").unwrap();
+
+        let Clause::Return(ret) = &query.clauses[1] else {
+            panic!("Expected RETURN clause");
+        };
+        assert_eq!(ret.items.len(), 3);
+        assert_eq!(ret.items[1].alias.as_deref(), Some("total"));
+        assert_eq!(
+            ret.items[1].expression,
+            Expression::Function {
+                name: "count".to_string(),
+                args: vec![Expression::Star],
+                distinct: false,
+            }
+        );
+        assert!(matches!(
+            &ret.items[2].expression,
+            Expression::Function { distinct: true, args, .. } if args.len() == 1
+        ));
+    }
+
+    #[test]
+    fn test_parse_variable_length_pattern() {
+        let query = parse_simple("MATCH p = (a:Person)-[:KNOWS*1..3]->(b) RETURN length(p)").unwrap();
+
+        let Clause::Match(m) = &query.clauses[0] else {
+            panic!("Expected MATCH clause");
+        };
+        let pattern = &m.patterns[0];
+        assert_eq!(pattern.variable.as_deref(), Some("p"));
+        assert_eq!(pattern.elements.len(), 3);
+
+        let PatternElement::Relationship(rel) = &pattern.elements[1] else {
+            panic!("Expected relationship pattern");
+        };
+        assert_eq!(rel.rel_types, vec!["KNOWS".to_string()]);
+        assert_eq!(rel.direction, RelationshipDirection::Outgoing);
+        assert_eq!(rel.length, Some(RelationshipLength { min: Some(1), max: Some(3) }));
+    }
+
+    #[test]
+    fn test_parse_relationship_lengths_and_directions() {
+        let rel = |cypher: &str| {
+            let query = parse_simple(cypher).unwrap();
+            let Clause::Match(m) = &query.clauses[0] else {
+                panic!("Expected MATCH clause");
+            };
+            match &m.patterns[0].elements[1] {
+                PatternElement::Relationship(rel) => rel.clone(),
+                _ => panic!("Expected relationship pattern"),
+            }
+        };
+
+        assert_eq!(rel("MATCH (a)-[*]->(b) RETURN b").length, Some(RelationshipLength { min: None, max: None }));
+        assert_eq!(rel("MATCH (a)-[*2]->(b) RETURN b").length, Some(RelationshipLength { min: Some(2), max: Some(2) }));
+        assert_eq!(rel("MATCH (a)-[*..4]->(b) RETURN b").length, Some(RelationshipLength { min: None, max: Some(4) }));
+        assert_eq!(rel("MATCH (a)<-[r:A|B]-(b) RETURN b").direction, RelationshipDirection::Incoming);
+        assert_eq!(rel("MATCH (a)--(b) RETURN b").direction, RelationshipDirection::Both);
+        assert_eq!(rel("MATCH (a)-->(b) RETURN b").length, None);
+    }
+
+    #[test]
+    fn test_parse_shortest_path_patterns() {
+        let pattern = |cypher: &str| {
+            let query = parse_simple(cypher).unwrap();
+            let Clause::Match(m) = &query.clauses[0] else {
+                panic!("Expected MATCH clause");
+            };
+            m.patterns[0].clone()
+        };
+
+        let shortest = pattern("MATCH p = shortestPath((a:Start)-[:ROAD*]->(b:End)) RETURN length(p)");
+        assert_eq!(shortest.variable.as_deref(), Some("p"));
+        assert_eq!(shortest.selector, Some(PathSelector::Shortest));
+        assert_eq!(shortest.elements.len(), 3);
+
+        let all = pattern("MATCH p = allShortestPaths((a)-[*]-(b)) RETURN p");
+        assert_eq!(all.selector, Some(PathSelector::AllShortest));
+        assert_eq!(pattern("MATCH (a)-->(b) RETURN b").selector, None);
+
+        assert!(parse_simple("MATCH p = shortestPath((a)-[*]->(b) RETURN p").is_err());
+    }
+
+    #[test]
+    fn test_parse_call_clause() {
+        let query = parse_simple("CALL algo.pagerank(0.85, 20, 1e-6) YIELD nodeId, score RETURN nodeId, score").unwrap();
+
+        let Clause::Call(call) = &query.clauses[0] else {
+            panic!("Expected CALL clause");
+        };
+        assert_eq!(call.procedure, "algo.pagerank");
+        assert_eq!(
+            call.args,
+            vec![
+                Expression::Literal(Literal::Float(0.85)),
+                Expression::Literal(Literal::Integer(20)),
+                Expression::Literal(Literal::Float(1e-6)),
+            ]
+        );
+        assert_eq!(call.yield_items, vec!["nodeId".to_string(), "score".to_string()]);
+        assert!(matches!(query.clauses[1], Clause::Return(_)));
+
+        let query = parse_simple("CALL algo.pagerank()").unwrap();
+        assert_eq!(query.clauses.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_unwind_clause() {
+        let query = parse_simple("UNWIND $pairs AS pair MATCH (a:Person {id: pair.a}) RETURN a").unwrap();
+        assert!(query.is_read_only());
+        assert_eq!(
+            query.clauses[0],
+            Clause::Unwind(UnwindClause {
+                expression: Expression::Parameter("pairs".to_string()),
+                variable: "pair".to_string(),
+            })
+        );
+        let Clause::Match(m) = &query.clauses[1] else {
+            panic!("Expected MATCH clause");
+        };
+        let [PatternElement::Node(node)] = m.patterns[0].elements.as_slice() else {
+            panic!("Expected a single node pattern");
+        };
+        assert_eq!(
+            node.properties.as_ref().unwrap().entries[0].1,
+            Expression::Property(Box::new(Expression::Variable("pair".to_string())), "a".to_string())
+        );
+
+        let query = parse_simple("MATCH (n) WHERE n.age > 1 UNWIND n.tags AS tag RETURN tag").unwrap();
+        assert!(matches!(&query.clauses[2], Clause::Unwind(unwind) if unwind.variable == "tag"));
+
+        let query = parse_simple("UNWIND [1, 2] AS x UNWIND [3, 4] AS y RETURN x, y").unwrap();
+        assert_eq!(query.clauses.len(), 3);
+
+        assert!(parse_simple("UNWIND [1, 2] x RETURN x").is_err());
+    }
+
+    #[test]
+    fn test_parse_nested_access() {
+        let query = parse_simple("MATCH (p) RETURN p.address.city, p.tags[0], $m['k'][-1]").unwrap();
+        let Clause::Return(ret) = &query.clauses[1] else {
+            panic!("Expected RETURN clause");
+        };
+        let p = || Box::new(Expression::Variable("p".to_string()));
+        let expected = [
+            Expression::Property(Box::new(Expression::Property(p(), "address".to_string())), "city".to_string()),
+            Expression::Index(
+                Box::new(Expression::Property(p(), "tags".to_string())),
+                Box::new(Expression::Literal(Literal::Integer(0))),
+            ),
+            Expression::Index(
+                Box::new(Expression::Index(
+                    Box::new(Expression::Parameter("m".to_string())),
+                    Box::new(Expression::Literal(Literal::String("k".to_string()))),
+                )),
+                Box::new(Expression::Literal(Literal::Integer(-1))),
+            ),
+        ];
+        let parsed: Vec<_> = ret.items.iter().map(|item| item.expression.clone()).collect();
+        assert_eq!(parsed, expected);
+
+        assert!(parse_simple("MATCH (p) RETURN p.tags[0").is_err());
+
+        let query = parse_simple("MATCH (n) RETURN duration.between(n.a, n.b), n.duration").unwrap();
+        let Clause::Return(ret) = &query.clauses[1] else {
+            panic!("Expected RETURN clause");
+        };
+        assert!(matches!(&ret.items[0].expression, Expression::Function { name, args, .. }
+            if name == "duration.between" && args.len() == 2));
+        assert!(matches!(&ret.items[1].expression, Expression::Property(_, property) if property == "duration"));
+
+        // A minus after an operand still subtracts
+        let query = parse_simple("MATCH (p) RETURN p.age -1").unwrap();
+        let Clause::Return(ret) = &query.clauses[1] else {
+            panic!("Expected RETURN clause");
+        };
+        assert!(matches!(ret.items[0].expression, Expression::Binary { op: BinaryOp::Subtract, .. }));
+    }
+
+    #[test]
+    fn test_parse_merge_clause() {
+        let query = parse_simple(
+            "MERGE (p:Person {email: $email, name: 'Alice'}) \
+             ON CREATE SET p.visits = 1, p:New ON MATCH SET p.visits = p.visits + 1 \
+             RETURN p.visits AS visits",
+        )
+        .unwrap();
+        assert!(!query.is_read_only());
+
+        let Clause::Merge(merge) = &query.clauses[0] else {
+            panic!("Expected MERGE clause");
+        };
+        let [PatternElement::Node(node)] = merge.pattern.elements.as_slice() else {
+            panic!("Expected a single node pattern");
+        };
+        assert_eq!(node.labels, vec!["Person"]);
+        let entries = &node.properties.as_ref().unwrap().entries;
+        assert_eq!(entries[0], ("email".to_string(), Expression::Parameter("email".to_string())));
+        assert_eq!(entries[1], ("name".to_string(), Expression::Literal(Literal::String("Alice".to_string()))));
+
+        assert_eq!(merge.on_create.len(), 2);
+        assert_eq!(
+            merge.on_create[1],
+            SetItem::Labels { variable: "p".to_string(), labels: vec!["New".to_string()] }
+        );
+        let [SetItem::Property { entity, property, value }] = merge.on_match.as_slice() else {
+            panic!("Expected a single ON MATCH item");
+        };
+        assert_eq!((entity.as_str(), property.as_str()), ("p", "visits"));
+        assert!(matches!(value, Expression::Binary { op: BinaryOp::Add, .. }));
+        assert!(matches!(query.clauses[1], Clause::Return(_)));
+
+        assert!(parse_simple("MERGE (p:Person) ON DELETE SET p.x = 1").is_err());
+        assert!(parse_simple("MERGE (p:Person) ON CREATE p.x = 1").is_err());
+        assert!(parse_simple("MERGE (p:Person {email: })").is_err());
+    }
+
+    #[test]
+    fn test_parse_set_and_remove_clauses() {
+        let query = parse_simple(
+            "MATCH (n:Person) WHERE n.name = 'Alice' \
+             SET n.age = 31, n:Admin:Staff SET n += {city: 'Paris', zip: null} \
+             REMOVE n.nickname, n:Guest SET n = {name: 'Alicia', active: true} \
+             RETURN n.age AS age",
+        )
+        .unwrap();
+        assert!(!query.is_read_only());
+
+        let Clause::Set(set) = &query.clauses[2] else {
+            panic!("Expected SET clause");
+        };
+        assert_eq!(
+            set.items[0],
+            SetItem::Property {
+                entity: "n".to_string(),
+                property: "age".to_string(),
+                value: Expression::Literal(Literal::Integer(31)),
+            }
+        );
+        assert_eq!(
+            set.items[1],
+            SetItem::Labels { variable: "n".to_string(), labels: vec!["Admin".to_string(), "Staff".to_string()] }
+        );
+
+        let Clause::Set(merge) = &query.clauses[3] else {
+            panic!("Expected SET clause");
+        };
+        let [SetItem::MergeProperties { variable, value: Expression::Map(map) }] = merge.items.as_slice() else {
+            panic!("Expected a single += item");
+        };
+        assert_eq!(variable, "n");
+        assert_eq!(map.entries[1], ("zip".to_string(), Expression::Literal(Literal::Null)));
+
+        let Clause::Remove(remove) = &query.clauses[4] else {
+            panic!("Expected REMOVE clause");
+        };
+        assert_eq!(
+            remove.items,
+            vec![
+                RemoveItem::Property { entity: "n".to_string(), property: "nickname".to_string() },
+                RemoveItem::Labels { variable: "n".to_string(), labels: vec!["Guest".to_string()] },
+            ]
+        );
+
+        let Clause::Set(replace) = &query.clauses[5] else {
+            panic!("Expected SET clause");
+        };
+        let [SetItem::AllProperties { value: Expression::Map(map), .. }] = replace.items.as_slice() else {
+            panic!("Expected a single = item");
+        };
+        assert_eq!(map.entries[1], ("active".to_string(), Expression::Literal(Literal::Boolean(true))));
+        assert!(matches!(query.clauses[6], Clause::Return(_)));
+
+        assert!(parse_simple("MATCH (n) SET n").is_err());
+        assert!(parse_simple("MATCH (n) REMOVE n = 1").is_err());
+    }
+
+    #[test]
+    fn test_parse_delete_clauses() {
+        let query = parse_simple("MATCH (a)-[r:KNOWS]->(b) DELETE r, b").unwrap();
+        assert!(!query.is_read_only());
+        assert_eq!(
+            query.clauses[1],
+            Clause::Delete(DeleteClause {
+                detach: false,
+                expressions: vec![Expression::Variable("r".to_string()), Expression::Variable("b".to_string())],
+            })
+        );
+
+        let query = parse_simple("MATCH (n:Person) WHERE n.age > 60 DETACH DELETE n RETURN count(n) AS c").unwrap();
+        assert!(matches!(&query.clauses[2], Clause::Delete(DeleteClause { detach: true, .. })));
+        assert!(matches!(query.clauses[3], Clause::Return(_)));
+
+        assert!(parse_simple("MATCH (n) DETACH n").is_err());
+    }
+
+    #[test]
+    fn test_parse_index_statements() {
+        let query = parse_simple("CREATE INDEX FOR (p:Person) ON (p.email)").unwrap();
+        assert!(!query.is_read_only());
+        let Clause::CreateIndex(create) = &query.clauses[0] else {
+            panic!("Expected CREATE INDEX");
+        };
+        assert_eq!(create.name, None);
+        assert_eq!(create.index_name(), "person_email");
+        assert!(!create.if_not_exists);
+
+        let query = parse_simple(
+            "create index person_name if not exists for (p:Person) on (p.lastName, p.firstName)",
+        )
+        .unwrap();
+        assert_eq!(
+            query.clauses,
+            vec![Clause::CreateIndex(CreateIndexClause {
+                name: Some("person_name".to_string()),
+                label: "Person".to_string(),
+                properties: vec!["lastName".to_string(), "firstName".to_string()],
+                if_not_exists: true,
+            })]
+        );
+
+        let query = parse_simple("CREATE INDEX ON :User(email)").unwrap();
+        let Clause::CreateIndex(create) = &query.clauses[0] else {
+            panic!("Expected CREATE INDEX");
+        };
+        assert_eq!((create.label.as_str(), create.properties.as_slice()), ("User", &["email".to_string()][..]));
+
+        let query = parse_simple("DROP INDEX person_email IF EXISTS").unwrap();
+        assert_eq!(
+            query.clauses,
+            vec![Clause::DropIndex(DropIndexClause { name: "person_email".to_string(), if_exists: true })]
+        );
+
+        assert!(parse_simple("CREATE INDEX FOR (p:Person) ON (q.email)").is_err());
+        assert!(parse_simple("CREATE INDEX FOR (p) ON (p.email)").is_err());
+        assert!(parse_simple("CREATE INDEX FOR (p:Person) ON (p.email) RETURN p").is_err());
+        assert!(parse_simple("DROP person_email").is_err());
+    }
+
+    #[test]
+    fn test_parse_boolean_where() {
+        let query = parse_simple("MATCH (p:Person) WHERE p.age >= 18 AND p.age < 65 OR p.age IN [1, 2] RETURN p").unwrap();
+        let Clause::Where(Expression::Binary { left, op: BinaryOp::Or, right }) = &query.clauses[1] else {
+            panic!("expected OR at the top, got {:?}", query.clauses[1]);
+        };
+        assert!(matches!(left.as_ref(), Expression::Binary { op: BinaryOp::And, .. }));
+        let Expression::Binary { op: BinaryOp::In, right: list, .. } = right.as_ref() else {
+            panic!("expected IN, got {:?}", right);
+        };
+        assert_eq!(
+            list.as_ref(),
+            &Expression::List(vec![Expression::Literal(Literal::Integer(1)), Expression::Literal(Literal::Integer(2))])
+        );
+
+        assert!(parse_simple("MATCH (p) WHERE p.age IN [1, 2 RETURN p").is_err());
+    }
+
+    #[test]
+    fn test_parse_explain_and_profile() {
+        assert_eq!(parse_simple("MATCH (p) RETURN p").unwrap().mode, QueryMode::Execute);
+        let explain = parse_simple("EXPLAIN MATCH (p) RETURN p").unwrap();
+        assert_eq!(explain.mode, QueryMode::Explain);
+        assert_eq!(explain.clauses, parse_simple("MATCH (p) RETURN p").unwrap().clauses);
+        assert_eq!(parse_simple("profile MATCH (p) RETURN p").unwrap().mode, QueryMode::Profile);
+    }
+
+    #[test]
+    fn test_parse_string_predicates() {
+        let operator = |query: &str| match &parse_simple(query).unwrap().clauses[1] {
+            Clause::Where(Expression::Binary { op, .. }) => *op,
+            other => panic!("expected a WHERE comparison, got {:?}", other),
+        };
+
+        assert_eq!(operator("MATCH (p:Person) WHERE p.name STARTS WITH 'Al' RETURN p"), BinaryOp::StartsWith);
+        assert_eq!(operator("MATCH (p:Person) WHERE p.name ends with $suffix RETURN p"), BinaryOp::EndsWith);
+        assert_eq!(operator("MATCH (p:Person) WHERE p.name CONTAINS 'li' RETURN p"), BinaryOp::Contains);
+        assert_eq!(operator("MATCH (p:Person) WHERE p.name =~ 'A.*' RETURN p"), BinaryOp::RegexMatch);
+        assert_eq!(operator("MATCH (p:Person) WHERE p.name = 'Alice' RETURN p"), BinaryOp::Equals);
+
+        assert!(parse_simple("MATCH (p:Person) WHERE p.name STARTS 'Al' RETURN p").is_err());
+    }
+
+    #[test]
+    fn test_nesting_depth_limit() {
+        let nested = |depth: usize| {
+            format!("MATCH (n) RETURN {}n{}", "f(".repeat(depth), ")".repeat(depth))
+        };
+
+        assert!(parse_simple(&nested(DEFAULT_MAX_PARSE_DEPTH - 1)).is_ok());
+
+        let err = parse_simple(&nested(3_000)).unwrap_err();
+        assert!(matches!(&err, Error::QueryParse(msg) if msg.contains("maximum depth")));
+
+        let limits = ParserLimits { max_depth: 3, ..Default::default() };
+        assert!(parse_simple_with_limits(&nested(2), limits).is_ok());
+        assert!(parse_simple_with_limits(&nested(3), limits).is_err());
+    }
+
+    #[test]
+    fn test_token_limit() {
+        let limits = ParserLimits { max_tokens: 20, ..Default::default() };
+        let chain = format!("MATCH (a){} RETURN a", "-->(b)".repeat(10));
+        let err = parse_simple_with_limits(&chain, limits).unwrap_err();
+        assert!(matches!(&err, Error::QueryParse(msg) if msg.contains("maximum of 20 tokens")));
+
+        assert!(parse_simple_with_limits("MATCH (a)-->(b) RETURN a", limits).is_ok());
+    }
+
+    #[test]
+    fn test_pathological_input_fails_gracefully() {
+        let inputs = [
+            format!("MATCH {}n{} RETURN n", "(".repeat(5_000), ")".repeat(5_000)),
+            format!("MATCH (n) RETURN {}", "(".repeat(5_000)),
+            format!("MATCH (a){}", "-[".repeat(5_000)),
+            "MATCH (n:Pérson) WHERE n.näme = 'Zoë' RETURN n.näme".to_string(),
+            "MATCH (n) RETURN ñ(".to_string(),
+            "MATCH (n) WHERE n.name = 'unterminated".to_string(),
+            "\u{0}\u{ffff}(((".to_string(),
+        ];
+
+        for input in &inputs {
+            // Errors are fine, panics and stack overflows are not
+            let _ = parse_simple(input);
+        }
+        assert!(parse_simple("MATCH (n:Pérson) RETURN n.näme").is_ok());
+    }
+
+    #[test]
+    fn test_parse_arithmetic() {
+        let expression = |cypher: &str| {
+            let query = parse_simple(cypher).unwrap();
+            let Clause::Return(ret) = &query.clauses.last().unwrap() else {
+                panic!("Expected RETURN clause");
+            };
+            ret.items[0].expression.clone()
+        };
+        let binary = |left: Expression, op, right: Expression| Expression::Binary {
+            left: Box::new(left),
+            op,
+            right: Box::new(right),
+        };
+        let int = |i| Expression::Literal(Literal::Integer(i));
+
+        // * binds tighter than +, and operators of equal precedence are left-associative
+        assert_eq!(
+            expression("RETURN 1 + 2 * 3 - 4"),
+            binary(binary(int(1), BinaryOp::Add, binary(int(2), BinaryOp::Multiply, int(3))), BinaryOp::Subtract, int(4))
+        );
+        assert_eq!(
+            expression("RETURN (1 + 2) % 3 ^ 2"),
+            binary(binary(int(1), BinaryOp::Add, int(2)), BinaryOp::Modulo, binary(int(3), BinaryOp::Power, int(2)))
+        );
+
+        let query = parse_simple("MATCH (n) WHERE n.count * 1000000 > $max RETURN n.count / 2 AS half").unwrap();
+        let Clause::Where(Expression::Binary { left, op, .. }) = &query.clauses[1] else {
+            panic!("Expected WHERE clause");
+        };
+        assert_eq!(*op, BinaryOp::GreaterThan);
+        assert!(matches!(left.as_ref(), Expression::Binary { op: BinaryOp::Multiply, .. }));
+
+        assert!(parse_simple("RETURN (1 + 2").is_err());
+        let nested = format!("RETURN {}1{}", "(".repeat(100), ")".repeat(100));
+        assert!(parse_simple(&nested).is_err());
+    }
 }
@@ -0,0 +1,294 @@
+//! Arrow IPC export of query results
+//!
+//! Encodes a materialized result set as an Arrow IPC stream so data-science
+//! clients (pandas, polars, pyarrow) can load it without JSON parsing.
+//!
+//! Each column is typed from the values it holds:
+//! - only integers: `Int64`
+//! - integers and floats: `Float64`
+//! - only strings: `Utf8`
+//! - only booleans: `Boolean`
+//! - only nulls: `Null`
+//! - anything else (lists, maps, temporal values, mixed types): `Utf8`
+//!   holding the JSON encoding of each value
+//!
+//! Nulls are preserved as Arrow nulls for every column type.
+
+use crate::http_server::property_value_to_json;
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int64Array, NullArray, RecordBatch, StringArray,
+};
+use arrow_ipc::writer::StreamWriter;
+use arrow_schema::{Field, Schema};
+use qilbee_core::{Error, PropertyValue, Result};
+use std::sync::Arc;
+
+/// Media type of Arrow IPC streams
+pub const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+
+/// Encode query results as an Arrow IPC stream with a single record batch
+pub fn query_result_to_arrow_ipc(
+    columns: &[String],
+    rows: &[Vec<PropertyValue>],
+) -> Result<Vec<u8>> {
+    let arrays: Vec<ArrayRef> = (0..columns.len())
+        .map(|i| column_to_array(rows.iter().map(|row| &row[i])))
+        .collect();
+
+    let fields: Vec<Field> = columns
+        .iter()
+        .zip(&arrays)
+        .map(|(name, array)| Field::new(name, array.data_type().clone(), true))
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+
+    let batch = RecordBatch::try_new(schema.clone(), arrays).map_err(arrow_error)?;
+
+    let mut buf = Vec::new();
+    let mut writer = StreamWriter::try_new(&mut buf, &schema).map_err(arrow_error)?;
+    writer.write(&batch).map_err(arrow_error)?;
+    writer.finish().map_err(arrow_error)?;
+    drop(writer);
+
+    Ok(buf)
+}
+
+/// Arrow representation chosen for a result column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnKind {
+    Null,
+    Integer,
+    Float,
+    String,
+    Boolean,
+    Json,
+}
+
+/// Pick the representation for a column from the values it holds
+fn column_kind<'a>(values: impl Iterator<Item = &'a PropertyValue>) -> ColumnKind {
+    let mut kind = ColumnKind::Null;
+
+    for value in values {
+        let value_kind = match value {
+            PropertyValue::Null => continue,
+            PropertyValue::Integer(_) => ColumnKind::Integer,
+            PropertyValue::Float(_) => ColumnKind::Float,
+            PropertyValue::String(_) => ColumnKind::String,
+            PropertyValue::Boolean(_) => ColumnKind::Boolean,
+            _ => return ColumnKind::Json,
+        };
+
+        kind = match (kind, value_kind) {
+            (ColumnKind::Null, k) => k,
+            (a, b) if a == b => a,
+            (ColumnKind::Integer, ColumnKind::Float) | (ColumnKind::Float, ColumnKind::Integer) => {
+                ColumnKind::Float
+            }
+            _ => return ColumnKind::Json,
+        };
+    }
+
+    kind
+}
+
+fn column_to_array<'a>(values: impl Iterator<Item = &'a PropertyValue> + Clone) -> ArrayRef {
+    match column_kind(values.clone()) {
+        ColumnKind::Null => Arc::new(NullArray::new(values.count())),
+        ColumnKind::Integer => Arc::new(
+            values
+                .map(|v| match v {
+                    PropertyValue::Integer(i) => Some(*i),
+                    _ => None,
+                })
+                .collect::<Int64Array>(),
+        ),
+        ColumnKind::Float => Arc::new(
+            values
+                .map(|v| match v {
+                    PropertyValue::Float(f) => Some(*f),
+                    PropertyValue::Integer(i) => Some(*i as f64),
+                    _ => None,
+                })
+                .collect::<Float64Array>(),
+        ),
+        ColumnKind::Boolean => Arc::new(
+            values
+                .map(|v| match v {
+                    PropertyValue::Boolean(b) => Some(*b),
+                    _ => None,
+                })
+                .collect::<BooleanArray>(),
+        ),
+        ColumnKind::String => Arc::new(
+            values
+                .map(|v| match v {
+                    PropertyValue::String(s) => Some(s.as_str()),
+                    _ => None,
+                })
+                .collect::<StringArray>(),
+        ),
+        // Complex or mixed columns fall back to one JSON document per value
+        ColumnKind::Json => Arc::new(
+            values
+                .map(|v| match v {
+                    PropertyValue::Null => None,
+                    v => Some(property_value_to_json(v).to_string()),
+                })
+                .collect::<StringArray>(),
+        ),
+    }
+}
+
+fn arrow_error(e: arrow_schema::ArrowError) -> Error {
+    Error::Serialization(format!("Arrow encoding failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow_array::Array;
+    use arrow_ipc::reader::StreamReader;
+    use arrow_schema::DataType;
+    use std::collections::HashMap;
+
+    fn decode(bytes: &[u8]) -> Vec<RecordBatch> {
+        StreamReader::try_new(bytes, None)
+            .unwrap()
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_arrow_ipc_round_trip() {
+        let columns: Vec<String> = ["name", "age", "score", "active", "tags", "missing"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+        let mut map = HashMap::new();
+        map.insert("k".to_string(), PropertyValue::Integer(1));
+        let rows = vec![
+            vec![
+                PropertyValue::String("Alice".to_string()),
+                PropertyValue::Integer(30),
+                PropertyValue::Float(1.5),
+                PropertyValue::Boolean(true),
+                PropertyValue::Array(vec![PropertyValue::String("a".to_string())]),
+                PropertyValue::Null,
+            ],
+            vec![
+                PropertyValue::String("Bob".to_string()),
+                PropertyValue::Null,
+                PropertyValue::Integer(2),
+                PropertyValue::Boolean(false),
+                PropertyValue::Map(map),
+                PropertyValue::Null,
+            ],
+        ];
+
+        let batches = decode(&query_result_to_arrow_ipc(&columns, &rows).unwrap());
+        assert_eq!(batches.len(), 1);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), 2);
+
+        let schema = batch.schema();
+        let names: Vec<&str> = schema.fields().iter().map(|f| f.name().as_str()).collect();
+        assert_eq!(
+            names,
+            vec!["name", "age", "score", "active", "tags", "missing"]
+        );
+
+        let name = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!((name.value(0), name.value(1)), ("Alice", "Bob"));
+
+        let age = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(age.value(0), 30);
+        assert!(age.is_null(1));
+
+        // Mixed integers and floats widen to Float64
+        let score = batch
+            .column(2)
+            .as_any()
+            .downcast_ref::<Float64Array>()
+            .unwrap();
+        assert_eq!((score.value(0), score.value(1)), (1.5, 2.0));
+
+        let active = batch
+            .column(3)
+            .as_any()
+            .downcast_ref::<BooleanArray>()
+            .unwrap();
+        assert!(active.value(0) && !active.value(1));
+
+        // Complex values are JSON-encoded strings
+        let tags = batch
+            .column(4)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        assert_eq!(tags.value(0), r#"["a"]"#);
+        assert_eq!(tags.value(1), r#"{"k":1}"#);
+
+        assert_eq!(batch.column(5).data_type(), &DataType::Null);
+    }
+
+    #[test]
+    fn test_arrow_ipc_empty_result() {
+        let columns = vec!["n".to_string()];
+        let batches = decode(&query_result_to_arrow_ipc(&columns, &[]).unwrap());
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].num_rows(), 0);
+        assert_eq!(batches[0].schema().field(0).name(), "n");
+    }
+
+    #[test]
+    fn test_arrow_ipc_from_query() {
+        use qilbee_graph::Database;
+        use qilbee_query::{QueryExecutor, QueryPlanner, parse_simple};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        for (name, age) in [("Alice", 30i64), ("Bob", 25)] {
+            let mut props = qilbee_core::Property::new();
+            props.set("name", name);
+            props.set("age", age);
+            graph
+                .create_node_with_properties(["Person"], props)
+                .unwrap();
+        }
+
+        let query =
+            parse_simple("MATCH (p:Person) RETURN p.name AS name, p.age AS age ORDER BY p.age")
+                .unwrap();
+        let plan = QueryPlanner::new().plan(&query).unwrap();
+        let result = QueryExecutor::new(Arc::new(graph))
+            .execute(&plan, &HashMap::new())
+            .unwrap();
+
+        let batches = decode(&query_result_to_arrow_ipc(&result.columns, &result.rows).unwrap());
+        let batch = &batches[0];
+        assert_eq!(batch.schema().field(0).name(), "name");
+        assert_eq!(batch.schema().field(1).data_type(), &DataType::Int64);
+
+        let names = batch
+            .column(0)
+            .as_any()
+            .downcast_ref::<StringArray>()
+            .unwrap();
+        let ages = batch
+            .column(1)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!((names.value(0), ages.value(0)), ("Bob", 25));
+        assert_eq!((names.value(1), ages.value(1)), ("Alice", 30));
+    }
+}
@@ -0,0 +1,444 @@
+//! Bolt query execution
+//!
+//! Runs Bolt `RUN` requests through the streaming executor. The executor's
+//! result chunks back the session's open result, so `PULL` hands out records
+//! batch by batch.
+//!
+//! Clients authenticate in `HELLO` with the `bearer` scheme, passing a JWT
+//! from `/api/v1/auth/login` or an API key as the credentials. Each `RUN`
+//! then needs `GraphRead`, or `GraphUpdate` for queries that write.
+
+use crate::security::{AuthService, Permission, User};
+use qilbee_core::{Error, PropertyValue, Result};
+use qilbee_graph::Database;
+use qilbee_protocol::bolt::{BoltResult, QueryRunner, serve_connection};
+use qilbee_query::{
+    DEFAULT_STREAM_CHUNK_SIZE, ExecutionStats, ParserLimits, QueryExecutor, QueryPlanner,
+    parse_simple_with_limits,
+};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tracing::debug;
+
+/// Runs Bolt queries against a database
+///
+/// Queries target the graph named by the `db` entry of the `RUN` metadata,
+/// or the database's default graph.
+#[derive(Clone)]
+pub struct DatabaseQueryRunner {
+    database: Arc<Database>,
+    /// Validates the credentials sent in `HELLO`
+    auth_service: Arc<AuthService>,
+    /// User authenticated by `HELLO`, whose roles gate each query
+    user: Option<User>,
+    /// Row cap for read queries without a LIMIT
+    default_limit: Option<usize>,
+    /// Nesting depth and size limits for parsing queries
+    parser_limits: ParserLimits,
+}
+
+impl DatabaseQueryRunner {
+    /// Create a runner for a database
+    pub fn new(database: Arc<Database>, auth_service: Arc<AuthService>) -> Self {
+        Self {
+            database,
+            auth_service,
+            user: None,
+            default_limit: None,
+            parser_limits: ParserLimits::default(),
+        }
+    }
+
+    /// Builder: cap read queries without a LIMIT at `limit` rows
+    pub fn with_default_limit(mut self, limit: usize) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    /// Builder: set the nesting depth and size limits for parsing queries
+    pub fn with_parser_limits(mut self, limits: ParserLimits) -> Self {
+        self.parser_limits = limits;
+        self
+    }
+}
+
+impl QueryRunner for DatabaseQueryRunner {
+    fn authenticate(&mut self, hello: &HashMap<String, PropertyValue>) -> Result<()> {
+        let credentials = match (
+            hello.get("scheme").and_then(|scheme| scheme.as_str()),
+            hello.get("credentials").and_then(|credentials| credentials.as_str()),
+        ) {
+            (Some("bearer"), Some(credentials)) => credentials,
+            _ => {
+                return Err(Error::AuthenticationFailed(
+                    "Expected a bearer token or API key".to_string(),
+                ));
+            }
+        };
+
+        let user = self
+            .auth_service
+            .validate_token(credentials)
+            .or_else(|_| self.auth_service.validate_api_key(credentials))
+            .map_err(|_| Error::AuthenticationFailed("Invalid credentials".to_string()))?;
+        debug!("Bolt client authenticated as {}", user.username);
+        self.user = Some(user);
+        Ok(())
+    }
+
+    fn run(
+        &self,
+        query: &str,
+        parameters: &HashMap<String, PropertyValue>,
+        metadata: &HashMap<String, PropertyValue>,
+    ) -> Result<BoltResult> {
+        let graph = match metadata.get("db").and_then(|db| db.as_str()) {
+            Some(name) => self.database.graph(name)?,
+            None => self.database.default_graph()?,
+        };
+
+        let user = self
+            .user
+            .as_ref()
+            .ok_or_else(|| Error::AuthenticationFailed("Not authenticated".to_string()))?;
+        let query = parse_simple_with_limits(query, self.parser_limits)?;
+        let permission = Permission::for_query(query.is_read_only());
+        if !user.has_permission(&permission) {
+            return Err(Error::Unauthorized(format!("Required permission: {:?}", permission)));
+        }
+        let mut planner = QueryPlanner::new()
+            .with_graph_schema(&graph)?
+            .with_graph_statistics(&graph, &query)?;
+        if let Some(limit) = self.default_limit {
+            planner = planner.with_default_limit(limit);
+        }
+        let plan = planner.plan(&query)?;
+        let stream = QueryExecutor::new(Arc::new(graph)).execute_stream(
+            &plan,
+            parameters,
+            DEFAULT_STREAM_CHUNK_SIZE,
+        )?;
+
+        let fields = stream.columns().to_vec();
+        let summary = result_summary(stream.stats());
+        Ok(BoltResult::new(fields, Box::new(stream), summary))
+    }
+}
+
+/// Serve Bolt connections accepted by `listener`
+///
+/// Each connection gets its own session over a copy of `runner`. Returns
+/// only when accepting a connection fails.
+pub async fn serve(listener: TcpListener, runner: DatabaseQueryRunner) -> Result<()> {
+    loop {
+        let (stream, peer) = listener.accept().await.map_err(Error::Io)?;
+        let runner = runner.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, runner).await {
+                debug!("Bolt connection from {} closed: {}", peer, e);
+            }
+        });
+    }
+}
+
+/// Metadata of the `SUCCESS` that completes a result
+///
+/// Follows the Bolt summary layout: the query type (`r` or `w`), the
+/// non-zero update counters under `stats`, and `t_last` in milliseconds.
+fn result_summary(stats: &ExecutionStats) -> HashMap<String, PropertyValue> {
+    let counters = [
+        ("nodes-created", stats.nodes_created),
+        ("nodes-deleted", stats.nodes_deleted),
+        ("relationships-created", stats.relationships_created),
+        ("relationships-deleted", stats.relationships_deleted),
+        ("properties-set", stats.properties_set),
+        ("indexes-added", stats.indexes_added),
+        ("indexes-removed", stats.indexes_removed),
+    ];
+    let updates: HashMap<String, PropertyValue> = counters
+        .iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(name, count)| (name.to_string(), PropertyValue::Integer(*count as i64)))
+        .collect();
+
+    let query_type = if updates.is_empty() { "r" } else { "w" };
+
+    let mut summary = HashMap::new();
+    summary.insert("type".to_string(), PropertyValue::String(query_type.to_string()));
+    summary.insert("t_last".to_string(), PropertyValue::Integer(stats.execution_time_ms as i64));
+    if !updates.is_empty() {
+        summary.insert("stats".to_string(), PropertyValue::Map(updates));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{
+        AuthConfig, BlacklistConfig, Credentials, Role, TokenBlacklist, TokenService, UserService,
+    };
+    use qilbee_core::Property;
+    use qilbee_protocol::bolt::{ALL_RECORDS, BoltMessage, BoltSession, BoltState, read_message, write_message};
+    use tempfile::TempDir;
+
+    const PASSWORD: &str = "SecureP@ss123!";
+
+    fn create_auth_service() -> Arc<AuthService> {
+        create_auth_service_with_api_key().0
+    }
+
+    /// Auth service with the read-only user `alice`, and an API key of hers
+    fn create_auth_service_with_api_key() -> (Arc<AuthService>, String) {
+        create_auth_service_as(Role::Read)
+    }
+
+    /// Auth service with the user `alice` holding `role`, and an API key of hers
+    fn create_auth_service_as(role: Role) -> (Arc<AuthService>, String) {
+        let user_service = Arc::new(UserService::new());
+        let mut user = user_service
+            .create_user("alice".to_string(), "alice@example.com".to_string(), PASSWORD)
+            .unwrap();
+        user.add_role(role);
+        user_service.update_user(user.clone()).unwrap();
+        let token_service = Arc::new(TokenService::new("test_secret".to_string()));
+        let (api_key, _) = token_service.generate_api_key(user.id, "bolt".to_string(), None).unwrap();
+        let auth_service = Arc::new(AuthService::new(
+            user_service,
+            token_service,
+            Arc::new(TokenBlacklist::new(BlacklistConfig::default())),
+            AuthConfig::default(),
+        ));
+        (auth_service, api_key)
+    }
+
+    fn access_token(auth_service: &AuthService) -> String {
+        let credentials = Credentials {
+            username: "alice".to_string(),
+            password: PASSWORD.to_string(),
+        };
+        auth_service.login(credentials).unwrap().1.access_token
+    }
+
+    fn hello(scheme: &str, credentials: &str) -> BoltMessage {
+        let mut metadata = HashMap::new();
+        metadata.insert("scheme".to_string(), PropertyValue::String(scheme.to_string()));
+        metadata.insert("credentials".to_string(), PropertyValue::String(credentials.to_string()));
+        BoltMessage::Hello { metadata }
+    }
+
+    /// Session over `runner` that has sent a valid `HELLO`
+    fn authenticated(runner: DatabaseQueryRunner) -> BoltSession<DatabaseQueryRunner> {
+        let token = access_token(&runner.auth_service);
+        let mut session = BoltSession::new(runner);
+        assert!(matches!(session.handle(hello("bearer", &token)).as_slice(), [BoltMessage::Success { .. }]));
+        session
+    }
+
+    fn create_test_session(people: usize) -> (BoltSession<DatabaseQueryRunner>, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("social").unwrap();
+        for i in 0..people {
+            let mut props = Property::new();
+            props.set("name", format!("Person{}", i));
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        let runner = DatabaseQueryRunner::new(Arc::new(db), create_auth_service());
+        (authenticated(runner), temp_dir)
+    }
+
+    fn run(cypher: &str, db: &str) -> BoltMessage {
+        let mut metadata = HashMap::new();
+        metadata.insert("db".to_string(), PropertyValue::String(db.to_string()));
+        BoltMessage::Run {
+            query: cypher.to_string(),
+            parameters: HashMap::new(),
+            metadata,
+        }
+    }
+
+    #[test]
+    fn test_bolt_run_and_pull() {
+        let (mut session, _dir) = create_test_session(2500);
+
+        let responses = session.handle(run("MATCH (p:Person) RETURN p.name AS name", "social"));
+        match responses.as_slice() {
+            [BoltMessage::Success { metadata }] => assert_eq!(
+                metadata.get("fields"),
+                Some(&PropertyValue::Array(vec![PropertyValue::String("name".to_string())]))
+            ),
+            other => panic!("expected SUCCESS, got {:?}", other),
+        }
+
+        // Spans several executor chunks
+        let first = session.handle(BoltMessage::Pull { n: 1500 });
+        assert_eq!(first.len(), 1501);
+        assert!(matches!(first.last(), Some(BoltMessage::Success { metadata })
+            if metadata.contains_key("has_more")));
+
+        let rest = session.handle(BoltMessage::Pull { n: ALL_RECORDS });
+        assert_eq!(rest.len(), 1001);
+        match rest.last() {
+            Some(BoltMessage::Success { metadata }) => {
+                assert_eq!(metadata.get("type"), Some(&PropertyValue::String("r".to_string())));
+                assert!(metadata.contains_key("t_last"));
+            }
+            other => panic!("expected SUCCESS, got {:?}", other),
+        }
+        assert_eq!(session.state(), BoltState::Ready);
+    }
+
+    #[test]
+    fn test_bolt_default_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("social").unwrap();
+        for _ in 0..25 {
+            graph.create_node_with_properties(["Person"], Property::new()).unwrap();
+        }
+        let runner = DatabaseQueryRunner::new(Arc::new(db), create_auth_service()).with_default_limit(10);
+        let mut session = authenticated(runner);
+
+        session.handle(run("MATCH (p:Person) RETURN p", "social"));
+        assert_eq!(session.handle(BoltMessage::Pull { n: ALL_RECORDS }).len(), 11);
+
+        // An explicit LIMIT overrides the default
+        session.handle(run("MATCH (p:Person) RETURN p LIMIT 20", "social"));
+        assert_eq!(session.handle(BoltMessage::Pull { n: ALL_RECORDS }).len(), 21);
+    }
+
+    #[test]
+    fn test_bolt_run_syntax_error() {
+        let (mut session, _dir) = create_test_session(0);
+        let responses = session.handle(run("MATCH (p:Person RETURN p", "social"));
+        assert!(matches!(responses.as_slice(), [BoltMessage::Failure { .. }]));
+        assert_eq!(session.state(), BoltState::Failed);
+    }
+
+    #[test]
+    fn test_bolt_parser_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let runner = DatabaseQueryRunner::new(Arc::new(db), create_auth_service())
+            .with_parser_limits(ParserLimits { max_depth: 8, max_tokens: 20 });
+        let mut session = authenticated(runner);
+
+        let long = format!("MATCH (p){} RETURN p", "-->(q)".repeat(10));
+        match session.handle(run(&long, "social")).as_slice() {
+            [BoltMessage::Failure { metadata }] => assert!(
+                matches!(metadata.get("message"), Some(PropertyValue::String(m)) if m.contains("maximum of 20 tokens"))
+            ),
+            other => panic!("expected FAILURE, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_bolt_authentication() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Arc::new(Database::open_for_testing(temp_dir.path()).unwrap());
+        let (auth_service, api_key) = create_auth_service_with_api_key();
+        let runner = DatabaseQueryRunner::new(db, auth_service.clone());
+
+        // API keys are accepted like access tokens
+        let mut session = BoltSession::new(runner.clone());
+        assert!(matches!(session.handle(hello("bearer", &api_key)).as_slice(), [BoltMessage::Success { .. }]));
+        assert_eq!(session.state(), BoltState::Ready);
+
+        for message in [
+            hello("bearer", "not-a-token"),
+            hello("basic", &access_token(&auth_service)),
+            hello("none", ""),
+        ] {
+            let mut session = BoltSession::new(runner.clone());
+            assert!(matches!(session.handle(message).as_slice(), [BoltMessage::Failure { .. }]));
+            assert_eq!(session.state(), BoltState::Defunct);
+        }
+    }
+
+    #[test]
+    fn test_bolt_query_permissions() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Arc::new(Database::open_for_testing(temp_dir.path()).unwrap());
+        let merge = run("MERGE (p:Person {name: 'Eve'})", "social");
+
+        // Read-only users may read but not write
+        let mut session = authenticated(DatabaseQueryRunner::new(db.clone(), create_auth_service()));
+        match session.handle(merge.clone()).as_slice() {
+            [BoltMessage::Failure { metadata }] => assert!(
+                matches!(metadata.get("message"), Some(PropertyValue::String(m)) if m.contains("GraphUpdate"))
+            ),
+            other => panic!("expected FAILURE, got {:?}", other),
+        }
+        session.handle(BoltMessage::Reset);
+        let read = session.handle(run("MATCH (p:Person) RETURN p", "social"));
+        assert!(matches!(read.as_slice(), [BoltMessage::Success { .. }]));
+
+        let developer = create_auth_service_as(Role::Developer).0;
+        let mut session = authenticated(DatabaseQueryRunner::new(db.clone(), developer));
+        assert!(matches!(session.handle(merge).as_slice(), [BoltMessage::Success { .. }]));
+
+        // Queries are refused before HELLO has authenticated anyone
+        let runner = DatabaseQueryRunner::new(db, create_auth_service());
+        let error = runner.run("MATCH (p:Person) RETURN p", &HashMap::new(), &HashMap::new()).err();
+        assert!(matches!(error, Some(Error::AuthenticationFailed(_))));
+    }
+
+    #[tokio::test]
+    async fn test_bolt_listener() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("social").unwrap();
+        for _ in 0..3 {
+            graph.create_node_with_properties(["Person"], Property::new()).unwrap();
+        }
+        let auth_service = create_auth_service();
+        let token = access_token(&auth_service);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, DatabaseQueryRunner::new(Arc::new(db), auth_service)));
+
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+        let mut client = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let mut handshake = vec![0x60, 0x60, 0xB0, 0x17, 0, 0, 4, 4];
+        handshake.extend_from_slice(&[0; 12]);
+        client.write_all(&handshake).await.unwrap();
+        let mut version = [0u8; 4];
+        client.read_exact(&mut version).await.unwrap();
+        assert_eq!(version, [0, 0, 4, 4]);
+
+        for message in [
+            hello("bearer", &token),
+            run("MATCH (p:Person) RETURN p", "social"),
+            BoltMessage::Pull { n: ALL_RECORDS },
+            BoltMessage::Goodbye,
+        ] {
+            write_message(&mut client, &message).await.unwrap();
+        }
+        let mut responses = Vec::new();
+        while let Some(bytes) = read_message(&mut client).await.unwrap() {
+            responses.push(BoltMessage::decode(&bytes).unwrap());
+        }
+
+        // HELLO and RUN succeed, then three records and the summary
+        assert_eq!(responses.len(), 6);
+        assert!(responses[2..5].iter().all(|m| matches!(m, BoltMessage::Record { .. })));
+        assert!(matches!(responses.last(), Some(BoltMessage::Success { .. })));
+    }
+
+    #[test]
+    fn test_result_summary_reports_updates() {
+        let stats = ExecutionStats {
+            nodes_created: 2,
+            ..Default::default()
+        };
+        let summary = result_summary(&stats);
+        assert_eq!(summary.get("type"), Some(&PropertyValue::String("w".to_string())));
+        let updates = summary.get("stats").and_then(|s| s.as_map()).unwrap();
+        assert_eq!(updates.get("nodes-created"), Some(&PropertyValue::Integer(2)));
+        assert!(!updates.contains_key("nodes-deleted"));
+
+        assert!(!result_summary(&ExecutionStats::default()).contains_key("stats"));
+    }
+}
@@ -1,51 +1,175 @@
 //! Server configuration
 
+use crate::metrics::IpNetwork;
+use crate::security::{PasswordHashConfig, TokenService};
+use qilbee_graph::DatabaseConfig;
+use qilbee_memory::{EmbeddingConfig, SimilarityMetric};
+use qilbee_query::simple_parser::{DEFAULT_MAX_PARSE_DEPTH, DEFAULT_MAX_QUERY_TOKENS, ParserLimits};
+use qilbee_query::{QueryCache, DEFAULT_QUERY_CACHE_ENTRIES, DEFAULT_QUERY_CACHE_TTL};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 
 /// Server configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ServerConfig {
     /// Data directory
     pub data_dir: PathBuf,
 
+    /// Directory graph checkpoints are written to and restored from
+    ///
+    /// Checkpoint paths given to the HTTP API are relative to it.
+    pub backup_dir: PathBuf,
+
     /// Bolt protocol port
     pub bolt_port: u16,
 
     /// HTTP API port
     pub http_port: u16,
 
+    /// gRPC port
+    pub grpc_port: u16,
+
     /// Enable Bolt protocol
     pub enable_bolt: bool,
 
     /// Enable HTTP API
     pub enable_http: bool,
 
+    /// Enable gRPC API
+    ///
+    /// Off by default. gRPC clients authenticate with the same access tokens
+    /// and API keys as HTTP clients.
+    pub enable_grpc: bool,
+
     /// Maximum concurrent connections
     pub max_connections: usize,
 
     /// Query timeout in seconds
     pub query_timeout_secs: u64,
 
+    /// LIMIT applied to read queries that don't specify one (`None` for unbounded)
+    pub default_query_limit: Option<usize>,
+
+    /// Maximum nesting depth of expressions in HTTP queries
+    pub max_query_depth: usize,
+
+    /// Maximum number of tokens in HTTP queries
+    pub max_query_tokens: usize,
+
+    /// Seconds a read query result stays cached (`None` disables the cache)
+    pub query_cache_ttl_secs: Option<u64>,
+
+    /// Maximum number of cached read query results
+    pub query_cache_entries: usize,
+
+    /// Persist agent memory episodes in the database (in-memory only when disabled)
+    pub persist_agent_memory: bool,
+
+    /// Persist revoked tokens and issued refresh tokens in the database, so
+    /// they stay revoked or usable across restarts (in-memory only when disabled)
+    pub persist_token_blacklist: bool,
+
+    /// Persist user accounts and their roles in the database (in-memory only when disabled)
+    pub persist_users: bool,
+
+    /// How user passwords are hashed
+    pub password_hash: PasswordHashConfig,
+
+    /// Password of the `admin` account created when no admin exists
+    /// (`None` generates a random one and logs it once)
+    pub admin_password: Option<String>,
+
+    /// Embedding provider for semantic search over agent memory (`None` disables it)
+    pub memory_embeddings: Option<EmbeddingConfig>,
+
+    /// Embedding providers for specific agents, keyed by agent ID
+    ///
+    /// Overrides `memory_embeddings` for those agents, so they can use another
+    /// model, dimension or API key.
+    pub agent_memory_embeddings: HashMap<String, EmbeddingConfig>,
+
+    /// Similarity metric for semantic search over agent memory
+    pub memory_similarity_metric: SimilarityMetric,
+
     /// Enable authentication
     pub auth_enabled: bool,
 
+    /// Networks allowed to scrape `/metrics` without credentials
+    pub metrics_allowlist: Vec<IpNetwork>,
+
+    /// PEM encoded RSA private and public key signing JWTs with RS256
+    /// (`None` signs them with HS256 and a shared secret)
+    pub jwt_rsa_keys: Option<(String, String)>,
+
+    /// PEM encoded public keys of earlier RSA key pairs, whose JWTs are
+    /// still accepted and which stay published at `/.well-known/jwks.json`
+    pub jwt_previous_public_keys: Vec<String>,
+
     /// Log level
     pub log_level: String,
+
+    /// Maximum number of graphs
+    pub max_graphs: usize,
+
+    /// Maximum number of nodes per graph (`None` for unlimited)
+    pub max_nodes_per_graph: Option<u64>,
+
+    /// Maximum number of relationships per graph (`None` for unlimited)
+    pub max_relationships_per_graph: Option<u64>,
+
+    /// Relationship property that orders adjacency scans (`None` for ID order)
+    pub adjacency_sort_property: Option<String>,
+
+    /// Maximum number of properties on a node or relationship (`None` for unlimited)
+    pub max_properties_per_entity: Option<usize>,
+
+    /// Maximum serialized size of a property value in bytes (`None` for unlimited)
+    pub max_property_value_bytes: Option<usize>,
+
+    /// Distance within which floats compare equal (`None` for exact comparison)
+    pub float_epsilon: Option<f64>,
 }
 
 impl Default for ServerConfig {
     fn default() -> Self {
         Self {
             data_dir: PathBuf::from("./data"),
+            backup_dir: PathBuf::from("./backups"),
             bolt_port: 7687,
             http_port: 7474,
+            grpc_port: 7688,
             enable_bolt: true,
             enable_http: true,
+            enable_grpc: false,
             max_connections: 1000,
             query_timeout_secs: 300,
+            default_query_limit: Some(10_000),
+            max_query_depth: DEFAULT_MAX_PARSE_DEPTH,
+            max_query_tokens: DEFAULT_MAX_QUERY_TOKENS,
+            query_cache_ttl_secs: Some(DEFAULT_QUERY_CACHE_TTL.as_secs()),
+            query_cache_entries: DEFAULT_QUERY_CACHE_ENTRIES,
+            persist_agent_memory: true,
+            persist_token_blacklist: true,
+            persist_users: true,
+            password_hash: PasswordHashConfig::default(),
+            admin_password: None,
+            memory_embeddings: None,
+            agent_memory_embeddings: HashMap::new(),
+            memory_similarity_metric: SimilarityMetric::Cosine,
             auth_enabled: false,
+            metrics_allowlist: IpNetwork::loopback(),
+            jwt_rsa_keys: None,
+            jwt_previous_public_keys: Vec::new(),
             log_level: "info".to_string(),
+            max_graphs: 10000,
+            max_nodes_per_graph: None,
+            max_relationships_per_graph: None,
+            adjacency_sort_property: None,
+            max_properties_per_entity: None,
+            max_property_value_bytes: None,
+            float_epsilon: None,
         }
     }
 }
@@ -92,6 +216,18 @@ impl ServerConfig {
         self
     }
 
+    /// Builder: set gRPC port
+    pub fn grpc_port(mut self, port: u16) -> Self {
+        self.grpc_port = port;
+        self
+    }
+
+    /// Builder: set the directory graph checkpoints are kept in
+    pub fn backup_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        self.backup_dir = dir.into();
+        self
+    }
+
     /// Builder: disable Bolt
     pub fn disable_bolt(mut self) -> Self {
         self.enable_bolt = false;
@@ -104,17 +240,219 @@ impl ServerConfig {
         self
     }
 
+    /// Builder: enable gRPC
+    pub fn with_grpc(mut self) -> Self {
+        self.enable_grpc = true;
+        self
+    }
+
+    /// Builder: disable gRPC
+    pub fn disable_grpc(mut self) -> Self {
+        self.enable_grpc = false;
+        self
+    }
+
+    /// Builder: set the default LIMIT for read queries
+    pub fn default_query_limit(mut self, limit: usize) -> Self {
+        self.default_query_limit = Some(limit);
+        self
+    }
+
+    /// Builder: return every row of read queries without a LIMIT
+    pub fn disable_default_query_limit(mut self) -> Self {
+        self.default_query_limit = None;
+        self
+    }
+
+    /// Builder: set the maximum expression nesting depth of queries
+    pub fn max_query_depth(mut self, depth: usize) -> Self {
+        self.max_query_depth = depth;
+        self
+    }
+
+    /// Builder: set the maximum number of tokens in a query
+    pub fn max_query_tokens(mut self, tokens: usize) -> Self {
+        self.max_query_tokens = tokens;
+        self
+    }
+
+    /// Builder: cache read query results for `secs` seconds
+    pub fn query_cache_ttl_secs(mut self, secs: u64) -> Self {
+        self.query_cache_ttl_secs = Some(secs);
+        self
+    }
+
+    /// Builder: set the maximum number of cached read query results
+    pub fn query_cache_entries(mut self, entries: usize) -> Self {
+        self.query_cache_entries = entries;
+        self
+    }
+
+    /// Builder: execute every query, never serving results from a cache
+    pub fn disable_query_cache(mut self) -> Self {
+        self.query_cache_ttl_secs = None;
+        self
+    }
+
+    /// Builder: keep agent memory in process memory only
+    pub fn disable_memory_persistence(mut self) -> Self {
+        self.persist_agent_memory = false;
+        self
+    }
+
+    /// Builder: keep revoked tokens and refresh tokens in process memory only
+    pub fn disable_token_blacklist_persistence(mut self) -> Self {
+        self.persist_token_blacklist = false;
+        self
+    }
+
+    /// Builder: keep user accounts in process memory only
+    pub fn disable_user_persistence(mut self) -> Self {
+        self.persist_users = false;
+        self
+    }
+
+    /// Builder: hash user passwords with the given algorithm and parameters
+    pub fn password_hash_config(mut self, config: PasswordHashConfig) -> Self {
+        self.password_hash = config;
+        self
+    }
+
+    /// Builder: set the password of the `admin` account created when no admin exists
+    pub fn admin_password(mut self, password: &str) -> Self {
+        self.admin_password = Some(password.to_string());
+        self
+    }
+
+    /// Builder: enable semantic search over agent memory
+    pub fn memory_embeddings(mut self, config: EmbeddingConfig) -> Self {
+        self.memory_embeddings = Some(config);
+        self
+    }
+
+    /// Builder: use a specific embedding provider for one agent's memory
+    pub fn agent_memory_embeddings(mut self, agent_id: &str, config: EmbeddingConfig) -> Self {
+        self.agent_memory_embeddings.insert(agent_id.to_string(), config);
+        self
+    }
+
+    /// Builder: set the similarity metric for semantic search over agent memory
+    pub fn memory_similarity_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.memory_similarity_metric = metric;
+        self
+    }
+
     /// Builder: enable auth
     pub fn with_auth(mut self) -> Self {
         self.auth_enabled = true;
         self
     }
 
+    /// Builder: allow `network` to scrape `/metrics` without credentials
+    pub fn allow_metrics_from(mut self, network: IpNetwork) -> Self {
+        self.metrics_allowlist.push(network);
+        self
+    }
+
+    /// Builder: sign JWTs with RS256 and a PEM encoded RSA key pair
+    pub fn jwt_rsa_keys(mut self, private_pem: &str, public_pem: &str) -> Self {
+        self.jwt_rsa_keys = Some((private_pem.to_string(), public_pem.to_string()));
+        self
+    }
+
+    /// Builder: keep accepting JWTs signed by an earlier RSA key pair
+    pub fn jwt_previous_public_key(mut self, public_pem: &str) -> Self {
+        self.jwt_previous_public_keys.push(public_pem.to_string());
+        self
+    }
+
     /// Builder: set log level
     pub fn log_level(mut self, level: &str) -> Self {
         self.log_level = level.to_string();
         self
     }
+
+    /// Builder: set maximum number of graphs
+    pub fn max_graphs(mut self, max: usize) -> Self {
+        self.max_graphs = max;
+        self
+    }
+
+    /// Builder: set maximum nodes per graph
+    pub fn max_nodes_per_graph(mut self, max: u64) -> Self {
+        self.max_nodes_per_graph = Some(max);
+        self
+    }
+
+    /// Builder: set maximum relationships per graph
+    pub fn max_relationships_per_graph(mut self, max: u64) -> Self {
+        self.max_relationships_per_graph = Some(max);
+        self
+    }
+
+    /// Builder: return relationships in order of a property (e.g. a timestamp)
+    pub fn adjacency_sort_property<S: Into<String>>(mut self, property: S) -> Self {
+        self.adjacency_sort_property = Some(property.into());
+        self
+    }
+
+    /// Builder: set maximum properties per node or relationship
+    pub fn max_properties_per_entity(mut self, max: usize) -> Self {
+        self.max_properties_per_entity = Some(max);
+        self
+    }
+
+    /// Builder: set maximum serialized size of a property value
+    pub fn max_property_value_bytes(mut self, max: usize) -> Self {
+        self.max_property_value_bytes = Some(max);
+        self
+    }
+
+    /// Builder: treat floats within `epsilon` of each other as equal in comparisons
+    pub fn float_epsilon(mut self, epsilon: f64) -> Self {
+        self.float_epsilon = Some(epsilon);
+        self
+    }
+
+    /// Parser limits derived from the server settings
+    pub fn parser_limits(&self) -> ParserLimits {
+        ParserLimits {
+            max_depth: self.max_query_depth,
+            max_tokens: self.max_query_tokens,
+        }
+    }
+
+    /// Query result cache derived from the server settings, `None` when disabled
+    pub fn query_cache(&self) -> Option<QueryCache> {
+        self.query_cache_ttl_secs
+            .map(|secs| QueryCache::new(std::time::Duration::from_secs(secs), self.query_cache_entries))
+    }
+
+    /// JWT token service derived from the server settings
+    pub fn token_service(&self) -> qilbee_core::Result<TokenService> {
+        let Some((private_pem, public_pem)) = &self.jwt_rsa_keys else {
+            return Ok(TokenService::new("qilbee_jwt_secret_change_in_production".to_string()));
+        };
+        let service = TokenService::with_rsa_keys(private_pem.as_bytes(), public_pem.as_bytes())?;
+        for public_pem in &self.jwt_previous_public_keys {
+            service.add_rsa_public_key(public_pem.as_bytes())?;
+        }
+        Ok(service)
+    }
+
+    /// Database configuration derived from the server settings
+    pub fn database_config(&self) -> DatabaseConfig {
+        DatabaseConfig {
+            max_graphs: self.max_graphs,
+            max_nodes_per_graph: self.max_nodes_per_graph,
+            max_relationships_per_graph: self.max_relationships_per_graph,
+            adjacency_sort_property: self.adjacency_sort_property.clone(),
+            max_properties_per_entity: self.max_properties_per_entity,
+            max_property_value_bytes: self.max_property_value_bytes,
+            float_epsilon: self.float_epsilon,
+            ..Default::default()
+        }
+    }
 }
 
 #[cfg(test)]
@@ -126,8 +464,18 @@ mod tests {
         let config = ServerConfig::default();
         assert_eq!(config.bolt_port, 7687);
         assert_eq!(config.http_port, 7474);
+        assert_eq!(config.grpc_port, 7688);
         assert!(config.enable_bolt);
         assert!(config.enable_http);
+        assert!(!config.enable_grpc);
+        assert_eq!(config.default_query_limit, Some(10_000));
+        assert!(config.persist_agent_memory);
+        assert!(config.persist_token_blacklist);
+        assert!(config.persist_users);
+        assert!(config.memory_embeddings.is_none());
+        assert_eq!(config.backup_dir, PathBuf::from("./backups"));
+        assert_eq!(config.metrics_allowlist, IpNetwork::loopback());
+        assert!(config.query_cache().is_some());
     }
 
     #[test]
@@ -135,12 +483,65 @@ mod tests {
         let config = ServerConfig::new("/data")
             .bolt_port(7688)
             .http_port(7475)
+            .grpc_port(7689)
+            .with_grpc()
+            .backup_dir("/backups")
             .with_auth()
-            .log_level("debug");
+            .log_level("debug")
+            .default_query_limit(500)
+            .max_query_depth(16)
+            .max_query_tokens(1_000)
+            .memory_embeddings(EmbeddingConfig::mock(64))
+            .agent_memory_embeddings("agent-2", EmbeddingConfig::mock(128))
+            .memory_similarity_metric(SimilarityMetric::DotProduct)
+            .allow_metrics_from("10.0.0.0/8".parse().unwrap());
 
         assert_eq!(config.bolt_port, 7688);
         assert_eq!(config.http_port, 7475);
+        assert_eq!(config.grpc_port, 7689);
+        assert!(config.enable_grpc);
+        assert!(!config.clone().disable_grpc().enable_grpc);
+        assert_eq!(config.backup_dir, PathBuf::from("/backups"));
         assert!(config.auth_enabled);
         assert_eq!(config.log_level, "debug");
+        assert_eq!(config.default_query_limit, Some(500));
+        assert_eq!(
+            config.parser_limits(),
+            ParserLimits { max_depth: 16, max_tokens: 1_000 }
+        );
+        assert_eq!(config.memory_embeddings.as_ref().unwrap().dimensions, 64);
+        assert_eq!(config.agent_memory_embeddings["agent-2"].dimensions, 128);
+        assert_eq!(config.memory_similarity_metric, SimilarityMetric::DotProduct);
+        assert!(config.metrics_allowlist.iter().any(|n| n.contains("10.4.0.1".parse().unwrap())));
+        assert_eq!(config.disable_default_query_limit().default_query_limit, None);
+        assert!(!ServerConfig::new("/data").disable_memory_persistence().persist_agent_memory);
+        assert!(!ServerConfig::new("/data").disable_token_blacklist_persistence().persist_token_blacklist);
+        assert!(!ServerConfig::new("/data").disable_user_persistence().persist_users);
+        let password_hash = PasswordHashConfig::pbkdf2_sha256(600_000);
+        assert_eq!(ServerConfig::new("/data").password_hash_config(password_hash.clone()).password_hash, password_hash);
+        assert_eq!(ServerConfig::new("/data").admin_password("Bootstrap@Pass1").admin_password.as_deref(), Some("Bootstrap@Pass1"));
+        assert!(ServerConfig::new("/data").disable_query_cache().query_cache().is_none());
+    }
+
+    #[test]
+    fn test_quota_builder() {
+        let config = ServerConfig::new("/data")
+            .max_graphs(5)
+            .max_nodes_per_graph(100)
+            .max_relationships_per_graph(500)
+            .adjacency_sort_property("createdAt")
+            .max_properties_per_entity(32)
+            .max_property_value_bytes(4096)
+            .float_epsilon(1e-9);
+
+        let db_config = config.database_config();
+        assert_eq!(db_config.adjacency_sort_property.as_deref(), Some("createdAt"));
+        assert_eq!(db_config.max_graphs, 5);
+        assert_eq!(db_config.max_nodes_per_graph, Some(100));
+        assert_eq!(db_config.max_relationships_per_graph, Some(500));
+        assert_eq!(db_config.max_properties_per_entity, Some(32));
+        assert_eq!(db_config.max_property_value_bytes, Some(4096));
+        assert_eq!(db_config.float_epsilon, Some(1e-9));
+        assert_eq!(ServerConfig::default().database_config().max_nodes_per_graph, None);
     }
 }
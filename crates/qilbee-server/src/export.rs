@@ -0,0 +1,179 @@
+//! Dumps of whole graphs
+//!
+//! A dump is newline-delimited JSON read from one storage snapshot, so it is
+//! consistent however many writes land while it is taken. The first record
+//! holds the schema, followed by every node and then every relationship in
+//! ID order:
+//!
+//! ```text
+//! {"kind": "schema", "indexes": [...], "constraints": [...]}
+//! {"kind": "node", "id": 1, "labels": ["Person"], "properties": {"name": "Alice"}}
+//! {"kind": "relationship", "id": 1, "startNode": 1, "type": "KNOWS", "endNode": 2, "properties": {}}
+//! ```
+//!
+//! Node and relationship records have the fields of bulk import rows, with
+//! internal IDs as the external IDs, so [`crate::import::import_dump`]
+//! restores a dump into another graph.
+
+use crate::http_server::property_to_json_map;
+use qilbee_core::{EntityId, Error, Result};
+use qilbee_graph::Graph;
+use serde_json::{json, Value};
+
+/// Media type of graph dumps
+pub const DUMP_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// Write a dump of `graph`, handing it to `send` in chunks of
+/// `chunk_records` records
+///
+/// Only one chunk is held in memory at a time. Stops early, without error,
+/// once `send` returns false.
+pub fn export_graph(graph: &Graph, chunk_records: usize, send: impl FnMut(String) -> bool) -> Result<()> {
+    let schema = graph
+        .schema()
+        .read()
+        .map_err(|_| Error::Internal("Failed to acquire schema lock".to_string()))?
+        .clone();
+    let mut indexes: Vec<_> = schema.indexes.into_values().collect();
+    indexes.sort_by(|a, b| a.name.cmp(&b.name));
+    let mut constraints: Vec<_> = schema.constraints.into_values().collect();
+    constraints.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut writer = ChunkWriter {
+        chunk: String::new(),
+        records: 0,
+        chunk_records: chunk_records.max(1),
+        send,
+    };
+    if !writer.push(json!({"kind": "schema", "indexes": indexes, "constraints": constraints})) {
+        return Ok(());
+    }
+
+    let storage = graph.storage();
+    let snapshot = storage.snapshot();
+    for node in snapshot.nodes(graph.id())? {
+        let node = node?;
+        // Labels are a set; sort them so dumps of the same graph are identical
+        let mut labels: Vec<_> = node.labels.iter().map(|label| label.name()).collect();
+        labels.sort_unstable();
+        let record = json!({
+            "kind": "node",
+            "id": node.id.as_internal(),
+            "labels": labels,
+            "properties": property_to_json_map(&node.properties),
+        });
+        if !writer.push(record) {
+            return Ok(());
+        }
+    }
+    for rel in snapshot.relationships(graph.id())? {
+        let rel = rel?;
+        let record = json!({
+            "kind": "relationship",
+            "id": rel.id.as_internal(),
+            "startNode": rel.source.as_internal(),
+            "type": rel.rel_type.name(),
+            "endNode": rel.target.as_internal(),
+            "properties": property_to_json_map(&rel.properties),
+        });
+        if !writer.push(record) {
+            return Ok(());
+        }
+    }
+    writer.flush();
+    Ok(())
+}
+
+/// Collects records into chunks for a dump
+struct ChunkWriter<F> {
+    chunk: String,
+    records: usize,
+    chunk_records: usize,
+    send: F,
+}
+
+impl<F: FnMut(String) -> bool> ChunkWriter<F> {
+    /// Add a record, sending the chunk once it is full; false if the
+    /// receiver is gone
+    fn push(&mut self, record: Value) -> bool {
+        self.chunk.push_str(&record.to_string());
+        self.chunk.push('\n');
+        self.records += 1;
+        self.records < self.chunk_records || self.flush()
+    }
+
+    /// Send the records collected so far
+    fn flush(&mut self) -> bool {
+        if self.records == 0 {
+            return true;
+        }
+        self.records = 0;
+        (self.send)(std::mem::take(&mut self.chunk))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qilbee_core::Property;
+    use qilbee_graph::{Database, Index};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_export_graph() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        graph.create_index(Index::range("person_name", "Person", "name")).unwrap();
+        let alice = graph
+            .create_node_with_properties(["Person"], Property::with("name", "Alice"))
+            .unwrap();
+        let bob = graph.create_node(["Person", "Admin"]).unwrap();
+        let knows = graph
+            .create_relationship_with_properties(alice.id, "KNOWS", bob.id, Property::with("since", 2020))
+            .unwrap();
+
+        let mut chunks = Vec::new();
+        export_graph(&graph, 2, |chunk| {
+            chunks.push(chunk);
+            true
+        })
+        .unwrap();
+        assert_eq!(chunks.len(), 2);
+        let records: Vec<Value> = chunks
+            .concat()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+
+        assert_eq!(records[0]["kind"], "schema");
+        assert_eq!(records[0]["indexes"][0]["name"], "person_name");
+        assert_eq!(records[0]["constraints"], json!([]));
+        assert_eq!(
+            records[1],
+            json!({"kind": "node", "id": alice.id.as_internal(), "labels": ["Person"], "properties": {"name": "Alice"}})
+        );
+        assert_eq!(records[2]["labels"], json!(["Admin", "Person"]));
+        assert_eq!(
+            records[3],
+            json!({
+                "kind": "relationship",
+                "id": knows.id.as_internal(),
+                "startNode": alice.id.as_internal(),
+                "type": "KNOWS",
+                "endNode": bob.id.as_internal(),
+                "properties": {"since": 2020},
+            })
+        );
+        assert_eq!(records.len(), 4);
+
+        // A receiver that goes away stops the export after its first chunk
+        let mut sent = 0;
+        export_graph(&graph, 1, |_| {
+            sent += 1;
+            false
+        })
+        .unwrap();
+        assert_eq!(sent, 1);
+    }
+}
@@ -0,0 +1,656 @@
+//! gRPC server implementation
+//!
+//! Implements the `QilbeeDb` service from `qilbee-protocol` on top of the
+//! database, mirroring the node, relationship and query endpoints of the HTTP
+//! API. `StreamQueryResults` sends results in row batches so clients can
+//! consume large result sets without buffering the whole response.
+//!
+//! Clients authenticate like HTTP clients, with an access token in an
+//! `authorization: Bearer <token>` header or an API key in `x-api-key`.
+//! Each call also needs a permission of the user's roles: the one
+//! [`Permission::for_query`] names for queries, and the node or
+//! relationship permission for the other calls.
+
+use crate::security::{AuthService, Permission, User};
+use futures::Stream;
+use qilbee_core::{EntityId, Error, Label, NodeId, RelationshipId};
+use qilbee_graph::Database;
+use qilbee_protocol::grpc::{
+    DEFAULT_STREAM_BATCH_SIZE, QilbeeDb, QilbeeDbServer, properties_from_proto,
+    properties_to_proto, proto, row_to_proto,
+};
+use qilbee_protocol::message::{QueryResult, QueryStats, Request};
+use qilbee_query::{
+    ExecutionStats, ParserLimits, QueryExecutor, QueryPlanner, QueryResultStream,
+    parse_simple_with_limits,
+};
+use std::pin::Pin;
+use std::sync::Arc;
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::{Response, Status};
+
+/// gRPC service backed by a database
+#[derive(Clone)]
+pub struct GrpcService {
+    database: Arc<Database>,
+    auth_service: Arc<AuthService>,
+    parser_limits: ParserLimits,
+    /// Row cap for read queries without a LIMIT
+    default_limit: Option<usize>,
+}
+
+impl GrpcService {
+    /// Create a new gRPC service
+    pub fn new(database: Arc<Database>, auth_service: Arc<AuthService>) -> Self {
+        Self {
+            database,
+            auth_service,
+            parser_limits: ParserLimits::default(),
+            default_limit: None,
+        }
+    }
+
+    /// Builder: parse queries with `limits`
+    pub fn with_parser_limits(mut self, limits: ParserLimits) -> Self {
+        self.parser_limits = limits;
+        self
+    }
+
+    /// Builder: cap read queries without a LIMIT at `limit` rows
+    pub fn with_default_limit(mut self, limit: usize) -> Self {
+        self.default_limit = Some(limit);
+        self
+    }
+
+    /// Wrap the service for registration with a tonic server, rejecting
+    /// calls without valid credentials
+    pub fn into_server(self) -> InterceptedService<QilbeeDbServer<Self>, GrpcAuthInterceptor> {
+        let interceptor = GrpcAuthInterceptor {
+            auth_service: Arc::clone(&self.auth_service),
+        };
+        QilbeeDbServer::with_interceptor(self, interceptor)
+    }
+
+    /// Parse, plan and execute a query request for `user`
+    fn run_query(&self, user: &User, request: proto::QueryRequest) -> qilbee_core::Result<QueryResultStream> {
+        let graph = self.database.graph(&request.graph)?;
+        let batch_size = match request.batch_size {
+            0 => DEFAULT_STREAM_BATCH_SIZE,
+            n => n as usize,
+        };
+
+        let (statement, parameters) = match Request::from(request) {
+            Request::Query { statement, parameters } => (statement, parameters),
+            _ => unreachable!("query requests convert to Request::Query"),
+        };
+
+        let query = parse_simple_with_limits(&statement, self.parser_limits)?;
+        check_permission(user, &Permission::for_query(query.is_read_only()))?;
+        let mut planner = QueryPlanner::new()
+            .with_graph_schema(&graph)?
+            .with_graph_statistics(&graph, &query)?;
+        if let Some(limit) = self.default_limit {
+            planner = planner.with_default_limit(limit);
+        }
+        let plan = planner.plan(&query)?;
+        QueryExecutor::new(Arc::new(graph)).execute_stream(&plan, &parameters, batch_size)
+    }
+}
+
+/// Authenticates gRPC calls with the same access tokens and API keys as the
+/// HTTP API, passing the user on in the request extensions
+#[derive(Clone)]
+pub struct GrpcAuthInterceptor {
+    auth_service: Arc<AuthService>,
+}
+
+impl Interceptor for GrpcAuthInterceptor {
+    fn call(&mut self, mut request: tonic::Request<()>) -> Result<tonic::Request<()>, Status> {
+        let metadata = request.metadata();
+        let bearer = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        let api_key = metadata.get("x-api-key").and_then(|value| value.to_str().ok());
+
+        let user = match (bearer, api_key) {
+            (Some(token), _) => self.auth_service.validate_token(token),
+            (None, Some(api_key)) => self.auth_service.validate_api_key(api_key),
+            (None, None) => return Err(Status::unauthenticated("Expected a bearer token or API key")),
+        }
+        .map_err(|_| Status::unauthenticated("Invalid credentials"))?;
+
+        request.extensions_mut().insert(user);
+        Ok(request)
+    }
+}
+
+/// The user [`GrpcAuthInterceptor`] authenticated the request as
+fn authenticated_user<T>(request: &tonic::Request<T>) -> qilbee_core::Result<User> {
+    request
+        .extensions()
+        .get::<User>()
+        .cloned()
+        .ok_or_else(|| Error::AuthenticationFailed("Authentication required".to_string()))
+}
+
+/// Check that the request's user has `permission`
+fn authorize<T>(request: &tonic::Request<T>, permission: &Permission) -> qilbee_core::Result<()> {
+    check_permission(&authenticated_user(request)?, permission)
+}
+
+/// Check that one of the user's roles grants `permission`
+fn check_permission(user: &User, permission: &Permission) -> qilbee_core::Result<()> {
+    if user.has_permission(permission) {
+        Ok(())
+    } else {
+        Err(Error::Unauthorized(format!("Required permission: {:?}", permission)))
+    }
+}
+
+type QueryChunkStream = Pin<Box<dyn Stream<Item = Result<proto::QueryResultChunk, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl QilbeeDb for GrpcService {
+    async fn execute_query(
+        &self,
+        request: tonic::Request<proto::QueryRequest>,
+    ) -> Result<Response<proto::QueryResponse>, Status> {
+        let user = authenticated_user(&request).map_err(error_status)?;
+        let mut stream = self.run_query(&user, request.into_inner()).map_err(error_status)?;
+
+        let mut result = QueryResult::new(stream.columns().to_vec(), Vec::new());
+        for chunk in stream.by_ref() {
+            result.rows.extend(chunk.map_err(error_status)?);
+        }
+        result.stats = execution_stats(stream.stats());
+
+        Ok(Response::new((&result).into()))
+    }
+
+    type StreamQueryResultsStream = QueryChunkStream;
+
+    async fn stream_query_results(
+        &self,
+        request: tonic::Request<proto::QueryRequest>,
+    ) -> Result<Response<Self::StreamQueryResultsStream>, Status> {
+        let user = authenticated_user(&request).map_err(error_status)?;
+        let stream = self.run_query(&user, request.into_inner()).map_err(error_status)?;
+        let chunks = query_result_chunks(stream);
+
+        Ok(Response::new(Box::pin(futures::stream::iter(chunks))))
+    }
+
+    async fn create_node(
+        &self,
+        request: tonic::Request<proto::CreateNodeRequest>,
+    ) -> Result<Response<proto::Node>, Status> {
+        authorize(&request, &Permission::NodeCreate).map_err(error_status)?;
+        let request = request.into_inner();
+        let graph = self.database.graph(&request.graph).map_err(error_status)?;
+
+        let labels: Vec<Label> = request.labels.iter().map(Label::new).collect();
+        let node = graph
+            .create_node_with_properties(labels, properties_from_proto(request.properties))
+            .map_err(error_status)?;
+
+        Ok(Response::new(node_to_proto(&node)))
+    }
+
+    async fn get_node(
+        &self,
+        request: tonic::Request<proto::GetNodeRequest>,
+    ) -> Result<Response<proto::Node>, Status> {
+        authorize(&request, &Permission::NodeRead).map_err(error_status)?;
+        let request = request.into_inner();
+        let graph = self.database.graph(&request.graph).map_err(error_status)?;
+
+        match graph.get_node(NodeId::from_internal(request.id)) {
+            Ok(Some(node)) => Ok(Response::new(node_to_proto(&node))),
+            Ok(None) => Err(Status::not_found("Node not found")),
+            Err(e) => Err(error_status(e)),
+        }
+    }
+
+    async fn create_relationship(
+        &self,
+        request: tonic::Request<proto::CreateRelationshipRequest>,
+    ) -> Result<Response<proto::Relationship>, Status> {
+        authorize(&request, &Permission::RelationshipCreate).map_err(error_status)?;
+        let request = request.into_inner();
+        let graph = self.database.graph(&request.graph).map_err(error_status)?;
+
+        let rel = graph
+            .create_relationship_with_properties(
+                NodeId::from_internal(request.start_node),
+                Label::new(&request.r#type),
+                NodeId::from_internal(request.end_node),
+                properties_from_proto(request.properties),
+            )
+            .map_err(error_status)?;
+
+        Ok(Response::new(relationship_to_proto(&rel)))
+    }
+
+    async fn get_relationship(
+        &self,
+        request: tonic::Request<proto::GetRelationshipRequest>,
+    ) -> Result<Response<proto::Relationship>, Status> {
+        authorize(&request, &Permission::RelationshipRead).map_err(error_status)?;
+        let request = request.into_inner();
+        let graph = self.database.graph(&request.graph).map_err(error_status)?;
+
+        match graph.get_relationship(RelationshipId::from_internal(request.id)) {
+            Ok(Some(rel)) => Ok(Response::new(relationship_to_proto(&rel))),
+            Ok(None) => Err(Status::not_found("Relationship not found")),
+            Err(e) => Err(error_status(e)),
+        }
+    }
+}
+
+/// Split a result stream into protobuf chunks
+///
+/// The first chunk carries the column names and the last one the statistics.
+/// An empty result still produces a single chunk so clients always learn the
+/// columns. Rows are produced one chunk ahead of the chunk being sent, so the
+/// last chunk is known when it is built; a failing chunk ends the stream with
+/// its error.
+fn query_result_chunks(
+    mut stream: QueryResultStream,
+) -> impl Iterator<Item = Result<proto::QueryResultChunk, Status>> + Send {
+    let mut columns = Some(stream.columns().to_vec());
+    let mut next = stream.next();
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if done {
+            return None;
+        }
+        let rows = match next.take() {
+            Some(Ok(rows)) => rows,
+            Some(Err(e)) => {
+                done = true;
+                return Some(Err(error_status(e)));
+            }
+            None => Vec::new(),
+        };
+        next = stream.next();
+        done = next.is_none();
+
+        Some(Ok(proto::QueryResultChunk {
+            columns: columns.take().unwrap_or_default(),
+            rows: rows.iter().map(|row| row_to_proto(row)).collect(),
+            stats: done.then(|| proto::QueryStats::from(&execution_stats(stream.stats()))),
+        }))
+    })
+}
+
+fn execution_stats(stats: &ExecutionStats) -> QueryStats {
+    QueryStats {
+        nodes_created: stats.nodes_created,
+        nodes_deleted: stats.nodes_deleted,
+        relationships_created: stats.relationships_created,
+        relationships_deleted: stats.relationships_deleted,
+        properties_set: stats.properties_set,
+        execution_time_ms: stats.execution_time_ms,
+        ..Default::default()
+    }
+}
+
+fn node_to_proto(node: &qilbee_core::Node) -> proto::Node {
+    proto::Node {
+        id: node.id.as_internal(),
+        labels: node.labels.iter().map(|l| l.name().to_string()).collect(),
+        properties: properties_to_proto(&node.properties),
+    }
+}
+
+fn relationship_to_proto(rel: &qilbee_core::Relationship) -> proto::Relationship {
+    proto::Relationship {
+        id: rel.id.as_internal(),
+        r#type: rel.rel_type.name().to_string(),
+        start_node: rel.source.as_internal(),
+        end_node: rel.target.as_internal(),
+        properties: properties_to_proto(&rel.properties),
+    }
+}
+
+/// Map a database error to the closest gRPC status
+fn error_status(e: Error) -> Status {
+    let message = e.to_string();
+    match e {
+        Error::QueryParse(_) | Error::InvalidQuery(_) => Status::invalid_argument(message),
+        Error::GraphNotFound(_) | Error::NodeNotFound(_) | Error::RelationshipNotFound(_) => {
+            Status::not_found(message)
+        }
+        Error::QuotaExceeded(_) => Status::resource_exhausted(message),
+        Error::AuthenticationFailed(_) => Status::unauthenticated(message),
+        Error::Unauthorized(_) => Status::permission_denied(message),
+        _ => Status::internal(message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::{
+        AuthConfig, BlacklistConfig, Credentials, PasswordHashConfig, Role, TokenBlacklist, TokenService, UserService,
+    };
+    use futures::StreamExt;
+    use qilbee_core::PropertyValue;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn query_parameters<I, K, V>(params: I) -> HashMap<String, proto::Value>
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<PropertyValue>,
+    {
+        params
+            .into_iter()
+            .map(|(k, v)| (k.into(), proto::Value::from(v.into())))
+            .collect()
+    }
+
+    const PASSWORD: &str = "SecureP@ss123!";
+
+    /// Auth service with the user `alice`, and an API key of hers
+    fn create_auth_service_with_api_key() -> (Arc<AuthService>, String) {
+        let user_service = Arc::new(UserService::new());
+        let user = user_service
+            .create_user("alice".to_string(), "alice@example.com".to_string(), PASSWORD)
+            .unwrap();
+        let token_service = Arc::new(TokenService::new("test_secret".to_string()));
+        let (api_key, _) = token_service.generate_api_key(user.id, "grpc".to_string(), None).unwrap();
+        let auth_service = Arc::new(AuthService::new(
+            user_service,
+            token_service,
+            Arc::new(TokenBlacklist::new(BlacklistConfig::default())),
+            AuthConfig::default(),
+        ));
+        (auth_service, api_key)
+    }
+
+    fn create_test_service() -> (GrpcService, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        (GrpcService::new(Arc::new(db), create_auth_service_with_api_key().0), temp_dir)
+    }
+
+    /// A request as the interceptor passes it on for a user with `role`
+    fn request_as<T>(role: Role, message: T) -> tonic::Request<T> {
+        let hash_config = PasswordHashConfig::pbkdf2_sha256(1);
+        let mut user = User::new_with_hash_config("user".to_string(), "user@example.com".to_string(), PASSWORD, &hash_config)
+            .unwrap();
+        user.roles = vec![role];
+        let mut request = tonic::Request::new(message);
+        request.extensions_mut().insert(user);
+        request
+    }
+
+    fn admin_request<T>(message: T) -> tonic::Request<T> {
+        request_as(Role::Admin, message)
+    }
+
+    fn query_request(cypher: &str, batch_size: u32) -> tonic::Request<proto::QueryRequest> {
+        admin_request(proto::QueryRequest {
+            graph: "test".to_string(),
+            cypher: cypher.to_string(),
+            parameters: HashMap::new(),
+            batch_size,
+        })
+    }
+
+    async fn create_person(service: &GrpcService, name: &str) -> proto::Node {
+        service
+            .create_node(admin_request(proto::CreateNodeRequest {
+                graph: "test".to_string(),
+                labels: vec!["Person".to_string()],
+                properties: query_parameters([("name", name)]),
+            }))
+            .await
+            .unwrap()
+            .into_inner()
+    }
+
+    #[tokio::test]
+    async fn test_grpc_create_and_get_node() {
+        let (service, _dir) = create_test_service();
+        let created = create_person(&service, "Alice").await;
+
+        let node = service
+            .get_node(admin_request(proto::GetNodeRequest {
+                graph: "test".to_string(),
+                id: created.id,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(node.labels, vec!["Person".to_string()]);
+        assert_eq!(
+            PropertyValue::from(node.properties["name"].clone()),
+            PropertyValue::String("Alice".to_string())
+        );
+
+        let missing = service
+            .get_node(admin_request(proto::GetNodeRequest {
+                graph: "test".to_string(),
+                id: created.id + 100,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(missing.code(), tonic::Code::NotFound);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_relationships() {
+        let (service, _dir) = create_test_service();
+        let alice = create_person(&service, "Alice").await;
+        let bob = create_person(&service, "Bob").await;
+
+        let created = service
+            .create_relationship(admin_request(proto::CreateRelationshipRequest {
+                graph: "test".to_string(),
+                start_node: alice.id,
+                r#type: "KNOWS".to_string(),
+                end_node: bob.id,
+                properties: query_parameters([("since", 2020i64)]),
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+
+        let rel = service
+            .get_relationship(admin_request(proto::GetRelationshipRequest {
+                graph: "test".to_string(),
+                id: created.id,
+            }))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(rel.r#type, "KNOWS");
+        assert_eq!((rel.start_node, rel.end_node), (alice.id, bob.id));
+    }
+
+    #[test]
+    fn test_grpc_auth_interceptor() {
+        let (auth_service, api_key) = create_auth_service_with_api_key();
+        let credentials = Credentials {
+            username: "alice".to_string(),
+            password: PASSWORD.to_string(),
+        };
+        let token = auth_service.login(credentials).unwrap().1.access_token;
+        let mut interceptor = GrpcAuthInterceptor { auth_service };
+
+        // The username the request is passed on as, or the status code it fails with
+        let call = |interceptor: &mut GrpcAuthInterceptor, header: Option<(&'static str, &str)>| {
+            let mut request = tonic::Request::new(());
+            if let Some((header, value)) = header {
+                request.metadata_mut().insert(header, value.parse().unwrap());
+            }
+            match interceptor.call(request) {
+                Ok(request) => Ok(request.extensions().get::<User>().unwrap().username.clone()),
+                Err(status) => Err(status.code()),
+            }
+        };
+        let bearer = format!("Bearer {}", token);
+        for header in [("authorization", bearer.as_str()), ("x-api-key", api_key.as_str())] {
+            assert_eq!(call(&mut interceptor, Some(header)), Ok("alice".to_string()));
+        }
+
+        let unauthenticated = Err(tonic::Code::Unauthenticated);
+        assert_eq!(call(&mut interceptor, None), unauthenticated);
+        for header in [("authorization", "Bearer invalid"), ("authorization", token.as_str()), ("x-api-key", "invalid")] {
+            assert_eq!(call(&mut interceptor, Some(header)), unauthenticated, "{:?}", header);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_grpc_permissions() {
+        let (service, _dir) = create_test_service();
+        let alice = create_person(&service, "Alice").await;
+
+        // Calls that did not pass the interceptor are rejected
+        let error = service
+            .get_node(tonic::Request::new(proto::GetNodeRequest { graph: "test".to_string(), id: alice.id }))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::Unauthenticated);
+
+        // Read-only users can read but not write
+        let read = |cypher: &str| request_as(Role::Read, query_request(cypher, 0).into_inner());
+        let get_node = proto::GetNodeRequest { graph: "test".to_string(), id: alice.id };
+        assert!(service.get_node(request_as(Role::Read, get_node)).await.is_ok());
+        let response = service.execute_query(read("MATCH (p:Person) RETURN p.name AS name")).await.unwrap();
+        assert_eq!(response.into_inner().rows.len(), 1);
+
+        let error = service
+            .execute_query(read("MATCH (p:Person) SET p.name = 'Mallory'"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::PermissionDenied);
+        let error = service
+            .stream_query_results(read("MATCH (p:Person) DETACH DELETE p"))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(error.code(), tonic::Code::PermissionDenied);
+        let create_node = proto::CreateNodeRequest {
+            graph: "test".to_string(),
+            labels: vec!["Person".to_string()],
+            properties: HashMap::new(),
+        };
+        let error = service.create_node(request_as(Role::Read, create_node)).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::PermissionDenied);
+
+        let names = service
+            .execute_query(query_request("MATCH (p:Person) WHERE p.name = 'Alice' RETURN p.name AS name", 0))
+            .await
+            .unwrap()
+            .into_inner();
+        assert_eq!(names.rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_execute_query() {
+        let (service, _dir) = create_test_service();
+        create_person(&service, "Alice").await;
+
+        let mut request = query_request("MATCH (p:Person) WHERE p.name = $name RETURN p.name AS name", 0);
+        request.get_mut().parameters = query_parameters([("name", "Alice")]);
+
+        let response = service.execute_query(request).await.unwrap().into_inner();
+        assert_eq!(response.columns, vec!["name".to_string()]);
+        assert_eq!(response.rows.len(), 1);
+        assert!(response.stats.is_some());
+
+        let error = service
+            .execute_query(query_request("NOT A QUERY", 0))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_parser_limits() {
+        let (service, _dir) = create_test_service();
+        let service = service.with_parser_limits(ParserLimits { max_depth: 8, max_tokens: 20 });
+        create_person(&service, "Alice").await;
+
+        let response = service
+            .execute_query(query_request("MATCH (p:Person) RETURN p.name AS name", 0))
+            .await
+            .unwrap();
+        assert_eq!(response.into_inner().rows.len(), 1);
+
+        let long = format!("MATCH (p){} RETURN p", "-->(q)".repeat(10));
+        let error = service.execute_query(query_request(&long, 0)).await.unwrap_err();
+        assert_eq!(error.code(), tonic::Code::InvalidArgument);
+        assert!(error.message().contains("maximum of 20 tokens"), "{}", error.message());
+    }
+
+    #[tokio::test]
+    async fn test_grpc_default_limit() {
+        let (service, _dir) = create_test_service();
+        let service = service.with_default_limit(10);
+        for i in 0..25 {
+            create_person(&service, &format!("Person{}", i)).await;
+        }
+
+        let capped = service
+            .execute_query(query_request("MATCH (p:Person) RETURN p.name AS name", 0))
+            .await
+            .unwrap();
+        assert_eq!(capped.into_inner().rows.len(), 10);
+
+        // An explicit LIMIT overrides the default
+        let explicit = service
+            .execute_query(query_request("MATCH (p:Person) RETURN p.name AS name LIMIT 20", 0))
+            .await
+            .unwrap();
+        assert_eq!(explicit.into_inner().rows.len(), 20);
+    }
+
+    #[tokio::test]
+    async fn test_grpc_stream_query_results() {
+        let (service, _dir) = create_test_service();
+        for i in 0..25 {
+            create_person(&service, &format!("Person{}", i)).await;
+        }
+
+        let stream = service
+            .stream_query_results(query_request("MATCH (p:Person) RETURN p.name AS name", 10))
+            .await
+            .unwrap()
+            .into_inner();
+        let chunks: Vec<_> = stream.map(|chunk| chunk.unwrap()).collect().await;
+
+        assert_eq!(
+            chunks.iter().map(|c| c.rows.len()).collect::<Vec<_>>(),
+            vec![10, 10, 5]
+        );
+        assert_eq!(chunks[0].columns, vec!["name".to_string()]);
+        assert!(chunks[1].columns.is_empty());
+        assert!(chunks[0].stats.is_none());
+        assert!(chunks[2].stats.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_grpc_stream_empty_result() {
+        let (service, _dir) = create_test_service();
+
+        let stream = service
+            .stream_query_results(query_request("MATCH (p:Person) RETURN p.name AS name", 10))
+            .await
+            .unwrap()
+            .into_inner();
+        let chunks: Vec<_> = stream.map(|chunk| chunk.unwrap()).collect().await;
+
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].columns, vec!["name".to_string()]);
+        assert!(chunks[0].rows.is_empty());
+        assert!(chunks[0].stats.is_some());
+    }
+}
@@ -1,17 +1,19 @@
 //! HTTP/REST API server implementation using Axum
 
 use axum::{
-    extract::{Path, Query as AxumQuery, State, FromRef},
-    http::StatusCode,
+    extract::{ConnectInfo, Path, Query as AxumQuery, State, FromRef},
+    http::{Method, StatusCode, Uri},
     response::{IntoResponse, Json},
     routing::{delete, get, post, put},
     Router,
 };
-use qilbee_core::{EntityId, Label, NodeId, Property, PropertyValue};
-use qilbee_graph::Database;
+use qilbee_core::temporal::{EventTime, TransactionTime};
+use qilbee_core::{Direction, EntityId, Label, Node, NodeId, Property, PropertyValue, Relationship};
+use qilbee_graph::{Database, NodeOrder};
 use qilbee_memory::{
-    AgentMemory, Episode, EpisodeContent, EpisodeType,
-    LLMConfig, LLMProviderType, LLMService,
+    CircuitState, DatabaseMemoryStorage, Episode, EpisodeContent, EpisodeCursor, EpisodePage,
+    EpisodeType, ForgetCriteria, ForgetReport, InMemoryStorage, LLMConfig, LLMProviderType,
+    LLMService, MemoryConfig, MemoryStorage, PersistentAgentMemory, SemanticSearchConfig,
 };
 use qilbee_protocol::http::HealthResponse;
 use std::collections::HashMap as StdHashMap;
@@ -19,16 +21,23 @@ use std::sync::Mutex;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::sync::Arc;
 use std::time::Instant;
 use tower_http::trace::TraceLayer;
 
+use crate::config::ServerConfig;
+use crate::export::DUMP_CONTENT_TYPE;
+use crate::import::{ImportFormat, ImportReport, DEFAULT_IMPORT_BATCH_SIZE};
+use crate::metrics::{
+    render_prometheus, track_requests, IpNetwork, QueryOperation, ServerMetrics, PROMETHEUS_CONTENT_TYPE,
+};
 use crate::security::{
-    AuthService, UserService, TokenService, Credentials, AuthConfig,
+    AuthService, UserService, TokenService, Credentials, AuthConfig, RefreshOutcome,
     RateLimitService, AuthMiddleware, global_rate_limit, require_auth, RbacService, AuditService, AuditConfig,
     AuditEventType, AuditResult, TokenBlacklist, BlacklistConfig, RevocationReason,
     AccountLockoutService, LockoutConfig, security_headers_middleware, CorsConfig,
-    https_redirect_middleware,
+    https_redirect_middleware, optional_auth, Permission, User, generate_password,
 };
 
 /// Shared application state
@@ -36,7 +45,13 @@ use crate::security::{
 pub struct AppState {
     pub database: Arc<Database>,
     pub start_time: Instant,
-    pub agent_memories: Arc<Mutex<StdHashMap<String, Arc<AgentMemory>>>>,
+    pub agent_memories: Arc<Mutex<StdHashMap<String, Arc<PersistentAgentMemory>>>>,
+    /// Episode storage shared by all agent memories
+    pub memory_storage: Arc<dyn MemoryStorage>,
+    /// Semantic search over agent memory (`None` when embeddings are disabled)
+    pub semantic_search: Option<SemanticSearchConfig>,
+    /// Semantic search for agents with their own embedding provider
+    pub agent_semantic_search: Arc<StdHashMap<String, SemanticSearchConfig>>,
     pub auth_service: Arc<AuthService>,
     pub token_service: Arc<TokenService>,
     pub user_service: Arc<UserService>,
@@ -46,36 +61,204 @@ pub struct AppState {
     pub auth_middleware: AuthMiddleware,
     /// LLM service for memory consolidation (runtime configurable)
     pub llm_service: Arc<LLMService>,
+    /// LIMIT applied to read queries that don't specify one
+    pub default_query_limit: Option<usize>,
+    /// Nesting depth and size limits for parsing queries
+    pub parser_limits: qilbee_query::ParserLimits,
+    /// Results of read queries (`None` when caching is disabled)
+    pub query_cache: Option<Arc<qilbee_query::QueryCache>>,
+    /// Request counts and query latencies for `/metrics`
+    pub metrics: Arc<ServerMetrics>,
+    /// Networks allowed to scrape `/metrics` without credentials
+    pub metrics_allowlist: Arc<Vec<IpNetwork>>,
+    /// Directory graph checkpoints are kept in
+    pub backup_dir: Arc<std::path::PathBuf>,
+}
+
+/// Restore an agent's vector index after a restart
+///
+/// Loads the saved index and embeds only episodes stored since it was saved.
+/// Without a usable saved index, the index is rebuilt from all episodes.
+async fn restore_vector_index(memory: &PersistentAgentMemory) {
+    let agent_id = memory.agent_id();
+    let restored = match memory.load_vector_index().await {
+        Ok(true) => memory.index_missing_episodes().await.map(|_| ()),
+        Ok(false) => memory.rebuild_vector_index().await.map(|_| ()),
+        Err(e) => {
+            tracing::warn!("Rebuilding vector index for agent {}: {}", agent_id, e);
+            memory.rebuild_vector_index().await.map(|_| ())
+        }
+    };
+    if let Err(e) = restored {
+        tracing::warn!("Failed to restore vector index for agent {}: {}", agent_id, e);
+    }
 }
 
 /// Implement FromRef to allow extracting AuthMiddleware from AppState in middleware
+impl AppState {
+    /// Semantic search configuration of an agent, falling back to the server-wide one
+    fn semantic_config(&self, agent_id: &str) -> Option<&SemanticSearchConfig> {
+        self.agent_semantic_search.get(agent_id).or(self.semantic_search.as_ref())
+    }
+
+    /// Get the memory of an agent, creating it on first use
+    ///
+    /// With semantic search enabled, the vector index of a newly loaded agent
+    /// is restored with [`restore_vector_index`].
+    async fn agent_memory(&self, agent_id: &str) -> Arc<PersistentAgentMemory> {
+        if let Some(memory) = self.agent_memories.lock().unwrap().get(agent_id) {
+            return memory.clone();
+        }
+
+        let mut memory =
+            PersistentAgentMemory::with_storage(MemoryConfig::new(agent_id), self.memory_storage.clone());
+        if let Some(semantic_config) = self.semantic_config(agent_id) {
+            memory = match memory.with_semantic_search(semantic_config.clone()) {
+                Ok(memory) => memory,
+                Err(e) => {
+                    tracing::warn!("Semantic search disabled for agent {}: {}", agent_id, e);
+                    PersistentAgentMemory::with_storage(
+                        MemoryConfig::new(agent_id),
+                        self.memory_storage.clone(),
+                    )
+                }
+            };
+        }
+        if memory.has_semantic_search() {
+            restore_vector_index(&memory).await;
+        }
+
+        self.agent_memories
+            .lock()
+            .unwrap()
+            .entry(agent_id.to_string())
+            .or_insert_with(|| Arc::new(memory))
+            .clone()
+    }
+
+    /// Get the memory of an agent that has stored episodes, including ones
+    /// persisted before a restart
+    async fn existing_agent_memory(&self, agent_id: &str) -> Option<Arc<PersistentAgentMemory>> {
+        if let Some(memory) = self.agent_memories.lock().unwrap().get(agent_id) {
+            return Some(memory.clone());
+        }
+
+        match self.memory_storage.episode_count(agent_id).await {
+            Ok(0) => None,
+            Ok(_) => Some(self.agent_memory(agent_id).await),
+            Err(e) => {
+                tracing::warn!("Failed to load memory for agent {}: {}", agent_id, e);
+                None
+            }
+        }
+    }
+}
+
 impl FromRef<AppState> for AuthMiddleware {
     fn from_ref(state: &AppState) -> Self {
         state.auth_middleware.clone()
     }
 }
 
-/// Create HTTP server router
+/// Create HTTP server router with the default server configuration
 pub fn create_router(database: Arc<Database>) -> Router {
+    create_router_with_config(database, &ServerConfig::default())
+}
+
+/// How often expired entries are removed from the token blacklist
+const BLACKLIST_CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(3600);
+
+/// Create HTTP server router
+///
+/// # Panics
+///
+/// Panics if the configured JWT signing keys or password hash parameters are
+/// invalid, the persisted user accounts, token blacklist or refresh tokens
+/// cannot be loaded, or the bootstrap admin user cannot be created.
+pub fn create_router_with_config(database: Arc<Database>, config: &ServerConfig) -> Router {
+    create_router_with_state(create_app_state(database, config))
+}
+
+/// Create the `admin` account if no active admin exists
+///
+/// The account is created in memory first and then imported, so the first
+/// run with persisted users stores the same accounts a run without
+/// persistence starts with. Without a configured admin password, a random
+/// one is generated and logged once.
+fn bootstrap_admin(user_service: &UserService, config: &ServerConfig) -> qilbee_core::Result<()> {
+    if user_service.has_admin() {
+        return Ok(());
+    }
+
+    let password = config.admin_password.clone().unwrap_or_else(generate_password);
+    let in_memory = UserService::new().with_password_hash_config(config.password_hash.clone())?;
+    in_memory.create_default_admin(&password)?;
+    // Nothing is imported once the database holds users, none of them an admin
+    if user_service.import_users(&in_memory)? == 0 {
+        user_service.create_default_admin(&password)?;
+    }
+
+    if config.admin_password.is_none() {
+        tracing::warn!(
+            "Created user 'admin' with generated password {}. Change it after logging in.",
+            password
+        );
+    }
+    Ok(())
+}
+
+/// Create the state shared by the HTTP routes
+///
+/// Other listeners use its services to authenticate clients the same way
+/// the HTTP API does.
+///
+/// # Panics
+///
+/// Panics if the configured JWT signing keys or password hash parameters are
+/// invalid, the persisted user accounts, token blacklist or refresh tokens
+/// cannot be loaded, or the bootstrap admin user cannot be created.
+pub fn create_app_state(database: Arc<Database>, config: &ServerConfig) -> AppState {
     // Initialize security services
-    let user_service = Arc::new(UserService::new());
-    let token_service = Arc::new(TokenService::new("qilbee_jwt_secret_change_in_production".to_string()));
+    // User accounts live in the database's users column family unless persistence is disabled
+    let user_service = Arc::new(
+        if config.persist_users {
+            UserService::with_storage(database.storage().clone()).expect("Failed to load user accounts")
+        } else {
+            UserService::new()
+        }
+        .with_password_hash_config(config.password_hash.clone())
+        .expect("Invalid password hash configuration"),
+    );
+    let token_service = Arc::new(config.token_service().expect("Invalid JWT signing keys"));
     let rate_limit_service = Arc::new(RateLimitService::new());
 
-    // Create bootstrap admin user for testing
-    // TODO: Replace with proper bootstrap process
-    let _ = user_service.create_default_admin("SecureAdmin@123!");
+    // Create the bootstrap admin user, unless an admin already exists
+    bootstrap_admin(&user_service, config).expect("Failed to create the bootstrap admin user");
 
-    // Create token blacklist (in-memory for now, can add persistence later)
-    let token_blacklist = Arc::new(TokenBlacklist::new(BlacklistConfig::default()));
+    // Revoked tokens live in the database's token blacklist column family unless persistence is disabled
+    let token_blacklist = Arc::new(if config.persist_token_blacklist {
+        TokenBlacklist::with_storage(database.storage().clone()).expect("Failed to load the token blacklist")
+    } else {
+        TokenBlacklist::new(BlacklistConfig::default())
+    });
+    if tokio::runtime::Handle::try_current().is_ok() {
+        token_blacklist.spawn_cleanup(BLACKLIST_CLEANUP_INTERVAL);
+    }
 
     let token_service_clone = token_service.clone();
-    let auth_service = Arc::new(AuthService::new(
+    // Issued refresh tokens are persisted next to the token blacklist
+    let mut auth_service = AuthService::new(
         user_service.clone(),
         token_service,
         token_blacklist,
         AuthConfig::default(),
-    ));
+    );
+    if config.persist_token_blacklist {
+        auth_service = auth_service
+            .with_storage(database.storage().clone())
+            .expect("Failed to load refresh tokens");
+    }
+    let auth_service = Arc::new(auth_service);
 
     // Create RBAC and Audit services for AuthMiddleware
     let rbac_service = Arc::new(RbacService::new());
@@ -102,24 +285,64 @@ pub fn create_router(database: Arc<Database>) -> Router {
         _ => Arc::new(LLMService::mock()),
     };
 
-    let state = AppState {
+    // Agent memory lives in the database's memory column family unless persistence is disabled
+    let memory_storage: Arc<dyn MemoryStorage> = if config.persist_agent_memory {
+        Arc::new(DatabaseMemoryStorage::new(database.storage().clone()))
+    } else {
+        Arc::new(InMemoryStorage::new())
+    };
+
+    AppState {
         database,
         start_time: Instant::now(),
         agent_memories: Arc::new(Mutex::new(StdHashMap::new())),
+        memory_storage,
+        semantic_search: config.memory_embeddings.clone().map(|embeddings| {
+            SemanticSearchConfig::new(embeddings).with_metric(config.memory_similarity_metric)
+        }),
+        agent_semantic_search: Arc::new(
+            config
+                .agent_memory_embeddings
+                .iter()
+                .map(|(agent_id, embeddings)| {
+                    let semantic_config = SemanticSearchConfig::new(embeddings.clone())
+                        .with_metric(config.memory_similarity_metric);
+                    (agent_id.clone(), semantic_config)
+                })
+                .collect(),
+        ),
         auth_service,
         token_service: token_service_clone,
         user_service: user_service.clone(),
         rate_limit_service,
         audit_service,
         lockout_service,
-        auth_middleware: auth_middleware.clone(),
+        auth_middleware,
         llm_service,
-    };
+        default_query_limit: config.default_query_limit,
+        parser_limits: config.parser_limits(),
+        query_cache: config.query_cache().map(Arc::new),
+        metrics: Arc::new(ServerMetrics::new()),
+        metrics_allowlist: Arc::new(config.metrics_allowlist.clone()),
+        backup_dir: Arc::new(config.backup_dir.clone()),
+    }
+}
+
+/// Create HTTP server router over existing state
+pub fn create_router_with_state(state: AppState) -> Router {
+    let auth_middleware = state.auth_middleware.clone();
+    let metrics = state.metrics.clone();
 
     // Build router with all routes and apply global rate limiting
     Router::new()
-        // Health check (rate limiting skipped in global middleware)
+        // Health and readiness checks (rate limiting skipped in global middleware)
         .route("/health", get(health_check))
+        .route("/ready", get(readiness_check))
+        .route("/metrics", get(prometheus_metrics))
+        // OpenAPI description of the graph, memory and auth routes
+        .route("/openapi.json", get(openapi_document))
+        // Public keys validating the JWTs the server issues
+        .route("/.well-known/jwks.json", get(jwks))
         // Auth endpoints
         .route("/api/v1/auth/login", post(auth_login))
         .route("/api/v1/auth/logout", post(auth_logout))
@@ -146,15 +369,50 @@ pub fn create_router(database: Arc<Database>) -> Router {
         // LLM configuration (Admin only)
         .route("/api/v1/llm/status", get(llm_status))
         .route("/api/v1/llm/config", put(llm_update_config))
+        // Memory maintenance (Admin only)
+        .route("/admin/memory/usage", get(memory_usage))
+        .route("/admin/memory/forget-all", post(forget_all_memory))
+        .route("/admin/graphs/:name/checkpoint", post(checkpoint_graph))
+        .route("/admin/graphs/:name/restore", post(restore_graph))
         // Graph operations
         .route("/graphs/:name", post(create_graph).delete(delete_graph))
-        .route("/graphs/:name/nodes", post(create_node).get(find_nodes))
+        .route("/graphs/:name/nodes", post(create_node).get(find_nodes).delete(delete_nodes_by_label))
         .route("/graphs/:name/nodes/:id", get(get_node).put(update_node).delete(delete_node))
         .route("/graphs/:name/relationships", post(create_relationship))
+        .route("/graphs/:name/relationships/batch", post(create_relationships_batch))
+        .route(
+            "/graphs/:name/import",
+            post(bulk_import).layer(axum::extract::DefaultBodyLimit::max(MAX_IMPORT_BODY_BYTES)),
+        )
+        .route("/graphs/:name/export", get(export_graph))
+        .route("/graphs/:name/stats", get(graph_stats))
         .route("/graphs/:name/nodes/:id/relationships", get(get_relationships))
-        .route("/graphs/:name/query", post(execute_query))
+        .route("/graphs/:name/diff", get(graph_diff))
+        // Credentials are optional; when given, audit events name the caller
+        .route(
+            "/graphs/:name/query",
+            post(execute_query).layer(axum::middleware::from_fn_with_state(
+                auth_middleware.clone(),
+                optional_auth,
+            )),
+        )
+        .route("/graphs/:name/query/estimate", post(estimate_query))
+        // Browsers can't set headers on WebSocket upgrades, so credentials may
+        // also arrive as a query parameter or in the first frame
+        .route(
+            "/graphs/:name/query/ws",
+            get(query_websocket).layer(axum::middleware::from_fn_with_state(
+                auth_middleware.clone(),
+                optional_auth,
+            )),
+        )
         // Memory operations (require authentication)
         .nest("/memory", memory_routes(auth_middleware.clone()))
+        // Count requests per matched route for /metrics
+        .route_layer(axum::middleware::from_fn_with_state(metrics, track_requests))
+        // JSON errors for unknown paths and for methods a route does not support
+        .fallback(route_not_found)
+        .method_not_allowed_fallback(method_not_allowed)
         // Apply global rate limiting middleware (determines endpoint type from path)
         // Uses from_fn_with_state for proper state access in middleware
         .layer(axum::middleware::from_fn_with_state(auth_middleware, global_rate_limit))
@@ -174,17 +432,21 @@ pub fn create_router(database: Arc<Database>) -> Router {
 /// All memory operations require valid authentication (JWT token or API key)
 fn memory_routes(auth_middleware: AuthMiddleware) -> Router<AppState> {
     Router::new()
-        .route("/:agent_id/episodes", post(store_episode))
+        .route("/:agent_id/episodes", post(store_episode).get(list_episodes))
+        .route("/:agent_id/episodes/batch", post(store_episodes_batch))
         .route("/:agent_id/episodes/:id", get(get_episode))
         .route("/:agent_id/episodes/:id/similar", get(find_similar_episodes))
         .route("/:agent_id/episodes/recent", get(get_recent_episodes))
+        .route("/:agent_id/episodes/as-of", get(get_episodes_as_of))
         .route("/:agent_id/episodes/search", post(search_episodes))
         .route("/:agent_id/episodes/semantic-search", post(semantic_search))
         .route("/:agent_id/episodes/hybrid-search", post(hybrid_search))
+        .route("/:agent_id/episodes/index", post(index_missing_episodes))
         .route("/:agent_id/statistics", get(get_memory_statistics))
         .route("/:agent_id/semantic-search/status", get(get_semantic_search_status))
         .route("/:agent_id/consolidate", post(consolidate_memory))
         .route("/:agent_id/forget", post(forget_memory))
+        .route("/:agent_id/export", get(export_episodes))
         .route("/:agent_id", delete(clear_memory))
         // Apply authentication middleware to all memory routes
         .layer(axum::middleware::from_fn_with_state(auth_middleware, require_auth))
@@ -202,6 +464,103 @@ async fn health_check(State(state): State<AppState>) -> impl IntoResponse {
     Json(response)
 }
 
+/// Readiness of the server and its external dependencies
+///
+/// The server stays ready while the LLM circuit breaker is open, since only
+/// memory consolidation depends on it, but reports itself as degraded.
+async fn readiness_check(State(state): State<AppState>) -> impl IntoResponse {
+    let llm = state.llm_service.circuit_breaker_status().await;
+    let status = match llm.state {
+        CircuitState::Closed => "ready",
+        CircuitState::Open | CircuitState::HalfOpen => "degraded",
+    };
+
+    Json(json!({
+        "status": status,
+        "checks": {
+            "llm": {
+                "circuit_breaker": llm
+            }
+        }
+    }))
+}
+
+/// Server and storage engine metrics in the Prometheus text format
+///
+/// Peers in the metrics allowlist scrape without credentials; anyone else
+/// needs a JWT or API key.
+async fn prometheus_metrics(
+    State(state): State<AppState>,
+    peer: Option<ConnectInfo<SocketAddr>>,
+    headers: axum::http::HeaderMap,
+) -> axum::response::Response {
+    let allowlisted = peer.is_some_and(|ConnectInfo(addr)| {
+        state.metrics_allowlist.iter().any(|network| network.contains(addr.ip()))
+    });
+    if !allowlisted && let Err(status) = extract_user_from_auth(&headers, &state) {
+        return (
+            status,
+            Json(json!({"error": "Unauthorized: metrics require authentication or an allowlisted address"})),
+        )
+            .into_response();
+    }
+
+    let active_agent_memories = state.agent_memories.lock().map(|memories| memories.len()).unwrap_or(0);
+    let storage = state.database.storage().clone();
+    match tokio::task::spawn_blocking(move || storage.metrics()).await {
+        Ok(Ok(storage_metrics)) => (
+            [(axum::http::header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)],
+            render_prometheus(&state.metrics, &storage_metrics, active_agent_memories),
+        )
+            .into_response(),
+        Ok(Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": e.to_string()})),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Metrics collection failed: {}", e)})),
+        )
+            .into_response(),
+    }
+}
+
+/// Respond to a path that matches no route
+async fn route_not_found(method: Method, uri: Uri) -> impl IntoResponse {
+    (
+        StatusCode::NOT_FOUND,
+        Json(json!({
+            "error": format!("No route for {} {}", method, uri.path()),
+            "error_code": "NOT_FOUND"
+        })),
+    )
+}
+
+/// Respond to a known path requested with an unsupported method
+///
+/// Axum adds the `Allow` header listing the route's methods to this response.
+async fn method_not_allowed(method: Method, uri: Uri) -> impl IntoResponse {
+    (
+        StatusCode::METHOD_NOT_ALLOWED,
+        Json(json!({
+            "error": format!("Method {} is not allowed for {}", method, uri.path()),
+            "error_code": "METHOD_NOT_ALLOWED"
+        })),
+    )
+}
+
+async fn openapi_document() -> impl IntoResponse {
+    Json(crate::openapi::openapi_spec())
+}
+
+/// Public keys validating the JWTs the server issues, as a JSON Web Key Set
+///
+/// Empty when JWTs are signed with a shared secret.
+async fn jwks(State(state): State<AppState>) -> impl IntoResponse {
+    Json(state.token_service.jwks())
+}
+
 // ==================== Graph Management ====================
 
 async fn create_graph(
@@ -211,7 +570,7 @@ async fn create_graph(
     match state.database.create_graph(&name) {
         Ok(_) => (StatusCode::CREATED, Json(json!({"name": name}))),
         Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            quota_error_status(&e, StatusCode::INTERNAL_SERVER_ERROR),
             Json(json!({"error": e.to_string()})),
         ),
     }
@@ -230,6 +589,14 @@ async fn delete_graph(
     }
 }
 
+/// Map quota violations to 403 Forbidden, other errors to `fallback`
+fn quota_error_status(error: &qilbee_core::Error, fallback: StatusCode) -> StatusCode {
+    match error {
+        qilbee_core::Error::QuotaExceeded(_) => StatusCode::FORBIDDEN,
+        _ => fallback,
+    }
+}
+
 // ==================== Node Operations ====================
 
 #[derive(Debug, Deserialize)]
@@ -245,6 +612,16 @@ struct NodeResponse {
     properties: HashMap<String, Value>,
 }
 
+impl From<&Node> for NodeResponse {
+    fn from(node: &Node) -> Self {
+        Self {
+            id: node.id.as_internal(),
+            labels: node.labels.iter().map(|l| l.name().to_string()).collect(),
+            properties: property_to_json_map(&node.properties),
+        }
+    }
+}
+
 async fn create_node(
     State(state): State<AppState>,
     Path(graph_name): Path<String>,
@@ -254,7 +631,7 @@ async fn create_node(
         Ok(g) => g,
         Err(e) => {
             return (
-                StatusCode::NOT_FOUND,
+                quota_error_status(&e, StatusCode::NOT_FOUND),
                 Json(json!({"error": e.to_string()})),
             );
         }
@@ -276,7 +653,7 @@ async fn create_node(
             (StatusCode::CREATED, Json(json!(response)))
         }
         Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            quota_error_status(&e, StatusCode::INTERNAL_SERVER_ERROR),
             Json(json!({"error": e.to_string()})),
         ),
     }
@@ -440,6 +817,9 @@ async fn delete_node(
 struct FindNodesQuery {
     label: Option<String>,
     limit: Option<usize>,
+    /// `id` for internal ID order, or the name of a property to sort by
+    #[serde(rename = "orderBy")]
+    order_by: Option<String>,
 }
 
 async fn find_nodes(
@@ -457,9 +837,15 @@ async fn find_nodes(
         }
     };
 
+    let order = match query.order_by.as_deref() {
+        None => NodeOrder::Unordered,
+        Some("id") => NodeOrder::Id,
+        Some(property) => NodeOrder::Property(property.to_string()),
+    };
+
     // Get nodes by label if specified, otherwise get all nodes
     let core_nodes = if let Some(label) = &query.label {
-        match graph.find_nodes_by_label(label) {
+        match graph.find_nodes_by_label_ordered(label, &order) {
             Ok(nodes) => nodes,
             Err(e) => {
                 return (
@@ -469,7 +855,7 @@ async fn find_nodes(
             }
         }
     } else {
-        match graph.get_all_nodes() {
+        match graph.get_all_nodes_ordered(&order) {
             Ok(nodes) => nodes,
             Err(e) => {
                 return (
@@ -504,6 +890,79 @@ async fn find_nodes(
     )
 }
 
+/// Nodes deleted per write batch by [`delete_nodes_by_label`]
+const DELETE_BATCH_NODES: usize = 1000;
+
+#[derive(Debug, Deserialize)]
+struct DeleteNodesQuery {
+    label: Option<String>,
+    /// Must be `true`, so that a mass delete is never sent by accident
+    #[serde(default)]
+    confirm: bool,
+}
+
+/// Delete every node with a label, together with their relationships
+///
+/// DELETE /graphs/:name/nodes?label=Temp&confirm=true
+///
+/// Requires the `GraphDelete` permission.
+async fn delete_nodes_by_label(
+    State(state): State<AppState>,
+    Path(graph_name): Path<String>,
+    AxumQuery(query): AxumQuery<DeleteNodesQuery>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    if let Err(status) = extract_user_with_permission(&headers, &state, &Permission::GraphDelete) {
+        return (
+            status,
+            Json(json!({"error": "Unauthorized: deleting nodes by label requires the GraphDelete permission"})),
+        );
+    }
+    let Some(label) = query.label else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": "The label query parameter is required"})),
+        );
+    };
+    if !query.confirm {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": format!("Deleting every node labeled {} requires confirm=true", label)})),
+        );
+    }
+
+    let graph = match state.database.graph_exists(&graph_name) {
+        Ok(true) => state.database.graph(&graph_name),
+        Ok(false) => Err(qilbee_core::Error::GraphNotFound(graph_name.clone())),
+        Err(e) => Err(e),
+    };
+    let graph = match graph {
+        Ok(g) => g,
+        Err(e) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": e.to_string()})));
+        }
+    };
+
+    let deleted = tokio::task::spawn_blocking(move || {
+        graph.detach_delete_nodes_by_label(&label, DELETE_BATCH_NODES)
+    })
+    .await;
+    match deleted {
+        Ok(Ok((nodes, relationships))) => (
+            StatusCode::OK,
+            Json(json!({"nodesDeleted": nodes, "relationshipsDeleted": relationships})),
+        ),
+        Ok(Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": e.to_string()})),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Delete failed: {}", e)})),
+        ),
+    }
+}
+
 // ==================== Relationship Operations ====================
 
 #[derive(Debug, Deserialize)]
@@ -529,6 +988,18 @@ struct RelationshipResponse {
     properties: HashMap<String, Value>,
 }
 
+impl From<&Relationship> for RelationshipResponse {
+    fn from(rel: &Relationship) -> Self {
+        Self {
+            id: rel.id.as_internal(),
+            rel_type: rel.rel_type.name().to_string(),
+            start_node: rel.source.as_internal(),
+            end_node: rel.target.as_internal(),
+            properties: property_to_json_map(&rel.properties),
+        }
+    }
+}
+
 async fn create_relationship(
     State(state): State<AppState>,
     Path(graph_name): Path<String>,
@@ -538,7 +1009,7 @@ async fn create_relationship(
         Ok(g) => g,
         Err(e) => {
             return (
-                StatusCode::NOT_FOUND,
+                quota_error_status(&e, StatusCode::NOT_FOUND),
                 Json(json!({"error": e.to_string()})),
             );
         }
@@ -562,359 +1033,463 @@ async fn create_relationship(
             (StatusCode::CREATED, Json(json!(response)))
         }
         Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
+            quota_error_status(&e, StatusCode::INTERNAL_SERVER_ERROR),
             Json(json!({"error": e.to_string()})),
         ),
     }
 }
 
-async fn get_relationships(
-    State(_state): State<AppState>,
-    Path((_graph_name, _node_id)): Path<(String, String)>,
-) -> impl IntoResponse {
-    // TODO: Implement relationship retrieval
-    let relationships: Vec<RelationshipResponse> = vec![];
-    (StatusCode::OK, Json(json!({"relationships": relationships})))
-}
-
-// ==================== Query Operations ====================
-
-#[derive(Debug, Deserialize)]
-struct QueryRequestJson {
-    cypher: String,
-    parameters: Option<HashMap<String, Value>>,
-}
+/// Maximum number of relationships in one batch create request
+const MAX_BATCH_RELATIONSHIPS: usize = 10_000;
 
-async fn execute_query(
+async fn create_relationships_batch(
     State(state): State<AppState>,
     Path(graph_name): Path<String>,
-    Json(request): Json<QueryRequestJson>,
+    Json(requests): Json<Vec<CreateRelationshipRequest>>,
 ) -> impl IntoResponse {
-    use qilbee_query::{parse_simple, QueryPlanner, QueryExecutor};
-    use std::sync::Arc;
+    if requests.len() > MAX_BATCH_RELATIONSHIPS {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!(
+                    "A batch may contain at most {} relationships",
+                    MAX_BATCH_RELATIONSHIPS
+                ),
+                "error_code": "VALIDATION_ERROR"
+            })),
+        );
+    }
 
     let graph = match state.database.graph(&graph_name) {
         Ok(g) => g,
         Err(e) => {
             return (
-                StatusCode::NOT_FOUND,
+                quota_error_status(&e, StatusCode::NOT_FOUND),
                 Json(json!({"error": e.to_string()})),
             );
         }
     };
 
-    // Parse the query
-    let parsed_query = match parse_simple(&request.cypher) {
-        Ok(q) => q,
+    let rows = requests.iter().map(|request| {
+        (
+            NodeId::from_internal(request.start_node),
+            Label::new(&request.rel_type),
+            NodeId::from_internal(request.end_node),
+            json_map_to_property(&request.properties),
+        )
+    });
+
+    // Rows with missing endpoints are reported without failing the rest
+    let outcomes = match graph.create_relationships_with_properties(rows) {
+        Ok(outcomes) => outcomes,
         Err(e) => {
             return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": format!("Parse error: {}", e)})),
+                quota_error_status(&e, StatusCode::INTERNAL_SERVER_ERROR),
+                Json(json!({"error": e.to_string()})),
             );
         }
     };
 
-    // Create execution plan
-    let planner = QueryPlanner::new();
-    let plan = match planner.plan(&parsed_query) {
-        Ok(p) => p,
-        Err(e) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": format!("Planning error: {}", e)})),
-            );
-        }
+    let mut ids = Vec::new();
+    let results: Vec<_> = outcomes
+        .into_iter()
+        .enumerate()
+        .map(|(index, outcome)| match outcome {
+            Ok(rel) => {
+                ids.push(rel.id.as_internal());
+                json!({"index": index, "id": rel.id.as_internal()})
+            }
+            Err(e) => json!({"index": index, "error": e.to_string()}),
+        })
+        .collect();
+
+    let failed = results.len() - ids.len();
+    let status = if failed == 0 {
+        StatusCode::CREATED
+    } else {
+        StatusCode::MULTI_STATUS
     };
+    (
+        status,
+        Json(json!({
+            "ids": ids,
+            "results": results,
+            "created": ids.len(),
+            "failed": failed
+        })),
+    )
+}
 
-    // Execute the plan
-    let executor = QueryExecutor::new(Arc::new(graph));
+/// Maximum size of an import request body
+const MAX_IMPORT_BODY_BYTES: usize = 512 * 1024 * 1024;
 
-    // Convert parameters from JSON Value to PropertyValue
-    let mut params = std::collections::HashMap::new();
-    if let Some(req_params) = &request.parameters {
-        for (key, value) in req_params {
-            let prop_value = match value {
-                Value::Number(n) if n.is_i64() => PropertyValue::Integer(n.as_i64().unwrap()),
-                Value::Number(n) if n.is_f64() => PropertyValue::Float(n.as_f64().unwrap()),
-                Value::String(s) => PropertyValue::String(s.clone()),
-                Value::Bool(b) => PropertyValue::Boolean(*b),
-                Value::Null => PropertyValue::Null,
-                _ => PropertyValue::Null,
-            };
-            params.insert(key.clone(), prop_value);
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportRequest {
+    #[serde(default)]
+    format: ImportFormat,
+    /// Node rows, as CSV with a header or as newline-delimited JSON
+    #[serde(default)]
+    nodes: String,
+    /// Relationship rows referencing the nodes by their external IDs
+    #[serde(default)]
+    relationships: String,
+    /// Rows written per batch (default 1000)
+    batch_size: Option<usize>,
+}
+
+enum ImportBody {
+    Rows(ImportRequest),
+    Dump(String),
+}
+
+/// Import nodes and relationships in batches
+///
+/// The body is an [`ImportRequest`], or a graph dump sent as
+/// `application/x-ndjson`. Responds with the created counts and the skipped
+/// rows. When the import stops early, the counts cover what was written
+/// before it and `error` says why.
+async fn bulk_import(
+    State(state): State<AppState>,
+    Path(graph_name): Path<String>,
+    headers: axum::http::HeaderMap,
+    body: axum::body::Bytes,
+) -> impl IntoResponse {
+    let is_dump = headers
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with(DUMP_CONTENT_TYPE));
+    let request = if is_dump {
+        match String::from_utf8(body.to_vec()) {
+            Ok(dump) => ImportBody::Dump(dump),
+            Err(_) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"error": "The dump is not valid UTF-8"})),
+                );
+            }
         }
-    }
+    } else {
+        match serde_json::from_slice::<ImportRequest>(&body) {
+            Ok(request) => ImportBody::Rows(request),
+            Err(e) => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Json(json!({"error": format!("Invalid import request: {}", e)})),
+                );
+            }
+        }
+    };
 
-    let result = match executor.execute(&plan, &params) {
-        Ok(r) => r,
+    let graph = match state.database.graph(&graph_name) {
+        Ok(g) => g,
         Err(e) => {
             return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Execution error: {}", e)})),
+                quota_error_status(&e, StatusCode::NOT_FOUND),
+                Json(json!({"error": e.to_string()})),
             );
         }
     };
 
-    // Convert result to JSON format expected by SDK
-    let mut results = Vec::new();
-    for row in result.rows {
-        let mut row_map = HashMap::new();
-        for (i, col_name) in result.columns.iter().enumerate() {
-            row_map.insert(col_name.clone(), property_value_to_json(&row[i]));
+    let import = tokio::task::spawn_blocking(move || {
+        let mut report = ImportReport::default();
+        let result = match request {
+            ImportBody::Rows(request) => crate::import::import_graph(
+                &graph,
+                request.format,
+                &request.nodes,
+                &request.relationships,
+                request.batch_size.unwrap_or(DEFAULT_IMPORT_BATCH_SIZE),
+                &mut report,
+            ),
+            ImportBody::Dump(dump) => crate::import::import_dump(&graph, &dump, DEFAULT_IMPORT_BATCH_SIZE, &mut report),
+        };
+        (report, result)
+    })
+    .await;
+
+    match import {
+        Ok((report, Ok(()))) => {
+            let status = if report.errors.is_empty() {
+                StatusCode::CREATED
+            } else {
+                StatusCode::MULTI_STATUS
+            };
+            (status, Json(json!(report)))
         }
-        results.push(row_map);
-    }
-
-    let response = json!({
-        "results": results,
-        "stats": {
-            "nodesCreated": result.stats.nodes_created,
-            "nodesDeleted": result.stats.nodes_deleted,
-            "relationshipsCreated": result.stats.relationships_created,
-            "relationshipsDeleted": result.stats.relationships_deleted,
-            "executionTimeMs": result.stats.execution_time_ms
+        Ok((report, Err(e))) => {
+            let status = match e {
+                qilbee_core::Error::ValidationError(_) => StatusCode::BAD_REQUEST,
+                _ => quota_error_status(&e, StatusCode::INTERNAL_SERVER_ERROR),
+            };
+            let mut body = json!(report);
+            body["error"] = json!(e.to_string());
+            (status, Json(body))
         }
-    });
-
-    (StatusCode::OK, Json(response))
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Import failed: {}", e)})),
+        ),
+    }
 }
 
-// ==================== Memory Operations ====================
+/// Records per chunk of a graph dump
+const EXPORT_CHUNK_RECORDS: usize = 1000;
 
-/// Error types for memory operations with proper HTTP status code mapping
-#[derive(Debug)]
-enum MemoryOperationError {
-    /// Agent memory not found (404)
-    AgentNotFound { agent_id: String },
-    /// Episode not found (404)
-    EpisodeNotFound { agent_id: String, episode_id: String },
-    /// Request validation failed (400)
-    ValidationError { field: String, message: String },
-    /// Memory operation failed (500)
-    OperationFailed { operation: String, message: String },
-    /// Storage error (500)
-    StorageError { message: String },
-}
+/// Stream a dump of the whole graph as newline-delimited JSON
+///
+/// The dump is read from a storage snapshot on a blocking thread and sent a
+/// chunk at a time, so it is never held in memory whole. POSTing it to the
+/// import endpoint of a fresh graph restores it.
+async fn export_graph(
+    State(state): State<AppState>,
+    Path(graph_name): Path<String>,
+) -> axum::response::Response {
+    let graph = match state.database.graph_exists(&graph_name) {
+        Ok(true) => state.database.graph(&graph_name),
+        Ok(false) => Err(qilbee_core::Error::GraphNotFound(graph_name.clone())),
+        Err(e) => Err(e),
+    };
+    let graph = match graph {
+        Ok(g) => g,
+        Err(e) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": e.to_string()}))).into_response();
+        }
+    };
 
-impl MemoryOperationError {
-    fn error_code(&self) -> &'static str {
-        match self {
-            MemoryOperationError::AgentNotFound { .. } => "AGENT_NOT_FOUND",
-            MemoryOperationError::EpisodeNotFound { .. } => "EPISODE_NOT_FOUND",
-            MemoryOperationError::ValidationError { .. } => "VALIDATION_ERROR",
-            MemoryOperationError::OperationFailed { .. } => "OPERATION_FAILED",
-            MemoryOperationError::StorageError { .. } => "STORAGE_ERROR",
+    // A few chunks may wait for a slow client before the export blocks
+    let (tx, rx) = tokio::sync::mpsc::channel::<std::io::Result<String>>(4);
+    tokio::task::spawn_blocking(move || {
+        let result = crate::export::export_graph(&graph, EXPORT_CHUNK_RECORDS, |chunk| {
+            tx.blocking_send(Ok(chunk)).is_ok()
+        });
+        if let Err(e) = result {
+            tracing::error!("Export of graph {} failed: {}", graph.name(), e);
+            let _ = tx.blocking_send(Err(std::io::Error::other(e.to_string())));
         }
-    }
+    });
+    let chunks = futures::stream::unfold(rx, |mut rx| async move { rx.recv().await.map(|chunk| (chunk, rx)) });
+
+    (
+        StatusCode::OK,
+        [(axum::http::header::CONTENT_TYPE, DUMP_CONTENT_TYPE)],
+        axum::body::Body::from_stream(chunks),
+    )
+        .into_response()
 }
 
-impl std::fmt::Display for MemoryOperationError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            MemoryOperationError::AgentNotFound { agent_id } => {
-                write!(f, "Agent memory not found for agent_id: {}", agent_id)
-            }
-            MemoryOperationError::EpisodeNotFound { agent_id, episode_id } => {
-                write!(f, "Episode {} not found for agent {}", episode_id, agent_id)
-            }
-            MemoryOperationError::ValidationError { field, message } => {
-                write!(f, "Validation error for '{}': {}", field, message)
-            }
-            MemoryOperationError::OperationFailed { operation, message } => {
-                write!(f, "{} operation failed: {}", operation, message)
-            }
-            MemoryOperationError::StorageError { message } => {
-                write!(f, "Storage error: {}", message)
-            }
+/// Node and relationship counts of a graph, in total, per label and per type
+async fn graph_stats(
+    State(state): State<AppState>,
+    Path(graph_name): Path<String>,
+) -> impl IntoResponse {
+    let graph = match state.database.graph_exists(&graph_name) {
+        Ok(true) => state.database.graph(&graph_name),
+        Ok(false) => Err(qilbee_core::Error::GraphNotFound(graph_name.clone())),
+        Err(e) => Err(e),
+    };
+    let graph = match graph {
+        Ok(g) => g,
+        Err(e) => {
+            return (StatusCode::NOT_FOUND, Json(json!({"error": e.to_string()})));
         }
-    }
-}
+    };
 
-impl IntoResponse for MemoryOperationError {
-    fn into_response(self) -> axum::response::Response {
-        let (status, error_response) = match &self {
-            MemoryOperationError::AgentNotFound { agent_id } => (
-                StatusCode::NOT_FOUND,
-                json!({
-                    "error": self.to_string(),
-                    "error_code": self.error_code(),
-                    "agent_id": agent_id
-                }),
-            ),
-            MemoryOperationError::EpisodeNotFound { agent_id, episode_id } => (
-                StatusCode::NOT_FOUND,
-                json!({
-                    "error": self.to_string(),
-                    "error_code": self.error_code(),
-                    "agent_id": agent_id,
-                    "episode_id": episode_id
-                }),
-            ),
-            MemoryOperationError::ValidationError { field, message } => (
-                StatusCode::BAD_REQUEST,
-                json!({
-                    "error": "Validation failed",
-                    "error_code": self.error_code(),
-                    "field": field,
-                    "details": message
-                }),
-            ),
-            MemoryOperationError::OperationFailed { operation, message } => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                json!({
-                    "error": self.to_string(),
-                    "error_code": self.error_code(),
-                    "operation": operation,
-                    "details": message
-                }),
-            ),
-            MemoryOperationError::StorageError { message } => (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                json!({
-                    "error": self.to_string(),
-                    "error_code": self.error_code(),
-                    "details": message
-                }),
-            ),
-        };
-        (status, Json(error_response)).into_response()
+    let stats = graph.node_count().and_then(|nodes| {
+        let relationships = graph.relationship_count()?;
+        Ok((nodes, relationships, graph.type_counts()?))
+    });
+    match stats {
+        Ok((node_count, relationship_count, counts)) => (
+            StatusCode::OK,
+            Json(json!({
+                "graph": graph_name,
+                "nodeCount": node_count,
+                "relationshipCount": relationship_count,
+                "labels": counts.labels,
+                "relationshipTypes": counts.relationship_types,
+                "estimated": counts.scanned,
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": e.to_string()})),
+        ),
     }
 }
 
 #[derive(Debug, Deserialize)]
-struct StoreEpisodeRequest {
-    #[serde(rename = "agentId")]
-    agent_id: String,
-    #[serde(rename = "episodeType")]
-    episode_type: String,
-    content: HashMap<String, Value>,
-    #[serde(rename = "eventTime")]
-    event_time: Option<i64>,
-    metadata: Option<HashMap<String, Value>>,
+struct NodeRelationshipsQuery {
+    /// Only return relationships of this type
+    #[serde(rename = "type")]
+    rel_type: Option<String>,
 }
 
-/// Request body for consolidating agent memory
-#[derive(Debug, Deserialize)]
-struct ConsolidateMemoryRequest {
-    /// Minimum relevance threshold (0.0 to 1.0) - episodes below this will be decayed
-    #[serde(default = "default_min_relevance")]
-    min_relevance: f64,
-    /// Decay factor to apply (0.0 to 1.0) - lower means more aggressive decay
-    #[serde(default = "default_decay_factor")]
-    decay_factor: f64,
+/// A relationship as seen from one of its endpoints
+#[derive(Debug, Serialize)]
+struct NodeRelationshipResponse {
+    #[serde(flatten)]
+    relationship: RelationshipResponse,
+    /// `outgoing` when the node is the start node, `incoming` otherwise
+    direction: &'static str,
 }
 
-fn default_min_relevance() -> f64 {
-    0.3
-}
+async fn get_relationships(
+    State(state): State<AppState>,
+    Path((graph_name, node_id)): Path<(String, String)>,
+    AxumQuery(query): AxumQuery<NodeRelationshipsQuery>,
+) -> impl IntoResponse {
+    let graph = match state.database.graph(&graph_name) {
+        Ok(g) => g,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": e.to_string()})),
+            );
+        }
+    };
 
-fn default_decay_factor() -> f64 {
-    0.9
-}
+    let id = match node_id.parse::<u64>() {
+        Ok(i) => NodeId::from_internal(i),
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid node ID"})),
+            );
+        }
+    };
 
-impl ConsolidateMemoryRequest {
-    fn validate(&self) -> Result<(), String> {
-        if self.min_relevance < 0.0 || self.min_relevance > 1.0 {
-            return Err("min_relevance must be between 0.0 and 1.0".to_string());
+    match graph.get_node(id) {
+        Ok(Some(_)) => {}
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "Node not found"})),
+            );
         }
-        if self.decay_factor < 0.0 || self.decay_factor > 1.0 {
-            return Err("decay_factor must be between 0.0 and 1.0".to_string());
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            );
         }
-        Ok(())
     }
+
+    // Self-loops are reported once, as outgoing
+    let rels = match &query.rel_type {
+        Some(rel_type) => graph.get_relationships_by_type(id, Direction::Both, rel_type),
+        None => graph.get_relationships(id, Direction::Both),
+    };
+    let rels = match rels {
+        Ok(rels) => rels,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            );
+        }
+    };
+
+    let relationships: Vec<NodeRelationshipResponse> = rels
+        .iter()
+        .map(|rel| NodeRelationshipResponse {
+            relationship: RelationshipResponse {
+                id: rel.id.as_internal(),
+                rel_type: rel.rel_type.name().to_string(),
+                start_node: rel.source.as_internal(),
+                end_node: rel.target.as_internal(),
+                properties: property_to_json_map(&rel.properties),
+            },
+            direction: if rel.source == id { "outgoing" } else { "incoming" },
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(json!({"relationships": relationships, "count": relationships.len()})),
+    )
 }
 
-/// Request body for forgetting agent memory
+// ==================== Change History ====================
+
 #[derive(Debug, Deserialize)]
-struct ForgetMemoryRequest {
-    /// Minimum relevance threshold (0.0 to 1.0) - episodes below this will be forgotten
-    #[serde(default = "default_forget_min_relevance")]
-    min_relevance: f64,
-    /// Maximum age in seconds - episodes older than this may be forgotten
-    #[serde(default)]
-    max_age_seconds: Option<u64>,
-    /// Episode types to target for forgetting (empty means all types)
-    #[serde(default)]
-    episode_types: Vec<String>,
+struct GraphDiffQuery {
+    /// Start of the diff, transaction time in milliseconds (exclusive)
+    from: i64,
+    /// End of the diff, transaction time in milliseconds (inclusive)
+    to: i64,
 }
 
-fn default_forget_min_relevance() -> f64 {
-    0.1
+/// An entity's state at the start and end of a diff
+#[derive(Debug, Serialize)]
+struct EntityUpdateResponse<T> {
+    before: T,
+    after: T,
 }
 
-impl ForgetMemoryRequest {
-    fn validate(&self) -> Result<(), String> {
-        if self.min_relevance < 0.0 || self.min_relevance > 1.0 {
-            return Err("min_relevance must be between 0.0 and 1.0".to_string());
+#[derive(Debug, Serialize)]
+struct EntityDiffResponse<T> {
+    created: Vec<T>,
+    updated: Vec<EntityUpdateResponse<T>>,
+    deleted: Vec<T>,
+}
+
+impl<T> EntityDiffResponse<T> {
+    fn new<E>(diff: &qilbee_storage::EntityDiff<E>) -> Self
+    where
+        T: for<'a> From<&'a E>,
+    {
+        Self {
+            created: diff.created.iter().map(T::from).collect(),
+            updated: diff
+                .updated
+                .iter()
+                .map(|u| EntityUpdateResponse {
+                    before: T::from(&u.before),
+                    after: T::from(&u.after),
+                })
+                .collect(),
+            deleted: diff.deleted.iter().map(T::from).collect(),
         }
-        Ok(())
     }
 }
 
-#[tracing::instrument(
-    name = "memory.store_episode",
-    skip(state, request),
-    fields(agent_id = %agent_id)
-)]
-async fn store_episode(
+async fn graph_diff(
     State(state): State<AppState>,
-    Path(agent_id): Path<String>,
-    Json(request): Json<StoreEpisodeRequest>,
+    Path(graph_name): Path<String>,
+    AxumQuery(query): AxumQuery<GraphDiffQuery>,
 ) -> impl IntoResponse {
-    // Get or create agent memory
-    let memory = {
-        let mut memories = state.agent_memories.lock().unwrap();
-        memories
-            .entry(agent_id.clone())
-            .or_insert_with(|| Arc::new(AgentMemory::for_agent(&agent_id)))
-            .clone()
-    };
-
-    // Parse episode type
-    let episode_type = match request.episode_type.as_str() {
-        "conversation" | "Conversation" => EpisodeType::Conversation,
-        "observation" | "Observation" => EpisodeType::Observation,
-        "action" => EpisodeType::Custom("Action".to_string()),
-        _ => EpisodeType::Conversation,
+    let graph = match state.database.graph(&graph_name) {
+        Ok(g) => g,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": e.to_string()})),
+            );
+        }
     };
 
-    // Create episode content from HashMap
-    // Support multiple field names for primary content:
-    // - primary, input, message, user_input, text, observation, action
-    let primary_content = request
-        .content
-        .get("primary")
-        .or(request.content.get("input"))
-        .or(request.content.get("message"))
-        .or(request.content.get("user_input"))
-        .or(request.content.get("text"))
-        .or(request.content.get("observation"))
-        .or(request.content.get("action"))
-        .and_then(|v| v.as_str())
-        .unwrap_or("");
-
-    let mut content = EpisodeContent::new(primary_content);
-
-    // Support multiple field names for secondary content:
-    // - secondary, output, response, agent_response, result
-    if let Some(secondary) = request.content.get("secondary")
-        .or(request.content.get("output"))
-        .or(request.content.get("response"))
-        .or(request.content.get("agent_response"))
-        .or(request.content.get("result"))
-    {
-        if let Some(s) = secondary.as_str() {
-            content = content.with_secondary(s);
+    let from = TransactionTime::from_millis(query.from);
+    let to = TransactionTime::from_millis(query.to);
+    match graph.diff(from, to) {
+        Ok(diff) => {
+            let nodes: EntityDiffResponse<NodeResponse> = EntityDiffResponse::new(&diff.nodes);
+            let relationships: EntityDiffResponse<RelationshipResponse> =
+                EntityDiffResponse::new(&diff.relationships);
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "from": query.from,
+                    "to": query.to,
+                    "nodes": nodes,
+                    "relationships": relationships,
+                })),
+            )
         }
-    }
-
-    // Create episode
-    let episode = Episode::new(&agent_id, episode_type, content);
-    let episode_id = episode.id.to_string();
-
-    // Store episode
-    match memory.store_episode(episode) {
-        Ok(_) => (StatusCode::CREATED, Json(json!({"episodeId": episode_id}))),
+        Err(e @ qilbee_core::Error::InvalidTemporalRange(_)) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": e.to_string()})),
+        ),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"error": e.to_string()})),
@@ -922,525 +1497,1064 @@ async fn store_episode(
     }
 }
 
-#[tracing::instrument(
-    name = "memory.get_episode",
-    skip(state),
-    fields(agent_id = %agent_id, episode_id = %episode_id)
-)]
-async fn get_episode(
+// ==================== Query Operations ====================
+
+#[derive(Debug, Deserialize)]
+struct QueryRequestJson {
+    cypher: String,
+    parameters: Option<HashMap<String, Value>>,
+}
+
+/// Layout of query results in the response body
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum QueryResultFormat {
+    /// `{"results": [{col: value, ...}, ...]}`
+    #[default]
+    Rows,
+    /// `{"columns": [...], "data": {col: [values...]}}`
+    Columns,
+    /// Arrow IPC stream (`application/vnd.apache.arrow.stream`)
+    Arrow,
+}
+
+#[derive(Debug, Deserialize)]
+struct QueryFormatParams {
+    #[serde(default)]
+    format: QueryResultFormat,
+    /// Execute the query even if its result is cached
+    #[serde(default, rename = "noCache")]
+    no_cache: bool,
+}
+
+/// Result sets with more rows than this are streamed as chunked JSON instead
+/// of being serialized into a single in-memory buffer
+///
+/// Kept well below the default query limit, so results capped by it still
+/// stream.
+const QUERY_STREAMING_THRESHOLD: usize = 1_000;
+
+async fn execute_query(
     State(state): State<AppState>,
-    Path((agent_id, episode_id)): Path<(String, String)>,
-) -> Result<impl IntoResponse, MemoryOperationError> {
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        memories.get(&agent_id).cloned().ok_or_else(|| {
-            MemoryOperationError::AgentNotFound {
-                agent_id: agent_id.clone(),
-            }
-        })?
+    Path(graph_name): Path<String>,
+    AxumQuery(options): AxumQuery<QueryFormatParams>,
+    user: Option<axum::Extension<User>>,
+    Json(request): Json<QueryRequestJson>,
+) -> axum::response::Response {
+    let user = user.map(|axum::Extension(user)| user);
+    use qilbee_query::{parse_simple_with_limits, QueryMode, QueryPlanner, QueryExecutor, DEFAULT_STREAM_CHUNK_SIZE};
+    use std::sync::Arc;
+
+    let graph = match state.database.graph(&graph_name) {
+        Ok(g) => g,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
     };
 
-    // Parse episode ID - just compare as string for now
-    // TODO: Implement proper UUID parsing for EpisodeId
-    // For now, get recent episodes and find by string comparison
-    let episodes = memory.get_recent_episodes(100).map_err(|e| {
-        MemoryOperationError::StorageError {
-            message: e.to_string(),
+    // Parse the query
+    let parsed_query = match parse_simple_with_limits(&request.cypher, state.parser_limits) {
+        Ok(q) => q,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Parse error: {}", e)})),
+            )
+                .into_response();
         }
-    })?;
+    };
 
-    let episode = episodes
-        .iter()
-        .find(|ep| ep.id.to_string() == episode_id)
-        .ok_or_else(|| MemoryOperationError::EpisodeNotFound {
-            agent_id: agent_id.clone(),
-            episode_id: episode_id.clone(),
-        })?;
+    // Authenticated callers need GraphRead, or GraphUpdate to write
+    let permission = Permission::for_query(parsed_query.is_read_only());
+    if user.as_ref().is_some_and(|user| !user.has_permission(&permission)) {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": format!("Required permission: {:?}", permission)})),
+        )
+            .into_response();
+    }
 
-    // Format content based on episode type for intuitive field names
-    let content_map = format_episode_content(&episode);
+    // Create execution plan against the graph's indexes and counts, capping
+    // unbounded reads at the configured default
+    let planner = QueryPlanner::new()
+        .with_graph_schema(&graph)
+        .and_then(|planner| planner.with_graph_statistics(&graph, &parsed_query));
+    let mut planner = match planner {
+        Ok(planner) => planner,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            )
+                .into_response();
+        }
+    };
+    if let Some(limit) = state.default_query_limit {
+        planner = planner.with_default_limit(limit);
+    }
+    let plan = match planner.plan(&parsed_query) {
+        Ok(p) => p,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Planning error: {}", e)})),
+            )
+                .into_response();
+        }
+    };
 
-    let response = json!({
-        "agentId": episode.agent_id,
-        "episodeId": episode.id.to_string(),
-        "episodeType": format!("{:?}", episode.episode_type),
-        "content": content_map,
-        "eventTime": episode.event_time.as_millis()
+    // EXPLAIN returns the plan without executing it
+    if plan.mode == QueryMode::Explain {
+        return (
+            StatusCode::OK,
+            Json(json!({"results": [], "plan": plan.description})),
+        )
+            .into_response();
+    }
+
+    // Execute the plan, answering repeated reads from the cache unless asked
+    // not to. Only results known to fit in the cache and under the streaming
+    // threshold are buffered for it; everything else is streamed.
+    let mut executor = QueryExecutor::new(Arc::new(graph));
+    let cache = state.query_cache.as_ref().filter(|cache| {
+        !options.no_cache
+            && plan
+                .max_rows()
+                .is_some_and(|rows| rows <= cache.max_rows().min(QUERY_STREAMING_THRESHOLD))
     });
-    Ok((StatusCode::OK, Json(response)))
-}
+    if let Some(cache) = cache {
+        executor = executor.with_cache(Arc::clone(cache));
+    }
 
-#[tracing::instrument(
-    name = "memory.get_recent_episodes",
-    skip(state),
-    fields(agent_id = %agent_id)
-)]
-async fn get_recent_episodes(
-    State(state): State<AppState>,
-    Path(agent_id): Path<String>,
-) -> impl IntoResponse {
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        match memories.get(&agent_id) {
-            Some(m) => m.clone(),
-            None => {
-                // Return empty list if agent doesn't exist yet
-                return (StatusCode::OK, Json(json!({"episodes": []})));
+    let params = query_parameters(request.parameters.as_ref());
+
+    let operation = QueryOperation::of(parsed_query.is_read_only());
+    let started = Instant::now();
+
+    // PROFILE returns the rows along with the profiled plan
+    if plan.mode == QueryMode::Profile {
+        let profiled = executor.profile(&plan, &params);
+        state.metrics.record_query(operation, started.elapsed());
+        audit_query(
+            &state,
+            user.as_ref(),
+            &graph_name,
+            &request,
+            profiled.as_ref().map(|(result, _)| &result.stats),
+        );
+        return match profiled {
+            Ok((result, profile)) => {
+                let mut stream = qilbee_query::QueryResultStream::new(result, DEFAULT_STREAM_CHUNK_SIZE);
+                match query_stream_to_rows_json(&mut stream) {
+                    Ok(mut body) => {
+                        body["plan"] = json!(profile);
+                        (StatusCode::OK, Json(body)).into_response()
+                    }
+                    Err(e) => (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(json!({"error": format!("Execution error: {}", e)})),
+                    )
+                        .into_response(),
+                }
             }
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Execution error: {}", e)})),
+            )
+                .into_response(),
+        };
+    }
+
+    // Streamed rows are produced as the response body is written
+    let executed = match cache {
+        Some(_) => executor
+            .execute_cached(&request.cypher, &plan, &params)
+            .map(|result| qilbee_query::QueryResultStream::new(result, DEFAULT_STREAM_CHUNK_SIZE)),
+        None => executor.execute_stream(&plan, &params, DEFAULT_STREAM_CHUNK_SIZE),
+    };
+    let stream = match executed {
+        Ok(s) => s,
+        Err(e) => {
+            state.metrics.record_query(operation, started.elapsed());
+            audit_query(&state, user.as_ref(), &graph_name, &request, Err(&e));
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Execution error: {}", e)})),
+            )
+                .into_response();
         }
     };
 
-    // Get recent episodes
-    match memory.get_recent_episodes(10) {
-        Ok(episodes) => {
-            let episode_list: Vec<_> = episodes
-                .iter()
-                .map(|ep| {
-                    json!({
-                        "agentId": ep.agent_id,
-                        "episodeId": ep.id.to_string(),
-                        "episodeType": format!("{:?}", ep.episode_type),
-                        "content": format_episode_content(ep),
-                        "eventTime": ep.event_time.as_millis()
-                    })
-                })
-                .collect();
-            (StatusCode::OK, Json(json!({"episodes": episode_list})))
-        }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": e.to_string()})),
-        ),
+    // Streamed rows are still being produced, so the query is recorded once
+    // the last of them has been
+    let report = move |outcome: QueryOutcome<'_>| {
+        state.metrics.record_query(operation, started.elapsed());
+        audit_query(&state, user.as_ref(), &graph_name, &request, outcome);
+    };
+    let mut response = query_stream_response(stream, options.format, plan.default_limit, report);
+    if let Some(limit) = plan.default_limit {
+        response.headers_mut().insert(DEFAULT_LIMIT_HEADER, limit.into());
     }
+    response
 }
 
-#[derive(Debug, Deserialize)]
-struct SearchEpisodesRequest {
-    query: String,
-    #[serde(default = "default_search_limit")]
-    limit: usize,
-}
+/// Record an executed query in the audit log
+///
+/// The event carries the normalized query text and the names of its
+/// parameters, never their values, together with whether the query changed
+/// data, how many rows it returned and how long it took, or the error it
+/// failed with.
+fn audit_query(
+    state: &AppState,
+    user: Option<&User>,
+    graph_name: &str,
+    request: &QueryRequestJson,
+    outcome: std::result::Result<&qilbee_query::ExecutionStats, &qilbee_core::Error>,
+) {
+    let mut parameters: Vec<&String> = request.parameters.iter().flat_map(|params| params.keys()).collect();
+    parameters.sort();
+    let mut metadata = json!({
+        "query": normalize_query_text(&request.cypher),
+        "parameters": parameters,
+    });
+    let result = match outcome {
+        Ok(stats) => {
+            metadata["mutated"] = json!(stats.contains_updates());
+            metadata["rows"] = json!(stats.rows_returned);
+            metadata["execution_time_ms"] = json!(stats.execution_time_ms);
+            AuditResult::Success
+        }
+        Err(e) => {
+            metadata["error"] = json!(e.to_string());
+            AuditResult::Error
+        }
+    };
 
-fn default_search_limit() -> usize {
-    10
+    state.audit_service.log_query_event(
+        user.map(|user| user.id.0.to_string()),
+        user.map(|user| user.username.clone()),
+        graph_name,
+        result,
+        metadata,
+    );
 }
 
-#[tracing::instrument(
-    name = "memory.search_episodes",
-    skip(state, request),
-    fields(agent_id = %agent_id)
-)]
-async fn search_episodes(
-    State(state): State<AppState>,
-    Path(agent_id): Path<String>,
-    Json(request): Json<SearchEpisodesRequest>,
-) -> impl IntoResponse {
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        match memories.get(&agent_id) {
-            Some(m) => m.clone(),
-            None => {
-                return (StatusCode::OK, Json(json!({"episodes": []})));
+/// Normalize a query for the audit log
+///
+/// Runs of whitespace collapse to one space and string literals are replaced
+/// by `?`, so values written into the query text are not recorded.
+fn normalize_query_text(cypher: &str) -> String {
+    let mut normalized = String::with_capacity(cypher.len());
+    let mut chars = cypher.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                // Skip to the closing quote, honoring backslash escapes
+                while let Some(next) = chars.next() {
+                    if next == '\\' {
+                        chars.next();
+                    } else if next == c {
+                        break;
+                    }
+                }
+                normalized.push('?');
+            }
+            c if c.is_whitespace() => {
+                if !normalized.is_empty() && !normalized.ends_with(' ') {
+                    normalized.push(' ');
+                }
             }
+            c => normalized.push(c),
         }
-    };
+    }
+    normalized.truncate(normalized.trim_end().len());
+    normalized
+}
 
-    // Search episodes using keyword search
-    match memory.search_episodes(&request.query) {
-        Ok(episodes) => {
-            let episode_list: Vec<_> = episodes
-                .iter()
-                .take(request.limit)
-                .map(|ep| {
-                    json!({
-                        "episodeId": ep.id.to_string(),
-                        "agentId": ep.agent_id,
-                        "episodeType": format!("{:?}", ep.episode_type),
-                        "content": {
-                            "primary": ep.content.primary,
-                            "secondary": ep.content.secondary
-                        },
-                        "eventTime": ep.event_time.as_millis(),
-                        "metadata": ep.metadata
-                    })
-                })
-                .collect();
-            (StatusCode::OK, Json(json!({"episodes": episode_list})))
-        }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": e.to_string()})),
-        ),
+/// Convert query parameters from JSON values to property values
+///
+/// Only scalars are supported; lists and objects become `Null`.
+fn query_parameters(parameters: Option<&HashMap<String, Value>>) -> HashMap<String, PropertyValue> {
+    let mut params = HashMap::new();
+    for (key, value) in parameters.into_iter().flatten() {
+        let prop_value = match value {
+            Value::Number(n) if n.is_i64() => PropertyValue::Integer(n.as_i64().unwrap()),
+            Value::Number(n) if n.is_f64() => PropertyValue::Float(n.as_f64().unwrap()),
+            Value::String(s) => PropertyValue::String(s.clone()),
+            Value::Bool(b) => PropertyValue::Boolean(*b),
+            Value::Null => PropertyValue::Null,
+            _ => PropertyValue::Null,
+        };
+        params.insert(key.clone(), prop_value);
     }
+    params
 }
 
-// ==================== Semantic Search Request/Response Types ====================
+/// Response header carrying the default LIMIT applied to a query
+const DEFAULT_LIMIT_HEADER: &str = "x-qilbee-default-limit";
 
-#[derive(Debug, Deserialize)]
-struct SemanticSearchRequest {
-    query: String,
-    #[serde(default = "default_search_limit")]
-    limit: usize,
-    #[serde(rename = "minScore")]
-    min_score: Option<f32>,
-}
+/// Outcome of a query once all of its rows have been produced
+type QueryOutcome<'a> = std::result::Result<&'a qilbee_query::ExecutionStats, &'a qilbee_core::Error>;
 
-#[derive(Debug, Deserialize)]
-struct HybridSearchRequest {
-    query: String,
-    #[serde(default = "default_search_limit")]
-    limit: usize,
-    #[serde(rename = "semanticWeight", default = "default_semantic_weight")]
-    semantic_weight: f32,
+/// A query result stream that reports its outcome exactly once: when it is
+/// exhausted, when a chunk fails, or when it is dropped unfinished
+struct ReportedStream<F: FnOnce(QueryOutcome<'_>)> {
+    stream: qilbee_query::QueryResultStream,
+    report: Option<F>,
 }
 
-fn default_semantic_weight() -> f32 {
-    0.5
-}
+impl<F: FnOnce(QueryOutcome<'_>)> ReportedStream<F> {
+    fn new(stream: qilbee_query::QueryResultStream, report: F) -> Self {
+        Self { stream, report: Some(report) }
+    }
 
-#[derive(Debug, Deserialize)]
-struct FindSimilarQuery {
-    #[serde(default = "default_search_limit")]
-    limit: usize,
+    /// Report `error`, or the stream's statistics if there is none
+    fn finish(&mut self, error: Option<&qilbee_core::Error>) {
+        if let Some(report) = self.report.take() {
+            report(error.map_or(Ok(self.stream.stats()), Err));
+        }
+    }
 }
 
-// ==================== Semantic Search Handlers ====================
+impl<F: FnOnce(QueryOutcome<'_>)> Drop for ReportedStream<F> {
+    fn drop(&mut self) {
+        self.finish(None);
+    }
+}
 
-#[tracing::instrument(
-    name = "memory.semantic_search",
-    skip(state, request),
-    fields(agent_id = %agent_id, query = %request.query)
-)]
-async fn semantic_search(
+/// Render a query result in the requested format
+///
+/// `default_limit` is the LIMIT the planner added; buffered JSON bodies
+/// report it under `defaultLimit`. `report` is called with the query's
+/// outcome once its rows have all been produced, which for a streamed body
+/// is only after the response has been sent.
+fn query_stream_response(
+    stream: qilbee_query::QueryResultStream,
+    format: QueryResultFormat,
+    default_limit: Option<usize>,
+    report: impl FnOnce(QueryOutcome<'_>) + Send + 'static,
+) -> axum::response::Response {
+    let mut stream = ReportedStream::new(stream, report);
+    if format == QueryResultFormat::Arrow {
+        let columns = stream.stream.columns().to_vec();
+        let exported = stream
+            .stream
+            .by_ref()
+            .collect::<qilbee_core::Result<Vec<_>>>()
+            .and_then(|chunks| {
+                let rows: Vec<_> = chunks.into_iter().flatten().collect();
+                crate::arrow_export::query_result_to_arrow_ipc(&columns, &rows)
+            });
+        stream.finish(exported.as_ref().err());
+        return match exported {
+            Ok(bytes) => (
+                StatusCode::OK,
+                [(axum::http::header::CONTENT_TYPE, crate::arrow_export::ARROW_STREAM_CONTENT_TYPE)],
+                bytes,
+            )
+                .into_response(),
+            Err(e) => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Execution error: {}", e)})),
+            )
+                .into_response(),
+        };
+    }
+
+    // Large results are streamed chunk by chunk to keep memory bounded;
+    // columnar results need every row before any column is complete
+    if format == QueryResultFormat::Rows
+        && stream.stream.remaining_rows().is_none_or(|rows| rows > QUERY_STREAMING_THRESHOLD)
+    {
+        let chunks = query_stream_body_chunks(stream, default_limit);
+        return (
+            StatusCode::OK,
+            [(axum::http::header::CONTENT_TYPE, "application/json")],
+            axum::body::Body::from_stream(futures::stream::iter(chunks)),
+        )
+            .into_response();
+    }
+
+    let body = match format {
+        QueryResultFormat::Columns => query_stream_to_columns_json(&mut stream.stream),
+        _ => query_stream_to_rows_json(&mut stream.stream),
+    };
+    stream.finish(body.as_ref().err());
+    let mut body = match body {
+        Ok(body) => body,
+        Err(e) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Execution error: {}", e)})),
+            )
+                .into_response();
+        }
+    };
+    if let Some(limit) = default_limit {
+        body["defaultLimit"] = default_limit_json(limit, stream.stream.stats().rows_returned as usize);
+    }
+    (StatusCode::OK, Json(body)).into_response()
+}
+
+/// Describe a default LIMIT applied to a read query
+///
+/// `limitReached` means more rows may exist; clients page through them by
+/// re-running the query with an explicit `SKIP ... LIMIT ...`.
+fn default_limit_json(limit: usize, rows: usize) -> Value {
+    json!({
+        "limit": limit,
+        "limitReached": rows >= limit,
+        "pagination": format!(
+            "No LIMIT was given, so at most {limit} rows were returned. \
+             Add ORDER BY with SKIP {limit} LIMIT {limit} to fetch the next page, \
+             or an explicit LIMIT to override the default."
+        )
+    })
+}
+
+/// Estimate the cost of a query without executing it
+async fn estimate_query(
     State(state): State<AppState>,
-    Path(agent_id): Path<String>,
-    Json(request): Json<SemanticSearchRequest>,
+    Path(graph_name): Path<String>,
+    Json(request): Json<QueryRequestJson>,
 ) -> impl IntoResponse {
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        match memories.get(&agent_id) {
-            Some(m) => m.clone(),
-            None => {
-                return (
-                    StatusCode::NOT_IMPLEMENTED,
-                    Json(json!({
-                        "error": "Semantic search is not enabled for this agent",
-                        "error_code": "SEMANTIC_SEARCH_NOT_ENABLED"
-                    })),
-                );
-            }
+    let graph = match state.database.graph(&graph_name) {
+        Ok(g) => g,
+        Err(e) => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": e.to_string()})),
+            );
         }
     };
 
-    // For now, semantic search falls back to keyword search since we're using AgentMemory (not PersistentAgentMemory)
-    // In production, PersistentAgentMemory with semantic search enabled would be used
-    // Return a message indicating semantic search is not available for in-memory agents
-    match memory.search_episodes(&request.query) {
-        Ok(episodes) => {
-            let results: Vec<_> = episodes
-                .iter()
-                .take(request.limit)
-                .enumerate()
-                .filter(|(_, ep)| {
-                    // Filter by min_score if specified (using rank-based score for now)
-                    if let Some(min) = request.min_score {
-                        // Simulate a decreasing score based on rank
-                        let score = 1.0 - (0.1 * (episodes.iter().position(|e| e.id == ep.id).unwrap_or(0) as f32));
-                        score >= min
-                    } else {
-                        true
-                    }
-                })
-                .map(|(i, ep)| {
-                    // Calculate a simulated score based on rank
-                    let score = 1.0 - (0.05 * i as f32);
-                    json!({
-                        "episode": {
-                            "episodeId": ep.id.to_string(),
-                            "agentId": ep.agent_id,
-                            "episodeType": format!("{:?}", ep.episode_type),
-                            "content": {
-                                "primary": ep.content.primary,
-                                "secondary": ep.content.secondary
-                            },
-                            "eventTime": ep.event_time.as_millis(),
-                            "metadata": ep.metadata
-                        },
-                        "score": score
-                    })
-                })
-                .collect();
-            (StatusCode::OK, Json(json!({"results": results})))
-        }
+    match estimate_query_json(&graph, &request.cypher) {
+        Ok(estimate) => (StatusCode::OK, Json(estimate)),
+        Err(e @ (qilbee_core::Error::QueryParse(_) | qilbee_core::Error::InvalidQuery(_))) => (
+            StatusCode::BAD_REQUEST,
+            Json(json!({"error": e.to_string()})),
+        ),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": e.to_string(),
-                "error_code": "SEARCH_ERROR"
-            })),
+            Json(json!({"error": e.to_string()})),
         ),
     }
 }
 
-#[tracing::instrument(
-    name = "memory.hybrid_search",
-    skip(state, request),
-    fields(agent_id = %agent_id, query = %request.query, semantic_weight = %request.semantic_weight)
-)]
-async fn hybrid_search(
-    State(state): State<AppState>,
-    Path(agent_id): Path<String>,
-    Json(request): Json<HybridSearchRequest>,
-) -> impl IntoResponse {
-    // Validate semantic_weight
-    if request.semantic_weight < 0.0 || request.semantic_weight > 1.0 {
-        return (
-            StatusCode::BAD_REQUEST,
-            Json(json!({
-                "error": "semanticWeight must be between 0.0 and 1.0",
-                "error_code": "VALIDATION_ERROR"
-            })),
-        );
+/// Parse and plan a query against the graph's indexes and node counts
+///
+/// Reports the planner's estimated row count and cost, and whether the plan
+/// reads from an index, so clients can reject expensive queries up front.
+fn estimate_query_json(graph: &qilbee_graph::Graph, cypher: &str) -> qilbee_core::Result<Value> {
+    use qilbee_query::{parse_simple, QueryPlanner};
+
+    let query = parse_simple(cypher)?;
+    let plan = QueryPlanner::new()
+        .with_graph_schema(graph)?
+        .with_graph_statistics(graph, &query)?
+        .plan(&query)?;
+
+    Ok(json!({
+        "estimatedRows": plan.estimated_rows,
+        "estimatedCost": plan.estimated_cost,
+        "usesIndex": plan.uses_index()
+    }))
+}
+
+/// Convert a query result to the row-oriented JSON format expected by the SDKs
+///
+/// Consumes the stream, so its statistics are final afterwards.
+fn query_stream_to_rows_json(stream: &mut qilbee_query::QueryResultStream) -> qilbee_core::Result<Value> {
+    let columns = stream.columns().to_vec();
+    let mut results = Vec::with_capacity(stream.remaining_rows().unwrap_or(0));
+    for chunk in stream.by_ref() {
+        results.extend(chunk?.iter().map(|row| query_row_to_json(&columns, row)));
     }
 
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        match memories.get(&agent_id) {
-            Some(m) => m.clone(),
+    Ok(json!({
+        "results": results,
+        "stats": query_stats_to_json(stream.stats())
+    }))
+}
+
+/// Convert a query result to columnar JSON, one value array per column
+///
+/// Consumes the stream, so its statistics are final afterwards.
+fn query_stream_to_columns_json(stream: &mut qilbee_query::QueryResultStream) -> qilbee_core::Result<Value> {
+    let columns = stream.columns().to_vec();
+    let mut data: Vec<Vec<Value>> = vec![Vec::with_capacity(stream.remaining_rows().unwrap_or(0)); columns.len()];
+
+    for chunk in stream.by_ref() {
+        for row in chunk? {
+            for (values, value) in data.iter_mut().zip(&row) {
+                values.push(property_value_to_json(value));
+            }
+        }
+    }
+
+    let data: serde_json::Map<String, Value> = columns
+        .iter()
+        .cloned()
+        .zip(data.into_iter().map(Value::Array))
+        .collect();
+
+    Ok(json!({
+        "columns": columns,
+        "data": data,
+        "stats": query_stats_to_json(stream.stats())
+    }))
+}
+
+/// Convert a result row to the JSON object expected by the SDKs
+fn query_row_to_json(columns: &[String], row: &[PropertyValue]) -> HashMap<String, Value> {
+    columns
+        .iter()
+        .zip(row)
+        .map(|(col_name, value)| (col_name.clone(), property_value_to_json(value)))
+        .collect()
+}
+
+/// Convert execution statistics to their JSON representation
+fn query_stats_to_json(stats: &qilbee_query::ExecutionStats) -> Value {
+    json!({
+        "nodesCreated": stats.nodes_created,
+        "nodesDeleted": stats.nodes_deleted,
+        "relationshipsCreated": stats.relationships_created,
+        "relationshipsDeleted": stats.relationships_deleted,
+        "propertiesSet": stats.properties_set,
+        "indexesAdded": stats.indexes_added,
+        "indexesRemoved": stats.indexes_removed,
+        "executionTimeMs": stats.execution_time_ms,
+        "cached": stats.cache_hits > 0
+    })
+}
+
+/// Serialize a query result stream as chunked JSON
+///
+/// Produces the same `{"results": [...], "stats": {...}}` document as the
+/// buffered path, but only ever holds one chunk of serialized rows at a time.
+/// The statistics, and `defaultLimit` if the planner added one, follow the
+/// last row once they are final. A chunk that fails ends the body with an
+/// error, which aborts the response.
+fn query_stream_body_chunks<F: FnOnce(QueryOutcome<'_>)>(
+    mut stream: ReportedStream<F>,
+    default_limit: Option<usize>,
+) -> impl Iterator<Item = std::io::Result<axum::body::Bytes>> {
+    use axum::body::Bytes;
+
+    let columns = stream.stream.columns().to_vec();
+    let mut prefix = Some(Bytes::from_static(b"{\"results\":["));
+    let mut first_row = true;
+    let mut done = false;
+
+    std::iter::from_fn(move || {
+        if let Some(prefix) = prefix.take() {
+            return Some(Ok(prefix));
+        }
+        if done {
+            return None;
+        }
+        match stream.stream.next() {
+            Some(Ok(chunk)) => {
+                let mut buf = Vec::new();
+                for row in chunk {
+                    if !first_row {
+                        buf.push(b',');
+                    }
+                    first_row = false;
+                    // Serializing a map of JSON values into a Vec cannot fail
+                    let _ = serde_json::to_writer(&mut buf, &query_row_to_json(&columns, &row));
+                }
+                Some(Ok(Bytes::from(buf)))
+            }
+            Some(Err(e)) => {
+                done = true;
+                stream.finish(Some(&e));
+                Some(Err(std::io::Error::other(e.to_string())))
+            }
             None => {
-                return (
-                    StatusCode::NOT_FOUND,
-                    Json(json!({
-                        "error": "Agent memory not found",
-                        "error_code": "AGENT_NOT_FOUND"
-                    })),
-                );
+                done = true;
+                stream.finish(None);
+                let stats = query_stats_to_json(stream.stream.stats());
+                let trailer = match default_limit {
+                    Some(limit) => {
+                        let default_limit = default_limit_json(limit, stream.stream.stats().rows_returned as usize);
+                        format!("],\"stats\":{},\"defaultLimit\":{}}}", stats, default_limit)
+                    }
+                    None => format!("],\"stats\":{}}}", stats),
+                };
+                Some(Ok(Bytes::from(trailer)))
             }
         }
+    })
+}
+
+// ==================== WebSocket Query Streaming ====================
+
+/// Credentials for WebSocket clients that can't send auth headers
+#[derive(Debug, Default, Deserialize)]
+struct WsAuthParams {
+    token: Option<String>,
+    #[serde(rename = "apiKey")]
+    api_key: Option<String>,
+}
+
+/// Frames sent by WebSocket query clients
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum WsClientFrame {
+    /// `{"type": "auth", "token": "..."}` or `{"type": "auth", "apiKey": "..."}`
+    Auth(WsAuthParams),
+    /// `{"type": "query", "cypher": "...", "parameters": {...}}`
+    Query(QueryRequestJson),
+}
+
+/// Upgrade to a WebSocket that runs queries and streams their results
+///
+/// Clients authenticate with the usual `Authorization` or `X-API-Key`
+/// headers, a `token`/`apiKey` query parameter, or an `auth` frame sent
+/// before the first query. Each `query` frame is answered with `rows` frames,
+/// one per executor chunk, followed by a `stats` frame, or with an `error`
+/// frame.
+async fn query_websocket(
+    State(state): State<AppState>,
+    Path(graph_name): Path<String>,
+    AxumQuery(credentials): AxumQuery<WsAuthParams>,
+    user: Option<axum::Extension<User>>,
+    ws: axum::extract::WebSocketUpgrade,
+) -> axum::response::Response {
+    let user = match user {
+        Some(axum::Extension(user)) => Some(user),
+        None => ws_authenticate(&state, &credentials),
     };
+    ws.on_upgrade(move |socket| query_socket(socket, state, graph_name, user))
+}
 
-    // For in-memory agents, hybrid search falls back to keyword search
-    // The keyword_weight and semantic_weight are recorded but not used
-    let keyword_weight = 1.0 - request.semantic_weight;
+/// Resolve WebSocket credentials to a user
+fn ws_authenticate(state: &AppState, credentials: &WsAuthParams) -> Option<User> {
+    let auth_service = &state.auth_middleware.auth_service;
+    if let Some(token) = &credentials.token {
+        return auth_service.validate_token(token).ok();
+    }
+    credentials
+        .api_key
+        .as_ref()
+        .and_then(|key| auth_service.validate_api_key(key).ok())
+}
 
-    match memory.search_episodes(&request.query) {
-        Ok(episodes) => {
-            let results: Vec<_> = episodes
-                .iter()
-                .take(request.limit)
-                .enumerate()
-                .map(|(i, ep)| {
-                    // Calculate simulated scores
-                    let keyword_score = 1.0 - (0.05 * i as f32);
-                    // Since no semantic search is available, semantic_score is null
-                    let combined_score = keyword_score * keyword_weight;
+/// Serve query frames until the client disconnects
+async fn query_socket(
+    mut socket: axum::extract::ws::WebSocket,
+    state: AppState,
+    graph_name: String,
+    mut user: Option<User>,
+) {
+    use axum::extract::ws::Message;
+
+    while let Some(Ok(message)) = socket.recv().await {
+        let text = match message {
+            Message::Text(text) => text,
+            Message::Close(_) => return,
+            _ => continue,
+        };
 
-                    json!({
-                        "episode": {
-                            "episodeId": ep.id.to_string(),
-                            "agentId": ep.agent_id,
-                            "episodeType": format!("{:?}", ep.episode_type),
-                            "content": {
-                                "primary": ep.content.primary,
-                                "secondary": ep.content.secondary
-                            },
-                            "eventTime": ep.event_time.as_millis(),
-                            "metadata": ep.metadata
-                        },
-                        "score": combined_score,
-                        "keywordScore": keyword_score,
-                        "semanticScore": null
-                    })
-                })
-                .collect();
-            (StatusCode::OK, Json(json!({"results": results})))
+        let sent = match serde_json::from_str::<WsClientFrame>(&text) {
+            Ok(WsClientFrame::Auth(credentials)) => {
+                user = ws_authenticate(&state, &credentials);
+                match user {
+                    Some(_) => ws_send(&mut socket, json!({"type": "authenticated"})).await,
+                    None => return ws_close_unauthorized(socket, "Invalid credentials").await,
+                }
+            }
+            Ok(WsClientFrame::Query(_)) if user.is_none() => {
+                return ws_close_unauthorized(socket, "Authentication required").await;
+            }
+            Ok(WsClientFrame::Query(request)) => {
+                stream_query_frames(&mut socket, &state, &graph_name, user.as_ref(), request).await
+            }
+            Err(e) => ws_send(&mut socket, ws_error_frame(format!("Invalid frame: {}", e))).await,
+        };
+
+        if sent.is_err() {
+            // Client went away; dropping the socket ends the connection
+            return;
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": e.to_string(),
-                "error_code": "SEARCH_ERROR"
-            })),
-        ),
     }
 }
 
-#[tracing::instrument(
-    name = "memory.find_similar_episodes",
-    skip(state),
-    fields(agent_id = %agent_id, episode_id = %episode_id)
-)]
-async fn find_similar_episodes(
-    State(state): State<AppState>,
-    Path((agent_id, episode_id)): Path<(String, String)>,
-    AxumQuery(query): AxumQuery<FindSimilarQuery>,
-) -> impl IntoResponse {
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        match memories.get(&agent_id) {
-            Some(m) => m.clone(),
-            None => {
-                return (
-                    StatusCode::NOT_FOUND,
-                    Json(json!({
-                        "error": "Agent memory not found",
-                        "error_code": "AGENT_NOT_FOUND"
-                    })),
-                );
-            }
+/// Run a query and send its result frames
+///
+/// The query executes on the blocking pool while the socket is watched for
+/// a disconnect. If the client leaves, either mid-execution or while rows
+/// are being sent, the remaining result is dropped without being serialized
+/// and an error is returned.
+async fn stream_query_frames(
+    socket: &mut axum::extract::ws::WebSocket,
+    state: &AppState,
+    graph_name: &str,
+    user: Option<&User>,
+    request: QueryRequestJson,
+) -> Result<(), axum::Error> {
+    use axum::extract::ws::Message;
+
+    let graph = match state.database.graph(graph_name) {
+        Ok(graph) => graph,
+        Err(e) => return ws_send(socket, ws_error_frame(e.to_string())).await,
+    };
+    let default_limit = state.default_query_limit;
+    let parser_limits = state.parser_limits;
+    let metrics = state.metrics.clone();
+    let execution = tokio::task::spawn_blocking(move || {
+        let result = execute_query_stream(graph, &request, default_limit, parser_limits, &metrics);
+        (request, result)
+    });
+    tokio::pin!(execution);
+
+    let result = loop {
+        tokio::select! {
+            result = &mut execution => break result,
+            message = socket.recv() => match message {
+                None | Some(Err(_)) | Some(Ok(Message::Close(_))) => {
+                    return Err(axum::Error::new("client disconnected during query execution"));
+                }
+                Some(Ok(Message::Text(_))) => {
+                    ws_send(socket, ws_error_frame("A query is already running".to_string())).await?;
+                }
+                Some(Ok(_)) => {}
+            },
         }
     };
 
-    // Get the source episode first
-    let episodes = match memory.get_recent_episodes(100) {
-        Ok(eps) => eps,
+    let (request, result) = match result {
+        Ok(executed) => executed,
+        Err(e) => return ws_send(socket, ws_error_frame(format!("Execution error: {}", e))).await,
+    };
+    let (mut stream, default_limit) = match result {
+        Ok(result) => result,
         Err(e) => {
-            return (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({
-                    "error": e.to_string(),
-                    "error_code": "STORAGE_ERROR"
-                })),
-            );
+            audit_query(state, user, graph_name, &request, Err(&e));
+            return ws_send(socket, ws_error_frame(e.to_string())).await;
         }
     };
 
-    let source_episode = match episodes.iter().find(|ep| ep.id.to_string() == episode_id) {
-        Some(ep) => ep,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({
-                    "error": format!("Episode {} not found", episode_id),
-                    "error_code": "EPISODE_NOT_FOUND"
-                })),
-            );
+    // Rows are produced on the blocking pool, one chunk per frame
+    let columns = stream.columns().to_vec();
+    loop {
+        let (rest, chunk) = tokio::task::spawn_blocking(move || {
+            let chunk = stream.next();
+            (stream, chunk)
+        })
+        .await
+        .map_err(axum::Error::new)?;
+        stream = rest;
+
+        match chunk {
+            Some(Ok(rows)) => {
+                if let Err(e) = ws_send(socket, query_rows_ws_frame(&columns, &rows)).await {
+                    audit_query(state, user, graph_name, &request, Ok(stream.stats()));
+                    return Err(e);
+                }
+            }
+            Some(Err(e)) => {
+                audit_query(state, user, graph_name, &request, Err(&e));
+                return ws_send(socket, ws_error_frame(e.to_string())).await;
+            }
+            None => break,
         }
-    };
+    }
 
-    // For in-memory agents without vector index, find similar episodes by keyword overlap
-    // Search using the source episode's content
-    let search_query = format!(
-        "{} {}",
-        source_episode.content.primary,
-        source_episode.content.secondary.as_deref().unwrap_or("")
-    );
+    audit_query(state, user, graph_name, &request, Ok(stream.stats()));
+    ws_send(socket, query_stats_ws_frame(stream.stats(), default_limit)).await
+}
 
-    match memory.search_episodes(&search_query) {
-        Ok(similar_episodes) => {
-            let results: Vec<_> = similar_episodes
-                .iter()
-                .filter(|ep| ep.id.to_string() != episode_id) // Exclude source episode
-                .take(query.limit)
-                .enumerate()
-                .map(|(i, ep)| {
-                    let score = 1.0 - (0.1 * i as f32);
-                    json!({
-                        "episode": {
-                            "episodeId": ep.id.to_string(),
-                            "agentId": ep.agent_id,
-                            "episodeType": format!("{:?}", ep.episode_type),
-                            "content": {
-                                "primary": ep.content.primary,
-                                "secondary": ep.content.secondary
-                            },
-                            "eventTime": ep.event_time.as_millis(),
-                            "metadata": ep.metadata
-                        },
-                        "score": score
-                    })
-                })
-                .collect();
-            (StatusCode::OK, Json(json!({"results": results})))
+/// Parse, plan and execute a query for the WebSocket transport
+///
+/// Returns the result stream and, if the planner added one, the default
+/// LIMIT.
+fn execute_query_stream(
+    graph: qilbee_graph::Graph,
+    request: &QueryRequestJson,
+    default_limit: Option<usize>,
+    parser_limits: qilbee_query::ParserLimits,
+    metrics: &ServerMetrics,
+) -> qilbee_core::Result<(qilbee_query::QueryResultStream, Option<usize>)> {
+    use qilbee_query::{parse_simple_with_limits, QueryExecutor, QueryPlanner, DEFAULT_STREAM_CHUNK_SIZE};
+
+    let query = parse_simple_with_limits(&request.cypher, parser_limits)?;
+    let mut planner = QueryPlanner::new()
+        .with_graph_schema(&graph)?
+        .with_graph_statistics(&graph, &query)?;
+    if let Some(limit) = default_limit {
+        planner = planner.with_default_limit(limit);
+    }
+    let plan = planner.plan(&query)?;
+    let params = query_parameters(request.parameters.as_ref());
+    let started = Instant::now();
+    let executed = QueryExecutor::new(Arc::new(graph)).execute_stream(&plan, &params, DEFAULT_STREAM_CHUNK_SIZE);
+    metrics.record_query(QueryOperation::of(query.is_read_only()), started.elapsed());
+    Ok((executed?, plan.default_limit))
+}
+
+/// Frame carrying one chunk of result rows
+///
+/// Rows use the same objects as the HTTP `results` array.
+fn query_rows_ws_frame(columns: &[String], rows: &[Vec<PropertyValue>]) -> Value {
+    let rows: Vec<_> = rows.iter().map(|row| query_row_to_json(columns, row)).collect();
+    json!({"type": "rows", "rows": rows})
+}
+
+/// Frame closing a query result, sent after its last `rows` frame
+fn query_stats_ws_frame(stats: &qilbee_query::ExecutionStats, default_limit: Option<usize>) -> Value {
+    let mut frame = json!({"type": "stats", "stats": query_stats_to_json(stats)});
+    if let Some(limit) = default_limit {
+        frame["defaultLimit"] = default_limit_json(limit, stats.rows_returned as usize);
+    }
+    frame
+}
+
+fn ws_error_frame(message: String) -> Value {
+    json!({"type": "error", "error": message})
+}
+
+async fn ws_send(socket: &mut axum::extract::ws::WebSocket, frame: Value) -> Result<(), axum::Error> {
+    socket.send(axum::extract::ws::Message::Text(frame.to_string())).await
+}
+
+/// Close the socket with a policy violation after failed authentication
+async fn ws_close_unauthorized(mut socket: axum::extract::ws::WebSocket, reason: &str) {
+    use axum::extract::ws::{close_code, CloseFrame, Message};
+
+    let _ = ws_send(&mut socket, ws_error_frame(reason.to_string())).await;
+    let _ = socket
+        .send(Message::Close(Some(CloseFrame {
+            code: close_code::POLICY,
+            reason: reason.to_string().into(),
+        })))
+        .await;
+}
+
+// ==================== Memory Operations ====================
+
+/// Error types for memory operations with proper HTTP status code mapping
+#[derive(Debug)]
+enum MemoryOperationError {
+    /// Agent memory not found (404)
+    AgentNotFound { agent_id: String },
+    /// Episode not found (404)
+    EpisodeNotFound { agent_id: String, episode_id: String },
+    /// Request validation failed (400)
+    ValidationError { field: String, message: String },
+    /// Memory operation failed (500)
+    OperationFailed { operation: String, message: String },
+    /// Storage error (500)
+    StorageError { message: String },
+}
+
+impl MemoryOperationError {
+    fn error_code(&self) -> &'static str {
+        match self {
+            MemoryOperationError::AgentNotFound { .. } => "AGENT_NOT_FOUND",
+            MemoryOperationError::EpisodeNotFound { .. } => "EPISODE_NOT_FOUND",
+            MemoryOperationError::ValidationError { .. } => "VALIDATION_ERROR",
+            MemoryOperationError::OperationFailed { .. } => "OPERATION_FAILED",
+            MemoryOperationError::StorageError { .. } => "STORAGE_ERROR",
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": e.to_string(),
-                "error_code": "SEARCH_ERROR"
-            })),
-        ),
     }
 }
 
-#[tracing::instrument(
-    name = "memory.get_semantic_search_status",
-    skip(state),
-    fields(agent_id = %agent_id)
-)]
-async fn get_semantic_search_status(
-    State(state): State<AppState>,
-    Path(agent_id): Path<String>,
-) -> impl IntoResponse {
-    // Check if agent memory exists
-    let memory_exists = {
-        let memories = state.agent_memories.lock().unwrap();
-        memories.contains_key(&agent_id)
-    };
+impl std::fmt::Display for MemoryOperationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MemoryOperationError::AgentNotFound { agent_id } => {
+                write!(f, "Agent memory not found for agent_id: {}", agent_id)
+            }
+            MemoryOperationError::EpisodeNotFound { agent_id, episode_id } => {
+                write!(f, "Episode {} not found for agent {}", episode_id, agent_id)
+            }
+            MemoryOperationError::ValidationError { field, message } => {
+                write!(f, "Validation error for '{}': {}", field, message)
+            }
+            MemoryOperationError::OperationFailed { operation, message } => {
+                write!(f, "{} operation failed: {}", operation, message)
+            }
+            MemoryOperationError::StorageError { message } => {
+                write!(f, "Storage error: {}", message)
+            }
+        }
+    }
+}
 
-    // For in-memory agents (AgentMemory), semantic search is not available
-    // Only PersistentAgentMemory supports semantic search
-    (
-        StatusCode::OK,
-        Json(json!({
-            "enabled": false,
-            "model": null,
-            "dimensions": null,
-            "indexedEpisodes": 0,
-            "agentExists": memory_exists,
-            "message": "Semantic search requires PersistentAgentMemory with vector embeddings enabled"
-        })),
-    )
+impl IntoResponse for MemoryOperationError {
+    fn into_response(self) -> axum::response::Response {
+        let (status, error_response) = match &self {
+            MemoryOperationError::AgentNotFound { agent_id } => (
+                StatusCode::NOT_FOUND,
+                json!({
+                    "error": self.to_string(),
+                    "error_code": self.error_code(),
+                    "agent_id": agent_id
+                }),
+            ),
+            MemoryOperationError::EpisodeNotFound { agent_id, episode_id } => (
+                StatusCode::NOT_FOUND,
+                json!({
+                    "error": self.to_string(),
+                    "error_code": self.error_code(),
+                    "agent_id": agent_id,
+                    "episode_id": episode_id
+                }),
+            ),
+            MemoryOperationError::ValidationError { field, message } => (
+                StatusCode::BAD_REQUEST,
+                json!({
+                    "error": "Validation failed",
+                    "error_code": self.error_code(),
+                    "field": field,
+                    "details": message
+                }),
+            ),
+            MemoryOperationError::OperationFailed { operation, message } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({
+                    "error": self.to_string(),
+                    "error_code": self.error_code(),
+                    "operation": operation,
+                    "details": message
+                }),
+            ),
+            MemoryOperationError::StorageError { message } => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                json!({
+                    "error": self.to_string(),
+                    "error_code": self.error_code(),
+                    "details": message
+                }),
+            ),
+        };
+        (status, Json(error_response)).into_response()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StoreEpisodeRequest {
+    #[serde(rename = "agentId")]
+    agent_id: String,
+    #[serde(rename = "episodeType")]
+    episode_type: String,
+    content: HashMap<String, Value>,
+    #[serde(rename = "eventTime")]
+    event_time: Option<i64>,
+    metadata: Option<HashMap<String, Value>>,
+}
+
+/// Request body for consolidating agent memory
+#[derive(Debug, Deserialize)]
+struct ConsolidateMemoryRequest {
+    /// Minimum relevance threshold (0.0 to 1.0) - episodes below this after decay are evicted
+    #[serde(default = "default_min_relevance")]
+    min_relevance: f64,
+    /// Decay factor to apply (0.0 to 1.0) - lower means more aggressive decay
+    #[serde(default = "default_decay_factor")]
+    decay_factor: f64,
+}
+
+fn default_min_relevance() -> f64 {
+    0.3
+}
+
+fn default_decay_factor() -> f64 {
+    0.9
+}
+
+impl ConsolidateMemoryRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.min_relevance < 0.0 || self.min_relevance > 1.0 {
+            return Err("min_relevance must be between 0.0 and 1.0".to_string());
+        }
+        if self.decay_factor < 0.0 || self.decay_factor > 1.0 {
+            return Err("decay_factor must be between 0.0 and 1.0".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// Request body for forgetting agent memory
+#[derive(Debug, Deserialize)]
+struct ForgetMemoryRequest {
+    /// Minimum relevance threshold (0.0 to 1.0) - episodes below this will be forgotten
+    #[serde(default = "default_forget_min_relevance")]
+    min_relevance: f64,
+    /// Maximum age in seconds - only episodes older than this are forgotten
+    #[serde(default)]
+    max_age_seconds: Option<u64>,
+    /// Episode types to target for forgetting (empty means all types)
+    #[serde(default)]
+    episode_types: Vec<String>,
+}
+
+fn default_forget_min_relevance() -> f64 {
+    0.1
+}
+
+impl ForgetMemoryRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.min_relevance < 0.0 || self.min_relevance > 1.0 {
+            return Err("min_relevance must be between 0.0 and 1.0".to_string());
+        }
+        Ok(())
+    }
+
+    /// Criteria matching episodes that meet every supplied condition
+    fn criteria(&self) -> ForgetCriteria {
+        let mut criteria = ForgetCriteria::new()
+            .min_relevance(self.min_relevance)
+            .episode_types(self.episode_types.iter().map(|t| parse_episode_type_filter(t)));
+        if let Some(max_age_seconds) = self.max_age_seconds {
+            criteria = criteria.max_age(std::time::Duration::from_secs(max_age_seconds));
+        }
+        criteria
+    }
+}
+
+/// Forgotten episode counts keyed by episode type name
+fn forgotten_by_type(report: &ForgetReport) -> serde_json::Map<String, Value> {
+    report
+        .by_type
+        .iter()
+        .map(|(episode_type, n)| (episode_type_name(episode_type), json!(n)))
+        .collect()
 }
 
 #[tracing::instrument(
-    name = "memory.get_statistics",
-    skip(state),
+    name = "memory.store_episode",
+    skip(state, request),
     fields(agent_id = %agent_id)
 )]
-async fn get_memory_statistics(
+async fn store_episode(
     State(state): State<AppState>,
     Path(agent_id): Path<String>,
+    Json(request): Json<StoreEpisodeRequest>,
 ) -> impl IntoResponse {
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        match memories.get(&agent_id) {
-            Some(m) => m.clone(),
-            None => {
-                // Return empty stats if agent doesn't exist yet
-                return (
-                    StatusCode::OK,
-                    Json(json!({
-                        "totalEpisodes": 0,
-                        "episodesByType": {},
-                        "oldestEpisode": null,
-                        "newestEpisode": null,
-                        "avgRelevance": 0.0
-                    })),
-                );
+    // Get or create agent memory
+    let memory = state.agent_memory(&agent_id).await;
+
+    let episode = episode_from_request(&agent_id, &request);
+    let episode_id = episode.id.to_string();
+
+    // Store episode, then add it to the vector index when semantic search is enabled
+    match memory.store_episode(episode.clone()).await {
+        Ok(_) => {
+            if memory.has_semantic_search()
+                && let Err(e) = memory.index_episode(&episode).await
+            {
+                tracing::warn!("Failed to index episode {}: {}", episode_id, e);
             }
+            (StatusCode::CREATED, Json(json!({"episodeId": episode_id})))
         }
-    };
-
-    // Get statistics
-    match memory.get_statistics() {
-        Ok(stats) => (
-            StatusCode::OK,
-            Json(json!({
-                "totalEpisodes": stats.total_episodes,
-                "episodesByType": {},
-                "oldestEpisode": stats.oldest_episode,
-                "newestEpisode": stats.newest_episode,
-                "avgRelevance": stats.avg_relevance
-            })),
-        ),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(json!({"error": e.to_string()})),
@@ -1448,1007 +2562,2851 @@ async fn get_memory_statistics(
     }
 }
 
+/// Maximum number of episodes in one batch store request
+const MAX_BATCH_EPISODES: usize = 1000;
+
 #[tracing::instrument(
-    name = "memory.consolidate",
-    skip(state, request),
-    fields(agent_id = %agent_id, min_relevance = %request.min_relevance, decay_factor = %request.decay_factor)
+    name = "memory.store_episodes_batch",
+    skip(state, items),
+    fields(agent_id = %agent_id, count = items.len())
 )]
-async fn consolidate_memory(
+async fn store_episodes_batch(
     State(state): State<AppState>,
     Path(agent_id): Path<String>,
-    Json(request): Json<ConsolidateMemoryRequest>,
-) -> Result<impl IntoResponse, MemoryOperationError> {
-    // Validate request parameters
-    request.validate().map_err(|e| MemoryOperationError::ValidationError {
-        field: "request".to_string(),
-        message: e,
-    })?;
+    Json(items): Json<Vec<Value>>,
+) -> impl IntoResponse {
+    if items.len() > MAX_BATCH_EPISODES {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("A batch may contain at most {} episodes", MAX_BATCH_EPISODES),
+                "error_code": "VALIDATION_ERROR"
+            })),
+        );
+    }
 
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        memories.get(&agent_id).cloned().ok_or_else(|| {
-            MemoryOperationError::AgentNotFound {
-                agent_id: agent_id.clone(),
+    // Validate each item on its own so one bad episode does not fail the batch
+    let mut episodes = Vec::new();
+    let mut results = Vec::with_capacity(items.len());
+    for (index, item) in items.into_iter().enumerate() {
+        let parsed = serde_json::from_value::<StoreEpisodeRequest>(item)
+            .map_err(|e| e.to_string())
+            .and_then(|request| {
+                if request.agent_id == agent_id {
+                    Ok(request)
+                } else {
+                    Err(format!("agentId '{}' does not match the path", request.agent_id))
+                }
+            });
+        match parsed {
+            Ok(request) => {
+                let episode = episode_from_request(&agent_id, &request);
+                results.push(json!({"index": index, "episodeId": episode.id.to_string()}));
+                episodes.push(episode);
             }
-        })?
+            Err(error) => results.push(json!({"index": index, "error": error})),
+        }
+    }
+
+    if !episodes.is_empty() {
+        let memory = state.agent_memory(&agent_id).await;
+        if let Err(e) = memory.store_episodes(&episodes).await {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
+            );
+        }
+        if memory.has_semantic_search()
+            && let Err(e) = memory.index_episodes(&episodes).await
+        {
+            tracing::warn!("Failed to index {} episodes: {}", episodes.len(), e);
+        }
+    }
+
+    let failed = results.len() - episodes.len();
+    let status = if failed == 0 {
+        StatusCode::CREATED
+    } else {
+        StatusCode::MULTI_STATUS
     };
+    let episode_ids: Vec<_> = episodes.iter().map(|e| e.id.to_string()).collect();
+    (
+        status,
+        Json(json!({
+            "episodeIds": episode_ids,
+            "results": results,
+            "stored": episodes.len(),
+            "failed": failed
+        })),
+    )
+}
 
-    // Get episode count before consolidation
-    let episodes_before = memory.episode_count().unwrap_or(0);
+/// Build an episode for `agent_id` from a store request
+fn episode_from_request(agent_id: &str, request: &StoreEpisodeRequest) -> Episode {
+    // Parse episode type
+    let episode_type = match request.episode_type.as_str() {
+        "conversation" | "Conversation" => EpisodeType::Conversation,
+        "observation" | "Observation" => EpisodeType::Observation,
+        "action" => EpisodeType::Custom("Action".to_string()),
+        _ => EpisodeType::Conversation,
+    };
 
-    // Apply decay (consolidation operation)
-    // Note: Currently using default apply_decay; future enhancement could use request.min_relevance and request.decay_factor
-    memory.apply_decay().map_err(|e| {
-        // Log audit event for failure
-        state.audit_service.log_memory_event(
-            AuditEventType::MemoryConsolidated,
-            None,
-            None,
-            &agent_id,
-            AuditResult::Error,
-            None,
-            serde_json::json!({"error": e.to_string()}),
-        );
-        MemoryOperationError::OperationFailed {
-            operation: "consolidate".to_string(),
-            message: e.to_string(),
-        }
-    })?;
+    // Create episode content from HashMap
+    // Support multiple field names for primary content:
+    // - primary, input, message, user_input, text, observation, action
+    let primary_content = request
+        .content
+        .get("primary")
+        .or(request.content.get("input"))
+        .or(request.content.get("message"))
+        .or(request.content.get("user_input"))
+        .or(request.content.get("text"))
+        .or(request.content.get("observation"))
+        .or(request.content.get("action"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
 
-    // Get episode count after consolidation
-    let episodes_after = memory.episode_count().unwrap_or(0);
+    let mut content = EpisodeContent::new(primary_content);
 
-    // Log audit event
-    state.audit_service.log_memory_event(
-        AuditEventType::MemoryConsolidated,
-        None,
-        None,
-        &agent_id,
-        AuditResult::Success,
-        None,
-        serde_json::json!({
-            "episodes_before": episodes_before,
-            "episodes_after": episodes_after,
-            "min_relevance": request.min_relevance,
-            "decay_factor": request.decay_factor
-        }),
-    );
+    // Support multiple field names for secondary content:
+    // - secondary, output, response, agent_response, result
+    if let Some(secondary) = request.content.get("secondary")
+        .or(request.content.get("output"))
+        .or(request.content.get("response"))
+        .or(request.content.get("agent_response"))
+        .or(request.content.get("result"))
+    {
+        if let Some(s) = secondary.as_str() {
+            content = content.with_secondary(s);
+        }
+    }
 
-    Ok((StatusCode::OK, Json(json!({
-        "consolidated": episodes_after,
-        "episodes_before": episodes_before,
-        "episodes_after": episodes_after,
-        "min_relevance": request.min_relevance,
-        "decay_factor": request.decay_factor
-    }))))
+    // Create episode, at the client's event time when given
+    match request.event_time {
+        Some(millis) => {
+            Episode::with_event_time(agent_id, episode_type, content, EventTime::from_millis(millis))
+        }
+        None => Episode::new(agent_id, episode_type, content),
+    }
 }
 
 #[tracing::instrument(
-    name = "memory.forget",
-    skip(state, request),
-    fields(agent_id = %agent_id, min_relevance = %request.min_relevance)
+    name = "memory.get_episode",
+    skip(state),
+    fields(agent_id = %agent_id, episode_id = %episode_id)
 )]
-async fn forget_memory(
+async fn get_episode(
     State(state): State<AppState>,
-    Path(agent_id): Path<String>,
-    Json(request): Json<ForgetMemoryRequest>,
+    Path((agent_id, episode_id)): Path<(String, String)>,
 ) -> Result<impl IntoResponse, MemoryOperationError> {
-    // Validate request parameters
-    request.validate().map_err(|e| MemoryOperationError::ValidationError {
-        field: "request".to_string(),
-        message: e,
-    })?;
-
     // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        memories.get(&agent_id).cloned().ok_or_else(|| {
-            MemoryOperationError::AgentNotFound {
-                agent_id: agent_id.clone(),
-            }
-        })?
-    };
-
-    // Get episode count before forget
-    let episodes_before = memory.episode_count().unwrap_or(0);
+    let memory = state
+        .existing_agent_memory(&agent_id)
+        .await
+        .ok_or_else(|| MemoryOperationError::AgentNotFound {
+            agent_id: agent_id.clone(),
+        })?;
 
-    // Forget low-relevance episodes
-    // Note: Currently using default forget; future enhancement could use request.min_relevance and request.max_age_seconds
-    let count = memory.forget().map_err(|e| {
-        // Log audit event for failure
-        state.audit_service.log_memory_event(
-            AuditEventType::MemoryForgotten,
-            None,
-            None,
-            &agent_id,
-            AuditResult::Error,
-            None,
-            serde_json::json!({"error": e.to_string()}),
-        );
-        MemoryOperationError::OperationFailed {
-            operation: "forget".to_string(),
-            message: e.to_string(),
-        }
-    })?;
+    let episode = memory
+        .get_episode_by_id_str(&episode_id)
+        .await
+        .map_err(|e| match e {
+            qilbee_core::Error::ValidationError(message) => MemoryOperationError::ValidationError {
+                field: "episode_id".to_string(),
+                message,
+            },
+            e => MemoryOperationError::StorageError {
+                message: e.to_string(),
+            },
+        })?
+        .ok_or_else(|| MemoryOperationError::EpisodeNotFound {
+            agent_id: agent_id.clone(),
+            episode_id: episode_id.clone(),
+        })?;
 
-    // Get episode count after forget
-    let episodes_after = memory.episode_count().unwrap_or(0);
+    // Format content based on episode type for intuitive field names
+    let content_map = format_episode_content(&episode);
 
-    // Log audit event
-    state.audit_service.log_memory_event(
-        AuditEventType::MemoryForgotten,
-        None,
-        None,
-        &agent_id,
-        AuditResult::Success,
-        None,
-        serde_json::json!({
-            "episodes_before": episodes_before,
-            "episodes_after": episodes_after,
-            "episodes_forgotten": count,
-            "min_relevance": request.min_relevance,
-            "max_age_seconds": request.max_age_seconds,
-            "episode_types": request.episode_types
-        }),
-    );
+    let response = json!({
+        "agentId": episode.agent_id,
+        "episodeId": episode.id.to_string(),
+        "episodeType": format!("{:?}", episode.episode_type),
+        "content": content_map,
+        "eventTime": episode.event_time.as_millis(),
+        "accessCount": episode.access_count(),
+        "lastAccessed": episode.last_accessed()
+    });
+    Ok((StatusCode::OK, Json(response)))
+}
 
-    Ok((StatusCode::OK, Json(json!({
-        "forgotten": count,
-        "episodes_before": episodes_before,
-        "episodes_after": episodes_after,
-        "min_relevance": request.min_relevance,
-        "max_age_seconds": request.max_age_seconds,
-        "episode_types": request.episode_types
-    }))))
+#[derive(Debug, Deserialize)]
+struct ListEpisodesQuery {
+    /// Only return episodes that are (`true`) or are not (`false`) consolidated
+    consolidated: Option<bool>,
 }
 
 #[tracing::instrument(
-    name = "memory.clear",
-    skip(state),
-    fields(agent_id = %agent_id)
+    name = "memory.list_episodes",
+    skip(state, query),
+    fields(agent_id = %agent_id, consolidated = ?query.consolidated)
 )]
-async fn clear_memory(
+async fn list_episodes(
     State(state): State<AppState>,
     Path(agent_id): Path<String>,
+    AxumQuery(query): AxumQuery<ListEpisodesQuery>,
 ) -> Result<impl IntoResponse, MemoryOperationError> {
-    // Get agent memory
-    let memory = {
-        let memories = state.agent_memories.lock().unwrap();
-        memories.get(&agent_id).cloned().ok_or_else(|| {
-            MemoryOperationError::AgentNotFound { agent_id: agent_id.clone() }
-        })?
+    let Some(memory) = state.existing_agent_memory(&agent_id).await else {
+        return Ok((StatusCode::OK, Json(json!({"episodes": [], "count": 0}))));
     };
 
-    // Get episode count before clear
-    let episodes_before = memory.episode_count().unwrap_or(0);
-
-    // Clear all episodes
-    memory.clear().map_err(|e| {
-        // Log audit event for failure
-        state.audit_service.log_memory_event(
-            AuditEventType::MemoryCleared,
-            None,
-            None,
-            &agent_id,
-            AuditResult::Error,
-            None,
-            serde_json::json!({"error": e.to_string()}),
-        );
-        MemoryOperationError::OperationFailed {
-            operation: "clear".to_string(),
-            message: e.to_string(),
-        }
-    })?;
-
-    // Log audit event for success
-    state.audit_service.log_memory_event(
-        AuditEventType::MemoryCleared,
-        None,
-        None,
-        &agent_id,
-        AuditResult::Success,
-        None,
-        serde_json::json!({
-            "episodes_cleared": episodes_before
+    let episodes = match query.consolidated {
+        Some(consolidated) => memory.get_episodes_by_consolidation(consolidated).await,
+        None => memory.get_all_episodes().await.map(|mut episodes| {
+            episodes.sort_by_key(|e| e.event_time);
+            episodes
         }),
-    );
+    }
+    .map_err(|e| MemoryOperationError::StorageError {
+        message: e.to_string(),
+    })?;
 
-    Ok((StatusCode::OK, Json(json!({"cleared": true, "episodes_cleared": episodes_before}))))
+    let episode_list: Vec<_> = episodes
+        .iter()
+        .map(|ep| {
+            json!({
+                "agentId": ep.agent_id,
+                "episodeId": ep.id.to_string(),
+                "episodeType": format!("{:?}", ep.episode_type),
+                "content": format_episode_content(ep),
+                "eventTime": ep.event_time.as_millis(),
+                "accessCount": ep.access_count(),
+                "lastAccessed": ep.last_accessed(),
+                "consolidated": ep.consolidated
+            })
+        })
+        .collect();
+    Ok((
+        StatusCode::OK,
+        Json(json!({"episodes": episode_list, "count": episode_list.len()})),
+    ))
 }
 
-// ==================== Authentication Operations ====================
-
 #[derive(Debug, Deserialize)]
-struct LoginRequest {
-    username: String,
-    password: String,
+struct RecentEpisodesQuery {
+    #[serde(default = "default_search_limit")]
+    limit: usize,
+    /// Cursor from a previous page's `nextCursor`, or a time in milliseconds
+    before: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
-struct LoginResponse {
-    access_token: String,
-    token_type: String,
-    expires_in: u64,
-    refresh_token: Option<String>,
-    username: String,
-    user_id: String,
+/// Parse an optional `before` pagination cursor
+fn parse_before_cursor(before: Option<&str>) -> Result<Option<EpisodeCursor>, MemoryOperationError> {
+    before
+        .map(|cursor| {
+            cursor.parse().map_err(|e: qilbee_core::Error| MemoryOperationError::ValidationError {
+                field: "before".to_string(),
+                message: e.to_string(),
+            })
+        })
+        .transpose()
 }
 
-async fn auth_login(
+#[tracing::instrument(
+    name = "memory.get_recent_episodes",
+    skip(state, query),
+    fields(agent_id = %agent_id)
+)]
+async fn get_recent_episodes(
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Json(request): Json<LoginRequest>,
-) -> impl IntoResponse {
-    // Extract client IP from X-Forwarded-For header or X-Real-IP
-    let client_ip = headers
-        .get("X-Forwarded-For")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            headers
-                .get("X-Real-IP")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        });
+    Path(agent_id): Path<String>,
+    AxumQuery(query): AxumQuery<RecentEpisodesQuery>,
+) -> Result<impl IntoResponse, MemoryOperationError> {
+    let before = parse_before_cursor(query.before.as_deref())?;
 
-    // Check if account is locked before attempting login
-    if let Err(lockout_status) = state.lockout_service.check_login_allowed(
-        &request.username,
-        client_ip.as_deref(),
-    ) {
-        // Log account lockout event
-        state.audit_service.log_auth_event(
-            AuditEventType::AccountLockoutTriggered,
-            &request.username,
-            AuditResult::Failure,
-            client_ip.clone(),
-            Some(format!(
-                "Account locked. Remaining: {:?}s, Reason: {:?}",
-                lockout_status.lockout_remaining_seconds,
-                lockout_status.lockout_reason
-            )),
-        );
+    // Get agent memory
+    let Some(memory) = state.existing_agent_memory(&agent_id).await else {
+        // Return empty list if agent doesn't exist yet
+        return Ok((StatusCode::OK, Json(json!({"episodes": [], "nextCursor": null}))));
+    };
 
-        return (
-            StatusCode::TOO_MANY_REQUESTS,
-            Json(json!({
-                "error": "Account locked due to too many failed login attempts",
-                "locked": true,
-                "lockout_expires": lockout_status.lockout_expires,
-                "lockout_remaining_seconds": lockout_status.lockout_remaining_seconds,
-                "lockout_reason": lockout_status.lockout_reason,
-            })),
-        );
-    }
+    let page = memory
+        .get_episodes_page(before, query.limit)
+        .await
+        .map_err(|e| MemoryOperationError::StorageError {
+            message: e.to_string(),
+        })?;
 
-    let credentials = Credentials {
-        username: request.username.clone(),
-        password: request.password,
+    let episode_list: Vec<_> = page
+        .episodes
+        .iter()
+        .map(|ep| {
+            json!({
+                "agentId": ep.agent_id,
+                "episodeId": ep.id.to_string(),
+                "episodeType": format!("{:?}", ep.episode_type),
+                "content": format_episode_content(ep),
+                "eventTime": ep.event_time.as_millis(),
+                "accessCount": ep.access_count(),
+                "lastAccessed": ep.last_accessed()
+            })
+        })
+        .collect();
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "episodes": episode_list,
+            "nextCursor": page.next_cursor.map(|c| c.to_string())
+        })),
+    ))
+}
+
+#[derive(Debug, Deserialize)]
+struct EpisodesAsOfQuery {
+    /// Transaction time in milliseconds since the Unix epoch
+    t: i64,
+}
+
+#[tracing::instrument(
+    name = "memory.get_episodes_as_of",
+    skip(state, query),
+    fields(agent_id = %agent_id, as_of = query.t)
+)]
+async fn get_episodes_as_of(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+    AxumQuery(query): AxumQuery<EpisodesAsOfQuery>,
+) -> Result<impl IntoResponse, MemoryOperationError> {
+    // An agent without memory knew nothing at any time
+    let Some(memory) = state.existing_agent_memory(&agent_id).await else {
+        return Ok((StatusCode::OK, Json(json!({"asOf": query.t, "episodes": []}))));
     };
 
-    match state.auth_service.login(credentials) {
-        Ok(token) => {
-            // Record successful login (resets failed attempt counter)
-            state.lockout_service.record_successful_login(
-                &request.username,
-                client_ip.as_deref(),
-            );
+    let episodes = memory
+        .get_episodes_as_of(TransactionTime::from_millis(query.t))
+        .await
+        .map_err(|e| MemoryOperationError::StorageError {
+            message: e.to_string(),
+        })?;
 
-            // Log successful login
-            state.audit_service.log_auth_event(
-                AuditEventType::Login,
-                &request.username,
-                AuditResult::Success,
-                client_ip,
-                None,
-            );
+    let episode_list: Vec<_> = episodes
+        .iter()
+        .map(|ep| {
+            json!({
+                "agentId": ep.agent_id,
+                "episodeId": ep.id.to_string(),
+                "episodeType": format!("{:?}", ep.episode_type),
+                "content": format_episode_content(ep),
+                "eventTime": ep.event_time.as_millis(),
+                "accessCount": ep.access_count(),
+                "lastAccessed": ep.last_accessed(),
+                "transactionTime": ep.transaction_time.as_millis(),
+                "invalidatedAt": ep.invalidated_at.map(|t| t.as_millis())
+            })
+        })
+        .collect();
+    Ok((StatusCode::OK, Json(json!({"asOf": query.t, "episodes": episode_list}))))
+}
 
-            // Get user_id from token claims (we should validate token to get claims)
-            // For now, we'll just return the token info
-            let response = LoginResponse {
-                access_token: token.access_token.clone(),
-                token_type: token.token_type,
-                expires_in: token.expires_in,
-                refresh_token: token.refresh_token,
-                username: request.username,
-                user_id: "user_id_placeholder".to_string(), // TODO: Extract from token
-            };
-            (StatusCode::OK, Json(json!(response)))
-        }
-        Err(e) => {
-            // Record failed login attempt
-            let lockout_status = state.lockout_service.record_failed_attempt(
-                &request.username,
-                client_ip.as_deref(),
-            );
+#[derive(Debug, Deserialize)]
+struct SearchEpisodesRequest {
+    query: String,
+    #[serde(default = "default_search_limit")]
+    limit: usize,
+    /// Cursor from a previous page's `nextCursor`
+    #[serde(default)]
+    before: Option<String>,
+}
 
-            // Log failed login with lockout status
-            let details = if lockout_status.locked {
-                Some(format!(
-                    "Account locked after {} failed attempts",
-                    lockout_status.failed_attempts
-                ))
-            } else {
-                Some(format!(
-                    "Failed attempt {}/{}, {} remaining",
-                    lockout_status.failed_attempts,
-                    lockout_status.failed_attempts + lockout_status.remaining_attempts,
-                    lockout_status.remaining_attempts
-                ))
-            };
+fn default_search_limit() -> usize {
+    10
+}
 
-            state.audit_service.log_auth_event(
-                AuditEventType::LoginFailed,
-                &request.username,
-                AuditResult::Failure,
-                client_ip,
-                details,
-            );
+#[tracing::instrument(
+    name = "memory.search_episodes",
+    skip(state, request),
+    fields(agent_id = %agent_id)
+)]
+async fn search_episodes(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+    Json(request): Json<SearchEpisodesRequest>,
+) -> Result<impl IntoResponse, MemoryOperationError> {
+    let before = parse_before_cursor(request.before.as_deref())?;
 
-            // If account got locked due to this attempt, log the lockout event
-            if lockout_status.locked {
-                state.audit_service.log_auth_event(
-                    AuditEventType::AccountLocked,
-                    &request.username,
-                    AuditResult::Success,
-                    None,
-                    Some(format!(
-                        "Account locked after {} failed attempts. Lockout expires: {:?}",
-                        lockout_status.failed_attempts,
-                        lockout_status.lockout_expires
-                    )),
-                );
-            }
+    // Get agent memory
+    let Some(memory) = state.existing_agent_memory(&agent_id).await else {
+        return Ok((StatusCode::OK, Json(json!({"episodes": [], "nextCursor": null}))));
+    };
 
-            // Return error with lockout information
-            let mut response = json!({
-                "error": format!("Invalid username or password: {}", e),
-                "failed_attempts": lockout_status.failed_attempts,
-                "remaining_attempts": lockout_status.remaining_attempts,
-            });
+    // Search episodes using keyword search
+    let matches = memory
+        .search_episodes(&request.query)
+        .await
+        .map_err(|e| MemoryOperationError::StorageError {
+            message: e.to_string(),
+        })?;
+    let page = EpisodePage::before(matches, before, request.limit);
 
-            if lockout_status.locked {
-                response["locked"] = json!(true);
-                response["lockout_expires"] = json!(lockout_status.lockout_expires);
-                response["lockout_remaining_seconds"] = json!(lockout_status.lockout_remaining_seconds);
-            }
+    let episode_list: Vec<_> = page
+        .episodes
+        .iter()
+        .map(|ep| {
+            json!({
+                "episodeId": ep.id.to_string(),
+                "agentId": ep.agent_id,
+                "episodeType": format!("{:?}", ep.episode_type),
+                "content": format_search_content(ep),
+                "eventTime": ep.event_time.as_millis(),
+                "accessCount": ep.access_count(),
+                "lastAccessed": ep.last_accessed(),
+                "metadata": ep.metadata
+            })
+        })
+        .collect();
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "episodes": episode_list,
+            "nextCursor": page.next_cursor.map(|c| c.to_string())
+        })),
+    ))
+}
 
-            (StatusCode::UNAUTHORIZED, Json(response))
-        }
-    }
+// ==================== Semantic Search Request/Response Types ====================
+
+#[derive(Debug, Deserialize)]
+struct SemanticSearchRequest {
+    query: String,
+    #[serde(default = "default_search_limit")]
+    limit: usize,
+    #[serde(rename = "minScore")]
+    min_score: Option<f32>,
+    #[serde(rename = "efSearch")]
+    ef_search: Option<usize>,
 }
 
 #[derive(Debug, Deserialize)]
-struct LogoutRequest {
-    user_id: String,
+struct HybridSearchRequest {
+    query: String,
+    #[serde(default = "default_search_limit")]
+    limit: usize,
+    #[serde(rename = "semanticWeight", default = "default_semantic_weight")]
+    semantic_weight: f32,
 }
 
-async fn auth_logout(
-    State(state): State<AppState>,
-    Json(request): Json<LogoutRequest>,
-) -> impl IntoResponse {
-    match state.auth_service.logout(&request.user_id) {
-        Ok(_) => (StatusCode::OK, Json(json!({"success": true}))),
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": e.to_string()})),
-        ),
-    }
+fn default_semantic_weight() -> f32 {
+    0.5
 }
 
 #[derive(Debug, Deserialize)]
-struct RefreshRequest {
-    refresh_token: String,
+struct FindSimilarQuery {
+    #[serde(default = "default_search_limit")]
+    limit: usize,
 }
 
-async fn auth_refresh(
+// ==================== Semantic Search Handlers ====================
+
+/// Largest per-query `efSearch` override accepted by semantic search
+const MAX_EF_SEARCH: usize = 10_000;
+
+#[tracing::instrument(
+    name = "memory.semantic_search",
+    skip(state, request),
+    fields(agent_id = %agent_id, query = %request.query)
+)]
+async fn semantic_search(
     State(state): State<AppState>,
-    Json(request): Json<RefreshRequest>,
+    Path(agent_id): Path<String>,
+    Json(request): Json<SemanticSearchRequest>,
 ) -> impl IntoResponse {
-    match state.auth_service.refresh_token(&request.refresh_token) {
-        Ok(token) => {
-            let response = json!({
-                "access_token": token.access_token,
-                "token_type": token.token_type,
-                "expires_in": token.expires_in,
-            });
-            (StatusCode::OK, Json(response))
+    // Get agent memory
+    let memory = match state.existing_agent_memory(&agent_id).await {
+        Some(m) => m,
+        None => {
+            return (
+                StatusCode::NOT_IMPLEMENTED,
+                Json(json!({
+                    "error": "Semantic search is not enabled for this agent",
+                    "error_code": "SEMANTIC_SEARCH_NOT_ENABLED"
+                })),
+            );
         }
-        Err(e) => (
-            StatusCode::UNAUTHORIZED,
-            Json(json!({"error": format!("Token refresh failed: {}", e)})),
-        ),
+    };
+
+    if !memory.has_semantic_search() {
+        return (
+            StatusCode::NOT_IMPLEMENTED,
+            Json(json!({
+                "error": "Semantic search is not enabled for this agent",
+                "error_code": "SEMANTIC_SEARCH_NOT_ENABLED"
+            })),
+        );
     }
-}
 
-// ==================== Token Revocation ====================
+    if request.ef_search.is_some_and(|ef| ef > MAX_EF_SEARCH) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": format!("efSearch may be at most {}", MAX_EF_SEARCH),
+                "error_code": "VALIDATION_ERROR"
+            })),
+        );
+    }
 
-/// Request to revoke a specific token
-#[derive(Debug, Deserialize)]
-struct RevokeTokenRequest {
-    /// The token to revoke (JWT access token)
-    token: String,
+    match memory
+        .semantic_search(&request.query, request.limit, request.ef_search)
+        .await
+    {
+        Ok(matches) => {
+            let results: Vec<_> = matches
+                .iter()
+                .filter(|m| request.min_score.is_none_or(|min| m.score >= min))
+                .map(|m| {
+                    let ep = &m.episode;
+                    json!({
+                        "episode": {
+                            "episodeId": ep.id.to_string(),
+                            "agentId": ep.agent_id,
+                            "episodeType": format!("{:?}", ep.episode_type),
+                            "content": format_search_content(ep),
+                            "eventTime": ep.event_time.as_millis(),
+                            "accessCount": ep.access_count(),
+                            "lastAccessed": ep.last_accessed(),
+                            "metadata": ep.metadata
+                        },
+                        "score": m.score
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(json!({"results": results})))
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": e.to_string(),
+                "error_code": "SEARCH_ERROR"
+            })),
+        ),
+    }
 }
 
-/// Revoke a specific token (logout)
-///
-/// POST /api/v1/auth/revoke
-///
-/// This endpoint allows a user to revoke their own token (logout).
-/// The token to be revoked is provided in the request body.
-/// No authentication header is required - the token being revoked serves as proof of ownership.
-async fn auth_revoke(
+#[tracing::instrument(
+    name = "memory.hybrid_search",
+    skip(state, request),
+    fields(agent_id = %agent_id, query = %request.query, semantic_weight = %request.semantic_weight)
+)]
+async fn hybrid_search(
     State(state): State<AppState>,
-    Json(request): Json<RevokeTokenRequest>,
+    Path(agent_id): Path<String>,
+    Json(request): Json<HybridSearchRequest>,
 ) -> impl IntoResponse {
-    // Get the token to revoke
-    let token_to_revoke = &request.token;
+    // Validate semantic_weight
+    if request.semantic_weight < 0.0 || request.semantic_weight > 1.0 {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(json!({
+                "error": "semanticWeight must be between 0.0 and 1.0",
+                "error_code": "VALIDATION_ERROR"
+            })),
+        );
+    }
 
-    // Validate the token and extract claims
-    let claims = match state.auth_service.validate_token_claims(token_to_revoke) {
-        Ok(claims) => claims,
-        Err(e) => {
+    // Get agent memory
+    let memory = match state.existing_agent_memory(&agent_id).await {
+        Some(m) => m,
+        None => {
             return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": format!("Invalid token: {}", e)})),
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "Agent memory not found",
+                    "error_code": "AGENT_NOT_FOUND"
+                })),
             );
         }
     };
 
-    // Get the expiration time from the token
-    let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0)
-        .unwrap_or_else(|| chrono::Utc::now() + chrono::Duration::hours(1));
+    // For in-memory agents, hybrid search falls back to keyword search
+    // The keyword_weight and semantic_weight are recorded but not used
+    let keyword_weight = 1.0 - request.semantic_weight;
 
-    // Revoke the token
-    match state.auth_service.revoke_token(
-        claims.jti.clone(),
-        claims.sub.clone(),
-        claims.username.clone(),
-        expires_at,
-        RevocationReason::Logout,
-    ) {
-        Ok(_) => {
-            // Log the revocation as TokenRevoked (with logout context)
-            state.audit_service.log_event(
-                AuditEventType::TokenRevoked,
-                Some(claims.sub.clone()),
-                Some(claims.username.clone()),
-                "revoke_token".to_string(),
-                format!("jti:{}", claims.jti),
-                AuditResult::Success,
-                None, // IP address can be extracted from request if needed
-                None, // User agent
-                serde_json::json!({
-                    "reason": "logout",
-                    "jti": claims.jti
-                }),
-            );
+    match memory.search_episodes(&request.query).await {
+        Ok(episodes) => {
+            let results: Vec<_> = episodes
+                .iter()
+                .take(request.limit)
+                .enumerate()
+                .map(|(i, ep)| {
+                    // Calculate simulated scores
+                    let keyword_score = 1.0 - (0.05 * i as f32);
+                    // Since no semantic search is available, semantic_score is null
+                    let combined_score = keyword_score * keyword_weight;
 
-            (
-                StatusCode::OK,
-                Json(json!({
-                    "success": true,
-                    "message": "Token revoked successfully",
-                    "jti": claims.jti
-                })),
-            )
+                    json!({
+                        "episode": {
+                            "episodeId": ep.id.to_string(),
+                            "agentId": ep.agent_id,
+                            "episodeType": format!("{:?}", ep.episode_type),
+                            "content": format_search_content(ep),
+                            "eventTime": ep.event_time.as_millis(),
+                            "accessCount": ep.access_count(),
+                            "lastAccessed": ep.last_accessed(),
+                            "metadata": ep.metadata
+                        },
+                        "score": combined_score,
+                        "keywordScore": keyword_score,
+                        "semanticScore": null
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(json!({"results": results})))
         }
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": format!("Failed to revoke token: {}", e)})),
+            Json(json!({
+                "error": e.to_string(),
+                "error_code": "SEARCH_ERROR"
+            })),
         ),
     }
 }
 
-/// Request to revoke all tokens for a user
-#[derive(Debug, Deserialize)]
-struct RevokeAllTokensRequest {
-    /// User ID whose tokens should be revoked
-    user_id: String,
-    /// Optional reason for revocation
-    reason: Option<String>,
-}
-
-/// Revoke all tokens for a user (Admin only)
-///
-/// POST /api/v1/auth/revoke-all
-/// Requires Admin role
-async fn auth_revoke_all(
+#[tracing::instrument(
+    name = "memory.find_similar_episodes",
+    skip(state),
+    fields(agent_id = %agent_id, episode_id = %episode_id)
+)]
+async fn find_similar_episodes(
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Json(request): Json<RevokeAllTokensRequest>,
+    Path((agent_id, episode_id)): Path<(String, String)>,
+    AxumQuery(query): AxumQuery<FindSimilarQuery>,
 ) -> impl IntoResponse {
-    // Require admin privileges
-    let admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
+    // Get agent memory
+    let memory = match state.existing_agent_memory(&agent_id).await {
+        Some(m) => m,
+        None => {
             return (
-                status,
-                Json(json!({"error": "Admin privileges required to revoke all tokens for a user"})),
+                StatusCode::NOT_FOUND,
+                Json(json!({
+                    "error": "Agent memory not found",
+                    "error_code": "AGENT_NOT_FOUND"
+                })),
             );
         }
     };
 
-    // Get the user to validate they exist
-    let user_id = match uuid::Uuid::parse_str(&request.user_id) {
-        Ok(id) => id,
-        Err(_) => {
+    // Get the source episode first
+    let source_episode = match memory.get_episode_by_id_str(&episode_id).await {
+        Ok(Some(ep)) => ep,
+        Err(qilbee_core::Error::ValidationError(message)) => {
             return (
                 StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid user ID format"})),
+                Json(json!({
+                    "error": message,
+                    "error_code": "VALIDATION_ERROR"
+                })),
             );
         }
-    };
-
-    let user = match state.user_service.get_user(&crate::security::UserId(user_id)) {
-        Some(user) => user,
-        None => {
+        Err(e) => {
             return (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "User not found"})),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({
+                    "error": e.to_string(),
+                    "error_code": "STORAGE_ERROR"
+                })),
             );
         }
-    };
-
-    // Determine the revocation reason
-    let reason = match request.reason.as_deref() {
-        Some("security_incident") => RevocationReason::SecurityIncident,
-        Some("password_changed") => RevocationReason::PasswordChanged,
-        _ => RevocationReason::AdminRevoke,
-    };
-
-    // Revoke all tokens for the user
-    match state.auth_service.revoke_all_user_tokens(
-        &request.user_id,
-        &user.username,
-        reason.clone(),
-    ) {
-        Ok(count) => {
-            // Log the revocation using AllTokensRevoked event type
-            state.audit_service.log_event(
-                AuditEventType::AllTokensRevoked,
-                Some(request.user_id.clone()),
-                Some(user.username.clone()),
-                "revoke_all_tokens".to_string(),
-                format!("user:{}", request.user_id),
-                AuditResult::Success,
-                None,
-                None,
-                json!({
-                    "reason": reason.to_string(),
-                    "previously_revoked_count": count,
-                    "revoked_by_admin_id": format!("{:?}", admin_id)
-                }),
-            );
-
-            (
-                StatusCode::OK,
+        Ok(None) => {
+            return (
+                StatusCode::NOT_FOUND,
                 Json(json!({
-                    "success": true,
-                    "message": "All tokens revoked for user",
-                    "user_id": request.user_id,
-                    "previously_revoked_count": count
+                    "error": format!("Episode {} not found", episode_id),
+                    "error_code": "EPISODE_NOT_FOUND"
                 })),
-            )
+            );
         }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": format!("Failed to revoke tokens: {}", e)})),
-        ),
-    }
-}
-
-// ==================== API Key Management ====================
+    };
 
-#[derive(Debug, Deserialize)]
-struct CreateApiKeyRequest {
-    name: String,
-    expires_in_days: Option<u32>,
-}
-
-#[derive(Debug, Serialize)]
-struct CreateApiKeyResponse {
-    key: String,
-    id: String,
-    prefix: String,
-    name: String,
-    created_at: String,
-    expires_at: Option<String>,
-}
-
-#[derive(Debug, Serialize)]
-struct ListApiKeysResponse {
-    keys: Vec<ApiKeyInfo>,
-}
+    // For in-memory agents without vector index, find similar episodes by keyword overlap
+    // Search using the source episode's content
+    let search_query = format!(
+        "{} {}",
+        source_episode.content.primary,
+        source_episode.content.secondary.as_deref().unwrap_or("")
+    );
 
-#[derive(Debug, Serialize)]
-struct ApiKeyInfo {
-    id: String,
-    prefix: String,
-    name: String,
-    created_at: String,
-    expires_at: Option<String>,
-    last_used: Option<String>,
-    is_active: bool,
+    match memory.search_episodes(&search_query).await {
+        Ok(similar_episodes) => {
+            let results: Vec<_> = similar_episodes
+                .iter()
+                .filter(|ep| ep.id.to_string() != episode_id) // Exclude source episode
+                .take(query.limit)
+                .enumerate()
+                .map(|(i, ep)| {
+                    let score = 1.0 - (0.1 * i as f32);
+                    json!({
+                        "episode": {
+                            "episodeId": ep.id.to_string(),
+                            "agentId": ep.agent_id,
+                            "episodeType": format!("{:?}", ep.episode_type),
+                            "content": format_search_content(ep),
+                            "eventTime": ep.event_time.as_millis(),
+                            "accessCount": ep.access_count(),
+                            "lastAccessed": ep.last_accessed(),
+                            "metadata": ep.metadata
+                        },
+                        "score": score
+                    })
+                })
+                .collect();
+            (StatusCode::OK, Json(json!({"results": results})))
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": e.to_string(),
+                "error_code": "SEARCH_ERROR"
+            })),
+        ),
+    }
 }
 
-async fn api_key_create(
+#[tracing::instrument(
+    name = "memory.index_missing_episodes",
+    skip(state),
+    fields(agent_id = %agent_id)
+)]
+async fn index_missing_episodes(
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Json(request): Json<CreateApiKeyRequest>,
-) -> impl IntoResponse {
-    // Extract user_id from JWT token in Authorization header
-    let user_id = match extract_user_from_auth(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
-            )
-        }
-    };
-
-    // Get username for audit logging
-    let username = state.user_service.get_user(&user_id)
-        .map(|u| u.username)
-        .unwrap_or_else(|| "unknown".to_string());
+    Path(agent_id): Path<String>,
+) -> Result<impl IntoResponse, MemoryOperationError> {
+    let memory = state
+        .existing_agent_memory(&agent_id)
+        .await
+        .ok_or_else(|| MemoryOperationError::AgentNotFound {
+            agent_id: agent_id.clone(),
+        })?;
 
-    // Generate API key with optional expiration
-    match state
-        .token_service
-        .generate_api_key(user_id, request.name.clone(), request.expires_in_days)
-    {
-        Ok((key, api_key)) => {
-            // Log successful API key creation
-            state.audit_service.log_api_key_event(
-                AuditEventType::ApiKeyCreated,
-                &user_id.0.to_string(),
-                &username,
-                &api_key.id,
-                AuditResult::Success,
-                None,
-            );
+    if !memory.has_semantic_search() {
+        return Ok((
+            StatusCode::NOT_IMPLEMENTED,
+            Json(json!({
+                "error": "Semantic search is not enabled for this agent",
+                "error_code": "SEMANTIC_SEARCH_NOT_ENABLED"
+            })),
+        ));
+    }
 
-            let response = CreateApiKeyResponse {
-                key,
-                id: api_key.id,
-                prefix: api_key.prefix,
-                name: api_key.name,
-                created_at: api_key.created_at.to_rfc3339(),
-                expires_at: api_key.expires_at.map(|dt| dt.to_rfc3339()),
-            };
-            (StatusCode::CREATED, Json(json!(response)))
+    let indexed = memory.index_missing_episodes().await.map_err(|e| {
+        MemoryOperationError::OperationFailed {
+            operation: "index".to_string(),
+            message: e.to_string(),
         }
-        Err(e) => {
-            // Log failed API key creation
-            state.audit_service.log_api_key_event(
-                AuditEventType::ApiKeyCreated,
-                &user_id.0.to_string(),
-                &username,
-                "unknown",
-                AuditResult::Failure,
-                None,
-            );
+    })?;
 
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to create API key: {}", e)})),
-            )
-        }
-    }
+    Ok((
+        StatusCode::OK,
+        Json(json!({
+            "indexed": indexed,
+            "indexedEpisodes": memory.vector_index_size().unwrap_or(0)
+        })),
+    ))
 }
 
-async fn api_key_list(
+#[tracing::instrument(
+    name = "memory.get_semantic_search_status",
+    skip(state),
+    fields(agent_id = %agent_id)
+)]
+async fn get_semantic_search_status(
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
+    Path(agent_id): Path<String>,
 ) -> impl IntoResponse {
-    // Extract user_id from JWT token
-    let user_id = match extract_user_from_auth(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
-            )
-        }
+    let memory = state.existing_agent_memory(&agent_id).await;
+    let Some(semantic_config) = state.semantic_config(&agent_id) else {
+        return (
+            StatusCode::OK,
+            Json(json!({
+                "enabled": false,
+                "model": null,
+                "dimensions": null,
+                "metric": null,
+                "indexedEpisodes": 0,
+                "agentExists": memory.is_some(),
+                "message": "Semantic search requires an embedding provider in the server configuration"
+            })),
+        );
     };
 
-    // List API keys for user
-    let keys = state.token_service.list_api_keys(&user_id);
-    let key_infos: Vec<ApiKeyInfo> = keys
-        .into_iter()
-        .map(|k| ApiKeyInfo {
-            id: k.id,
-            prefix: k.prefix,
-            name: k.name,
-            created_at: k.created_at.to_rfc3339(),
-            expires_at: k.expires_at.map(|dt| dt.to_rfc3339()),
-            last_used: k.last_used.map(|dt| dt.to_rfc3339()),
-            is_active: k.is_active,
-        })
-        .collect();
-
+    let indexed_episodes = memory
+        .as_ref()
+        .and_then(|m| m.vector_index_size().ok())
+        .unwrap_or(0);
     (
         StatusCode::OK,
-        Json(json!(ListApiKeysResponse { keys: key_infos })),
+        Json(json!({
+            "enabled": true,
+            "model": semantic_config.embedding_config.model,
+            "dimensions": memory
+                .as_ref()
+                .and_then(|m| m.embedding_dimensions())
+                .unwrap_or(semantic_config.embedding_config.dimensions),
+            "metric": semantic_config.hnsw_config.metric,
+            "indexedEpisodes": indexed_episodes,
+            "agentExists": memory.is_some()
+        })),
     )
 }
 
-async fn api_key_revoke(
+#[tracing::instrument(
+    name = "memory.get_statistics",
+    skip(state),
+    fields(agent_id = %agent_id)
+)]
+async fn get_memory_statistics(
     State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    axum::extract::Path(key_id): axum::extract::Path<String>,
+    Path(agent_id): Path<String>,
 ) -> impl IntoResponse {
-    // Extract user_id from JWT token to verify ownership
-    let user_id = match extract_user_from_auth(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
+    // Get agent memory
+    let memory = match state.existing_agent_memory(&agent_id).await {
+        Some(m) => m,
+        None => {
+            // Return empty stats if agent doesn't exist yet
             return (
-                status,
-                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
-            )
-        }
-    };
-
-    // Get username for audit logging
-    let username = state.user_service.get_user(&user_id)
-        .map(|u| u.username)
-        .unwrap_or_else(|| "unknown".to_string());
-
-    // Revoke API key by ID
-    match state.token_service.revoke_api_key_by_id(&key_id) {
-        Ok(_) => {
-            state.audit_service.log_api_key_event(
-                AuditEventType::ApiKeyRevoked,
-                &user_id.0.to_string(),
-                &username,
-                &key_id,
-                AuditResult::Success,
-                None,
-            );
-
-            (
                 StatusCode::OK,
-                Json(json!({"success": true, "message": "API key revoked"})),
-            )
-        }
-        Err(e) => {
-            state.audit_service.log_api_key_event(
-                AuditEventType::ApiKeyRevoked,
-                &user_id.0.to_string(),
-                &username,
-                &key_id,
-                AuditResult::Failure,
-                None,
+                Json(json!({
+                    "totalEpisodes": 0,
+                    "episodesByType": {},
+                    "oldestEpisode": null,
+                    "newestEpisode": null,
+                    "avgRelevance": 0.0
+                })),
             );
-
-            (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": format!("Failed to revoke API key: {}", e)})),
-            )
         }
+    };
+
+    // Get statistics
+    match memory.get_statistics().await {
+        Ok(stats) => (
+            StatusCode::OK,
+            Json(json!({
+                "totalEpisodes": stats.total_episodes,
+                "episodesByType": {},
+                "oldestEpisode": stats.oldest_episode,
+                "newestEpisode": stats.newest_episode,
+                "avgRelevance": stats.avg_relevance
+            })),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": e.to_string()})),
+        ),
     }
 }
 
-// ==================== API Key Rotation ====================
+#[tracing::instrument(
+    name = "memory.consolidate",
+    skip(state, request),
+    fields(agent_id = %agent_id, min_relevance = %request.min_relevance, decay_factor = %request.decay_factor)
+)]
+async fn consolidate_memory(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+    Json(request): Json<ConsolidateMemoryRequest>,
+) -> Result<impl IntoResponse, MemoryOperationError> {
+    // Validate request parameters
+    request.validate().map_err(|e| MemoryOperationError::ValidationError {
+        field: "request".to_string(),
+        message: e,
+    })?;
+
+    // Get agent memory
+    let memory = state
+        .existing_agent_memory(&agent_id)
+        .await
+        .ok_or_else(|| MemoryOperationError::AgentNotFound {
+            agent_id: agent_id.clone(),
+        })?;
+
+    // Get episode count before consolidation
+    let episodes_before = memory.episode_count().await.unwrap_or(0);
+
+    // Apply decay, evicting episodes that fall below the threshold
+    let report = memory
+        .apply_decay_with_threshold(request.decay_factor, request.min_relevance)
+        .await
+        .map_err(|e| {
+            // Log audit event for failure
+            state.audit_service.log_memory_event(
+                AuditEventType::MemoryConsolidated,
+                None,
+                None,
+                &agent_id,
+                AuditResult::Error,
+                None,
+                serde_json::json!({"error": e.to_string()}),
+            );
+            MemoryOperationError::OperationFailed {
+                operation: "consolidate".to_string(),
+                message: e.to_string(),
+            }
+        })?;
+
+    // Get episode count after consolidation
+    let episodes_after = memory.episode_count().await.unwrap_or(0);
+
+    // Log audit event
+    state.audit_service.log_memory_event(
+        AuditEventType::MemoryConsolidated,
+        None,
+        None,
+        &agent_id,
+        AuditResult::Success,
+        None,
+        serde_json::json!({
+            "episodes_before": episodes_before,
+            "episodes_after": episodes_after,
+            "episodes_decayed": report.decayed,
+            "episodes_evicted": report.evicted,
+            "min_relevance": request.min_relevance,
+            "decay_factor": request.decay_factor
+        }),
+    );
+
+    Ok((StatusCode::OK, Json(json!({
+        "consolidated": episodes_after,
+        "episodes_before": episodes_before,
+        "episodes_after": episodes_after,
+        "episodes_decayed": report.decayed,
+        "episodes_evicted": report.evicted,
+        "min_relevance": request.min_relevance,
+        "decay_factor": request.decay_factor
+    }))))
+}
+
+#[tracing::instrument(
+    name = "memory.forget",
+    skip(state, request),
+    fields(agent_id = %agent_id, min_relevance = %request.min_relevance)
+)]
+async fn forget_memory(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+    Json(request): Json<ForgetMemoryRequest>,
+) -> Result<impl IntoResponse, MemoryOperationError> {
+    // Validate request parameters
+    request.validate().map_err(|e| MemoryOperationError::ValidationError {
+        field: "request".to_string(),
+        message: e,
+    })?;
+
+    // Get agent memory
+    let memory = state
+        .existing_agent_memory(&agent_id)
+        .await
+        .ok_or_else(|| MemoryOperationError::AgentNotFound {
+            agent_id: agent_id.clone(),
+        })?;
+
+    // Get episode count before forget
+    let episodes_before = memory.episode_count().await.unwrap_or(0);
+
+    // Forget episodes matching every supplied criterion
+    let report = memory.forget(&request.criteria()).await.map_err(|e| {
+        // Log audit event for failure
+        state.audit_service.log_memory_event(
+            AuditEventType::MemoryForgotten,
+            None,
+            None,
+            &agent_id,
+            AuditResult::Error,
+            None,
+            serde_json::json!({"error": e.to_string()}),
+        );
+        MemoryOperationError::OperationFailed {
+            operation: "forget".to_string(),
+            message: e.to_string(),
+        }
+    })?;
+
+    // Get episode count after forget
+    let episodes_after = memory.episode_count().await.unwrap_or(0);
+    let count = report.total();
+    let forgotten_by_type = forgotten_by_type(&report);
+
+    // Log audit event
+    state.audit_service.log_memory_event(
+        AuditEventType::MemoryForgotten,
+        None,
+        None,
+        &agent_id,
+        AuditResult::Success,
+        None,
+        serde_json::json!({
+            "episodes_before": episodes_before,
+            "episodes_after": episodes_after,
+            "episodes_forgotten": count,
+            "forgotten_by_type": forgotten_by_type,
+            "min_relevance": request.min_relevance,
+            "max_age_seconds": request.max_age_seconds,
+            "episode_types": request.episode_types
+        }),
+    );
+
+    Ok((StatusCode::OK, Json(json!({
+        "forgotten": count,
+        "forgotten_by_type": forgotten_by_type,
+        "episodes_before": episodes_before,
+        "episodes_after": episodes_after,
+        "min_relevance": request.min_relevance,
+        "max_age_seconds": request.max_age_seconds,
+        "episode_types": request.episode_types
+    }))))
+}
+
+#[tracing::instrument(
+    name = "memory.clear",
+    skip(state),
+    fields(agent_id = %agent_id)
+)]
+async fn clear_memory(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+) -> Result<impl IntoResponse, MemoryOperationError> {
+    // Get agent memory
+    let memory = state
+        .existing_agent_memory(&agent_id)
+        .await
+        .ok_or_else(|| MemoryOperationError::AgentNotFound {
+            agent_id: agent_id.clone(),
+        })?;
+
+    // Get episode count before clear
+    let episodes_before = memory.episode_count().await.unwrap_or(0);
+
+    // Clear all episodes
+    memory.clear().await.map_err(|e| {
+        // Log audit event for failure
+        state.audit_service.log_memory_event(
+            AuditEventType::MemoryCleared,
+            None,
+            None,
+            &agent_id,
+            AuditResult::Error,
+            None,
+            serde_json::json!({"error": e.to_string()}),
+        );
+        MemoryOperationError::OperationFailed {
+            operation: "clear".to_string(),
+            message: e.to_string(),
+        }
+    })?;
+
+    // Log audit event for success
+    state.audit_service.log_memory_event(
+        AuditEventType::MemoryCleared,
+        None,
+        None,
+        &agent_id,
+        AuditResult::Success,
+        None,
+        serde_json::json!({
+            "episodes_cleared": episodes_before
+        }),
+    );
+
+    Ok((StatusCode::OK, Json(json!({"cleared": true, "episodes_cleared": episodes_before}))))
+}
+
+// ==================== Episode Export ====================
+
+/// Episodes read from storage per page while exporting
+const EXPORT_PAGE_SIZE: usize = 500;
 
 #[derive(Debug, Deserialize)]
-struct RotateApiKeyRequest {
-    current_key: String,
-    new_name: Option<String>,
-    expires_in_days: Option<u32>,
+struct ExportEpisodesQuery {
+    /// Earliest event time in milliseconds (inclusive)
+    from: Option<i64>,
+    /// Latest event time in milliseconds (inclusive)
+    to: Option<i64>,
+    /// Episode type to export
+    #[serde(rename = "type")]
+    episode_type: Option<String>,
+    #[serde(default = "default_export_format")]
+    format: String,
+}
+
+fn default_export_format() -> String {
+    "jsonl".to_string()
+}
+
+/// Parse an episode type filter; names match case-insensitively
+fn parse_episode_type_filter(name: &str) -> EpisodeType {
+    match name.to_lowercase().replace('_', "").as_str() {
+        "conversation" => EpisodeType::Conversation,
+        "taskexecution" => EpisodeType::TaskExecution,
+        "observation" => EpisodeType::Observation,
+        "decision" => EpisodeType::Decision,
+        "error" => EpisodeType::Error,
+        "action" => EpisodeType::Custom("Action".to_string()),
+        _ => EpisodeType::Custom(name.to_string()),
+    }
+}
+
+/// Name of an episode type in API responses
+fn episode_type_name(episode_type: &EpisodeType) -> String {
+    match episode_type {
+        EpisodeType::Custom(name) => name.clone(),
+        other => format!("{:?}", other),
+    }
+}
+
+/// One NDJSON line of an episode export
+fn episode_export_line(episode: &Episode) -> String {
+    let mut line = json!({
+        "agentId": episode.agent_id,
+        "episodeId": episode.id.to_string(),
+        "episodeType": format!("{:?}", episode.episode_type),
+        "content": format_episode_content(episode),
+        "eventTime": episode.event_time.as_millis(),
+        "metadata": episode.metadata
+    })
+    .to_string();
+    line.push('\n');
+    line
+}
+
+#[tracing::instrument(
+    name = "memory.export",
+    skip(state, query),
+    fields(agent_id = %agent_id)
+)]
+async fn export_episodes(
+    State(state): State<AppState>,
+    Path(agent_id): Path<String>,
+    AxumQuery(query): AxumQuery<ExportEpisodesQuery>,
+) -> Result<axum::response::Response, MemoryOperationError> {
+    if !matches!(query.format.as_str(), "jsonl" | "ndjson") {
+        return Err(MemoryOperationError::ValidationError {
+            field: "format".to_string(),
+            message: format!("Unsupported export format '{}'; expected 'jsonl'", query.format),
+        });
+    }
+    let from = query.from.unwrap_or(i64::MIN);
+    let to = query.to.unwrap_or(i64::MAX);
+    if from > to {
+        return Err(MemoryOperationError::ValidationError {
+            field: "from".to_string(),
+            message: "from must not be after to".to_string(),
+        });
+    }
+
+    let memory = state
+        .existing_agent_memory(&agent_id)
+        .await
+        .ok_or_else(|| MemoryOperationError::AgentNotFound {
+            agent_id: agent_id.clone(),
+        })?;
+    let storage = memory.storage().clone();
+    let episode_type = query.episode_type.as_deref().map(parse_episode_type_filter);
+
+    // Episodes are read a page at a time, so the export never holds more
+    // than one page in memory. The outer Option marks the end of the export.
+    let pages = futures::stream::unfold(Some(None), move |cursor: Option<Option<EpisodeCursor>>| {
+        let storage = storage.clone();
+        let agent_id = agent_id.clone();
+        let episode_type = episode_type.clone();
+        async move {
+            let after = cursor?;
+            match storage.get_episodes_page(&agent_id, from, to, after, EXPORT_PAGE_SIZE).await {
+                Ok(page) => {
+                    let next = page
+                        .last()
+                        .filter(|_| page.len() == EXPORT_PAGE_SIZE)
+                        .map(|last| Some(EpisodeCursor::at(last)));
+                    let lines: String = page
+                        .iter()
+                        .filter(|ep| episode_type.as_ref().is_none_or(|t| &ep.episode_type == t))
+                        .map(episode_export_line)
+                        .collect();
+                    Some((Ok(lines), next))
+                }
+                Err(e) => {
+                    tracing::error!("Episode export for agent {} failed: {}", agent_id, e);
+                    Some((Err(std::io::Error::other(e.to_string())), None))
+                }
+            }
+        }
+    });
+
+    Ok((
+        StatusCode::OK,
+        [(axum::http::header::CONTENT_TYPE, "application/x-ndjson")],
+        axum::body::Body::from_stream(pages),
+    )
+        .into_response())
+}
+
+// ==================== Authentication Operations ====================
+
+#[derive(Debug, Deserialize)]
+struct LoginRequest {
+    username: String,
+    password: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LoginResponse {
+    access_token: String,
+    token_type: String,
+    expires_in: u64,
+    refresh_token: Option<String>,
+    username: String,
+    user_id: String,
+}
+
+async fn auth_login(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<LoginRequest>,
+) -> impl IntoResponse {
+    // Extract client IP from X-Forwarded-For header or X-Real-IP
+    let client_ip = headers
+        .get("X-Forwarded-For")
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
+        .or_else(|| {
+            headers
+                .get("X-Real-IP")
+                .and_then(|v| v.to_str().ok())
+                .map(|s| s.to_string())
+        });
+
+    // Check if account is locked before attempting login
+    if let Err(lockout_status) = state.lockout_service.check_login_allowed(
+        &request.username,
+        client_ip.as_deref(),
+    ) {
+        // Log account lockout event
+        state.audit_service.log_auth_event(
+            AuditEventType::AccountLockoutTriggered,
+            &request.username,
+            AuditResult::Failure,
+            client_ip.clone(),
+            Some(format!(
+                "Account locked. Remaining: {:?}s, Reason: {:?}",
+                lockout_status.lockout_remaining_seconds,
+                lockout_status.lockout_reason
+            )),
+        );
+
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            Json(json!({
+                "error": "Account locked due to too many failed login attempts",
+                "locked": true,
+                "lockout_expires": lockout_status.lockout_expires,
+                "lockout_remaining_seconds": lockout_status.lockout_remaining_seconds,
+                "lockout_reason": lockout_status.lockout_reason,
+            })),
+        );
+    }
+
+    let credentials = Credentials {
+        username: request.username.clone(),
+        password: request.password,
+    };
+
+    match state.auth_service.login(credentials) {
+        Ok((user_id, token)) => {
+            // Record successful login (resets failed attempt counter)
+            state.lockout_service.record_successful_login(
+                &request.username,
+                client_ip.as_deref(),
+            );
+
+            // Log successful login
+            state.audit_service.log_auth_event(
+                AuditEventType::Login,
+                &request.username,
+                AuditResult::Success,
+                client_ip,
+                None,
+            );
+
+            let response = LoginResponse {
+                access_token: token.access_token.clone(),
+                token_type: token.token_type,
+                expires_in: token.expires_in,
+                refresh_token: token.refresh_token,
+                username: request.username,
+                user_id: user_id.0.to_string(),
+            };
+            (StatusCode::OK, Json(json!(response)))
+        }
+        Err(e) => {
+            // Record failed login attempt
+            let lockout_status = state.lockout_service.record_failed_attempt(
+                &request.username,
+                client_ip.as_deref(),
+            );
+
+            // Log failed login with lockout status
+            let details = if lockout_status.locked {
+                Some(format!(
+                    "Account locked after {} failed attempts",
+                    lockout_status.failed_attempts
+                ))
+            } else {
+                Some(format!(
+                    "Failed attempt {}/{}, {} remaining",
+                    lockout_status.failed_attempts,
+                    lockout_status.failed_attempts + lockout_status.remaining_attempts,
+                    lockout_status.remaining_attempts
+                ))
+            };
+
+            state.audit_service.log_auth_event(
+                AuditEventType::LoginFailed,
+                &request.username,
+                AuditResult::Failure,
+                client_ip,
+                details,
+            );
+
+            // If account got locked due to this attempt, log the lockout event
+            if lockout_status.locked {
+                state.audit_service.log_auth_event(
+                    AuditEventType::AccountLocked,
+                    &request.username,
+                    AuditResult::Success,
+                    None,
+                    Some(format!(
+                        "Account locked after {} failed attempts. Lockout expires: {:?}",
+                        lockout_status.failed_attempts,
+                        lockout_status.lockout_expires
+                    )),
+                );
+            }
+
+            // Return error with lockout information
+            let mut response = json!({
+                "error": format!("Invalid username or password: {}", e),
+                "failed_attempts": lockout_status.failed_attempts,
+                "remaining_attempts": lockout_status.remaining_attempts,
+            });
+
+            if lockout_status.locked {
+                response["locked"] = json!(true);
+                response["lockout_expires"] = json!(lockout_status.lockout_expires);
+                response["lockout_remaining_seconds"] = json!(lockout_status.lockout_remaining_seconds);
+            }
+
+            (StatusCode::UNAUTHORIZED, Json(response))
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LogoutRequest {
+    user_id: String,
+}
+
+async fn auth_logout(
+    State(state): State<AppState>,
+    Json(request): Json<LogoutRequest>,
+) -> impl IntoResponse {
+    match state.auth_service.logout(&request.user_id) {
+        Ok(_) => (StatusCode::OK, Json(json!({"success": true}))),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": e.to_string()})),
+        ),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct RefreshRequest {
+    refresh_token: String,
+}
+
+/// Exchange a refresh token for a new access token and refresh token
+///
+/// POST /api/v1/auth/refresh
+///
+/// The presented refresh token is spent. Presenting it again is treated as
+/// a stolen token: all of the user's tokens are revoked and a security
+/// incident is audited.
+async fn auth_refresh(
+    State(state): State<AppState>,
+    Json(request): Json<RefreshRequest>,
+) -> impl IntoResponse {
+    match state.auth_service.refresh_token(&request.refresh_token) {
+        Ok(RefreshOutcome::Rotated(token)) => {
+            let response = json!({
+                "access_token": token.access_token,
+                "token_type": token.token_type,
+                "expires_in": token.expires_in,
+                "refresh_token": token.refresh_token,
+            });
+            (StatusCode::OK, Json(response))
+        }
+        Ok(RefreshOutcome::ReuseDetected { user_id, username, family_id }) => {
+            state.audit_service.log_event(
+                AuditEventType::SecurityIncident,
+                Some(user_id.0.to_string()),
+                Some(username),
+                "refresh_token_reuse".to_string(),
+                format!("user:{}", user_id.0),
+                AuditResult::Unauthorized,
+                None,
+                None,
+                json!({
+                    "refresh_token_family": family_id,
+                    "reason": RevocationReason::SecurityIncident.to_string()
+                }),
+            );
+
+            (
+                StatusCode::UNAUTHORIZED,
+                Json(json!({"error": "Refresh token reuse detected; all tokens of the user have been revoked"})),
+            )
+        }
+        Err(e) => (
+            StatusCode::UNAUTHORIZED,
+            Json(json!({"error": format!("Token refresh failed: {}", e)})),
+        ),
+    }
+}
+
+// ==================== Token Revocation ====================
+
+/// Request to revoke a specific token
+#[derive(Debug, Deserialize)]
+struct RevokeTokenRequest {
+    /// The token to revoke (JWT access token)
+    token: String,
+}
+
+/// Revoke a specific token (logout)
+///
+/// POST /api/v1/auth/revoke
+///
+/// This endpoint allows a user to revoke their own token (logout).
+/// The token to be revoked is provided in the request body.
+/// No authentication header is required - the token being revoked serves as proof of ownership.
+async fn auth_revoke(
+    State(state): State<AppState>,
+    Json(request): Json<RevokeTokenRequest>,
+) -> impl IntoResponse {
+    // Get the token to revoke
+    let token_to_revoke = &request.token;
+
+    // Validate the token and extract claims
+    let claims = match state.auth_service.validate_token_claims(token_to_revoke) {
+        Ok(claims) => claims,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Invalid token: {}", e)})),
+            );
+        }
+    };
+
+    // Get the expiration time from the token
+    let expires_at = chrono::DateTime::from_timestamp(claims.exp as i64, 0)
+        .unwrap_or_else(|| chrono::Utc::now() + chrono::Duration::hours(1));
+
+    // Revoke the token
+    match state.auth_service.revoke_token(
+        claims.jti.clone(),
+        claims.sub.clone(),
+        claims.username.clone(),
+        expires_at,
+        RevocationReason::Logout,
+    ) {
+        Ok(_) => {
+            // Log the revocation as TokenRevoked (with logout context)
+            state.audit_service.log_event(
+                AuditEventType::TokenRevoked,
+                Some(claims.sub.clone()),
+                Some(claims.username.clone()),
+                "revoke_token".to_string(),
+                format!("jti:{}", claims.jti),
+                AuditResult::Success,
+                None, // IP address can be extracted from request if needed
+                None, // User agent
+                serde_json::json!({
+                    "reason": "logout",
+                    "jti": claims.jti
+                }),
+            );
+
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "success": true,
+                    "message": "Token revoked successfully",
+                    "jti": claims.jti
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Failed to revoke token: {}", e)})),
+        ),
+    }
+}
+
+/// Request to revoke all tokens for a user
+#[derive(Debug, Deserialize)]
+struct RevokeAllTokensRequest {
+    /// User ID whose tokens should be revoked
+    user_id: String,
+    /// Optional reason for revocation
+    reason: Option<String>,
+}
+
+/// Revoke all tokens for a user (Admin only)
+///
+/// POST /api/v1/auth/revoke-all
+/// Requires Admin role
+async fn auth_revoke_all(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<RevokeAllTokensRequest>,
+) -> impl IntoResponse {
+    // Require admin privileges
+    let admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Admin privileges required to revoke all tokens for a user"})),
+            );
+        }
+    };
+
+    // Get the user to validate they exist
+    let user_id = match uuid::Uuid::parse_str(&request.user_id) {
+        Ok(id) => id,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid user ID format"})),
+            );
+        }
+    };
+
+    let user = match state.user_service.get_user(&crate::security::UserId(user_id)) {
+        Some(user) => user,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "User not found"})),
+            );
+        }
+    };
+
+    // Determine the revocation reason
+    let reason = match request.reason.as_deref() {
+        Some("security_incident") => RevocationReason::SecurityIncident,
+        Some("password_changed") => RevocationReason::PasswordChanged,
+        _ => RevocationReason::AdminRevoke,
+    };
+
+    // Revoke all tokens for the user
+    match state.auth_service.revoke_all_user_tokens(
+        &request.user_id,
+        &user.username,
+        reason.clone(),
+    ) {
+        Ok(count) => {
+            // Log the revocation using AllTokensRevoked event type
+            state.audit_service.log_event(
+                AuditEventType::AllTokensRevoked,
+                Some(request.user_id.clone()),
+                Some(user.username.clone()),
+                "revoke_all_tokens".to_string(),
+                format!("user:{}", request.user_id),
+                AuditResult::Success,
+                None,
+                None,
+                json!({
+                    "reason": reason.to_string(),
+                    "previously_revoked_count": count,
+                    "revoked_by_admin_id": format!("{:?}", admin_id)
+                }),
+            );
+
+            (
+                StatusCode::OK,
+                Json(json!({
+                    "success": true,
+                    "message": "All tokens revoked for user",
+                    "user_id": request.user_id,
+                    "previously_revoked_count": count
+                })),
+            )
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Failed to revoke tokens: {}", e)})),
+        ),
+    }
+}
+
+// ==================== API Key Management ====================
+
+#[derive(Debug, Deserialize)]
+struct CreateApiKeyRequest {
+    name: String,
+    expires_in_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct CreateApiKeyResponse {
+    key: String,
+    id: String,
+    prefix: String,
+    name: String,
+    created_at: String,
+    expires_at: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListApiKeysResponse {
+    keys: Vec<ApiKeyInfo>,
+}
+
+#[derive(Debug, Serialize)]
+struct ApiKeyInfo {
+    id: String,
+    prefix: String,
+    name: String,
+    created_at: String,
+    expires_at: Option<String>,
+    last_used: Option<String>,
+    is_active: bool,
+}
+
+async fn api_key_create(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> impl IntoResponse {
+    // Extract user_id from JWT token in Authorization header
+    let user_id = match extract_user_from_auth(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
+            )
+        }
+    };
+
+    // Get username for audit logging
+    let username = state.user_service.get_user(&user_id)
+        .map(|u| u.username)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Generate API key with optional expiration
+    match state
+        .token_service
+        .generate_api_key(user_id, request.name.clone(), request.expires_in_days)
+    {
+        Ok((key, api_key)) => {
+            // Log successful API key creation
+            state.audit_service.log_api_key_event(
+                AuditEventType::ApiKeyCreated,
+                &user_id.0.to_string(),
+                &username,
+                &api_key.id,
+                AuditResult::Success,
+                None,
+            );
+
+            let response = CreateApiKeyResponse {
+                key,
+                id: api_key.id,
+                prefix: api_key.prefix,
+                name: api_key.name,
+                created_at: api_key.created_at.to_rfc3339(),
+                expires_at: api_key.expires_at.map(|dt| dt.to_rfc3339()),
+            };
+            (StatusCode::CREATED, Json(json!(response)))
+        }
+        Err(e) => {
+            // Log failed API key creation
+            state.audit_service.log_api_key_event(
+                AuditEventType::ApiKeyCreated,
+                &user_id.0.to_string(),
+                &username,
+                "unknown",
+                AuditResult::Failure,
+                None,
+            );
+
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to create API key: {}", e)})),
+            )
+        }
+    }
+}
+
+async fn api_key_list(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    // Extract user_id from JWT token
+    let user_id = match extract_user_from_auth(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
+            )
+        }
+    };
+
+    // List API keys for user
+    let keys = state.token_service.list_api_keys(&user_id);
+    let key_infos: Vec<ApiKeyInfo> = keys
+        .into_iter()
+        .map(|k| ApiKeyInfo {
+            id: k.id,
+            prefix: k.prefix,
+            name: k.name,
+            created_at: k.created_at.to_rfc3339(),
+            expires_at: k.expires_at.map(|dt| dt.to_rfc3339()),
+            last_used: k.last_used.map(|dt| dt.to_rfc3339()),
+            is_active: k.is_active,
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(json!(ListApiKeysResponse { keys: key_infos })),
+    )
+}
+
+async fn api_key_revoke(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(key_id): axum::extract::Path<String>,
+) -> impl IntoResponse {
+    // Extract user_id from JWT token to verify ownership
+    let user_id = match extract_user_from_auth(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
+            )
+        }
+    };
+
+    // Get username for audit logging
+    let username = state.user_service.get_user(&user_id)
+        .map(|u| u.username)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Revoke API key by ID
+    match state.token_service.revoke_api_key_by_id(&key_id) {
+        Ok(_) => {
+            state.audit_service.log_api_key_event(
+                AuditEventType::ApiKeyRevoked,
+                &user_id.0.to_string(),
+                &username,
+                &key_id,
+                AuditResult::Success,
+                None,
+            );
+
+            (
+                StatusCode::OK,
+                Json(json!({"success": true, "message": "API key revoked"})),
+            )
+        }
+        Err(e) => {
+            state.audit_service.log_api_key_event(
+                AuditEventType::ApiKeyRevoked,
+                &user_id.0.to_string(),
+                &username,
+                &key_id,
+                AuditResult::Failure,
+                None,
+            );
+
+            (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": format!("Failed to revoke API key: {}", e)})),
+            )
+        }
+    }
+}
+
+// ==================== API Key Rotation ====================
+
+#[derive(Debug, Deserialize)]
+struct RotateApiKeyRequest {
+    current_key: String,
+    new_name: Option<String>,
+    expires_in_days: Option<u32>,
+}
+
+async fn api_key_rotate(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<RotateApiKeyRequest>,
+) -> impl IntoResponse {
+    // Extract user_id from JWT token or API key
+    let user_id = match extract_user_from_auth(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: invalid or missing authentication"})),
+            )
+        }
+    };
+
+    // Get username for audit logging
+    let username = state.user_service.get_user(&user_id)
+        .map(|u| u.username)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Rotate API key
+    match state
+        .token_service
+        .rotate_api_key(&request.current_key, request.new_name.clone(), request.expires_in_days)
+    {
+        Ok((new_key, api_key)) => {
+            // Log successful API key rotation
+            state.audit_service.log_api_key_event(
+                AuditEventType::ApiKeyCreated,
+                &user_id.0.to_string(),
+                &username,
+                &api_key.id,
+                AuditResult::Success,
+                Some("rotated from previous key".to_string()),
+            );
+
+            let response = CreateApiKeyResponse {
+                key: new_key,
+                id: api_key.id,
+                prefix: api_key.prefix,
+                name: api_key.name,
+                created_at: api_key.created_at.to_rfc3339(),
+                expires_at: api_key.expires_at.map(|dt| dt.to_rfc3339()),
+            };
+            (StatusCode::CREATED, Json(json!(response)))
+        }
+        Err(e) => {
+            state.audit_service.log_api_key_event(
+                AuditEventType::ApiKeyCreated,
+                &user_id.0.to_string(),
+                &username,
+                "unknown",
+                AuditResult::Failure,
+                Some(format!("rotation failed: {}", e)),
+            );
+
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Failed to rotate API key: {}", e)})),
+            )
+        }
+    }
+}
+
+/// Helper function to extract user_id from either JWT token or API key
+/// Supports both Bearer token (Authorization: Bearer <token>) and API key (X-API-Key: <key>)
+fn extract_user_from_auth(
+    headers: &axum::http::HeaderMap,
+    state: &AppState,
+) -> Result<crate::security::UserId, StatusCode> {
+    use crate::security::UserId;
+    use uuid::Uuid;
+
+    // Try X-API-Key header first
+    if let Some(api_key_header) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
+        // Validate API key and get associated user_id
+        match state.token_service.validate_api_key(api_key_header) {
+            Ok(user_id) => return Ok(user_id),
+            Err(_) => return Err(StatusCode::UNAUTHORIZED),
+        }
+    }
+
+    // Fall back to JWT Bearer token
+    if let Some(auth_header) = headers.get("Authorization").and_then(|v| v.to_str().ok()) {
+        if let Some(token) = auth_header.strip_prefix("Bearer ") {
+            match state.token_service.validate_jwt(token) {
+                Ok(claims) => {
+                    let uuid = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
+                    return Ok(UserId(uuid));
+                }
+                Err(_) => return Err(StatusCode::UNAUTHORIZED),
+            }
+        }
+    }
+
+    // No valid authentication found
+    Err(StatusCode::UNAUTHORIZED)
+}
+
+// ==================== User Management Endpoints ====================
+
+/// Request/response DTOs for user management
+#[derive(Debug, Deserialize)]
+struct CreateUserRequest {
+    username: String,
+    email: String,
+    password: String,
+    roles: Option<Vec<super::security::rbac::Role>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateUserRequest {
+    email: Option<String>,
+    password: Option<String>,
+    is_active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateRolesRequest {
+    roles: Vec<super::security::rbac::Role>,
+}
+
+#[derive(Debug, Serialize)]
+struct UserResponse {
+    id: String,
+    username: String,
+    email: String,
+    roles: Vec<super::security::rbac::Role>,
+    is_active: bool,
+    created_at: String,
+    updated_at: String,
+    last_login: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct ListUsersResponse {
+    users: Vec<UserResponse>,
+}
+
+/// Helper to check if user has Admin role
+fn require_admin(claims: &super::security::token::Claims) -> Result<(), StatusCode> {
+    use super::security::rbac::Role;
+
+    if claims.roles.contains(&Role::Admin) {
+        Ok(())
+    } else {
+        Err(StatusCode::FORBIDDEN)
+    }
+}
+
+/// Helper to extract and validate admin user from either JWT token or API key
+fn extract_admin_from_token(
+    headers: &axum::http::HeaderMap,
+    state: &AppState,
+) -> Result<crate::security::UserId, StatusCode> {
+    let user = extract_authenticated_user(headers, state)?;
+
+    // Check if user has Admin role
+    if !user.roles.contains(&super::security::rbac::Role::Admin) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(user.id)
+}
+
+/// Helper to extract a user from either JWT token or API key and check that
+/// one of their roles grants `permission`
+fn extract_user_with_permission(
+    headers: &axum::http::HeaderMap,
+    state: &AppState,
+    permission: &Permission,
+) -> Result<crate::security::UserId, StatusCode> {
+    let user = extract_authenticated_user(headers, state)?;
+
+    if !user.roles.iter().any(|role| role.permissions().contains(permission)) {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(user.id)
+}
+
+/// Helper to look up the user of either a JWT token or an API key
+fn extract_authenticated_user(
+    headers: &axum::http::HeaderMap,
+    state: &AppState,
+) -> Result<User, StatusCode> {
+    // Try X-API-Key header first
+    if let Some(api_key_header) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
+        // Validate API key and get associated user_id
+        let user_id = state.token_service.validate_api_key(api_key_header)
+            .map_err(|_| StatusCode::UNAUTHORIZED)?;
+
+        return state.user_service.get_user(&user_id)
+            .ok_or(StatusCode::UNAUTHORIZED);
+    }
+
+    // Fall back to JWT Bearer token
+    let auth_header = headers
+        .get("Authorization")
+        .and_then(|v| v.to_str().ok())
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let token = auth_header
+        .strip_prefix("Bearer ")
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    // Use auth_service.validate_token which checks the blacklist
+    state.auth_service.validate_token(token).map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+/// Create a new user (Admin only)
+async fn user_create(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<CreateUserRequest>,
+) -> impl IntoResponse {
+    // Require admin privileges
+    let admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
+        }
+    };
+
+    // Get admin username for audit logging
+    let admin_username = state.user_service.get_user(&admin_id)
+        .map(|u| u.username)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Create user with provided roles or default to Read
+    let roles = request.roles.unwrap_or_else(|| vec![super::security::rbac::Role::Read]);
+
+    match state.user_service.create_user(
+        request.username.clone(),
+        request.email.clone(),
+        &request.password,
+    ) {
+        Ok(mut user) => {
+            // Update roles if provided
+            user.roles = roles.clone();
+
+            // Log successful user creation
+            state.audit_service.log_user_event(
+                AuditEventType::UserCreated,
+                &admin_id.0.to_string(),
+                &admin_username,
+                &user.id.0.to_string(),
+                AuditResult::Success,
+                None,
+                serde_json::json!({
+                    "new_username": user.username,
+                    "new_email": user.email,
+                    "roles": roles
+                }),
+            );
+
+            let response = UserResponse {
+                id: user.id.0.to_string(),
+                username: user.username,
+                email: user.email,
+                roles: user.roles,
+                is_active: user.is_active,
+                created_at: user.created_at.to_rfc3339(),
+                updated_at: user.updated_at.to_rfc3339(),
+                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
+            };
+
+            (StatusCode::CREATED, Json(json!(response)))
+        }
+        Err(e) => {
+            // Log failed user creation
+            state.audit_service.log_user_event(
+                AuditEventType::UserCreated,
+                &admin_id.0.to_string(),
+                &admin_username,
+                "unknown",
+                AuditResult::Failure,
+                None,
+                serde_json::json!({
+                    "attempted_username": request.username,
+                    "error": e.to_string()
+                }),
+            );
+
+            (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": format!("Failed to create user: {}", e)})),
+            )
+        }
+    }
+}
+
+/// List all users (Admin only)
+async fn user_list(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    // Require admin privileges
+    let _admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
+        }
+    };
+
+    let users = state.user_service.list_users();
+    let user_responses: Vec<UserResponse> = users
+        .into_iter()
+        .map(|u| UserResponse {
+            id: u.id.0.to_string(),
+            username: u.username,
+            email: u.email,
+            roles: u.roles,
+            is_active: u.is_active,
+            created_at: u.created_at.to_rfc3339(),
+            updated_at: u.updated_at.to_rfc3339(),
+            last_login: u.last_login.map(|dt| dt.to_rfc3339()),
+        })
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(json!(ListUsersResponse { users: user_responses })),
+    )
+}
+
+/// Get user details (own user or Admin)
+async fn user_get(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(user_id_str): axum::extract::Path<String>,
+) -> impl IntoResponse {
+    use uuid::Uuid;
+    use crate::security::UserId;
+
+    // Extract requesting user
+    let requester_id = match extract_user_from_auth(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
+            )
+        }
+    };
+
+    // Parse target user ID
+    let target_uuid = match Uuid::parse_str(&user_id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid user ID format"})),
+            )
+        }
+    };
+    let target_id = UserId(target_uuid);
+
+    // Check if requester is admin or requesting own user
+    let auth_header = headers.get("Authorization").and_then(|v| v.to_str().ok()).unwrap();
+    let token = auth_header.strip_prefix("Bearer ").unwrap();
+    let is_admin = if let Ok(claims) = state.token_service.validate_jwt(token) {
+        require_admin(&claims).is_ok()
+    } else {
+        false
+    };
+
+    if requester_id != target_id && !is_admin {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "Access denied: can only view own user or admin required"})),
+        );
+    }
+
+    match state.user_service.get_user(&target_id) {
+        Some(user) => {
+            let response = UserResponse {
+                id: user.id.0.to_string(),
+                username: user.username,
+                email: user.email,
+                roles: user.roles,
+                is_active: user.is_active,
+                created_at: user.created_at.to_rfc3339(),
+                updated_at: user.updated_at.to_rfc3339(),
+                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
+            };
+            (StatusCode::OK, Json(json!(response)))
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "User not found"})),
+        ),
+    }
+}
+
+/// Update user (own user or Admin)
+async fn user_update(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(user_id_str): axum::extract::Path<String>,
+    Json(request): Json<UpdateUserRequest>,
+) -> impl IntoResponse {
+    use uuid::Uuid;
+    use crate::security::UserId;
+
+    // Extract requesting user
+    let requester_id = match extract_user_from_auth(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
+            )
+        }
+    };
+
+    // Get requester username for audit logging
+    let requester_username = state.user_service.get_user(&requester_id)
+        .map(|u| u.username)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Parse target user ID
+    let target_uuid = match Uuid::parse_str(&user_id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid user ID format"})),
+            )
+        }
+    };
+    let target_id = UserId(target_uuid);
+
+    // Check if requester is admin or updating own user
+    let auth_header = headers.get("Authorization").and_then(|v| v.to_str().ok()).unwrap();
+    let token = auth_header.strip_prefix("Bearer ").unwrap();
+    let is_admin = if let Ok(claims) = state.token_service.validate_jwt(token) {
+        require_admin(&claims).is_ok()
+    } else {
+        false
+    };
+
+    if requester_id != target_id && !is_admin {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(json!({"error": "Access denied: can only update own user or admin required"})),
+        );
+    }
+
+    // Get existing user
+    let mut user = match state.user_service.get_user(&target_id) {
+        Some(u) => u,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "User not found"})),
+            )
+        }
+    };
+
+    // Track what was updated for audit logging
+    let mut changes = serde_json::Map::new();
+    let password_changed = request.password.is_some();
+
+    // Apply updates
+    if let Some(ref email) = request.email {
+        changes.insert("email_changed".to_string(), serde_json::json!(true));
+        user.email = email.clone();
+    }
+    if let Some(ref password) = request.password {
+        // Re-hash password using user method
+        if let Err(e) = user.update_password_with_hash_config(password, state.user_service.password_hash_config()) {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to hash password: {}", e)})),
+            )
+        }
+        changes.insert("password_changed".to_string(), serde_json::json!(true));
+    }
+    if let Some(is_active) = request.is_active {
+        // Only admin can change is_active status
+        if !is_admin {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(json!({"error": "Only admin can change user active status"})),
+            );
+        }
+        changes.insert("is_active".to_string(), serde_json::json!(is_active));
+        user.is_active = is_active;
+    }
+
+    match state.user_service.update_user(user.clone()) {
+        Ok(_) => {
+            // Log the appropriate event type
+            let event_type = if password_changed {
+                AuditEventType::UserPasswordChanged
+            } else {
+                AuditEventType::UserUpdated
+            };
+
+            state.audit_service.log_user_event(
+                event_type,
+                &requester_id.0.to_string(),
+                &requester_username,
+                &target_id.0.to_string(),
+                AuditResult::Success,
+                None,
+                serde_json::Value::Object(changes),
+            );
+
+            let response = UserResponse {
+                id: user.id.0.to_string(),
+                username: user.username,
+                email: user.email,
+                roles: user.roles,
+                is_active: user.is_active,
+                created_at: user.created_at.to_rfc3339(),
+                updated_at: user.updated_at.to_rfc3339(),
+                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
+            };
+            (StatusCode::OK, Json(json!(response)))
+        }
+        Err(e) => {
+            state.audit_service.log_user_event(
+                AuditEventType::UserUpdated,
+                &requester_id.0.to_string(),
+                &requester_username,
+                &target_id.0.to_string(),
+                AuditResult::Failure,
+                None,
+                serde_json::json!({"error": e.to_string()}),
+            );
+
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update user: {}", e)})),
+            )
+        }
+    }
+}
+
+/// Delete user (Admin only)
+async fn user_delete(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(user_id_str): axum::extract::Path<String>,
+) -> impl IntoResponse {
+    use uuid::Uuid;
+    use crate::security::UserId;
+
+    // Require admin privileges
+    let admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
+        }
+    };
+
+    // Get admin username for audit logging
+    let admin_username = state.user_service.get_user(&admin_id)
+        .map(|u| u.username)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    // Parse target user ID
+    let target_uuid = match Uuid::parse_str(&user_id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid user ID format"})),
+            )
+        }
+    };
+    let target_id = UserId(target_uuid);
+
+    // Get the target user info before deletion for audit log
+    let target_username = state.user_service.get_user(&target_id)
+        .map(|u| u.username)
+        .unwrap_or_else(|| "unknown".to_string());
+
+    match state.user_service.delete_user(&target_id) {
+        Ok(_) => {
+            state.audit_service.log_user_event(
+                AuditEventType::UserDeleted,
+                &admin_id.0.to_string(),
+                &admin_username,
+                &target_id.0.to_string(),
+                AuditResult::Success,
+                None,
+                serde_json::json!({"deleted_username": target_username}),
+            );
+
+            (
+                StatusCode::OK,
+                Json(json!({"success": true, "message": "User deleted"})),
+            )
+        }
+        Err(e) => {
+            state.audit_service.log_user_event(
+                AuditEventType::UserDeleted,
+                &admin_id.0.to_string(),
+                &admin_username,
+                &target_id.0.to_string(),
+                AuditResult::Failure,
+                None,
+                serde_json::json!({"error": e.to_string()}),
+            );
+
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to delete user: {}", e)})),
+            )
+        }
+    }
 }
 
-async fn api_key_rotate(
+/// Update user roles (Admin only)
+async fn user_update_roles(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-    Json(request): Json<RotateApiKeyRequest>,
+    axum::extract::Path(user_id_str): axum::extract::Path<String>,
+    Json(request): Json<UpdateRolesRequest>,
 ) -> impl IntoResponse {
-    // Extract user_id from JWT token or API key
-    let user_id = match extract_user_from_auth(&headers, &state) {
+    use uuid::Uuid;
+    use crate::security::UserId;
+
+    // Require admin privileges
+    let admin_id = match extract_admin_from_token(&headers, &state) {
         Ok(uid) => uid,
         Err(status) => {
             return (
                 status,
-                Json(json!({"error": "Unauthorized: invalid or missing authentication"})),
+                Json(json!({"error": "Unauthorized: Admin access required"})),
             )
         }
     };
 
-    // Get username for audit logging
-    let username = state.user_service.get_user(&user_id)
+    // Get admin username for audit logging
+    let admin_username = state.user_service.get_user(&admin_id)
         .map(|u| u.username)
         .unwrap_or_else(|| "unknown".to_string());
 
-    // Rotate API key
-    match state
-        .token_service
-        .rotate_api_key(&request.current_key, request.new_name.clone(), request.expires_in_days)
-    {
-        Ok((new_key, api_key)) => {
-            // Log successful API key rotation
-            state.audit_service.log_api_key_event(
-                AuditEventType::ApiKeyCreated,
-                &user_id.0.to_string(),
-                &username,
-                &api_key.id,
+    // Parse target user ID
+    let target_uuid = match Uuid::parse_str(&user_id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid user ID format"})),
+            )
+        }
+    };
+    let target_id = UserId(target_uuid);
+
+    // Get existing user
+    let mut user = match state.user_service.get_user(&target_id) {
+        Some(u) => u,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "User not found"})),
+            )
+        }
+    };
+
+    // Track old roles for audit log
+    let old_roles = user.roles.clone();
+    let new_roles = request.roles.clone();
+
+    // Update roles
+    user.roles = request.roles;
+
+    match state.user_service.update_user(user.clone()) {
+        Ok(_) => {
+            // Log role change as RoleAssigned event (covers both assignment and removal)
+            state.audit_service.log_user_event(
+                AuditEventType::RoleAssigned,
+                &admin_id.0.to_string(),
+                &admin_username,
+                &target_id.0.to_string(),
                 AuditResult::Success,
-                Some("rotated from previous key".to_string()),
+                None,
+                serde_json::json!({
+                    "target_username": user.username,
+                    "old_roles": old_roles,
+                    "new_roles": new_roles
+                }),
             );
 
-            let response = CreateApiKeyResponse {
-                key: new_key,
-                id: api_key.id,
-                prefix: api_key.prefix,
-                name: api_key.name,
-                created_at: api_key.created_at.to_rfc3339(),
-                expires_at: api_key.expires_at.map(|dt| dt.to_rfc3339()),
+            let response = UserResponse {
+                id: user.id.0.to_string(),
+                username: user.username,
+                email: user.email,
+                roles: user.roles,
+                is_active: user.is_active,
+                created_at: user.created_at.to_rfc3339(),
+                updated_at: user.updated_at.to_rfc3339(),
+                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
             };
-            (StatusCode::CREATED, Json(json!(response)))
+            (StatusCode::OK, Json(json!(response)))
+        }
+        Err(e) => {
+            state.audit_service.log_user_event(
+                AuditEventType::RoleAssigned,
+                &admin_id.0.to_string(),
+                &admin_username,
+                &target_id.0.to_string(),
+                AuditResult::Failure,
+                None,
+                serde_json::json!({"error": e.to_string()}),
+            );
+
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Failed to update user roles: {}", e)})),
+            )
+        }
+    }
+}
+
+// ==================== Rate Limit Policy Management Endpoints ====================
+
+use crate::security::{RateLimitPolicy, PolicyId, EndpointType};
+
+/// Request/response DTOs for rate limit policy management
+#[derive(Debug, Deserialize)]
+struct CreateRateLimitPolicyRequest {
+    name: String,
+    endpoint_type: EndpointType,
+    max_requests: u32,
+    window_secs: u64,
+    enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct UpdateRateLimitPolicyRequest {
+    name: Option<String>,
+    max_requests: Option<u32>,
+    window_secs: Option<u64>,
+    enabled: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct RateLimitPolicyResponse {
+    id: String,
+    name: String,
+    endpoint_type: EndpointType,
+    max_requests: u32,
+    window_secs: u64,
+    enabled: bool,
+    created_at: String,
+    updated_at: String,
+    created_by: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ListRateLimitPoliciesResponse {
+    policies: Vec<RateLimitPolicyResponse>,
+}
+
+impl From<RateLimitPolicy> for RateLimitPolicyResponse {
+    fn from(policy: RateLimitPolicy) -> Self {
+        Self {
+            id: policy.id.0.to_string(),
+            name: policy.name,
+            endpoint_type: policy.endpoint_type,
+            max_requests: policy.max_requests,
+            window_secs: policy.window_secs,
+            enabled: policy.enabled,
+            created_at: policy.created_at.to_rfc3339(),
+            updated_at: policy.updated_at.to_rfc3339(),
+            created_by: policy.created_by,
+        }
+    }
+}
+
+/// Create a new rate limit policy (Admin only)
+async fn rate_limit_create(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<CreateRateLimitPolicyRequest>,
+) -> impl IntoResponse {
+    // Require admin privileges
+    let admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
+        }
+    };
+
+    // Create policy
+    let policy = RateLimitPolicy {
+        id: PolicyId::new(),
+        name: request.name,
+        endpoint_type: request.endpoint_type,
+        max_requests: request.max_requests,
+        window_secs: request.window_secs,
+        enabled: request.enabled,
+        created_at: chrono::Utc::now(),
+        updated_at: chrono::Utc::now(),
+        created_by: admin_id.0.to_string(),
+    };
+
+    let policy_id = state.rate_limit_service.create_policy(policy.clone());
+
+    // Get the stored policy to return the correct ID
+    let stored_policy = state.rate_limit_service.get_policy(policy_id).unwrap();
+    let response = RateLimitPolicyResponse::from(stored_policy);
+
+    (StatusCode::CREATED, Json(json!(response)))
+}
+
+/// List all rate limit policies (Admin only)
+async fn rate_limit_list(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    // Require admin privileges
+    let _admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
+        }
+    };
+
+    let policies = state.rate_limit_service.list_policies();
+    let policy_responses: Vec<RateLimitPolicyResponse> = policies
+        .into_iter()
+        .map(RateLimitPolicyResponse::from)
+        .collect();
+
+    (
+        StatusCode::OK,
+        Json(json!(ListRateLimitPoliciesResponse { policies: policy_responses })),
+    )
+}
+
+/// Get a specific rate limit policy (Admin only)
+async fn rate_limit_get(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(policy_id_str): axum::extract::Path<String>,
+) -> impl IntoResponse {
+    use uuid::Uuid;
+
+    // Require admin privileges
+    let _admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
         }
-        Err(e) => {
-            state.audit_service.log_api_key_event(
-                AuditEventType::ApiKeyCreated,
-                &user_id.0.to_string(),
-                &username,
-                "unknown",
-                AuditResult::Failure,
-                Some(format!("rotation failed: {}", e)),
-            );
+    };
 
-            (
+    // Parse policy ID
+    let policy_uuid = match Uuid::parse_str(&policy_id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return (
                 StatusCode::BAD_REQUEST,
-                Json(json!({"error": format!("Failed to rotate API key: {}", e)})),
+                Json(json!({"error": "Invalid policy ID format"})),
             )
         }
+    };
+    let policy_id = PolicyId(policy_uuid);
+
+    match state.rate_limit_service.get_policy(policy_id) {
+        Some(policy) => {
+            let response = RateLimitPolicyResponse::from(policy);
+            (StatusCode::OK, Json(json!(response)))
+        }
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Rate limit policy not found"})),
+        ),
     }
 }
 
-/// Helper function to extract user_id from either JWT token or API key
-/// Supports both Bearer token (Authorization: Bearer <token>) and API key (X-API-Key: <key>)
-fn extract_user_from_auth(
-    headers: &axum::http::HeaderMap,
-    state: &AppState,
-) -> Result<crate::security::UserId, StatusCode> {
-    use crate::security::UserId;
+/// Update a rate limit policy (Admin only)
+async fn rate_limit_update(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(policy_id_str): axum::extract::Path<String>,
+    Json(request): Json<UpdateRateLimitPolicyRequest>,
+) -> impl IntoResponse {
     use uuid::Uuid;
 
-    // Try X-API-Key header first
-    if let Some(api_key_header) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
-        // Validate API key and get associated user_id
-        match state.token_service.validate_api_key(api_key_header) {
-            Ok(user_id) => return Ok(user_id),
-            Err(_) => return Err(StatusCode::UNAUTHORIZED),
+    // Require admin privileges
+    let _admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
         }
-    }
+    };
 
-    // Fall back to JWT Bearer token
-    if let Some(auth_header) = headers.get("Authorization").and_then(|v| v.to_str().ok()) {
-        if let Some(token) = auth_header.strip_prefix("Bearer ") {
-            match state.token_service.validate_jwt(token) {
-                Ok(claims) => {
-                    let uuid = Uuid::parse_str(&claims.sub).map_err(|_| StatusCode::UNAUTHORIZED)?;
-                    return Ok(UserId(uuid));
-                }
-                Err(_) => return Err(StatusCode::UNAUTHORIZED),
-            }
+    // Parse policy ID
+    let policy_uuid = match Uuid::parse_str(&policy_id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid policy ID format"})),
+            )
         }
-    }
-
-    // No valid authentication found
-    Err(StatusCode::UNAUTHORIZED)
-}
-
-// ==================== User Management Endpoints ====================
-
-/// Request/response DTOs for user management
-#[derive(Debug, Deserialize)]
-struct CreateUserRequest {
-    username: String,
-    email: String,
-    password: String,
-    roles: Option<Vec<super::security::rbac::Role>>,
-}
-
-#[derive(Debug, Deserialize)]
-struct UpdateUserRequest {
-    email: Option<String>,
-    password: Option<String>,
-    is_active: Option<bool>,
-}
-
-#[derive(Debug, Deserialize)]
-struct UpdateRolesRequest {
-    roles: Vec<super::security::rbac::Role>,
-}
-
-#[derive(Debug, Serialize)]
-struct UserResponse {
-    id: String,
-    username: String,
-    email: String,
-    roles: Vec<super::security::rbac::Role>,
-    is_active: bool,
-    created_at: String,
-    updated_at: String,
-    last_login: Option<String>,
-}
+    };
+    let policy_id = PolicyId(policy_uuid);
 
-#[derive(Debug, Serialize)]
-struct ListUsersResponse {
-    users: Vec<UserResponse>,
-}
+    // Get existing policy
+    let mut policy = match state.rate_limit_service.get_policy(policy_id) {
+        Some(p) => p,
+        None => {
+            return (
+                StatusCode::NOT_FOUND,
+                Json(json!({"error": "Rate limit policy not found"})),
+            )
+        }
+    };
 
-/// Helper to check if user has Admin role
-fn require_admin(claims: &super::security::token::Claims) -> Result<(), StatusCode> {
-    use super::security::rbac::Role;
+    // Update fields
+    if let Some(name) = request.name {
+        policy.name = name;
+    }
+    if let Some(max_requests) = request.max_requests {
+        policy.max_requests = max_requests;
+    }
+    if let Some(window_secs) = request.window_secs {
+        policy.window_secs = window_secs;
+    }
+    if let Some(enabled) = request.enabled {
+        policy.enabled = enabled;
+    }
 
-    if claims.roles.contains(&Role::Admin) {
-        Ok(())
-    } else {
-        Err(StatusCode::FORBIDDEN)
+    match state.rate_limit_service.update_policy(policy_id, policy.clone()) {
+        Some(_) => {
+            let response = RateLimitPolicyResponse::from(policy);
+            (StatusCode::OK, Json(json!(response)))
+        }
+        None => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": "Failed to update rate limit policy"})),
+        ),
     }
 }
 
-/// Helper to extract and validate admin user from either JWT token or API key
-fn extract_admin_from_token(
-    headers: &axum::http::HeaderMap,
-    state: &AppState,
-) -> Result<crate::security::UserId, StatusCode> {
-    use crate::security::UserId;
+/// Delete a rate limit policy (Admin only)
+async fn rate_limit_delete(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    axum::extract::Path(policy_id_str): axum::extract::Path<String>,
+) -> impl IntoResponse {
     use uuid::Uuid;
 
-    // Try X-API-Key header first
-    if let Some(api_key_header) = headers.get("X-API-Key").and_then(|v| v.to_str().ok()) {
-        // Validate API key and get associated user_id
-        let user_id = state.token_service.validate_api_key(api_key_header)
-            .map_err(|_| StatusCode::UNAUTHORIZED)?;
-
-        // Get user to check admin role
-        let user = state.user_service.get_user(&user_id)
-            .ok_or(StatusCode::UNAUTHORIZED)?;
+    // Require admin privileges
+    let _admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
+            return (
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
+            )
+        }
+    };
 
-        // Check if user has Admin role
-        if !user.roles.contains(&super::security::rbac::Role::Admin) {
-            return Err(StatusCode::FORBIDDEN);
+    // Parse policy ID
+    let policy_uuid = match Uuid::parse_str(&policy_id_str) {
+        Ok(uuid) => uuid,
+        Err(_) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({"error": "Invalid policy ID format"})),
+            )
         }
+    };
+    let policy_id = PolicyId(policy_uuid);
 
-        return Ok(user_id);
+    match state.rate_limit_service.delete_policy(policy_id) {
+        Some(_) => (
+            StatusCode::OK,
+            Json(json!({"success": true, "message": "Rate limit policy deleted"})),
+        ),
+        None => (
+            StatusCode::NOT_FOUND,
+            Json(json!({"error": "Rate limit policy not found"})),
+        ),
     }
+}
 
-    // Fall back to JWT Bearer token
-    let auth_header = headers
-        .get("Authorization")
-        .and_then(|v| v.to_str().ok())
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+// ==================== Audit Log Query Endpoint ====================
 
-    let token = auth_header
-        .strip_prefix("Bearer ")
-        .ok_or(StatusCode::UNAUTHORIZED)?;
+use crate::security::AuditFilter;
 
-    // Use auth_service.validate_token which checks the blacklist
-    match state.auth_service.validate_token(token) {
-        Ok(user) => {
-            // Check if user has Admin role
-            if !user.roles.contains(&super::security::rbac::Role::Admin) {
-                return Err(StatusCode::FORBIDDEN);
-            }
+/// Query parameters for audit log filtering
+#[derive(Debug, Deserialize)]
+struct AuditLogsQueryParams {
+    user_id: Option<String>,
+    username: Option<String>,
+    event_type: Option<String>,
+    action: Option<String>,
+    result: Option<String>,
+    ip_address: Option<String>,
+    start_time: Option<String>,
+    end_time: Option<String>,
+    limit: Option<usize>,
+}
 
-            Ok(user.id)
-        }
-        Err(_) => Err(StatusCode::UNAUTHORIZED),
-    }
+/// Response format for audit events
+#[derive(Debug, Serialize)]
+struct AuditEventResponse {
+    event_id: String,
+    event_type: String,
+    timestamp: String,
+    user_id: Option<String>,
+    username: Option<String>,
+    action: String,
+    resource: String,
+    result: String,
+    ip_address: Option<String>,
+    user_agent: Option<String>,
+    metadata: serde_json::Value,
+    transaction_time: String,
 }
 
-/// Create a new user (Admin only)
-async fn user_create(
+/// Query audit logs (Admin only)
+async fn audit_logs_query(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-    Json(request): Json<CreateUserRequest>,
+    AxumQuery(params): AxumQuery<AuditLogsQueryParams>,
 ) -> impl IntoResponse {
     // Require admin privileges
-    let admin_id = match extract_admin_from_token(&headers, &state) {
+    let _admin_id = match extract_admin_from_token(&headers, &state) {
         Ok(uid) => uid,
         Err(status) => {
             return (
@@ -2458,76 +5416,113 @@ async fn user_create(
         }
     };
 
-    // Get admin username for audit logging
-    let admin_username = state.user_service.get_user(&admin_id)
-        .map(|u| u.username)
-        .unwrap_or_else(|| "unknown".to_string());
-
-    // Create user with provided roles or default to Read
-    let roles = request.roles.unwrap_or_else(|| vec![super::security::rbac::Role::Read]);
+    // Build filter from query params
+    let mut filter = AuditFilter::new();
 
-    match state.user_service.create_user(
-        request.username.clone(),
-        request.email.clone(),
-        &request.password,
-    ) {
-        Ok(mut user) => {
-            // Update roles if provided
-            user.roles = roles.clone();
+    if let Some(user_id) = params.user_id {
+        filter = filter.user_id(user_id);
+    }
+    if let Some(username) = params.username {
+        filter = filter.username(username);
+    }
+    if let Some(event_type_str) = params.event_type {
+        // Parse event type from string
+        let event_type = match event_type_str.as_str() {
+            "login" => Some(AuditEventType::Login),
+            "login_failed" => Some(AuditEventType::LoginFailed),
+            "logout" => Some(AuditEventType::Logout),
+            "token_refresh" => Some(AuditEventType::TokenRefresh),
+            "token_refresh_failed" => Some(AuditEventType::TokenRefreshFailed),
+            "security_incident" => Some(AuditEventType::SecurityIncident),
+            "api_key_created" => Some(AuditEventType::ApiKeyCreated),
+            "api_key_revoked" => Some(AuditEventType::ApiKeyRevoked),
+            "api_key_used" => Some(AuditEventType::ApiKeyUsed),
+            "api_key_validation_failed" => Some(AuditEventType::ApiKeyValidationFailed),
+            "user_created" => Some(AuditEventType::UserCreated),
+            "user_updated" => Some(AuditEventType::UserUpdated),
+            "user_deleted" => Some(AuditEventType::UserDeleted),
+            "user_password_changed" => Some(AuditEventType::UserPasswordChanged),
+            "role_assigned" => Some(AuditEventType::RoleAssigned),
+            "role_removed" => Some(AuditEventType::RoleRemoved),
+            "permission_denied" => Some(AuditEventType::PermissionDenied),
+            "access_granted" => Some(AuditEventType::AccessGranted),
+            "rate_limit_exceeded" => Some(AuditEventType::RateLimitExceeded),
+            "system_startup" => Some(AuditEventType::SystemStartup),
+            "system_shutdown" => Some(AuditEventType::SystemShutdown),
+            "configuration_changed" => Some(AuditEventType::ConfigurationChanged),
+            "query_executed" => Some(AuditEventType::QueryExecuted),
+            _ => None,
+        };
+        if let Some(et) = event_type {
+            filter = filter.event_type(et);
+        }
+    }
+    if let Some(action) = params.action {
+        filter = filter.action(action);
+    }
+    if let Some(result_str) = params.result {
+        let result = match result_str.as_str() {
+            "success" => Some(AuditResult::Success),
+            "failure" => Some(AuditResult::Failure),
+            "unauthorized" => Some(AuditResult::Unauthorized),
+            "forbidden" => Some(AuditResult::Forbidden),
+            "error" => Some(AuditResult::Error),
+            _ => None,
+        };
+        if let Some(r) = result {
+            filter = filter.result(r);
+        }
+    }
+    if let Some(ip) = params.ip_address {
+        filter = filter.ip_address(ip);
+    }
+    if let (Some(start_str), Some(end_str)) = (&params.start_time, &params.end_time) {
+        if let (Ok(start), Ok(end)) = (
+            chrono::DateTime::parse_from_rfc3339(start_str),
+            chrono::DateTime::parse_from_rfc3339(end_str),
+        ) {
+            filter = filter.time_range(start.with_timezone(&chrono::Utc), end.with_timezone(&chrono::Utc));
+        }
+    }
 
-            // Log successful user creation
-            state.audit_service.log_user_event(
-                AuditEventType::UserCreated,
-                &admin_id.0.to_string(),
-                &admin_username,
-                &user.id.0.to_string(),
-                AuditResult::Success,
-                None,
-                serde_json::json!({
-                    "new_username": user.username,
-                    "new_email": user.email,
-                    "roles": roles
-                }),
-            );
+    let limit = params.limit.unwrap_or(100).min(1000); // Max 1000 events per query
 
-            let response = UserResponse {
-                id: user.id.0.to_string(),
-                username: user.username,
-                email: user.email,
-                roles: user.roles,
-                is_active: user.is_active,
-                created_at: user.created_at.to_rfc3339(),
-                updated_at: user.updated_at.to_rfc3339(),
-                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
-            };
+    // Query events
+    let events = state.audit_service.query_events(filter, limit);
 
-            (StatusCode::CREATED, Json(json!(response)))
-        }
-        Err(e) => {
-            // Log failed user creation
-            state.audit_service.log_user_event(
-                AuditEventType::UserCreated,
-                &admin_id.0.to_string(),
-                &admin_username,
-                "unknown",
-                AuditResult::Failure,
-                None,
-                serde_json::json!({
-                    "attempted_username": request.username,
-                    "error": e.to_string()
-                }),
-            );
+    // Convert to response format
+    let event_responses: Vec<AuditEventResponse> = events
+        .into_iter()
+        .map(|e| AuditEventResponse {
+            event_id: e.event_id,
+            event_type: e.event_type.to_string(),
+            timestamp: e.timestamp.to_rfc3339(),
+            user_id: e.user_id,
+            username: e.username,
+            action: e.action,
+            resource: e.resource,
+            result: e.result.to_string(),
+            ip_address: e.ip_address,
+            user_agent: e.user_agent,
+            metadata: e.metadata,
+            transaction_time: e.transaction_time.to_rfc3339(),
+        })
+        .collect();
 
-            (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": format!("Failed to create user: {}", e)})),
-            )
-        }
-    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "events": event_responses,
+            "count": event_responses.len(),
+            "limit": limit
+        })),
+    )
 }
 
-/// List all users (Admin only)
-async fn user_list(
+// ==================== Account Lockout Handlers ====================
+
+/// List all locked accounts (Admin only)
+async fn lockout_list(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
 ) -> impl IntoResponse {
@@ -2542,1250 +5537,3047 @@ async fn user_list(
         }
     };
 
-    let users = state.user_service.list_users();
-    let user_responses: Vec<UserResponse> = users
-        .into_iter()
-        .map(|u| UserResponse {
-            id: u.id.0.to_string(),
-            username: u.username,
-            email: u.email,
-            roles: u.roles,
-            is_active: u.is_active,
-            created_at: u.created_at.to_rfc3339(),
-            updated_at: u.updated_at.to_rfc3339(),
-            last_login: u.last_login.map(|dt| dt.to_rfc3339()),
-        })
-        .collect();
+    let locked_users = state.lockout_service.get_locked_users();
 
     (
         StatusCode::OK,
-        Json(json!(ListUsersResponse { users: user_responses })),
+        Json(json!({
+            "locked_users": locked_users,
+            "count": locked_users.len()
+        })),
     )
 }
 
-/// Get user details (own user or Admin)
-async fn user_get(
+/// Get lockout status for a specific user (Admin only)
+async fn lockout_status(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-    axum::extract::Path(user_id_str): axum::extract::Path<String>,
+    Path(username): Path<String>,
 ) -> impl IntoResponse {
-    use uuid::Uuid;
-    use crate::security::UserId;
-
-    // Extract requesting user
-    let requester_id = match extract_user_from_auth(&headers, &state) {
+    // Require admin privileges
+    let _admin_id = match extract_admin_from_token(&headers, &state) {
         Ok(uid) => uid,
         Err(status) => {
             return (
                 status,
-                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
-            )
-        }
-    };
-
-    // Parse target user ID
-    let target_uuid = match Uuid::parse_str(&user_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid user ID format"})),
+                Json(json!({"error": "Unauthorized: Admin access required"})),
             )
         }
     };
-    let target_id = UserId(target_uuid);
 
-    // Check if requester is admin or requesting own user
-    let auth_header = headers.get("Authorization").and_then(|v| v.to_str().ok()).unwrap();
-    let token = auth_header.strip_prefix("Bearer ").unwrap();
-    let is_admin = if let Ok(claims) = state.token_service.validate_jwt(token) {
-        require_admin(&claims).is_ok()
-    } else {
-        false
-    };
+    let status = state.lockout_service.get_user_status(&username);
 
-    if requester_id != target_id && !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({"error": "Access denied: can only view own user or admin required"})),
-        );
-    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "username": username,
+            "status": status
+        })),
+    )
+}
 
-    match state.user_service.get_user(&target_id) {
-        Some(user) => {
-            let response = UserResponse {
-                id: user.id.0.to_string(),
-                username: user.username,
-                email: user.email,
-                roles: user.roles,
-                is_active: user.is_active,
-                created_at: user.created_at.to_rfc3339(),
-                updated_at: user.updated_at.to_rfc3339(),
-                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
-            };
-            (StatusCode::OK, Json(json!(response)))
-        }
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(json!({"error": "User not found"})),
-        ),
-    }
+#[derive(Debug, Deserialize)]
+struct LockUserRequest {
+    reason: Option<String>,
 }
 
-/// Update user (own user or Admin)
-async fn user_update(
+/// Manually lock a user account (Admin only)
+async fn lockout_lock(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-    axum::extract::Path(user_id_str): axum::extract::Path<String>,
-    Json(request): Json<UpdateUserRequest>,
+    Path(username): Path<String>,
+    Json(request): Json<LockUserRequest>,
 ) -> impl IntoResponse {
-    use uuid::Uuid;
-    use crate::security::UserId;
-
-    // Extract requesting user
-    let requester_id = match extract_user_from_auth(&headers, &state) {
+    // Require admin privileges
+    let admin_id = match extract_admin_from_token(&headers, &state) {
         Ok(uid) => uid,
         Err(status) => {
             return (
                 status,
-                Json(json!({"error": "Unauthorized: invalid or missing JWT token"})),
+                Json(json!({"error": "Unauthorized: Admin access required"})),
             )
         }
     };
 
-    // Get requester username for audit logging
-    let requester_username = state.user_service.get_user(&requester_id)
-        .map(|u| u.username)
-        .unwrap_or_else(|| "unknown".to_string());
+    state.lockout_service.lock_user(&username, request.reason.clone());
 
-    // Parse target user ID
-    let target_uuid = match Uuid::parse_str(&user_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
+    // Log the manual lock event
+    state.audit_service.log_auth_event(
+        AuditEventType::AccountLocked,
+        &username,
+        AuditResult::Success,
+        None,
+        Some(format!(
+            "Manually locked by admin {:?}. Reason: {:?}",
+            admin_id,
+            request.reason
+        )),
+    );
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "success": true,
+            "message": format!("Account '{}' has been locked", username)
+        })),
+    )
+}
+
+/// Manually unlock a user account (Admin only)
+async fn lockout_unlock(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+    Path(username): Path<String>,
+) -> impl IntoResponse {
+    // Require admin privileges
+    let admin_id = match extract_admin_from_token(&headers, &state) {
+        Ok(uid) => uid,
+        Err(status) => {
             return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid user ID format"})),
+                status,
+                Json(json!({"error": "Unauthorized: Admin access required"})),
             )
         }
     };
-    let target_id = UserId(target_uuid);
 
-    // Check if requester is admin or updating own user
-    let auth_header = headers.get("Authorization").and_then(|v| v.to_str().ok()).unwrap();
-    let token = auth_header.strip_prefix("Bearer ").unwrap();
-    let is_admin = if let Ok(claims) = state.token_service.validate_jwt(token) {
-        require_admin(&claims).is_ok()
-    } else {
-        false
-    };
+    let was_locked = state.lockout_service.unlock_user(&username);
 
-    if requester_id != target_id && !is_admin {
-        return (
-            StatusCode::FORBIDDEN,
-            Json(json!({"error": "Access denied: can only update own user or admin required"})),
+    if was_locked {
+        // Log the unlock event
+        state.audit_service.log_auth_event(
+            AuditEventType::AccountUnlocked,
+            &username,
+            AuditResult::Success,
+            None,
+            Some(format!("Unlocked by admin {:?}", admin_id)),
         );
-    }
 
-    // Get existing user
-    let mut user = match state.user_service.get_user(&target_id) {
-        Some(u) => u,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "User not found"})),
-            )
-        }
-    };
+        (
+            StatusCode::OK,
+            Json(json!({
+                "success": true,
+                "message": format!("Account '{}' has been unlocked", username)
+            })),
+        )
+    } else {
+        (
+            StatusCode::NOT_FOUND,
+            Json(json!({
+                "success": false,
+                "error": format!("No lockout record found for user '{}'", username)
+            })),
+        )
+    }
+}
 
-    // Track what was updated for audit logging
-    let mut changes = serde_json::Map::new();
-    let password_changed = request.password.is_some();
+// ==================== Memory Maintenance ====================
 
-    // Apply updates
-    if let Some(ref email) = request.email {
-        changes.insert("email_changed".to_string(), serde_json::json!(true));
-        user.email = email.clone();
+/// Report memory storage used by each agent, largest first (Admin only)
+///
+/// GET /admin/memory/usage
+async fn memory_usage(
+    State(state): State<AppState>,
+    headers: axum::http::HeaderMap,
+) -> impl IntoResponse {
+    // Require admin privileges
+    if let Err(status) = extract_admin_from_token(&headers, &state) {
+        return (
+            status,
+            Json(json!({"error": "Unauthorized: Admin access required"})),
+        );
     }
-    if let Some(ref password) = request.password {
-        // Re-hash password using user method
-        if let Err(e) = user.update_password(password) {
+
+    // Measuring walks every memory entry, so keep it off the async workers
+    let storage = DatabaseMemoryStorage::new(state.database.storage().clone());
+    let usage = match tokio::task::spawn_blocking(move || storage.usage()).await {
+        Ok(Ok(usage)) => usage,
+        Ok(Err(e)) => {
             return (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to hash password: {}", e)})),
-            )
+                Json(json!({"error": e.to_string()})),
+            );
         }
-        changes.insert("password_changed".to_string(), serde_json::json!(true));
-    }
-    if let Some(is_active) = request.is_active {
-        // Only admin can change is_active status
-        if !is_admin {
+        Err(e) => {
             return (
-                StatusCode::FORBIDDEN,
-                Json(json!({"error": "Only admin can change user active status"})),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Usage scan failed: {}", e)})),
             );
         }
-        changes.insert("is_active".to_string(), serde_json::json!(is_active));
-        user.is_active = is_active;
-    }
-
-    match state.user_service.update_user(user.clone()) {
-        Ok(_) => {
-            // Log the appropriate event type
-            let event_type = if password_changed {
-                AuditEventType::UserPasswordChanged
-            } else {
-                AuditEventType::UserUpdated
-            };
-
-            state.audit_service.log_user_event(
-                event_type,
-                &requester_id.0.to_string(),
-                &requester_username,
-                &target_id.0.to_string(),
-                AuditResult::Success,
-                None,
-                serde_json::Value::Object(changes),
-            );
+    };
 
-            let response = UserResponse {
-                id: user.id.0.to_string(),
-                username: user.username,
-                email: user.email,
-                roles: user.roles,
-                is_active: user.is_active,
-                created_at: user.created_at.to_rfc3339(),
-                updated_at: user.updated_at.to_rfc3339(),
-                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
-            };
-            (StatusCode::OK, Json(json!(response)))
-        }
-        Err(e) => {
-            state.audit_service.log_user_event(
-                AuditEventType::UserUpdated,
-                &requester_id.0.to_string(),
-                &requester_username,
-                &target_id.0.to_string(),
-                AuditResult::Failure,
-                None,
-                serde_json::json!({"error": e.to_string()}),
-            );
+    let agents: Vec<_> = usage
+        .iter()
+        .map(|agent| {
+            json!({
+                "agent_id": agent.agent_id,
+                "episodes": agent.episodes,
+                "index_entries": agent.index_entries,
+                "episode_bytes": agent.episode_bytes,
+                "index_bytes": agent.index_bytes,
+                "total_bytes": agent.total_bytes()
+            })
+        })
+        .collect();
+    let total_bytes: u64 = usage.iter().map(|agent| agent.total_bytes()).sum();
 
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to update user: {}", e)})),
-            )
-        }
-    }
+    (
+        StatusCode::OK,
+        Json(json!({
+            "agents": agents,
+            "count": agents.len(),
+            "total_bytes": total_bytes
+        })),
+    )
 }
 
-/// Delete user (Admin only)
-async fn user_delete(
+/// Apply a forgetting policy to every agent with stored memory (Admin only)
+///
+/// POST /admin/memory/forget-all
+///
+/// Takes the same body as the per-agent forget endpoint. Agents are processed
+/// one at a time and a failure is reported for that agent without stopping
+/// the others.
+async fn forget_all_memory(
     State(state): State<AppState>,
     headers: axum::http::HeaderMap,
-    axum::extract::Path(user_id_str): axum::extract::Path<String>,
+    Json(request): Json<ForgetMemoryRequest>,
 ) -> impl IntoResponse {
-    use uuid::Uuid;
-    use crate::security::UserId;
-
     // Require admin privileges
-    let admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
-        }
-    };
+    if let Err(status) = extract_admin_from_token(&headers, &state) {
+        return (
+            status,
+            Json(json!({"error": "Unauthorized: Admin access required"})),
+        );
+    }
 
-    // Get admin username for audit logging
-    let admin_username = state.user_service.get_user(&admin_id)
-        .map(|u| u.username)
-        .unwrap_or_else(|| "unknown".to_string());
+    if let Err(message) = request.validate() {
+        return (StatusCode::BAD_REQUEST, Json(json!({"error": message})));
+    }
 
-    // Parse target user ID
-    let target_uuid = match Uuid::parse_str(&user_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
+    // Finding the agents walks every memory entry, so keep it off the async workers
+    let storage = DatabaseMemoryStorage::new(state.database.storage().clone());
+    let mut agent_ids = match tokio::task::spawn_blocking(move || storage.usage()).await {
+        Ok(Ok(usage)) => usage.into_iter().map(|agent| agent.agent_id).collect::<Vec<_>>(),
+        Ok(Err(e)) => {
             return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid user ID format"})),
-            )
-        }
-    };
-    let target_id = UserId(target_uuid);
-
-    // Get the target user info before deletion for audit log
-    let target_username = state.user_service.get_user(&target_id)
-        .map(|u| u.username)
-        .unwrap_or_else(|| "unknown".to_string());
-
-    match state.user_service.delete_user(&target_id) {
-        Ok(_) => {
-            state.audit_service.log_user_event(
-                AuditEventType::UserDeleted,
-                &admin_id.0.to_string(),
-                &admin_username,
-                &target_id.0.to_string(),
-                AuditResult::Success,
-                None,
-                serde_json::json!({"deleted_username": target_username}),
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": e.to_string()})),
             );
-
-            (
-                StatusCode::OK,
-                Json(json!({"success": true, "message": "User deleted"})),
-            )
         }
         Err(e) => {
-            state.audit_service.log_user_event(
-                AuditEventType::UserDeleted,
-                &admin_id.0.to_string(),
-                &admin_username,
-                &target_id.0.to_string(),
-                AuditResult::Failure,
-                None,
-                serde_json::json!({"error": e.to_string()}),
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(json!({"error": format!("Agent scan failed: {}", e)})),
             );
+        }
+    };
+    agent_ids.sort();
+
+    let criteria = request.criteria();
+    let mut agents = Vec::with_capacity(agent_ids.len());
+    let mut total_forgotten = 0;
+    let mut failed = 0;
+    for agent_id in agent_ids {
+        let result = match state.existing_agent_memory(&agent_id).await {
+            Some(memory) => memory.forget(&criteria).await.map_err(|e| e.to_string()),
+            None => Err(format!("Memory for agent '{}' could not be loaded", agent_id)),
+        };
 
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to delete user: {}", e)})),
-            )
+        match result {
+            Ok(report) => {
+                let by_type = forgotten_by_type(&report);
+                total_forgotten += report.total();
+                state.audit_service.log_memory_event(
+                    AuditEventType::MemoryForgotten,
+                    None,
+                    None,
+                    &agent_id,
+                    AuditResult::Success,
+                    None,
+                    json!({
+                        "episodes_forgotten": report.total(),
+                        "forgotten_by_type": by_type,
+                        "min_relevance": request.min_relevance,
+                        "max_age_seconds": request.max_age_seconds,
+                        "episode_types": request.episode_types,
+                        "bulk": true
+                    }),
+                );
+                agents.push(json!({
+                    "agent_id": agent_id,
+                    "forgotten": report.total(),
+                    "forgotten_by_type": by_type
+                }));
+            }
+            Err(error) => {
+                tracing::warn!("Bulk forget failed for agent {}: {}", agent_id, error);
+                failed += 1;
+                state.audit_service.log_memory_event(
+                    AuditEventType::MemoryForgotten,
+                    None,
+                    None,
+                    &agent_id,
+                    AuditResult::Error,
+                    None,
+                    json!({"error": error, "bulk": true}),
+                );
+                agents.push(json!({"agent_id": agent_id, "error": error}));
+            }
         }
     }
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "agents": agents,
+            "count": agents.len(),
+            "failed": failed,
+            "forgotten": total_forgotten,
+            "min_relevance": request.min_relevance,
+            "max_age_seconds": request.max_age_seconds,
+            "episode_types": request.episode_types
+        })),
+    )
 }
 
-/// Update user roles (Admin only)
-async fn user_update_roles(
+// ==================== Graph Checkpoints ====================
+
+#[derive(Debug, Deserialize)]
+struct CheckpointRequest {
+    /// Directory of the checkpoint, relative to the server's backup directory
+    path: String,
+}
+
+/// Resolve a checkpoint path under `backup_dir`
+///
+/// Only relative paths that stay inside the backup directory are accepted:
+/// absolute paths and `.` or `..` components are rejected, and so are paths
+/// leading out of it through a symlink. The backup directory is created if
+/// missing.
+fn resolve_backup_path(backup_dir: &std::path::Path, path: &str) -> Result<std::path::PathBuf, String> {
+    use std::path::Component;
+
+    let relative = std::path::Path::new(path);
+    let mut components = relative.components().peekable();
+    if components.peek().is_none() {
+        return Err("Checkpoint path must not be empty".to_string());
+    }
+    if !components.all(|component| matches!(component, Component::Normal(_))) {
+        return Err(format!(
+            "Checkpoint path '{}' must be relative to the backup directory, without '.' or '..'",
+            path
+        ));
+    }
+
+    let unavailable = |e: std::io::Error| format!("Backup directory is not available: {}", e);
+    std::fs::create_dir_all(backup_dir).map_err(unavailable)?;
+    let backup_dir = backup_dir.canonicalize().map_err(unavailable)?;
+    let resolved = backup_dir.join(relative);
+    // The deepest existing ancestor shows where symlinks on the path lead
+    let existing = resolved
+        .ancestors()
+        .find(|ancestor| ancestor.symlink_metadata().is_ok())
+        .unwrap_or(&backup_dir)
+        .canonicalize()
+        .map_err(|e| format!("Checkpoint path '{}' cannot be resolved: {}", path, e))?;
+    if !existing.starts_with(&backup_dir) {
+        return Err(format!("Checkpoint path '{}' leads outside the backup directory", path));
+    }
+    Ok(resolved)
+}
+
+/// Create a point-in-time checkpoint of a graph (Admin only)
+///
+/// POST /admin/graphs/:name/checkpoint
+///
+/// The checkpoint is written to a new directory under the server's backup
+/// directory without stopping reads or writes; it hard-links the storage
+/// files, so it is cheap however large the graph. It is a checkpoint of the
+/// whole database: besides every other graph, it holds the user accounts
+/// with their password hashes, the token blacklist and agent memory.
+async fn checkpoint_graph(
     State(state): State<AppState>,
+    Path(graph_name): Path<String>,
     headers: axum::http::HeaderMap,
-    axum::extract::Path(user_id_str): axum::extract::Path<String>,
-    Json(request): Json<UpdateRolesRequest>,
+    Json(request): Json<CheckpointRequest>,
 ) -> impl IntoResponse {
-    use uuid::Uuid;
-    use crate::security::UserId;
+    if let Err(status) = extract_admin_from_token(&headers, &state) {
+        return (
+            status,
+            Json(json!({"error": "Unauthorized: Admin access required"})),
+        );
+    }
+    let path = match resolve_backup_path(&state.backup_dir, &request.path) {
+        Ok(path) => path,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))),
+    };
+    if path.exists() {
+        return (
+            StatusCode::CONFLICT,
+            Json(json!({"error": format!("'{}' already exists", request.path)})),
+        );
+    }
 
-    // Require admin privileges
-    let admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
+    let database = state.database.clone();
+    let name = graph_name.clone();
+    let checkpoint = move || {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
         }
+        database.checkpoint(&name, &path)
     };
+    match tokio::task::spawn_blocking(checkpoint).await {
+        Ok(Ok(())) => (
+            StatusCode::CREATED,
+            Json(json!({"graph": graph_name, "path": request.path})),
+        ),
+        Ok(Err(e @ qilbee_core::Error::GraphNotFound(_))) => {
+            (StatusCode::NOT_FOUND, Json(json!({"error": e.to_string()})))
+        }
+        Ok(Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": e.to_string()})),
+        ),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Checkpoint failed: {}", e)})),
+        ),
+    }
+}
 
-    // Get admin username for audit logging
-    let admin_username = state.user_service.get_user(&admin_id)
-        .map(|u| u.username)
-        .unwrap_or_else(|| "unknown".to_string());
+/// Restore a checkpointed graph as a new graph (Admin only)
+///
+/// POST /admin/graphs/:name/restore
+///
+/// Copies the graph of the checkpoint at `path`, relative to the server's
+/// backup directory, into the graph `name`, which must not exist yet, and
+/// responds like a bulk import. If the restore fails, the partly restored
+/// graph is deleted again. The checkpoint is left unchanged.
+async fn restore_graph(
+    State(state): State<AppState>,
+    Path(graph_name): Path<String>,
+    headers: axum::http::HeaderMap,
+    Json(request): Json<CheckpointRequest>,
+) -> impl IntoResponse {
+    if let Err(status) = extract_admin_from_token(&headers, &state) {
+        return (
+            status,
+            Json(json!({"error": "Unauthorized: Admin access required"})),
+        );
+    }
 
-    // Parse target user ID
-    let target_uuid = match Uuid::parse_str(&user_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid user ID format"})),
-            )
-        }
+    let path = match resolve_backup_path(&state.backup_dir, &request.path) {
+        Ok(path) => path,
+        Err(e) => return (StatusCode::BAD_REQUEST, Json(json!({"error": e}))),
     };
-    let target_id = UserId(target_uuid);
 
-    // Get existing user
-    let mut user = match state.user_service.get_user(&target_id) {
-        Some(u) => u,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "User not found"})),
-            )
+    let database = state.database.clone();
+    let restore = tokio::task::spawn_blocking(move || {
+        let mut report = ImportReport::default();
+        let result = qilbee_graph::Database::restore_from(&path).and_then(|source| {
+            crate::import::restore_graph(&database, &graph_name, &source, DEFAULT_IMPORT_BATCH_SIZE, &mut report)
+        });
+        (report, result)
+    })
+    .await;
+
+    match restore {
+        Ok((report, Ok(()))) => {
+            let status = if report.errors.is_empty() {
+                StatusCode::CREATED
+            } else {
+                StatusCode::MULTI_STATUS
+            };
+            (status, Json(json!(report)))
         }
-    };
+        Ok((report, Err(e))) => {
+            let status = match e {
+                qilbee_core::Error::ValidationError(_) => StatusCode::BAD_REQUEST,
+                qilbee_core::Error::InvalidGraphOperation(_) => StatusCode::CONFLICT,
+                _ => quota_error_status(&e, StatusCode::INTERNAL_SERVER_ERROR),
+            };
+            let mut body = json!(report);
+            body["error"] = json!(e.to_string());
+            (status, Json(body))
+        }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({"error": format!("Restore failed: {}", e)})),
+        ),
+    }
+}
 
-    // Track old roles for audit log
-    let old_roles = user.roles.clone();
-    let new_roles = request.roles.clone();
+// ==================== LLM Configuration ====================
 
-    // Update roles
-    user.roles = request.roles;
+/// Request body for updating LLM configuration
+#[derive(Debug, Deserialize)]
+struct LLMConfigUpdateRequest {
+    /// Provider type: "openai" or "mock"
+    provider: String,
+    /// API key (required for OpenAI)
+    api_key: Option<String>,
+    /// Model name (optional, defaults based on provider)
+    model: Option<String>,
+    /// Temperature (optional, 0.0-2.0)
+    temperature: Option<f32>,
+    /// Max tokens (optional)
+    max_tokens: Option<u32>,
+}
 
-    match state.user_service.update_user(user.clone()) {
-        Ok(_) => {
-            // Log role change as RoleAssigned event (covers both assignment and removal)
-            state.audit_service.log_user_event(
-                AuditEventType::RoleAssigned,
-                &admin_id.0.to_string(),
-                &admin_username,
-                &target_id.0.to_string(),
-                AuditResult::Success,
-                None,
-                serde_json::json!({
-                    "target_username": user.username,
-                    "old_roles": old_roles,
-                    "new_roles": new_roles
-                }),
-            );
+/// Response for LLM status
+#[derive(Debug, Serialize)]
+struct LLMStatusResponse {
+    configured: bool,
+    provider: String,
+    model: String,
+}
 
-            let response = UserResponse {
-                id: user.id.0.to_string(),
-                username: user.username,
-                email: user.email,
-                roles: user.roles,
-                is_active: user.is_active,
-                created_at: user.created_at.to_rfc3339(),
-                updated_at: user.updated_at.to_rfc3339(),
-                last_login: user.last_login.map(|dt| dt.to_rfc3339()),
+/// Get current LLM configuration status
+async fn llm_status(State(state): State<AppState>) -> impl IntoResponse {
+    let is_configured = state.llm_service.is_configured().await;
+    let model_name = state.llm_service.model_name().await;
+    let config = state.llm_service.get_config().await;
+
+    let provider_name = match config.provider {
+        LLMProviderType::Mock => "mock",
+        LLMProviderType::OpenAI => "openai",
+    };
+
+    (
+        StatusCode::OK,
+        Json(json!({
+            "configured": is_configured,
+            "provider": provider_name,
+            "model": model_name,
+            "temperature": config.temperature,
+            "max_tokens": config.max_tokens,
+            "circuit_breaker": state.llm_service.circuit_breaker_status().await
+        })),
+    )
+}
+
+/// Update LLM configuration at runtime
+async fn llm_update_config(
+    State(state): State<AppState>,
+    Json(request): Json<LLMConfigUpdateRequest>,
+) -> impl IntoResponse {
+    // Build new configuration based on provider type
+    let new_config = match request.provider.to_lowercase().as_str() {
+        "openai" => {
+            let api_key = match request.api_key {
+                Some(key) if !key.is_empty() => key,
+                _ => {
+                    return (
+                        StatusCode::BAD_REQUEST,
+                        Json(json!({
+                            "error": "API key is required for OpenAI provider"
+                        })),
+                    )
+                }
             };
-            (StatusCode::OK, Json(json!(response)))
+
+            // Use openai_mini as base and customize
+            let mut config = LLMConfig::openai_mini(&api_key);
+
+            // Override model if specified
+            if let Some(ref model) = request.model {
+                config.model = model.clone();
+            }
+
+            if let Some(temp) = request.temperature {
+                config.temperature = temp;
+            }
+            if let Some(max_tokens) = request.max_tokens {
+                config.max_tokens = max_tokens;
+            }
+
+            config
         }
-        Err(e) => {
-            state.audit_service.log_user_event(
-                AuditEventType::RoleAssigned,
-                &admin_id.0.to_string(),
-                &admin_username,
-                &target_id.0.to_string(),
-                AuditResult::Failure,
-                None,
-                serde_json::json!({"error": e.to_string()}),
-            );
+        "mock" => LLMConfig::mock(),
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(json!({
+                    "error": format!("Unknown provider type: '{}'. Supported: 'openai', 'mock'", request.provider)
+                })),
+            )
+        }
+    };
+
+    // Update the LLM service configuration
+    match state.llm_service.update_config(new_config).await {
+        Ok(()) => {
+            let model_name = state.llm_service.model_name().await;
+            let is_configured = state.llm_service.is_configured().await;
 
             (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(json!({"error": format!("Failed to update user roles: {}", e)})),
+                StatusCode::OK,
+                Json(json!({
+                    "success": true,
+                    "message": "LLM configuration updated successfully",
+                    "configured": is_configured,
+                    "provider": request.provider.to_lowercase(),
+                    "model": model_name
+                })),
             )
         }
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(json!({
+                "error": format!("Failed to update LLM configuration: {}", e)
+            })),
+        ),
     }
 }
 
-// ==================== Rate Limit Policy Management Endpoints ====================
+// ==================== Helper Functions ====================
 
-use crate::security::{RateLimitPolicy, PolicyId, EndpointType};
+/// Format episode content with intuitive field names based on episode type.
+/// For conversations: user_input, agent_response
+/// For observations: observation
+/// For task execution: action, result
+fn format_episode_content(episode: &Episode) -> Value {
+    let mut content_map = serde_json::Map::new();
 
-/// Request/response DTOs for rate limit policy management
-#[derive(Debug, Deserialize)]
-struct CreateRateLimitPolicyRequest {
-    name: String,
-    endpoint_type: EndpointType,
-    max_requests: u32,
-    window_secs: u64,
-    enabled: bool,
-}
+    match &episode.episode_type {
+        EpisodeType::Conversation => {
+            content_map.insert("user_input".to_string(), json!(episode.content.primary));
+            if let Some(ref secondary) = episode.content.secondary {
+                content_map.insert("agent_response".to_string(), json!(secondary));
+            }
+        }
+        EpisodeType::Observation => {
+            content_map.insert("observation".to_string(), json!(episode.content.primary));
+            if let Some(ref secondary) = episode.content.secondary {
+                content_map.insert("details".to_string(), json!(secondary));
+            }
+        }
+        EpisodeType::TaskExecution => {
+            content_map.insert("action".to_string(), json!(episode.content.primary));
+            if let Some(ref secondary) = episode.content.secondary {
+                content_map.insert("result".to_string(), json!(secondary));
+            }
+        }
+        _ => {
+            // For custom/other types, use generic primary/secondary
+            content_map.insert("primary".to_string(), json!(episode.content.primary));
+            if let Some(ref secondary) = episode.content.secondary {
+                content_map.insert("secondary".to_string(), json!(secondary));
+            }
+            insert_custom_type(&mut content_map, episode);
+        }
+    }
 
-#[derive(Debug, Deserialize)]
-struct UpdateRateLimitPolicyRequest {
-    name: Option<String>,
-    max_requests: Option<u32>,
-    window_secs: Option<u64>,
-    enabled: Option<bool>,
-}
+    // Include context if present
+    if let Some(ref context) = episode.content.context {
+        content_map.insert("context".to_string(), json!(context));
+    }
 
-#[derive(Debug, Serialize)]
-struct RateLimitPolicyResponse {
-    id: String,
-    name: String,
-    endpoint_type: EndpointType,
-    max_requests: u32,
-    window_secs: u64,
-    enabled: bool,
-    created_at: String,
-    updated_at: String,
-    created_by: String,
+    Value::Object(content_map)
 }
 
-#[derive(Debug, Serialize)]
-struct ListRateLimitPoliciesResponse {
-    policies: Vec<RateLimitPolicyResponse>,
+/// Format episode content for search results as plain primary/secondary text
+fn format_search_content(episode: &Episode) -> Value {
+    let mut content_map = serde_json::Map::new();
+    content_map.insert("primary".to_string(), json!(episode.content.primary));
+    content_map.insert("secondary".to_string(), json!(episode.content.secondary));
+    insert_custom_type(&mut content_map, episode);
+    Value::Object(content_map)
 }
 
-impl From<RateLimitPolicy> for RateLimitPolicyResponse {
-    fn from(policy: RateLimitPolicy) -> Self {
-        Self {
-            id: policy.id.0.to_string(),
-            name: policy.name,
-            endpoint_type: policy.endpoint_type,
-            max_requests: policy.max_requests,
-            window_secs: policy.window_secs,
-            enabled: policy.enabled,
-            created_at: policy.created_at.to_rfc3339(),
-            updated_at: policy.updated_at.to_rfc3339(),
-            created_by: policy.created_by,
-        }
+/// Name a custom episode's kind in its content, so clients can tell custom
+/// episode types apart without parsing `episodeType`
+fn insert_custom_type(content_map: &mut serde_json::Map<String, Value>, episode: &Episode) {
+    if let EpisodeType::Custom(name) = &episode.episode_type {
+        content_map.insert("type".to_string(), json!(name));
     }
 }
 
-/// Create a new rate limit policy (Admin only)
-async fn rate_limit_create(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Json(request): Json<CreateRateLimitPolicyRequest>,
-) -> impl IntoResponse {
-    // Require admin privileges
-    let admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
+fn json_to_property_value(value: &Value) -> Option<PropertyValue> {
+    match value {
+        Value::Null => Some(PropertyValue::Null),
+        Value::Bool(b) => Some(PropertyValue::Boolean(*b)),
+        Value::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                Some(PropertyValue::Integer(i))
+            } else if let Some(f) = n.as_f64() {
+                Some(PropertyValue::Float(f))
+            } else {
+                None
+            }
         }
-    };
-
-    // Create policy
-    let policy = RateLimitPolicy {
-        id: PolicyId::new(),
-        name: request.name,
-        endpoint_type: request.endpoint_type,
-        max_requests: request.max_requests,
-        window_secs: request.window_secs,
-        enabled: request.enabled,
-        created_at: chrono::Utc::now(),
-        updated_at: chrono::Utc::now(),
-        created_by: admin_id.0.to_string(),
-    };
-
-    let policy_id = state.rate_limit_service.create_policy(policy.clone());
-
-    // Get the stored policy to return the correct ID
-    let stored_policy = state.rate_limit_service.get_policy(policy_id).unwrap();
-    let response = RateLimitPolicyResponse::from(stored_policy);
-
-    (StatusCode::CREATED, Json(json!(response)))
-}
-
-/// List all rate limit policies (Admin only)
-async fn rate_limit_list(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-) -> impl IntoResponse {
-    // Require admin privileges
-    let _admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
+        Value::String(s) => Some(PropertyValue::String(s.clone())),
+        Value::Array(arr) => {
+            let values: Vec<_> = arr.iter().filter_map(json_to_property_value).collect();
+            Some(PropertyValue::Array(values))
         }
-    };
-
-    let policies = state.rate_limit_service.list_policies();
-    let policy_responses: Vec<RateLimitPolicyResponse> = policies
-        .into_iter()
-        .map(RateLimitPolicyResponse::from)
-        .collect();
-
-    (
-        StatusCode::OK,
-        Json(json!(ListRateLimitPoliciesResponse { policies: policy_responses })),
-    )
+        Value::Object(obj) => {
+            let mut map = HashMap::new();
+            for (k, v) in obj {
+                if let Some(prop_val) = json_to_property_value(v) {
+                    map.insert(k.clone(), prop_val);
+                }
+            }
+            Some(PropertyValue::Map(map))
+        }
+    }
 }
 
-/// Get a specific rate limit policy (Admin only)
-async fn rate_limit_get(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    axum::extract::Path(policy_id_str): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    use uuid::Uuid;
-
-    // Require admin privileges
-    let _admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
+pub(crate) fn property_value_to_json(value: &PropertyValue) -> Value {
+    match value {
+        PropertyValue::Null => Value::Null,
+        PropertyValue::Boolean(b) => Value::Bool(*b),
+        PropertyValue::Integer(i) => json!(*i),
+        PropertyValue::Float(f) => json!(*f),
+        PropertyValue::String(s) => Value::String(s.clone()),
+        PropertyValue::Array(list) => Value::Array(list.iter().map(property_value_to_json).collect()),
+        PropertyValue::Map(map) => {
+            let mut obj = serde_json::Map::new();
+            for (k, v) in map.iter() {
+                obj.insert(k.clone(), property_value_to_json(v));
+            }
+            Value::Object(obj)
         }
-    };
-
-    // Parse policy ID
-    let policy_uuid = match Uuid::parse_str(&policy_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid policy ID format"})),
-            )
+        // Handle temporal types - convert to JSON strings/numbers for now
+        PropertyValue::Date(d) => json!(*d),
+        PropertyValue::Time(t) => json!(*t),
+        PropertyValue::DateTime(dt) => json!(*dt),
+        PropertyValue::Duration(dur) => json!(*dur),
+        PropertyValue::Bytes(b) => {
+            // Encode bytes as base64 string
+            Value::String(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, b))
+        }
+        PropertyValue::Point2D { x, y, srid } => {
+            json!({"x": x, "y": y, "srid": srid})
+        }
+        PropertyValue::Point3D { x, y, z, srid } => {
+            json!({"x": x, "y": y, "z": z, "srid": srid})
         }
-    };
-    let policy_id = PolicyId(policy_uuid);
+    }
+}
 
-    match state.rate_limit_service.get_policy(policy_id) {
-        Some(policy) => {
-            let response = RateLimitPolicyResponse::from(policy);
-            (StatusCode::OK, Json(json!(response)))
+pub(crate) fn json_map_to_property(map: &HashMap<String, Value>) -> Property {
+    let mut props = Property::new();
+    for (k, v) in map {
+        if let Some(prop_val) = json_to_property_value(v) {
+            props.set(k.clone(), prop_val);
         }
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(json!({"error": "Rate limit policy not found"})),
-        ),
     }
+    props
 }
 
-/// Update a rate limit policy (Admin only)
-async fn rate_limit_update(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    axum::extract::Path(policy_id_str): axum::extract::Path<String>,
-    Json(request): Json<UpdateRateLimitPolicyRequest>,
-) -> impl IntoResponse {
-    use uuid::Uuid;
-
-    // Require admin privileges
-    let _admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
-        }
-    };
+pub(crate) fn property_to_json_map(props: &Property) -> HashMap<String, Value> {
+    let mut result = HashMap::new();
+    for (k, v) in props.iter() {
+        result.insert(k.clone(), property_value_to_json(v));
+    }
+    result
+}
 
-    // Parse policy ID
-    let policy_uuid = match Uuid::parse_str(&policy_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid policy ID format"})),
-            )
-        }
-    };
-    let policy_id = PolicyId(policy_uuid);
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qilbee_query::{ExecutionStats, QueryResult, QueryResultStream};
 
-    // Get existing policy
-    let mut policy = match state.rate_limit_service.get_policy(policy_id) {
-        Some(p) => p,
-        None => {
-            return (
-                StatusCode::NOT_FOUND,
-                Json(json!({"error": "Rate limit policy not found"})),
-            )
-        }
-    };
+    /// Password of the bootstrap admin in tests
+    const ADMIN_PASSWORD: &str = "SecureAdmin@123!";
 
-    // Update fields
-    if let Some(name) = request.name {
-        policy.name = name;
+    /// Server configuration with a known admin password
+    fn test_config() -> ServerConfig {
+        ServerConfig::default().admin_password(ADMIN_PASSWORD)
     }
-    if let Some(max_requests) = request.max_requests {
-        policy.max_requests = max_requests;
+
+    fn integer_stream(count: i64, chunk_size: usize) -> QueryResultStream {
+        let result = QueryResult {
+            columns: vec!["n".to_string()],
+            rows: (0..count).map(|i| vec![PropertyValue::Integer(i)]).collect(),
+            stats: ExecutionStats::default(),
+        };
+        QueryResultStream::new(result, chunk_size)
     }
-    if let Some(window_secs) = request.window_secs {
-        policy.window_secs = window_secs;
+
+    #[test]
+    fn test_query_stream_body_chunks() {
+        let chunks: Vec<_> = query_stream_body_chunks(ReportedStream::new(integer_stream(2500, 1000), |_| {}), None)
+            .map(Result::unwrap)
+            .collect();
+
+        // Envelope prefix, three row chunks, stats suffix
+        assert_eq!(chunks.len(), 5);
+
+        let body: Vec<u8> = chunks.iter().flat_map(|c| c.iter().copied()).collect();
+        let parsed: Value = serde_json::from_slice(&body).unwrap();
+        let results = parsed["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2500);
+        assert_eq!(results[0]["n"], 0);
+        assert_eq!(results[2499]["n"], 2499);
+        assert_eq!(parsed["stats"]["nodesCreated"], 0);
     }
-    if let Some(enabled) = request.enabled {
-        policy.enabled = enabled;
+
+    #[test]
+    fn test_large_results_are_streamed() {
+        use axum::body::HttpBody;
+
+        let default_limit = crate::config::ServerConfig::default().default_query_limit.unwrap();
+        assert!(QUERY_STREAMING_THRESHOLD < default_limit);
+
+        let rows = QUERY_STREAMING_THRESHOLD as i64;
+        let buffered = query_stream_response(integer_stream(rows, 1000), QueryResultFormat::Rows, None, |_| {});
+        assert!(buffered.body().size_hint().exact().is_some());
+
+        // A result capped at the default limit is over the threshold
+        let rows = default_limit as i64;
+        let streamed = query_stream_response(integer_stream(rows, 1000), QueryResultFormat::Rows, Some(default_limit), |_| {});
+        assert!(streamed.body().size_hint().exact().is_none());
     }
 
-    match state.rate_limit_service.update_policy(policy_id, policy.clone()) {
-        Some(_) => {
-            let response = RateLimitPolicyResponse::from(policy);
-            (StatusCode::OK, Json(json!(response)))
+    #[test]
+    fn test_query_result_formats_are_equivalent() {
+        let result = QueryResult {
+            columns: vec!["name".to_string(), "age".to_string()],
+            rows: vec![
+                vec![PropertyValue::String("Alice".to_string()), PropertyValue::Integer(30)],
+                vec![PropertyValue::String("Bob".to_string()), PropertyValue::Null],
+            ],
+            stats: ExecutionStats::default(),
+        };
+
+        let rows = query_stream_to_rows_json(&mut QueryResultStream::new(result.clone(), 1)).unwrap();
+        let columnar = query_stream_to_columns_json(&mut QueryResultStream::new(result, 1)).unwrap();
+
+        assert_eq!(columnar["columns"], json!(["name", "age"]));
+        assert_eq!(columnar["data"]["name"], json!(["Alice", "Bob"]));
+        assert_eq!(columnar["data"]["age"], json!([30, null]));
+        assert_eq!(columnar["stats"], rows["stats"]);
+
+        // Every cell of the row format matches the columnar one
+        let results = rows["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        for (i, row) in results.iter().enumerate() {
+            for column in ["name", "age"] {
+                assert_eq!(row[column], columnar["data"][column][i]);
+            }
         }
-        None => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({"error": "Failed to update rate limit policy"})),
-        ),
     }
-}
 
-/// Delete a rate limit policy (Admin only)
-async fn rate_limit_delete(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    axum::extract::Path(policy_id_str): axum::extract::Path<String>,
-) -> impl IntoResponse {
-    use uuid::Uuid;
+    #[test]
+    fn test_query_format_param() {
+        let parse = |uri: &str| {
+            AxumQuery::<QueryFormatParams>::try_from_uri(&uri.parse().unwrap()).map(|q| q.0.format)
+        };
+        assert_eq!(parse("/graphs/g/query").unwrap(), QueryResultFormat::Rows);
+        assert_eq!(parse("/graphs/g/query?format=rows").unwrap(), QueryResultFormat::Rows);
+        assert_eq!(parse("/graphs/g/query?format=columns").unwrap(), QueryResultFormat::Columns);
+        assert_eq!(parse("/graphs/g/query?format=arrow").unwrap(), QueryResultFormat::Arrow);
+        assert!(parse("/graphs/g/query?format=xml").is_err());
+    }
 
-    // Require admin privileges
-    let _admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
-        }
-    };
+    #[test]
+    fn test_estimate_query() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        for i in 0..50 {
+            let mut props = Property::new();
+            props.set("name", format!("Person{}", i));
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        graph.create_index(qilbee_graph::Index::range("person_name", "Person", "name")).unwrap();
+
+        let indexed = estimate_query_json(
+            &graph,
+            "MATCH (p:Person) WHERE p.name = 'Person7' RETURN p.name AS name",
+        )
+        .unwrap();
+        let scan = estimate_query_json(&graph, "MATCH (p:Person) RETURN p.name AS name").unwrap();
+
+        assert_eq!(indexed["usesIndex"], true);
+        assert_eq!(scan["usesIndex"], false);
+        assert_eq!(indexed["estimatedRows"], 1);
+        assert_eq!(scan["estimatedRows"], 50);
+        assert!(indexed["estimatedCost"].as_f64().unwrap() < scan["estimatedCost"].as_f64().unwrap());
+
+        // Estimating never executes the query
+        assert!(estimate_query_json(&graph, "MATCH (p:Person) RETURN").is_err());
+        assert_eq!(graph.node_count().unwrap(), 50);
+    }
 
-    // Parse policy ID
-    let policy_uuid = match Uuid::parse_str(&policy_id_str) {
-        Ok(uuid) => uuid,
-        Err(_) => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({"error": "Invalid policy ID format"})),
-            )
+    #[tokio::test]
+    async fn test_default_query_limit() {
+        use qilbee_query::{parse_simple, QueryExecutor, QueryPlanner, DEFAULT_STREAM_CHUNK_SIZE};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        for i in 0..30 {
+            let mut props = Property::new();
+            props.set("name", format!("Person{}", i));
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        let executor = QueryExecutor::new(Arc::new(graph));
+        let planner = QueryPlanner::new().with_default_limit(10);
+
+        let run = |cypher: &str| {
+            let plan = planner.plan(&parse_simple(cypher).unwrap()).unwrap();
+            let stream = executor
+                .execute_stream(&plan, &HashMap::new(), DEFAULT_STREAM_CHUNK_SIZE)
+                .unwrap();
+            query_stream_response(stream, QueryResultFormat::Rows, plan.default_limit, |_| {})
+        };
+        let body = |response: axum::response::Response| async {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&bytes).unwrap()
+        };
+
+        // Unlimited reads are capped and flagged
+        let capped = body(run("MATCH (p:Person) RETURN p.name AS name")).await;
+        assert_eq!(capped["results"].as_array().unwrap().len(), 10);
+        assert_eq!(capped["defaultLimit"]["limit"], 10);
+        assert_eq!(capped["defaultLimit"]["limitReached"], true);
+        assert!(capped["defaultLimit"]["pagination"].as_str().unwrap().contains("SKIP 10 LIMIT 10"));
+
+        // An explicit LIMIT overrides the default
+        let explicit = body(run("MATCH (p:Person) RETURN p.name AS name LIMIT 25")).await;
+        assert_eq!(explicit["results"].as_array().unwrap().len(), 25);
+        assert!(explicit.get("defaultLimit").is_none());
+    }
+
+    #[test]
+    fn test_query_ws_frames() {
+        let mut stream = integer_stream(2500, 1000);
+        let columns = stream.columns().to_vec();
+        let frames: Vec<Value> = stream
+            .by_ref()
+            .map(|chunk| query_rows_ws_frame(&columns, &chunk.unwrap()))
+            .collect();
+
+        // Three row chunks, then stats
+        assert_eq!(frames.len(), 3);
+        assert!(frames.iter().all(|f| f["type"] == "rows"));
+        assert_eq!(frames[0]["rows"].as_array().unwrap().len(), 1000);
+        assert_eq!(frames[2]["rows"].as_array().unwrap().len(), 500);
+        assert_eq!(frames[2]["rows"][499]["n"], 2499);
+
+        let stats = query_stats_ws_frame(stream.stats(), None);
+        assert_eq!(stats["type"], "stats");
+        assert_eq!(stats["stats"]["nodesCreated"], 0);
+        assert!(stats.get("defaultLimit").is_none());
+
+        // The default limit is reached once that many rows were returned
+        let stats = query_stats_ws_frame(&ExecutionStats::default(), Some(10));
+        assert_eq!(stats["defaultLimit"]["limitReached"], false);
+        let returned = ExecutionStats { rows_returned: 10, ..Default::default() };
+        assert_eq!(query_stats_ws_frame(&returned, Some(10))["defaultLimit"]["limitReached"], true);
+    }
+
+    #[tokio::test]
+    async fn test_query_websocket() {
+        use futures::{SinkExt, StreamExt};
+        use tokio_tungstenite::tungstenite::Message;
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("social").unwrap();
+        for i in 0..3 {
+            let mut props = Property::new();
+            props.set("name", format!("Person{}", i));
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        let router = create_router_with_config(Arc::new(db), &test_config());
+
+        let login = axum::http::Request::post("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(
+                json!({"username": "admin", "password": ADMIN_PASSWORD}).to_string(),
+            ))
+            .unwrap();
+        let response = router.clone().oneshot(login).await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let token = serde_json::from_slice::<Value>(&bytes).unwrap()["access_token"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        let query = Message::Text(
+            json!({"type": "query", "cypher": "MATCH (p:Person) RETURN p.name AS name"}).to_string(),
+        );
+        let next_frame = |frame: Option<Result<Message, _>>| match frame {
+            Some(Ok(Message::Text(text))) => serde_json::from_str::<Value>(&text).unwrap(),
+            other => panic!("expected text frame, got {:?}", other),
+        };
+
+        // Queries are refused without credentials
+        let url = format!("ws://{}/graphs/social/query/ws", addr);
+        let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        socket.send(query.clone()).await.unwrap();
+        assert_eq!(next_frame(socket.next().await)["type"], "error");
+        assert!(matches!(socket.next().await, Some(Ok(Message::Close(Some(_))))));
+
+        // Token as a query parameter
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("{}?token={}", url, token))
+            .await
+            .unwrap();
+        socket.send(query.clone()).await.unwrap();
+        let rows = next_frame(socket.next().await);
+        assert_eq!(rows["type"], "rows");
+        assert_eq!(rows["rows"].as_array().unwrap().len(), 3);
+        assert_eq!(next_frame(socket.next().await)["type"], "stats");
+
+        // Token in the first frame, then a failing query keeps the socket open
+        let (mut socket, _) = tokio_tungstenite::connect_async(&url).await.unwrap();
+        socket
+            .send(Message::Text(json!({"type": "auth", "token": token}).to_string()))
+            .await
+            .unwrap();
+        assert_eq!(next_frame(socket.next().await)["type"], "authenticated");
+        socket
+            .send(Message::Text(json!({"type": "query", "cypher": "MATCH (p:Person RETURN p"}).to_string()))
+            .await
+            .unwrap();
+        assert_eq!(next_frame(socket.next().await)["type"], "error");
+        socket.send(query).await.unwrap();
+        assert_eq!(next_frame(socket.next().await)["type"], "rows");
+        assert_eq!(next_frame(socket.next().await)["type"], "stats");
+    }
+
+    #[test]
+    fn test_ws_client_frames() {
+        let frame: WsClientFrame = serde_json::from_value(json!({
+            "type": "query",
+            "cypher": "MATCH (n) RETURN n",
+            "parameters": {"x": 1}
+        }))
+        .unwrap();
+        match frame {
+            WsClientFrame::Query(request) => {
+                assert_eq!(request.cypher, "MATCH (n) RETURN n");
+                let params = query_parameters(request.parameters.as_ref());
+                assert_eq!(params.get("x"), Some(&PropertyValue::Integer(1)));
+            }
+            other => panic!("expected query frame, got {:?}", other),
         }
-    };
-    let policy_id = PolicyId(policy_uuid);
 
-    match state.rate_limit_service.delete_policy(policy_id) {
-        Some(_) => (
-            StatusCode::OK,
-            Json(json!({"success": true, "message": "Rate limit policy deleted"})),
-        ),
-        None => (
-            StatusCode::NOT_FOUND,
-            Json(json!({"error": "Rate limit policy not found"})),
-        ),
+        let frame: WsClientFrame =
+            serde_json::from_value(json!({"type": "auth", "apiKey": "qilbee_key"})).unwrap();
+        assert!(matches!(frame, WsClientFrame::Auth(WsAuthParams { token: None, api_key: Some(_) })));
+
+        assert!(serde_json::from_value::<WsClientFrame>(json!({"cypher": "MATCH (n) RETURN n"})).is_err());
     }
-}
 
-// ==================== Audit Log Query Endpoint ====================
+    #[test]
+    fn test_query_stream_body_empty() {
+        let body: Vec<u8> = query_stream_body_chunks(ReportedStream::new(integer_stream(0, 1000), |_| {}), None)
+            .flat_map(|c| c.unwrap().to_vec())
+            .collect();
+        let parsed: Value = serde_json::from_slice(&body).unwrap();
+        assert!(parsed["results"].as_array().unwrap().is_empty());
+    }
 
-use crate::security::AuditFilter;
+    async fn call(router: &Router, request: axum::http::Request<axum::body::Body>) -> (StatusCode, Value) {
+        use tower::ServiceExt;
 
-/// Query parameters for audit log filtering
-#[derive(Debug, Deserialize)]
-struct AuditLogsQueryParams {
-    user_id: Option<String>,
-    username: Option<String>,
-    event_type: Option<String>,
-    action: Option<String>,
-    result: Option<String>,
-    ip_address: Option<String>,
-    start_time: Option<String>,
-    end_time: Option<String>,
-    limit: Option<usize>,
-}
+        let response = router.clone().oneshot(request).await.unwrap();
+        let status = response.status();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&bytes).unwrap())
+    }
 
-/// Response format for audit events
-#[derive(Debug, Serialize)]
-struct AuditEventResponse {
-    event_id: String,
-    event_type: String,
-    timestamp: String,
-    user_id: Option<String>,
-    username: Option<String>,
-    action: String,
-    resource: String,
-    result: String,
-    ip_address: Option<String>,
-    user_agent: Option<String>,
-    metadata: serde_json::Value,
-    transaction_time: String,
-}
+    async fn login(router: &Router) -> String {
+        let request = axum::http::Request::post("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(
+                json!({"username": "admin", "password": ADMIN_PASSWORD}).to_string(),
+            ))
+            .unwrap();
+        call(router, request).await.1["access_token"].as_str().unwrap().to_string()
+    }
 
-/// Query audit logs (Admin only)
-async fn audit_logs_query(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    AxumQuery(params): AxumQuery<AuditLogsQueryParams>,
-) -> impl IntoResponse {
-    // Require admin privileges
-    let _admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
-        }
-    };
+    async fn memory_request(
+        router: &Router,
+        token: &str,
+        method: &str,
+        path: &str,
+        body: Value,
+    ) -> (StatusCode, Value) {
+        let request = axum::http::Request::builder()
+            .method(method)
+            .uri(format!("/memory/agent-1{}", path))
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::from(body.to_string()))
+            .unwrap();
+        call(router, request).await
+    }
 
-    // Build filter from query params
-    let mut filter = AuditFilter::new();
+    async fn store(router: &Router, token: &str, message: &str) {
+        let episode = json!({
+            "agentId": "agent-1",
+            "episodeType": "conversation",
+            "content": {"primary": message}
+        });
+        let (status, body) = memory_request(router, token, "POST", "/episodes", episode).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert!(body["episodeId"].is_string());
+    }
 
-    if let Some(user_id) = params.user_id {
-        filter = filter.user_id(user_id);
+    #[tokio::test]
+    async fn test_agent_memory_persistence() {
+        async fn recent(router: &Router, token: &str) -> Vec<Value> {
+            let request = axum::http::Request::get("/memory/agent-1/episodes/recent")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap();
+            call(router, request).await.1["episodes"].as_array().unwrap().clone()
+        }
+
+        // Episodes survive a restart
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        {
+            let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+            let router = create_router_with_config(Arc::new(db), &test_config());
+            let token = login(&router).await;
+            store(&router, &token, "Remember this").await;
+        }
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+        let token = login(&router).await;
+        let episodes = recent(&router, &token).await;
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0]["agentId"], "agent-1");
+
+        // With persistence disabled, agents are kept in memory only
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let config = test_config().disable_memory_persistence();
+        let router = create_router_with_config(db.clone(), &config);
+        let token = login(&router).await;
+        store(&router, &token, "Forget this").await;
+        assert_eq!(recent(&router, &token).await.len(), 1);
+        assert!(db.storage().scan_memory(&[]).unwrap().is_empty());
     }
-    if let Some(username) = params.username {
-        filter = filter.username(username);
+
+    #[tokio::test]
+    async fn test_token_blacklist_persistence() {
+        async fn status(router: &Router, token: &str) -> StatusCode {
+            let request = axum::http::Request::get("/memory/agent-1/episodes/recent")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap();
+            call(router, request).await.0
+        }
+
+        // Revocations are written to the database
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db.clone(), &test_config());
+        let token = login(&router).await;
+        let request = axum::http::Request::post("/api/v1/auth/revoke")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(json!({"token": token}).to_string()))
+            .unwrap();
+        assert_eq!(call(&router, request).await.0, StatusCode::OK);
+        assert_eq!(status(&router, &token).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(db.storage().scan_revoked_tokens().unwrap().len(), 1);
+
+        // With persistence disabled, nothing is written to the database
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let config = test_config().disable_token_blacklist_persistence();
+        let router = create_router_with_config(db.clone(), &config);
+        let token = login(&router).await;
+        let request = axum::http::Request::post("/api/v1/auth/revoke")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(json!({"token": token}).to_string()))
+            .unwrap();
+        assert_eq!(call(&router, request).await.0, StatusCode::OK);
+        assert!(db.storage().scan_revoked_tokens().unwrap().is_empty());
     }
-    if let Some(event_type_str) = params.event_type {
-        // Parse event type from string
-        let event_type = match event_type_str.as_str() {
-            "login" => Some(AuditEventType::Login),
-            "login_failed" => Some(AuditEventType::LoginFailed),
-            "logout" => Some(AuditEventType::Logout),
-            "token_refresh" => Some(AuditEventType::TokenRefresh),
-            "token_refresh_failed" => Some(AuditEventType::TokenRefreshFailed),
-            "api_key_created" => Some(AuditEventType::ApiKeyCreated),
-            "api_key_revoked" => Some(AuditEventType::ApiKeyRevoked),
-            "api_key_used" => Some(AuditEventType::ApiKeyUsed),
-            "api_key_validation_failed" => Some(AuditEventType::ApiKeyValidationFailed),
-            "user_created" => Some(AuditEventType::UserCreated),
-            "user_updated" => Some(AuditEventType::UserUpdated),
-            "user_deleted" => Some(AuditEventType::UserDeleted),
-            "user_password_changed" => Some(AuditEventType::UserPasswordChanged),
-            "role_assigned" => Some(AuditEventType::RoleAssigned),
-            "role_removed" => Some(AuditEventType::RoleRemoved),
-            "permission_denied" => Some(AuditEventType::PermissionDenied),
-            "access_granted" => Some(AuditEventType::AccessGranted),
-            "rate_limit_exceeded" => Some(AuditEventType::RateLimitExceeded),
-            "system_startup" => Some(AuditEventType::SystemStartup),
-            "system_shutdown" => Some(AuditEventType::SystemShutdown),
-            "configuration_changed" => Some(AuditEventType::ConfigurationChanged),
-            _ => None,
+
+    #[tokio::test]
+    async fn test_user_persistence() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (admin_token, revoked) = {
+            let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+            let router = create_router_with_config(Arc::new(db), &test_config());
+            let token = login(&router).await;
+            let create_user = axum::http::Request::post("/api/v1/users")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(
+                    json!({"username": "reader", "email": "reader@example.com", "password": "SecureReader@123!", "roles": ["Read"]})
+                        .to_string(),
+                ))
+                .unwrap();
+            assert_eq!(call(&router, create_user).await.0, StatusCode::CREATED);
+
+            let revoked = login(&router).await;
+            let request = axum::http::Request::post("/api/v1/auth/revoke")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(json!({"token": revoked}).to_string()))
+                .unwrap();
+            assert_eq!(call(&router, request).await.0, StatusCode::OK);
+            (token, revoked)
         };
-        if let Some(et) = event_type {
-            filter = filter.event_type(et);
-        }
+
+        // After a restart, created users can log in, existing tokens stay valid
+        // and revoked ones stay revoked
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+        let reader_login = axum::http::Request::post("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(
+                json!({"username": "reader", "password": "SecureReader@123!"}).to_string(),
+            ))
+            .unwrap();
+        assert_eq!(call(&router, reader_login).await.0, StatusCode::OK);
+
+        let list_users = |token: &str| {
+            axum::http::Request::get("/api/v1/users")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let (status, body) = call(&router, list_users(&admin_token)).await;
+        assert_eq!(status, StatusCode::OK);
+        // The default admin is not created again
+        assert_eq!(body["users"].as_array().unwrap().len(), 2);
+        assert_eq!(call(&router, list_users(&revoked)).await.0, StatusCode::UNAUTHORIZED);
     }
-    if let Some(action) = params.action {
-        filter = filter.action(action);
+
+    #[test]
+    fn test_bootstrap_admin() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+
+        // The first persistent run stores the configured admin
+        let users = UserService::with_storage(db.storage().clone()).unwrap();
+        bootstrap_admin(&users, &test_config()).unwrap();
+        assert!(users.authenticate("admin", ADMIN_PASSWORD).is_ok());
+        let users = UserService::with_storage(db.storage().clone()).unwrap();
+        assert!(users.has_admin());
+        bootstrap_admin(&users, &test_config()).unwrap();
+        assert_eq!(users.list_users().len(), 1);
+
+        // Without a configured password, one is generated
+        let users = UserService::new();
+        users
+            .create_user("reader".to_string(), "reader@example.com".to_string(), "SecureReader@123!")
+            .unwrap();
+        bootstrap_admin(&users, &ServerConfig::default()).unwrap();
+        assert!(users.has_admin());
+        assert!(users.authenticate("admin", ADMIN_PASSWORD).is_err());
+
+        // Creation errors are reported
+        let users = UserService::new();
+        users
+            .create_user("admin".to_string(), "other@example.com".to_string(), "SecureReader@123!")
+            .unwrap();
+        assert!(bootstrap_admin(&users, &test_config()).is_err());
     }
-    if let Some(result_str) = params.result {
-        let result = match result_str.as_str() {
-            "success" => Some(AuditResult::Success),
-            "failure" => Some(AuditResult::Failure),
-            "unauthorized" => Some(AuditResult::Unauthorized),
-            "forbidden" => Some(AuditResult::Forbidden),
-            "error" => Some(AuditResult::Error),
-            _ => None,
+
+    #[tokio::test]
+    async fn test_login_user_id() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+
+        let request = axum::http::Request::post("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(
+                json!({"username": "admin", "password": ADMIN_PASSWORD}).to_string(),
+            ))
+            .unwrap();
+        let (status, body) = call(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        let token = body["access_token"].as_str().unwrap();
+
+        let request = axum::http::Request::get("/api/v1/users")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let (_, users) = call(&router, request).await;
+        let admin = users["users"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|user| user["username"] == "admin")
+            .unwrap();
+        assert_eq!(body["user_id"], admin["id"]);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_rotation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+        let admin_token = login(&router).await;
+        let post = |path: &str, token: Option<&str>, body: Value| {
+            let mut request = axum::http::Request::post(path).header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            request.body(axum::body::Body::from(body.to_string())).unwrap()
         };
-        if let Some(r) = result {
-            filter = filter.result(r);
-        }
+
+        let create_user = post(
+            "/api/v1/users",
+            Some(&admin_token),
+            json!({"username": "reader", "email": "reader@example.com", "password": "SecureReader@123!", "roles": ["Read"]}),
+        );
+        assert_eq!(call(&router, create_user).await.0, StatusCode::CREATED);
+        let reader_login = post(
+            "/api/v1/auth/login",
+            None,
+            json!({"username": "reader", "password": "SecureReader@123!"}),
+        );
+        let (_, body) = call(&router, reader_login).await;
+        let first = body["refresh_token"].as_str().unwrap().to_string();
+
+        // Each refresh returns a new refresh token
+        let (status, body) = call(&router, post("/api/v1/auth/refresh", None, json!({"refresh_token": first}))).await;
+        assert_eq!(status, StatusCode::OK);
+        let second = body["refresh_token"].as_str().unwrap().to_string();
+        assert_ne!(first, second);
+        assert!(body["access_token"].is_string());
+
+        // Reusing a spent refresh token revokes the user's tokens and is audited
+        let (status, _) = call(&router, post("/api/v1/auth/refresh", None, json!({"refresh_token": first}))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let (status, _) = call(&router, post("/api/v1/auth/refresh", None, json!({"refresh_token": second}))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let request = axum::http::Request::get("/api/v1/audit-logs?event_type=security_incident")
+            .header("authorization", format!("Bearer {}", admin_token))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let (status, body) = call(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        let events = body["events"].as_array().unwrap();
+        assert_eq!(events.len(), 1, "{}", body);
+        assert_eq!(events[0]["username"], "reader");
+        assert_eq!(events[0]["action"], "refresh_token_reuse");
     }
-    if let Some(ip) = params.ip_address {
-        filter = filter.ip_address(ip);
+
+    #[tokio::test]
+    async fn test_semantic_search() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let search = json!({"query": "graph databases store nodes", "limit": 5});
+
+        // Without an embedding provider, semantic search is refused rather than faked
+        let router = create_router_with_config(db.clone(), &test_config());
+        let token = login(&router).await;
+        store(&router, &token, "graph databases store nodes").await;
+        let (status, body) =
+            memory_request(&router, &token, "POST", "/episodes/semantic-search", search.clone()).await;
+        assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+        assert_eq!(body["error_code"], "SEMANTIC_SEARCH_NOT_ENABLED");
+
+        // With embeddings, previously stored episodes are indexed when the agent is loaded
+        let config = test_config().memory_embeddings(qilbee_memory::EmbeddingConfig::mock(64));
+        let router = create_router_with_config(db, &config);
+        let token = login(&router).await;
+        store(&router, &token, "the weather is sunny today").await;
+
+        let (status, first) =
+            memory_request(&router, &token, "POST", "/episodes/semantic-search", search.clone()).await;
+        assert_eq!(status, StatusCode::OK);
+        let results = first["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["episode"]["content"]["primary"], "graph databases store nodes");
+        assert!((results[0]["score"].as_f64().unwrap() - 1.0).abs() < 1e-5);
+        assert!(results[1]["score"].as_f64().unwrap() < results[0]["score"].as_f64().unwrap());
+
+        // Scores are stable across identical queries
+        let (_, second) =
+            memory_request(&router, &token, "POST", "/episodes/semantic-search", search).await;
+        assert_eq!(first, second);
+
+        // minScore drops weaker matches
+        let search = json!({"query": "graph databases store nodes", "minScore": 0.99});
+        let (_, filtered) =
+            memory_request(&router, &token, "POST", "/episodes/semantic-search", search).await;
+        assert_eq!(filtered["results"].as_array().unwrap().len(), 1);
+
+        // efSearch widens a single query without changing its results here
+        let search = json!({"query": "graph databases store nodes", "limit": 5, "efSearch": 200});
+        let (status, wide) =
+            memory_request(&router, &token, "POST", "/episodes/semantic-search", search).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(wide, first);
+        let search = json!({"query": "graph databases store nodes", "efSearch": 1_000_000});
+        let (status, body) =
+            memory_request(&router, &token, "POST", "/episodes/semantic-search", search).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["error_code"], "VALIDATION_ERROR");
+
+        let (_, status) =
+            memory_request(&router, &token, "GET", "/semantic-search/status", Value::Null).await;
+        assert_eq!(status["enabled"], true);
+        assert_eq!(status["indexedEpisodes"], 2);
     }
-    if let (Some(start_str), Some(end_str)) = (&params.start_time, &params.end_time) {
-        if let (Ok(start), Ok(end)) = (
-            chrono::DateTime::parse_from_rfc3339(start_str),
-            chrono::DateTime::parse_from_rfc3339(end_str),
-        ) {
-            filter = filter.time_range(start.with_timezone(&chrono::Utc), end.with_timezone(&chrono::Utc));
+
+    #[tokio::test]
+    async fn test_per_agent_embeddings() {
+        use qilbee_memory::EmbeddingConfig;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let config = test_config()
+            .memory_embeddings(EmbeddingConfig::mock(384))
+            .agent_memory_embeddings("agent-2", EmbeddingConfig::mock(1536));
+        let router = create_router_with_config(db, &config);
+        let token = login(&router).await;
+
+        for (agent_id, dimensions) in [("agent-1", 384), ("agent-2", 1536), ("agent-3", 384)] {
+            let request = |method: &str, path: &str, body: Value| {
+                axum::http::Request::builder()
+                    .method(method)
+                    .uri(format!("/memory/{}{}", agent_id, path))
+                    .header("content-type", "application/json")
+                    .header("authorization", format!("Bearer {}", token))
+                    .body(axum::body::Body::from(body.to_string()))
+                    .unwrap()
+            };
+            let episode = json!({
+                "agentId": agent_id,
+                "episodeType": "conversation",
+                "content": {"primary": "graph databases store nodes"}
+            });
+            let (status, _) = call(&router, request("POST", "/episodes", episode)).await;
+            assert_eq!(status, StatusCode::CREATED);
+
+            let (_, body) = call(&router, request("GET", "/semantic-search/status", Value::Null)).await;
+            assert_eq!(body["dimensions"], dimensions, "{}", agent_id);
+            assert_eq!(body["indexedEpisodes"], 1, "{}", agent_id);
+
+            let search = json!({"query": "graph databases store nodes", "limit": 1});
+            let (status, body) =
+                call(&router, request("POST", "/episodes/semantic-search", search)).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["results"].as_array().unwrap().len(), 1);
         }
     }
 
-    let limit = params.limit.unwrap_or(100).min(1000); // Max 1000 events per query
+    #[tokio::test]
+    async fn test_index_missing_episodes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let config = test_config().memory_embeddings(qilbee_memory::EmbeddingConfig::mock(64));
+        let router = create_router_with_config(db.clone(), &config);
+        let token = login(&router).await;
+        store(&router, &token, "indexed on store").await;
+
+        // Episodes written straight to storage bypass the vector index
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+        for message in ["written elsewhere", "also written elsewhere"] {
+            storage
+                .store_episode("agent-1", &Episode::observation("agent-1", message))
+                .await
+                .unwrap();
+        }
+
+        let (status, body) =
+            memory_request(&router, &token, "POST", "/episodes/index", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["indexed"], 2);
+        assert_eq!(body["indexedEpisodes"], 3);
+
+        let (_, body) =
+            memory_request(&router, &token, "POST", "/episodes/index", Value::Null).await;
+        assert_eq!(body["indexed"], 0);
+
+        // Without embeddings the endpoint is refused
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let (status, body) =
+            memory_request(&router, &token, "POST", "/episodes/index", Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_IMPLEMENTED);
+        assert_eq!(body["error_code"], "SEMANTIC_SEARCH_NOT_ENABLED");
+    }
 
-    // Query events
-    let events = state.audit_service.query_events(filter, limit);
+    #[tokio::test]
+    async fn test_consolidate_memory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+        let token = login(&router).await;
+        store(&router, &token, "First").await;
+        store(&router, &token, "Second").await;
+
+        // A gentle decay keeps every episode
+        let (status, body) = memory_request(
+            &router,
+            &token,
+            "POST",
+            "/consolidate",
+            json!({"min_relevance": 0.3, "decay_factor": 0.99}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["episodes_decayed"], 2);
+        assert_eq!(body["episodes_evicted"], 0);
+        assert_eq!(body["episodes_after"], 2);
+
+        // An aggressive decay evicts them
+        let (status, body) = memory_request(
+            &router,
+            &token,
+            "POST",
+            "/consolidate",
+            json!({"min_relevance": 0.3, "decay_factor": 0.05}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["episodes_decayed"], 0);
+        assert_eq!(body["episodes_evicted"], 2);
+        assert_eq!(body["episodes_after"], 0);
+
+        let (status, _) = memory_request(
+            &router,
+            &token,
+            "POST",
+            "/consolidate",
+            json!({"decay_factor": 1.5}),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
 
-    // Convert to response format
-    let event_responses: Vec<AuditEventResponse> = events
-        .into_iter()
-        .map(|e| AuditEventResponse {
-            event_id: e.event_id,
-            event_type: e.event_type.to_string(),
-            timestamp: e.timestamp.to_rfc3339(),
-            user_id: e.user_id,
-            username: e.username,
-            action: e.action,
-            resource: e.resource,
-            result: e.result.to_string(),
-            ip_address: e.ip_address,
-            user_agent: e.user_agent,
-            metadata: e.metadata,
-            transaction_time: e.transaction_time.to_rfc3339(),
-        })
-        .collect();
+    #[tokio::test]
+    async fn test_get_episode_by_id() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db.clone(), &test_config());
+        let token = login(&router).await;
+
+        let episode = json!({
+            "agentId": "agent-1",
+            "episodeType": "observation",
+            "content": {"primary": "the oldest episode"}
+        });
+        let (_, body) = memory_request(&router, &token, "POST", "/episodes", episode).await;
+        let episode_id = body["episodeId"].as_str().unwrap().to_string();
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "events": event_responses,
-            "count": event_responses.len(),
-            "limit": limit
-        })),
-    )
-}
+        // Bury the episode under more than a page of recent ones
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+        for i in 0..120 {
+            let episode = Episode::observation("agent-1", &format!("newer episode {}", i));
+            storage.store_episode("agent-1", &episode).await.unwrap();
+        }
 
-// ==================== Account Lockout Handlers ====================
+        let path = format!("/episodes/{}", episode_id);
+        let (status, body) = memory_request(&router, &token, "GET", &path, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["episodeId"], episode_id);
 
-/// List all locked accounts (Admin only)
-async fn lockout_list(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-) -> impl IntoResponse {
-    // Require admin privileges
-    let _admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
+        let path = format!("/episodes/{}/similar", episode_id);
+        let (status, _) = memory_request(&router, &token, "GET", &path, Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+
+        // Well-formed but absent ids are not found; malformed ids are rejected
+        let path = format!("/episodes/{}", uuid::Uuid::new_v4());
+        let (status, body) = memory_request(&router, &token, "GET", &path, Value::Null).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(body["error_code"], "EPISODE_NOT_FOUND");
+
+        for path in ["/episodes/not-a-uuid", "/episodes/not-a-uuid/similar"] {
+            let (status, body) = memory_request(&router, &token, "GET", path, Value::Null).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert_eq!(body["error_code"], "VALIDATION_ERROR");
         }
-    };
+    }
 
-    let locked_users = state.lockout_service.get_locked_users();
+    #[tokio::test]
+    async fn test_episode_access_tracking() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+
+        let episode = json!({
+            "agentId": "agent-1",
+            "episodeType": "observation",
+            "content": {"primary": "the sky is blue"}
+        });
+        let (_, body) = memory_request(&router, &token, "POST", "/episodes", episode).await;
+        let path = format!("/episodes/{}", body["episodeId"].as_str().unwrap());
+
+        let mut last_accessed = 0;
+        for expected in 1..=3 {
+            tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+            let (status, body) = memory_request(&router, &token, "GET", &path, Value::Null).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["accessCount"], expected);
+            let accessed = body["lastAccessed"].as_i64().unwrap();
+            assert!(accessed > last_accessed);
+            last_accessed = accessed;
+        }
+
+        // Listings and searches report the stored counts without adding to them
+        let (_, body) = memory_request(&router, &token, "GET", "/episodes/recent", Value::Null).await;
+        assert_eq!(body["episodes"][0]["accessCount"], 3);
+        assert_eq!(body["episodes"][0]["lastAccessed"], last_accessed);
+        let search = json!({"query": "sky"});
+        let (_, body) = memory_request(&router, &token, "POST", "/episodes/search", search).await;
+        assert_eq!(body["episodes"][0]["accessCount"], 3);
+    }
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "locked_users": locked_users,
-            "count": locked_users.len()
-        })),
-    )
-}
+    #[tokio::test]
+    async fn test_list_episodes_by_consolidation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db.clone(), &test_config());
+        let token = login(&router).await;
+
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+        let episodes: Vec<_> = (0..4)
+            .map(|i| Episode::observation("agent-1", &format!("Event {}", i)))
+            .collect();
+        storage.store_episodes("agent-1", &episodes).await.unwrap();
+        for episode in &episodes[..2] {
+            let mut consolidated = episode.clone();
+            consolidated.mark_consolidated();
+            storage.update_episode("agent-1", &consolidated).await.unwrap();
+        }
+
+        let ids = |body: &Value| -> Vec<String> {
+            body["episodes"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|ep| ep["episodeId"].as_str().unwrap().to_string())
+                .collect()
+        };
 
-/// Get lockout status for a specific user (Admin only)
-async fn lockout_status(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Path(username): Path<String>,
-) -> impl IntoResponse {
-    // Require admin privileges
-    let _admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
-        }
-    };
+        let (status, body) =
+            memory_request(&router, &token, "GET", "/episodes?consolidated=true", Value::Null).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 2);
+        assert_eq!(ids(&body), vec![episodes[0].id.to_string(), episodes[1].id.to_string()]);
+        assert_eq!(body["episodes"][0]["consolidated"], true);
 
-    let status = state.lockout_service.get_user_status(&username);
+        let (_, body) =
+            memory_request(&router, &token, "GET", "/episodes?consolidated=false", Value::Null).await;
+        assert_eq!(ids(&body), vec![episodes[2].id.to_string(), episodes[3].id.to_string()]);
+        assert_eq!(body["episodes"][0]["consolidated"], false);
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "username": username,
-            "status": status
-        })),
-    )
-}
+        let (_, body) = memory_request(&router, &token, "GET", "/episodes", Value::Null).await;
+        assert_eq!(body["count"], 4);
+    }
 
-#[derive(Debug, Deserialize)]
-struct LockUserRequest {
-    reason: Option<String>,
-}
+    #[tokio::test]
+    async fn test_custom_episode_type_in_content() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+
+        let episode = json!({
+            "agentId": "agent-1",
+            "episodeType": "action",
+            "content": {"primary": "opened the ticket", "secondary": "ticket #42"}
+        });
+        let (status, body) = memory_request(&router, &token, "POST", "/episodes", episode).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let path = format!("/episodes/{}", body["episodeId"].as_str().unwrap());
+
+        let (_, body) = memory_request(&router, &token, "GET", &path, Value::Null).await;
+        assert_eq!(
+            body["content"],
+            json!({"type": "Action", "primary": "opened the ticket", "secondary": "ticket #42"})
+        );
 
-/// Manually lock a user account (Admin only)
-async fn lockout_lock(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Path(username): Path<String>,
-    Json(request): Json<LockUserRequest>,
-) -> impl IntoResponse {
-    // Require admin privileges
-    let admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
+        let (_, body) = memory_request(&router, &token, "GET", "/episodes/recent", Value::Null).await;
+        assert_eq!(body["episodes"][0]["content"]["type"], "Action");
+        let search = json!({"query": "ticket"});
+        let (_, body) = memory_request(&router, &token, "POST", "/episodes/search", search).await;
+        assert_eq!(body["episodes"][0]["content"]["type"], "Action");
+        assert_eq!(body["episodes"][0]["content"]["primary"], "opened the ticket");
+
+        // Built-in types keep their own field names and carry no type
+        let observation = Episode::observation("agent-1", "the sky is blue");
+        assert_eq!(format_episode_content(&observation), json!({"observation": "the sky is blue"}));
+        assert!(format_search_content(&observation).get("type").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_store_episodes_batch() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let config = test_config().memory_embeddings(qilbee_memory::EmbeddingConfig::mock(64));
+        let router = create_router_with_config(db, &config);
+        let token = login(&router).await;
+
+        let episode = |agent: &str, message: &str| {
+            json!({
+                "agentId": agent,
+                "episodeType": "observation",
+                "content": {"primary": message}
+            })
+        };
+        let batch = json!([
+            episode("agent-1", "first"),
+            {"agentId": "agent-1", "content": {"primary": "no type"}},
+            episode("agent-1", "second"),
+            episode("agent-2", "wrong agent")
+        ]);
+
+        // Invalid items are reported without failing the rest of the batch
+        let (status, body) = memory_request(&router, &token, "POST", "/episodes/batch", batch).await;
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert_eq!(body["stored"], 2);
+        assert_eq!(body["failed"], 2);
+        let results = body["results"].as_array().unwrap();
+        assert!(results[1]["error"].as_str().unwrap().contains("episodeType"));
+        assert!(results[3]["error"].as_str().unwrap().contains("agent-2"));
+
+        let ids = body["episodeIds"].as_array().unwrap();
+        assert_eq!(ids, &vec![results[0]["episodeId"].clone(), results[2]["episodeId"].clone()]);
+        for (id, message) in ids.iter().zip(["first", "second"]) {
+            let path = format!("/episodes/{}", id.as_str().unwrap());
+            let (status, body) = memory_request(&router, &token, "GET", &path, Value::Null).await;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(body["content"]["observation"], message);
+        }
+
+        // Stored episodes are indexed for semantic search
+        let (_, status) =
+            memory_request(&router, &token, "GET", "/semantic-search/status", Value::Null).await;
+        assert_eq!(status["indexedEpisodes"], 2);
+
+        let batch = json!([episode("agent-1", "third")]);
+        let (status, body) = memory_request(&router, &token, "POST", "/episodes/batch", batch).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["stored"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_relationships_batch() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        let hub = graph.create_node(["Person"]).unwrap().id;
+        let others: Vec<_> = (0..500)
+            .map(|_| graph.create_node(["Person"]).unwrap().id)
+            .collect();
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+
+        let mut batch: Vec<Value> = others
+            .iter()
+            .map(|id| {
+                json!({
+                    "startNode": hub.as_internal(),
+                    "type": "KNOWS",
+                    "endNode": id.as_internal(),
+                    "properties": {"since": 2020}
+                })
+            })
+            .collect();
+        batch.insert(
+            3,
+            json!({"startNode": hub.as_internal(), "type": "KNOWS", "endNode": 999_999, "properties": {}}),
+        );
+
+        let request = axum::http::Request::post("/graphs/social/relationships/batch")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::from(Value::Array(batch).to_string()))
+            .unwrap();
+        let (status, body) = call(&router, request).await;
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert_eq!(body["created"], 500);
+        assert_eq!(body["failed"], 1);
+        assert_eq!(body["ids"].as_array().unwrap().len(), 500);
+        let results = body["results"].as_array().unwrap();
+        assert_eq!(results[3]["index"], 3);
+        assert!(results[3]["error"].as_str().unwrap().contains("999999"));
+
+        // Both adjacency directions are populated for every created relationship
+        assert_eq!(graph.get_relationships(hub, qilbee_core::Direction::Outgoing).unwrap().len(), 500);
+        for id in &others {
+            let incoming = graph.get_relationships(*id, qilbee_core::Direction::Incoming).unwrap();
+            assert_eq!(incoming.len(), 1);
+            assert_eq!(incoming[0].get_property("since"), Some(&PropertyValue::Integer(2020)));
         }
-    };
+    }
 
-    state.lockout_service.lock_user(&username, request.reason.clone());
+    #[tokio::test]
+    async fn test_bulk_import() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let import = |body: Value| {
+            axum::http::Request::post("/graphs/social/import")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
 
-    // Log the manual lock event
-    state.audit_service.log_auth_event(
-        AuditEventType::AccountLocked,
-        &username,
-        AuditResult::Success,
-        None,
-        Some(format!(
-            "Manually locked by admin {:?}. Reason: {:?}",
-            admin_id,
-            request.reason
-        )),
-    );
+        let mut nodes = String::from(":ID,:LABEL,name\n");
+        let mut relationships = String::from(":START_ID,:TYPE,:END_ID\n");
+        for i in 0..2500 {
+            nodes.push_str(&format!("p{},Person,user{}\n", i, i));
+            relationships.push_str(&format!("p{},KNOWS,p{}\n", i, (i + 1) % 2500));
+        }
+        relationships.push_str("p1,KNOWS,missing\n");
+
+        let (status, body) = call(&router, import(json!({"nodes": nodes, "relationships": relationships}))).await;
+        assert_eq!(status, StatusCode::MULTI_STATUS);
+        assert_eq!(body["nodesCreated"], 2500);
+        assert_eq!(body["relationshipsCreated"], 2500);
+        assert_eq!(
+            body["errors"],
+            json!([{"section": "relationships", "line": 2502, "message": "Unknown node id 'missing'"}])
+        );
+        assert_eq!(graph.node_count().unwrap(), 2500);
+        assert_eq!(graph.relationship_count().unwrap(), 2500);
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "success": true,
-            "message": format!("Account '{}' has been locked", username)
-        })),
-    )
-}
+        let ndjson = json!({
+            "format": "ndjson",
+            "nodes": "{\"id\": 1, \"labels\": [\"City\"], \"properties\": {\"name\": \"Paris\"}}\n"
+        });
+        let (status, body) = call(&router, import(ndjson)).await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["nodesCreated"], 1);
+
+        let (status, body) = call(&router, import(json!({"nodes": "name\nEve\n"}))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains(":ID"));
+        assert_eq!(body["nodesCreated"], 0);
+    }
 
-/// Manually unlock a user account (Admin only)
-async fn lockout_unlock(
-    State(state): State<AppState>,
-    headers: axum::http::HeaderMap,
-    Path(username): Path<String>,
-) -> impl IntoResponse {
-    // Require admin privileges
-    let admin_id = match extract_admin_from_token(&headers, &state) {
-        Ok(uid) => uid,
-        Err(status) => {
-            return (
-                status,
-                Json(json!({"error": "Unauthorized: Admin access required"})),
-            )
+    #[tokio::test]
+    async fn test_export_restores_into_fresh_graph() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let source = db.graph("source").unwrap();
+        source
+            .create_constraint(qilbee_graph::Constraint::unique("uniq_email", "Person", "email"))
+            .unwrap();
+        source
+            .create_index(qilbee_graph::Index::range("person_age", "Person", "age"))
+            .unwrap();
+        let mut ids = Vec::new();
+        for i in 0..1500 {
+            let mut properties = Property::new();
+            properties.set("email", format!("user{}@example.com", i));
+            properties.set("age", i as i64 % 90);
+            ids.push(source.create_node_with_properties(["Person"], properties).unwrap().id);
+        }
+        for pair in ids.windows(2).take(1200) {
+            source
+                .create_relationship_with_properties(pair[0], "KNOWS", pair[1], Property::with("since", 2020))
+                .unwrap();
+        }
+        let router = create_router_with_config(db.clone(), &test_config());
+        let token = login(&router).await;
+
+        let export = |graph: &str| {
+            axum::http::Request::get(format!("/graphs/{}/export", graph))
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let response = router.clone().oneshot(export("source")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+        let dump = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        let records: Vec<Value> = String::from_utf8(dump.to_vec())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 1 + 1500 + 1200);
+        assert_eq!(records[0]["kind"], "schema");
+        assert_eq!(records[1]["kind"], "node");
+        assert_eq!(records[1501]["kind"], "relationship");
+
+        let restore = axum::http::Request::post("/graphs/copy/import")
+            .header("content-type", "application/x-ndjson")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::from(dump))
+            .unwrap();
+        let (status, body) = call(&router, restore).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        assert_eq!(body["nodesCreated"], 1500);
+        assert_eq!(body["relationshipsCreated"], 1200);
+
+        let copy = db.graph("copy").unwrap();
+        assert_eq!(copy.node_count().unwrap(), 1500);
+        assert_eq!(copy.relationship_count().unwrap(), 1200);
+        {
+            let schema = copy.schema().read().unwrap();
+            assert!(schema.get_index("person_age").is_some());
+            assert!(schema.get_constraint("uniq_email").is_some());
+        }
+        let found = copy
+            .find_nodes_by_label_and_property("Person", "email", &PropertyValue::String("user7@example.com".into()))
+            .unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].properties.get("age"), Some(&PropertyValue::Integer(7)));
+        let knows = copy.get_relationships(found[0].id, Direction::Outgoing).unwrap();
+        assert_eq!(knows.len(), 1);
+        assert_eq!(knows[0].properties.get("since"), Some(&PropertyValue::Integer(2020)));
+
+        let response = router.clone().oneshot(export("missing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_get_node_relationships() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        let alice = graph.create_node(["Person"]).unwrap().id;
+        let bob = graph.create_node(["Person"]).unwrap().id;
+        let acme = graph.create_node(["Company"]).unwrap().id;
+        let knows = graph.create_relationship(alice, "KNOWS", bob).unwrap().id;
+        let knows_back = graph.create_relationship(bob, "KNOWS", alice).unwrap().id;
+        let works_at = graph.create_relationship(alice, "WORKS_AT", acme).unwrap().id;
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+
+        let get = |path: String| {
+            axum::http::Request::get(path)
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let (status, body) = call(&router, get(format!("/graphs/social/nodes/{}/relationships", alice.as_internal()))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 3);
+        let direction_of = |id: qilbee_core::RelationshipId| {
+            body["relationships"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|rel| rel["id"] == id.as_internal())
+                .map(|rel| rel["direction"].clone())
+                .unwrap()
+        };
+        assert_eq!(direction_of(knows), "outgoing");
+        assert_eq!(direction_of(knows_back), "incoming");
+        assert_eq!(direction_of(works_at), "outgoing");
+
+        let (status, body) = call(&router, get(format!("/graphs/social/nodes/{}/relationships?type=WORKS_AT", alice.as_internal()))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 1);
+        let rel = &body["relationships"][0];
+        assert_eq!(rel["type"], "WORKS_AT");
+        assert_eq!(rel["startNode"], alice.as_internal());
+        assert_eq!(rel["endNode"], acme.as_internal());
+
+        let (status, _) = call(&router, get("/graphs/social/nodes/999999/relationships".to_string())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_query_audit_events() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let request = |method: &str, path: &str, body: Value| {
+            axum::http::Request::builder()
+                .method(method)
+                .uri(path)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let (status, _) = call(
+            &router,
+            request(
+                "POST",
+                "/graphs/social/query",
+                json!({"cypher": "MERGE (p:Person {name: 'Alice', ssn: '123-45-6789'})", "parameters": {"source": "import-42"}}),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let (status, _) = call(
+            &router,
+            request("POST", "/graphs/social/query", json!({"cypher": "MATCH (p:Person)\n  RETURN p.name"})),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, body) = call(
+            &router,
+            request("GET", "/api/v1/audit-logs?event_type=query_executed", Value::Null),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let events = body["events"].as_array().unwrap();
+        assert_eq!(events.len(), 2, "{}", body);
+        for value in ["123-45-6789", "Alice", "import-42"] {
+            assert!(!body.to_string().contains(value), "{} in {}", value, body);
         }
-    };
 
-    let was_locked = state.lockout_service.unlock_user(&username);
+        let event = |query: &str| {
+            events
+                .iter()
+                .find(|event| event["metadata"]["query"] == query)
+                .unwrap_or_else(|| panic!("no event for {query} in {body}"))
+        };
+        let merge = event("MERGE (p:Person {name: ?, ssn: ?})");
+        assert_eq!(merge["action"], "query_executed");
+        assert_eq!(merge["resource"], "graph:social");
+        assert_eq!(merge["result"], "success");
+        assert_eq!(merge["username"], "admin");
+        assert!(merge["user_id"].is_string());
+        assert_eq!(merge["metadata"]["parameters"], json!(["source"]));
+        assert_eq!(merge["metadata"]["mutated"], true);
+        assert!(merge["metadata"]["execution_time_ms"].is_u64());
+
+        let read = event("MATCH (p:Person) RETURN p.name");
+        assert_eq!(read["metadata"]["mutated"], false);
+        assert_eq!(read["metadata"]["rows"], 1);
+    }
 
-    if was_locked {
-        // Log the unlock event
-        state.audit_service.log_auth_event(
-            AuditEventType::AccountUnlocked,
-            &username,
-            AuditResult::Success,
-            None,
-            Some(format!("Unlocked by admin {:?}", admin_id)),
+    #[tokio::test]
+    async fn test_query_permissions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+
+        let create_user = axum::http::Request::post("/api/v1/users")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::from(
+                json!({"username": "reader", "email": "reader@example.com", "password": "SecureReader@123!", "roles": ["Read"]})
+                    .to_string(),
+            ))
+            .unwrap();
+        assert_eq!(call(&router, create_user).await.0, StatusCode::CREATED);
+        let reader_login = axum::http::Request::post("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(
+                json!({"username": "reader", "password": "SecureReader@123!"}).to_string(),
+            ))
+            .unwrap();
+        let reader = call(&router, reader_login).await.1["access_token"].as_str().unwrap().to_string();
+        let query = |cypher: &str, token: &str| {
+            axum::http::Request::post("/graphs/social/query")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(json!({"cypher": cypher}).to_string()))
+                .unwrap()
+        };
+
+        // Read-only users may read but not write
+        let (status, body) = call(&router, query("MERGE (p:Person {name: 'Eve'})", &reader)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        assert!(body["error"].as_str().unwrap().contains("GraphUpdate"));
+        let (status, _) = call(&router, query("MATCH (p:Person) RETURN p", &reader)).await;
+        assert_eq!(status, StatusCode::OK);
+
+        let (status, _) = call(&router, query("MERGE (p:Person {name: 'Eve'})", &token)).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    #[test]
+    fn test_normalize_query_text() {
+        assert_eq!(
+            normalize_query_text("  MATCH (u:User)\n\tWHERE u.email = 'a@b.c'  AND u.note = \"it\\\"s\"\nRETURN u "),
+            "MATCH (u:User) WHERE u.email = ? AND u.note = ? RETURN u"
         );
+        assert_eq!(normalize_query_text("RETURN 'it\\'s' AS x"), "RETURN ? AS x");
+    }
 
-        (
-            StatusCode::OK,
-            Json(json!({
-                "success": true,
-                "message": format!("Account '{}' has been unlocked", username)
-            })),
+    #[tokio::test]
+    async fn test_delete_nodes_by_label() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        let alice = graph.create_node(["Person"]).unwrap();
+        let temp = graph.create_node(["Temp"]).unwrap();
+        graph.create_node(["Temp"]).unwrap();
+        graph.create_relationship(alice.id, "SEES", temp.id).unwrap();
+
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let delete = |path: &str, token: &str| {
+            axum::http::Request::delete(path)
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let (status, _) = call(&router, delete("/graphs/social/nodes?label=Temp", &token)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call(&router, delete("/graphs/social/nodes?confirm=true", &token)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = call(&router, delete("/graphs/missing/nodes?label=Temp&confirm=true", &token)).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Read-only users may not delete in bulk
+        let create_user = axum::http::Request::post("/api/v1/users")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::from(
+                json!({"username": "reader", "email": "reader@example.com", "password": "SecureReader@123!", "roles": ["Read"]})
+                    .to_string(),
+            ))
+            .unwrap();
+        assert_eq!(call(&router, create_user).await.0, StatusCode::CREATED);
+        let reader_login = axum::http::Request::post("/api/v1/auth/login")
+            .header("content-type", "application/json")
+            .body(axum::body::Body::from(
+                json!({"username": "reader", "password": "SecureReader@123!"}).to_string(),
+            ))
+            .unwrap();
+        let reader = call(&router, reader_login).await.1["access_token"].as_str().unwrap().to_string();
+        let (status, _) = call(&router, delete("/graphs/social/nodes?label=Temp&confirm=true", &reader)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let unauthenticated = axum::http::Request::delete("/graphs/social/nodes?label=Temp&confirm=true")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(call(&router, unauthenticated).await.0, StatusCode::UNAUTHORIZED);
+
+        let (status, body) = call(&router, delete("/graphs/social/nodes?label=Temp&confirm=true", &token)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({"nodesDeleted": 2, "relationshipsDeleted": 1}));
+
+        let (_, body) = call(&router, delete("/graphs/social/nodes?label=Temp&confirm=true", &token)).await;
+        assert_eq!(body, json!({"nodesDeleted": 0, "relationshipsDeleted": 0}));
+    }
+
+    #[tokio::test]
+    async fn test_graph_stats() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        let alice = graph.create_node(["Person"]).unwrap();
+        let bob = graph.create_node(["Person", "Admin"]).unwrap();
+        let acme = graph.create_node(["Company"]).unwrap();
+        graph.create_relationship(alice.id, "KNOWS", bob.id).unwrap();
+        graph.create_relationship(alice.id, "WORKS_AT", acme.id).unwrap();
+        graph.create_relationship(bob.id, "WORKS_AT", acme.id).unwrap();
+
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let get = |path: &str| {
+            axum::http::Request::get(path)
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let (status, body) = call(&router, get("/graphs/social/stats")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            json!({
+                "graph": "social",
+                "nodeCount": 3,
+                "relationshipCount": 3,
+                "labels": {"Admin": 1, "Company": 1, "Person": 2},
+                "relationshipTypes": {"KNOWS": 1, "WORKS_AT": 2},
+                "estimated": false,
+            })
+        );
+
+        let (status, _) = call(&router, get("/graphs/missing/stats")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_graph_diff() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        let mark = || {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            let time = TransactionTime::now().as_millis();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            time
+        };
+
+        let alice = graph.create_node(["Person"]).unwrap();
+        let bob = graph.create_node(["Person"]).unwrap();
+        let from = mark();
+        let carol = graph.create_node(["Person"]).unwrap();
+        let knows = graph.create_relationship(alice.id, "KNOWS", carol.id).unwrap();
+        let mut renamed = alice.clone();
+        renamed.set_property("name", "Alice");
+        graph.update_node(&renamed).unwrap();
+        graph.delete_node(bob.id).unwrap();
+        let to = mark();
+        graph.delete_relationship(knows.id).unwrap();
+
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let get = |path: String| {
+            axum::http::Request::get(path)
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+
+        let (status, body) = call(&router, get(format!("/graphs/social/diff?from={}&to={}", from, to))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["from"], from);
+        assert_eq!(body["to"], to);
+        assert_eq!(body["nodes"]["created"].as_array().unwrap().len(), 1);
+        assert_eq!(body["nodes"]["created"][0]["id"], carol.id.as_internal());
+        assert_eq!(body["nodes"]["updated"].as_array().unwrap().len(), 1);
+        assert_eq!(body["nodes"]["updated"][0]["before"]["properties"], json!({}));
+        assert_eq!(body["nodes"]["updated"][0]["after"]["properties"]["name"], "Alice");
+        assert_eq!(body["nodes"]["deleted"].as_array().unwrap().len(), 1);
+        assert_eq!(body["nodes"]["deleted"][0]["id"], bob.id.as_internal());
+        assert_eq!(body["relationships"]["created"].as_array().unwrap().len(), 1);
+        assert_eq!(body["relationships"]["created"][0]["type"], "KNOWS");
+        assert!(body["relationships"]["updated"].as_array().unwrap().is_empty());
+        assert!(body["relationships"]["deleted"].as_array().unwrap().is_empty());
+
+        let (status, _) = call(&router, get(format!("/graphs/social/diff?from={}&to={}", to, from))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_query_cache() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let post = |uri: &str, body: Value| {
+            axum::http::Request::post(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
+        let names = json!({"cypher": "MATCH (p:Person) RETURN p.name AS name LIMIT 100"});
+        let person = |name: &str| json!({"labels": ["Person"], "properties": {"name": name}});
+
+        let (status, _) = call(&router, post("/graphs/social/nodes", person("Alice"))).await;
+        assert_eq!(status, StatusCode::CREATED);
+        let (_, body) = call(&router, post("/graphs/social/query", names.clone())).await;
+        assert_eq!(body["stats"]["cached"], false);
+        let (_, body) = call(&router, post("/graphs/social/query", names.clone())).await;
+        assert_eq!(body["stats"]["cached"], true);
+        assert_eq!(body["results"], json!([{"name": "Alice"}]));
+
+        let (_, body) = call(&router, post("/graphs/social/query?noCache=true", names.clone())).await;
+        assert_eq!(body["stats"]["cached"], false);
+
+        // A write through the REST API invalidates the cached result
+        call(&router, post("/graphs/social/nodes", person("Bob"))).await;
+        let (_, body) = call(&router, post("/graphs/social/query", names.clone())).await;
+        assert_eq!(body["stats"]["cached"], false);
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+
+        // Results that may outgrow the streaming threshold are streamed, not cached
+        let unbounded = json!({"cypher": "MATCH (p:Person) RETURN p.name AS name"});
+        for _ in 0..2 {
+            let (_, body) = call(&router, post("/graphs/social/query", unbounded.clone())).await;
+            assert_eq!(body["stats"]["cached"], false);
+            assert_eq!(body["results"].as_array().unwrap().len(), 2);
+        }
+
+        // Writes are never served from the cache
+        let rename = json!({"cypher": "MATCH (p:Person {name: 'Bob'}) SET p.name = 'Carol'"});
+        for _ in 0..2 {
+            let (_, body) = call(&router, post("/graphs/social/query", rename.clone())).await;
+            assert_eq!(body["stats"]["cached"], false);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_read_your_writes() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let post = |uri: &str, body: Value| {
+            axum::http::Request::post(uri)
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let (status, created) = call(
+            &router,
+            post("/graphs/social/nodes", json!({"labels": ["Person"], "properties": {"name": "Alice"}})),
         )
-    } else {
-        (
-            StatusCode::NOT_FOUND,
-            Json(json!({
-                "success": false,
-                "error": format!("No lockout record found for user '{}'", username)
-            })),
+        .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let id = created["id"].as_u64().unwrap();
+
+        // Read straight back, with no flush in between
+        let request = axum::http::Request::get(format!("/graphs/social/nodes/{}", id))
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let (status, node) = call(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(node["properties"]["name"], "Alice");
+
+        // Index-backed lookups see the new node too
+        let (status, body) = call(
+            &router,
+            post(
+                "/graphs/social/query",
+                json!({"cypher": "MATCH (p:Person {name: 'Alice'}) RETURN p.name AS name"}),
+            ),
         )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"].as_array().unwrap().len(), 1);
     }
-}
 
-// ==================== LLM Configuration ====================
+    #[tokio::test]
+    async fn test_query_parser_limits() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        db.graph("social").unwrap().create_node(["Person"]).unwrap();
+        let config = test_config().max_query_depth(8).max_query_tokens(200);
+        let router = create_router_with_config(db, &config);
+        let token = login(&router).await;
+
+        let query = |cypher: String| {
+            axum::http::Request::post("/graphs/social/query")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(json!({"cypher": cypher}).to_string()))
+                .unwrap()
+        };
+        let nested = |depth: usize| {
+            format!("MATCH (p:Person) RETURN {}p{}", "toUpper(".repeat(depth), ")".repeat(depth))
+        };
 
-/// Request body for updating LLM configuration
-#[derive(Debug, Deserialize)]
-struct LLMConfigUpdateRequest {
-    /// Provider type: "openai" or "mock"
-    provider: String,
-    /// API key (required for OpenAI)
-    api_key: Option<String>,
-    /// Model name (optional, defaults based on provider)
-    model: Option<String>,
-    /// Temperature (optional, 0.0-2.0)
-    temperature: Option<f32>,
-    /// Max tokens (optional)
-    max_tokens: Option<u32>,
-}
+        let (status, _) = call(&router, query("MATCH (p:Person) RETURN p".to_string())).await;
+        assert_eq!(status, StatusCode::OK);
 
-/// Response for LLM status
-#[derive(Debug, Serialize)]
-struct LLMStatusResponse {
-    configured: bool,
-    provider: String,
-    model: String,
-}
+        let (status, body) = call(&router, query(nested(50))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("maximum depth of 8"));
 
-/// Get current LLM configuration status
-async fn llm_status(State(state): State<AppState>) -> impl IntoResponse {
-    let is_configured = state.llm_service.is_configured().await;
-    let model_name = state.llm_service.model_name().await;
-    let config = state.llm_service.get_config().await;
+        let (status, body) = call(&router, query(format!("MATCH (p){} RETURN p", "-->(q)".repeat(100)))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert!(body["error"].as_str().unwrap().contains("maximum of 200 tokens"));
+    }
 
-    let provider_name = match config.provider {
-        LLMProviderType::Mock => "mock",
-        LLMProviderType::OpenAI => "openai",
-    };
+    #[tokio::test]
+    async fn test_explain_and_profile_queries() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        for name in ["Alice", "Bob"] {
+            let mut props = Property::new();
+            props.set("name", name);
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let query = |cypher: &str| {
+            axum::http::Request::post("/graphs/social/query")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(json!({"cypher": cypher}).to_string()))
+                .unwrap()
+        };
 
-    (
-        StatusCode::OK,
-        Json(json!({
-            "configured": is_configured,
-            "provider": provider_name,
-            "model": model_name,
-            "temperature": config.temperature,
-            "max_tokens": config.max_tokens
-        })),
-    )
-}
+        let (status, body) = call(&router, query("EXPLAIN MATCH (p:Person) DETACH DELETE p")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"], json!([]));
+        assert_eq!(body["plan"]["operator"], "DetachDelete");
+        assert_eq!(body["plan"]["children"][0]["operator"], "NodeByLabelScan p:Person");
+        assert!(body["plan"]["estimatedRows"].is_u64());
+        assert!(body["plan"].get("rows").is_none());
+
+        let (status, body) = call(&router, query("PROFILE MATCH (p:Person) RETURN p.name AS name")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+        let scan = &body["plan"]["children"][0]["children"][0];
+        assert_eq!(scan["operator"], "NodeByLabelScan p:Person");
+        assert_eq!(scan["rows"], 2);
+        assert_eq!(scan["dbHits"], 2);
+
+        // Plain queries carry no plan, and EXPLAIN deleted nothing
+        let (_, body) = call(&router, query("MATCH (p:Person) RETURN p.name AS name")).await;
+        assert!(body.get("plan").is_none());
+        assert_eq!(body["results"].as_array().unwrap().len(), 2);
+    }
 
-/// Update LLM configuration at runtime
-async fn llm_update_config(
-    State(state): State<AppState>,
-    Json(request): Json<LLMConfigUpdateRequest>,
-) -> impl IntoResponse {
-    // Build new configuration based on provider type
-    let new_config = match request.provider.to_lowercase().as_str() {
-        "openai" => {
-            let api_key = match request.api_key {
-                Some(key) if !key.is_empty() => key,
-                _ => {
-                    return (
-                        StatusCode::BAD_REQUEST,
-                        Json(json!({
-                            "error": "API key is required for OpenAI provider"
-                        })),
-                    )
-                }
-            };
+    #[tokio::test]
+    async fn test_query_uses_declared_indexes_only() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        for i in 0..30 {
+            let mut props = Property::new();
+            props.set("name", format!("Person{:02}", i));
+            props.set("city", if i % 10 == 0 { "Paris" } else { "Rome" });
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        let parisians = json!([{"name": "Person00"}, {"name": "Person10"}, {"name": "Person20"}]);
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let query = |cypher: &str| {
+            axum::http::Request::post("/graphs/social/query")
+                .header("content-type", "application/json")
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::from(json!({"cypher": cypher}).to_string()))
+                .unwrap()
+        };
+        let operators = |plan: &Value| {
+            let mut operators = Vec::new();
+            let mut next = Some(plan);
+            while let Some(description) = next {
+                operators.push(description["operator"].as_str().unwrap().to_string());
+                next = description["children"].get(0);
+            }
+            operators
+        };
+        let cypher = "MATCH (p:Person) WHERE p.city = 'Paris' RETURN p.name AS name ORDER BY name";
+
+        // Unindexed property: the label is scanned and filtered
+        let (status, body) = call(&router, query(&format!("EXPLAIN {}", cypher))).await;
+        assert_eq!(status, StatusCode::OK);
+        let plan = operators(&body["plan"]);
+        assert!(plan.contains(&"NodeByLabelScan p:Person".to_string()), "{:?}", plan);
+        assert!(plan.contains(&"Filter".to_string()), "{:?}", plan);
+        let (status, body) = call(&router, query(cypher)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"], parisians);
+
+        // Once indexed, the same query seeks the index
+        let (status, _) = call(&router, query("CREATE INDEX FOR (p:Person) ON (p.city)")).await;
+        assert_eq!(status, StatusCode::OK);
+        let (_, body) = call(&router, query(&format!("EXPLAIN {}", cypher))).await;
+        let plan = operators(&body["plan"]);
+        assert!(plan.contains(&"IndexSeek p:Person(city)".to_string()), "{:?}", plan);
+        let (_, body) = call(&router, query(cypher)).await;
+        assert_eq!(body["results"], parisians);
+    }
 
-            // Use openai_mini as base and customize
-            let mut config = LLMConfig::openai_mini(&api_key);
+    #[tokio::test]
+    async fn test_query_expands_from_smaller_label() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        let mut cities = Vec::new();
+        for name in ["Paris", "Rome"] {
+            let mut props = Property::new();
+            props.set("name", name);
+            cities.push(graph.create_node_with_properties(["City"], props).unwrap().id);
+        }
+        for i in 0..30 {
+            let mut props = Property::new();
+            props.set("name", format!("Person{}", i));
+            let person = graph.create_node_with_properties(["Person"], props).unwrap();
+            graph.create_relationship(person.id, "LIVES_IN", cities[i % 2]).unwrap();
+        }
+        let router = create_router_with_config(db, &test_config());
+        let token = login(&router).await;
+        let request = axum::http::Request::post("/graphs/social/query")
+            .header("content-type", "application/json")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::from(
+                json!({"cypher": "PROFILE MATCH (p:Person)-[:LIVES_IN]->(c:City) WHERE c.name = 'Rome' RETURN p.name AS name"})
+                    .to_string(),
+            ))
+            .unwrap();
+
+        // Written from Person, but executed from the two cities
+        let (status, body) = call(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["results"].as_array().unwrap().len(), 15);
+        let mut operators = Vec::new();
+        let mut next = Some(&body["plan"]);
+        while let Some(description) = next {
+            operators.push((description["operator"].as_str().unwrap().to_string(), description["rows"].clone()));
+            next = description["children"].get(0);
+        }
+        assert!(operators.contains(&("Expand (c)--(p)".to_string(), json!(30))), "{:?}", operators);
+        assert_eq!(operators.last().unwrap(), &("NodeByLabelScan c:City".to_string(), json!(2)));
+    }
 
-            // Override model if specified
-            if let Some(ref model) = request.model {
-                config.model = model.clone();
-            }
+    #[tokio::test]
+    async fn test_memory_usage() {
+        use tower::ServiceExt;
 
-            if let Some(temp) = request.temperature {
-                config.temperature = temp;
-            }
-            if let Some(max_tokens) = request.max_tokens {
-                config.max_tokens = max_tokens;
-            }
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db.clone(), &test_config());
+        let token = login(&router).await;
 
-            config
-        }
-        "mock" => LLMConfig::mock(),
-        _ => {
-            return (
-                StatusCode::BAD_REQUEST,
-                Json(json!({
-                    "error": format!("Unknown provider type: '{}'. Supported: 'openai', 'mock'", request.provider)
-                })),
-            )
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+        for (agent, count) in [("small-agent", 2), ("large-agent", 20)] {
+            let episodes: Vec<_> = (0..count)
+                .map(|i| Episode::observation(agent, &format!("{} event {}", agent, i)))
+                .collect();
+            storage.store_episodes(agent, &episodes).await.unwrap();
+        }
+
+        let request = axum::http::Request::get("/admin/memory/usage")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let (status, body) = call(&router, request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 2);
+        let agents = body["agents"].as_array().unwrap();
+        assert_eq!(agents[0]["agent_id"], "large-agent");
+        assert_eq!(agents[0]["episodes"], 20);
+        assert_eq!(agents[1]["agent_id"], "small-agent");
+        assert_eq!(agents[1]["index_entries"], 2);
+        assert!(agents[0]["total_bytes"].as_u64().unwrap() > agents[1]["total_bytes"].as_u64().unwrap());
+
+        // Admin credentials are required
+        let request = axum::http::Request::get("/admin/memory/usage")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(router.clone().oneshot(request).await.unwrap().status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_forget_memory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+        let token = login(&router).await;
+
+        let now = chrono::Utc::now().timestamp_millis();
+        for (episode_type, event_time) in [
+            ("observation", now - 7_200_000),
+            ("observation", now),
+            ("conversation", now - 7_200_000),
+        ] {
+            let episode = json!({
+                "agentId": "agent-1",
+                "episodeType": episode_type,
+                "content": {"primary": episode_type},
+                "eventTime": event_time
+            });
+            let (status, _) = memory_request(&router, &token, "POST", "/episodes", episode).await;
+            assert_eq!(status, StatusCode::CREATED);
         }
-    };
 
-    // Update the LLM service configuration
-    match state.llm_service.update_config(new_config).await {
-        Ok(()) => {
-            let model_name = state.llm_service.model_name().await;
-            let is_configured = state.llm_service.is_configured().await;
+        // Lower every episode's relevance without evicting any
+        let decay = json!({"min_relevance": 0.0, "decay_factor": 0.05});
+        memory_request(&router, &token, "POST", "/consolidate", decay).await;
 
-            (
-                StatusCode::OK,
-                Json(json!({
-                    "success": true,
-                    "message": "LLM configuration updated successfully",
-                    "configured": is_configured,
-                    "provider": request.provider.to_lowercase(),
-                    "model": model_name
-                })),
-            )
-        }
-        Err(e) => (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(json!({
-                "error": format!("Failed to update LLM configuration: {}", e)
-            })),
-        ),
+        // Only the old observation matches every criterion
+        let forget = json!({
+            "min_relevance": 0.1,
+            "max_age_seconds": 3600,
+            "episode_types": ["observation"]
+        });
+        let (status, body) = memory_request(&router, &token, "POST", "/forget", forget).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["forgotten"], 1);
+        assert_eq!(body["forgotten_by_type"], json!({"Observation": 1}));
+        assert_eq!(body["episodes_after"], 2);
+
+        // Recent episodes survive a relevance-only sweep with a max age
+        let forget = json!({"min_relevance": 0.1, "max_age_seconds": 3600});
+        let (_, body) = memory_request(&router, &token, "POST", "/forget", forget).await;
+        assert_eq!(body["forgotten_by_type"], json!({"Conversation": 1}));
+        assert_eq!(body["episodes_after"], 1);
     }
-}
 
-// ==================== Helper Functions ====================
+    #[tokio::test]
+    async fn test_forget_all_memory() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db.clone(), &test_config());
+        let token = login(&router).await;
+
+        let storage = DatabaseMemoryStorage::new(db.storage().clone());
+        for (agent, stale, fresh) in [("agent-a", 2, 1), ("agent-b", 1, 0), ("broken-agent", 1, 0)] {
+            let episodes: Vec<_> = (0..stale + fresh)
+                .map(|i| {
+                    let mut episode = Episode::observation(agent, &format!("{} event {}", agent, i));
+                    if i < stale {
+                        episode.relevance.score = 0.05;
+                    }
+                    episode
+                })
+                .collect();
+            storage.store_episodes(agent, &episodes).await.unwrap();
+        }
+
+        // Corrupt the broken agent's only episode so forgetting it fails
+        let (key, _) = db
+            .storage()
+            .scan_memory(&[])
+            .unwrap()
+            .into_iter()
+            .find(|(key, _)| key.windows(12).any(|w| w == b"broken-agent"))
+            .unwrap();
+        db.storage().write_memory(&[(key, b"corrupt".to_vec())], &[]).unwrap();
+
+        let forget_all = |body: Value, token: Option<&str>| {
+            let mut request = axum::http::Request::post("/admin/memory/forget-all")
+                .header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            request.body(axum::body::Body::from(body.to_string())).unwrap()
+        };
 
-/// Format episode content with intuitive field names based on episode type.
-/// For conversations: user_input, agent_response
-/// For observations: observation
-/// For task execution: action, result
-fn format_episode_content(episode: &Episode) -> Value {
-    let mut content_map = serde_json::Map::new();
+        let (status, body) = call(&router, forget_all(json!({"min_relevance": 0.1}), Some(&token))).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["count"], 3);
+        assert_eq!(body["failed"], 1);
+        assert_eq!(body["forgotten"], 3);
+        let agents = body["agents"].as_array().unwrap();
+        assert_eq!(agents[0]["agent_id"], "agent-a");
+        assert_eq!(agents[0]["forgotten"], 2);
+        assert_eq!(agents[0]["forgotten_by_type"], json!({"Observation": 2}));
+        assert_eq!(agents[1]["agent_id"], "agent-b");
+        assert_eq!(agents[1]["forgotten"], 1);
+        assert_eq!(agents[2]["agent_id"], "broken-agent");
+        assert!(agents[2]["error"].is_string());
+        assert!(agents[2].get("forgotten").is_none());
+
+        // The failure did not stop the agents after it from being processed
+        assert_eq!(storage.episode_count("agent-a").await.unwrap(), 1);
+        assert_eq!(storage.episode_count("agent-b").await.unwrap(), 0);
+
+        // Parameters are validated like the per-agent endpoint
+        let (status, _) = call(&router, forget_all(json!({"min_relevance": 2.0}), Some(&token))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        // Admin credentials are required
+        let response = router.clone().oneshot(forget_all(json!({}), None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
 
-    match &episode.episode_type {
-        EpisodeType::Conversation => {
-            content_map.insert("user_input".to_string(), json!(episode.content.primary));
-            if let Some(ref secondary) = episode.content.secondary {
-                content_map.insert("agent_response".to_string(), json!(secondary));
-            }
-        }
-        EpisodeType::Observation => {
-            content_map.insert("observation".to_string(), json!(episode.content.primary));
-            if let Some(ref secondary) = episode.content.secondary {
-                content_map.insert("details".to_string(), json!(secondary));
-            }
-        }
-        EpisodeType::TaskExecution => {
-            content_map.insert("action".to_string(), json!(episode.content.primary));
-            if let Some(ref secondary) = episode.content.secondary {
-                content_map.insert("result".to_string(), json!(secondary));
-            }
-        }
-        _ => {
-            // For custom/other types, use generic primary/secondary
-            content_map.insert("primary".to_string(), json!(episode.content.primary));
-            if let Some(ref secondary) = episode.content.secondary {
-                content_map.insert("secondary".to_string(), json!(secondary));
+    #[tokio::test]
+    async fn test_checkpoint_and_restore_graph() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        graph
+            .create_constraint(qilbee_graph::Constraint::unique("uniq_name", "Person", "name"))
+            .unwrap();
+        let alice = graph
+            .create_node_with_properties(["Person"], Property::with("name", "Alice"))
+            .unwrap()
+            .id;
+        let bob = graph
+            .create_node_with_properties(["Person"], Property::with("name", "Bob"))
+            .unwrap()
+            .id;
+        graph.create_relationship(alice, "KNOWS", bob).unwrap();
+        let backups = temp_dir.path().join("backups");
+        let router = create_router_with_config(db.clone(), &test_config().backup_dir(&backups));
+        let token = login(&router).await;
+
+        let admin = |path: &str, body: Value, token: Option<&str>| {
+            let mut request = axum::http::Request::post(path).header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
             }
-        }
+            request.body(axum::body::Body::from(body.to_string())).unwrap()
+        };
+        let checkpoint = json!({"path": "daily/social"});
+        let (status, body) = call(&router, admin("/admin/graphs/social/checkpoint", checkpoint.clone(), Some(&token))).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        assert_eq!(body["graph"], "social");
+        assert_eq!(body["path"], "daily/social");
+        assert!(backups.join("daily/social").is_dir());
+
+        // Paths outside the backup directory are rejected
+        let outside = temp_dir.path().join("outside").to_string_lossy().into_owned();
+        for path in [outside.as_str(), "../outside", "daily/../../outside", "./social", ""] {
+            let body = json!({"path": path});
+            let (status, _) = call(&router, admin("/admin/graphs/social/checkpoint", body.clone(), Some(&token))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", path);
+            let (status, _) = call(&router, admin("/admin/graphs/copy/restore", body, Some(&token))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", path);
+        }
+        assert!(!temp_dir.path().join("outside").exists());
+
+        // So are paths leading out of it through a symlink
+        #[cfg(unix)]
+        {
+            let outside = temp_dir.path().join("outside");
+            std::fs::create_dir_all(&outside).unwrap();
+            std::os::unix::fs::symlink(&outside, backups.join("escape")).unwrap();
+            let body = json!({"path": "escape/social"});
+            let (status, _) = call(&router, admin("/admin/graphs/social/checkpoint", body.clone(), Some(&token))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            let (status, _) = call(&router, admin("/admin/graphs/copy/restore", body, Some(&token))).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+            assert!(!outside.join("social").exists());
+        }
+
+        // Writes after the checkpoint are not restored
+        graph.create_node(["Person"]).unwrap();
+
+        let (status, _) = call(&router, admin("/admin/graphs/social/checkpoint", checkpoint.clone(), Some(&token))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let elsewhere = json!({"path": "other"});
+        let (status, _) = call(&router, admin("/admin/graphs/missing/checkpoint", elsewhere, Some(&token))).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, body) = call(&router, admin("/admin/graphs/restored/restore", checkpoint.clone(), Some(&token))).await;
+        assert_eq!(status, StatusCode::CREATED, "{}", body);
+        assert_eq!(body["nodesCreated"], 2);
+        assert_eq!(body["relationshipsCreated"], 1);
+        let restored = db.graph("restored").unwrap();
+        assert_eq!(restored.node_count().unwrap(), 2);
+        assert!(restored.schema().read().unwrap().get_constraint("uniq_name").is_some());
+        let bobs = restored
+            .find_nodes_by_label_and_property("Person", "name", &PropertyValue::String("Bob".into()))
+            .unwrap();
+        assert_eq!(restored.get_relationships(bobs[0].id, Direction::Incoming).unwrap().len(), 1);
+
+        // The target must be a new graph
+        let (status, _) = call(&router, admin("/admin/graphs/social/restore", checkpoint.clone(), Some(&token))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = call(&router, admin("/admin/graphs/copy/restore", json!({"path": "nonexistent"}), Some(&token))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let response = router
+            .clone()
+            .oneshot(admin("/admin/graphs/social/checkpoint", checkpoint, None))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
     }
 
-    // Include context if present
-    if let Some(ref context) = episode.content.context {
-        content_map.insert("context".to_string(), json!(context));
+    #[tokio::test]
+    async fn test_export_episodes() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+        let token = login(&router).await;
+
+        for (episode_type, millis) in [
+            ("conversation", 1000),
+            ("observation", 2000),
+            ("conversation", 3000),
+            ("conversation", 4000),
+            ("observation", 5000),
+        ] {
+            let episode = json!({
+                "agentId": "agent-1",
+                "episodeType": episode_type,
+                "content": {"primary": format!("{} at {}", episode_type, millis)},
+                "eventTime": millis
+            });
+            let (status, _) = memory_request(&router, &token, "POST", "/episodes", episode).await;
+            assert_eq!(status, StatusCode::CREATED);
+        }
+
+        let export = |query: &str| {
+            axum::http::Request::get(format!("/memory/agent-1/export?{}", query))
+                .header("authorization", format!("Bearer {}", token))
+                .body(axum::body::Body::empty())
+                .unwrap()
+        };
+        let response = router
+            .clone()
+            .oneshot(export("from=2000&to=4500&type=conversation&format=jsonl"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "application/x-ndjson");
+
+        // Every line is a JSON document; only matching episodes appear, in event time order
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(bytes.to_vec()).unwrap();
+        assert!(text.ends_with('\n'));
+        let lines: Vec<Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        let times: Vec<_> = lines.iter().map(|l| l["eventTime"].as_i64().unwrap()).collect();
+        assert_eq!(times, vec![3000, 4000]);
+        assert!(lines.iter().all(|l| l["episodeType"] == "Conversation"));
+
+        let response = router.clone().oneshot(export("")).await.unwrap();
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(String::from_utf8(bytes.to_vec()).unwrap().lines().count(), 5);
+
+        let response = router.clone().oneshot(export("format=csv")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
     }
 
-    Value::Object(content_map)
-}
+    #[tokio::test]
+    async fn test_readiness_check() {
+        use tower::ServiceExt;
 
-fn json_to_property_value(value: &Value) -> Option<PropertyValue> {
-    match value {
-        Value::Null => Some(PropertyValue::Null),
-        Value::Bool(b) => Some(PropertyValue::Boolean(*b)),
-        Value::Number(n) => {
-            if let Some(i) = n.as_i64() {
-                Some(PropertyValue::Integer(i))
-            } else if let Some(f) = n.as_f64() {
-                Some(PropertyValue::Float(f))
-            } else {
-                None
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        let request = axum::http::Request::get("/ready").body(axum::body::Body::empty()).unwrap();
+        let response = create_router_with_config(Arc::new(db), &test_config()).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["status"], "ready");
+        assert_eq!(body["checks"]["llm"]["circuit_breaker"]["state"], "closed");
+    }
+
+    #[tokio::test]
+    async fn test_prometheus_metrics() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap();
+        db.graph("test").unwrap().create_node(["Person"]).unwrap();
+        let router = create_router_with_config(Arc::new(db), &test_config());
+        let metrics_request = |peer: Option<&str>, token: Option<&str>| {
+            let mut request = axum::http::Request::get("/metrics");
+            if let Some(token) = token {
+                request = request.header("Authorization", format!("Bearer {}", token));
             }
-        }
-        Value::String(s) => Some(PropertyValue::String(s.clone())),
-        Value::Array(arr) => {
-            let values: Vec<_> = arr.iter().filter_map(json_to_property_value).collect();
-            Some(PropertyValue::Array(values))
-        }
-        Value::Object(obj) => {
-            let mut map = HashMap::new();
-            for (k, v) in obj {
-                if let Some(prop_val) = json_to_property_value(v) {
-                    map.insert(k.clone(), prop_val);
-                }
+            let mut request = request.body(axum::body::Body::empty()).unwrap();
+            if let Some(peer) = peer {
+                request.extensions_mut().insert(ConnectInfo(peer.parse::<SocketAddr>().unwrap()));
             }
-            Some(PropertyValue::Map(map))
+            request
+        };
+
+        // Outside the allowlist a scrape needs credentials
+        let (status, body) = call(&router, metrics_request(Some("10.0.0.5:4000"), None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        assert!(body["error"].as_str().unwrap().contains("metrics require authentication"));
+
+        for cypher in ["MATCH (n:Person) RETURN n", "MATCH (n:Person) SET n.seen = true"] {
+            let request = axum::http::Request::post("/graphs/test/query")
+                .header("content-type", "application/json")
+                .body(axum::body::Body::from(json!({"cypher": cypher}).to_string()))
+                .unwrap();
+            assert_eq!(router.clone().oneshot(request).await.unwrap().status(), StatusCode::OK);
+        }
+
+        let token = login(&router).await;
+        for (peer, token) in [(Some("127.0.0.1:4000"), None), (Some("10.0.0.5:4000"), Some(token.as_str()))] {
+            let response = router.clone().oneshot(metrics_request(peer, token)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()[axum::http::header::CONTENT_TYPE], PROMETHEUS_CONTENT_TYPE);
+
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            let text = String::from_utf8(bytes.to_vec()).unwrap();
+            assert!(text.contains(
+                "qilbee_http_requests_total{method=\"POST\",route=\"/graphs/:name/query\",status=\"200\"} 2\n"
+            ));
+            assert!(text.contains("qilbee_query_duration_seconds_count{operation=\"read\"} 1\n"));
+            assert!(text.contains("qilbee_query_duration_seconds_count{operation=\"write\"} 1\n"));
+            assert!(text.contains("qilbee_agent_memories_active 0\n"));
+            assert!(text.contains("qilbee_storage_estimated_keys{column_family=\"nodes\"}"));
+            assert!(text.contains("qilbee_storage_block_cache_hits_total"));
         }
     }
-}
 
-fn property_value_to_json(value: &PropertyValue) -> Value {
-    match value {
-        PropertyValue::Null => Value::Null,
-        PropertyValue::Boolean(b) => Value::Bool(*b),
-        PropertyValue::Integer(i) => json!(*i),
-        PropertyValue::Float(f) => json!(*f),
-        PropertyValue::String(s) => Value::String(s.clone()),
-        PropertyValue::Array(list) => Value::Array(list.iter().map(property_value_to_json).collect()),
-        PropertyValue::Map(map) => {
-            let mut obj = serde_json::Map::new();
-            for (k, v) in map.iter() {
-                obj.insert(k.clone(), property_value_to_json(v));
+    #[tokio::test]
+    async fn test_unknown_route_and_method_errors() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let router = create_router_with_config(db, &test_config());
+        let send = |method: &str, uri: &str| {
+            let request = axum::http::Request::builder()
+                .method(method)
+                .uri(uri)
+                .body(axum::body::Body::empty())
+                .unwrap();
+            router.clone().oneshot(request)
+        };
+        let json_body = |response: axum::response::Response| async {
+            let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            serde_json::from_slice::<Value>(&bytes).unwrap()
+        };
+
+        // Wrong method on a known route
+        let response = send("DELETE", "/graphs/social/query").await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(response.headers()[axum::http::header::ALLOW], "POST");
+        let body = json_body(response).await;
+        assert_eq!(body["error_code"], "METHOD_NOT_ALLOWED");
+        assert!(body["error"].as_str().unwrap().contains("DELETE"));
+
+        let response = send("PATCH", "/graphs/social/nodes/1").await.unwrap();
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        let allow = response.headers()[axum::http::header::ALLOW].to_str().unwrap().to_string();
+        let mut allowed: Vec<_> = allow.split(',').collect();
+        allowed.sort_unstable();
+        assert_eq!(allowed, ["DELETE", "GET", "HEAD", "PUT"]);
+
+        // Unknown path
+        let response = send("GET", "/no/such/route").await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(response.headers().get(axum::http::header::ALLOW).is_none());
+        let body = json_body(response).await;
+        assert_eq!(body["error_code"], "NOT_FOUND");
+        assert_eq!(body["error"], "No route for GET /no/such/route");
+    }
+
+    #[tokio::test]
+    async fn test_find_nodes_order_by() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let graph = db.graph("social").unwrap();
+        for name in ["Carol", "Alice", "Bob"] {
+            let mut props = Property::new();
+            props.set("name", name);
+            graph.create_node_with_properties(["Person"], props).unwrap();
+        }
+        let router = create_router_with_config(db, &test_config());
+
+        let names = |uri: &'static str| {
+            let router = router.clone();
+            async move {
+                let request = axum::http::Request::builder()
+                    .uri(uri)
+                    .body(axum::body::Body::empty())
+                    .unwrap();
+                let response = router.oneshot(request).await.unwrap();
+                assert_eq!(response.status(), StatusCode::OK);
+                let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+                let body: Value = serde_json::from_slice(&bytes).unwrap();
+                body["nodes"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|node| node["properties"]["name"].as_str().unwrap().to_string())
+                    .collect::<Vec<_>>()
             }
-            Value::Object(obj)
-        }
-        // Handle temporal types - convert to JSON strings/numbers for now
-        PropertyValue::Date(d) => json!(*d),
-        PropertyValue::Time(t) => json!(*t),
-        PropertyValue::DateTime(dt) => json!(*dt),
-        PropertyValue::Duration(dur) => json!(*dur),
-        PropertyValue::Bytes(b) => {
-            // Encode bytes as base64 string
-            Value::String(base64::Engine::encode(&base64::engine::general_purpose::STANDARD, b))
-        }
-        PropertyValue::Point2D { x, y, srid } => {
-            json!({"x": x, "y": y, "srid": srid})
-        }
-        PropertyValue::Point3D { x, y, z, srid } => {
-            json!({"x": x, "y": y, "z": z, "srid": srid})
-        }
+        };
+
+        assert_eq!(
+            names("/graphs/social/nodes?label=Person&orderBy=name").await,
+            ["Alice", "Bob", "Carol"]
+        );
+        // Ordering happens before the limit
+        assert_eq!(
+            names("/graphs/social/nodes?orderBy=name&limit=2").await,
+            ["Alice", "Bob"]
+        );
+        let by_id = names("/graphs/social/nodes?orderBy=id").await;
+        assert_eq!(by_id.len(), 3);
+        assert_eq!(by_id, names("/graphs/social/nodes?orderBy=id").await);
     }
-}
 
-fn json_map_to_property(map: &HashMap<String, Value>) -> Property {
-    let mut props = Property::new();
-    for (k, v) in map {
-        if let Some(prop_val) = json_to_property_value(v) {
-            props.set(k.clone(), prop_val);
-        }
+    #[tokio::test]
+    async fn test_jwks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let jwks_request = || axum::http::Request::get("/.well-known/jwks.json").body(axum::body::Body::empty()).unwrap();
+
+        // Shared-secret tokens have no public key to publish
+        let (status, body) = call(&create_router_with_config(db.clone(), &test_config()), jwks_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({"keys": []}));
+
+        let config = test_config()
+            .jwt_rsa_keys(
+                include_str!("security/testdata/rsa_rotated_private.pem"),
+                include_str!("security/testdata/rsa_rotated_public.pem"),
+            )
+            .jwt_previous_public_key(include_str!("security/testdata/rsa_public.pem"));
+        let router = create_router_with_config(db, &config);
+        let (status, body) = call(&router, jwks_request()).await;
+        assert_eq!(status, StatusCode::OK);
+        let jwks: jsonwebtoken::jwk::JwkSet = serde_json::from_value(body.clone()).unwrap();
+        assert_eq!(jwks.keys.len(), 2);
+        assert_eq!(body["keys"][0]["kty"], "RSA");
+        assert_eq!(body["keys"][0]["alg"], "RS256");
+        assert_eq!(body["keys"][0]["use"], "sig");
+
+        // Issued tokens name a published key, which validates them
+        let token = login(&router).await;
+        let kid = jsonwebtoken::decode_header(&token).unwrap().kid.unwrap();
+        let key = jsonwebtoken::DecodingKey::from_jwk(jwks.find(&kid).unwrap()).unwrap();
+        let validation = jsonwebtoken::Validation::new(jsonwebtoken::Algorithm::RS256);
+        let claims = jsonwebtoken::decode::<crate::security::Claims>(&token, &key, &validation).unwrap().claims;
+        assert_eq!(claims.username, "admin");
+
+        let request = axum::http::Request::get("/api/v1/users")
+            .header("authorization", format!("Bearer {}", token))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(call(&router, request).await.0, StatusCode::OK);
     }
-    props
-}
 
-fn property_to_json_map(props: &Property) -> HashMap<String, Value> {
-    let mut result = HashMap::new();
-    for (k, v) in props.iter() {
-        result.insert(k.clone(), property_value_to_json(v));
+    #[tokio::test]
+    async fn test_openapi_spec() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db = Arc::new(qilbee_graph::Database::open_for_testing(temp_dir.path()).unwrap());
+        let request = axum::http::Request::get("/openapi.json").body(axum::body::Body::empty()).unwrap();
+        let (status, spec) = call(&create_router_with_config(db, &test_config()), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(spec["openapi"], "3.0.3");
+
+        let body_schema = |path: &str, method: &str| {
+            let operation = &spec["paths"][path][method];
+            assert!(operation.is_object(), "missing {} {}", method, path);
+            let reference = operation["requestBody"]["content"]["application/json"]["schema"]["$ref"]
+                .as_str()
+                .unwrap()
+                .rsplit('/')
+                .next()
+                .unwrap()
+                .to_string();
+            spec["components"]["schemas"][&reference].clone()
+        };
+
+        // Query, node and login endpoints with their request bodies
+        let query = body_schema("/graphs/{name}/query", "post");
+        assert_eq!(query["required"], json!(["cypher"]));
+        assert!(query["properties"]["parameters"].is_object());
+        let node = body_schema("/graphs/{name}/nodes", "post");
+        assert_eq!(node["required"], json!(["labels", "properties"]));
+        let login = body_schema("/api/v1/auth/login", "post");
+        assert_eq!(login["required"], json!(["username", "password"]));
+        assert_eq!(spec["paths"]["/api/v1/auth/login"]["post"]["security"], json!([]));
+        assert!(spec["paths"]["/memory/{agent_id}/episodes"]["post"].is_object());
+
+        // Every example request body is accepted by the handler's own type
+        let schemas = &spec["components"]["schemas"];
+        let example = |name: &str| schemas[name]["example"].clone();
+        serde_json::from_value::<QueryRequestJson>(example("QueryRequest")).unwrap();
+        serde_json::from_value::<CreateNodeRequest>(example("CreateNodeRequest")).unwrap();
+        serde_json::from_value::<UpdateNodeRequest>(example("UpdateNodeRequest")).unwrap();
+        serde_json::from_value::<CreateRelationshipRequest>(example("CreateRelationshipRequest")).unwrap();
+        serde_json::from_value::<StoreEpisodeRequest>(example("StoreEpisodeRequest")).unwrap();
+        serde_json::from_value::<SearchEpisodesRequest>(example("SearchEpisodesRequest")).unwrap();
+        serde_json::from_value::<SemanticSearchRequest>(example("SemanticSearchRequest")).unwrap();
+        serde_json::from_value::<HybridSearchRequest>(example("HybridSearchRequest")).unwrap();
+        serde_json::from_value::<ConsolidateMemoryRequest>(example("ConsolidateMemoryRequest")).unwrap();
+        serde_json::from_value::<ForgetMemoryRequest>(example("ForgetMemoryRequest")).unwrap();
+        serde_json::from_value::<LoginRequest>(example("LoginRequest")).unwrap();
+        serde_json::from_value::<LogoutRequest>(example("LogoutRequest")).unwrap();
+        serde_json::from_value::<RefreshRequest>(example("RefreshRequest")).unwrap();
+        serde_json::from_value::<RevokeTokenRequest>(example("RevokeTokenRequest")).unwrap();
+        serde_json::from_value::<RevokeAllTokensRequest>(example("RevokeAllTokensRequest")).unwrap();
+
+        // Every $ref points at a defined schema
+        let text = spec.to_string();
+        for reference in text.split("\"#/components/schemas/").skip(1) {
+            let name = &reference[..reference.find('"').unwrap()];
+            assert!(schemas[name].is_object(), "undefined schema {}", name);
+        }
     }
-    result
 }
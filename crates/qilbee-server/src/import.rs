@@ -0,0 +1,934 @@
+//! Bulk import of nodes and relationships
+//!
+//! Nodes are imported first, in batches that are each written atomically.
+//! Every node carries an external ID, unique within the import, that the
+//! relationships use to name their endpoints; it is mapped to the internal
+//! ID of the created node and not stored. Rows that cannot be parsed or
+//! written are reported with their line number and skipped.
+//!
+//! CSV input starts with a header row. Node files need an `:ID` column and
+//! may have a `:LABEL` column of `;`-separated labels; relationship files
+//! need `:START_ID`, `:TYPE` and `:END_ID` columns. Every other column is a
+//! property:
+//! - empty fields are left out
+//! - unquoted integers, floats and `true`/`false` get those types
+//! - anything else, and every quoted field, is a string
+//!
+//! Newline-delimited JSON has one object per line, nodes as
+//! `{"id": "p1", "labels": [...], "properties": {...}}` and relationships
+//! as `{"startNode": "p1", "type": "KNOWS", "endNode": "p2", "properties": {...}}`.
+//!
+//! A dump written by [`crate::export`] is restored from a single input, its
+//! records told apart by their `kind`.
+
+use crate::http_server::json_map_to_property;
+use qilbee_core::{EntityId, Error, NodeId, Property, PropertyValue, Result};
+use qilbee_graph::{Constraint, Database, Graph, Index};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use tracing::{info, warn};
+
+/// Default number of rows written per batch
+pub const DEFAULT_IMPORT_BATCH_SIZE: usize = 1000;
+
+/// Batches written between flushes of the storage engine
+const FLUSH_INTERVAL_BATCHES: usize = 100;
+
+/// Kinds of dump records
+const DUMP_KINDS: &[&str] = &["schema", "node", "relationship"];
+
+/// Layout of the imported rows
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    #[default]
+    Csv,
+    Ndjson,
+}
+
+/// Input an import row came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportSection {
+    Nodes,
+    Relationships,
+}
+
+/// A row that was skipped
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImportRowError {
+    pub section: ImportSection,
+    /// 1-based line of the row, the line it starts on for multi-line CSV rows
+    pub line: usize,
+    pub message: String,
+}
+
+/// Progress of an import, with the skipped rows in input order
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportReport {
+    pub nodes_created: u64,
+    pub relationships_created: u64,
+    pub errors: Vec<ImportRowError>,
+}
+
+/// Import `nodes`, then `relationships` between them, into `graph`
+///
+/// `report` counts the created entities and collects the skipped rows as the
+/// import goes. A missing CSV header column fails the import before anything
+/// is written; a failed batch write, such as one exceeding a quota, stops it,
+/// leaving the batches written before in place.
+pub fn import_graph(
+    graph: &Graph,
+    format: ImportFormat,
+    nodes: &str,
+    relationships: &str,
+    batch_size: usize,
+    report: &mut ImportReport,
+) -> Result<()> {
+    let node_rows = rows::<NodeRow>(format, nodes)?;
+    let relationship_rows = rows::<RelationshipRow>(format, relationships)?;
+    let result = import_rows(graph, node_rows, relationship_rows, batch_size, report);
+    finish_import(graph, result, report)
+}
+
+/// Restore a dump of another graph into `graph`
+///
+/// The indexes and constraints of the schema record are created first,
+/// skipping those the graph already has, so the constraints hold for the
+/// restored nodes. Nodes and relationships are then imported like NDJSON
+/// rows, their IDs in the dump serving as external IDs. A malformed schema
+/// record fails the import before anything is written.
+pub fn import_dump(graph: &Graph, dump: &str, batch_size: usize, report: &mut ImportReport) -> Result<()> {
+    restore_schema(graph, dump)?;
+    let result = import_rows(
+        graph,
+        dump_rows::<NodeRow>(dump),
+        dump_rows::<RelationshipRow>(dump),
+        batch_size,
+        report,
+    );
+    finish_import(graph, result, report)
+}
+
+/// Copy the schema, nodes and relationships of `source` into `graph`
+///
+/// `source` is read a node at a time from one storage snapshot, as for a
+/// dump, and written like an import with its node IDs as external IDs.
+/// Skipped rows are numbered by their position in `source`.
+pub fn copy_graph(graph: &Graph, source: &Graph, batch_size: usize, report: &mut ImportReport) -> Result<()> {
+    let schema = source
+        .schema()
+        .read()
+        .map_err(|_| Error::Internal("Failed to acquire schema lock".to_string()))?
+        .clone();
+    create_schema(graph, schema.indexes.into_values(), schema.constraints.into_values())?;
+
+    let storage = source.storage();
+    let snapshot = storage.snapshot();
+    let node_rows = snapshot.nodes(source.id())?.enumerate().map(|(index, node)| {
+        let row = node.map_err(|e| e.to_string()).map(|node| NodeRow {
+            id: node.id.as_internal().to_string(),
+            labels: node.labels.iter().map(|label| label.name().to_string()).collect(),
+            properties: node.properties,
+        });
+        (index + 1, row)
+    });
+    let relationship_rows = snapshot.relationships(source.id())?.enumerate().map(|(index, rel)| {
+        let row = rel.map_err(|e| e.to_string()).map(|rel| RelationshipRow {
+            start: rel.source.as_internal().to_string(),
+            rel_type: rel.rel_type.name().to_string(),
+            end: rel.target.as_internal().to_string(),
+            properties: rel.properties,
+        });
+        (index + 1, row)
+    });
+
+    let result = import_rows(graph, Box::new(node_rows), Box::new(relationship_rows), batch_size, report);
+    finish_import(graph, result, report)
+}
+
+/// Copy `source` into the new graph `name` of `database`, as for a restore
+///
+/// If the copy fails, the new graph is cleared and deleted again, so a
+/// failed restore leaves no partly restored graph behind.
+pub fn restore_graph(
+    database: &Database,
+    name: &str,
+    source: &Graph,
+    batch_size: usize,
+    report: &mut ImportReport,
+) -> Result<()> {
+    let graph = database.create_graph(name)?;
+    let result = copy_graph(&graph, source, batch_size, report);
+    if result.is_err() {
+        let removed = graph.clear(batch_size.max(1)).and_then(|()| database.delete_graph(name));
+        if let Err(e) = removed {
+            warn!("Failed to remove partly restored graph {}: {}", name, e);
+        }
+    }
+    result
+}
+
+fn finish_import(graph: &Graph, result: Result<()>, report: &mut ImportReport) -> Result<()> {
+    // Rows failing to parse are reported before those failing to write
+    report.errors.sort_by_key(|error| (error.section, error.line));
+    result?;
+
+    info!(
+        "Imported {} nodes and {} relationships into graph {}, skipping {} rows",
+        report.nodes_created,
+        report.relationships_created,
+        graph.name(),
+        report.errors.len()
+    );
+    Ok(())
+}
+
+/// Create the schema objects of the dump's leading schema record
+fn restore_schema(graph: &Graph, dump: &str) -> Result<()> {
+    #[derive(Deserialize)]
+    struct SchemaRecord {
+        #[serde(default)]
+        indexes: Vec<Index>,
+        #[serde(default)]
+        constraints: Vec<Constraint>,
+    }
+
+    let Some((line, Ok(record))) = json_lines(dump).next() else {
+        return Ok(());
+    };
+    if record.get("kind").and_then(Value::as_str) != Some("schema") {
+        return Ok(());
+    }
+    let schema: SchemaRecord = serde_json::from_value(record).map_err(|e| {
+        Error::ValidationError(format!("Invalid schema record on line {}: {}", line, e))
+    })?;
+    create_schema(graph, schema.indexes, schema.constraints)
+}
+
+/// Create the constraints, then the indexes, the graph does not have yet
+fn create_schema(
+    graph: &Graph,
+    indexes: impl IntoIterator<Item = Index>,
+    constraints: impl IntoIterator<Item = Constraint>,
+) -> Result<()> {
+    for constraint in constraints {
+        graph.create_constraint(constraint)?;
+    }
+    for index in indexes {
+        graph.create_index(index)?;
+    }
+    Ok(())
+}
+
+fn import_rows(
+    graph: &Graph,
+    node_rows: Rows<'_, NodeRow>,
+    relationship_rows: Rows<'_, RelationshipRow>,
+    batch_size: usize,
+    report: &mut ImportReport,
+) -> Result<()> {
+    let mut importer = Importer {
+        graph,
+        report,
+        node_ids: HashMap::new(),
+        batches: 0,
+    };
+    let batch_size = batch_size.max(1);
+
+    let mut batch = Vec::with_capacity(batch_size);
+    for (line, row) in node_rows {
+        match row {
+            Ok(row) => batch.push((line, row)),
+            Err(message) => importer.skip(ImportSection::Nodes, line, message),
+        }
+        if batch.len() == batch_size {
+            importer.write_nodes(&mut batch)?;
+        }
+    }
+    importer.write_nodes(&mut batch)?;
+
+    let mut batch = Vec::with_capacity(batch_size);
+    for (line, row) in relationship_rows {
+        match row {
+            Ok(row) => batch.push((line, row)),
+            Err(message) => importer.skip(ImportSection::Relationships, line, message),
+        }
+        if batch.len() == batch_size {
+            importer.write_relationships(&mut batch)?;
+        }
+    }
+    importer.write_relationships(&mut batch)?;
+
+    if !importer.batches.is_multiple_of(FLUSH_INTERVAL_BATCHES) {
+        graph.storage().flush()?;
+    }
+    Ok(())
+}
+
+struct Importer<'a> {
+    graph: &'a Graph,
+    report: &'a mut ImportReport,
+    /// Internal IDs of the imported nodes by external ID
+    node_ids: HashMap<String, NodeId>,
+    batches: usize,
+}
+
+impl Importer<'_> {
+    fn skip(&mut self, section: ImportSection, line: usize, message: String) {
+        self.report.errors.push(ImportRowError { section, line, message });
+    }
+
+    /// Create a batch of nodes, remembering the IDs they got
+    fn write_nodes(&mut self, batch: &mut Vec<(usize, NodeRow)>) -> Result<()> {
+        let mut keys = Vec::with_capacity(batch.len());
+        let mut rows = Vec::with_capacity(batch.len());
+        let mut batch_ids = HashSet::new();
+        for (line, row) in batch.drain(..) {
+            if self.node_ids.contains_key(&row.id) || !batch_ids.insert(row.id.clone()) {
+                self.skip(ImportSection::Nodes, line, format!("Duplicate node id '{}'", row.id));
+                continue;
+            }
+            keys.push((line, row.id));
+            rows.push((row.labels, row.properties));
+        }
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let results = self.graph.create_nodes_with_properties(rows)?;
+        for ((line, id), result) in keys.into_iter().zip(results) {
+            match result {
+                Ok(node) => {
+                    self.node_ids.insert(id, node.id);
+                    self.report.nodes_created += 1;
+                }
+                Err(e) => self.skip(ImportSection::Nodes, line, e.to_string()),
+            }
+        }
+        self.batch_written()
+    }
+
+    /// Create a batch of relationships between imported nodes
+    fn write_relationships(&mut self, batch: &mut Vec<(usize, RelationshipRow)>) -> Result<()> {
+        let mut lines = Vec::with_capacity(batch.len());
+        let mut rows = Vec::with_capacity(batch.len());
+        for (line, row) in batch.drain(..) {
+            let endpoints = [&row.start, &row.end].map(|id| self.node_ids.get(id).copied().ok_or(id));
+            match endpoints {
+                [Ok(source), Ok(target)] => {
+                    lines.push(line);
+                    rows.push((source, row.rel_type, target, row.properties));
+                }
+                [Err(id), _] | [_, Err(id)] => {
+                    let message = format!("Unknown node id '{}'", id);
+                    self.skip(ImportSection::Relationships, line, message);
+                }
+            }
+        }
+        if rows.is_empty() {
+            return Ok(());
+        }
+
+        let results = self.graph.create_relationships_with_properties(rows)?;
+        for (line, result) in lines.into_iter().zip(results) {
+            match result {
+                Ok(_) => self.report.relationships_created += 1,
+                Err(e) => self.skip(ImportSection::Relationships, line, e.to_string()),
+            }
+        }
+        self.batch_written()
+    }
+
+    /// Flush every few batches so a long import does not pile up memtables
+    fn batch_written(&mut self) -> Result<()> {
+        self.batches += 1;
+        if self.batches.is_multiple_of(FLUSH_INTERVAL_BATCHES) {
+            self.graph.storage().flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// A row of the import input, read from CSV or JSON
+trait ImportRow: Sized {
+    const SECTION: ImportSection;
+
+    /// `kind` of the row's dump records
+    const KIND: &'static str;
+
+    /// Columns the CSV header must have
+    const REQUIRED_COLUMNS: &'static [&'static str];
+
+    fn from_csv(header: &[String], fields: Vec<CsvField>) -> std::result::Result<Self, String>;
+
+    fn from_json(value: Value) -> std::result::Result<Self, String>;
+}
+
+struct NodeRow {
+    id: String,
+    labels: Vec<String>,
+    properties: Property,
+}
+
+impl ImportRow for NodeRow {
+    const SECTION: ImportSection = ImportSection::Nodes;
+    const KIND: &'static str = "node";
+    const REQUIRED_COLUMNS: &'static [&'static str] = &[":ID"];
+
+    fn from_csv(header: &[String], fields: Vec<CsvField>) -> std::result::Result<Self, String> {
+        let mut id = None;
+        let mut labels = Vec::new();
+        let mut properties = Property::new();
+        for (column, field) in header.iter().zip(fields) {
+            match column.as_str() {
+                ":ID" => id = Some(field.text),
+                ":LABEL" => {
+                    labels = field
+                        .text
+                        .split(';')
+                        .map(str::trim)
+                        .filter(|label| !label.is_empty())
+                        .map(String::from)
+                        .collect()
+                }
+                _ => {
+                    if let Some(value) = field.into_value() {
+                        properties.set(column.clone(), value);
+                    }
+                }
+            }
+        }
+
+        let id = id.filter(|id| !id.is_empty()).ok_or("Missing node id")?;
+        Ok(Self { id, labels, properties })
+    }
+
+    fn from_json(value: Value) -> std::result::Result<Self, String> {
+        #[derive(Deserialize)]
+        struct JsonNode {
+            id: Value,
+            #[serde(default)]
+            labels: Vec<String>,
+            #[serde(default)]
+            properties: HashMap<String, Value>,
+        }
+
+        let node: JsonNode = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok(Self {
+            id: external_id(&node.id)?,
+            labels: node.labels,
+            properties: json_map_to_property(&node.properties),
+        })
+    }
+}
+
+struct RelationshipRow {
+    start: String,
+    rel_type: String,
+    end: String,
+    properties: Property,
+}
+
+impl ImportRow for RelationshipRow {
+    const SECTION: ImportSection = ImportSection::Relationships;
+    const KIND: &'static str = "relationship";
+    const REQUIRED_COLUMNS: &'static [&'static str] = &[":START_ID", ":TYPE", ":END_ID"];
+
+    fn from_csv(header: &[String], fields: Vec<CsvField>) -> std::result::Result<Self, String> {
+        let (mut start, mut rel_type, mut end) = (String::new(), String::new(), String::new());
+        let mut properties = Property::new();
+        for (column, field) in header.iter().zip(fields) {
+            match column.as_str() {
+                ":START_ID" => start = field.text,
+                ":TYPE" => rel_type = field.text,
+                ":END_ID" => end = field.text,
+                _ => {
+                    if let Some(value) = field.into_value() {
+                        properties.set(column.clone(), value);
+                    }
+                }
+            }
+        }
+
+        if start.is_empty() || end.is_empty() {
+            return Err("Missing start or end node id".to_string());
+        }
+        if rel_type.is_empty() {
+            return Err("Missing relationship type".to_string());
+        }
+        Ok(Self { start, rel_type, end, properties })
+    }
+
+    fn from_json(value: Value) -> std::result::Result<Self, String> {
+        #[derive(Deserialize)]
+        struct JsonRelationship {
+            #[serde(rename = "startNode")]
+            start_node: Value,
+            #[serde(rename = "type")]
+            rel_type: String,
+            #[serde(rename = "endNode")]
+            end_node: Value,
+            #[serde(default)]
+            properties: HashMap<String, Value>,
+        }
+
+        let rel: JsonRelationship = serde_json::from_value(value).map_err(|e| e.to_string())?;
+        Ok(Self {
+            start: external_id(&rel.start_node)?,
+            rel_type: rel.rel_type,
+            end: external_id(&rel.end_node)?,
+            properties: json_map_to_property(&rel.properties),
+        })
+    }
+}
+
+/// External node ID given as a JSON string or number
+fn external_id(value: &Value) -> std::result::Result<String, String> {
+    match value {
+        Value::String(id) if !id.is_empty() => Ok(id.clone()),
+        Value::Number(id) => Ok(id.to_string()),
+        _ => Err(format!("Invalid node id {}", value)),
+    }
+}
+
+/// Parsed rows with their line numbers
+type Rows<'a, T> = Box<dyn Iterator<Item = (usize, std::result::Result<T, String>)> + 'a>;
+
+/// Rows of `input`, or an error if the CSV header lacks a required column
+fn rows<'a, T: ImportRow + 'a>(format: ImportFormat, input: &'a str) -> Result<Rows<'a, T>> {
+    if format == ImportFormat::Ndjson {
+        return Ok(Box::new(
+            json_lines(input).map(|(line, value)| (line, value.and_then(T::from_json))),
+        ));
+    }
+
+    let mut records = CsvRecords::new(input);
+    let header: Vec<String> = match records.next() {
+        None => return Ok(Box::new(std::iter::empty())),
+        Some((line, Err(message))) => {
+            return Err(Error::ValidationError(format!(
+                "Invalid {} header on line {}: {}",
+                section_name(T::SECTION),
+                line,
+                message
+            )))
+        }
+        Some((_, Ok(fields))) => fields.into_iter().map(|field| field.text.trim().to_string()).collect(),
+    };
+    if let Some(column) = T::REQUIRED_COLUMNS.iter().find(|column| !header.iter().any(|c| c == *column)) {
+        return Err(Error::ValidationError(format!(
+            "The {} header has no {} column",
+            section_name(T::SECTION),
+            column
+        )));
+    }
+
+    Ok(Box::new(records.map(move |(line, record)| {
+        let row = record.and_then(|fields| {
+            if fields.len() != header.len() {
+                return Err(format!("Expected {} fields, found {}", header.len(), fields.len()));
+            }
+            T::from_csv(&header, fields)
+        });
+        (line, row)
+    })))
+}
+
+/// Rows of the dump records of `T::KIND`
+///
+/// Every pass over the dump sees all records, so the malformed ones and
+/// those of unknown kinds are reported by the node pass only.
+fn dump_rows<'a, T: ImportRow + 'a>(dump: &'a str) -> Rows<'a, T> {
+    Box::new(json_lines(dump).filter_map(|(line, record)| {
+        let reports_invalid = T::SECTION == ImportSection::Nodes;
+        let row = match record {
+            Ok(record) => {
+                let kind = record.get("kind").and_then(Value::as_str);
+                if kind == Some(T::KIND) {
+                    T::from_json(record)
+                } else if kind.is_some_and(|kind| DUMP_KINDS.contains(&kind)) || !reports_invalid {
+                    return None;
+                } else {
+                    Err(match kind {
+                        Some(kind) => format!("Unknown record kind '{}'", kind),
+                        None => "Missing record kind".to_string(),
+                    })
+                }
+            }
+            Err(_) if !reports_invalid => return None,
+            Err(message) => Err(message),
+        };
+        Some((line, row))
+    }))
+}
+
+/// Non-blank lines of newline-delimited JSON, parsed, with their line numbers
+fn json_lines(input: &str) -> impl Iterator<Item = (usize, std::result::Result<Value, String>)> + '_ {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index + 1, serde_json::from_str(line).map_err(|e| e.to_string())))
+}
+
+fn section_name(section: ImportSection) -> &'static str {
+    match section {
+        ImportSection::Nodes => "nodes",
+        ImportSection::Relationships => "relationships",
+    }
+}
+
+/// A CSV field and whether it was quoted
+struct CsvField {
+    text: String,
+    quoted: bool,
+}
+
+impl CsvField {
+    /// Property value of the field, `None` for an empty unquoted field
+    fn into_value(self) -> Option<PropertyValue> {
+        if self.quoted {
+            return Some(PropertyValue::String(self.text));
+        }
+        let text = self.text.trim();
+        if text.is_empty() {
+            None
+        } else if let Ok(i) = text.parse::<i64>() {
+            Some(PropertyValue::Integer(i))
+        } else if let Some(f) = text
+            .parse::<f64>()
+            .ok()
+            .filter(|f| f.is_finite() && text.contains(|c: char| c.is_ascii_digit()))
+        {
+            Some(PropertyValue::Float(f))
+        } else if text.eq_ignore_ascii_case("true") || text.eq_ignore_ascii_case("false") {
+            Some(PropertyValue::Boolean(text.eq_ignore_ascii_case("true")))
+        } else {
+            Some(PropertyValue::String(self.text))
+        }
+    }
+}
+
+/// Records of RFC 4180 CSV text, with the line each starts on
+///
+/// Quoted fields may hold commas, newlines and `""` escapes. Blank lines
+/// are skipped. A malformed record is reported and reading resumes on the
+/// next line.
+struct CsvRecords<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> CsvRecords<'a> {
+    fn new(input: &'a str) -> Self {
+        Self { input, pos: 0, line: 1 }
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.input[self.pos..].chars().next()?;
+        self.pos += c.len_utf8();
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.input[self.pos..].chars().next()
+    }
+
+    /// Read one record; the end of input or of the line has not been consumed
+    fn read_record(&mut self) -> std::result::Result<Vec<CsvField>, String> {
+        let mut fields = Vec::new();
+        loop {
+            let mut field = CsvField { text: String::new(), quoted: false };
+            if self.peek_char() == Some('"') {
+                self.next_char();
+                field.quoted = true;
+                loop {
+                    match self.next_char() {
+                        None => return Err("Unterminated quoted field".to_string()),
+                        Some('"') if self.peek_char() == Some('"') => {
+                            self.next_char();
+                            field.text.push('"');
+                        }
+                        Some('"') => break,
+                        Some(c) => field.text.push(c),
+                    }
+                }
+            }
+            while let Some(c) = self.peek_char() {
+                if c == ',' || c == '\n' || (c == '\r' && self.input[self.pos..].starts_with("\r\n")) {
+                    break;
+                }
+                if field.quoted {
+                    return Err("Unexpected text after quoted field".to_string());
+                }
+                field.text.push(c);
+                self.next_char();
+            }
+            fields.push(field);
+
+            if self.peek_char() == Some(',') {
+                self.next_char();
+            } else {
+                return Ok(fields);
+            }
+        }
+    }
+
+    /// Move past the end of the current line
+    fn skip_line(&mut self) {
+        while let Some(c) = self.next_char() {
+            if c == '\n' {
+                break;
+            }
+        }
+    }
+}
+
+impl Iterator for CsvRecords<'_> {
+    type Item = (usize, std::result::Result<Vec<CsvField>, String>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // Skip blank lines
+        while let Some(c) = self.peek_char() {
+            if c == '\n' || c == '\r' {
+                self.next_char();
+            } else {
+                break;
+            }
+        }
+        self.peek_char()?;
+
+        let line = self.line;
+        let record = self.read_record();
+        self.skip_line();
+        Some((line, record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use qilbee_core::Direction;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn records(input: &str) -> Vec<(usize, std::result::Result<Vec<String>, String>)> {
+        CsvRecords::new(input)
+            .map(|(line, record)| (line, record.map(|fields| fields.into_iter().map(|f| f.text).collect())))
+            .collect()
+    }
+
+    #[test]
+    fn test_csv_records() {
+        let strings = |fields: &[&str]| Ok(fields.iter().map(|f| f.to_string()).collect::<Vec<_>>());
+        assert_eq!(
+            records("a,b\r\n\n\"x, \"\"y\"\"\",\n\"multi\nline\",z\n"),
+            vec![
+                (1, strings(&["a", "b"])),
+                (3, strings(&["x, \"y\"", ""])),
+                (4, strings(&["multi\nline", "z"])),
+            ]
+        );
+
+        // A malformed record does not affect the next one
+        let parsed = records("\"a\"b,c\nd,e");
+        assert!(parsed[0].1.is_err());
+        assert_eq!(parsed[1], (2, strings(&["d", "e"])));
+        assert!(records("a,\"b").last().unwrap().1.is_err());
+    }
+
+    #[test]
+    fn test_csv_value_types() {
+        let value = |text: &str, quoted: bool| CsvField { text: text.to_string(), quoted }.into_value();
+        assert_eq!(value("42", false), Some(PropertyValue::Integer(42)));
+        assert_eq!(value("4.5", false), Some(PropertyValue::Float(4.5)));
+        assert_eq!(value("TRUE", false), Some(PropertyValue::Boolean(true)));
+        assert_eq!(value("inf", false), Some(PropertyValue::String("inf".to_string())));
+        assert_eq!(value("42", true), Some(PropertyValue::String("42".to_string())));
+        assert_eq!(value("", true), Some(PropertyValue::String(String::new())));
+        assert_eq!(value(" ", false), None);
+    }
+
+    #[test]
+    fn test_import_csv() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+
+        let nodes = ":ID,:LABEL,name,age\n\
+                     p1,Person;Employee,Alice,30\n\
+                     p2,Person,\"Bob, Jr.\",\n\
+                     p1,Person,Duplicate,1\n\
+                     p3,Person,Carol\n\
+                     ,Person,Nobody,5\n";
+        let relationships = ":START_ID,:TYPE,:END_ID,since\n\
+                             p1,KNOWS,p2,2020\n\
+                             p2,KNOWS,p9,2021\n\
+                             p2,,p1,\n";
+        let mut report = ImportReport::default();
+        import_graph(&graph, ImportFormat::Csv, nodes, relationships, 1, &mut report).unwrap();
+
+        assert_eq!(report.nodes_created, 2);
+        assert_eq!(report.relationships_created, 1);
+        let skipped: Vec<_> = report.errors.iter().map(|e| (e.section, e.line)).collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (ImportSection::Nodes, 4),
+                (ImportSection::Nodes, 5),
+                (ImportSection::Nodes, 6),
+                (ImportSection::Relationships, 3),
+                (ImportSection::Relationships, 4),
+            ]
+        );
+        assert_eq!(report.errors[3].message, "Unknown node id 'p9'");
+
+        let alice = &graph
+            .find_nodes_by_label_and_property("Person", "name", &PropertyValue::String("Alice".to_string()))
+            .unwrap()[0];
+        assert!(alice.has_label_name("Employee"));
+        assert_eq!(alice.get_property("age"), Some(&PropertyValue::Integer(30)));
+        let knows = graph.get_relationships(alice.id, Direction::Outgoing).unwrap();
+        assert_eq!(knows.len(), 1);
+        assert_eq!(knows[0].get_property("since"), Some(&PropertyValue::Integer(2020)));
+        let bob = graph.get_node(knows[0].target).unwrap().unwrap();
+        assert_eq!(bob.get_property("name"), Some(&PropertyValue::String("Bob, Jr.".to_string())));
+        assert_eq!(bob.get_property("age"), None);
+
+        // A header without the required columns fails before writing anything
+        let mut report = ImportReport::default();
+        let result = import_graph(&graph, ImportFormat::Csv, "name\nEve\n", "", 100, &mut report);
+        assert!(matches!(result, Err(Error::ValidationError(_))));
+        assert_eq!(graph.node_count().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_import_ndjson() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+        graph
+            .create_constraint(Constraint::unique("uniq_email", "User", "email"))
+            .unwrap();
+
+        let nodes = r#"{"id": 1, "labels": ["User"], "properties": {"email": "a@example.com"}}
+{"id": 2, "labels": ["User"], "properties": {"email": "a@example.com"}}
+
+{"id": "3", "labels": ["User"], "properties": {"email": "c@example.com", "tags": ["x"]}}
+not json
+"#;
+        let relationships = r#"{"startNode": 1, "type": "FOLLOWS", "endNode": "3", "properties": {"weight": 0.5}}
+{"startNode": 1, "type": "FOLLOWS", "endNode": 2}
+"#;
+        let mut report = ImportReport::default();
+        import_graph(&graph, ImportFormat::Ndjson, nodes, relationships, 100, &mut report).unwrap();
+
+        assert_eq!(report.nodes_created, 2);
+        assert_eq!(report.relationships_created, 1);
+        let skipped: Vec<_> = report.errors.iter().map(|e| (e.section, e.line)).collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (ImportSection::Nodes, 2),
+                (ImportSection::Nodes, 5),
+                (ImportSection::Relationships, 2),
+            ]
+        );
+        assert!(report.errors[0].message.contains("email"), "{}", report.errors[0].message);
+        assert_eq!(graph.relationship_count().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_failed_restore_leaves_no_graph() {
+        let source_dir = TempDir::new().unwrap();
+        let source = Database::open_for_testing(source_dir.path()).unwrap().graph("source").unwrap();
+        source
+            .create_constraint(Constraint::unique("uniq_name", "Person", "name"))
+            .unwrap();
+        for name in ["Alice", "Bob", "Carol"] {
+            source
+                .create_node_with_properties(["Person"], Property::with("name", name))
+                .unwrap();
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = qilbee_graph::DatabaseConfig {
+            max_nodes_per_graph: Some(2),
+            ..Default::default()
+        };
+        let db = Database::open_with_config(temp_dir.path(), config).unwrap();
+
+        // The third node exceeds the quota after two were restored
+        let mut report = ImportReport::default();
+        let error = restore_graph(&db, "restored", &source, 1, &mut report).unwrap_err();
+        assert!(matches!(error, Error::QuotaExceeded(_)), "{}", error);
+        assert_eq!(report.nodes_created, 2);
+        assert!(!db.graph_exists("restored").unwrap());
+
+        // A graph created later with the same name starts empty
+        let graph = db.graph("restored").unwrap();
+        assert_eq!(graph.node_count().unwrap(), 0);
+        assert!(graph.get_all_node_ids().unwrap().is_empty());
+        assert!(graph.schema().read().unwrap().get_constraint("uniq_name").is_none());
+
+        // An existing graph is not deleted when the restore is refused
+        assert!(matches!(
+            restore_graph(&db, "restored", &source, 1, &mut ImportReport::default()),
+            Err(Error::InvalidGraphOperation(_))
+        ));
+        assert!(db.graph_exists("restored").unwrap());
+    }
+
+    #[test]
+    fn test_import_dump() {
+        let temp_dir = TempDir::new().unwrap();
+        let db = Database::open_for_testing(temp_dir.path()).unwrap();
+        let graph = db.graph("test").unwrap();
+
+        let schema = json!({
+            "kind": "schema",
+            "indexes": [],
+            "constraints": [Constraint::unique("uniq_email", "User", "email")],
+        });
+        let dump = format!(
+            r#"{}
+{{"kind": "node", "id": 7, "labels": ["User"], "properties": {{"email": "a@example.com"}}}}
+{{"kind": "node", "id": 8, "labels": ["User"], "properties": {{"email": "a@example.com"}}}}
+{{"kind": "relationship", "id": 1, "startNode": 7, "type": "FOLLOWS", "endNode": 7, "properties": {{}}}}
+{{"kind": "relationship", "id": 2, "startNode": 7, "type": "FOLLOWS", "endNode": 8, "properties": {{}}}}
+{{"kind": "view"}}
+{{"kind": "node", "id": 9
+"#,
+            schema
+        );
+        let mut report = ImportReport::default();
+        import_dump(&graph, &dump, 100, &mut report).unwrap();
+
+        assert!(graph.schema().read().unwrap().get_constraint("uniq_email").is_some());
+        assert_eq!(report.nodes_created, 1);
+        assert_eq!(report.relationships_created, 1);
+        let skipped: Vec<_> = report.errors.iter().map(|e| (e.section, e.line)).collect();
+        assert_eq!(
+            skipped,
+            vec![
+                (ImportSection::Nodes, 3),
+                (ImportSection::Nodes, 6),
+                (ImportSection::Nodes, 7),
+                (ImportSection::Relationships, 5),
+            ]
+        );
+        assert_eq!(report.errors[1].message, "Unknown record kind 'view'");
+        assert_eq!(report.errors[3].message, "Unknown node id '8'");
+
+        let invalid = r#"{"kind": "schema", "indexes": 1}"#;
+        let error = import_dump(&graph, invalid, 100, &mut ImportReport::default()).unwrap_err();
+        assert!(matches!(error, Error::ValidationError(_)), "{}", error);
+    }
+}
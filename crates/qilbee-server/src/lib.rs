@@ -10,9 +10,16 @@
 //! - Agent memory management
 //! - Enterprise-grade security
 
+pub mod arrow_export;
+pub mod bolt_server;
 pub mod config;
+pub mod export;
+pub mod grpc_server;
 pub mod server;
 pub mod http_server;
+pub mod import;
+pub mod metrics;
+pub mod openapi;
 pub mod security;
 
 pub use config::ServerConfig;
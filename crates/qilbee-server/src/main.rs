@@ -23,7 +23,10 @@ async fn main() {
         .map(PathBuf::from)
         .unwrap_or_else(|| PathBuf::from("./data"));
 
-    let config = ServerConfig::new(&data_dir);
+    let mut config = ServerConfig::new(&data_dir);
+    if let Ok(password) = std::env::var("QILBEEDB_ADMIN_PASSWORD") {
+        config = config.admin_password(&password);
+    }
 
     // Create and start server
     let server = match Server::new(config) {
@@ -0,0 +1,384 @@
+//! Prometheus metrics
+//!
+//! [`ServerMetrics`] counts HTTP requests and times query execution;
+//! [`render_prometheus`] combines them with the storage engine's metrics in
+//! the Prometheus text exposition format served at `/metrics`.
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    middleware::Next,
+    response::Response,
+};
+use qilbee_core::{Error, Result};
+use qilbee_storage::{ColumnFamilyMetrics, StorageMetrics};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::net::IpAddr;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Media type of the Prometheus text exposition format
+pub const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Upper bounds, in seconds, of the query latency histogram buckets
+pub const QUERY_LATENCY_BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// A per column family gauge: its name, help text and value
+type ColumnFamilyGauge = (&'static str, &'static str, fn(&ColumnFamilyMetrics) -> u64);
+
+/// Whether a query only reads data or also writes it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum QueryOperation {
+    Read,
+    Write,
+}
+
+impl QueryOperation {
+    /// Operation of a query, given whether it is read only
+    pub fn of(read_only: bool) -> Self {
+        if read_only { Self::Read } else { Self::Write }
+    }
+
+    /// Label value of the operation
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+/// Cumulative counts of observations per latency bucket
+#[derive(Debug, Clone, Default)]
+struct Histogram {
+    /// Observations at or below each bound of [`QUERY_LATENCY_BUCKETS`]
+    buckets: [u64; QUERY_LATENCY_BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(QUERY_LATENCY_BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Request counts and query latencies collected since the server started
+#[derive(Debug, Default)]
+pub struct ServerMetrics {
+    /// Requests per method, route and status code
+    requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+    query_latency: Mutex<BTreeMap<QueryOperation, Histogram>>,
+}
+
+impl ServerMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a request to `route`, the path pattern it matched
+    pub fn record_request(&self, method: &str, route: &str, status: u16) {
+        if let Ok(mut requests) = self.requests.lock() {
+            *requests.entry((method.to_string(), route.to_string(), status)).or_insert(0) += 1;
+        }
+    }
+
+    /// Record how long a query took to execute
+    pub fn record_query(&self, operation: QueryOperation, duration: Duration) {
+        if let Ok(mut latency) = self.query_latency.lock() {
+            latency.entry(operation).or_default().observe(duration.as_secs_f64());
+        }
+    }
+
+    /// Requests counted for a method, route and status code
+    pub fn request_count(&self, method: &str, route: &str, status: u16) -> u64 {
+        self.requests
+            .lock()
+            .ok()
+            .and_then(|requests| requests.get(&(method.to_string(), route.to_string(), status)).copied())
+            .unwrap_or(0)
+    }
+
+    /// Queries timed for an operation
+    pub fn query_count(&self, operation: QueryOperation) -> u64 {
+        self.query_latency
+            .lock()
+            .ok()
+            .and_then(|latency| latency.get(&operation).map(|histogram| histogram.count))
+            .unwrap_or(0)
+    }
+}
+
+/// Middleware counting requests per matched route
+///
+/// Applied as a route layer, so requests that match no route are not
+/// counted and every label value is a route pattern rather than a path.
+pub async fn track_requests(State(metrics): State<Arc<ServerMetrics>>, req: Request, next: Next) -> Response {
+    let method = req.method().to_string();
+    let route = req.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string());
+    let response = next.run(req).await;
+    if let Some(route) = route {
+        metrics.record_request(&method, &route, response.status().as_u16());
+    }
+    response
+}
+
+/// An IP address range in CIDR notation, such as `10.0.0.0/8`
+///
+/// A bare address is a range of one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct IpNetwork {
+    address: IpAddr,
+    prefix_len: u8,
+}
+
+impl IpNetwork {
+    /// Range of addresses sharing the first `prefix_len` bits of `address`
+    pub fn new(address: IpAddr, prefix_len: u8) -> Result<Self> {
+        let max = if address.is_ipv4() { 32 } else { 128 };
+        if prefix_len > max {
+            return Err(Error::Configuration(format!(
+                "Prefix length {} is longer than the {} bits of {}",
+                prefix_len, max, address
+            )));
+        }
+        Ok(Self { address, prefix_len })
+    }
+
+    /// Whether `address` lies in the range
+    ///
+    /// IPv4-mapped IPv6 addresses, as reported by dual-stack listeners,
+    /// match IPv4 ranges.
+    pub fn contains(&self, address: IpAddr) -> bool {
+        let address = match address {
+            IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(address, IpAddr::V4),
+            v4 => v4,
+        };
+        match (self.address, address) {
+            (IpAddr::V4(network), IpAddr::V4(address)) => {
+                let mask = u32::MAX.checked_shl(32 - self.prefix_len as u32).unwrap_or(0);
+                u32::from(network) & mask == u32::from(address) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(address)) => {
+                let mask = u128::MAX.checked_shl(128 - self.prefix_len as u32).unwrap_or(0);
+                u128::from(network) & mask == u128::from(address) & mask
+            }
+            _ => false,
+        }
+    }
+
+    /// The IPv4 and IPv6 loopback ranges
+    pub fn loopback() -> Vec<Self> {
+        vec![
+            Self { address: IpAddr::from([127, 0, 0, 0]), prefix_len: 8 },
+            Self { address: IpAddr::from([0, 0, 0, 0, 0, 0, 0, 1]), prefix_len: 128 },
+        ]
+    }
+}
+
+impl FromStr for IpNetwork {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let invalid = || Error::Configuration(format!("Invalid IP network: {}", s));
+        let (address, prefix_len) = match s.split_once('/') {
+            Some((address, prefix_len)) => (address, Some(prefix_len.parse().map_err(|_| invalid())?)),
+            None => (s, None),
+        };
+        let address: IpAddr = address.parse().map_err(|_| invalid())?;
+        let prefix_len = prefix_len.unwrap_or(if address.is_ipv4() { 32 } else { 128 });
+        Self::new(address, prefix_len)
+    }
+}
+
+impl TryFrom<String> for IpNetwork {
+    type Error = Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<IpNetwork> for String {
+    fn from(network: IpNetwork) -> Self {
+        format!("{}/{}", network.address, network.prefix_len)
+    }
+}
+
+/// Render the server's and the storage engine's metrics in the Prometheus
+/// text format
+pub fn render_prometheus(server: &ServerMetrics, storage: &StorageMetrics, active_agent_memories: usize) -> String {
+    let mut out = String::new();
+
+    counter(&mut out, "qilbee_http_requests_total", "HTTP requests by method, route and status code");
+    if let Ok(requests) = server.requests.lock() {
+        for ((method, route, status), count) in requests.iter() {
+            let status = status.to_string();
+            let labels = [("method", method.as_str()), ("route", route.as_str()), ("status", status.as_str())];
+            sample(&mut out, "qilbee_http_requests_total", &labels, *count as f64);
+        }
+    }
+
+    histogram(&mut out, "qilbee_query_duration_seconds", "Query execution time by operation");
+    if let Ok(latency) = server.query_latency.lock() {
+        for operation in [QueryOperation::Read, QueryOperation::Write] {
+            let histogram = latency.get(&operation).cloned().unwrap_or_default();
+            let op = ("operation", operation.as_str());
+            for (count, bound) in histogram.buckets.iter().zip(QUERY_LATENCY_BUCKETS) {
+                let le = bound.to_string();
+                sample(&mut out, "qilbee_query_duration_seconds_bucket", &[op, ("le", &le)], *count as f64);
+            }
+            let count = histogram.count as f64;
+            sample(&mut out, "qilbee_query_duration_seconds_bucket", &[op, ("le", "+Inf")], count);
+            sample(&mut out, "qilbee_query_duration_seconds_sum", &[op], histogram.sum);
+            sample(&mut out, "qilbee_query_duration_seconds_count", &[op], count);
+        }
+    }
+
+    gauge(&mut out, "qilbee_agent_memories_active", "Agent memories loaded by the server");
+    sample(&mut out, "qilbee_agent_memories_active", &[], active_agent_memories as f64);
+
+    let per_column_family: [ColumnFamilyGauge; 3] = [
+        (
+            "qilbee_storage_estimated_keys",
+            "Estimated number of keys, including overwritten and deleted keys not yet compacted away",
+            |cf| cf.estimated_keys,
+        ),
+        ("qilbee_storage_sst_bytes", "Total size of the SST files", |cf| cf.sst_bytes),
+        (
+            "qilbee_storage_pending_compaction_bytes",
+            "Estimated bytes compaction has to rewrite",
+            |cf| cf.pending_compaction_bytes,
+        ),
+    ];
+    for (name, help, value) in per_column_family {
+        gauge(&mut out, name, help);
+        for cf in &storage.column_families {
+            sample(&mut out, name, &[("column_family", &cf.name)], value(cf) as f64);
+        }
+    }
+
+    counter(&mut out, "qilbee_storage_block_cache_hits_total", "Block reads served from the block cache");
+    sample(&mut out, "qilbee_storage_block_cache_hits_total", &[], storage.block_cache_hits as f64);
+    counter(&mut out, "qilbee_storage_block_cache_misses_total", "Block reads that missed the block cache");
+    sample(&mut out, "qilbee_storage_block_cache_misses_total", &[], storage.block_cache_misses as f64);
+    if let Some(rate) = storage.block_cache_hit_rate() {
+        gauge(&mut out, "qilbee_storage_block_cache_hit_ratio", "Share of block reads served from the block cache");
+        sample(&mut out, "qilbee_storage_block_cache_hit_ratio", &[], rate);
+    }
+
+    out
+}
+
+fn gauge(out: &mut String, name: &str, help: &str) {
+    metadata(out, name, help, "gauge");
+}
+
+fn counter(out: &mut String, name: &str, help: &str) {
+    metadata(out, name, help, "counter");
+}
+
+fn histogram(out: &mut String, name: &str, help: &str) {
+    metadata(out, name, help, "histogram");
+}
+
+fn metadata(out: &mut String, name: &str, help: &str, kind: &str) {
+    let _ = writeln!(out, "# HELP {} {}\n# TYPE {} {}", name, help, name, kind);
+}
+
+/// Write one sample with its labels
+fn sample(out: &mut String, name: &str, labels: &[(&str, &str)], value: f64) {
+    out.push_str(name);
+    if !labels.is_empty() {
+        let labels: Vec<_> = labels
+            .iter()
+            .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(value)))
+            .collect();
+        let _ = write!(out, "{{{}}}", labels.join(","));
+    }
+    let _ = writeln!(out, " {}", value);
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_prometheus() {
+        let storage = StorageMetrics {
+            column_families: vec![ColumnFamilyMetrics {
+                name: "nodes".to_string(),
+                estimated_keys: 12,
+                sst_bytes: 4096,
+                pending_compaction_bytes: 0,
+            }],
+            block_cache_hits: 9,
+            block_cache_misses: 3,
+        };
+        let server = ServerMetrics::new();
+        server.record_request("POST", "/graphs/:name/query", 200);
+        server.record_request("POST", "/graphs/:name/query", 200);
+        server.record_query(QueryOperation::Read, Duration::from_millis(3));
+        server.record_query(QueryOperation::Read, Duration::from_secs(20));
+        let text = render_prometheus(&server, &storage, 2);
+
+        assert!(text.contains(
+            "qilbee_http_requests_total{method=\"POST\",route=\"/graphs/:name/query\",status=\"200\"} 2\n"
+        ));
+        assert!(text.contains("# TYPE qilbee_query_duration_seconds histogram\n"));
+        assert!(text.contains("qilbee_query_duration_seconds_bucket{operation=\"read\",le=\"0.001\"} 0\n"));
+        assert!(text.contains("qilbee_query_duration_seconds_bucket{operation=\"read\",le=\"0.005\"} 1\n"));
+        assert!(text.contains("qilbee_query_duration_seconds_bucket{operation=\"read\",le=\"10\"} 1\n"));
+        assert!(text.contains("qilbee_query_duration_seconds_bucket{operation=\"read\",le=\"+Inf\"} 2\n"));
+        assert!(text.contains("qilbee_query_duration_seconds_count{operation=\"write\"} 0\n"));
+        assert!(text.contains("qilbee_agent_memories_active 2\n"));
+        assert!(text.contains("# TYPE qilbee_storage_sst_bytes gauge\n"));
+        assert!(text.contains("qilbee_storage_estimated_keys{column_family=\"nodes\"} 12\n"));
+        assert!(text.contains("qilbee_storage_sst_bytes{column_family=\"nodes\"} 4096\n"));
+        assert!(text.contains("# TYPE qilbee_storage_block_cache_hits_total counter\n"));
+        assert!(text.contains("qilbee_storage_block_cache_hits_total 9\n"));
+        assert!(text.contains("qilbee_storage_block_cache_hit_ratio 0.75\n"));
+
+        // Without reads there is no hit ratio to report
+        let text = render_prometheus(&server, &StorageMetrics::default(), 0);
+        assert!(!text.contains("hit_ratio"));
+    }
+
+    #[test]
+    fn test_ip_network() {
+        let network: IpNetwork = "10.1.0.0/16".parse().unwrap();
+        assert!(network.contains("10.1.200.3".parse().unwrap()));
+        assert!(!network.contains("10.2.0.1".parse().unwrap()));
+        assert!(network.contains("::ffff:10.1.0.9".parse().unwrap()));
+
+        let host: IpNetwork = "192.168.1.5".parse().unwrap();
+        assert!(host.contains("192.168.1.5".parse().unwrap()));
+        assert!(!host.contains("192.168.1.6".parse().unwrap()));
+        assert_eq!(String::from(host), "192.168.1.5/32");
+
+        let v6: IpNetwork = "fd00::/8".parse().unwrap();
+        assert!(v6.contains("fd12::1".parse().unwrap()));
+        assert!(!v6.contains("10.1.0.1".parse().unwrap()));
+        assert!("0.0.0.0/0".parse::<IpNetwork>().unwrap().contains("8.8.8.8".parse().unwrap()));
+
+        assert!(IpNetwork::loopback().iter().any(|n| n.contains("127.0.0.1".parse().unwrap())));
+        assert!(IpNetwork::loopback().iter().any(|n| n.contains("::1".parse().unwrap())));
+
+        for invalid in ["10.0.0.0/33", "10.0.0/8", "fd00::/129", "10.0.0.0/x"] {
+            assert!(matches!(invalid.parse::<IpNetwork>(), Err(Error::Configuration(_))), "{}", invalid);
+        }
+    }
+}
@@ -0,0 +1,760 @@
+//! OpenAPI description of the HTTP API
+//!
+//! Served at `GET /openapi.json`. The component schemas mirror the request and
+//! response types in [`crate::http_server`]; the tests there deserialize each
+//! request example into its Rust type so the two cannot silently drift apart.
+
+use serde_json::{json, Map, Value};
+
+/// Build the OpenAPI 3.0 document for the graph, memory and auth routes
+pub fn openapi_spec() -> Value {
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "QilbeeDB HTTP API",
+            "version": env!("CARGO_PKG_VERSION"),
+            "description": "Graph, agent memory and authentication endpoints"
+        },
+        "tags": [
+            {"name": "graph", "description": "Graphs, nodes, relationships and Cypher queries"},
+            {"name": "memory", "description": "Episodic agent memory"},
+            {"name": "auth", "description": "Login and token management"}
+        ],
+        "security": [{"bearerAuth": []}, {"apiKeyAuth": []}],
+        "paths": paths(),
+        "components": {
+            "securitySchemes": {
+                "bearerAuth": {"type": "http", "scheme": "bearer", "bearerFormat": "JWT"},
+                "apiKeyAuth": {"type": "apiKey", "in": "header", "name": "X-API-Key"}
+            },
+            "schemas": schemas()
+        }
+    })
+}
+
+fn paths() -> Value {
+    let mut paths = Map::new();
+    let mut add = |path: &str, method: &str, operation: Value| {
+        paths
+            .entry(path)
+            .or_insert_with(|| json!({}))
+            .as_object_mut()
+            .expect("path items are objects")
+            .insert(method.to_string(), operation);
+    };
+
+    // Graph operations
+    let graph = || path_param("name", "Graph name");
+    let node_id = || path_param("id", "Node ID");
+    add("/graphs/{name}", "post", operation("graph", "createGraph", "Create a graph")
+        .params([graph()])
+        .response("201", "Graph created", object_schema(json!({"name": {"type": "string"}})))
+        .response("403", "Graph quota exceeded", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}", "delete", operation("graph", "deleteGraph", "Delete a graph")
+        .params([graph()])
+        .response("200", "Graph deleted", object_schema(json!({"deleted": {"type": "boolean"}})))
+        .build());
+    add("/graphs/{name}/nodes", "post", operation("graph", "createNode", "Create a node")
+        .params([graph()])
+        .body("CreateNodeRequest")
+        .response("201", "Node created", schema_ref("Node"))
+        .response("403", "Node quota or property limit exceeded", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/nodes", "get", operation("graph", "findNodes", "List nodes, optionally by label")
+        .params([
+            graph(),
+            query_param("label", "Only return nodes with this label", json!({"type": "string"})),
+            query_param("limit", "Maximum number of nodes (default 100)", json!({"type": "integer", "minimum": 0})),
+            query_param("orderBy", "`id` for internal ID order, or a property name", json!({"type": "string"})),
+        ])
+        .response("200", "Matching nodes", object_schema(json!({
+            "nodes": {"type": "array", "items": schema_ref("Node")},
+            "count": {"type": "integer"}
+        })))
+        .build());
+    add("/graphs/{name}/nodes", "delete", operation("graph", "deleteNodesByLabel", "Delete every node with a label and its relationships")
+        .params([
+            graph(),
+            query_param("label", "Delete the nodes with this label", json!({"type": "string"})),
+            query_param("confirm", "Must be `true`", json!({"type": "boolean", "default": false})),
+        ])
+        .response("200", "Nodes and relationships deleted", object_schema(json!({
+            "nodesDeleted": {"type": "integer", "format": "int64"},
+            "relationshipsDeleted": {"type": "integer", "format": "int64"}
+        })))
+        .response("400", "`label` is missing or `confirm` is not `true`", schema_ref("Error"))
+        .response("401", "Not authenticated", schema_ref("Error"))
+        .response("403", "The caller lacks the `GraphDelete` permission", schema_ref("Error"))
+        .response("404", "Graph not found", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/nodes/{id}", "get", operation("graph", "getNode", "Get a node")
+        .params([graph(), node_id()])
+        .response("200", "The node", schema_ref("Node"))
+        .response("404", "Node not found", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/nodes/{id}", "put", operation("graph", "updateNode", "Replace a node's labels and properties")
+        .params([graph(), node_id()])
+        .body("UpdateNodeRequest")
+        .response("200", "The updated node", schema_ref("Node"))
+        .response("404", "Node not found", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/nodes/{id}", "delete", operation("graph", "deleteNode", "Delete a node")
+        .params([graph(), node_id()])
+        .response("200", "Node deleted", object_schema(json!({"deleted": {"type": "boolean"}})))
+        .response("404", "Node not found", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/nodes/{id}/relationships", "get", operation("graph", "getRelationships", "List a node's incoming and outgoing relationships")
+        .params([
+            graph(),
+            node_id(),
+            query_param("type", "Only return relationships of this type", json!({"type": "string"})),
+        ])
+        .response("200", "The node's relationships", object_schema(json!({
+            "relationships": {"type": "array", "items": schema_ref("NodeRelationship")},
+            "count": {"type": "integer"}
+        })))
+        .response("404", "Node not found", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/diff", "get", operation("graph", "graphDiff", "List the entities created, updated or deleted between two transaction times")
+        .params([
+            graph(),
+            query_param("from", "Start transaction time in milliseconds, exclusive", json!({"type": "integer", "format": "int64"})),
+            query_param("to", "End transaction time in milliseconds, inclusive", json!({"type": "integer", "format": "int64"})),
+        ])
+        .response("200", "Changes between the two times", schema_ref("GraphDiff"))
+        .response("400", "`from` is after `to`", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/relationships", "post", operation("graph", "createRelationship", "Create a relationship")
+        .params([graph()])
+        .body("CreateRelationshipRequest")
+        .response("201", "Relationship created", schema_ref("Relationship"))
+        .response("404", "An endpoint node does not exist", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/relationships/batch", "post", operation("graph", "createRelationshipsBatch", "Create many relationships")
+        .params([graph()])
+        .body_schema(json!({"type": "array", "items": schema_ref("CreateRelationshipRequest")}))
+        .response("201", "All relationships created", schema_ref("BatchResult"))
+        .response("207", "Some relationships failed; see `results`", schema_ref("BatchResult"))
+        .response("400", "Batch too large", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/import", "post", operation("graph", "importGraph", "Bulk import nodes and relationships")
+        .params([graph()])
+        .body("ImportRequest")
+        .alternate_body("application/x-ndjson", schema_ref("GraphDumpRecord"))
+        .response("201", "All rows imported", schema_ref("ImportReport"))
+        .response("207", "Some rows were skipped; see `errors`", schema_ref("ImportReport"))
+        .response("400", "A CSV header lacks a required column", schema_ref("ImportReport"))
+        .response("403", "The import stopped at the graph's quota", schema_ref("ImportReport"))
+        .build());
+    add("/graphs/{name}/export", "get", operation("graph", "exportGraph", "Dump a whole graph as NDJSON")
+        .params([graph()])
+        .raw_response("200", "The schema, then every node and relationship, one record per line", "application/x-ndjson", schema_ref("GraphDumpRecord"))
+        .response("404", "Graph not found", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/stats", "get", operation("graph", "graphStats", "Count nodes and relationships per label and type")
+        .params([graph()])
+        .response("200", "Node and relationship counts", schema_ref("GraphStats"))
+        .response("404", "Graph not found", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/query", "post", operation("graph", "executeQuery", "Run a Cypher query")
+        .params([
+            graph(),
+            query_param("format", "Result layout", json!({"type": "string", "enum": ["rows", "columns", "arrow"], "default": "rows"})),
+            query_param("noCache", "Execute the query even if its result is cached", json!({"type": "boolean", "default": false})),
+        ])
+        .body("QueryRequest")
+        .response("200", "Query results", schema_ref("QueryResponse"))
+        .response("400", "The query could not be parsed or planned", schema_ref("Error"))
+        .build());
+    add("/graphs/{name}/query/estimate", "post", operation("graph", "estimateQuery", "Plan a query without running it")
+        .params([graph()])
+        .body("QueryRequest")
+        .response("200", "Planner estimates", schema_ref("QueryEstimate"))
+        .response("400", "The query could not be parsed or planned", schema_ref("Error"))
+        .build());
+
+    // Memory operations
+    let agent = || path_param("agent_id", "Agent ID");
+    let episode_id = || path_param("id", "Episode ID");
+    let limit = || query_param("limit", "Maximum number of episodes (default 10)", json!({"type": "integer", "minimum": 0}));
+    add("/memory/{agent_id}/episodes", "post", operation("memory", "storeEpisode", "Store an episode")
+        .params([agent()])
+        .body("StoreEpisodeRequest")
+        .response("201", "Episode stored", object_schema(json!({"episodeId": {"type": "string"}})))
+        .build());
+    add("/memory/{agent_id}/episodes", "get", operation("memory", "listEpisodes", "List episodes, optionally by consolidation status")
+        .params([
+            agent(),
+            query_param("consolidated", "Only return consolidated (`true`) or unconsolidated (`false`) episodes", json!({"type": "boolean"})),
+        ])
+        .response("200", "Valid episodes in event time order", object_schema(json!({
+            "episodes": {"type": "array", "items": schema_ref("Episode")},
+            "count": {"type": "integer"}
+        })))
+        .build());
+    add("/memory/{agent_id}/episodes/batch", "post", operation("memory", "storeEpisodesBatch", "Store many episodes")
+        .params([agent()])
+        .body_schema(json!({"type": "array", "items": schema_ref("StoreEpisodeRequest")}))
+        .response("201", "All episodes stored", schema_ref("BatchResult"))
+        .response("207", "Some episodes failed; see `results`", schema_ref("BatchResult"))
+        .build());
+    add("/memory/{agent_id}/episodes/{id}", "get", operation("memory", "getEpisode", "Get an episode")
+        .params([agent(), episode_id()])
+        .response("200", "The episode", schema_ref("Episode"))
+        .response("404", "Episode not found", schema_ref("Error"))
+        .build());
+    add("/memory/{agent_id}/episodes/{id}/similar", "get", operation("memory", "findSimilarEpisodes", "Find episodes similar to one episode")
+        .params([agent(), episode_id(), limit()])
+        .response("200", "Similar episodes", schema_ref("ScoredEpisodes"))
+        .build());
+    add("/memory/{agent_id}/episodes/recent", "get", operation("memory", "getRecentEpisodes", "Page through recent episodes, newest first")
+        .params([
+            agent(),
+            limit(),
+            query_param("before", "`nextCursor` of the previous page, or a time in milliseconds", json!({"type": "string"})),
+        ])
+        .response("200", "A page of episodes", schema_ref("EpisodePage"))
+        .build());
+    add("/memory/{agent_id}/episodes/as-of", "get", operation("memory", "getEpisodesAsOf", "Episodes as recorded at a point in time")
+        .params([
+            agent(),
+            required(query_param("t", "Transaction time in milliseconds since the Unix epoch", json!({"type": "integer", "format": "int64"}))),
+        ])
+        .response("200", "Episodes known at that time", schema_ref("EpisodePage"))
+        .build());
+    add("/memory/{agent_id}/episodes/search", "post", operation("memory", "searchEpisodes", "Keyword search")
+        .params([agent()])
+        .body("SearchEpisodesRequest")
+        .response("200", "A page of matching episodes", schema_ref("EpisodePage"))
+        .build());
+    add("/memory/{agent_id}/episodes/semantic-search", "post", operation("memory", "semanticSearch", "Vector similarity search")
+        .params([agent()])
+        .body("SemanticSearchRequest")
+        .response("200", "Matching episodes with scores", schema_ref("ScoredEpisodes"))
+        .response("400", "Semantic search is not enabled", schema_ref("Error"))
+        .build());
+    add("/memory/{agent_id}/episodes/hybrid-search", "post", operation("memory", "hybridSearch", "Combined keyword and vector search")
+        .params([agent()])
+        .body("HybridSearchRequest")
+        .response("200", "Matching episodes with scores", schema_ref("ScoredEpisodes"))
+        .build());
+    add("/memory/{agent_id}/episodes/index", "post", operation("memory", "indexMissingEpisodes", "Embed episodes missing from the vector index")
+        .params([agent()])
+        .response("200", "Indexing summary", object_schema(json!({
+            "indexed": {"type": "integer"},
+            "indexedEpisodes": {"type": "integer"}
+        })))
+        .build());
+    add("/memory/{agent_id}/statistics", "get", operation("memory", "getMemoryStatistics", "Memory statistics")
+        .params([agent()])
+        .response("200", "Statistics", object_schema(json!({
+            "totalEpisodes": {"type": "integer"},
+            "episodesByType": {"type": "object"},
+            "oldestEpisode": {"type": "integer", "nullable": true},
+            "newestEpisode": {"type": "integer", "nullable": true},
+            "avgRelevance": {"type": "number"}
+        })))
+        .build());
+    add("/memory/{agent_id}/semantic-search/status", "get", operation("memory", "getSemanticSearchStatus", "Semantic search configuration")
+        .params([agent()])
+        .response("200", "Status", object_schema(json!({
+            "enabled": {"type": "boolean"},
+            "indexedEpisodes": {"type": "integer"},
+            "agentExists": {"type": "boolean"}
+        })))
+        .build());
+    add("/memory/{agent_id}/consolidate", "post", operation("memory", "consolidateMemory", "Decay relevance and evict weak episodes")
+        .params([agent()])
+        .body("ConsolidateMemoryRequest")
+        .response("200", "Consolidation summary", object_schema(json!({"consolidated": {"type": "integer"}})))
+        .build());
+    add("/memory/{agent_id}/forget", "post", operation("memory", "forgetMemory", "Forget low-relevance episodes")
+        .params([agent()])
+        .body("ForgetMemoryRequest")
+        .response("200", "Forget summary", object_schema(json!({"forgotten": {"type": "integer"}})))
+        .build());
+    add("/memory/{agent_id}/export", "get", operation("memory", "exportEpisodes", "Export episodes as NDJSON")
+        .params([
+            agent(),
+            query_param("from", "Earliest event time in milliseconds (inclusive)", json!({"type": "integer", "format": "int64"})),
+            query_param("to", "Latest event time in milliseconds (inclusive)", json!({"type": "integer", "format": "int64"})),
+            query_param("type", "Episode type to export", json!({"type": "string"})),
+            query_param("format", "Export format", json!({"type": "string", "default": "ndjson"})),
+        ])
+        .raw_response("200", "One episode per line", "application/x-ndjson", schema_ref("Episode"))
+        .build());
+    add("/memory/{agent_id}", "delete", operation("memory", "clearMemory", "Delete all of an agent's episodes")
+        .params([agent()])
+        .response("200", "Memory cleared", object_schema(json!({"episodes_cleared": {"type": "integer"}})))
+        .build());
+
+    // Authentication
+    add("/api/v1/auth/login", "post", operation("auth", "login", "Exchange a username and password for tokens")
+        .public()
+        .body("LoginRequest")
+        .response("200", "Logged in", schema_ref("LoginResponse"))
+        .response("401", "Invalid credentials", schema_ref("Error"))
+        .response("429", "Account locked after repeated failures", schema_ref("Error"))
+        .build());
+    add("/api/v1/auth/logout", "post", operation("auth", "logout", "End a user's sessions")
+        .body("LogoutRequest")
+        .response("200", "Logged out", object_schema(json!({"success": {"type": "boolean"}})))
+        .build());
+    add("/api/v1/auth/refresh", "post", operation("auth", "refreshToken", "Exchange a refresh token for a new access token")
+        .public()
+        .body("RefreshRequest")
+        .response("200", "New access token and refresh token", object_schema(json!({
+            "access_token": {"type": "string"},
+            "token_type": {"type": "string"},
+            "expires_in": {"type": "integer"},
+            "refresh_token": {"type": "string"}
+        })))
+        .response("401", "Invalid, expired or reused refresh token", schema_ref("Error"))
+        .build());
+    add("/api/v1/auth/revoke", "post", operation("auth", "revokeToken", "Revoke one access token")
+        .body("RevokeTokenRequest")
+        .response("200", "Token revoked", json!({"type": "object"}))
+        .build());
+    add("/api/v1/auth/revoke-all", "post", operation("auth", "revokeAllTokens", "Revoke every token of a user")
+        .body("RevokeAllTokensRequest")
+        .response("200", "Tokens revoked", json!({"type": "object"}))
+        .build());
+    add("/.well-known/jwks.json", "get", operation("auth", "jwks", "Public keys validating issued access tokens")
+        .public()
+        .response("200", "JSON Web Key Set, empty when tokens are signed with a shared secret", object_schema(json!({
+            "keys": {"type": "array", "items": {"type": "object"}}
+        })))
+        .build());
+
+    Value::Object(paths)
+}
+
+fn schemas() -> Value {
+    let properties_map = json!({"type": "object", "additionalProperties": true});
+    let episode = object_schema(json!({
+        "agentId": {"type": "string"},
+        "episodeId": {"type": "string"},
+        "episodeType": {"type": "string"},
+        "content": {
+            "type": "object",
+            "additionalProperties": true,
+            "description": "Fields depend on the episode type; custom types include their name as `type`"
+        },
+        "eventTime": {"type": "integer", "format": "int64", "description": "Milliseconds since the Unix epoch"},
+        "accessCount": {"type": "integer", "description": "Times the episode was fetched by ID"},
+        "lastAccessed": {"type": "integer", "format": "int64", "description": "Milliseconds since the Unix epoch"},
+        "consolidated": {"type": "boolean", "description": "Whether the episode has been promoted to long-term memory"},
+        "metadata": {"type": "object", "additionalProperties": true}
+    }));
+
+    json!({
+        "Error": object_schema(json!({
+            "error": {"type": "string"},
+            "error_code": {"type": "string"}
+        })),
+        "CreateNodeRequest": {
+            "type": "object",
+            "required": ["labels", "properties"],
+            "properties": {
+                "labels": {"type": "array", "items": {"type": "string"}},
+                "properties": properties_map
+            },
+            "example": {"labels": ["Person"], "properties": {"name": "Alice", "age": 30}}
+        },
+        "UpdateNodeRequest": {
+            "type": "object",
+            "required": ["labels", "properties"],
+            "properties": {
+                "labels": {"type": "array", "items": {"type": "string"}},
+                "properties": properties_map
+            },
+            "example": {"labels": ["Person"], "properties": {"name": "Alice", "age": 31}}
+        },
+        "Node": object_schema(json!({
+            "id": {"type": "integer", "format": "int64"},
+            "labels": {"type": "array", "items": {"type": "string"}},
+            "properties": properties_map
+        })),
+        "CreateRelationshipRequest": {
+            "type": "object",
+            "required": ["startNode", "type", "endNode", "properties"],
+            "properties": {
+                "startNode": {"type": "integer", "format": "int64"},
+                "type": {"type": "string"},
+                "endNode": {"type": "integer", "format": "int64"},
+                "properties": properties_map
+            },
+            "example": {"startNode": 1, "type": "KNOWS", "endNode": 2, "properties": {"since": 2020}}
+        },
+        "Relationship": object_schema(json!({
+            "id": {"type": "integer", "format": "int64"},
+            "type": {"type": "string"},
+            "startNode": {"type": "integer", "format": "int64"},
+            "endNode": {"type": "integer", "format": "int64"},
+            "properties": properties_map
+        })),
+        "NodeRelationship": object_schema(json!({
+            "id": {"type": "integer", "format": "int64"},
+            "type": {"type": "string"},
+            "startNode": {"type": "integer", "format": "int64"},
+            "endNode": {"type": "integer", "format": "int64"},
+            "properties": properties_map,
+            "direction": {
+                "type": "string",
+                "enum": ["outgoing", "incoming"],
+                "description": "`outgoing` when the node is the start node; self-loops are listed once, as outgoing"
+            }
+        })),
+        "GraphDiff": object_schema(json!({
+            "from": {"type": "integer", "format": "int64"},
+            "to": {"type": "integer", "format": "int64"},
+            "nodes": entity_diff_schema("Node"),
+            "relationships": entity_diff_schema("Relationship")
+        })),
+        "BatchResult": object_schema(json!({
+            "results": {
+                "type": "array",
+                "items": object_schema(json!({
+                    "index": {"type": "integer"},
+                    "id": {"type": "integer"},
+                    "episodeId": {"type": "string"},
+                    "error": {"type": "string"}
+                }))
+            },
+            "created": {"type": "integer"},
+            "failed": {"type": "integer"}
+        })),
+        "ImportRequest": {
+            "type": "object",
+            "properties": {
+                "format": {"type": "string", "enum": ["csv", "ndjson"], "default": "csv"},
+                "nodes": {"type": "string", "description": "CSV with `:ID`, optional `:LABEL` and property columns, or one JSON node per line"},
+                "relationships": {"type": "string", "description": "CSV with `:START_ID`, `:TYPE`, `:END_ID` and property columns, or one JSON relationship per line"},
+                "batchSize": {"type": "integer", "minimum": 1, "default": 1000}
+            },
+            "example": {
+                "nodes": ":ID,:LABEL,name\np1,Person,Alice\np2,Person,Bob\n",
+                "relationships": ":START_ID,:TYPE,:END_ID,since\np1,KNOWS,p2,2020\n"
+            }
+        },
+        "ImportReport": object_schema(json!({
+            "nodesCreated": {"type": "integer"},
+            "relationshipsCreated": {"type": "integer"},
+            "errors": {
+                "type": "array",
+                "items": object_schema(json!({
+                    "section": {"type": "string", "enum": ["nodes", "relationships"]},
+                    "line": {"type": "integer"},
+                    "message": {"type": "string"}
+                }))
+            },
+            "error": {"type": "string", "description": "Why the import stopped early"}
+        })),
+        "GraphDumpRecord": object_schema(json!({
+            "kind": {"type": "string", "enum": ["schema", "node", "relationship"]},
+            "indexes": {"type": "array", "items": {"type": "object"}, "description": "Schema records only"},
+            "constraints": {"type": "array", "items": {"type": "object"}, "description": "Schema records only"},
+            "id": {"type": "integer", "format": "int64"},
+            "labels": {"type": "array", "items": {"type": "string"}},
+            "startNode": {"type": "integer", "format": "int64"},
+            "type": {"type": "string"},
+            "endNode": {"type": "integer", "format": "int64"},
+            "properties": {"type": "object", "additionalProperties": true}
+        })),
+        "GraphStats": object_schema(json!({
+            "graph": {"type": "string"},
+            "nodeCount": {"type": "integer", "format": "int64"},
+            "relationshipCount": {"type": "integer", "format": "int64"},
+            "labels": {"type": "object", "additionalProperties": {"type": "integer", "format": "int64"}},
+            "relationshipTypes": {"type": "object", "additionalProperties": {"type": "integer", "format": "int64"}},
+            "estimated": {"type": "boolean", "description": "The per-label and per-type counts were taken by a scan"}
+        })),
+        "QueryRequest": {
+            "type": "object",
+            "required": ["cypher"],
+            "properties": {
+                "cypher": {"type": "string"},
+                "parameters": {"type": "object", "additionalProperties": true, "nullable": true}
+            },
+            "example": {
+                "cypher": "MATCH (p:Person) WHERE p.age > $age RETURN p.name AS name",
+                "parameters": {"age": 25}
+            }
+        },
+        "QueryStats": object_schema(json!({
+            "nodesCreated": {"type": "integer"},
+            "nodesDeleted": {"type": "integer"},
+            "relationshipsCreated": {"type": "integer"},
+            "relationshipsDeleted": {"type": "integer"},
+            "propertiesSet": {"type": "integer"},
+            "indexesAdded": {"type": "integer"},
+            "indexesRemoved": {"type": "integer"},
+            "executionTimeMs": {"type": "integer"},
+            "cached": {"type": "boolean", "description": "Whether the result was served from the query cache"}
+        })),
+        "QueryResponse": {
+            "description": "With `format=rows`, `results` holds one object per row. With `format=columns`, `columns` lists the column names and `data` maps each to its values. `EXPLAIN` and `PROFILE` queries always use rows and add `plan`.",
+            "type": "object",
+            "properties": {
+                "results": {"type": "array", "items": {"type": "object", "additionalProperties": true}},
+                "columns": {"type": "array", "items": {"type": "string"}},
+                "data": {"type": "object", "additionalProperties": {"type": "array", "items": {}}},
+                "stats": schema_ref("QueryStats"),
+                "plan": schema_ref("PlanDescription")
+            }
+        },
+        "PlanDescription": {
+            "description": "Operator tree. `rows`, `dbHits` and `timeMs` are only present for `PROFILE` and exclude the operator's inputs.",
+            "type": "object",
+            "required": ["operator", "estimatedRows", "estimatedCost", "children"],
+            "properties": {
+                "operator": {"type": "string"},
+                "estimatedRows": {"type": "integer"},
+                "estimatedCost": {"type": "number"},
+                "rows": {"type": "integer"},
+                "dbHits": {"type": "integer"},
+                "timeMs": {"type": "number"},
+                "children": {"type": "array", "items": schema_ref("PlanDescription")}
+            }
+        },
+        "QueryEstimate": object_schema(json!({
+            "estimatedRows": {"type": "integer"},
+            "estimatedCost": {"type": "number"},
+            "usesIndex": {"type": "boolean"}
+        })),
+        "StoreEpisodeRequest": {
+            "type": "object",
+            "required": ["agentId", "episodeType", "content"],
+            "properties": {
+                "agentId": {"type": "string"},
+                "episodeType": {"type": "string", "description": "`conversation`, `observation` or `action`"},
+                "content": {"type": "object", "additionalProperties": true},
+                "eventTime": {"type": "integer", "format": "int64", "nullable": true},
+                "metadata": {"type": "object", "additionalProperties": true, "nullable": true}
+            },
+            "example": {
+                "agentId": "assistant",
+                "episodeType": "conversation",
+                "content": {"user_input": "Hello", "agent_response": "Hi there"}
+            }
+        },
+        "Episode": episode,
+        "EpisodePage": object_schema(json!({
+            "episodes": {"type": "array", "items": schema_ref("Episode")},
+            "nextCursor": {"type": "string", "nullable": true}
+        })),
+        "ScoredEpisodes": object_schema(json!({
+            "results": {
+                "type": "array",
+                "items": object_schema(json!({
+                    "episode": schema_ref("Episode"),
+                    "score": {"type": "number"}
+                }))
+            }
+        })),
+        "SearchEpisodesRequest": {
+            "type": "object",
+            "required": ["query"],
+            "properties": {
+                "query": {"type": "string"},
+                "limit": {"type": "integer", "default": 10},
+                "before": {"type": "string", "nullable": true}
+            },
+            "example": {"query": "weather", "limit": 10}
+        },
+        "SemanticSearchRequest": {
+            "type": "object",
+            "required": ["query"],
+            "properties": {
+                "query": {"type": "string"},
+                "limit": {"type": "integer", "default": 10},
+                "minScore": {"type": "number", "nullable": true},
+                "efSearch": {"type": "integer", "nullable": true}
+            },
+            "example": {"query": "questions about the weather", "limit": 5, "minScore": 0.5}
+        },
+        "HybridSearchRequest": {
+            "type": "object",
+            "required": ["query"],
+            "properties": {
+                "query": {"type": "string"},
+                "limit": {"type": "integer", "default": 10},
+                "semanticWeight": {"type": "number", "default": 0.5}
+            },
+            "example": {"query": "weather", "semanticWeight": 0.7}
+        },
+        "ConsolidateMemoryRequest": {
+            "type": "object",
+            "properties": {
+                "min_relevance": {"type": "number", "minimum": 0, "maximum": 1, "default": 0.3},
+                "decay_factor": {"type": "number", "minimum": 0, "maximum": 1, "default": 0.9}
+            },
+            "example": {"min_relevance": 0.3, "decay_factor": 0.9}
+        },
+        "ForgetMemoryRequest": {
+            "type": "object",
+            "properties": {
+                "min_relevance": {"type": "number", "minimum": 0, "maximum": 1, "default": 0.1},
+                "max_age_seconds": {"type": "integer", "nullable": true},
+                "episode_types": {"type": "array", "items": {"type": "string"}}
+            },
+            "example": {"min_relevance": 0.1, "max_age_seconds": 86400}
+        },
+        "LoginRequest": {
+            "type": "object",
+            "required": ["username", "password"],
+            "properties": {
+                "username": {"type": "string"},
+                "password": {"type": "string", "format": "password"}
+            },
+            "example": {"username": "admin", "password": "secret"}
+        },
+        "LoginResponse": object_schema(json!({
+            "access_token": {"type": "string"},
+            "token_type": {"type": "string"},
+            "expires_in": {"type": "integer"},
+            "refresh_token": {"type": "string", "nullable": true},
+            "username": {"type": "string"},
+            "user_id": {"type": "string", "format": "uuid"}
+        })),
+        "LogoutRequest": {
+            "type": "object",
+            "required": ["user_id"],
+            "properties": {"user_id": {"type": "string"}},
+            "example": {"user_id": "2f1c3c0e-0000-0000-0000-000000000000"}
+        },
+        "RefreshRequest": {
+            "type": "object",
+            "required": ["refresh_token"],
+            "properties": {"refresh_token": {"type": "string"}},
+            "example": {"refresh_token": "qilbee_rt_..."}
+        },
+        "RevokeTokenRequest": {
+            "type": "object",
+            "required": ["token"],
+            "properties": {"token": {"type": "string"}},
+            "example": {"token": "eyJhbGciOi..."}
+        },
+        "RevokeAllTokensRequest": {
+            "type": "object",
+            "required": ["user_id"],
+            "properties": {
+                "user_id": {"type": "string"},
+                "reason": {"type": "string", "nullable": true}
+            },
+            "example": {"user_id": "2f1c3c0e-0000-0000-0000-000000000000", "reason": "Password reset"}
+        }
+    })
+}
+
+/// Builder for one operation object
+struct Operation {
+    value: Map<String, Value>,
+}
+
+fn operation(tag: &str, operation_id: &str, summary: &str) -> Operation {
+    let mut value = Map::new();
+    value.insert("tags".to_string(), json!([tag]));
+    value.insert("operationId".to_string(), json!(operation_id));
+    value.insert("summary".to_string(), json!(summary));
+    value.insert("responses".to_string(), json!({}));
+    Operation { value }
+}
+
+impl Operation {
+    fn params<const N: usize>(mut self, params: [Value; N]) -> Self {
+        self.value.insert("parameters".to_string(), Value::Array(params.into()));
+        self
+    }
+
+    /// JSON request body described by a component schema
+    fn body(self, schema: &str) -> Self {
+        self.body_schema(schema_ref(schema))
+    }
+
+    fn body_schema(mut self, schema: Value) -> Self {
+        self.value.insert(
+            "requestBody".to_string(),
+            json!({"required": true, "content": {"application/json": {"schema": schema}}}),
+        );
+        self
+    }
+
+    /// Another media type the request body may be sent as
+    fn alternate_body(mut self, content_type: &str, schema: Value) -> Self {
+        self.value["requestBody"]["content"][content_type] = json!({"schema": schema});
+        self
+    }
+
+    fn response(self, status: &str, description: &str, schema: Value) -> Self {
+        self.raw_response(status, description, "application/json", schema)
+    }
+
+    fn raw_response(mut self, status: &str, description: &str, content_type: &str, schema: Value) -> Self {
+        self.value["responses"][status] = json!({
+            "description": description,
+            "content": {content_type: {"schema": schema}}
+        });
+        self
+    }
+
+    /// Callable without credentials
+    fn public(mut self) -> Self {
+        self.value.insert("security".to_string(), json!([]));
+        self
+    }
+
+    fn build(self) -> Value {
+        Value::Object(self.value)
+    }
+}
+
+fn required(mut parameter: Value) -> Value {
+    parameter["required"] = json!(true);
+    parameter
+}
+
+fn path_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": {"type": "string"}
+    })
+}
+
+fn query_param(name: &str, description: &str, schema: Value) -> Value {
+    json!({
+        "name": name,
+        "in": "query",
+        "required": false,
+        "description": description,
+        "schema": schema
+    })
+}
+
+/// Created, updated and deleted entities of one kind in a graph diff
+fn entity_diff_schema(entity: &str) -> Value {
+    object_schema(json!({
+        "created": {"type": "array", "items": schema_ref(entity)},
+        "updated": {"type": "array", "items": object_schema(json!({
+            "before": schema_ref(entity),
+            "after": schema_ref(entity)
+        }))},
+        "deleted": {
+            "type": "array",
+            "items": schema_ref(entity),
+            "description": "Each entity in its last state before the delete"
+        }
+    }))
+}
+
+fn schema_ref(name: &str) -> Value {
+    json!({"$ref": format!("#/components/schemas/{}", name)})
+}
+
+fn object_schema(properties: Value) -> Value {
+    json!({"type": "object", "properties": properties})
+}
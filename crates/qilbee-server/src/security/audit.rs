@@ -51,6 +51,9 @@ pub enum AuditEventType {
     TokenRevoked,
     AllTokensRevoked,
 
+    // Security incident events
+    SecurityIncident,
+
     // Account lockout events
     AccountLocked,
     AccountUnlocked,
@@ -65,6 +68,9 @@ pub enum AuditEventType {
     MemoryConsolidated,
     MemoryForgotten,
     MemoryCleared,
+
+    // Query events
+    QueryExecuted,
 }
 
 impl std::fmt::Display for AuditEventType {
@@ -90,6 +96,7 @@ impl std::fmt::Display for AuditEventType {
             AuditEventType::RateLimitExceeded => write!(f, "rate_limit_exceeded"),
             AuditEventType::TokenRevoked => write!(f, "token_revoked"),
             AuditEventType::AllTokensRevoked => write!(f, "all_tokens_revoked"),
+            AuditEventType::SecurityIncident => write!(f, "security_incident"),
             AuditEventType::AccountLocked => write!(f, "account_locked"),
             AuditEventType::AccountUnlocked => write!(f, "account_unlocked"),
             AuditEventType::AccountLockoutTriggered => write!(f, "account_lockout_triggered"),
@@ -99,6 +106,7 @@ impl std::fmt::Display for AuditEventType {
             AuditEventType::MemoryConsolidated => write!(f, "memory_consolidated"),
             AuditEventType::MemoryForgotten => write!(f, "memory_forgotten"),
             AuditEventType::MemoryCleared => write!(f, "memory_cleared"),
+            AuditEventType::QueryExecuted => write!(f, "query_executed"),
         }
     }
 }
@@ -696,6 +704,30 @@ impl AuditService {
         );
     }
 
+    /// Log an executed query against a graph
+    ///
+    /// `metadata` describes the query; it must not carry parameter values.
+    pub fn log_query_event(
+        &self,
+        user_id: Option<String>,
+        username: Option<String>,
+        graph: &str,
+        result: AuditResult,
+        metadata: serde_json::Value,
+    ) {
+        self.log_event(
+            AuditEventType::QueryExecuted,
+            user_id,
+            username,
+            AuditEventType::QueryExecuted.to_string(),
+            format!("graph:{}", graph),
+            result,
+            None,
+            None,
+            metadata,
+        );
+    }
+
     /// Get recent audit events
     pub fn get_recent_events(&self, limit: usize) -> Vec<AuditEvent> {
         self.log.get_recent(limit)
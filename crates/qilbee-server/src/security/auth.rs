@@ -1,13 +1,15 @@
 //! Authentication service
 
-use crate::security::{User, UserService, TokenService};
+use crate::security::{User, UserId, UserService, TokenService};
 use super::token::AuthToken;
 use super::token_blacklist::{TokenBlacklist, RevocationReason};
 use qilbee_core::Result;
+use qilbee_storage::StorageEngine;
 use serde::{Deserialize, Serialize};
 use std::sync::{Arc, RwLock};
 use std::collections::HashMap;
 use chrono::{DateTime, Utc, Duration};
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
 /// Authentication credentials
@@ -114,11 +116,45 @@ impl Default for AuthConfig {
     }
 }
 
+/// Issued refresh token
+///
+/// Each refresh is answered with a new refresh token, so a login starts a
+/// chain of refresh tokens: the family. Spent tokens are kept until they
+/// expire, so presenting one again is detected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RefreshTokenRecord {
+    family_id: String,
+    user_id: UserId,
+    username: String,
+    expires_at: DateTime<Utc>,
+    /// Whether the token has already been exchanged
+    used: bool,
+}
+
+/// Outcome of exchanging a refresh token
+#[derive(Debug)]
+pub enum RefreshOutcome {
+    /// The refresh token is spent, and a new access token and refresh token issued
+    Rotated(AuthToken),
+    /// A spent refresh token was presented again, so it may have been
+    /// stolen; all of the user's tokens have been revoked
+    ReuseDetected {
+        user_id: UserId,
+        username: String,
+        /// Family of the reused token, identifying the login it descends from
+        family_id: String,
+    },
+}
+
 /// Main authentication service
 pub struct AuthService {
     user_service: Arc<UserService>,
     token_service: Arc<TokenService>,
     token_blacklist: Arc<TokenBlacklist>,
+    /// Issued refresh tokens, keyed by the SHA-256 hash of the token
+    refresh_tokens: Arc<RwLock<HashMap<String, RefreshTokenRecord>>>,
+    /// Database the refresh tokens are persisted in
+    storage: Option<StorageEngine>,
     sessions: Arc<RwLock<HashMap<String, Session>>>,
     login_attempts: Arc<RwLock<HashMap<String, LoginAttempt>>>,
     config: AuthConfig,
@@ -136,14 +172,49 @@ impl AuthService {
             user_service,
             token_service,
             token_blacklist,
+            refresh_tokens: Arc::new(RwLock::new(HashMap::new())),
+            storage: None,
             sessions: Arc::new(RwLock::new(HashMap::new())),
             login_attempts: Arc::new(RwLock::new(HashMap::new())),
             config,
         }
     }
 
-    /// Authenticate user with credentials and return JWT token
-    pub fn login(&self, credentials: Credentials) -> Result<AuthToken> {
+    /// Builder: persist refresh tokens in the database's refresh token column family
+    ///
+    /// Refresh tokens issued before a restart are loaded, so they can still
+    /// be exchanged and their reuse detected. Those already past expiry are
+    /// deleted.
+    pub fn with_storage(mut self, storage: StorageEngine) -> Result<Self> {
+        let now = Utc::now();
+        let mut refresh_tokens = HashMap::new();
+        let mut expired = Vec::new();
+        for (key, value) in storage.scan_refresh_tokens()? {
+            let record: RefreshTokenRecord = serde_json::from_slice(&value).map_err(|e| {
+                qilbee_core::Error::Internal(format!("Failed to parse refresh token record: {}", e))
+            })?;
+            if record.expires_at > now {
+                let hash = String::from_utf8(key).map_err(|e| {
+                    qilbee_core::Error::Internal(format!("Invalid refresh token key: {}", e))
+                })?;
+                refresh_tokens.insert(hash, record);
+            } else {
+                expired.push(key);
+            }
+        }
+        if !expired.is_empty() {
+            storage.write_refresh_tokens(&[], &expired)?;
+        }
+
+        self.refresh_tokens = Arc::new(RwLock::new(refresh_tokens));
+        self.storage = Some(storage);
+        Ok(self)
+    }
+
+    /// Authenticate user with credentials and return their ID and JWT token
+    ///
+    /// The token carries a refresh token starting a new refresh token family.
+    pub fn login(&self, credentials: Credentials) -> Result<(UserId, AuthToken)> {
         // Check if account is locked
         if self.is_account_locked(&credentials.username) {
             return Err(qilbee_core::Error::AuthenticationFailed(
@@ -166,11 +237,12 @@ impl AuthService {
         };
 
         // Generate JWT token
-        let token = self.token_service.generate_jwt(
+        let mut token = self.token_service.generate_jwt(
             user.id.clone(),
             user.username.clone(),
             user.roles.clone(),
         )?;
+        token.refresh_token = Some(self.issue_refresh_token(&user, Uuid::new_v4().to_string())?);
 
         // Create session
         let session = Session {
@@ -183,7 +255,7 @@ impl AuthService {
 
         self.sessions.write().unwrap().insert(user.id.0.to_string(), session);
 
-        Ok(token)
+        Ok((user.id, token))
     }
 
     /// Validate JWT token and return user
@@ -243,7 +315,7 @@ impl AuthService {
     /// Logout user by invalidating session
     pub fn logout(&self, user_id: &str) -> Result<()> {
         self.sessions.write().unwrap().remove(user_id);
-        Ok(())
+        self.remove_refresh_tokens(user_id)
     }
 
     /// Revoke a specific token
@@ -269,8 +341,9 @@ impl AuthService {
         username: &str,
         reason: RevocationReason,
     ) -> Result<usize> {
-        // Also remove the session
+        // Also remove the session and refresh tokens
         self.sessions.write().unwrap().remove(user_id);
+        self.remove_refresh_tokens(user_id)?;
         self.token_blacklist.revoke_all_for_user(user_id, username, reason)
     }
 
@@ -284,17 +357,107 @@ impl AuthService {
         self.token_blacklist.cleanup_expired()
     }
 
-    /// Refresh JWT token
-    pub fn refresh_token(&self, token: &str) -> Result<AuthToken> {
-        // Validate current token
-        let user = self.validate_token(token)?;
+    /// Exchange a refresh token for a new access token and refresh token
+    ///
+    /// The presented refresh token is spent. Presenting a spent token again
+    /// revokes all of the user's tokens with [`RevocationReason::SecurityIncident`].
+    pub fn refresh_token(&self, refresh_token: &str) -> Result<RefreshOutcome> {
+        let (family_id, user_id) = {
+            let mut refresh_tokens = self.refresh_tokens.write().unwrap();
+            let record = refresh_tokens
+                .get_mut(&refresh_token_hash(refresh_token))
+                .filter(|record| record.expires_at > Utc::now())
+                .ok_or_else(|| qilbee_core::Error::AuthenticationFailed("Invalid refresh token".to_string()))?;
+
+            if record.used {
+                let (user_id, username, family_id) =
+                    (record.user_id, record.username.clone(), record.family_id.clone());
+                drop(refresh_tokens);
+                self.revoke_all_user_tokens(&user_id.0.to_string(), &username, RevocationReason::SecurityIncident)?;
+                return Ok(RefreshOutcome::ReuseDetected { user_id, username, family_id });
+            }
+            // Spend the token in the database first, so it can't be exchanged again after a restart
+            let spent = RefreshTokenRecord { used: true, ..record.clone() };
+            self.store_refresh_tokens(&[(refresh_token_hash(refresh_token), &spent)], &[])?;
+            *record = spent;
+            (record.family_id.clone(), record.user_id)
+        };
+
+        let user = self
+            .user_service
+            .get_user(&user_id)
+            .filter(|user| user.is_active)
+            .ok_or_else(|| qilbee_core::Error::AuthenticationFailed("User account is disabled".to_string()))?;
 
-        // Generate new token
-        self.token_service.generate_jwt(
-            user.id.clone(),
+        let mut token = self.token_service.generate_jwt(
+            user.id,
             user.username.clone(),
             user.roles.clone(),
-        )
+        )?;
+        token.refresh_token = Some(self.issue_refresh_token(&user, family_id)?);
+        Ok(RefreshOutcome::Rotated(token))
+    }
+
+    /// Issue a refresh token in the family `family_id`, dropping expired ones
+    fn issue_refresh_token(&self, user: &User, family_id: String) -> Result<String> {
+        let (token, expires_at) = self.token_service.generate_refresh_token();
+        let record = RefreshTokenRecord {
+            family_id,
+            user_id: user.id,
+            username: user.username.clone(),
+            expires_at,
+            used: false,
+        };
+
+        let hash = refresh_token_hash(&token);
+
+        let mut refresh_tokens = self.refresh_tokens.write().unwrap();
+        let now = Utc::now();
+        let expired: Vec<String> = refresh_tokens
+            .iter()
+            .filter(|(_, record)| record.expires_at <= now)
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        self.store_refresh_tokens(&[(hash.clone(), &record)], &expired)?;
+
+        for hash in &expired {
+            refresh_tokens.remove(hash);
+        }
+        refresh_tokens.insert(hash, record);
+        Ok(token)
+    }
+
+    /// Invalidate every refresh token of a user
+    fn remove_refresh_tokens(&self, user_id: &str) -> Result<()> {
+        let mut refresh_tokens = self.refresh_tokens.write().unwrap();
+        let removed: Vec<String> = refresh_tokens
+            .iter()
+            .filter(|(_, record)| record.user_id.0.to_string() == user_id)
+            .map(|(hash, _)| hash.clone())
+            .collect();
+        self.store_refresh_tokens(&[], &removed)?;
+
+        for hash in &removed {
+            refresh_tokens.remove(hash);
+        }
+        Ok(())
+    }
+
+    /// Write and delete persisted refresh token records, keyed by token hash
+    fn store_refresh_tokens(&self, puts: &[(String, &RefreshTokenRecord)], deletes: &[String]) -> Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        let puts = puts
+            .iter()
+            .map(|(hash, record)| {
+                serde_json::to_vec(record)
+                    .map(|json| (hash.clone().into_bytes(), json))
+                    .map_err(|e| qilbee_core::Error::Internal(format!("Failed to serialize refresh token record: {}", e)))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let deletes: Vec<Vec<u8>> = deletes.iter().map(|hash| hash.clone().into_bytes()).collect();
+        storage.write_refresh_tokens(&puts, &deletes)
     }
 
     /// Check if account is locked due to failed login attempts
@@ -371,6 +534,13 @@ impl AuthService {
     }
 }
 
+/// Hash a refresh token for storage, so stored hashes cannot be used as tokens
+fn refresh_token_hash(token: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(token.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -403,7 +573,8 @@ mod tests {
             password: "SecureP@ss123!".to_string(),
         };
 
-        let token = auth_service.login(credentials).unwrap();
+        let (user_id, token) = auth_service.login(credentials).unwrap();
+        assert_eq!(user_id, user.id);
         assert_eq!(token.token_type, "Bearer");
         assert!(token.expires_in > 0);
     }
@@ -458,7 +629,7 @@ mod tests {
             password: "SecureP@ss123!".to_string(),
         };
 
-        let token = auth_service.login(credentials).unwrap();
+        let (_, token) = auth_service.login(credentials).unwrap();
 
         // Validate token
         let user = auth_service.validate_token(&token.access_token).unwrap();
@@ -562,7 +733,7 @@ mod tests {
             password: "SecureP@ss123!".to_string(),
         };
 
-        let token = auth_service.login(credentials).unwrap();
+        let (_, token) = auth_service.login(credentials).unwrap();
 
         // Token should be valid
         let user = auth_service.validate_token(&token.access_token).unwrap();
@@ -610,7 +781,7 @@ mod tests {
             password: "SecureP@ss123!".to_string(),
         };
 
-        let token = auth_service.login(credentials).unwrap();
+        let (_, token) = auth_service.login(credentials).unwrap();
 
         // Token should be valid
         let user = auth_service.validate_token(&token.access_token).unwrap();
@@ -628,4 +799,164 @@ mod tests {
         assert!(result.is_err());
         assert!(result.unwrap_err().to_string().contains("invalidated"));
     }
+
+    #[test]
+    fn test_refresh_token_rotation() {
+        let user_service = Arc::new(UserService::new());
+        let token_service = Arc::new(TokenService::new("test_secret".to_string()));
+        let auth_service = AuthService::new(
+            user_service.clone(),
+            token_service,
+            create_test_blacklist(),
+            AuthConfig::default(),
+        );
+        let user = user_service
+            .create_user("testuser".to_string(), "test@example.com".to_string(), "SecureP@ss123!")
+            .unwrap();
+        let credentials = Credentials {
+            username: "testuser".to_string(),
+            password: "SecureP@ss123!".to_string(),
+        };
+        let (_, login_token) = auth_service.login(credentials).unwrap();
+        let first = login_token.refresh_token.unwrap();
+
+        // Access tokens and unknown strings are not refresh tokens
+        assert!(auth_service.refresh_token(&login_token.access_token).is_err());
+        assert!(auth_service.refresh_token("qilbee_rt_unknown").is_err());
+
+        // Refreshing spends the refresh token and issues a new one
+        let RefreshOutcome::Rotated(rotated) = auth_service.refresh_token(&first).unwrap() else {
+            panic!("expected a rotated token");
+        };
+        let second = rotated.refresh_token.clone().unwrap();
+        assert_ne!(first, second);
+        assert_eq!(auth_service.validate_token(&rotated.access_token).unwrap().id, user.id);
+
+        // Reusing the spent token revokes every token of the user
+        let outcome = auth_service.refresh_token(&first).unwrap();
+        assert!(matches!(outcome, RefreshOutcome::ReuseDetected { user_id, .. } if user_id == user.id));
+        assert!(auth_service.validate_token(&rotated.access_token).is_err());
+        assert!(auth_service.refresh_token(&second).is_err());
+    }
+
+    #[test]
+    fn test_revocations_survive_restart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user_service = Arc::new(UserService::new());
+        let token_service = Arc::new(TokenService::new("test_secret".to_string()));
+        let start = || {
+            let options = qilbee_storage::StorageOptions::for_testing(temp_dir.path());
+            let storage = qilbee_storage::StorageEngine::open(options).unwrap();
+            AuthService::new(
+                user_service.clone(),
+                token_service.clone(),
+                Arc::new(TokenBlacklist::with_storage(storage).unwrap()),
+                AuthConfig::default(),
+            )
+        };
+        let login = |auth_service: &AuthService, username: &str| {
+            let credentials = Credentials {
+                username: username.to_string(),
+                password: "SecureP@ss123!".to_string(),
+            };
+            auth_service.login(credentials).unwrap().1.access_token
+        };
+
+        let alice = user_service
+            .create_user("alice".to_string(), "alice@example.com".to_string(), "SecureP@ss123!")
+            .unwrap();
+        let bob = user_service
+            .create_user("bob".to_string(), "bob@example.com".to_string(), "SecureP@ss123!")
+            .unwrap();
+
+        let (revoked, valid, invalidated) = {
+            let auth_service = start();
+            let revoked = login(&auth_service, "alice");
+            let valid = login(&auth_service, "alice");
+            let invalidated = login(&auth_service, "bob");
+
+            let claims = auth_service.validate_token_claims(&revoked).unwrap();
+            auth_service.revoke_token(
+                claims.jti,
+                alice.id.0.to_string(),
+                alice.username.clone(),
+                DateTime::from_timestamp(claims.exp as i64, 0).unwrap(),
+                RevocationReason::Logout,
+            ).unwrap();
+            auth_service.revoke_all_user_tokens(
+                &bob.id.0.to_string(),
+                &bob.username,
+                RevocationReason::RevokeAll,
+            ).unwrap();
+            (revoked, valid, invalidated)
+        };
+
+        let auth_service = start();
+        assert!(auth_service.validate_token(&revoked).unwrap_err().to_string().contains("revoked"));
+        assert!(auth_service.validate_token(&invalidated).unwrap_err().to_string().contains("invalidated"));
+        assert_eq!(auth_service.validate_token(&valid).unwrap().username, "alice");
+    }
+
+    #[test]
+    fn test_refresh_tokens_survive_restart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user_service = Arc::new(UserService::new());
+        let token_service = Arc::new(TokenService::new("test_secret".to_string()));
+        let start = || {
+            let options = qilbee_storage::StorageOptions::for_testing(temp_dir.path());
+            let storage = qilbee_storage::StorageEngine::open(options).unwrap();
+            AuthService::new(
+                user_service.clone(),
+                token_service.clone(),
+                Arc::new(TokenBlacklist::with_storage(storage.clone()).unwrap()),
+                AuthConfig::default(),
+            )
+            .with_storage(storage)
+            .unwrap()
+        };
+        let login = |auth_service: &AuthService, username: &str| {
+            let credentials = Credentials {
+                username: username.to_string(),
+                password: "SecureP@ss123!".to_string(),
+            };
+            let (user_id, token) = auth_service.login(credentials).unwrap();
+            (user_id, token.refresh_token.unwrap())
+        };
+        let rotate = |auth_service: &AuthService, refresh_token: &str| match auth_service.refresh_token(refresh_token) {
+            Ok(RefreshOutcome::Rotated(token)) => token.refresh_token.unwrap(),
+            other => panic!("expected a rotated token, got {:?}", other),
+        };
+
+        user_service
+            .create_user("alice".to_string(), "alice@example.com".to_string(), "SecureP@ss123!")
+            .unwrap();
+        user_service
+            .create_user("bob".to_string(), "bob@example.com".to_string(), "SecureP@ss123!")
+            .unwrap();
+
+        let (first, second, logged_out) = {
+            let auth_service = start();
+            let (_, first) = login(&auth_service, "alice");
+            let second = rotate(&auth_service, &first);
+            let (bob, logged_out) = login(&auth_service, "bob");
+            auth_service.logout(&bob.0.to_string()).unwrap();
+            (first, second, logged_out)
+        };
+
+        // Issued tokens can still be exchanged, logged out ones can't
+        {
+            let auth_service = start();
+            assert!(auth_service.refresh_token(&logged_out).is_err());
+            rotate(&auth_service, &second);
+        }
+
+        // Spent tokens stay spent, and reuse revokes the user's refresh tokens
+        let auth_service = start();
+        assert!(matches!(
+            auth_service.refresh_token(&second).unwrap(),
+            RefreshOutcome::ReuseDetected { username, .. } if username == "alice"
+        ));
+        drop(auth_service);
+        assert!(start().refresh_token(&first).is_err());
+    }
 }
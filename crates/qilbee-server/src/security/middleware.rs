@@ -361,8 +361,8 @@ pub async fn global_rate_limit(
 
     tracing::debug!("Global rate limit middleware called for path: {} method: {}", path, method);
 
-    // Skip rate limiting for health check
-    if path == "/health" {
+    // Skip rate limiting for health and readiness checks and metrics scrapes
+    if path == "/health" || path == "/ready" || path == "/metrics" {
         return next.run(req).await;
     }
 
@@ -18,7 +18,7 @@ pub mod security_headers;
 pub mod cors;
 pub mod https;
 
-pub use auth::{AuthService, Credentials, AuthConfig, Session};
+pub use auth::{AuthService, Credentials, AuthConfig, RefreshOutcome, Session};
 pub use rbac::{Permission, Role, RbacService};
 pub use user::{User, UserService, UserId};
 pub use middleware::{AuthMiddleware, require_auth, require_permission, optional_auth, get_user, rate_limit, global_rate_limit};
@@ -28,7 +28,7 @@ pub use bootstrap::{BootstrapService, BootstrapState};
 pub use rate_limit::{RateLimitService, RateLimitPolicy, RateLimitKey, RateLimitInfo, EndpointType, PolicyId};
 pub use token_blacklist::{TokenBlacklist, BlacklistConfig, BlacklistedToken, RevocationReason};
 pub use account_lockout::{AccountLockoutService, LockoutConfig, LockoutStatus};
-pub use password::{validate_password, PasswordPolicy, PasswordValidationResult, PASSWORD_REQUIREMENTS};
+pub use password::{generate_password, validate_password, verify_password_hash, PasswordHashAlgorithm, PasswordHashConfig, PasswordPolicy, PasswordValidationResult, PASSWORD_REQUIREMENTS};
 pub use security_headers::{security_headers_middleware, SecurityHeadersConfig};
 pub use cors::CorsConfig;
 pub use https::{HttpsConfig, TlsConfig, TlsVersion, https_redirect_middleware, check_tls_config};
@@ -1,9 +1,15 @@
-//! Password validation and policy enforcement
+//! Password validation, policy enforcement and hashing
 //!
-//! Provides enterprise-grade password complexity validation.
+//! Provides enterprise-grade password complexity validation, and hashes
+//! passwords with configurable Argon2 or PBKDF2 parameters.
 
+use argon2::password_hash::{rand_core::OsRng, Output, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Argon2, Params, Version};
+use pbkdf2::pbkdf2_hmac;
 use qilbee_core::Result;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 
 /// Minimum password length requirement
 pub const MIN_PASSWORD_LENGTH: usize = 12;
@@ -79,6 +85,20 @@ pub fn validate_password(password: &str) -> Result<()> {
     validate_password_with_policy(password, &PasswordPolicy::default())
 }
 
+/// Generate a random password meeting the default policy
+pub fn generate_password() -> String {
+    const CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789!@#$%^&*-_=+";
+    let mut rng = rand::thread_rng();
+    loop {
+        let password: String = (0..24)
+            .map(|_| CHARSET[rng.gen_range(0..CHARSET.len())] as char)
+            .collect();
+        if validate_password(&password).is_ok() {
+            return password;
+        }
+    }
+}
+
 /// Validate password against a custom policy
 pub fn validate_password_with_policy(password: &str, policy: &PasswordPolicy) -> Result<()> {
     let result = check_password_strength(password, policy);
@@ -139,6 +159,183 @@ pub fn is_password_strong(password: &str) -> bool {
     check_password_strength(password, &policy).is_valid
 }
 
+/// Algorithm passwords are hashed with
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PasswordHashAlgorithm {
+    /// Argon2id, resisting both side-channel and GPU attacks
+    Argon2id,
+    /// Argon2i
+    Argon2i,
+    /// PBKDF2 with HMAC-SHA256, for deployments requiring FIPS-approved algorithms
+    Pbkdf2Sha256,
+}
+
+impl PasswordHashAlgorithm {
+    /// Identifier of the algorithm in PHC strings
+    pub fn identifier(&self) -> &'static str {
+        match self {
+            PasswordHashAlgorithm::Argon2id => "argon2id",
+            PasswordHashAlgorithm::Argon2i => "argon2i",
+            PasswordHashAlgorithm::Pbkdf2Sha256 => "pbkdf2-sha256",
+        }
+    }
+}
+
+/// Password hashing configuration
+///
+/// Hashes are PHC strings such as `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`,
+/// which carry their algorithm and parameters. Hashes made with other
+/// parameters or another supported algorithm therefore still verify.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PasswordHashConfig {
+    /// Hashing algorithm
+    pub algorithm: PasswordHashAlgorithm,
+    /// Memory cost in KiB (Argon2 only)
+    pub memory_cost_kib: u32,
+    /// Argon2 passes over memory, or PBKDF2 rounds
+    pub iterations: u32,
+    /// Degree of parallelism (Argon2 only)
+    pub parallelism: u32,
+}
+
+impl Default for PasswordHashConfig {
+    /// Argon2id with 19 MiB of memory, 2 iterations and 1 lane, as recommended by OWASP
+    fn default() -> Self {
+        Self::argon2id(19_456, 2, 1)
+    }
+}
+
+impl PasswordHashConfig {
+    /// Argon2id with the given memory cost in KiB, iterations and parallelism
+    pub fn argon2id(memory_cost_kib: u32, iterations: u32, parallelism: u32) -> Self {
+        Self {
+            algorithm: PasswordHashAlgorithm::Argon2id,
+            memory_cost_kib,
+            iterations,
+            parallelism,
+        }
+    }
+
+    /// PBKDF2-HMAC-SHA256 with the given number of rounds (OWASP recommends 600,000)
+    pub fn pbkdf2_sha256(iterations: u32) -> Self {
+        Self {
+            algorithm: PasswordHashAlgorithm::Pbkdf2Sha256,
+            memory_cost_kib: 0,
+            iterations,
+            parallelism: 1,
+        }
+    }
+
+    /// Check that passwords can be hashed with these parameters
+    ///
+    /// Users whose hashes need rehashing could not log in with an invalid
+    /// configuration, so it is rejected before it is used.
+    pub fn validate(&self) -> Result<()> {
+        match self.algorithm {
+            PasswordHashAlgorithm::Argon2id | PasswordHashAlgorithm::Argon2i => {
+                Params::new(self.memory_cost_kib, self.iterations, self.parallelism, None)
+                    .map(|_| ())
+                    .map_err(|e| qilbee_core::Error::Configuration(format!("Invalid password hash parameters: {}", e)))
+            }
+            PasswordHashAlgorithm::Pbkdf2Sha256 if self.iterations == 0 => {
+                Err(qilbee_core::Error::Configuration("PBKDF2 needs at least one iteration".to_string()))
+            }
+            PasswordHashAlgorithm::Pbkdf2Sha256 => Ok(()),
+        }
+    }
+
+    /// Hash a password with a random salt, returning a PHC string
+    pub fn hash(&self, password: &str) -> Result<String> {
+        self.validate()?;
+        let salt = SaltString::generate(&mut OsRng);
+        let argon2_algorithm = match self.algorithm {
+            PasswordHashAlgorithm::Argon2id => argon2::Algorithm::Argon2id,
+            PasswordHashAlgorithm::Argon2i => argon2::Algorithm::Argon2i,
+            PasswordHashAlgorithm::Pbkdf2Sha256 => {
+                let mut salt_bytes = [0u8; 64];
+                let salt_bytes = salt
+                    .as_salt()
+                    .decode_b64(&mut salt_bytes)
+                    .map_err(|e| qilbee_core::Error::Internal(format!("Failed to hash password: {}", e)))?;
+                let mut derived = [0u8; 32];
+                pbkdf2_hmac::<Sha256>(password.as_bytes(), salt_bytes, self.iterations, &mut derived);
+                let output = Output::new(&derived)
+                    .map_err(|e| qilbee_core::Error::Internal(format!("Failed to hash password: {}", e)))?;
+                return Ok(format!(
+                    "${}$i={},l={}${}${}",
+                    self.algorithm.identifier(),
+                    self.iterations,
+                    derived.len(),
+                    salt.as_str(),
+                    output
+                ));
+            }
+        };
+
+        let params = Params::new(self.memory_cost_kib, self.iterations, self.parallelism, None)
+            .map_err(|e| qilbee_core::Error::Configuration(format!("Invalid password hash parameters: {}", e)))?;
+        let password_hash = Argon2::new(argon2_algorithm, Version::V0x13, params)
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|e| qilbee_core::Error::Internal(format!("Failed to hash password: {}", e)))?
+            .to_string();
+
+        Ok(password_hash)
+    }
+
+    /// Check if a hash was made with another algorithm or other parameters,
+    /// and should be replaced by a fresh one
+    pub fn needs_rehash(&self, hash: &str) -> bool {
+        let Ok(parsed) = PasswordHash::new(hash) else {
+            return true;
+        };
+        if parsed.algorithm.as_str() != self.algorithm.identifier() {
+            return true;
+        }
+
+        let param = |name: &str| parsed.params.get_decimal(name);
+        match self.algorithm {
+            PasswordHashAlgorithm::Argon2id | PasswordHashAlgorithm::Argon2i => {
+                parsed.version != Some(Version::V0x13.into())
+                    || param("m") != Some(self.memory_cost_kib)
+                    || param("t") != Some(self.iterations)
+                    || param("p") != Some(self.parallelism)
+            }
+            PasswordHashAlgorithm::Pbkdf2Sha256 => param("i") != Some(self.iterations),
+        }
+    }
+}
+
+/// Verify a password against a PHC string hash
+///
+/// The algorithm and parameters are taken from the hash, so hashes from
+/// earlier configurations still verify. Argon2 (any variant) and
+/// PBKDF2-HMAC-SHA256 hashes are supported.
+pub fn verify_password_hash(password: &str, hash: &str) -> Result<bool> {
+    let parsed = PasswordHash::new(hash)
+        .map_err(|e| qilbee_core::Error::Internal(format!("Invalid password hash: {}", e)))?;
+
+    match parsed.algorithm.as_str() {
+        "argon2id" | "argon2i" | "argon2d" => Ok(Argon2::default().verify_password(password.as_bytes(), &parsed).is_ok()),
+        "pbkdf2-sha256" => {
+            let invalid = || qilbee_core::Error::Internal("Invalid PBKDF2 password hash".to_string());
+            let iterations = parsed.params.get_decimal("i").filter(|&i| i > 0).ok_or_else(invalid)?;
+            let (salt, expected) = parsed.salt.zip(parsed.hash).ok_or_else(invalid)?;
+            let mut salt_bytes = [0u8; 64];
+            let salt_bytes = salt.decode_b64(&mut salt_bytes).map_err(|_| invalid())?;
+
+            let mut derived = vec![0u8; expected.len()];
+            pbkdf2_hmac::<Sha256>(password.as_bytes(), salt_bytes, iterations, &mut derived);
+            // Output compares in constant time
+            Ok(Output::new(&derived).is_ok_and(|output| output == expected))
+        }
+        algorithm => Err(qilbee_core::Error::Internal(format!(
+            "Unsupported password hash algorithm: {}",
+            algorithm
+        ))),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,6 +407,13 @@ mod tests {
         assert!(!is_password_strong("weak"));
     }
 
+    #[test]
+    fn test_generate_password() {
+        let password = generate_password();
+        assert!(validate_password(&password).is_ok());
+        assert_ne!(generate_password(), password);
+    }
+
     #[test]
     fn test_custom_policy() {
         let lenient_policy = PasswordPolicy {
@@ -225,4 +429,74 @@ mod tests {
         assert!(validate_password_with_policy("simple123", &lenient_policy).is_ok());
         assert!(validate_password("simple123").is_err());
     }
+
+    #[test]
+    fn test_pbkdf2_sha256() {
+        fn derive(password: &str, salt: &str, iterations: u32, length: usize) -> String {
+            let mut output = vec![0u8; length];
+            pbkdf2_hmac::<Sha256>(password.as_bytes(), salt.as_bytes(), iterations, &mut output);
+            output.iter().map(|b| format!("{:02x}", b)).collect()
+        }
+
+        // RFC 7914 and common PBKDF2-HMAC-SHA256 test vectors
+        assert_eq!(
+            derive("password", "salt", 1, 32),
+            "120fb6cffcf8b32c43e7225256c4f837a86548c92ccc35480805987cb70be17b"
+        );
+        assert_eq!(
+            derive("passwd", "salt", 1, 64),
+            "55ac046e56e3089fec1691c22544b605f94185216dde0465e68b9d57c20dacbc\
+             49ca9cccf179b645991664b39d77ef317c71b845b1e30bd509112041d3a19783"
+        );
+
+        // Hashes stored before are still accepted
+        let stored = "$pbkdf2-sha256$i=1000,l=32$cWlsYmVlLXNhbHQtMDAwMQ$wtIVef6X/HHSBD9vhA09qw+I9a1cDIy1+Mr3NABgLN8";
+        assert!(verify_password_hash("SecurePass123!", stored).unwrap());
+        assert!(!verify_password_hash("WrongPass123!", stored).unwrap());
+    }
+
+    #[test]
+    fn test_validate_password_hash_config() {
+        assert!(PasswordHashConfig::default().validate().is_ok());
+        assert!(PasswordHashConfig::pbkdf2_sha256(600_000).validate().is_ok());
+        assert!(PasswordHashConfig::argon2id(0, 1, 1).validate().is_err());
+        assert!(PasswordHashConfig::argon2id(19_456, 0, 1).validate().is_err());
+        assert!(PasswordHashConfig::argon2id(19_456, 2, 0).validate().is_err());
+        assert!(PasswordHashConfig::pbkdf2_sha256(0).validate().is_err());
+    }
+
+    #[test]
+    fn test_password_hash_config() {
+        let password = "SecurePass123!";
+
+        let argon2 = PasswordHashConfig::argon2id(1024, 1, 1);
+        let hash = argon2.hash(password).unwrap();
+        assert!(hash.starts_with("$argon2id$v=19$m=1024,t=1,p=1$"), "{}", hash);
+        assert!(verify_password_hash(password, &hash).unwrap());
+        assert!(!verify_password_hash("WrongPass123!", &hash).unwrap());
+        assert!(!argon2.needs_rehash(&hash));
+        assert!(PasswordHashConfig::argon2id(2048, 1, 1).needs_rehash(&hash));
+        assert!(PasswordHashConfig::default().needs_rehash(&hash));
+
+        // The default parameters are those of `Argon2::default()`
+        let default_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &SaltString::generate(&mut OsRng))
+            .unwrap()
+            .to_string();
+        assert!(!PasswordHashConfig::default().needs_rehash(&default_hash));
+
+        let pbkdf2 = PasswordHashConfig::pbkdf2_sha256(1000);
+        let hash = pbkdf2.hash(password).unwrap();
+        assert!(hash.starts_with("$pbkdf2-sha256$i=1000,l=32$"), "{}", hash);
+        assert!(verify_password_hash(password, &hash).unwrap());
+        assert!(!verify_password_hash("WrongPass123!", &hash).unwrap());
+        assert!(!pbkdf2.needs_rehash(&hash));
+        assert!(PasswordHashConfig::pbkdf2_sha256(2000).needs_rehash(&hash));
+        assert!(argon2.needs_rehash(&hash));
+
+        // Unknown algorithms and invalid parameters are errors
+        assert!(verify_password_hash(password, "$scrypt$ln=16,r=8,p=1$c2FsdA$aGFzaA").is_err());
+        assert!(PasswordHashConfig::argon2id(0, 1, 1).hash(password).is_err());
+        assert!(PasswordHashConfig::pbkdf2_sha256(0).hash(password).is_err());
+    }
 }
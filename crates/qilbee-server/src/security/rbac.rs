@@ -58,6 +58,20 @@ pub enum Permission {
     AuditManage,
 }
 
+impl Permission {
+    /// Permission needed to run a Cypher query
+    ///
+    /// Read-only queries need `GraphRead`. Queries that write data or change
+    /// the schema need `GraphUpdate`.
+    pub fn for_query(read_only: bool) -> Self {
+        if read_only {
+            Permission::GraphRead
+        } else {
+            Permission::GraphUpdate
+        }
+    }
+}
+
 /// Predefined roles with associated permissions
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Role {
@@ -286,6 +300,15 @@ mod tests {
         assert!(!read_perms.contains(&Permission::GraphCreate));
     }
 
+    #[test]
+    fn test_query_permission() {
+        assert_eq!(Permission::for_query(true), Permission::GraphRead);
+        assert_eq!(Permission::for_query(false), Permission::GraphUpdate);
+        assert!(Role::Read.permissions().contains(&Permission::for_query(true)));
+        assert!(!Role::DataScientist.permissions().contains(&Permission::for_query(false)));
+        assert!(Role::Developer.permissions().contains(&Permission::for_query(false)));
+    }
+
     #[test]
     fn test_rbac_service() {
         let rbac = RbacService::new();
@@ -1,7 +1,13 @@
 //! JWT and API Key token management
 
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
 use chrono::{DateTime, Duration, Utc};
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use jsonwebtoken::jwk::{
+    AlgorithmParameters, CommonParameters, Jwk, JwkSet, KeyAlgorithm, PublicKeyUse, RSAKeyParameters, RSAKeyType,
+};
+use jsonwebtoken::{decode, decode_header, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use simple_asn1::ASN1Block;
 use rand::{distributions::Alphanumeric, Rng};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -47,25 +53,146 @@ pub struct ApiKey {
     pub is_active: bool,
 }
 
+/// A key JWTs are validated with
+struct VerificationKey {
+    /// ID stamped into the header of the JWTs the key validates, `None` for
+    /// a shared secret
+    kid: Option<String>,
+    decoding_key: DecodingKey,
+    /// Public key as published in the JWKS, for RSA keys
+    jwk: Option<Jwk>,
+}
+
+/// Signing and validation keys of a token service
+struct JwtKeys {
+    encoding_key: EncodingKey,
+    signing_kid: Option<String>,
+    /// Keys JWTs are validated with, including the signing key's
+    verification_keys: Vec<VerificationKey>,
+}
+
 /// Token service
 pub struct TokenService {
-    jwt_secret: String,
+    /// Algorithm JWTs are signed with; tokens signed with any other are rejected
+    jwt_algorithm: Algorithm,
+    keys: RwLock<JwtKeys>,
     jwt_expiration_secs: u64,
     refresh_expiration_secs: u64,
     api_keys: Arc<RwLock<HashMap<String, ApiKey>>>,
 }
 
 impl TokenService {
-    /// Create new token service
+    /// Create new token service signing HS256 JWTs with a shared secret
     pub fn new(jwt_secret: String) -> Self {
+        let keys = JwtKeys {
+            encoding_key: EncodingKey::from_secret(jwt_secret.as_bytes()),
+            signing_kid: None,
+            verification_keys: vec![VerificationKey {
+                kid: None,
+                decoding_key: DecodingKey::from_secret(jwt_secret.as_bytes()),
+                jwk: None,
+            }],
+        };
+        Self::with_keys(Algorithm::HS256, keys)
+    }
+
+    /// Create new token service signing RS256 JWTs with an RSA private key
+    ///
+    /// Both keys are PEM encoded. JWTs are validated with the public key,
+    /// which other services can hold to validate tokens without being able
+    /// to issue them; see [`Self::jwks`]. JWT headers carry the key's ID,
+    /// its RFC 7638 thumbprint.
+    pub fn with_rsa_keys(private_pem: &[u8], public_pem: &[u8]) -> Result<Self> {
+        let encoding_key = rsa_encoding_key(private_pem)?;
+        let verification_key = rsa_verification_key(public_pem)?;
+        let keys = JwtKeys {
+            encoding_key,
+            signing_kid: verification_key.kid.clone(),
+            verification_keys: vec![verification_key],
+        };
+        Ok(Self::with_keys(Algorithm::RS256, keys))
+    }
+
+    fn with_keys(jwt_algorithm: Algorithm, keys: JwtKeys) -> Self {
         Self {
-            jwt_secret,
+            jwt_algorithm,
+            keys: RwLock::new(keys),
             jwt_expiration_secs: 86400,      // 24 hours
             refresh_expiration_secs: 2592000, // 30 days
             api_keys: Arc::new(RwLock::new(HashMap::new())),
         }
     }
 
+    /// Sign JWTs with a new RSA key pair from now on, returning its key ID
+    ///
+    /// The previous keys stay published and keep validating the JWTs they
+    /// signed until removed with [`Self::remove_key`].
+    pub fn rotate_rsa_keys(&self, private_pem: &[u8], public_pem: &[u8]) -> Result<String> {
+        self.require_rsa()?;
+        let encoding_key = rsa_encoding_key(private_pem)?;
+        let verification_key = rsa_verification_key(public_pem)?;
+        let kid = verification_key.kid.clone();
+
+        let mut keys = self.keys.write().unwrap();
+        keys.verification_keys.retain(|key| key.kid != kid);
+        keys.verification_keys.push(verification_key);
+        keys.encoding_key = encoding_key;
+        keys.signing_kid = kid.clone();
+        Ok(kid.unwrap_or_default())
+    }
+
+    /// Publish and accept the PEM encoded public key of an earlier RSA key
+    /// pair, e.g. one rotated out before a restart, returning its key ID
+    pub fn add_rsa_public_key(&self, public_pem: &[u8]) -> Result<String> {
+        self.require_rsa()?;
+        let verification_key = rsa_verification_key(public_pem)?;
+        let kid = verification_key.kid.clone().unwrap_or_default();
+
+        let mut keys = self.keys.write().unwrap();
+        if !keys.verification_keys.iter().any(|key| key.kid == verification_key.kid) {
+            keys.verification_keys.push(verification_key);
+        }
+        Ok(kid)
+    }
+
+    /// Stop publishing and accepting the key `kid`
+    ///
+    /// JWTs it signed no longer validate. The signing key cannot be removed.
+    pub fn remove_key(&self, kid: &str) -> Result<()> {
+        let mut keys = self.keys.write().unwrap();
+        if keys.signing_kid.as_deref() == Some(kid) {
+            return Err(qilbee_core::Error::ValidationError("Cannot remove the signing key".to_string()));
+        }
+        let count = keys.verification_keys.len();
+        keys.verification_keys.retain(|key| key.kid.as_deref() != Some(kid));
+        if keys.verification_keys.len() == count {
+            return Err(qilbee_core::Error::ValidationError(format!("Unknown key: {}", kid)));
+        }
+        Ok(())
+    }
+
+    /// ID of the key JWTs are signed with, `None` for a shared secret
+    pub fn signing_kid(&self) -> Option<String> {
+        self.keys.read().unwrap().signing_kid.clone()
+    }
+
+    /// Public keys JWTs are validated with, as a JSON Web Key Set
+    ///
+    /// Empty when JWTs are signed with a shared secret.
+    pub fn jwks(&self) -> JwkSet {
+        let keys = self.keys.read().unwrap();
+        JwkSet { keys: keys.verification_keys.iter().filter_map(|key| key.jwk.clone()).collect() }
+    }
+
+    fn require_rsa(&self) -> Result<()> {
+        if self.jwt_algorithm != Algorithm::RS256 {
+            return Err(qilbee_core::Error::Configuration(
+                "RSA keys require a token service created with RSA keys".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
     /// Generate JWT token
     pub fn generate_jwt(&self, user_id: UserId, username: String, roles: Vec<Role>) -> Result<AuthToken> {
         let now = Utc::now();
@@ -80,12 +207,10 @@ impl TokenService {
             jti: Uuid::new_v4().to_string(),
         };
 
-        let token = encode(
-            &Header::default(),
-            &claims,
-            &EncodingKey::from_secret(self.jwt_secret.as_bytes()),
-        )
-        .map_err(|e| qilbee_core::Error::Internal(format!("Failed to generate JWT: {}", e)))?;
+        let keys = self.keys.read().unwrap();
+        let header = Header { kid: keys.signing_kid.clone(), ..Header::new(self.jwt_algorithm) };
+        let token = encode(&header, &claims, &keys.encoding_key)
+            .map_err(|e| qilbee_core::Error::Internal(format!("Failed to generate JWT: {}", e)))?;
 
         Ok(AuthToken {
             access_token: token,
@@ -95,15 +220,38 @@ impl TokenService {
         })
     }
 
+    /// Generate an opaque refresh token, returning it with its expiration time
+    pub fn generate_refresh_token(&self) -> (String, DateTime<Utc>) {
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(48)
+            .map(char::from)
+            .collect();
+        let expires_at = Utc::now() + Duration::seconds(self.refresh_expiration_secs as i64);
+
+        (format!("qilbee_rt_{}", token), expires_at)
+    }
+
     /// Validate JWT token
+    ///
+    /// Tokens whose `alg` header is not the configured algorithm are
+    /// rejected, so an HS256 token "signed" with the RSA public key cannot
+    /// pass for an RS256 one. The `kid` header picks the key, defaulting to
+    /// the signing key; tokens naming a key that is not published are
+    /// rejected.
     pub fn validate_jwt(&self, token: &str) -> Result<Claims> {
-        let validation = Validation::default();
-        let token_data = decode::<Claims>(
-            token,
-            &DecodingKey::from_secret(self.jwt_secret.as_bytes()),
-            &validation,
-        )
-        .map_err(|e| qilbee_core::Error::Internal(format!("Invalid JWT: {}", e)))?;
+        let header = decode_header(token).map_err(|e| qilbee_core::Error::Internal(format!("Invalid JWT: {}", e)))?;
+        let keys = self.keys.read().unwrap();
+        let kid = header.kid.as_ref().or(keys.signing_kid.as_ref());
+        let key = keys
+            .verification_keys
+            .iter()
+            .find(|key| key.kid.as_ref() == kid)
+            .ok_or_else(|| qilbee_core::Error::Internal("Invalid JWT: unknown signing key".to_string()))?;
+
+        let validation = Validation::new(self.jwt_algorithm);
+        let token_data = decode::<Claims>(token, &key.decoding_key, &validation)
+            .map_err(|e| qilbee_core::Error::Internal(format!("Invalid JWT: {}", e)))?;
 
         Ok(token_data.claims)
     }
@@ -248,6 +396,69 @@ impl TokenService {
     }
 }
 
+fn rsa_encoding_key(private_pem: &[u8]) -> Result<EncodingKey> {
+    EncodingKey::from_rsa_pem(private_pem)
+        .map_err(|e| qilbee_core::Error::Configuration(format!("Invalid RSA private key: {}", e)))
+}
+
+/// Verification key and JWK of a PEM encoded RSA public key, identified by
+/// its RFC 7638 thumbprint
+fn rsa_verification_key(public_pem: &[u8]) -> Result<VerificationKey> {
+    let (n, e) = rsa_public_components(public_pem)?;
+    let decoding_key = DecodingKey::from_rsa_components(&n, &e)
+        .map_err(|err| qilbee_core::Error::Configuration(format!("Invalid RSA public key: {}", err)))?;
+
+    // Members in lexicographic order, without whitespace
+    let thumbprint_input = format!(r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#, e, n);
+    let kid = URL_SAFE_NO_PAD.encode(Sha256::digest(thumbprint_input.as_bytes()));
+
+    let jwk = Jwk {
+        common: CommonParameters {
+            public_key_use: Some(PublicKeyUse::Signature),
+            key_algorithm: Some(KeyAlgorithm::RS256),
+            key_id: Some(kid.clone()),
+            ..Default::default()
+        },
+        algorithm: AlgorithmParameters::RSA(RSAKeyParameters { key_type: RSAKeyType::RSA, n, e }),
+    };
+    Ok(VerificationKey { kid: Some(kid), decoding_key, jwk: Some(jwk) })
+}
+
+/// Modulus and exponent of a PEM encoded RSA public key, base64url encoded
+/// as in a JWK
+///
+/// Accepts SubjectPublicKeyInfo (`PUBLIC KEY`) and PKCS#1 (`RSA PUBLIC
+/// KEY`) blocks.
+fn rsa_public_components(public_pem: &[u8]) -> Result<(String, String)> {
+    let invalid = |reason: String| qilbee_core::Error::Configuration(format!("Invalid RSA public key: {}", reason));
+    let pem = pem::parse(public_pem).map_err(|e| invalid(e.to_string()))?;
+    let der = |bytes: &[u8]| simple_asn1::from_der(bytes).map_err(|e| invalid(e.to_string()));
+
+    let rsa_public_key = match pem.tag() {
+        "RSA PUBLIC KEY" => pem.contents().to_vec(),
+        // The key follows the algorithm identifier, in a bit string
+        "PUBLIC KEY" => match der(pem.contents())?.as_slice() {
+            [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+                [_, ASN1Block::BitString(_, _, key)] => key.clone(),
+                _ => return Err(invalid("malformed public key info".to_string())),
+            },
+            _ => return Err(invalid("malformed public key info".to_string())),
+        },
+        tag => return Err(invalid(format!("unexpected {} block", tag))),
+    };
+
+    match der(&rsa_public_key)?.as_slice() {
+        [ASN1Block::Sequence(_, items)] => match items.as_slice() {
+            [ASN1Block::Integer(_, n), ASN1Block::Integer(_, e)] => Ok((
+                URL_SAFE_NO_PAD.encode(n.to_bytes_be().1),
+                URL_SAFE_NO_PAD.encode(e.to_bytes_be().1),
+            )),
+            _ => Err(invalid("not an RSA key".to_string())),
+        },
+        _ => Err(invalid("not an RSA key".to_string())),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -267,6 +478,103 @@ mod tests {
         assert_eq!(claims.roles, roles);
     }
 
+    const TEST_RSA_PRIVATE_KEY: &str = include_str!("testdata/rsa_private.pem");
+    const TEST_RSA_PUBLIC_KEY: &str = include_str!("testdata/rsa_public.pem");
+    const ROTATED_RSA_PRIVATE_KEY: &str = include_str!("testdata/rsa_rotated_private.pem");
+    const ROTATED_RSA_PUBLIC_KEY: &str = include_str!("testdata/rsa_rotated_public.pem");
+
+    #[test]
+    fn test_rs256_jwt() {
+        let service =
+            TokenService::with_rsa_keys(TEST_RSA_PRIVATE_KEY.as_bytes(), TEST_RSA_PUBLIC_KEY.as_bytes()).unwrap();
+        let token = service
+            .generate_jwt(UserId::new(), "testuser".to_string(), vec![Role::Read])
+            .unwrap();
+        assert_eq!(jsonwebtoken::decode_header(&token.access_token).unwrap().alg, Algorithm::RS256);
+        assert_eq!(service.validate_jwt(&token.access_token).unwrap().username, "testuser");
+
+        // An HS256 token keyed with the public key must not pass as RS256
+        let forged = TokenService::new(TEST_RSA_PUBLIC_KEY.to_string())
+            .generate_jwt(UserId::new(), "attacker".to_string(), vec![Role::Admin])
+            .unwrap();
+        assert!(service.validate_jwt(&forged.access_token).is_err());
+
+        // And RS256 tokens don't pass an HS256 service
+        assert!(TokenService::new("test_secret".to_string()).validate_jwt(&token.access_token).is_err());
+
+        assert!(TokenService::with_rsa_keys(b"not a key", TEST_RSA_PUBLIC_KEY.as_bytes()).is_err());
+        assert!(TokenService::with_rsa_keys(TEST_RSA_PRIVATE_KEY.as_bytes(), b"not a key").is_err());
+    }
+
+    #[test]
+    fn test_rsa_key_rotation() {
+        let service =
+            TokenService::with_rsa_keys(TEST_RSA_PRIVATE_KEY.as_bytes(), TEST_RSA_PUBLIC_KEY.as_bytes()).unwrap();
+        let first_kid = service.signing_kid().unwrap();
+        let token = |service: &TokenService| {
+            service.generate_jwt(UserId::new(), "testuser".to_string(), vec![Role::Read]).unwrap().access_token
+        };
+        let old_token = token(&service);
+        assert_eq!(jsonwebtoken::decode_header(&old_token).unwrap().kid, Some(first_kid.clone()));
+
+        let jwks = service.jwks();
+        assert_eq!(jwks.keys.len(), 1);
+        let jwk = jwks.find(&first_kid).unwrap();
+        // A published key validates the service's tokens on its own
+        let decoding_key = DecodingKey::from_jwk(jwk).unwrap();
+        assert!(decode::<Claims>(&old_token, &decoding_key, &Validation::new(Algorithm::RS256)).is_ok());
+
+        let second_kid = service
+            .rotate_rsa_keys(ROTATED_RSA_PRIVATE_KEY.as_bytes(), ROTATED_RSA_PUBLIC_KEY.as_bytes())
+            .unwrap();
+        assert_ne!(second_kid, first_kid);
+        let new_token = token(&service);
+        assert_eq!(jsonwebtoken::decode_header(&new_token).unwrap().kid, Some(second_kid.clone()));
+        assert_eq!(service.jwks().keys.len(), 2);
+
+        // Tokens of the previous key validate while it is published
+        assert!(service.validate_jwt(&old_token).is_ok());
+        assert!(service.validate_jwt(&new_token).is_ok());
+        assert!(service.remove_key(&second_kid).is_err());
+        service.remove_key(&first_kid).unwrap();
+        assert!(service.validate_jwt(&old_token).is_err());
+        assert!(service.validate_jwt(&new_token).is_ok());
+
+        // A restarted service can keep accepting the previous key
+        let restarted =
+            TokenService::with_rsa_keys(ROTATED_RSA_PRIVATE_KEY.as_bytes(), ROTATED_RSA_PUBLIC_KEY.as_bytes()).unwrap();
+        assert!(restarted.validate_jwt(&old_token).is_err());
+        assert_eq!(restarted.add_rsa_public_key(TEST_RSA_PUBLIC_KEY.as_bytes()).unwrap(), first_kid);
+        assert!(restarted.validate_jwt(&old_token).is_ok());
+
+        // Shared-secret services publish nothing
+        let hs256 = TokenService::new("test_secret".to_string());
+        assert!(hs256.jwks().keys.is_empty());
+        assert!(hs256.add_rsa_public_key(TEST_RSA_PUBLIC_KEY.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn test_rsa_thumbprint() {
+        // RFC 7638, section 3.1
+        let n = "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECPebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0fM4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw";
+        let public_pem = rsa_public_pem(n, "AQAB");
+        let key = rsa_verification_key(public_pem.as_bytes()).unwrap();
+        assert_eq!(key.kid.as_deref(), Some("NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"));
+    }
+
+    /// PKCS#1 PEM of the RSA public key with base64url modulus `n` and exponent `e`
+    fn rsa_public_pem(n: &str, e: &str) -> String {
+        let integer = |value: &str| {
+            let mut bytes = URL_SAFE_NO_PAD.decode(value).unwrap();
+            if bytes[0] & 0x80 != 0 {
+                bytes.insert(0, 0);
+            }
+            ASN1Block::Integer(0, simple_asn1::BigInt::from_signed_bytes_be(&bytes))
+        };
+        let der = simple_asn1::to_der(&ASN1Block::Sequence(0, vec![integer(n), integer(e)])).unwrap();
+        pem::encode(&pem::Pem::new("RSA PUBLIC KEY", der))
+    }
+
     #[test]
     fn test_api_key_generation_and_validation() {
         let service = TokenService::new("test_secret".to_string());
@@ -1,7 +1,7 @@
 //! Token blacklist for JWT revocation
 //!
 //! Provides the ability to revoke JWT tokens before their natural expiration.
-//! Uses in-memory storage with optional file-based persistence.
+//! Uses in-memory storage with optional file-based or database persistence.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -9,8 +9,10 @@ use std::collections::HashSet;
 use std::fs::{File, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
-use std::sync::{Arc, RwLock};
+use std::sync::{Arc, RwLock, Weak};
+use std::time::Duration;
 use qilbee_core::Result;
+use qilbee_storage::StorageEngine;
 
 /// Reason for token revocation
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -96,6 +98,8 @@ pub struct TokenBlacklist {
     entries: Arc<RwLock<Vec<BlacklistedToken>>>,
     /// Configuration
     config: BlacklistConfig,
+    /// Database holding the blacklist, keyed by token ID (`None` when not persisted there)
+    storage: Option<StorageEngine>,
 }
 
 impl TokenBlacklist {
@@ -105,6 +109,7 @@ impl TokenBlacklist {
             blacklisted_ids: Arc::new(RwLock::new(HashSet::new())),
             entries: Arc::new(RwLock::new(Vec::new())),
             config,
+            storage: None,
         };
 
         // Load from disk if persistence is enabled
@@ -115,6 +120,43 @@ impl TokenBlacklist {
         blacklist
     }
 
+    /// Create a token blacklist persisted in the database's token blacklist column family
+    ///
+    /// Entries revoked before a restart are loaded, and those already past
+    /// expiry are deleted.
+    pub fn with_storage(storage: StorageEngine) -> Result<Self> {
+        let blacklist = Self {
+            blacklisted_ids: Arc::new(RwLock::new(HashSet::new())),
+            entries: Arc::new(RwLock::new(Vec::new())),
+            config: BlacklistConfig::default(),
+            storage: Some(storage),
+        };
+        blacklist.load_from_storage()?;
+        Ok(blacklist)
+    }
+
+    /// Periodically clean up expired entries on the current Tokio runtime
+    ///
+    /// The task stops once the blacklist is dropped.
+    pub fn spawn_cleanup(self: &Arc<Self>, interval: Duration) -> tokio::task::JoinHandle<()> {
+        let blacklist = Arc::downgrade(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // The first tick completes immediately
+            ticker.tick().await;
+            loop {
+                ticker.tick().await;
+                let Some(blacklist) = Weak::upgrade(&blacklist) else {
+                    break;
+                };
+                let removed = blacklist.cleanup_expired();
+                if removed > 0 {
+                    tracing::debug!("Removed {} expired token blacklist entries", removed);
+                }
+            }
+        })
+    }
+
     /// Add a token to the blacklist
     pub fn revoke(
         &self,
@@ -133,6 +175,9 @@ impl TokenBlacklist {
             reason,
         };
 
+        // Persist to the database before the revocation takes effect
+        self.store(&entry)?;
+
         // Add to in-memory set
         self.blacklisted_ids.write().unwrap().insert(token_id);
 
@@ -148,8 +193,35 @@ impl TokenBlacklist {
     }
 
     /// Check if a token is revoked
+    ///
+    /// With database persistence, tokens not revoked in memory are looked up
+    /// in the database, and a failed lookup counts as revoked.
     pub fn is_revoked(&self, token_id: &str) -> bool {
-        self.blacklisted_ids.read().unwrap().contains(token_id)
+        if self.blacklisted_ids.read().unwrap().contains(token_id) {
+            return true;
+        }
+        let Some(storage) = &self.storage else {
+            return false;
+        };
+        if token_id.starts_with("revoke_all_") {
+            return false;
+        }
+
+        let stored = storage.get_revoked_token(token_id.as_bytes()).and_then(|value| {
+            value
+                .map(|bytes| {
+                    serde_json::from_slice::<BlacklistedToken>(&bytes)
+                        .map_err(|e| qilbee_core::Error::Internal(format!("Failed to parse blacklist entry: {}", e)))
+                })
+                .transpose()
+        });
+        match stored {
+            Ok(entry) => entry.is_some_and(|entry| entry.expires_at > Utc::now()),
+            Err(e) => {
+                tracing::error!("Failed to look up token {} in the blacklist: {}", token_id, e);
+                true
+            }
+        }
     }
 
     /// Revoke all tokens for a user
@@ -187,6 +259,7 @@ impl TokenBlacklist {
             reason,
         };
 
+        self.store(&entry)?;
         self.entries.write().unwrap().push(entry.clone());
 
         if self.config.persist_to_disk {
@@ -232,15 +305,15 @@ impl TokenBlacklist {
     /// Returns the number of entries removed.
     pub fn cleanup_expired(&self) -> usize {
         let now = Utc::now();
-        let mut count = 0;
 
         // Clean up entries
-        {
+        let expired: Vec<Vec<u8>> = {
             let mut entries = self.entries.write().unwrap();
-            let original_len = entries.len();
-            entries.retain(|e| e.expires_at > now);
-            count = original_len - entries.len();
-        }
+            let (kept, expired): (Vec<_>, Vec<_>) = entries.drain(..).partition(|e| e.expires_at > now);
+            *entries = kept;
+            expired.into_iter().map(|e| e.token_id.into_bytes()).collect()
+        };
+        let count = expired.len();
 
         // Rebuild the ID set from remaining entries
         {
@@ -254,6 +327,13 @@ impl TokenBlacklist {
             }
         }
 
+        if let Some(storage) = &self.storage
+            && count > 0
+            && let Err(e) = storage.write_revoked_tokens(&[], &expired)
+        {
+            tracing::error!("Failed to delete expired blacklist entries: {}", e);
+        }
+
         // Rewrite persistence file with cleaned data
         if self.config.persist_to_disk && count > 0 {
             if let Err(e) = self.rewrite_persistence_file() {
@@ -285,6 +365,50 @@ impl TokenBlacklist {
             .collect()
     }
 
+    /// Write an entry to the database, if the blacklist is persisted there
+    fn store(&self, entry: &BlacklistedToken) -> Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+        let json = serde_json::to_vec(entry)
+            .map_err(|e| qilbee_core::Error::Internal(format!("Failed to serialize blacklist entry: {}", e)))?;
+        storage.write_revoked_tokens(&[(entry.token_id.clone().into_bytes(), json)], &[])
+    }
+
+    /// Load the blacklist from the database, deleting expired entries
+    fn load_from_storage(&self) -> Result<()> {
+        let Some(storage) = &self.storage else {
+            return Ok(());
+        };
+
+        let now = Utc::now();
+        let mut expired = Vec::new();
+        for (key, value) in storage.scan_revoked_tokens()? {
+            let entry: BlacklistedToken = serde_json::from_slice(&value)
+                .map_err(|e| qilbee_core::Error::Internal(format!("Failed to parse blacklist entry: {}", e)))?;
+
+            if entry.expires_at <= now {
+                expired.push(key);
+                continue;
+            }
+            if !entry.token_id.starts_with("revoke_all_") {
+                self.blacklisted_ids.write().unwrap().insert(entry.token_id.clone());
+            }
+            self.entries.write().unwrap().push(entry);
+        }
+        if !expired.is_empty() {
+            storage.write_revoked_tokens(&[], &expired)?;
+        }
+
+        tracing::info!(
+            "Loaded {} blacklist entries ({} expired entries removed)",
+            self.entries.read().unwrap().len(),
+            expired.len()
+        );
+
+        Ok(())
+    }
+
     /// Load blacklist from disk
     fn load_from_disk(&self) -> Result<()> {
         let path = match &self.config.persistence_path {
@@ -538,4 +662,52 @@ mod tests {
         let user_b_entries = blacklist.get_user_entries("user-b");
         assert_eq!(user_b_entries.len(), 1);
     }
+
+    #[test]
+    fn test_storage_persistence() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let open = || {
+            let options = qilbee_storage::StorageOptions::for_testing(temp_dir.path());
+            TokenBlacklist::with_storage(StorageEngine::open(options).unwrap()).unwrap()
+        };
+
+        {
+            let blacklist = open();
+            for (token_id, expires_at) in [
+                ("revoked", Utc::now() + Duration::hours(1)),
+                ("expired", Utc::now() - Duration::hours(1)),
+            ] {
+                blacklist.revoke(
+                    token_id.to_string(),
+                    "user-a".to_string(),
+                    "usera".to_string(),
+                    expires_at,
+                    RevocationReason::Logout,
+                ).unwrap();
+            }
+            blacklist.revoke_all_for_user("user-b", "userb", RevocationReason::RevokeAll).unwrap();
+        }
+
+        // Revocations survive a restart, and expired entries are dropped on load
+        let blacklist = open();
+        assert!(blacklist.is_revoked("revoked"));
+        assert!(!blacklist.is_revoked("expired"));
+        assert!(!blacklist.is_revoked("unknown"));
+        assert_eq!(blacklist.count(), 1);
+        assert!(blacklist.is_invalidated_by_revoke_all("user-b", Utc::now() - Duration::minutes(5)));
+        assert_eq!(blacklist.storage.as_ref().unwrap().scan_revoked_tokens().unwrap().len(), 2);
+
+        // Cleanup deletes expired entries from the database
+        blacklist.revoke(
+            "short-lived".to_string(),
+            "user-a".to_string(),
+            "usera".to_string(),
+            Utc::now() - Duration::seconds(1),
+            RevocationReason::Logout,
+        ).unwrap();
+        assert_eq!(blacklist.cleanup_expired(), 1);
+        let stored = blacklist.storage.as_ref().unwrap().scan_revoked_tokens().unwrap();
+        assert!(stored.iter().all(|(key, _)| key != b"short-lived"));
+        assert_eq!(stored.len(), 2);
+    }
 }
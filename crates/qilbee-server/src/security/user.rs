@@ -3,15 +3,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
-use argon2::{
-    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString},
-    Argon2,
-};
 use chrono::{DateTime, Utc};
 use uuid::Uuid;
 use qilbee_core::Result;
-use super::rbac::Role;
-use super::password::validate_password;
+use qilbee_storage::StorageEngine;
+use super::rbac::{Permission, Role};
+use super::password::{validate_password, verify_password_hash, PasswordHashConfig};
 
 /// Unique user identifier
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
@@ -35,7 +32,7 @@ pub struct User {
     pub id: UserId,
     pub username: String,
     pub email: String,
-    #[serde(skip_serializing)]
+    #[serde(skip_serializing, default)]
     pub password_hash: String,
     pub roles: Vec<Role>,
     pub is_active: bool,
@@ -49,10 +46,21 @@ impl User {
     /// Create a new user with hashed password
     /// Validates password complexity before creation
     pub fn new(username: String, email: String, password: &str) -> Result<Self> {
+        Self::new_with_hash_config(username, email, password, &PasswordHashConfig::default())
+    }
+
+    /// Create a new user with the password hashed as configured
+    /// Validates password complexity before creation
+    pub fn new_with_hash_config(
+        username: String,
+        email: String,
+        password: &str,
+        hash_config: &PasswordHashConfig,
+    ) -> Result<Self> {
         // Validate password complexity
         validate_password(password)?;
 
-        let password_hash = hash_password(password)?;
+        let password_hash = hash_config.hash(password)?;
 
         Ok(Self {
             id: UserId::new(),
@@ -76,10 +84,20 @@ impl User {
     /// Update password
     /// Validates password complexity before updating
     pub fn update_password(&mut self, new_password: &str) -> Result<()> {
+        self.update_password_with_hash_config(new_password, &PasswordHashConfig::default())
+    }
+
+    /// Update password, hashing it as configured
+    /// Validates password complexity before updating
+    pub fn update_password_with_hash_config(
+        &mut self,
+        new_password: &str,
+        hash_config: &PasswordHashConfig,
+    ) -> Result<()> {
         // Validate password complexity
         validate_password(new_password)?;
 
-        self.password_hash = hash_password(new_password)?;
+        self.password_hash = hash_config.hash(new_password)?;
         self.updated_at = Utc::now();
         Ok(())
     }
@@ -103,6 +121,11 @@ impl User {
         self.roles.contains(role)
     }
 
+    /// Check if one of the user's built-in roles grants a permission
+    pub fn has_permission(&self, permission: &Permission) -> bool {
+        self.roles.iter().any(|role| role.permissions().contains(permission))
+    }
+
     /// Record login
     pub fn record_login(&mut self) {
         self.last_login = Some(Utc::now());
@@ -110,29 +133,24 @@ impl User {
     }
 }
 
-/// Hash password using Argon2
+/// Hash password using Argon2id with the default parameters
 pub fn hash_password(password: &str) -> Result<String> {
-    let salt = SaltString::generate(&mut OsRng);
-    let argon2 = Argon2::default();
-
-    let password_hash = argon2
-        .hash_password(password.as_bytes(), &salt)
-        .map_err(|e| qilbee_core::Error::Internal(format!("Failed to hash password: {}", e)))?
-        .to_string();
-
-    Ok(password_hash)
+    PasswordHashConfig::default().hash(password)
 }
 
-/// Verify password against hash
+/// Verify password against hash, made with any supported algorithm
 pub fn verify_password(password: &str, hash: &str) -> Result<bool> {
-    let parsed_hash = PasswordHash::new(hash)
-        .map_err(|e| qilbee_core::Error::Internal(format!("Invalid password hash: {}", e)))?;
-
-    let argon2 = Argon2::default();
+    verify_password_hash(password, hash)
+}
 
-    Ok(argon2
-        .verify_password(password.as_bytes(), &parsed_hash)
-        .is_ok())
+/// User account as stored in the database
+///
+/// `User` never serializes its password hash, so it is stored alongside.
+#[derive(Serialize, Deserialize)]
+struct StoredUser {
+    #[serde(flatten)]
+    user: User,
+    password_hash: String,
 }
 
 /// User service for managing users
@@ -140,6 +158,10 @@ pub struct UserService {
     users: Arc<RwLock<HashMap<UserId, User>>>,
     username_index: Arc<RwLock<HashMap<String, UserId>>>,
     email_index: Arc<RwLock<HashMap<String, UserId>>>,
+    /// Database holding user accounts (`None` keeps them in memory only)
+    storage: Option<StorageEngine>,
+    /// How new and outdated passwords are hashed
+    password_hash_config: PasswordHashConfig,
 }
 
 impl UserService {
@@ -149,7 +171,70 @@ impl UserService {
             users: Arc::new(RwLock::new(HashMap::new())),
             username_index: Arc::new(RwLock::new(HashMap::new())),
             email_index: Arc::new(RwLock::new(HashMap::new())),
+            storage: None,
+            password_hash_config: PasswordHashConfig::default(),
+        }
+    }
+
+    /// Builder: hash passwords with the given algorithm and parameters
+    ///
+    /// Hashes made otherwise still verify, and are replaced on the user's
+    /// next successful login. Fails if the parameters are invalid, since
+    /// such users could not log in.
+    pub fn with_password_hash_config(mut self, config: PasswordHashConfig) -> Result<Self> {
+        config.validate()?;
+        self.password_hash_config = config;
+        Ok(self)
+    }
+
+    /// How passwords are hashed
+    pub fn password_hash_config(&self) -> &PasswordHashConfig {
+        &self.password_hash_config
+    }
+
+    /// Create a user service persisted in the database's users column family,
+    /// loading the users stored by earlier runs
+    pub fn with_storage(storage: StorageEngine) -> Result<Self> {
+        let service = Self {
+            storage: Some(storage.clone()),
+            ..Self::new()
+        };
+
+        for (_, value) in storage.scan_users()? {
+            let stored: StoredUser = serde_json::from_slice(&value)
+                .map_err(|e| qilbee_core::Error::Internal(format!("Failed to parse stored user: {}", e)))?;
+            let mut user = stored.user;
+            user.password_hash = stored.password_hash;
+            service.index(user);
+        }
+
+        Ok(service)
+    }
+
+    /// Import the users of another user service, such as one kept in memory
+    /// before persistence was enabled
+    ///
+    /// Only the first persistent run imports: once this service holds any
+    /// user, nothing is imported. Returns the number of users imported.
+    pub fn import_users(&self, source: &UserService) -> Result<usize> {
+        if !self.users.read().unwrap().is_empty() {
+            return Ok(0);
+        }
+
+        let users = source.list_users();
+        if let Some(storage) = &self.storage {
+            let puts = users
+                .iter()
+                .map(Self::serialize)
+                .collect::<Result<Vec<_>>>()?;
+            storage.write_users(&puts, &[])?;
+        }
+        let count = users.len();
+        for user in users {
+            self.index(user);
         }
+
+        Ok(count)
     }
 
     /// Create a new user
@@ -164,13 +249,11 @@ impl UserService {
             return Err(qilbee_core::Error::Internal("Email already exists".to_string()));
         }
 
-        let user = User::new(username.clone(), email.clone(), password)?;
-        let user_id = user.id;
+        let user = User::new_with_hash_config(username, email, password, &self.password_hash_config)?;
 
         // Store user
-        self.users.write().unwrap().insert(user_id, user.clone());
-        self.username_index.write().unwrap().insert(username, user_id);
-        self.email_index.write().unwrap().insert(email, user_id);
+        self.store(&user)?;
+        self.index(user.clone());
 
         Ok(user)
     }
@@ -194,6 +277,7 @@ impl UserService {
 
     /// Update user
     pub fn update_user(&self, user: User) -> Result<()> {
+        self.store(&user)?;
         let user_id = user.id;
         self.users.write().unwrap().insert(user_id, user);
         Ok(())
@@ -201,6 +285,9 @@ impl UserService {
 
     /// Delete user
     pub fn delete_user(&self, user_id: &UserId) -> Result<()> {
+        if let Some(storage) = &self.storage {
+            storage.write_users(&[], &[user_id.0.to_string().into_bytes()])?;
+        }
         if let Some(user) = self.users.write().unwrap().remove(user_id) {
             self.username_index.write().unwrap().remove(&user.username);
             self.email_index.write().unwrap().remove(&user.email);
@@ -222,8 +309,13 @@ impl UserService {
             return Err(qilbee_core::Error::Internal("Invalid credentials".to_string()));
         }
 
-        // Record login
+        // Rehash outdated hashes while the password is at hand
         let mut updated_user = user;
+        if self.password_hash_config.needs_rehash(&updated_user.password_hash) {
+            updated_user.password_hash = self.password_hash_config.hash(password)?;
+        }
+
+        // Record login
         updated_user.record_login();
         self.update_user(updated_user.clone())?;
 
@@ -235,6 +327,15 @@ impl UserService {
         self.users.read().unwrap().values().cloned().collect()
     }
 
+    /// Check if any active user has the admin role
+    pub fn has_admin(&self) -> bool {
+        self.users
+            .read()
+            .unwrap()
+            .values()
+            .any(|user| user.is_active && user.has_role(&Role::Admin))
+    }
+
     /// Create default admin user
     pub fn create_default_admin(&self, password: &str) -> Result<User> {
         let mut user = self.create_user(
@@ -248,6 +349,32 @@ impl UserService {
 
         Ok(user)
     }
+
+    /// Write a user to the database, if users are persisted
+    fn store(&self, user: &User) -> Result<()> {
+        match &self.storage {
+            Some(storage) => storage.write_users(&[Self::serialize(user)?], &[]),
+            None => Ok(()),
+        }
+    }
+
+    /// Serialize a user into its database key and value
+    fn serialize(user: &User) -> Result<(Vec<u8>, Vec<u8>)> {
+        let stored = StoredUser {
+            user: user.clone(),
+            password_hash: user.password_hash.clone(),
+        };
+        let value = serde_json::to_vec(&stored)
+            .map_err(|e| qilbee_core::Error::Internal(format!("Failed to serialize user: {}", e)))?;
+        Ok((user.id.0.to_string().into_bytes(), value))
+    }
+
+    /// Add a user to the in-memory maps
+    fn index(&self, user: User) {
+        self.username_index.write().unwrap().insert(user.username.clone(), user.id);
+        self.email_index.write().unwrap().insert(user.email.clone(), user.id);
+        self.users.write().unwrap().insert(user.id, user);
+    }
 }
 
 impl Default for UserService {
@@ -321,4 +448,102 @@ mod tests {
         let result = service.create_user("bob".to_string(), "bob@example.com".to_string(), "weak");
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_user_persistence() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let open = || {
+            let options = qilbee_storage::StorageOptions::for_testing(temp_dir.path());
+            UserService::with_storage(StorageEngine::open(options).unwrap()).unwrap()
+        };
+
+        let (alice, bob) = {
+            let service = open();
+            assert!(!service.has_admin());
+            let admin = service.create_default_admin(TEST_PASSWORD).unwrap();
+            assert!(service.has_admin());
+            let mut alice = service
+                .create_user("alice".to_string(), "alice@example.com".to_string(), TEST_PASSWORD)
+                .unwrap();
+            alice.add_role(Role::Developer);
+            service.update_user(alice.clone()).unwrap();
+            let bob = service
+                .create_user("bob".to_string(), "bob@example.com".to_string(), TEST_PASSWORD)
+                .unwrap();
+            service.delete_user(&bob.id).unwrap();
+            assert_eq!(service.list_users().len(), 2);
+            assert_ne!(admin.id, alice.id);
+            (alice, bob)
+        };
+
+        // Users, password hashes and roles survive a restart
+        let service = open();
+        assert!(service.has_admin());
+        assert_eq!(service.list_users().len(), 2);
+        assert!(service.get_user(&bob.id).is_none());
+        let restored = service.get_user_by_email("alice@example.com").unwrap();
+        assert_eq!(restored.id, alice.id);
+        assert_eq!(restored.roles, alice.roles);
+        assert!(service.authenticate("alice", TEST_PASSWORD).is_ok());
+        assert!(service.authenticate("alice", "WrongP@ssword1!").is_err());
+
+        // Password hashes are never part of a user's JSON
+        let json = serde_json::to_value(&restored).unwrap();
+        assert!(json.get("password_hash").is_none());
+    }
+
+    #[test]
+    fn test_password_rehash_on_login() {
+        let default_service = UserService::new();
+        let user = default_service
+            .create_user("alice".to_string(), "alice@example.com".to_string(), TEST_PASSWORD)
+            .unwrap();
+        assert!(user.password_hash.starts_with("$argon2id$"));
+
+        // Hashes from an earlier configuration verify, and are replaced on login
+        assert!(UserService::new().with_password_hash_config(PasswordHashConfig::pbkdf2_sha256(0)).is_err());
+        let service = UserService::new().with_password_hash_config(PasswordHashConfig::pbkdf2_sha256(1000)).unwrap();
+        service.import_users(&default_service).unwrap();
+        assert!(service.authenticate("alice", "WrongP@ssword1!").is_err());
+        assert_eq!(service.get_user(&user.id).unwrap().password_hash, user.password_hash);
+
+        let authed = service.authenticate("alice", TEST_PASSWORD).unwrap();
+        assert!(authed.password_hash.starts_with("$pbkdf2-sha256$i=1000,"));
+        assert_eq!(service.get_user(&user.id).unwrap().password_hash, authed.password_hash);
+        assert!(service.authenticate("alice", TEST_PASSWORD).is_ok());
+
+        // New users are hashed as configured
+        let bob = service
+            .create_user("bob".to_string(), "bob@example.com".to_string(), TEST_PASSWORD)
+            .unwrap();
+        assert!(bob.password_hash.starts_with("$pbkdf2-sha256$"));
+    }
+
+    #[test]
+    fn test_import_users() {
+        let in_memory = UserService::new();
+        in_memory.create_default_admin(TEST_PASSWORD).unwrap();
+        in_memory
+            .create_user("alice".to_string(), "alice@example.com".to_string(), TEST_PASSWORD)
+            .unwrap();
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let open = || {
+            let options = qilbee_storage::StorageOptions::for_testing(temp_dir.path());
+            UserService::with_storage(StorageEngine::open(options).unwrap()).unwrap()
+        };
+
+        // The first persistent run imports the in-memory users
+        {
+            let service = open();
+            assert_eq!(service.import_users(&in_memory).unwrap(), 2);
+            assert!(service.has_admin());
+        }
+
+        // Later runs keep the stored users
+        let service = open();
+        assert_eq!(service.list_users().len(), 2);
+        assert!(service.authenticate("alice", TEST_PASSWORD).is_ok());
+        assert_eq!(service.import_users(&in_memory).unwrap(), 0);
+    }
 }
@@ -1,10 +1,12 @@
 //! Server implementation
 
+use crate::bolt_server::{self, DatabaseQueryRunner};
 use crate::config::ServerConfig;
+use crate::grpc_server::GrpcService;
 use crate::http_server;
 use crate::security::{UserService, BootstrapService};
 use qilbee_core::{Error, Result};
-use qilbee_graph::Database;
+use qilbee_graph::{Database, MigrationRunner};
 use std::sync::Arc;
 use tokio::task::JoinHandle;
 use tracing::{info, warn};
@@ -22,17 +24,39 @@ pub struct Server {
 
     /// HTTP server handle
     http_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
+
+    /// gRPC server handle
+    grpc_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
+
+    /// Bolt server handle
+    bolt_handle: std::sync::Mutex<Option<JoinHandle<()>>>,
 }
 
 impl Server {
     /// Create a new server instance
     pub fn new(config: ServerConfig) -> Result<Self> {
-        let database = Database::open(&config.data_dir)?;
+        // Refuse to start with password hash parameters that would fail logins
+        config.password_hash.validate()?;
+
+        let database = Database::open_with_config(&config.data_dir, config.database_config())?;
+
+        // Bring the schema up to date before serving requests
+        let applied = MigrationRunner::builtin().run(&database)?;
+        if applied > 0 {
+            info!("Applied {} schema migration(s)", applied);
+        }
 
         // Run bootstrap if authentication is enabled
         if config.auth_enabled {
             info!("Authentication is enabled, checking bootstrap status...");
-            let user_service = Arc::new(UserService::new());
+            let user_service = Arc::new(
+                if config.persist_users {
+                    UserService::with_storage(database.storage().clone())?
+                } else {
+                    UserService::new()
+                }
+                .with_password_hash_config(config.password_hash.clone())?,
+            );
             let bootstrap = BootstrapService::new(
                 config.data_dir.clone(),
                 user_service.clone(),
@@ -52,6 +76,8 @@ impl Server {
             database: Arc::new(database),
             running: std::sync::atomic::AtomicBool::new(false),
             http_handle: std::sync::Mutex::new(None),
+            grpc_handle: std::sync::Mutex::new(None),
+            bolt_handle: std::sync::Mutex::new(None),
         })
     }
 
@@ -79,16 +105,73 @@ impl Server {
         info!("Starting QilbeeDB server...");
         info!("Data directory: {:?}", self.config.data_dir);
 
-        if self.config.enable_bolt {
+        // Bolt and gRPC clients authenticate against the same accounts and tokens as the HTTP API
+        let state = (self.config.enable_http || self.config.enable_bolt || self.config.enable_grpc)
+            .then(|| http_server::create_app_state(Arc::clone(&self.database), &self.config));
+
+        if let Some(state) = state.as_ref().filter(|_| self.config.enable_bolt) {
             info!("Bolt protocol enabled on port {}", self.config.bolt_port);
-            // TODO: Start Bolt listener
+
+            // Start Bolt server
+            let mut runner = DatabaseQueryRunner::new(Arc::clone(&self.database), state.auth_service.clone())
+                .with_parser_limits(self.config.parser_limits());
+            if let Some(limit) = self.config.default_query_limit {
+                runner = runner.with_default_limit(limit);
+            }
+            let addr = format!("0.0.0.0:{}", self.config.bolt_port);
+            let listener = tokio::net::TcpListener::bind(&addr)
+                .await
+                .map_err(Error::Io)?;
+
+            info!("Bolt server listening on {}", addr);
+
+            // Spawn Bolt server task
+            let handle = tokio::spawn(async move {
+                if let Err(e) = bolt_server::serve(listener, runner).await {
+                    warn!("Bolt server error: {}", e);
+                }
+            });
+
+            *self.bolt_handle.lock().unwrap() = Some(handle);
         }
 
-        if self.config.enable_http {
+        if let Some(state) = state.as_ref().filter(|_| self.config.enable_grpc) {
+            info!("gRPC API enabled on port {}", self.config.grpc_port);
+
+            // Start gRPC server
+            let mut service = GrpcService::new(Arc::clone(&self.database), state.auth_service.clone())
+                .with_parser_limits(self.config.parser_limits());
+            if let Some(limit) = self.config.default_query_limit {
+                service = service.with_default_limit(limit);
+            }
+            let service = service.into_server();
+            let addr = format!("0.0.0.0:{}", self.config.grpc_port);
+            let listener = tokio::net::TcpListener::bind(&addr)
+                .await
+                .map_err(Error::Io)?;
+
+            info!("gRPC server listening on {}", addr);
+
+            // Spawn gRPC server task
+            let handle = tokio::spawn(async move {
+                let incoming = tokio_stream::wrappers::TcpListenerStream::new(listener);
+                if let Err(e) = tonic::transport::Server::builder()
+                    .add_service(service)
+                    .serve_with_incoming(incoming)
+                    .await
+                {
+                    warn!("gRPC server error: {}", e);
+                }
+            });
+
+            *self.grpc_handle.lock().unwrap() = Some(handle);
+        }
+
+        if let Some(state) = state.filter(|_| self.config.enable_http) {
             info!("HTTP API enabled on port {}", self.config.http_port);
 
             // Start HTTP server
-            let router = http_server::create_router(Arc::clone(&self.database));
+            let router = http_server::create_router_with_state(state);
             let addr = format!("0.0.0.0:{}", self.config.http_port);
             let listener = tokio::net::TcpListener::bind(&addr)
                 .await
@@ -98,7 +181,9 @@ impl Server {
 
             // Spawn HTTP server task
             let handle = tokio::spawn(async move {
-                if let Err(e) = axum::serve(listener, router).await {
+                // Peer addresses let /metrics admit allowlisted scrapers
+                let service = router.into_make_service_with_connect_info::<std::net::SocketAddr>();
+                if let Err(e) = axum::serve(listener, service).await {
                     warn!("HTTP server error: {}", e);
                 }
             });
@@ -162,6 +247,14 @@ mod tests {
         assert!(server.config().enable_http);
     }
 
+    #[test]
+    fn test_invalid_password_hash_config() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = ServerConfig::for_development(temp_dir.path())
+            .password_hash_config(crate::security::PasswordHashConfig::pbkdf2_sha256(0));
+        assert!(Server::new(config).is_err());
+    }
+
     #[test]
     fn test_server_database() {
         let (server, _dir) = create_test_server();
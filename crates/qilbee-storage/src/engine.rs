@@ -1,12 +1,25 @@
 //! Storage engine implementation using RocksDB
 
-use crate::keys::KeyBuilder;
-use crate::options::StorageOptions;
-use qilbee_core::{EntityId, Error, GraphId, Node, NodeId, PropertyValue, Relationship, RelationshipId, Result};
-use rocksdb::{ColumnFamily, ColumnFamilyDescriptor, Options, WriteBatch, DB};
+use crate::history::{Change, ChangeLog, GraphDiff};
+use crate::keys::{prefix, sortable_property_value, KeyBuilder, KeyDecoder};
+use crate::metrics::{
+    ticker_count, ColumnFamilyMetrics, StorageMetrics, BLOCK_CACHE_HIT_TICKER, BLOCK_CACHE_MISS_TICKER,
+};
+use crate::options::{Compression, StorageOptions};
+use crate::snapshot::StorageSnapshot;
+use qilbee_core::temporal::TransactionTime;
+use qilbee_core::{
+    compare_floats, EntityId, Error, GraphId, Node, NodeId, Property, PropertyValue, Relationship, RelationshipId, Result,
+};
+use serde::{Deserialize, Serialize};
+use rocksdb::{
+    Cache, ColumnFamily, ColumnFamilyDescriptor, Direction, IteratorMode, Options, Snapshot, WriteBatch, WriteOptions, DB,
+};
 use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
 use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
 use tracing::{debug, info, warn};
 
 /// Compute a hash for a property value for indexing
@@ -52,7 +65,8 @@ fn hash_property_value(value: &PropertyValue) -> u64 {
 /// Compare two property values for ordering
 /// Returns -1 if a < b, 0 if a == b, 1 if a > b
 /// For incompatible types, returns 0 (equal)
-fn compare_property_values(a: &PropertyValue, b: &PropertyValue) -> i32 {
+/// Numbers within `float_epsilon` of each other are equal
+fn compare_property_values(a: &PropertyValue, b: &PropertyValue, float_epsilon: Option<f64>) -> i32 {
     match (a, b) {
         (PropertyValue::Null, PropertyValue::Null) => 0,
         (PropertyValue::Null, _) => -1,
@@ -61,16 +75,16 @@ fn compare_property_values(a: &PropertyValue, b: &PropertyValue) -> i32 {
         (PropertyValue::Boolean(a), PropertyValue::Boolean(b)) => a.cmp(b) as i32,
         (PropertyValue::Integer(a), PropertyValue::Integer(b)) => a.cmp(b) as i32,
         (PropertyValue::Float(a), PropertyValue::Float(b)) => {
-            a.partial_cmp(b).map(|o| o as i32).unwrap_or(0)
+            compare_floats(*a, *b, float_epsilon).map(|o| o as i32).unwrap_or(0)
         }
         (PropertyValue::String(a), PropertyValue::String(b)) => a.cmp(b) as i32,
 
         // Allow integer/float comparison
         (PropertyValue::Integer(a), PropertyValue::Float(b)) => {
-            (*a as f64).partial_cmp(b).map(|o| o as i32).unwrap_or(0)
+            compare_floats(*a as f64, *b, float_epsilon).map(|o| o as i32).unwrap_or(0)
         }
         (PropertyValue::Float(a), PropertyValue::Integer(b)) => {
-            a.partial_cmp(&(*b as f64)).map(|o| o as i32).unwrap_or(0)
+            compare_floats(*a, *b as f64, float_epsilon).map(|o| o as i32).unwrap_or(0)
         }
 
         // Date/time comparisons
@@ -92,11 +106,40 @@ pub mod cf {
     pub const ADJACENCY_OUT: &str = "adjacency_out";
     pub const ADJACENCY_IN: &str = "adjacency_in";
     pub const PROPERTY_INDEX: &str = "property_index";
+    pub const REL_PROPERTY_INDEX: &str = "rel_property_index";
+    pub const HISTORY: &str = "history";
     pub const SCHEMA: &str = "schema";
     pub const META: &str = "meta";
     pub const MEMORY: &str = "memory";
+    pub const TOKEN_BLACKLIST: &str = "token_blacklist";
+    pub const USERS: &str = "users";
+    pub const REFRESH_TOKENS: &str = "refresh_tokens";
 }
 
+/// Meta key recording which property the ordered adjacency index is built on
+const ADJACENCY_SORT_META: &str = "adjacency_sort_property";
+
+/// Relationships re-indexed per write when the adjacency sort property changes
+const ADJACENCY_REINDEX_BATCH: usize = 10_000;
+
+/// Meta key marking that label and relationship type counts are maintained
+const ENTITY_COUNTS_META: &str = "entity_counts";
+
+/// Meta key marking that relationship properties are indexed
+const REL_PROPERTY_INDEX_META: &str = "rel_property_index";
+
+/// Graph meta key of the version counter bumped by every write to a graph
+const GRAPH_VERSION_META: &str = "version";
+
+/// Schema key type of composite index definitions
+const COMPOSITE_INDEX_SCHEMA: &str = "composite_index";
+
+/// Entities written per batch by `bulk_seed`
+const SEED_BATCH_SIZE: usize = 10_000;
+
+/// Nodes indexed per batch by `build_property_index`
+const INDEX_BUILD_BATCH_SIZE: usize = 1_000;
+
 /// All column families used by QilbeeDB
 pub const COLUMN_FAMILIES: &[&str] = &[
     cf::NODES,
@@ -105,15 +148,133 @@ pub const COLUMN_FAMILIES: &[&str] = &[
     cf::ADJACENCY_OUT,
     cf::ADJACENCY_IN,
     cf::PROPERTY_INDEX,
+    cf::REL_PROPERTY_INDEX,
+    cf::HISTORY,
     cf::SCHEMA,
     cf::META,
     cf::MEMORY,
+    cf::TOKEN_BLACKLIST,
+    cf::USERS,
+    cf::REFRESH_TOKENS,
 ];
 
+fn compression_type(compression: Compression) -> rocksdb::DBCompressionType {
+    match compression {
+        Compression::None => rocksdb::DBCompressionType::None,
+        Compression::Snappy => rocksdb::DBCompressionType::Snappy,
+        Compression::Lz4 => rocksdb::DBCompressionType::Lz4,
+        Compression::Zstd => rocksdb::DBCompressionType::Zstd,
+    }
+}
+
 /// The main storage engine for QilbeeDB
 pub struct StorageEngine {
     db: Arc<DB>,
     options: StorageOptions,
+    /// Serializes entity reads with the writes that depend on them (existence
+    /// checks, replacing a node's index entries, updating stored counts)
+    create_lock: Arc<Mutex<()>>,
+    /// Orders change history records written in the same millisecond
+    history_sequence: Arc<AtomicU64>,
+    /// Composite index definitions by graph, mirrored from the schema column family
+    composite_indexes: Arc<RwLock<HashMap<GraphId, Vec<CompositeIndex>>>>,
+}
+
+/// Progress of [`StorageEngine::build_property_index`], reported after each batch
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexBuildProgress {
+    /// Nodes with the label examined so far
+    pub scanned: u64,
+    /// Nodes with the label found when the build started
+    pub total: u64,
+    /// Nodes given an index entry so far
+    pub indexed: u64,
+}
+
+/// Nodes per label and relationships per type of a graph
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeCounts {
+    pub labels: BTreeMap<String, u64>,
+    pub relationship_types: BTreeMap<String, u64>,
+    /// Whether the counts were taken by scanning the label index and
+    /// adjacency lists, because the database has no maintained counts
+    pub scanned: bool,
+}
+
+/// An index over an ordered list of node properties
+///
+/// Each node with the label and the first property gets one entry keyed by
+/// the hashes of its values in index order, so nodes matching values for any
+/// leading subset of the properties are found by a single prefix scan.
+/// Missing trailing properties are indexed as null.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CompositeIndex {
+    /// Name of the index, unique within a graph
+    pub name: String,
+    /// Label of the indexed nodes
+    pub label: String,
+    /// Indexed properties, most significant first
+    pub properties: Vec<String>,
+}
+
+impl CompositeIndex {
+    /// Create a composite index definition
+    pub fn new<I, S>(name: &str, label: &str, properties: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            name: name.to_string(),
+            label: label.to_string(),
+            properties: properties.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Hashes of `node`'s values for the indexed properties, or `None` if
+    /// the node does not belong in the index
+    fn value_hashes(&self, node: &Node) -> Option<Vec<u64>> {
+        if !node.labels.iter().any(|label| label.name() == self.label) {
+            return None;
+        }
+        node.properties.get(self.properties.first()?)?;
+        Some(
+            self.properties
+                .iter()
+                .map(|property| hash_property_value(node.properties.get(property).unwrap_or(&PropertyValue::Null)))
+                .collect(),
+        )
+    }
+
+    /// Whether `node` belongs in the index with `values` for its leading
+    /// properties, comparing numbers within `epsilon`
+    pub(crate) fn matches(&self, node: &Node, values: &[PropertyValue], epsilon: Option<f64>) -> bool {
+        node.has_label_name(&self.label)
+            && self
+                .properties
+                .iter()
+                .zip(values)
+                .all(|(property, value)| node.properties.get(property).is_some_and(|actual| actual.equals_within(value, epsilon)))
+    }
+}
+
+/// Changes to the stored label and relationship type counts made by a batch
+#[derive(Debug, Default)]
+struct CountDeltas(HashMap<Vec<u8>, i64>);
+
+impl CountDeltas {
+    /// Count `node` once per label, `delta` being +1 for a write and -1 for a delete
+    fn node(&mut self, graph_id: GraphId, node: &Node, delta: i64) {
+        for label in &node.labels {
+            *self.0.entry(KeyBuilder::label_count(graph_id, label.name())).or_default() += delta;
+        }
+    }
+
+    /// Count `rel` under its type, `delta` being +1 for a write and -1 for a delete
+    fn relationship(&mut self, graph_id: GraphId, rel: &Relationship, delta: i64) {
+        let key = KeyBuilder::relationship_type_count(graph_id, rel.rel_type.name());
+        *self.0.entry(key).or_default() += delta;
+    }
 }
 
 impl StorageEngine {
@@ -121,6 +282,29 @@ impl StorageEngine {
     pub fn open(options: StorageOptions) -> Result<Self> {
         info!("Opening storage engine at {:?}", options.path);
 
+        let engine = Self::open_db(options, false)?;
+        engine.load_composite_indexes()?;
+        engine.sync_ordered_adjacency()?;
+        engine.sync_entity_counts()?;
+        engine.sync_rel_property_index()?;
+
+        info!("Storage engine opened successfully");
+        Ok(engine)
+    }
+
+    /// Open an existing database, such as a checkpoint, for reading only
+    ///
+    /// Writes fail. Nothing is migrated, so the database must be in the
+    /// current storage format.
+    pub fn open_read_only(options: StorageOptions) -> Result<Self> {
+        info!("Opening storage engine at {:?} read-only", options.path);
+
+        let engine = Self::open_db(options, true)?;
+        engine.load_composite_indexes()?;
+        Ok(engine)
+    }
+
+    fn open_db(options: StorageOptions, read_only: bool) -> Result<Self> {
         let mut db_opts = Options::default();
         db_opts.create_if_missing(options.create_if_missing);
         db_opts.create_missing_column_families(true);
@@ -129,37 +313,262 @@ impl StorageEngine {
         db_opts.set_target_file_size_base(options.target_file_size_base);
         db_opts.set_max_bytes_for_level_base(options.max_bytes_for_level_base);
         db_opts.set_max_background_jobs(options.max_background_jobs);
+        // Tickers such as block cache hits are only counted with statistics on
+        db_opts.enable_statistics();
 
         if options.enable_compression {
             db_opts.set_compression_type(rocksdb::DBCompressionType::Lz4);
         }
 
+        if let Some(name) = options.column_families.keys().find(|name| !COLUMN_FAMILIES.contains(&name.as_str())) {
+            return Err(Error::Configuration(format!("Unknown column family '{}'", name)));
+        }
+
         // Create column family descriptors
+        let shared_cache = Cache::new_lru_cache(options.block_cache_size);
         let cf_descriptors: Vec<ColumnFamilyDescriptor> = COLUMN_FAMILIES
             .iter()
             .map(|name| {
+                let overrides = options.column_families.get(*name).copied().unwrap_or_default();
                 let mut cf_opts = Options::default();
+                cf_opts.set_compression_type(compression_type(options.compression_for(name)));
+
+                let mut block_opts = rocksdb::BlockBasedOptions::default();
+                match overrides.block_cache_size {
+                    Some(size) => block_opts.set_block_cache(&Cache::new_lru_cache(size)),
+                    None => block_opts.set_block_cache(&shared_cache),
+                }
                 if options.enable_bloom_filter {
-                    let mut block_opts = rocksdb::BlockBasedOptions::default();
                     block_opts
                         .set_bloom_filter(options.bloom_filter_bits_per_key as f64, false);
-                    cf_opts.set_block_based_table_factory(&block_opts);
                 }
+                cf_opts.set_block_based_table_factory(&block_opts);
                 ColumnFamilyDescriptor::new(*name, cf_opts)
             })
             .collect();
 
-        let db = DB::open_cf_descriptors(&db_opts, &options.path, cf_descriptors)
-            .map_err(|e| Error::Storage(e.to_string()))?;
-
-        info!("Storage engine opened successfully");
+        let db = if read_only {
+            DB::open_cf_descriptors_read_only(&db_opts, &options.path, cf_descriptors, false)
+        } else {
+            DB::open_cf_descriptors(&db_opts, &options.path, cf_descriptors)
+        }
+        .map_err(|e| Error::Storage(e.to_string()))?;
 
         Ok(Self {
             db: Arc::new(db),
             options,
+            create_lock: Arc::new(Mutex::new(())),
+            history_sequence: Arc::new(AtomicU64::new(0)),
+            composite_indexes: Arc::new(RwLock::new(HashMap::new())),
         })
     }
 
+    /// Rebuild the ordered adjacency index if it was built for a different
+    /// sort property than the one configured, or drop it if none is
+    fn sync_ordered_adjacency(&self) -> Result<()> {
+        let wanted = self.options.adjacency_sort_property.as_deref();
+        let current = self.get_meta(ADJACENCY_SORT_META)?;
+        if current.as_deref() == wanted.map(str::as_bytes) {
+            return Ok(());
+        }
+
+        info!("Rebuilding ordered adjacency index for property {:?}", wanted);
+
+        let mut batch = WriteBatch::default();
+        for (cf_name, key_prefix) in [
+            (cf::ADJACENCY_OUT, prefix::ADJACENCY_OUT_ORDERED),
+            (cf::ADJACENCY_IN, prefix::ADJACENCY_IN_ORDERED),
+        ] {
+            batch.delete_range_cf(self.cf(cf_name)?, [key_prefix], [key_prefix + 1]);
+        }
+
+        if let Some(property) = wanted {
+            let cf = self.cf(cf::RELATIONSHIPS)?;
+            let mut pending = 0;
+            for item in self.db.iterator_cf(&cf, IteratorMode::Start) {
+                let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+
+                // Relationship keys are prefix, graph ID, relationship ID
+                let mut decoder = KeyDecoder::new(&key);
+                let (Some(prefix::RELATIONSHIP), Some(graph_id)) =
+                    (decoder.read_u8(), decoder.read_u64())
+                else {
+                    continue;
+                };
+
+                let rel: Relationship = bincode::deserialize(&value)
+                    .map_err(|e| Error::Deserialization(e.to_string()))?;
+                self.batch_put_ordered_adjacency(
+                    &mut batch,
+                    GraphId::from_internal(graph_id),
+                    &rel,
+                    property,
+                )?;
+
+                pending += 1;
+                if pending == ADJACENCY_REINDEX_BATCH {
+                    self.write_batch(std::mem::take(&mut batch))?;
+                    pending = 0;
+                }
+            }
+        }
+
+        let meta_cf = self.cf(cf::META)?;
+        let meta_key = KeyBuilder::meta(ADJACENCY_SORT_META);
+        match wanted {
+            Some(property) => batch.put_cf(&meta_cf, &meta_key, property.as_bytes()),
+            None => batch.delete_cf(&meta_cf, &meta_key),
+        }
+
+        self.write_batch(batch)
+    }
+
+    /// Count the labels and relationship types of a database written before
+    /// counts were maintained on every write
+    fn sync_entity_counts(&self) -> Result<()> {
+        if self.get_meta(ENTITY_COUNTS_META)?.is_some() {
+            return Ok(());
+        }
+
+        info!("Building label and relationship type counts");
+
+        let mut counts: HashMap<Vec<u8>, u64> = HashMap::new();
+
+        // Label index keys are prefix, graph ID, label, node ID
+        let label_cf = self.cf(cf::LABEL_INDEX)?;
+        for item in self.db.iterator_cf(&label_cf, IteratorMode::Start) {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            let mut decoder = KeyDecoder::new(&key);
+            if let (Some(prefix::LABEL_INDEX), Some(graph_id), Some(label)) =
+                (decoder.read_u8(), decoder.read_u64(), decoder.read_string())
+            {
+                let key = KeyBuilder::label_count(GraphId::from_internal(graph_id), label);
+                *counts.entry(key).or_default() += 1;
+            }
+        }
+
+        // Unordered outgoing adjacency keys are prefix, graph ID, source, type, relationship ID
+        let adj_out_cf = self.cf(cf::ADJACENCY_OUT)?;
+        for item in self.db.iterator_cf(&adj_out_cf, IteratorMode::Start) {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            let mut decoder = KeyDecoder::new(&key);
+            if let (Some(prefix::ADJACENCY_OUT), Some(graph_id), Some(_), Some(rel_type)) = (
+                decoder.read_u8(),
+                decoder.read_u64(),
+                decoder.read_u64(),
+                decoder.read_string(),
+            ) {
+                let key =
+                    KeyBuilder::relationship_type_count(GraphId::from_internal(graph_id), rel_type);
+                *counts.entry(key).or_default() += 1;
+            }
+        }
+
+        let meta_cf = self.cf(cf::META)?;
+        let mut batch = WriteBatch::default();
+        for (key, count) in counts {
+            batch.put_cf(&meta_cf, &key, count.to_be_bytes());
+        }
+        batch.put_cf(&meta_cf, KeyBuilder::meta(ENTITY_COUNTS_META), [1]);
+
+        self.write_batch(batch)
+    }
+
+    /// Index the properties of relationships stored before relationship
+    /// properties were indexed
+    ///
+    /// Runs once per database; afterwards every relationship write keeps the
+    /// index up to date.
+    fn sync_rel_property_index(&self) -> Result<()> {
+        if self.get_meta(REL_PROPERTY_INDEX_META)?.is_some() {
+            return Ok(());
+        }
+
+        info!("Building relationship property index");
+
+        // Relationship keys are prefix, graph ID, relationship ID
+        let rel_cf = self.cf(cf::RELATIONSHIPS)?;
+        let mut batch = WriteBatch::default();
+        for item in self.db.iterator_cf(&rel_cf, IteratorMode::Start) {
+            let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            let mut decoder = KeyDecoder::new(&key);
+            if let (Some(prefix::RELATIONSHIP), Some(graph_id)) = (decoder.read_u8(), decoder.read_u64()) {
+                let rel: Relationship = bincode::deserialize(&value)
+                    .map_err(|e| Error::Deserialization(e.to_string()))?;
+                self.batch_put_rel_property_index(&mut batch, GraphId::from_internal(graph_id), &rel)?;
+            }
+        }
+
+        let meta_cf = self.cf(cf::META)?;
+        batch.put_cf(&meta_cf, KeyBuilder::meta(REL_PROPERTY_INDEX_META), [1]);
+        self.write_batch(batch)
+    }
+
+    /// Add the updated values of the counts changed by `deltas` to `batch`
+    ///
+    /// Must be called under [`Self::lock_entity_writes`] and the batch written
+    /// before releasing it, or concurrent writers lose each other's updates.
+    fn batch_apply_counts(&self, batch: &mut WriteBatch, deltas: CountDeltas) -> Result<()> {
+        let cf = self.cf(cf::META)?;
+        for (key, delta) in deltas.0 {
+            if delta == 0 {
+                continue;
+            }
+            let current = self.read_count(&key)?;
+            let updated = current.saturating_add_signed(delta);
+            if updated == 0 {
+                batch.delete_cf(&cf, &key);
+            } else {
+                batch.put_cf(&cf, &key, updated.to_be_bytes());
+            }
+        }
+        Ok(())
+    }
+
+    /// Read a stored count, 0 if absent
+    fn read_count(&self, key: &[u8]) -> Result<u64> {
+        let cf = self.cf(cf::META)?;
+        let value = self
+            .db
+            .get_cf(&cf, key)
+            .map_err(|e| Error::Storage(e.to_string()))?;
+
+        match value {
+            Some(bytes) => {
+                let bytes: [u8; 8] = bytes
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| Error::DataCorruption("Invalid entity count".to_string()))?;
+                Ok(u64::from_be_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Add the change history records of `log` to `batch`, bumping the
+    /// graph's version if it changed anything
+    ///
+    /// Must be called under [`Self::lock_entity_writes`], like
+    /// [`Self::batch_apply_counts`], so records are ordered as their writes.
+    /// Every entity write goes through here, so the version changes with
+    /// every write that lands.
+    fn batch_record_history(&self, batch: &mut WriteBatch, graph_id: GraphId, log: ChangeLog) -> Result<()> {
+        if !log.0.is_empty() {
+            let key = KeyBuilder::graph_meta(graph_id, GRAPH_VERSION_META);
+            let version = self.read_count(&key)? + 1;
+            batch.put_cf(self.cf(cf::META)?, key, version.to_be_bytes());
+        }
+
+        let cf = self.cf(cf::HISTORY)?;
+        let now = TransactionTime::now().as_millis();
+        for change in log.0 {
+            let sequence = self.history_sequence.fetch_add(1, Ordering::Relaxed);
+            let value = bincode::serialize(&change).map_err(|e| Error::Serialization(e.to_string()))?;
+            batch.put_cf(&cf, KeyBuilder::history(graph_id, now, sequence), value);
+        }
+        Ok(())
+    }
+
     /// Get a reference to a column family
     fn cf(&self, name: &str) -> Result<&ColumnFamily> {
         self.db
@@ -167,14 +576,219 @@ impl StorageEngine {
             .ok_or_else(|| Error::Internal(format!("Column family not found: {}", name)))
     }
 
+    /// Read a key from the latest state, or from `snapshot` when given
+    fn get_at(&self, snapshot: Option<&Snapshot<'_>>, cf_name: &str, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf = self.cf(cf_name)?;
+        match snapshot {
+            Some(snapshot) => snapshot.get_cf(&cf, key),
+            None => self.db.get_cf(&cf, key),
+        }
+        .map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    /// Iterate forward from `prefix` in the latest state, or in `snapshot`
+    /// when given
+    ///
+    /// Callers stop at the first key without the prefix.
+    fn scan_at<'s>(
+        &'s self,
+        snapshot: Option<&'s Snapshot<'_>>,
+        cf_name: &str,
+        prefix: &[u8],
+    ) -> Result<Box<dyn Iterator<Item = std::result::Result<(Box<[u8]>, Box<[u8]>), rocksdb::Error>> + 's>> {
+        let cf = self.cf(cf_name)?;
+        Ok(match snapshot {
+            Some(snapshot) => Box::new(snapshot.iterator_cf(&cf, IteratorMode::From(prefix, Direction::Forward))),
+            None => Box::new(self.db.prefix_iterator_cf(&cf, prefix)),
+        })
+    }
+
+    /// Take a RocksDB snapshot of the latest state
+    pub(crate) fn db_snapshot(&self) -> Snapshot<'_> {
+        self.db.snapshot()
+    }
+
+    /// Write options derived from the WAL settings
+    fn write_options(&self) -> WriteOptions {
+        let mut opts = WriteOptions::default();
+        opts.disable_wal(!self.options.enable_wal);
+        opts.set_sync(self.options.enable_wal && self.options.sync_wal);
+        opts
+    }
+
+    /// Apply a batch atomically
+    ///
+    /// Once this returns, the batch is visible to every reader of this engine
+    /// (and its clones) without a flush. Whether it survives a crash depends on
+    /// `enable_wal` and `sync_wal`.
+    fn write_batch(&self, batch: WriteBatch) -> Result<()> {
+        self.db
+            .write_opt(batch, &self.write_options())
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    /// Reject an entity whose properties exceed the configured limits
+    fn check_property_limits(
+        &self,
+        kind: &str,
+        id: impl std::fmt::Debug,
+        properties: &Property,
+    ) -> Result<()> {
+        if let Some(max) = self.options.max_properties_per_entity {
+            if properties.len() > max {
+                return Err(Error::QuotaExceeded(format!(
+                    "{} {:?} has {} properties, more than the limit of {}",
+                    kind,
+                    id,
+                    properties.len(),
+                    max
+                )));
+            }
+        }
+
+        if let Some(max) = self.options.max_property_value_bytes {
+            for (name, value) in properties.iter() {
+                let size = bincode::serialized_size(value)
+                    .map_err(|e| Error::Serialization(e.to_string()))?;
+                if size > max as u64 {
+                    return Err(Error::QuotaExceeded(format!(
+                        "Property '{}' of {} {:?} is {} bytes, more than the limit of {} bytes",
+                        name, kind, id, size, max
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     // ========== Node Operations ==========
 
-    /// Store a node
-    pub fn put_node(&self, graph_id: GraphId, node: &Node) -> Result<()> {
+    /// Create a new node, failing with `Error::Conflict` if the ID is taken
+    ///
+    /// Unlike `put_node`, this never overwrites an existing node.
+    pub fn create_node(&self, graph_id: GraphId, node: &Node) -> Result<()> {
+        self.create_node_checked(graph_id, node, || Ok(()))
+    }
+
+    /// Like [`Self::create_node`], running `check` first while holding the
+    /// write lock, so no other write lands between the check and the insert
+    ///
+    /// Used to enforce constraints that depend on other stored nodes; if
+    /// `check` fails nothing is written.
+    pub fn create_node_checked(
+        &self,
+        graph_id: GraphId,
+        node: &Node,
+        check: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let _guard = self.lock_entity_writes()?;
+        check()?;
+
         let key = KeyBuilder::node(graph_id, node.id);
-        let value = bincode::serialize(node).map_err(|e| Error::Serialization(e.to_string()))?;
+        let cf = self.cf(cf::NODES)?;
+        let exists = self
+            .db
+            .get_pinned_cf(&cf, &key)
+            .map_err(|e| Error::Storage(e.to_string()))?
+            .is_some();
+
+        if exists {
+            return Err(Error::Conflict(format!(
+                "Node {:?} already exists in graph {:?}",
+                node.id, graph_id
+            )));
+        }
+
+        let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+        self.batch_put_node(&mut batch, graph_id, node)?;
+        deltas.node(graph_id, node, 1);
+        history.node(None, Some(node.clone()));
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+        self.write_batch(batch)?;
+
+        debug!("Created node {:?} in graph {:?}", node.id, graph_id);
+        Ok(())
+    }
+
+    /// Store many new nodes and their index entries in one atomic write
+    ///
+    /// Unlike [`Self::create_node`], the nodes are not checked for existing
+    /// nodes with the same ID, so they must have freshly generated IDs.
+    pub fn create_nodes(&self, graph_id: GraphId, nodes: &[Node]) -> Result<()> {
+        let _guard = self.lock_entity_writes()?;
+
+        let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+        for node in nodes {
+            self.batch_put_node(&mut batch, graph_id, node)?;
+            deltas.node(graph_id, node, 1);
+            history.node(None, Some(node.clone()));
+        }
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+        self.write_batch(batch)?;
+
+        debug!("Created {} nodes in graph {:?}", nodes.len(), graph_id);
+        Ok(())
+    }
+
+    /// Store a node, overwriting any existing node with the same ID
+    ///
+    /// Label and property index entries of the previous version are replaced
+    /// in the same batch, so lookups never see values the node no longer has.
+    pub fn put_node(&self, graph_id: GraphId, node: &Node) -> Result<()> {
+        self.put_node_checked(graph_id, node, || Ok(()))
+    }
+
+    /// Like [`Self::put_node`], running `check` first while holding the
+    /// write lock; if it fails nothing is written
+    pub fn put_node_checked(
+        &self,
+        graph_id: GraphId,
+        node: &Node,
+        check: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let _guard = self.lock_entity_writes()?;
+        check()?;
 
         let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+        let previous = self.get_node(graph_id, node.id)?;
+        if let Some(previous) = &previous {
+            self.batch_delete_node_indexes(&mut batch, graph_id, previous)?;
+            deltas.node(graph_id, previous, -1);
+        }
+        self.batch_put_node(&mut batch, graph_id, node)?;
+        deltas.node(graph_id, node, 1);
+        history.node(previous, Some(node.clone()));
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+        self.write_batch(batch)?;
+
+        debug!("Stored node {:?} in graph {:?}", node.id, graph_id);
+        Ok(())
+    }
+
+    /// Hold while writing an entity based on a read of its stored version or
+    /// of the stored counts
+    fn lock_entity_writes(&self) -> Result<std::sync::MutexGuard<'_, ()>> {
+        self.create_lock
+            .lock()
+            .map_err(|_| Error::Internal("Failed to acquire create lock".to_string()))
+    }
+
+    /// Add a node with its label and property index entries to `batch`
+    fn batch_put_node(&self, batch: &mut WriteBatch, graph_id: GraphId, node: &Node) -> Result<()> {
+        self.check_property_limits("Node", node.id, &node.properties)?;
+
+        let key = KeyBuilder::node(graph_id, node.id);
+        let value = bincode::serialize(node).map_err(|e| Error::Serialization(e.to_string()))?;
 
         // Store node data
         let cf = self.cf(cf::NODES)?;
@@ -191,52 +805,118 @@ impl StorageEngine {
         let prop_cf = self.cf(cf::PROPERTY_INDEX)?;
         for label in &node.labels {
             for (prop_name, prop_value) in node.properties.iter() {
-                let value_hash = hash_property_value(prop_value);
-                let prop_key = KeyBuilder::property_index(
-                    graph_id,
-                    label.name(),
-                    prop_name,
-                    value_hash,
-                    node.id.as_internal(),
-                );
-                // Store the serialized property value for retrieval
-                let prop_value_bytes = bincode::serialize(prop_value)
-                    .map_err(|e| Error::Serialization(e.to_string()))?;
-                batch.put_cf(&prop_cf, &prop_key, &prop_value_bytes);
+                Self::batch_put_property_entry(batch, prop_cf, graph_id, label.name(), prop_name, prop_value, node.id)?;
             }
         }
 
-        self.db
-            .write(batch)
-            .map_err(|e| Error::Storage(e.to_string()))?;
+        // Update composite indices
+        for index in self.composite_indexes(graph_id)? {
+            if let Some(hashes) = index.value_hashes(node) {
+                let key = KeyBuilder::composite_index(graph_id, &index.name, &hashes, node.id);
+                batch.put_cf(&prop_cf, &key, []);
+            }
+        }
 
-        debug!("Stored node {:?} in graph {:?}", node.id, graph_id);
+        Ok(())
+    }
+
+    /// Add the property index entry of one label, property and value of a node
+    fn batch_put_property_entry(
+        batch: &mut WriteBatch,
+        prop_cf: &ColumnFamily,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
+        value: &PropertyValue,
+        node_id: NodeId,
+    ) -> Result<()> {
+        let value_hash = hash_property_value(value);
+        let prop_key = KeyBuilder::property_index(graph_id, label, property, value_hash, node_id.as_internal());
+        // Store the serialized property value for retrieval
+        let prop_value_bytes = bincode::serialize(value).map_err(|e| Error::Serialization(e.to_string()))?;
+        batch.put_cf(prop_cf, &prop_key, &prop_value_bytes);
+        Ok(())
+    }
+
+    /// Load a pre-built set of nodes and relationships, such as a
+    /// [`qilbee_core::dataset::Dataset`]
+    ///
+    /// Entities are written with all their indexes in large batches, which is
+    /// much faster than storing them one by one. IDs are taken as given and
+    /// graph-level bookkeeping (entity counts, ID reservations) is not
+    /// updated, so this is meant for benchmarks and tests rather than
+    /// importing into a live graph.
+    pub fn bulk_seed(
+        &self,
+        graph_id: GraphId,
+        nodes: &[Node],
+        relationships: &[Relationship],
+    ) -> Result<()> {
+        for chunk in nodes.chunks(SEED_BATCH_SIZE) {
+            self.create_nodes(graph_id, chunk)?;
+        }
+
+        for chunk in relationships.chunks(SEED_BATCH_SIZE) {
+            self.put_relationships(graph_id, chunk)?;
+        }
+
+        info!(
+            "Seeded graph {:?} with {} nodes and {} relationships",
+            graph_id,
+            nodes.len(),
+            relationships.len()
+        );
         Ok(())
     }
 
     /// Get a node by ID
     pub fn get_node(&self, graph_id: GraphId, node_id: NodeId) -> Result<Option<Node>> {
+        self.node_at(None, graph_id, node_id)
+    }
+
+    /// Get a node by ID as of `snapshot`, or the latest version
+    pub(crate) fn node_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        node_id: NodeId,
+    ) -> Result<Option<Node>> {
         let key = KeyBuilder::node(graph_id, node_id);
-        let cf = self.cf(cf::NODES)?;
 
-        match self.db.get_cf(&cf, &key) {
-            Ok(Some(value)) => {
+        match self.get_at(snapshot, cf::NODES, &key)? {
+            Some(value) => {
                 let node: Node = bincode::deserialize(&value)
                     .map_err(|e| Error::Deserialization(e.to_string()))?;
                 Ok(Some(node))
             }
-            Ok(None) => Ok(None),
-            Err(e) => Err(Error::Storage(e.to_string())),
+            None => Ok(None),
         }
     }
 
     /// Delete a node
+    ///
+    /// Relationships of the node are left in place; see
+    /// [`Self::detach_delete_node`].
     pub fn delete_node(&self, graph_id: GraphId, node_id: NodeId) -> Result<bool> {
+        self.delete_node_checked(graph_id, node_id, || Ok(()))
+    }
+
+    /// Like [`Self::delete_node`], running `check` first while holding the
+    /// write lock; if it fails nothing is deleted
+    pub fn delete_node_checked(
+        &self,
+        graph_id: GraphId,
+        node_id: NodeId,
+        check: impl FnOnce() -> Result<()>,
+    ) -> Result<bool> {
+        let _guard = self.lock_entity_writes()?;
+
         // First get the node to remove label and property indices
         let node = match self.get_node(graph_id, node_id)? {
             Some(n) => n,
             None => return Ok(false),
         };
+        check()?;
 
         let mut batch = WriteBatch::default();
 
@@ -244,44 +924,163 @@ impl StorageEngine {
         let key = KeyBuilder::node(graph_id, node_id);
         let cf = self.cf(cf::NODES)?;
         batch.delete_cf(&cf, &key);
+        self.batch_delete_node_indexes(&mut batch, graph_id, &node)?;
 
-        // Remove label indices
-        let label_cf = self.cf(cf::LABEL_INDEX)?;
-        for label in &node.labels {
-            let label_key = KeyBuilder::label_index(graph_id, label.name(), node_id);
-            batch.delete_cf(&label_cf, &label_key);
-        }
-
-        // Remove property indices
-        let prop_cf = self.cf(cf::PROPERTY_INDEX)?;
-        for label in &node.labels {
-            for (prop_name, prop_value) in node.properties.iter() {
-                let value_hash = hash_property_value(prop_value);
-                let prop_key = KeyBuilder::property_index(
-                    graph_id,
-                    label.name(),
-                    prop_name,
-                    value_hash,
-                    node_id.as_internal(),
-                );
-                batch.delete_cf(&prop_cf, &prop_key);
-            }
-        }
+        let mut deltas = CountDeltas::default();
+        deltas.node(graph_id, &node, -1);
+        self.batch_apply_counts(&mut batch, deltas)?;
+        let mut history = ChangeLog::default();
+        history.node(Some(node), None);
+        self.batch_record_history(&mut batch, graph_id, history)?;
 
-        self.db
-            .write(batch)
-            .map_err(|e| Error::Storage(e.to_string()))?;
+        self.write_batch(batch)?;
 
         debug!("Deleted node {:?} from graph {:?}", node_id, graph_id);
         Ok(true)
     }
 
-    /// Get all nodes in a graph
-    pub fn get_all_nodes(&self, graph_id: GraphId) -> Result<Vec<Node>> {
-        let prefix = KeyBuilder::node_prefix(graph_id);
-        let cf = self.cf(cf::NODES)?;
-
-        let mut nodes = Vec::new();
+    /// Delete up to `limit` nodes with a label and all their relationships
+    ///
+    /// The nodes and relationships are deleted in one write batch. Returns
+    /// the number of nodes and relationships deleted; no nodes are left with
+    /// the label once this returns zero nodes.
+    pub fn detach_delete_nodes_with_label(
+        &self,
+        graph_id: GraphId,
+        label: &str,
+        limit: usize,
+    ) -> Result<(u64, u64)> {
+        let _guard = self.lock_entity_writes()?;
+
+        let prefix = KeyBuilder::label_index_prefix(graph_id, label);
+        let mut nodes = Vec::new();
+        for item in self.scan_at(None, cf::LABEL_INDEX, &prefix)? {
+            if nodes.len() >= limit {
+                break;
+            }
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let node_id = KeyDecoder::new(&key[prefix.len()..])
+                .read_u64()
+                .map(NodeId::from_internal)
+                .ok_or_else(|| Error::DataCorruption("Invalid label index key".to_string()))?;
+            if let Some(node) = self.get_node(graph_id, node_id)? {
+                nodes.push(node);
+            }
+        }
+
+        let counts = self.write_detach_delete(graph_id, nodes)?;
+
+        debug!(
+            "Deleted {} nodes with label {} and {} relationships from graph {:?}",
+            counts.0, label, counts.1, graph_id
+        );
+        Ok(counts)
+    }
+
+    /// Delete a node and all its relationships in one write batch
+    ///
+    /// Returns the number of relationships deleted, or `None` if the node
+    /// did not exist.
+    pub fn detach_delete_node(&self, graph_id: GraphId, node_id: NodeId) -> Result<Option<u64>> {
+        let _guard = self.lock_entity_writes()?;
+
+        let Some(node) = self.get_node(graph_id, node_id)? else {
+            return Ok(None);
+        };
+        let (_, relationships) = self.write_detach_delete(graph_id, vec![node])?;
+
+        debug!(
+            "Deleted node {:?} and {} relationships from graph {:?}",
+            node_id, relationships, graph_id
+        );
+        Ok(Some(relationships))
+    }
+
+    /// Write the deletes of `nodes` and all their relationships as one batch,
+    /// returning the number of nodes and relationships deleted
+    ///
+    /// Callers hold the entity write lock.
+    fn write_detach_delete(&self, graph_id: GraphId, nodes: Vec<Node>) -> Result<(u64, u64)> {
+        let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+
+        // Relationships between two of the nodes, and self-loops, are found twice
+        let mut relationships = HashMap::new();
+        for node in &nodes {
+            for rel in self.get_outgoing_relationships(graph_id, node.id)? {
+                relationships.insert(rel.id, rel);
+            }
+            for rel in self.get_incoming_relationships(graph_id, node.id)? {
+                relationships.insert(rel.id, rel);
+            }
+        }
+        for rel in relationships.values() {
+            self.batch_delete_relationship(&mut batch, &mut deltas, &mut history, graph_id, rel)?;
+        }
+
+        let nodes_cf = self.cf(cf::NODES)?;
+        for node in &nodes {
+            batch.delete_cf(&nodes_cf, KeyBuilder::node(graph_id, node.id));
+            self.batch_delete_node_indexes(&mut batch, graph_id, node)?;
+            deltas.node(graph_id, node, -1);
+        }
+        let counts = (nodes.len() as u64, relationships.len() as u64);
+        for node in nodes {
+            history.node(Some(node), None);
+        }
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+
+        self.write_batch(batch)?;
+        Ok(counts)
+    }
+
+    /// Add deletes for a node's label and property index entries to `batch`
+    fn batch_delete_node_indexes(&self, batch: &mut WriteBatch, graph_id: GraphId, node: &Node) -> Result<()> {
+        // Remove label indices
+        let label_cf = self.cf(cf::LABEL_INDEX)?;
+        for label in &node.labels {
+            let label_key = KeyBuilder::label_index(graph_id, label.name(), node.id);
+            batch.delete_cf(&label_cf, &label_key);
+        }
+
+        // Remove property indices
+        let prop_cf = self.cf(cf::PROPERTY_INDEX)?;
+        for label in &node.labels {
+            for (prop_name, prop_value) in node.properties.iter() {
+                let value_hash = hash_property_value(prop_value);
+                let prop_key = KeyBuilder::property_index(
+                    graph_id,
+                    label.name(),
+                    prop_name,
+                    value_hash,
+                    node.id.as_internal(),
+                );
+                batch.delete_cf(&prop_cf, &prop_key);
+            }
+        }
+
+        // Remove composite indices
+        for index in self.composite_indexes(graph_id)? {
+            if let Some(hashes) = index.value_hashes(node) {
+                let key = KeyBuilder::composite_index(graph_id, &index.name, &hashes, node.id);
+                batch.delete_cf(&prop_cf, &key);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Get all nodes in a graph
+    pub fn get_all_nodes(&self, graph_id: GraphId) -> Result<Vec<Node>> {
+        let prefix = KeyBuilder::node_prefix(graph_id);
+        let cf = self.cf(cf::NODES)?;
+
+        let mut nodes = Vec::new();
         let iter = self.db.prefix_iterator_cf(&cf, &prefix);
 
         for item in iter {
@@ -301,13 +1100,187 @@ impl StorageEngine {
         Ok(nodes)
     }
 
+    /// Get the IDs of all nodes in a graph
+    ///
+    /// Only reads keys, so whole-graph algorithms can enumerate nodes without
+    /// deserializing labels and properties.
+    pub fn get_all_node_ids(&self, graph_id: GraphId) -> Result<Vec<NodeId>> {
+        let prefix = KeyBuilder::node_prefix(graph_id);
+        let cf = self.cf(cf::NODES)?;
+
+        let mut ids = Vec::new();
+        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
+
+        for item in iter {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+
+            if !key.starts_with(&prefix) {
+                break;
+            }
+
+            // Node keys end with the 8-byte node ID
+            if key.len() >= 8 {
+                let id_bytes: [u8; 8] = key[key.len() - 8..].try_into().unwrap();
+                ids.push(NodeId::from_internal(u64::from_be_bytes(id_bytes)));
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Get up to `limit` IDs of the nodes of a graph, or of those with
+    /// `label`, that come after `after`, in ID order
+    pub fn node_ids_after(
+        &self,
+        graph_id: GraphId,
+        label: Option<&str>,
+        after: Option<NodeId>,
+        limit: usize,
+    ) -> Result<Vec<NodeId>> {
+        self.node_ids_at(None, graph_id, label, after, limit)
+    }
+
+    /// Get up to `limit` IDs of the nodes of a graph, or of those with
+    /// `label`, that come after `after`, in ID order, as of `snapshot` or in
+    /// the latest state
+    ///
+    /// Node and label index keys end with the big-endian node ID, so each
+    /// page is a single seek past the last ID of the previous one.
+    pub(crate) fn node_ids_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        label: Option<&str>,
+        after: Option<NodeId>,
+        limit: usize,
+    ) -> Result<Vec<NodeId>> {
+        let (cf_name, prefix) = match label {
+            Some(label) => (cf::LABEL_INDEX, KeyBuilder::label_index_prefix(graph_id, label)),
+            None => (cf::NODES, KeyBuilder::node_prefix(graph_id)),
+        };
+        let mut start = prefix.clone();
+        match after.map(|id| id.as_internal().checked_add(1)) {
+            Some(Some(next)) => start.extend_from_slice(&next.to_be_bytes()),
+            // Nothing comes after the largest ID
+            Some(None) => return Ok(Vec::new()),
+            None => {}
+        }
+
+        let mut ids = Vec::new();
+        for item in self.scan_at(snapshot, cf_name, &start)? {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&prefix) || ids.len() >= limit {
+                break;
+            }
+            if key.len() == prefix.len() + 8 {
+                let id_bytes: [u8; 8] = key[prefix.len()..].try_into().unwrap();
+                ids.push(NodeId::from_internal(u64::from_be_bytes(id_bytes)));
+            }
+        }
+
+        Ok(ids)
+    }
+
+    /// Count the nodes carrying a label
+    ///
+    /// Reads the count maintained on every write, so the planner can size
+    /// label scans without touching the label index.
+    pub fn label_count(&self, graph_id: GraphId, label: &str) -> Result<u64> {
+        self.read_count(&KeyBuilder::label_count(graph_id, label))
+    }
+
+    /// Version of a graph's data, 0 before its first write
+    ///
+    /// Bumped in the same batch as every write that creates, changes or
+    /// deletes a node or relationship, so a result computed while the version
+    /// was unchanged is still current.
+    pub fn graph_version(&self, graph_id: GraphId) -> Result<u64> {
+        self.read_count(&KeyBuilder::graph_meta(graph_id, GRAPH_VERSION_META))
+    }
+
+    /// Count the nodes of every label and the relationships of every type
+    ///
+    /// Reads the counts maintained on every write, in time proportional to
+    /// the number of labels and types. A database opened read only before
+    /// its counts were built has none, so the label index and adjacency
+    /// lists are scanned instead and the result is marked as scanned.
+    pub fn type_counts(&self, graph_id: GraphId) -> Result<TypeCounts> {
+        if self.get_meta(ENTITY_COUNTS_META)?.is_some() {
+            let read_counts = |prefix: Vec<u8>| -> Result<BTreeMap<String, u64>> {
+                let mut counts = BTreeMap::new();
+                for item in self.scan_at(None, cf::META, &prefix)? {
+                    let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+                    if !key.starts_with(&prefix) {
+                        break;
+                    }
+                    let name = KeyDecoder::new(&key[prefix.len()..])
+                        .read_string()
+                        .ok_or_else(|| Error::DataCorruption("Invalid count key".to_string()))?;
+                    let count: [u8; 8] = value
+                        .as_ref()
+                        .try_into()
+                        .map_err(|_| Error::DataCorruption("Invalid entity count".to_string()))?;
+                    counts.insert(name.to_string(), u64::from_be_bytes(count));
+                }
+                Ok(counts)
+            };
+            return Ok(TypeCounts {
+                labels: read_counts(KeyBuilder::label_count_prefix(graph_id))?,
+                relationship_types: read_counts(KeyBuilder::relationship_type_count_prefix(graph_id))?,
+                scanned: false,
+            });
+        }
+
+        let mut counts = TypeCounts { scanned: true, ..Default::default() };
+
+        // Label index keys are prefix, graph ID, label, node ID
+        let prefix = KeyBuilder::label_index_graph_prefix(graph_id);
+        for item in self.scan_at(None, cf::LABEL_INDEX, &prefix)? {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            if let Some(label) = KeyDecoder::new(&key[prefix.len()..]).read_string() {
+                *counts.labels.entry(label.to_string()).or_default() += 1;
+            }
+        }
+
+        // Unordered outgoing adjacency keys are prefix, graph ID, source, type, relationship ID
+        let prefix = KeyBuilder::adjacency_out_graph_prefix(graph_id);
+        for item in self.scan_at(None, cf::ADJACENCY_OUT, &prefix)? {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let mut decoder = KeyDecoder::new(&key[prefix.len()..]);
+            if let (Some(_), Some(rel_type)) = (decoder.read_u64(), decoder.read_string()) {
+                *counts.relationship_types.entry(rel_type.to_string()).or_default() += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Count the relationships of a type, maintained like [`Self::label_count`]
+    pub fn relationship_type_count(&self, graph_id: GraphId, rel_type: &str) -> Result<u64> {
+        self.read_count(&KeyBuilder::relationship_type_count(graph_id, rel_type))
+    }
+
     /// Get all nodes with a specific label
     pub fn get_nodes_by_label(&self, graph_id: GraphId, label: &str) -> Result<Vec<Node>> {
+        self.nodes_by_label_at(None, graph_id, label)
+    }
+
+    /// Get all nodes with a label as of `snapshot`, or the latest versions
+    pub(crate) fn nodes_by_label_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        label: &str,
+    ) -> Result<Vec<Node>> {
         let prefix = KeyBuilder::label_index_prefix(graph_id, label);
-        let cf = self.cf(cf::LABEL_INDEX)?;
 
         let mut nodes = Vec::new();
-        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
+        let iter = self.scan_at(snapshot, cf::LABEL_INDEX, &prefix)?;
 
         for item in iter {
             let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
@@ -322,7 +1295,7 @@ impl StorageEngine {
                 let node_id_bytes: [u8; 8] = key[key.len() - 8..].try_into().unwrap();
                 let node_id = NodeId::from_internal(u64::from_be_bytes(node_id_bytes));
 
-                if let Some(node) = self.get_node(graph_id, node_id)? {
+                if let Some(node) = self.node_at(snapshot, graph_id, node_id)? {
                     nodes.push(node);
                 }
             }
@@ -340,12 +1313,36 @@ impl StorageEngine {
         property: &str,
         value: &PropertyValue,
     ) -> Result<Vec<Node>> {
+        self.nodes_by_property_at(None, graph_id, label, property, value)
+    }
+
+    /// Get nodes by property value as of `snapshot`, or the latest versions
+    pub(crate) fn nodes_by_property_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
+        value: &PropertyValue,
+    ) -> Result<Vec<Node>> {
+        // Near-equal numbers hash differently, so with a tolerance they are
+        // compared on every node that has the property
+        let epsilon = self.options.float_epsilon;
+        if epsilon.is_some() && matches!(value, PropertyValue::Float(_) | PropertyValue::Integer(_)) {
+            let mut nodes = self.nodes_with_property_at(snapshot, graph_id, label, property)?;
+            nodes.retain(|node| {
+                node.properties
+                    .get(property)
+                    .is_some_and(|actual| actual.equals_within(value, epsilon))
+            });
+            return Ok(nodes);
+        }
+
         let value_hash = hash_property_value(value);
         let prefix = KeyBuilder::property_index_value_prefix(graph_id, label, property, value_hash);
-        let cf = self.cf(cf::PROPERTY_INDEX)?;
 
         let mut nodes = Vec::new();
-        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
+        let iter = self.scan_at(snapshot, cf::PROPERTY_INDEX, &prefix)?;
 
         for item in iter {
             let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
@@ -360,7 +1357,7 @@ impl StorageEngine {
                 let node_id_bytes: [u8; 8] = key[key.len() - 8..].try_into().unwrap();
                 let node_id = NodeId::from_internal(u64::from_be_bytes(node_id_bytes));
 
-                if let Some(node) = self.get_node(graph_id, node_id)? {
+                if let Some(node) = self.node_at(snapshot, graph_id, node_id)? {
                     // Verify the property value matches (in case of hash collision)
                     if let Some(actual_value) = node.properties.get(property) {
                         if actual_value == value {
@@ -380,12 +1377,22 @@ impl StorageEngine {
         graph_id: GraphId,
         label: &str,
         property: &str,
+    ) -> Result<Vec<Node>> {
+        self.nodes_with_property_at(None, graph_id, label, property)
+    }
+
+    /// Get nodes that have a property as of `snapshot`, or the latest versions
+    pub(crate) fn nodes_with_property_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
     ) -> Result<Vec<Node>> {
         let prefix = KeyBuilder::property_index_prefix(graph_id, label, property);
-        let cf = self.cf(cf::PROPERTY_INDEX)?;
 
         let mut node_ids = std::collections::HashSet::new();
-        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
+        let iter = self.scan_at(snapshot, cf::PROPERTY_INDEX, &prefix)?;
 
         for item in iter {
             let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
@@ -406,7 +1413,7 @@ impl StorageEngine {
         // Fetch all unique nodes
         let mut nodes = Vec::new();
         for node_id in node_ids {
-            if let Some(node) = self.get_node(graph_id, node_id)? {
+            if let Some(node) = self.node_at(snapshot, graph_id, node_id)? {
                 nodes.push(node);
             }
         }
@@ -423,23 +1430,322 @@ impl StorageEngine {
         property: &str,
         min_value: Option<&PropertyValue>,
         max_value: Option<&PropertyValue>,
+    ) -> Result<Vec<Node>> {
+        self.nodes_by_property_range_at(None, graph_id, label, property, min_value, max_value)
+    }
+
+    /// Get nodes by property range as of `snapshot`, or the latest versions
+    pub(crate) fn nodes_by_property_range_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
+        min_value: Option<&PropertyValue>,
+        max_value: Option<&PropertyValue>,
     ) -> Result<Vec<Node>> {
         // For range queries, we need to scan all nodes with the property
         // and filter by range. This is less efficient than B-tree but works.
-        let all_nodes = self.get_nodes_with_property(graph_id, label, property)?;
+        let all_nodes = self.nodes_with_property_at(snapshot, graph_id, label, property)?;
 
         let nodes: Vec<Node> = all_nodes
             .into_iter()
-            .filter(|node| {
-                if let Some(value) = node.properties.get(property) {
-                    let above_min = min_value.map_or(true, |min| compare_property_values(value, min) >= 0);
-                    let below_max = max_value.map_or(true, |max| compare_property_values(value, max) <= 0);
-                    above_min && below_max
-                } else {
-                    false
+            .filter(|node| self.property_in_range(node, property, min_value, max_value))
+            .collect();
+
+        Ok(nodes)
+    }
+
+    /// Whether `node` has `property` within `[min, max]`, comparing numbers
+    /// within the configured float tolerance
+    pub(crate) fn property_in_range(
+        &self,
+        node: &Node,
+        property: &str,
+        min_value: Option<&PropertyValue>,
+        max_value: Option<&PropertyValue>,
+    ) -> bool {
+        if let Some(value) = node.properties.get(property) {
+            let epsilon = self.options.float_epsilon;
+            let above_min = min_value.map_or(true, |min| compare_property_values(value, min, epsilon) >= 0);
+            let below_max = max_value.map_or(true, |max| compare_property_values(value, max, epsilon) <= 0);
+            above_min && below_max
+        } else {
+            false
+        }
+    }
+
+    /// Distance within which floats compare equal, if any
+    pub fn float_epsilon(&self) -> Option<f64> {
+        self.options.float_epsilon
+    }
+
+    // ========== Schema Definitions ==========
+
+    /// Store a schema definition of a graph, such as a constraint, under its
+    /// type and name
+    pub fn put_schema_object(&self, graph_id: GraphId, schema_type: &str, name: &str, value: &[u8]) -> Result<()> {
+        let cf = self.cf(cf::SCHEMA)?;
+        self.db
+            .put_cf_opt(&cf, KeyBuilder::schema(graph_id, schema_type, name), value, &self.write_options())
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    /// Remove a schema definition of a graph
+    pub fn delete_schema_object(&self, graph_id: GraphId, schema_type: &str, name: &str) -> Result<()> {
+        let cf = self.cf(cf::SCHEMA)?;
+        self.db
+            .delete_cf_opt(&cf, KeyBuilder::schema(graph_id, schema_type, name), &self.write_options())
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    /// Get the names and values of a graph's schema definitions of one type
+    pub fn schema_objects(&self, graph_id: GraphId, schema_type: &str) -> Result<Vec<(String, Vec<u8>)>> {
+        let prefix = KeyBuilder::schema_prefix(graph_id, schema_type);
+        let cf = self.cf(cf::SCHEMA)?;
+
+        let mut objects = Vec::new();
+        for item in self.db.prefix_iterator_cf(&cf, &prefix) {
+            let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            let name = KeyDecoder::new(&key[prefix.len()..])
+                .read_string()
+                .ok_or_else(|| Error::DataCorruption("Invalid schema key".to_string()))?;
+            objects.push((name.to_string(), value.to_vec()));
+        }
+        Ok(objects)
+    }
+
+    // ========== Property Index Builds ==========
+
+    /// Write the property index entries of `label` and `property` for every
+    /// stored node that has them, returning how many nodes were indexed
+    ///
+    /// Writes keep their own nodes indexed, so this only fills in entries
+    /// missing from data stored without them, such as nodes written by older
+    /// versions. The graph stays readable and writable throughout: the nodes
+    /// to visit are listed from a snapshot, then indexed in batches, each
+    /// holding the write lock only while it rereads its nodes and writes
+    /// their entries. Rereading catches up with writes made since the
+    /// snapshot, so nodes deleted meanwhile are skipped and updated ones are
+    /// indexed by their current value; nodes created meanwhile were indexed
+    /// when written. `progress` is called after each batch.
+    pub fn build_property_index(
+        &self,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
+        mut progress: impl FnMut(IndexBuildProgress),
+    ) -> Result<u64> {
+        let node_ids: Vec<NodeId> = {
+            let snapshot = self.db.snapshot();
+            self.nodes_by_label_at(Some(&snapshot), graph_id, label)?
+                .into_iter()
+                .map(|node| node.id)
+                .collect()
+        };
+
+        let prop_cf = self.cf(cf::PROPERTY_INDEX)?;
+        let mut status = IndexBuildProgress { total: node_ids.len() as u64, ..Default::default() };
+        for chunk in node_ids.chunks(INDEX_BUILD_BATCH_SIZE) {
+            let _guard = self.lock_entity_writes()?;
+            let mut batch = WriteBatch::default();
+            for &node_id in chunk {
+                let Some(node) = self.get_node(graph_id, node_id)? else {
+                    continue;
+                };
+                if let (true, Some(value)) = (node.has_label_name(label), node.properties.get(property)) {
+                    Self::batch_put_property_entry(&mut batch, prop_cf, graph_id, label, property, value, node_id)?;
+                    status.indexed += 1;
                 }
+            }
+            self.write_batch(batch)?;
+            status.scanned += chunk.len() as u64;
+            progress(status);
+        }
+
+        info!("Indexed {} :{} nodes on {}", status.indexed, label, property);
+        Ok(status.indexed)
+    }
+
+    // ========== Composite Indexes ==========
+
+    /// Load the composite index definitions of every graph
+    fn load_composite_indexes(&self) -> Result<()> {
+        // Definition keys are prefix, graph ID, schema type, index name
+        let cf = self.cf(cf::SCHEMA)?;
+        let mut indexes: HashMap<GraphId, Vec<CompositeIndex>> = HashMap::new();
+        for item in self.db.prefix_iterator_cf(&cf, [prefix::SCHEMA]) {
+            let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            let mut decoder = KeyDecoder::new(&key);
+            let (Some(prefix::SCHEMA), Some(graph_id), Some(COMPOSITE_INDEX_SCHEMA)) =
+                (decoder.read_u8(), decoder.read_u64(), decoder.read_string())
+            else {
+                continue;
+            };
+            let index: CompositeIndex = bincode::deserialize(&value)
+                .map_err(|e| Error::Deserialization(e.to_string()))?;
+            indexes.entry(GraphId::from_internal(graph_id)).or_default().push(index);
+        }
+
+        *self
+            .composite_indexes
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire composite index lock".to_string()))? = indexes;
+        Ok(())
+    }
+
+    /// Get the composite indexes of a graph
+    pub fn composite_indexes(&self, graph_id: GraphId) -> Result<Vec<CompositeIndex>> {
+        let indexes = self
+            .composite_indexes
+            .read()
+            .map_err(|_| Error::Internal("Failed to acquire composite index lock".to_string()))?;
+        Ok(indexes.get(&graph_id).cloned().unwrap_or_default())
+    }
+
+    /// Create a composite index and index the nodes already stored
+    ///
+    /// Returns `false` if the graph already has a composite index with the
+    /// same name.
+    pub fn create_composite_index(&self, graph_id: GraphId, index: CompositeIndex) -> Result<bool> {
+        if index.properties.is_empty() {
+            return Err(Error::IndexOperation(format!(
+                "Composite index {} has no properties",
+                index.name
+            )));
+        }
+
+        let _guard = self.lock_entity_writes()?;
+        if self.composite_indexes(graph_id)?.iter().any(|existing| existing.name == index.name) {
+            return Ok(false);
+        }
+
+        let mut batch = WriteBatch::default();
+        let prop_cf = self.cf(cf::PROPERTY_INDEX)?;
+        for node in self.get_nodes_by_label(graph_id, &index.label)? {
+            if let Some(hashes) = index.value_hashes(&node) {
+                let key = KeyBuilder::composite_index(graph_id, &index.name, &hashes, node.id);
+                batch.put_cf(&prop_cf, &key, []);
+            }
+        }
+        let schema_cf = self.cf(cf::SCHEMA)?;
+        let value = bincode::serialize(&index).map_err(|e| Error::Serialization(e.to_string()))?;
+        batch.put_cf(&schema_cf, KeyBuilder::schema(graph_id, COMPOSITE_INDEX_SCHEMA, &index.name), value);
+        self.write_batch(batch)?;
+
+        info!("Created composite index {} on {}({})", index.name, index.label, index.properties.join(", "));
+        self.composite_indexes
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire composite index lock".to_string()))?
+            .entry(graph_id)
+            .or_default()
+            .push(index);
+        Ok(true)
+    }
+
+    /// Drop a composite index and its entries
+    ///
+    /// Returns `false` if the graph has no composite index with this name.
+    pub fn drop_composite_index(&self, graph_id: GraphId, name: &str) -> Result<bool> {
+        let _guard = self.lock_entity_writes()?;
+        let mut indexes = self
+            .composite_indexes
+            .write()
+            .map_err(|_| Error::Internal("Failed to acquire composite index lock".to_string()))?;
+        let Some(graph_indexes) = indexes.get_mut(&graph_id) else {
+            return Ok(false);
+        };
+        let Some(position) = graph_indexes.iter().position(|index| index.name == name) else {
+            return Ok(false);
+        };
+
+        let mut batch = WriteBatch::default();
+        let prop_cf = self.cf(cf::PROPERTY_INDEX)?;
+        let prefix = KeyBuilder::composite_index_prefix(graph_id, name, &[]);
+        for item in self.db.prefix_iterator_cf(&prop_cf, &prefix) {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+            batch.delete_cf(&prop_cf, &key);
+        }
+        let schema_cf = self.cf(cf::SCHEMA)?;
+        batch.delete_cf(&schema_cf, KeyBuilder::schema(graph_id, COMPOSITE_INDEX_SCHEMA, name));
+        self.write_batch(batch)?;
+
+        graph_indexes.remove(position);
+        info!("Dropped composite index {}", name);
+        Ok(true)
+    }
+
+    /// Get nodes by the values of the leading properties of a composite index
+    ///
+    /// `values` are matched against the index's properties in order and may
+    /// cover any non-empty prefix of them. The matching nodes are found by a
+    /// single prefix scan of the index.
+    pub fn get_nodes_by_composite_property(
+        &self,
+        graph_id: GraphId,
+        index_name: &str,
+        values: &[PropertyValue],
+    ) -> Result<Vec<Node>> {
+        self.nodes_by_composite_property_at(None, graph_id, index_name, values)
+    }
+
+    /// Get nodes by composite index values as of `snapshot`, or the latest versions
+    pub(crate) fn nodes_by_composite_property_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        index_name: &str,
+        values: &[PropertyValue],
+    ) -> Result<Vec<Node>> {
+        let index = self
+            .composite_indexes(graph_id)?
+            .into_iter()
+            .find(|index| index.name == index_name)
+            .ok_or_else(|| Error::IndexNotFound(index_name.to_string()))?;
+        if values.is_empty() || values.len() > index.properties.len() {
+            return Err(Error::IndexOperation(format!(
+                "Composite index {} takes 1 to {} values, got {}",
+                index_name,
+                index.properties.len(),
+                values.len()
+            )));
+        }
+
+        // With a float tolerance, numbers are checked on each node rather
+        // than looked up by hash, so the scan stops at the first number
+        let epsilon = self.options.float_epsilon;
+        let hashes: Vec<u64> = values
+            .iter()
+            .take_while(|value| {
+                epsilon.is_none() || !matches!(value, PropertyValue::Float(_) | PropertyValue::Integer(_))
             })
+            .map(hash_property_value)
             .collect();
+        let prefix = KeyBuilder::composite_index_prefix(graph_id, index_name, &hashes);
+
+        let mut nodes = Vec::new();
+        for item in self.scan_at(snapshot, cf::PROPERTY_INDEX, &prefix)? {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&prefix) {
+                break;
+            }
+
+            // The node ID is the last 8 bytes of the key
+            let node_id_bytes: [u8; 8] = key[key.len() - 8..].try_into().unwrap();
+            let node_id = NodeId::from_internal(u64::from_be_bytes(node_id_bytes));
+            if let Some(node) = self.node_at(snapshot, graph_id, node_id)? {
+                // Verify the values match (in case of hash collision)
+                if index.matches(&node, values, epsilon) {
+                    nodes.push(node);
+                }
+            }
+        }
 
         Ok(nodes)
     }
@@ -448,10 +1754,85 @@ impl StorageEngine {
 
     /// Store a relationship
     pub fn put_relationship(&self, graph_id: GraphId, rel: &Relationship) -> Result<()> {
-        let key = KeyBuilder::relationship(graph_id, rel.id);
-        let value = bincode::serialize(rel).map_err(|e| Error::Serialization(e.to_string()))?;
+        self.put_relationship_checked(graph_id, rel, || Ok(()))
+    }
+
+    /// Like [`Self::put_relationship`], running `check` first while holding
+    /// the write lock; if it fails nothing is written
+    pub fn put_relationship_checked(
+        &self,
+        graph_id: GraphId,
+        rel: &Relationship,
+        check: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let _guard = self.lock_entity_writes()?;
+        check()?;
+
+        let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+        self.batch_put_relationship(&mut batch, &mut deltas, &mut history, graph_id, rel)?;
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+
+        self.write_batch(batch)?;
+
+        debug!(
+            "Stored relationship {:?} ({:?})-[:{}]->({:?})",
+            rel.id,
+            rel.source,
+            rel.rel_type.name(),
+            rel.target
+        );
+        Ok(())
+    }
+
+    /// Store many relationships and their adjacency entries in one atomic write
+    pub fn put_relationships(&self, graph_id: GraphId, rels: &[Relationship]) -> Result<()> {
+        self.put_relationships_checked(graph_id, rels, || Ok(()))
+    }
+
+    /// Like [`Self::put_relationships`], running `check` first while holding
+    /// the write lock; if it fails nothing is written
+    pub fn put_relationships_checked(
+        &self,
+        graph_id: GraphId,
+        rels: &[Relationship],
+        check: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let _guard = self.lock_entity_writes()?;
+        check()?;
 
         let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+        for rel in rels {
+            self.batch_put_relationship(&mut batch, &mut deltas, &mut history, graph_id, rel)?;
+        }
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+
+        self.write_batch(batch)?;
+
+        debug!("Stored {} relationships in graph {:?}", rels.len(), graph_id);
+        Ok(())
+    }
+
+    /// Add a relationship and both adjacency index entries to `batch`, its
+    /// type count change to `deltas` and the write to `history`
+    fn batch_put_relationship(
+        &self,
+        batch: &mut WriteBatch,
+        deltas: &mut CountDeltas,
+        history: &mut ChangeLog,
+        graph_id: GraphId,
+        rel: &Relationship,
+    ) -> Result<()> {
+        self.check_property_limits("Relationship", rel.id, &rel.properties)?;
+        let existing = self.get_relationship(graph_id, rel.id)?;
+
+        let key = KeyBuilder::relationship(graph_id, rel.id);
+        let value = bincode::serialize(rel).map_err(|e| Error::Serialization(e.to_string()))?;
 
         // Store relationship data
         let cf = self.cf(cf::RELATIONSHIPS)?;
@@ -471,17 +1852,116 @@ impl StorageEngine {
         let source_bytes = rel.source.as_internal().to_be_bytes();
         batch.put_cf(&adj_in_cf, &adj_in_key, &source_bytes);
 
-        self.db
-            .write(batch)
-            .map_err(|e| Error::Storage(e.to_string()))?;
+        if let Some(property) = &self.options.adjacency_sort_property {
+            // Drop the old ordered entries in case the sort value changed
+            if let Some(existing) = &existing {
+                self.batch_delete_ordered_adjacency(batch, graph_id, existing, property)?;
+            }
+            self.batch_put_ordered_adjacency(batch, graph_id, rel, property)?;
+        }
 
-        debug!(
-            "Stored relationship {:?} ({:?})-[:{}]->({:?})",
-            rel.id,
-            rel.source,
-            rel.rel_type.name(),
-            rel.target
+        // Replace the property index entries of the stored version
+        if let Some(existing) = &existing {
+            self.batch_delete_rel_property_index(batch, graph_id, existing)?;
+        }
+        self.batch_put_rel_property_index(batch, graph_id, rel)?;
+
+        if let Some(existing) = &existing {
+            deltas.relationship(graph_id, existing, -1);
+        }
+        deltas.relationship(graph_id, rel, 1);
+        history.relationship(existing, Some(rel.clone()));
+
+        Ok(())
+    }
+
+    /// Add the property index entries of a relationship to `batch`
+    fn batch_put_rel_property_index(
+        &self,
+        batch: &mut WriteBatch,
+        graph_id: GraphId,
+        rel: &Relationship,
+    ) -> Result<()> {
+        let prop_cf = self.cf(cf::REL_PROPERTY_INDEX)?;
+        for (prop_name, prop_value) in rel.properties.iter() {
+            let prop_key = KeyBuilder::rel_property_index(
+                graph_id,
+                rel.rel_type.name(),
+                prop_name,
+                hash_property_value(prop_value),
+                rel.id,
+            );
+            batch.put_cf(&prop_cf, &prop_key, &[]);
+        }
+        Ok(())
+    }
+
+    /// Remove the property index entries of a relationship in `batch`
+    fn batch_delete_rel_property_index(
+        &self,
+        batch: &mut WriteBatch,
+        graph_id: GraphId,
+        rel: &Relationship,
+    ) -> Result<()> {
+        let prop_cf = self.cf(cf::REL_PROPERTY_INDEX)?;
+        for (prop_name, prop_value) in rel.properties.iter() {
+            let prop_key = KeyBuilder::rel_property_index(
+                graph_id,
+                rel.rel_type.name(),
+                prop_name,
+                hash_property_value(prop_value),
+                rel.id,
+            );
+            batch.delete_cf(&prop_cf, &prop_key);
+        }
+        Ok(())
+    }
+
+    /// Add the property-ordered adjacency entries of a relationship to `batch`
+    fn batch_put_ordered_adjacency(
+        &self,
+        batch: &mut WriteBatch,
+        graph_id: GraphId,
+        rel: &Relationship,
+        property: &str,
+    ) -> Result<()> {
+        let sort_key = sortable_property_value(rel.get_property(property));
+
+        let adj_out_cf = self.cf(cf::ADJACENCY_OUT)?;
+        let adj_out_key =
+            KeyBuilder::adjacency_out_ordered(graph_id, rel.source, &sort_key, rel.id);
+        batch.put_cf(&adj_out_cf, &adj_out_key, rel.target.as_internal().to_be_bytes());
+
+        let adj_in_cf = self.cf(cf::ADJACENCY_IN)?;
+        let adj_in_key =
+            KeyBuilder::adjacency_in_ordered(graph_id, rel.target, &sort_key, rel.id);
+        batch.put_cf(&adj_in_cf, &adj_in_key, rel.source.as_internal().to_be_bytes());
+
+        Ok(())
+    }
+
+    /// Remove the property-ordered adjacency entries of a relationship in `batch`
+    fn batch_delete_ordered_adjacency(
+        &self,
+        batch: &mut WriteBatch,
+        graph_id: GraphId,
+        rel: &Relationship,
+        property: &str,
+    ) -> Result<()> {
+        let sort_key = sortable_property_value(rel.get_property(property));
+
+        let adj_out_cf = self.cf(cf::ADJACENCY_OUT)?;
+        batch.delete_cf(
+            &adj_out_cf,
+            KeyBuilder::adjacency_out_ordered(graph_id, rel.source, &sort_key, rel.id),
+        );
+
+        let adj_in_cf = self.cf(cf::ADJACENCY_IN)?;
+        batch.delete_cf(
+            &adj_in_cf,
+            KeyBuilder::adjacency_in_ordered(graph_id, rel.target, &sort_key, rel.id),
         );
+
         Ok(())
     }
 
@@ -490,19 +1970,140 @@ impl StorageEngine {
         &self,
         graph_id: GraphId,
         rel_id: RelationshipId,
+    ) -> Result<Option<Relationship>> {
+        self.relationship_at(None, graph_id, rel_id)
+    }
+
+    /// Get a relationship by ID as of `snapshot`, or the latest version
+    pub(crate) fn relationship_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        rel_id: RelationshipId,
     ) -> Result<Option<Relationship>> {
         let key = KeyBuilder::relationship(graph_id, rel_id);
-        let cf = self.cf(cf::RELATIONSHIPS)?;
 
-        match self.db.get_cf(&cf, &key) {
-            Ok(Some(value)) => {
+        match self.get_at(snapshot, cf::RELATIONSHIPS, &key)? {
+            Some(value) => {
                 let rel: Relationship = bincode::deserialize(&value)
                     .map_err(|e| Error::Deserialization(e.to_string()))?;
                 Ok(Some(rel))
             }
-            Ok(None) => Ok(None),
-            Err(e) => Err(Error::Storage(e.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Get all relationships in a graph
+    pub fn get_all_relationships(&self, graph_id: GraphId) -> Result<Vec<Relationship>> {
+        self.scan_relationships(graph_id, |_| true)
+    }
+
+    /// Get all relationships of a type in a graph
+    ///
+    /// Relationship keys do not include the type, so this scans every
+    /// relationship in the graph. The stored type count lets a type with no
+    /// relationships return without scanning.
+    pub fn get_relationships_by_type(
+        &self,
+        graph_id: GraphId,
+        rel_type: &str,
+    ) -> Result<Vec<Relationship>> {
+        if self.relationship_type_count(graph_id, rel_type)? == 0 {
+            return Ok(Vec::new());
         }
+        self.scan_relationships(graph_id, |rel| rel.rel_type.name() == rel_type)
+    }
+
+    /// Get relationships of a type by property value using the relationship
+    /// property index
+    pub fn get_relationships_by_property(
+        &self,
+        graph_id: GraphId,
+        rel_type: &str,
+        property: &str,
+        value: &PropertyValue,
+    ) -> Result<Vec<Relationship>> {
+        let value_hash = hash_property_value(value);
+        let prefix =
+            KeyBuilder::rel_property_index_value_prefix(graph_id, rel_type, property, value_hash);
+        let cf = self.cf(cf::REL_PROPERTY_INDEX)?;
+
+        let mut relationships = Vec::new();
+        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
+
+        for item in iter {
+            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
+
+            if !key.starts_with(&prefix) {
+                break;
+            }
+
+            // Relationship ID is the last 8 bytes of the key
+            if key.len() >= 8 {
+                let rel_id_bytes: [u8; 8] = key[key.len() - 8..].try_into().unwrap();
+                let rel_id = RelationshipId::from_internal(u64::from_be_bytes(rel_id_bytes));
+
+                if let Some(rel) = self.get_relationship(graph_id, rel_id)? {
+                    // Verify the property value matches (in case of hash collision)
+                    if rel.properties.get(property) == Some(value) {
+                        relationships.push(rel);
+                    }
+                }
+            }
+        }
+
+        Ok(relationships)
+    }
+
+    /// Load the relationships of a graph that pass `filter`, in ID order
+    fn scan_relationships(
+        &self,
+        graph_id: GraphId,
+        filter: impl Fn(&Relationship) -> bool,
+    ) -> Result<Vec<Relationship>> {
+        let prefix = KeyBuilder::relationship_prefix(graph_id);
+        let cf = self.cf(cf::RELATIONSHIPS)?;
+
+        let mut relationships = Vec::new();
+        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
+
+        for item in iter {
+            let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+
+            if !key.starts_with(&prefix) {
+                break;
+            }
+
+            let rel: Relationship = bincode::deserialize(&value)
+                .map_err(|e| Error::Deserialization(e.to_string()))?;
+            if filter(&rel) {
+                relationships.push(rel);
+            }
+        }
+
+        Ok(relationships)
+    }
+
+    /// Iterate over the entities stored under `prefix` in key order, as of
+    /// `snapshot` or in the latest state
+    ///
+    /// Entities are read one at a time, so whole graphs can be walked without
+    /// holding them in memory.
+    pub(crate) fn entities_at<'s, T: serde::de::DeserializeOwned>(
+        &'s self,
+        snapshot: Option<&'s Snapshot<'_>>,
+        cf_name: &str,
+        prefix: Vec<u8>,
+    ) -> Result<impl Iterator<Item = Result<T>> + 's> {
+        let iter = self.scan_at(snapshot, cf_name, &prefix)?;
+        Ok(iter
+            .map(|item| item.map_err(|e| Error::Storage(e.to_string())))
+            .take_while(move |item| item.as_ref().map_or(true, |(key, _)| key.starts_with(&prefix)))
+            .map(|item| {
+                item.and_then(|(_, value)| {
+                    bincode::deserialize(&value).map_err(|e| Error::Deserialization(e.to_string()))
+                })
+            }))
     }
 
     /// Delete a relationship
@@ -511,6 +2112,8 @@ impl StorageEngine {
         graph_id: GraphId,
         rel_id: RelationshipId,
     ) -> Result<bool> {
+        let _guard = self.lock_entity_writes()?;
+
         // First get the relationship to remove adjacency indices
         let rel = match self.get_relationship(graph_id, rel_id)? {
             Some(r) => r,
@@ -518,9 +2121,30 @@ impl StorageEngine {
         };
 
         let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+        self.batch_delete_relationship(&mut batch, &mut deltas, &mut history, graph_id, &rel)?;
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+
+        self.write_batch(batch)?;
+
+        debug!("Deleted relationship {:?} from graph {:?}", rel_id, graph_id);
+        Ok(true)
+    }
 
+    /// Add deletes for a stored relationship and its adjacency entries to
+    /// `batch`, its type count change to `deltas` and the delete to `history`
+    fn batch_delete_relationship(
+        &self,
+        batch: &mut WriteBatch,
+        deltas: &mut CountDeltas,
+        history: &mut ChangeLog,
+        graph_id: GraphId,
+        rel: &Relationship,
+    ) -> Result<()> {
         // Remove relationship
-        let key = KeyBuilder::relationship(graph_id, rel_id);
+        let key = KeyBuilder::relationship(graph_id, rel.id);
         let cf = self.cf(cf::RELATIONSHIPS)?;
         batch.delete_cf(&cf, &key);
 
@@ -536,63 +2160,82 @@ impl StorageEngine {
             KeyBuilder::adjacency_in(graph_id, rel.target, rel.rel_type.name(), rel.id);
         batch.delete_cf(&adj_in_cf, &adj_in_key);
 
-        self.db
-            .write(batch)
-            .map_err(|e| Error::Storage(e.to_string()))?;
+        if let Some(property) = &self.options.adjacency_sort_property {
+            self.batch_delete_ordered_adjacency(batch, graph_id, rel, property)?;
+        }
+        self.batch_delete_rel_property_index(batch, graph_id, rel)?;
 
-        debug!("Deleted relationship {:?} from graph {:?}", rel_id, graph_id);
-        Ok(true)
+        deltas.relationship(graph_id, rel, -1);
+        history.relationship(Some(rel.clone()), None);
+        Ok(())
     }
 
     /// Get outgoing relationships from a node
+    ///
+    /// Relationships come back in relationship ID order, or ordered by
+    /// `StorageOptions::adjacency_sort_property` when one is configured.
     pub fn get_outgoing_relationships(
-        &self,
-        graph_id: GraphId,
-        node_id: NodeId,
-    ) -> Result<Vec<Relationship>> {
-        let prefix = KeyBuilder::adjacency_out_prefix(graph_id, node_id);
-        let cf = self.cf(cf::ADJACENCY_OUT)?;
-
-        let mut relationships = Vec::new();
-        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
-
-        for item in iter {
-            let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
-
-            if !key.starts_with(&prefix) {
-                break;
-            }
-
-            // Extract relationship ID from the end of the key
-            if key.len() >= 8 {
-                let rel_id_bytes: [u8; 8] = key[key.len() - 8..].try_into().unwrap();
-                let rel_id = RelationshipId::from_internal(u64::from_be_bytes(rel_id_bytes));
-
-                if let Some(rel) = self.get_relationship(graph_id, rel_id)? {
-                    relationships.push(rel);
-                }
-            }
-        }
+        &self,
+        graph_id: GraphId,
+        node_id: NodeId,
+    ) -> Result<Vec<Relationship>> {
+        self.outgoing_at(None, graph_id, node_id)
+    }
 
-        Ok(relationships)
+    /// Get outgoing relationships from a node as of `snapshot`, or the latest ones
+    pub(crate) fn outgoing_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        node_id: NodeId,
+    ) -> Result<Vec<Relationship>> {
+        let prefix = match self.options.adjacency_sort_property {
+            Some(_) => KeyBuilder::adjacency_out_ordered_prefix(graph_id, node_id),
+            None => KeyBuilder::adjacency_out_prefix(graph_id, node_id),
+        };
+        self.scan_adjacency(snapshot, cf::ADJACENCY_OUT, graph_id, &prefix)
     }
 
     /// Get incoming relationships to a node
+    ///
+    /// Ordered the same way as [`Self::get_outgoing_relationships`].
     pub fn get_incoming_relationships(
         &self,
         graph_id: GraphId,
         node_id: NodeId,
     ) -> Result<Vec<Relationship>> {
-        let prefix = KeyBuilder::adjacency_in_prefix(graph_id, node_id);
-        let cf = self.cf(cf::ADJACENCY_IN)?;
+        self.incoming_at(None, graph_id, node_id)
+    }
+
+    /// Get incoming relationships to a node as of `snapshot`, or the latest ones
+    pub(crate) fn incoming_at(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        graph_id: GraphId,
+        node_id: NodeId,
+    ) -> Result<Vec<Relationship>> {
+        let prefix = match self.options.adjacency_sort_property {
+            Some(_) => KeyBuilder::adjacency_in_ordered_prefix(graph_id, node_id),
+            None => KeyBuilder::adjacency_in_prefix(graph_id, node_id),
+        };
+        self.scan_adjacency(snapshot, cf::ADJACENCY_IN, graph_id, &prefix)
+    }
 
+    /// Load the relationships of every adjacency key under `prefix`, in key order
+    fn scan_adjacency(
+        &self,
+        snapshot: Option<&Snapshot<'_>>,
+        cf_name: &str,
+        graph_id: GraphId,
+        prefix: &[u8],
+    ) -> Result<Vec<Relationship>> {
         let mut relationships = Vec::new();
-        let iter = self.db.prefix_iterator_cf(&cf, &prefix);
+        let iter = self.scan_at(snapshot, cf_name, prefix)?;
 
         for item in iter {
             let (key, _) = item.map_err(|e| Error::Storage(e.to_string()))?;
 
-            if !key.starts_with(&prefix) {
+            if !key.starts_with(prefix) {
                 break;
             }
 
@@ -601,7 +2244,7 @@ impl StorageEngine {
                 let rel_id_bytes: [u8; 8] = key[key.len() - 8..].try_into().unwrap();
                 let rel_id = RelationshipId::from_internal(u64::from_be_bytes(rel_id_bytes));
 
-                if let Some(rel) = self.get_relationship(graph_id, rel_id)? {
+                if let Some(rel) = self.relationship_at(snapshot, graph_id, rel_id)? {
                     relationships.push(rel);
                 }
             }
@@ -618,7 +2261,7 @@ impl StorageEngine {
         let cf = self.cf(cf::META)?;
 
         self.db
-            .put_cf(&cf, &storage_key, value)
+            .put_cf_opt(&cf, &storage_key, value, &self.write_options())
             .map_err(|e| Error::Storage(e.to_string()))?;
 
         Ok(())
@@ -634,16 +2277,324 @@ impl StorageEngine {
             .map_err(|e| Error::Storage(e.to_string()))
     }
 
+    // ========== Agent Memory Operations ==========
+
+    /// Get a value from the agent memory column family
+    pub fn get_memory(&self, key: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf = self.cf(cf::MEMORY)?;
+
+        self.db
+            .get_cf(&cf, key)
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    /// Atomically write and delete agent memory entries
+    ///
+    /// Puts are applied before deletes, so a key in both lists ends up deleted.
+    pub fn write_memory(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let cf = self.cf(cf::MEMORY)?;
+
+        let mut batch = WriteBatch::default();
+        for (key, value) in puts {
+            batch.put_cf(&cf, key, value);
+        }
+        for key in deletes {
+            batch.delete_cf(&cf, key);
+        }
+
+        self.write_batch(batch)
+    }
+
+    /// Get all agent memory entries whose key starts with `prefix`, in key order
+    pub fn scan_memory(&self, prefix: &[u8]) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        self.scan_memory_from(prefix, prefix, usize::MAX)
+    }
+
+    /// Get up to `limit` agent memory entries whose key starts with `prefix`,
+    /// beginning at the first key at or after `start`, in key order
+    pub fn scan_memory_from(
+        &self,
+        prefix: &[u8],
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let cf = self.cf(cf::MEMORY)?;
+
+        let mut entries = Vec::new();
+        let iter = self.db.iterator_cf(&cf, IteratorMode::From(start, Direction::Forward));
+
+        for item in iter {
+            let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+
+            if !key.starts_with(prefix) || entries.len() >= limit {
+                break;
+            }
+            entries.push((key.to_vec(), value.to_vec()));
+        }
+
+        Ok(entries)
+    }
+
+    // ========== Token Blacklist Operations ==========
+
+    /// Get a revoked token entry by token ID
+    pub fn get_revoked_token(&self, token_id: &[u8]) -> Result<Option<Vec<u8>>> {
+        let cf = self.cf(cf::TOKEN_BLACKLIST)?;
+
+        self.db
+            .get_cf(&cf, token_id)
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
+    /// Atomically write and delete revoked token entries, keyed by token ID
+    pub fn write_revoked_tokens(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let cf = self.cf(cf::TOKEN_BLACKLIST)?;
+
+        let mut batch = WriteBatch::default();
+        for (key, value) in puts {
+            batch.put_cf(&cf, key, value);
+        }
+        for key in deletes {
+            batch.delete_cf(&cf, key);
+        }
+
+        self.write_batch(batch)
+    }
+
+    /// Get all revoked token entries, in token ID order
+    pub fn scan_revoked_tokens(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let cf = self.cf(cf::TOKEN_BLACKLIST)?;
+
+        self.db
+            .iterator_cf(&cf, IteratorMode::Start)
+            .map(|item| {
+                item.map(|(key, value)| (key.to_vec(), value.to_vec()))
+                    .map_err(|e| Error::Storage(e.to_string()))
+            })
+            .collect()
+    }
+
+    // ========== User Account Operations ==========
+
+    /// Atomically write and delete user account entries, keyed by user ID
+    pub fn write_users(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let cf = self.cf(cf::USERS)?;
+
+        let mut batch = WriteBatch::default();
+        for (key, value) in puts {
+            batch.put_cf(&cf, key, value);
+        }
+        for key in deletes {
+            batch.delete_cf(&cf, key);
+        }
+
+        self.write_batch(batch)
+    }
+
+    /// Get all user account entries, in user ID order
+    pub fn scan_users(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let cf = self.cf(cf::USERS)?;
+
+        self.db
+            .iterator_cf(&cf, IteratorMode::Start)
+            .map(|item| {
+                item.map(|(key, value)| (key.to_vec(), value.to_vec()))
+                    .map_err(|e| Error::Storage(e.to_string()))
+            })
+            .collect()
+    }
+
+    // ========== Refresh Token Operations ==========
+
+    /// Atomically write and delete refresh token entries, keyed by token hash
+    pub fn write_refresh_tokens(&self, puts: &[(Vec<u8>, Vec<u8>)], deletes: &[Vec<u8>]) -> Result<()> {
+        let cf = self.cf(cf::REFRESH_TOKENS)?;
+
+        let mut batch = WriteBatch::default();
+        for (key, value) in puts {
+            batch.put_cf(&cf, key, value);
+        }
+        for key in deletes {
+            batch.delete_cf(&cf, key);
+        }
+
+        self.write_batch(batch)
+    }
+
+    /// Get all refresh token entries, in token hash order
+    pub fn scan_refresh_tokens(&self) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+        let cf = self.cf(cf::REFRESH_TOKENS)?;
+
+        self.db
+            .iterator_cf(&cf, IteratorMode::Start)
+            .map(|item| {
+                item.map(|(key, value)| (key.to_vec(), value.to_vec()))
+                    .map_err(|e| Error::Storage(e.to_string()))
+            })
+            .collect()
+    }
+
+    // ========== Schema Versioning ==========
+
+    /// Get the applied schema version (0 if no migration has run)
+    pub fn get_schema_version(&self) -> Result<u64> {
+        let cf = self.cf(cf::SCHEMA)?;
+        let value = self
+            .db
+            .get_cf(&cf, KeyBuilder::schema_version())
+            .map_err(|e| Error::Storage(e.to_string()))?;
+
+        match value {
+            Some(bytes) => {
+                let bytes: [u8; 8] = bytes.as_slice().try_into().map_err(|_| {
+                    Error::DataCorruption("Invalid schema version".to_string())
+                })?;
+                Ok(u64::from_be_bytes(bytes))
+            }
+            None => Ok(0),
+        }
+    }
+
+    /// Record the applied schema version
+    pub fn set_schema_version(&self, version: u64) -> Result<()> {
+        let cf = self.cf(cf::SCHEMA)?;
+        self.db
+            .put_cf_opt(&cf, KeyBuilder::schema_version(), version.to_be_bytes(), &self.write_options())
+            .map_err(|e| Error::Storage(e.to_string()))
+    }
+
     // ========== Transaction Operations ==========
 
     /// Begin a new transaction for a graph
+    ///
+    /// The transaction reads from a snapshot taken now, see [`Self::snapshot`].
     pub fn begin_transaction(&self, graph_id: GraphId) -> Result<crate::transaction::Transaction> {
         Ok(crate::transaction::Transaction::new(self.clone(), graph_id))
     }
 
+    /// Take a consistent, read-only view of the database as it is now
+    ///
+    /// Reads through the snapshot never see writes committed after it was
+    /// taken, so a sequence of reads can't observe half of a concurrent
+    /// transaction or batch.
+    pub fn snapshot(&self) -> StorageSnapshot {
+        StorageSnapshot::new(self.clone())
+    }
+
+    /// Compare a graph at two transaction times
+    ///
+    /// Reports the nodes and relationships created, updated or deleted by
+    /// writes committed after `from` and up to and including `to`. Each entity
+    /// appears once, by its state at `from` and at `to`, however many times it
+    /// changed in between.
+    pub fn diff(&self, graph_id: GraphId, from: TransactionTime, to: TransactionTime) -> Result<GraphDiff> {
+        if from > to {
+            return Err(Error::InvalidTemporalRange(format!(
+                "diff start {} is after its end {}",
+                from.as_millis(),
+                to.as_millis()
+            )));
+        }
+
+        let cf = self.cf(cf::HISTORY)?;
+        let graph_prefix = KeyBuilder::history_from(graph_id, 0)[..9].to_vec();
+        let start = KeyBuilder::history_from(graph_id, from.as_millis().saturating_add(1));
+
+        let mut changes = Vec::new();
+        for item in self.db.iterator_cf(&cf, IteratorMode::From(&start, Direction::Forward)) {
+            let (key, value) = item.map_err(|e| Error::Storage(e.to_string()))?;
+            if !key.starts_with(&graph_prefix) {
+                break;
+            }
+
+            let mut decoder = KeyDecoder::new(&key[graph_prefix.len()..]);
+            let Some(millis) = decoder.read_u64() else {
+                continue;
+            };
+            if millis > to.as_millis().max(0) as u64 {
+                break;
+            }
+
+            let change: Change = bincode::deserialize(&value)
+                .map_err(|e| Error::Deserialization(e.to_string()))?;
+            changes.push(change);
+        }
+
+        Ok(GraphDiff::from_changes(changes))
+    }
+
+    /// Write the final state of every entity a transaction changed in one batch
+    ///
+    /// `None` deletes the entity. Index entries of the stored versions are
+    /// replaced and counts updated in the same batch, so either all of the
+    /// transaction's writes become visible or none do.
+    ///
+    /// `check` runs first, under the lock that orders entity writes, so
+    /// whatever it verifies against the latest state still holds when the
+    /// batch is written.
+    pub(crate) fn apply_changes(
+        &self,
+        graph_id: GraphId,
+        nodes: &HashMap<NodeId, Option<Node>>,
+        relationships: &HashMap<RelationshipId, Option<Relationship>>,
+        check: impl FnOnce() -> Result<()>,
+    ) -> Result<()> {
+        let _guard = self.lock_entity_writes()?;
+        check()?;
+
+        let mut batch = WriteBatch::default();
+        let mut deltas = CountDeltas::default();
+        let mut history = ChangeLog::default();
+
+        for (&node_id, node) in nodes {
+            let previous = self.get_node(graph_id, node_id)?;
+            if let Some(previous) = &previous {
+                self.batch_delete_node_indexes(&mut batch, graph_id, previous)?;
+                deltas.node(graph_id, previous, -1);
+            }
+            // Deleting a node that was never stored changes nothing
+            if previous.is_some() || node.is_some() {
+                history.node(previous, node.clone());
+            }
+            match node {
+                Some(node) => {
+                    self.batch_put_node(&mut batch, graph_id, node)?;
+                    deltas.node(graph_id, node, 1);
+                }
+                None => batch.delete_cf(self.cf(cf::NODES)?, KeyBuilder::node(graph_id, node_id)),
+            }
+        }
+
+        for (&rel_id, rel) in relationships {
+            match rel {
+                Some(rel) => {
+                    self.batch_put_relationship(&mut batch, &mut deltas, &mut history, graph_id, rel)?
+                }
+                None => {
+                    if let Some(existing) = self.get_relationship(graph_id, rel_id)? {
+                        self.batch_delete_relationship(
+                            &mut batch,
+                            &mut deltas,
+                            &mut history,
+                            graph_id,
+                            &existing,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        self.batch_apply_counts(&mut batch, deltas)?;
+        self.batch_record_history(&mut batch, graph_id, history)?;
+        self.write_batch(batch)
+    }
+
     // ========== Utility Operations ==========
 
     /// Flush all in-memory data to disk
+    ///
+    /// Only needed to persist memtables into SST files; writes are readable
+    /// as soon as they return.
     pub fn flush(&self) -> Result<()> {
         self.db
             .flush()
@@ -652,6 +2603,54 @@ impl StorageEngine {
         Ok(())
     }
 
+    /// Create a checkpoint of the whole database in the new directory `path`
+    ///
+    /// The checkpoint is a database that [`Self::open`] and
+    /// [`Self::open_read_only`] accept, holding every write batch committed
+    /// before it was taken and none committed after. Its SST files are hard
+    /// links to the live ones when `path` is on the same filesystem, so
+    /// taking it is cheap whatever the size of the database.
+    pub fn checkpoint<P: AsRef<std::path::Path>>(&self, path: P) -> Result<()> {
+        rocksdb::checkpoint::Checkpoint::new(&self.db)
+            .and_then(|checkpoint| checkpoint.create_checkpoint(path.as_ref()))
+            .map_err(|e| Error::Storage(e.to_string()))?;
+        info!("Created checkpoint at {:?}", path.as_ref());
+        Ok(())
+    }
+
+    /// Gather the key counts, file sizes, compaction backlog and block cache
+    /// effectiveness of the database
+    pub fn metrics(&self) -> Result<StorageMetrics> {
+        let property = |cf: &ColumnFamily, name: &str| {
+            self.db
+                .property_int_value_cf(cf, name)
+                .map(Option::unwrap_or_default)
+                .map_err(|e| Error::Storage(e.to_string()))
+        };
+
+        let mut column_families = Vec::with_capacity(COLUMN_FAMILIES.len());
+        for name in COLUMN_FAMILIES {
+            let cf = self.cf(name)?;
+            column_families.push(ColumnFamilyMetrics {
+                name: name.to_string(),
+                estimated_keys: property(cf, "rocksdb.estimate-num-keys")?,
+                sst_bytes: property(cf, "rocksdb.total-sst-files-size")?,
+                pending_compaction_bytes: property(cf, "rocksdb.estimate-pending-compaction-bytes")?,
+            });
+        }
+
+        let statistics = self
+            .db
+            .property_value("rocksdb.options-statistics")
+            .map_err(|e| Error::Storage(e.to_string()))?
+            .unwrap_or_default();
+        Ok(StorageMetrics {
+            column_families,
+            block_cache_hits: ticker_count(&statistics, BLOCK_CACHE_HIT_TICKER),
+            block_cache_misses: ticker_count(&statistics, BLOCK_CACHE_MISS_TICKER),
+        })
+    }
+
     /// Get database statistics
     pub fn stats(&self) -> String {
         self.db
@@ -660,6 +2659,17 @@ impl StorageEngine {
             .unwrap_or_default()
     }
 
+    /// Compact one column family, such as after deleting much of its data
+    pub fn compact_cf(&self, name: &str) -> Result<()> {
+        if !COLUMN_FAMILIES.contains(&name) {
+            return Err(Error::ValidationError(format!("Unknown column family '{}'", name)));
+        }
+        let cf = self.cf(name)?;
+        self.db.compact_range_cf(&cf, None::<&[u8]>, None::<&[u8]>);
+        info!("Column family {} compacted", name);
+        Ok(())
+    }
+
     /// Compact the database
     pub fn compact(&self) -> Result<()> {
         for cf_name in COLUMN_FAMILIES {
@@ -677,6 +2687,9 @@ impl Clone for StorageEngine {
         Self {
             db: Arc::clone(&self.db),
             options: self.options.clone(),
+            create_lock: Arc::clone(&self.create_lock),
+            history_sequence: Arc::clone(&self.history_sequence),
+            composite_indexes: Arc::clone(&self.composite_indexes),
         }
     }
 }
@@ -684,6 +2697,9 @@ impl Clone for StorageEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::history::EntityUpdate;
+    use crate::options::ColumnFamilyOptions;
+    use qilbee_core::dataset::Dataset;
     use qilbee_core::{EntityId, IdGenerator, Property};
     use tempfile::TempDir;
 
@@ -700,6 +2716,39 @@ mod tests {
         assert!(engine.get_meta("test").unwrap().is_none());
     }
 
+    #[test]
+    fn test_schema_version() {
+        let (engine, _dir) = create_test_engine();
+        assert_eq!(engine.get_schema_version().unwrap(), 0);
+
+        engine.set_schema_version(3).unwrap();
+        assert_eq!(engine.get_schema_version().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_create_node_conflict() {
+        let (engine, _dir) = create_test_engine();
+        let graph_id = GraphId::from_name("test");
+
+        let node = Node::with_labels(NodeId::from_internal(7), ["Person"]);
+        engine.create_node(graph_id, &node).unwrap();
+
+        // A second create with the same id is rejected
+        let mut duplicate = Node::with_labels(NodeId::from_internal(7), ["Company"]);
+        let result = engine.create_node(graph_id, &duplicate);
+        assert!(matches!(result, Err(Error::Conflict(_))));
+        assert!(engine.get_node(graph_id, node.id).unwrap().unwrap().has_label_name("Person"));
+
+        // An explicit upsert still overwrites
+        duplicate.set_property("name", "Acme");
+        engine.put_node(graph_id, &duplicate).unwrap();
+        let stored = engine.get_node(graph_id, node.id).unwrap().unwrap();
+        assert!(stored.has_label_name("Company"));
+
+        // The same id is free in another graph
+        engine.create_node(GraphId::from_name("other"), &node).unwrap();
+    }
+
     #[test]
     fn test_node_crud() {
         let (engine, _dir) = create_test_engine();
@@ -710,44 +2759,294 @@ mod tests {
         let mut node = Node::with_labels(id_gen.next_node_id(), ["Person"]);
         node.set_property("name", "Alice");
 
-        // Store
-        engine.put_node(graph_id, &node).unwrap();
+        // Store
+        engine.put_node(graph_id, &node).unwrap();
+
+        // Read
+        let retrieved = engine.get_node(graph_id, node.id).unwrap().unwrap();
+        assert_eq!(retrieved.id, node.id);
+        assert!(retrieved.has_label_name("Person"));
+        assert_eq!(
+            retrieved.get_property("name").and_then(|v| v.as_str()),
+            Some("Alice")
+        );
+
+        // Delete
+        assert!(engine.delete_node(graph_id, node.id).unwrap());
+        assert!(engine.get_node(graph_id, node.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_your_writes_without_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut options = StorageOptions::for_testing(temp_dir.path());
+        options.enable_wal = false;
+        let engine = StorageEngine::open(options).unwrap();
+        let reader = engine.clone();
+        let graph_id = GraphId::from_name("test");
+
+        let mut node = Node::with_labels(NodeId::from_internal(1), ["User"]);
+        node.set_property("name", "Alice");
+        engine.create_node(graph_id, &node).unwrap();
+
+        // Visible through every handle immediately, including the indexes
+        let read = reader.get_node(graph_id, node.id).unwrap().unwrap();
+        assert_eq!(read.get_property("name"), Some(&"Alice".into()));
+        assert_eq!(reader.get_nodes_by_label(graph_id, "User").unwrap().len(), 1);
+        let by_name = reader
+            .get_nodes_by_property(graph_id, "User", "name", &"Alice".into())
+            .unwrap();
+        assert_eq!(by_name.len(), 1);
+
+        engine.delete_node(graph_id, node.id).unwrap();
+        assert!(reader.get_node(graph_id, node.id).unwrap().is_none());
+        assert!(reader.get_nodes_by_label(graph_id, "User").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_property_limits() {
+        let temp_dir = TempDir::new().unwrap();
+        let options = StorageOptions::for_testing(temp_dir.path())
+            .max_properties_per_entity(2)
+            .max_property_value_bytes(32);
+        let engine = StorageEngine::open(options).unwrap();
+        let graph_id = GraphId::from_name("test");
+
+        let mut node = Node::with_labels(NodeId::from_internal(1), ["User"]);
+        node.set_property("name", "Alice");
+        node.set_property("age", 30i64);
+        engine.create_node(graph_id, &node).unwrap();
+
+        // Too many properties
+        let mut crowded = node.clone();
+        crowded.set_property("city", "Paris");
+        let result = engine.put_node(graph_id, &crowded);
+        assert!(matches!(result, Err(Error::QuotaExceeded(ref msg)) if msg.contains("3 properties")));
+
+        // A value that is too large
+        let mut oversized = Node::with_labels(NodeId::from_internal(2), ["User"]);
+        oversized.set_property("bio", "x".repeat(64));
+        let result = engine.create_node(graph_id, &oversized);
+        assert!(matches!(result, Err(Error::QuotaExceeded(ref msg)) if msg.contains("'bio'")));
+        assert!(engine.get_node(graph_id, oversized.id).unwrap().is_none());
+
+        // The stored node is untouched by the rejected update
+        let stored = engine.get_node(graph_id, node.id).unwrap().unwrap();
+        assert_eq!(stored.properties.len(), 2);
+
+        // Relationships are held to the same limits
+        let mut rel = Relationship::new(RelationshipId::from_internal(1), "KNOWS", node.id, node.id);
+        rel.set_property("note", "y".repeat(64));
+        let result = engine.put_relationship(graph_id, &rel);
+        assert!(matches!(result, Err(Error::QuotaExceeded(_))));
+    }
+
+    #[test]
+    fn test_nodes_by_label() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        // Create nodes with different labels
+        let node1 = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let node2 = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let node3 = Node::with_labels(id_gen.next_node_id(), ["Company"]);
+
+        engine.put_node(graph_id, &node1).unwrap();
+        engine.put_node(graph_id, &node2).unwrap();
+        engine.put_node(graph_id, &node3).unwrap();
+
+        // Query by label
+        let people = engine.get_nodes_by_label(graph_id, "Person").unwrap();
+        assert_eq!(people.len(), 2);
+
+        let companies = engine.get_nodes_by_label(graph_id, "Company").unwrap();
+        assert_eq!(companies.len(), 1);
+    }
+
+    #[test]
+    fn test_names_containing_separator_bytes() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let long = "L".repeat(70_000);
+        let labels = ["Person", "Person\0", "Person\0Company", "Per:son", long.as_str()];
+
+        let mut nodes = Vec::new();
+        for label in labels {
+            let mut node = Node::with_labels(id_gen.next_node_id(), [label]);
+            node.set_property(label, "Alice");
+            engine.put_node(graph_id, &node).unwrap();
+            nodes.push(node);
+        }
+
+        for (label, node) in labels.iter().zip(&nodes) {
+            let found = engine.get_nodes_by_label(graph_id, label).unwrap();
+            assert_eq!(found.len(), 1, "label {:?}", &label[..label.len().min(16)]);
+            assert_eq!(found[0].id, node.id);
+
+            let alices = engine
+                .get_nodes_by_property(graph_id, label, label, &PropertyValue::String("Alice".to_string()))
+                .unwrap();
+            assert_eq!(alices.len(), 1);
+            assert_eq!(alices[0].id, node.id);
+        }
+    }
+
+    #[test]
+    fn test_all_node_ids() {
+        let (engine, _dir) = create_test_engine();
+        let graph_id = GraphId::from_name("test");
+
+        for id in [3, 1, 300] {
+            engine.put_node(graph_id, &Node::with_labels(NodeId::from_internal(id), ["Person"])).unwrap();
+        }
+        engine
+            .put_node(GraphId::from_name("other"), &Node::with_labels(NodeId::from_internal(2), ["Person"]))
+            .unwrap();
+
+        let ids: Vec<u64> = engine.get_all_node_ids(graph_id).unwrap().iter().map(|id| id.as_internal()).collect();
+        assert_eq!(ids, vec![1, 3, 300]);
+    }
+
+    #[test]
+    fn test_label_count() {
+        let (engine, _dir) = create_test_engine();
+        let graph_id = GraphId::from_name("test");
+
+        engine.put_node(graph_id, &Node::with_labels(NodeId::from_internal(1), ["Person"])).unwrap();
+        engine.put_node(graph_id, &Node::with_labels(NodeId::from_internal(2), ["Person", "Admin"])).unwrap();
+        engine.put_node(graph_id, &Node::with_labels(NodeId::from_internal(3), ["Company"])).unwrap();
 
-        // Read
-        let retrieved = engine.get_node(graph_id, node.id).unwrap().unwrap();
-        assert_eq!(retrieved.id, node.id);
-        assert!(retrieved.has_label_name("Person"));
-        assert_eq!(
-            retrieved.get_property("name").and_then(|v| v.as_str()),
-            Some("Alice")
-        );
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 2);
+        assert_eq!(engine.label_count(graph_id, "Admin").unwrap(), 1);
+        assert_eq!(engine.label_count(graph_id, "Missing").unwrap(), 0);
 
-        // Delete
-        assert!(engine.delete_node(graph_id, node.id).unwrap());
-        assert!(engine.get_node(graph_id, node.id).unwrap().is_none());
+        // Relabeling moves the node between counts, overwriting keeps them
+        engine.put_node(graph_id, &Node::with_labels(NodeId::from_internal(2), ["Person"])).unwrap();
+        engine.put_node(graph_id, &Node::with_labels(NodeId::from_internal(1), ["Person"])).unwrap();
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 2);
+        assert_eq!(engine.label_count(graph_id, "Admin").unwrap(), 0);
+
+        assert!(engine.delete_node(graph_id, NodeId::from_internal(1)).unwrap());
+        assert!(!engine.delete_node(graph_id, NodeId::from_internal(1)).unwrap());
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 1);
+
+        // Counts are kept per graph
+        assert_eq!(engine.label_count(GraphId::from_name("other"), "Company").unwrap(), 0);
     }
 
     #[test]
-    fn test_nodes_by_label() {
+    fn test_relationship_type_count() {
         let (engine, _dir) = create_test_engine();
-        let id_gen = IdGenerator::new();
         let graph_id = GraphId::from_name("test");
+        let (a, b) = (NodeId::from_internal(1), NodeId::from_internal(2));
+
+        let knows = Relationship::new(RelationshipId::from_internal(1), "KNOWS", a, b);
+        engine.put_relationships(graph_id, &[
+            knows.clone(),
+            Relationship::new(RelationshipId::from_internal(2), "KNOWS", b, a),
+            Relationship::new(RelationshipId::from_internal(3), "WORKS_AT", a, b),
+        ]).unwrap();
+        engine.put_relationship(graph_id, &knows).unwrap();
+        assert_eq!(engine.relationship_type_count(graph_id, "KNOWS").unwrap(), 2);
+        assert_eq!(engine.relationship_type_count(graph_id, "WORKS_AT").unwrap(), 1);
+
+        assert!(engine.delete_relationship(graph_id, knows.id).unwrap());
+        assert_eq!(engine.relationship_type_count(graph_id, "KNOWS").unwrap(), 1);
+    }
 
-        // Create nodes with different labels
-        let node1 = Node::with_labels(id_gen.next_node_id(), ["Person"]);
-        let node2 = Node::with_labels(id_gen.next_node_id(), ["Person"]);
-        let node3 = Node::with_labels(id_gen.next_node_id(), ["Company"]);
+    #[test]
+    fn test_counts_built_on_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let graph_id = GraphId::from_name("test");
 
-        engine.put_node(graph_id, &node1).unwrap();
-        engine.put_node(graph_id, &node2).unwrap();
-        engine.put_node(graph_id, &node3).unwrap();
+        {
+            let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+            let (a, b) = (NodeId::from_internal(1), NodeId::from_internal(2));
+            engine.put_node(graph_id, &Node::with_labels(a, ["Person"])).unwrap();
+            engine.put_node(graph_id, &Node::with_labels(b, ["Person", "Admin"])).unwrap();
+            engine.put_relationship(graph_id, &Relationship::new(RelationshipId::from_internal(1), "KNOWS", a, b)).unwrap();
+
+            // Simulate a database written before counts were maintained
+            let meta_cf = engine.cf(cf::META).unwrap();
+            for key in [
+                KeyBuilder::label_count(graph_id, "Person"),
+                KeyBuilder::label_count(graph_id, "Admin"),
+                KeyBuilder::relationship_type_count(graph_id, "KNOWS"),
+                KeyBuilder::meta(ENTITY_COUNTS_META),
+            ] {
+                engine.db.delete_cf(&meta_cf, key).unwrap();
+            }
+            assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 0);
+        }
 
-        // Query by label
-        let people = engine.get_nodes_by_label(graph_id, "Person").unwrap();
-        assert_eq!(people.len(), 2);
+        let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 2);
+        assert_eq!(engine.label_count(graph_id, "Admin").unwrap(), 1);
+        assert_eq!(engine.relationship_type_count(graph_id, "KNOWS").unwrap(), 1);
+    }
 
-        let companies = engine.get_nodes_by_label(graph_id, "Company").unwrap();
-        assert_eq!(companies.len(), 1);
+    #[test]
+    fn test_detach_delete_nodes_with_label() {
+        let (engine, _dir) = create_test_engine();
+        let graph_id = GraphId::from_name("test");
+        let node = |id: u64, label: &str| Node::with_labels(NodeId::from_internal(id), [label]);
+        for id in 1..=3 {
+            engine.put_node(graph_id, &node(id, "Temp")).unwrap();
+        }
+        engine.put_node(graph_id, &node(4, "Person")).unwrap();
+        let rel = |id: u64, source: u64, target: u64| {
+            Relationship::new(
+                RelationshipId::from_internal(id),
+                "LINKS",
+                NodeId::from_internal(source),
+                NodeId::from_internal(target),
+            )
+        };
+        engine.put_relationships(graph_id, &[rel(1, 1, 2), rel(2, 2, 4), rel(3, 3, 3), rel(4, 4, 4)]).unwrap();
+
+        let (nodes, _) = engine.detach_delete_nodes_with_label(graph_id, "Temp", 2).unwrap();
+        assert_eq!(nodes, 2);
+        let (nodes, _) = engine.detach_delete_nodes_with_label(graph_id, "Temp", 2).unwrap();
+        assert_eq!(nodes, 1);
+        assert_eq!(engine.detach_delete_nodes_with_label(graph_id, "Temp", 2).unwrap(), (0, 0));
+
+        assert!(engine.get_nodes_by_label(graph_id, "Temp").unwrap().is_empty());
+        assert!(engine.get_node(graph_id, NodeId::from_internal(4)).unwrap().is_some());
+        let remaining = engine.get_outgoing_relationships(graph_id, NodeId::from_internal(4)).unwrap();
+        assert_eq!(remaining.iter().map(|rel| rel.id.as_internal()).collect::<Vec<_>>(), vec![4]);
+        assert_eq!(engine.label_count(graph_id, "Temp").unwrap(), 0);
+        assert_eq!(engine.relationship_type_count(graph_id, "LINKS").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_type_counts() {
+        let (engine, _dir) = create_test_engine();
+        let graph_id = GraphId::from_name("test");
+        let (a, b) = (NodeId::from_internal(1), NodeId::from_internal(2));
+        engine.put_node(graph_id, &Node::with_labels(a, ["Person"])).unwrap();
+        engine.put_node(graph_id, &Node::with_labels(b, ["Person", "Admin"])).unwrap();
+        engine.put_relationship(graph_id, &Relationship::new(RelationshipId::from_internal(1), "KNOWS", a, b)).unwrap();
+        engine.put_relationship(graph_id, &Relationship::new(RelationshipId::from_internal(2), "KNOWS", b, a)).unwrap();
+        engine.put_relationship(graph_id, &Relationship::new(RelationshipId::from_internal(3), "MANAGES", b, a)).unwrap();
+        let other = GraphId::from_name("other");
+        engine.put_node(other, &Node::with_labels(a, ["Company"])).unwrap();
+
+        let counts = engine.type_counts(graph_id).unwrap();
+        assert_eq!(counts.labels, BTreeMap::from([("Admin".to_string(), 1), ("Person".to_string(), 2)]));
+        assert_eq!(
+            counts.relationship_types,
+            BTreeMap::from([("KNOWS".to_string(), 2), ("MANAGES".to_string(), 1)])
+        );
+        assert!(!counts.scanned);
+
+        // Without maintained counts the same numbers come from a scan
+        let meta_cf = engine.cf(cf::META).unwrap();
+        engine.db.delete_cf(&meta_cf, KeyBuilder::meta(ENTITY_COUNTS_META)).unwrap();
+        let scanned = engine.type_counts(graph_id).unwrap();
+        assert!(scanned.scanned);
+        assert_eq!(TypeCounts { scanned: false, ..scanned }, counts);
     }
 
     #[test]
@@ -778,6 +3077,37 @@ mod tests {
         assert!(engine.get_relationship(graph_id, rel.id).unwrap().is_none());
     }
 
+    #[test]
+    fn test_scan_relationships() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let other_graph = GraphId::from_name("other");
+
+        let node1 = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let node2 = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &node1).unwrap();
+        engine.put_node(graph_id, &node2).unwrap();
+
+        let knows = Relationship::new(id_gen.next_relationship_id(), "KNOWS", node1.id, node2.id);
+        let likes = Relationship::new(id_gen.next_relationship_id(), "LIKES", node2.id, node1.id);
+        engine.put_relationship(graph_id, &knows).unwrap();
+        engine.put_relationship(graph_id, &likes).unwrap();
+        let elsewhere = Relationship::new(id_gen.next_relationship_id(), "KNOWS", node1.id, node2.id);
+        engine.put_relationship(other_graph, &elsewhere).unwrap();
+
+        let all = engine.get_all_relationships(graph_id).unwrap();
+        assert_eq!(all, vec![knows.clone(), likes.clone()]);
+
+        assert_eq!(engine.get_relationships_by_type(graph_id, "KNOWS").unwrap(), vec![knows]);
+        assert_eq!(engine.get_relationships_by_type(graph_id, "LIKES").unwrap(), vec![likes.clone()]);
+        assert!(engine.get_relationships_by_type(graph_id, "FOLLOWS").unwrap().is_empty());
+
+        engine.delete_relationship(graph_id, likes.id).unwrap();
+        assert!(engine.get_relationships_by_type(graph_id, "LIKES").unwrap().is_empty());
+        assert_eq!(engine.get_all_relationships(other_graph).unwrap(), vec![elsewhere]);
+    }
+
     #[test]
     fn test_adjacency_queries() {
         let (engine, _dir) = create_test_engine();
@@ -808,6 +3138,138 @@ mod tests {
         assert_eq!(incoming[0].source, node1.id);
     }
 
+    #[test]
+    fn test_put_relationships_batch() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let hub = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &hub).unwrap();
+        let mut rels = Vec::new();
+        for _ in 0..50 {
+            let node = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+            engine.put_node(graph_id, &node).unwrap();
+            rels.push(Relationship::new(id_gen.next_relationship_id(), "KNOWS", hub.id, node.id));
+        }
+
+        engine.put_relationships(graph_id, &rels).unwrap();
+
+        assert_eq!(engine.get_outgoing_relationships(graph_id, hub.id).unwrap().len(), 50);
+        for rel in &rels {
+            let incoming = engine.get_incoming_relationships(graph_id, rel.target).unwrap();
+            assert_eq!(incoming.len(), 1);
+            assert_eq!(incoming[0].id, rel.id);
+        }
+    }
+
+    #[test]
+    fn test_adjacency_sort_property() {
+        let temp_dir = TempDir::new().unwrap();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let hub = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let mut rels = Vec::new();
+        {
+            let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+            engine.put_node(graph_id, &hub).unwrap();
+            for timestamp in [30, 10, 50, 20, 40] {
+                let node = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+                engine.put_node(graph_id, &node).unwrap();
+                let mut props = Property::new();
+                props.set("timestamp", timestamp);
+                let rel = Relationship::with_properties(
+                    id_gen.next_relationship_id(),
+                    "MESSAGED",
+                    hub.id,
+                    node.id,
+                    props,
+                );
+                engine.put_relationship(graph_id, &rel).unwrap();
+                rels.push(rel);
+            }
+        }
+
+        let timestamps = |rels: Vec<Relationship>| -> Vec<Option<i64>> {
+            rels.iter()
+                .map(|r| r.get_property("timestamp").and_then(|v| v.as_integer()))
+                .collect()
+        };
+
+        // Enabling the option indexes relationships written before it was set
+        let options = StorageOptions::for_testing(temp_dir.path()).adjacency_sort_property("timestamp");
+        let engine = StorageEngine::open(options).unwrap();
+        assert_eq!(
+            timestamps(engine.get_outgoing_relationships(graph_id, hub.id).unwrap()),
+            vec![Some(10), Some(20), Some(30), Some(40), Some(50)]
+        );
+
+        // Updates move the edge and edges without the property sort last
+        let mut moved = rels[2].clone();
+        moved.set_property("timestamp", 5);
+        engine.put_relationship(graph_id, &moved).unwrap();
+        let node = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &node).unwrap();
+        let untimed = Relationship::new(id_gen.next_relationship_id(), "MESSAGED", hub.id, node.id);
+        engine.put_relationship(graph_id, &untimed).unwrap();
+        let mut early = Property::new();
+        early.set("timestamp", -7);
+        let first = Relationship::with_properties(
+            id_gen.next_relationship_id(),
+            "MESSAGED",
+            node.id,
+            hub.id,
+            early,
+        );
+        engine.put_relationship(graph_id, &first).unwrap();
+        assert!(engine.delete_relationship(graph_id, rels[4].id).unwrap());
+
+        assert_eq!(
+            timestamps(engine.get_outgoing_relationships(graph_id, hub.id).unwrap()),
+            vec![Some(5), Some(10), Some(20), Some(30), None]
+        );
+
+        // Incoming scans read the ordered index as well
+        let incoming = engine.get_incoming_relationships(graph_id, hub.id).unwrap();
+        assert_eq!(timestamps(incoming), vec![Some(-7)]);
+        drop(engine);
+
+        // Switching the option back returns edges in ID order
+        let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+        let ids: Vec<_> = engine
+            .get_outgoing_relationships(graph_id, hub.id)
+            .unwrap()
+            .iter()
+            .map(|r| r.id)
+            .collect();
+        assert_eq!(ids, vec![rels[0].id, rels[1].id, rels[2].id, rels[3].id, untimed.id]);
+    }
+
+    #[test]
+    fn test_bulk_seed() {
+        let (engine, _dir) = create_test_engine();
+        let graph_id = GraphId::from_name("test");
+        let dataset = Dataset::social(7, 500, 4);
+
+        engine
+            .bulk_seed(graph_id, &dataset.nodes, &dataset.relationships)
+            .unwrap();
+
+        assert_eq!(engine.get_all_node_ids(graph_id).unwrap().len(), 500);
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 500);
+        let first = &dataset.nodes[0];
+        assert_eq!(
+            engine.get_outgoing_relationships(graph_id, first.id).unwrap().len(),
+            4
+        );
+
+        let name = first.get_property("name").unwrap();
+        let found = engine.get_nodes_by_property(graph_id, "Person", "name", name).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, first.id);
+    }
+
     #[test]
     fn test_metadata() {
         let (engine, _dir) = create_test_engine();
@@ -817,6 +3279,172 @@ mod tests {
         assert_eq!(&value, b"1.0.0");
     }
 
+    #[test]
+    fn test_checkpoint() {
+        let (engine, dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let before = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &before).unwrap();
+
+        let path = dir.path().join("checkpoint");
+        engine.checkpoint(&path).unwrap();
+        let after = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &after).unwrap();
+
+        let checkpoint = StorageEngine::open_read_only(StorageOptions::for_testing(&path)).unwrap();
+        assert!(checkpoint.get_node(graph_id, before.id).unwrap().is_some());
+        assert!(checkpoint.get_node(graph_id, after.id).unwrap().is_none());
+        assert_eq!(checkpoint.label_count(graph_id, "Person").unwrap(), 1);
+
+        // A checkpoint is never written over
+        assert!(engine.checkpoint(&path).is_err());
+    }
+
+    #[test]
+    fn test_graph_version() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let other = GraphId::from_name("other");
+        assert_eq!(engine.graph_version(graph_id).unwrap(), 0);
+
+        let mut node = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.create_node(graph_id, &node).unwrap();
+        assert_eq!(engine.graph_version(graph_id).unwrap(), 1);
+
+        node.set_property("name", "Alice");
+        engine.put_node(graph_id, &node).unwrap();
+        assert_eq!(engine.graph_version(graph_id).unwrap(), 2);
+
+        // Deleting nothing changes nothing
+        assert!(!engine.delete_node(graph_id, id_gen.next_node_id()).unwrap());
+        assert_eq!(engine.graph_version(graph_id).unwrap(), 2);
+
+        let mut tx = engine.begin_transaction(graph_id).unwrap();
+        tx.delete_node(node.id).unwrap();
+        tx.commit().unwrap();
+        assert_eq!(engine.graph_version(graph_id).unwrap(), 3);
+        assert_eq!(engine.graph_version(other).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_column_family_options() {
+        let temp_dir = TempDir::new().unwrap();
+        let options = StorageOptions::for_testing(temp_dir.path())
+            .column_family(cf::MEMORY, ColumnFamilyOptions::default().compression(Compression::Zstd))
+            .column_family(cf::NODES, ColumnFamilyOptions::default().block_cache_size(1024 * 1024));
+        let engine = StorageEngine::open(options).unwrap();
+        engine.compact_cf(cf::NODES).unwrap();
+        assert!(matches!(engine.compact_cf("unknown"), Err(Error::ValidationError(_))));
+        drop(engine);
+
+        let options = StorageOptions::for_testing(temp_dir.path().join("other"))
+            .column_family("unknown", ColumnFamilyOptions::default());
+        assert!(matches!(StorageEngine::open(options), Err(Error::Configuration(_))));
+    }
+
+    #[test]
+    fn test_metrics() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        for _ in 0..3 {
+            engine.put_node(graph_id, &Node::with_labels(id_gen.next_node_id(), ["Person"])).unwrap();
+        }
+        engine.flush().unwrap();
+
+        let metrics = engine.metrics().unwrap();
+        let names: Vec<_> = metrics.column_families.iter().map(|cf| cf.name.as_str()).collect();
+        assert_eq!(names, COLUMN_FAMILIES);
+        let nodes = &metrics.column_families[0];
+        assert_eq!(nodes.estimated_keys, 3);
+        assert!(nodes.sst_bytes > 0);
+        assert!(metrics.total_sst_bytes() >= nodes.sst_bytes);
+    }
+
+    #[test]
+    fn test_memory_entries() {
+        let (engine, _dir) = create_test_engine();
+
+        engine
+            .write_memory(
+                &[
+                    (b"agent-a/2".to_vec(), b"second".to_vec()),
+                    (b"agent-a/1".to_vec(), b"first".to_vec()),
+                    (b"agent-b/1".to_vec(), b"other".to_vec()),
+                ],
+                &[],
+            )
+            .unwrap();
+
+        let entries = engine.scan_memory(b"agent-a/").unwrap();
+        assert_eq!(
+            entries,
+            vec![
+                (b"agent-a/1".to_vec(), b"first".to_vec()),
+                (b"agent-a/2".to_vec(), b"second".to_vec()),
+            ]
+        );
+
+        let page = engine.scan_memory_from(b"agent-a/", b"agent-a/10", 5).unwrap();
+        assert_eq!(page, vec![(b"agent-a/2".to_vec(), b"second".to_vec())]);
+        assert_eq!(engine.scan_memory_from(b"agent-a/", b"agent-a/", 1).unwrap().len(), 1);
+
+        engine.write_memory(&[], &[b"agent-a/1".to_vec()]).unwrap();
+        assert!(engine.get_memory(b"agent-a/1").unwrap().is_none());
+        assert_eq!(engine.get_memory(b"agent-b/1").unwrap(), Some(b"other".to_vec()));
+
+        // Memory entries live apart from graph metadata
+        assert!(engine.get_meta("agent-b/1").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_revoked_tokens() {
+        let (engine, _dir) = create_test_engine();
+
+        engine
+            .write_revoked_tokens(
+                &[(b"jti-2".to_vec(), b"second".to_vec()), (b"jti-1".to_vec(), b"first".to_vec())],
+                &[],
+            )
+            .unwrap();
+        assert_eq!(engine.get_revoked_token(b"jti-1").unwrap(), Some(b"first".to_vec()));
+        assert!(engine.get_memory(b"jti-1").unwrap().is_none());
+
+        engine.write_revoked_tokens(&[], &[b"jti-1".to_vec()]).unwrap();
+        assert!(engine.get_revoked_token(b"jti-1").unwrap().is_none());
+        assert_eq!(engine.scan_revoked_tokens().unwrap(), vec![(b"jti-2".to_vec(), b"second".to_vec())]);
+    }
+
+    #[test]
+    fn test_users() {
+        let (engine, _dir) = create_test_engine();
+
+        engine
+            .write_users(&[(b"user-2".to_vec(), b"bob".to_vec()), (b"user-1".to_vec(), b"alice".to_vec())], &[])
+            .unwrap();
+        assert_eq!(engine.scan_users().unwrap()[0], (b"user-1".to_vec(), b"alice".to_vec()));
+        assert!(engine.get_revoked_token(b"user-1").unwrap().is_none());
+
+        engine.write_users(&[], &[b"user-1".to_vec()]).unwrap();
+        assert_eq!(engine.scan_users().unwrap(), vec![(b"user-2".to_vec(), b"bob".to_vec())]);
+    }
+
+    #[test]
+    fn test_refresh_tokens() {
+        let (engine, _dir) = create_test_engine();
+
+        engine
+            .write_refresh_tokens(&[(b"hash-2".to_vec(), b"second".to_vec()), (b"hash-1".to_vec(), b"first".to_vec())], &[])
+            .unwrap();
+        assert_eq!(engine.scan_refresh_tokens().unwrap()[0], (b"hash-1".to_vec(), b"first".to_vec()));
+        assert!(engine.scan_users().unwrap().is_empty());
+
+        engine.write_refresh_tokens(&[], &[b"hash-1".to_vec()]).unwrap();
+        assert_eq!(engine.scan_refresh_tokens().unwrap(), vec![(b"hash-2".to_vec(), b"second".to_vec())]);
+    }
+
     #[test]
     fn test_property_index_basic() {
         let (engine, _dir) = create_test_engine();
@@ -887,6 +3515,28 @@ mod tests {
         assert_eq!(alicias.len(), 1);
     }
 
+    #[test]
+    fn test_put_node_replaces_index_entries() {
+        let (engine, _dir) = create_test_engine();
+        let graph_id = GraphId::from_name("test");
+
+        let mut node = Node::with_labels(NodeId::from_internal(1), ["Person", "Guest"]);
+        node.set_property("name", "Alice");
+        node.set_property("nickname", "Al");
+        engine.put_node(graph_id, &node).unwrap();
+
+        // Drop a label and a property
+        node.remove_label(&"Guest".into());
+        node.remove_property("nickname");
+        engine.put_node(graph_id, &node).unwrap();
+
+        assert!(engine.get_nodes_by_label(graph_id, "Guest").unwrap().is_empty());
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 1);
+        assert!(engine.get_nodes_with_property(graph_id, "Person", "nickname").unwrap().is_empty());
+        assert!(engine.get_nodes_with_property(graph_id, "Guest", "name").unwrap().is_empty());
+        assert_eq!(engine.get_nodes_with_property(graph_id, "Person", "name").unwrap().len(), 1);
+    }
+
     #[test]
     fn test_property_index_delete() {
         let (engine, _dir) = create_test_engine();
@@ -982,6 +3632,114 @@ mod tests {
         assert_eq!(age_25.len(), 1);
     }
 
+    #[test]
+    fn test_build_property_index_backfills_missing_entries() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let person = |city: &str| {
+            let mut node = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+            node.set_property("city", city);
+            node
+        };
+
+        let nodes: Vec<Node> = (0..2_500).map(|_| person("Paris")).collect();
+        for node in &nodes {
+            engine.put_node(graph_id, node).unwrap();
+        }
+        engine.put_node(graph_id, &Node::with_labels(id_gen.next_node_id(), ["Person"])).unwrap();
+
+        // Drop the entries, as for data stored before they were written
+        let prop_cf = engine.cf(cf::PROPERTY_INDEX).unwrap();
+        let prefix = KeyBuilder::property_index_prefix(graph_id, "Person", "city");
+        let mut batch = WriteBatch::default();
+        for item in engine.db.prefix_iterator_cf(prop_cf, &prefix) {
+            let (key, _) = item.unwrap();
+            if key.starts_with(&prefix) {
+                batch.delete_cf(prop_cf, &key);
+            }
+        }
+        engine.write_batch(batch).unwrap();
+        let paris = PropertyValue::String("Paris".to_string());
+        assert!(engine.get_nodes_by_property(graph_id, "Person", "city", &paris).unwrap().is_empty());
+
+        // Writes during the build are indexed whichever batch they land near
+        let mut reports = Vec::new();
+        let (indexed, created) = std::thread::scope(|scope| {
+            let writer = scope.spawn(|| {
+                let created: Vec<Node> = (0..200).map(|_| person("Paris")).collect();
+                for (i, node) in created.iter().enumerate() {
+                    engine.put_node(graph_id, node).unwrap();
+                    let mut moved = nodes[i].clone();
+                    moved.set_property("city", "Rome");
+                    engine.put_node(graph_id, &moved).unwrap();
+                    engine.delete_node(graph_id, nodes[i + 200].id).unwrap();
+                }
+                created
+            });
+            let indexed = engine
+                .build_property_index(graph_id, "Person", "city", |progress| reports.push(progress))
+                .unwrap();
+            (indexed, writer.join().unwrap())
+        });
+
+        assert!(indexed <= 2_500);
+        assert_eq!(reports.len(), 3);
+        assert_eq!(reports.last().unwrap().scanned, reports[0].total);
+        assert_eq!(reports.last().unwrap().indexed, indexed);
+
+        let mut expected: Vec<u64> = nodes[400..].iter().chain(&created).map(|node| node.id.as_internal()).collect();
+        expected.sort();
+        let mut found: Vec<u64> = engine
+            .get_nodes_by_property(graph_id, "Person", "city", &paris)
+            .unwrap()
+            .into_iter()
+            .map(|node| node.id.as_internal())
+            .collect();
+        found.sort();
+        assert_eq!(found, expected);
+        let rome = PropertyValue::String("Rome".to_string());
+        assert_eq!(engine.get_nodes_by_property(graph_id, "Person", "city", &rome).unwrap().len(), 200);
+    }
+
+    #[test]
+    fn test_property_index_float_epsilon() {
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let mut near = Node::with_labels(id_gen.next_node_id(), ["Reading"]);
+        near.set_property("value", 0.1 + 0.2);
+        let mut far = Node::with_labels(id_gen.next_node_id(), ["Reading"]);
+        far.set_property("value", 0.31);
+        let target = PropertyValue::Float(0.3);
+
+        for (epsilon, expected) in [(None, 0), (Some(1e-9), 1)] {
+            let temp_dir = TempDir::new().unwrap();
+            let mut options = StorageOptions::for_testing(temp_dir.path());
+            options.float_epsilon = epsilon;
+            let engine = StorageEngine::open(options).unwrap();
+            engine.put_node(graph_id, &near).unwrap();
+            engine.put_node(graph_id, &far).unwrap();
+            let index = CompositeIndex::new("reading_value", "Reading", ["value", "unit"]);
+            engine.create_composite_index(graph_id, index).unwrap();
+
+            let equal = engine
+                .get_nodes_by_property(graph_id, "Reading", "value", &target)
+                .unwrap();
+            assert_eq!(equal.len(), expected);
+            assert!(equal.iter().all(|node| node.id == near.id));
+
+            // Range bounds and composite lookups use the same tolerance
+            let at_most = engine
+                .get_nodes_by_property_range(graph_id, "Reading", "value", None, Some(&target))
+                .unwrap();
+            assert_eq!(at_most.len(), expected);
+            let composite = engine
+                .get_nodes_by_composite_property(graph_id, "reading_value", std::slice::from_ref(&target))
+                .unwrap();
+            assert_eq!(composite.len(), expected);
+        }
+    }
+
     #[test]
     fn test_property_index_boolean_values() {
         let (engine, _dir) = create_test_engine();
@@ -1089,4 +3847,235 @@ mod tests {
             .unwrap();
         assert_eq!(at_most_35.len(), 2); // ages 20 and 30
     }
+
+    #[test]
+    fn test_rel_property_index() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let user = Node::with_labels(id_gen.next_node_id(), ["User"]);
+        let movie = Node::with_labels(id_gen.next_node_id(), ["Movie"]);
+        engine.put_node(graph_id, &user).unwrap();
+        engine.put_node(graph_id, &movie).unwrap();
+
+        let mut loved = Relationship::new(id_gen.next_relationship_id(), "RATED", user.id, movie.id);
+        loved.set_property("score", 5i64);
+        let mut disliked = Relationship::new(id_gen.next_relationship_id(), "RATED", user.id, movie.id);
+        disliked.set_property("score", 1i64);
+        let mut reviewed = Relationship::new(id_gen.next_relationship_id(), "REVIEWED", user.id, movie.id);
+        reviewed.set_property("score", 5i64);
+        engine.put_relationships(graph_id, &[loved.clone(), disliked.clone(), reviewed.clone()]).unwrap();
+
+        let five = PropertyValue::Integer(5);
+        let one = PropertyValue::Integer(1);
+        let found = engine.get_relationships_by_property(graph_id, "RATED", "score", &five).unwrap();
+        assert_eq!(found, vec![loved.clone()]);
+        assert!(engine.get_relationships_by_property(graph_id, "RATED", "stars", &five).unwrap().is_empty());
+
+        // Updating a relationship moves it to its new value
+        disliked.set_property("score", 5i64);
+        engine.put_relationship(graph_id, &disliked).unwrap();
+        assert!(engine.get_relationships_by_property(graph_id, "RATED", "score", &one).unwrap().is_empty());
+        assert_eq!(engine.get_relationships_by_property(graph_id, "RATED", "score", &five).unwrap().len(), 2);
+
+        // Deleting a relationship removes its entries
+        engine.delete_relationship(graph_id, loved.id).unwrap();
+        let found = engine.get_relationships_by_property(graph_id, "RATED", "score", &five).unwrap();
+        assert_eq!(found, vec![disliked]);
+        let prop_cf = engine.cf(cf::REL_PROPERTY_INDEX).unwrap();
+        let loved_key = KeyBuilder::rel_property_index(graph_id, "RATED", "score", hash_property_value(&five), loved.id);
+        assert!(engine.db.get_cf(&prop_cf, loved_key).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_rel_property_index_built_on_open() {
+        let temp_dir = TempDir::new().unwrap();
+        let graph_id = GraphId::from_name("test");
+        let (a, b) = (NodeId::from_internal(1), NodeId::from_internal(2));
+        let mut rel = Relationship::new(RelationshipId::from_internal(1), "RATED", a, b);
+        rel.set_property("score", 4i64);
+
+        {
+            let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+            engine.put_relationship(graph_id, &rel).unwrap();
+
+            // Simulate a database written before relationship properties were indexed
+            let prop_cf = engine.cf(cf::REL_PROPERTY_INDEX).unwrap();
+            let key = KeyBuilder::rel_property_index(
+                graph_id,
+                "RATED",
+                "score",
+                hash_property_value(&PropertyValue::Integer(4)),
+                rel.id,
+            );
+            engine.db.delete_cf(&prop_cf, key).unwrap();
+            let meta_cf = engine.cf(cf::META).unwrap();
+            engine.db.delete_cf(&meta_cf, KeyBuilder::meta(REL_PROPERTY_INDEX_META)).unwrap();
+            assert!(engine
+                .get_relationships_by_property(graph_id, "RATED", "score", &PropertyValue::Integer(4))
+                .unwrap()
+                .is_empty());
+        }
+
+        let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+        let found = engine
+            .get_relationships_by_property(graph_id, "RATED", "score", &PropertyValue::Integer(4))
+            .unwrap();
+        assert_eq!(found, vec![rel]);
+    }
+
+    #[test]
+    fn test_composite_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let graph_id = GraphId::from_name("test");
+        let person = |id: u64, last: &str, first: Option<&str>| {
+            let mut node = Node::with_labels(NodeId::from_internal(id), ["Person"]);
+            node.set_property("lastName", last);
+            if let Some(first) = first {
+                node.set_property("firstName", first);
+            }
+            node
+        };
+        let string = |s: &str| PropertyValue::String(s.to_string());
+        let ada = person(1, "Lovelace", Some("Ada"));
+        let byron = person(2, "Lovelace", Some("Byron"));
+        let grace = person(3, "Hopper", Some("Grace"));
+        let anonymous = person(4, "Lovelace", None);
+
+        {
+            let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+            engine.put_node(graph_id, &ada).unwrap();
+            engine.put_node(graph_id, &byron).unwrap();
+
+            // Nodes stored before the index was created are indexed too
+            let index = CompositeIndex::new("person_name", "Person", ["lastName", "firstName"]);
+            assert!(engine.create_composite_index(graph_id, index.clone()).unwrap());
+            assert!(!engine.create_composite_index(graph_id, index).unwrap());
+            engine.put_node(graph_id, &grace).unwrap();
+            engine.put_node(graph_id, &anonymous).unwrap();
+        }
+
+        let engine = StorageEngine::open(StorageOptions::for_testing(temp_dir.path())).unwrap();
+        assert_eq!(engine.composite_indexes(graph_id).unwrap().len(), 1);
+        let find = |values: &[PropertyValue]| {
+            let mut nodes = engine
+                .get_nodes_by_composite_property(graph_id, "person_name", values)
+                .unwrap();
+            nodes.sort_by_key(|n| n.id.as_internal());
+            nodes
+        };
+
+        assert_eq!(find(&[string("Lovelace"), string("Ada")]), vec![ada.clone()]);
+        assert_eq!(find(&[string("Lovelace")]), vec![ada.clone(), byron.clone(), anonymous]);
+        assert!(find(&[string("Hopper"), string("Ada")]).is_empty());
+
+        // Updates and deletes move and remove entries
+        let renamed = person(1, "King", Some("Ada"));
+        engine.put_node(graph_id, &renamed).unwrap();
+        engine.delete_node(graph_id, byron.id).unwrap();
+        assert!(find(&[string("Lovelace"), string("Ada")]).is_empty());
+        assert!(find(&[string("Lovelace"), string("Byron")]).is_empty());
+        assert_eq!(find(&[string("King"), string("Ada")]), vec![renamed]);
+
+        assert!(matches!(
+            engine.get_nodes_by_composite_property(graph_id, "missing", &[string("King")]),
+            Err(Error::IndexNotFound(_))
+        ));
+        assert!(engine.get_nodes_by_composite_property(graph_id, "person_name", &[]).is_err());
+
+        assert!(engine.drop_composite_index(graph_id, "person_name").unwrap());
+        assert!(!engine.drop_composite_index(graph_id, "person_name").unwrap());
+        let prop_cf = engine.cf(cf::PROPERTY_INDEX).unwrap();
+        let prefix = KeyBuilder::composite_index_prefix(graph_id, "person_name", &[]);
+        assert!(engine
+            .db
+            .prefix_iterator_cf(&prop_cf, &prefix)
+            .all(|item| !item.unwrap().0.starts_with(&prefix)));
+    }
+
+    #[test]
+    fn test_diff_between_transaction_times() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let other_graph = GraphId::from_name("other");
+
+        // Pause around each marker so writes never share its millisecond
+        let mark = || {
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            let time = TransactionTime::now();
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            time
+        };
+
+        let alice = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let bob = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let carol = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        for node in [&alice, &bob, &carol] {
+            engine.create_node(graph_id, node).unwrap();
+        }
+        let knows = Relationship::new(id_gen.next_relationship_id(), "KNOWS", alice.id, bob.id);
+        engine.put_relationship(graph_id, &knows).unwrap();
+        let start = mark();
+
+        let dave = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.create_node(graph_id, &dave).unwrap();
+        let mut renamed = alice.clone();
+        renamed.set_property("name", "Alice");
+        engine.put_node(graph_id, &renamed).unwrap();
+        let mut renamed_again = renamed.clone();
+        renamed_again.set_property("name", "Alicia");
+        engine.put_node(graph_id, &renamed_again).unwrap();
+        engine.delete_node(graph_id, carol.id).unwrap();
+        let mut weighted = knows.clone();
+        weighted.set_property("since", 2020i64);
+        engine.put_relationship(graph_id, &weighted).unwrap();
+        let likes = Relationship::new(id_gen.next_relationship_id(), "LIKES", bob.id, dave.id);
+        engine.put_relationship(graph_id, &likes).unwrap();
+        // Rewritten unchanged, and created then deleted: neither is reported
+        engine.put_node(graph_id, &bob).unwrap();
+        let temp = Node::with_labels(id_gen.next_node_id(), ["Temp"]);
+        engine.create_node(graph_id, &temp).unwrap();
+        engine.delete_node(graph_id, temp.id).unwrap();
+        engine
+            .create_node(other_graph, &Node::with_labels(id_gen.next_node_id(), ["Person"]))
+            .unwrap();
+        let end = mark();
+
+        engine.delete_relationship(graph_id, likes.id).unwrap();
+        engine
+            .create_node(graph_id, &Node::with_labels(id_gen.next_node_id(), ["Person"]))
+            .unwrap();
+
+        let diff = engine.diff(graph_id, start, end).unwrap();
+        assert_eq!(diff.nodes.created, vec![dave]);
+        assert_eq!(
+            diff.nodes.updated,
+            vec![EntityUpdate {
+                before: alice,
+                after: renamed_again
+            }]
+        );
+        assert_eq!(diff.nodes.deleted, vec![carol]);
+        assert_eq!(diff.relationships.created, vec![likes.clone()]);
+        assert_eq!(
+            diff.relationships.updated,
+            vec![EntityUpdate {
+                before: knows,
+                after: weighted
+            }]
+        );
+        assert!(diff.relationships.deleted.is_empty());
+
+        let later = engine.diff(graph_id, end, TransactionTime::now()).unwrap();
+        assert_eq!(later.nodes.created.len(), 1);
+        assert_eq!(later.relationships.deleted, vec![likes]);
+
+        assert!(engine.diff(graph_id, end, end).unwrap().is_empty());
+        assert!(matches!(
+            engine.diff(graph_id, end, start),
+            Err(Error::InvalidTemporalRange(_))
+        ));
+    }
 }
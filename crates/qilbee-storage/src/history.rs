@@ -0,0 +1,143 @@
+//! Change history of graph entities
+//!
+//! Every write of a node or relationship records the entity's state before
+//! and after the write, keyed by transaction time. Comparing the first and
+//! last record of each entity between two times tells what was created,
+//! updated or deleted in between.
+
+use qilbee_core::{EntityId, Node, NodeId, Relationship, RelationshipId};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// One recorded write: the entity before and after it, `None` meaning absent
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) enum Change {
+    Node {
+        before: Option<Node>,
+        after: Option<Node>,
+    },
+    Relationship {
+        before: Option<Relationship>,
+        after: Option<Relationship>,
+    },
+}
+
+/// Changes made by a batch, recorded in the order they were applied
+#[derive(Debug, Default)]
+pub(crate) struct ChangeLog(pub(crate) Vec<Change>);
+
+impl ChangeLog {
+    /// Record a node write; `after` is `None` for a delete
+    pub(crate) fn node(&mut self, before: Option<Node>, after: Option<Node>) {
+        self.0.push(Change::Node { before, after });
+    }
+
+    /// Record a relationship write; `after` is `None` for a delete
+    pub(crate) fn relationship(&mut self, before: Option<Relationship>, after: Option<Relationship>) {
+        self.0.push(Change::Relationship { before, after });
+    }
+}
+
+/// An entity that existed at both ends of a diff but changed in between
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityUpdate<T> {
+    /// State at the start of the diff
+    pub before: T,
+    /// State at the end of the diff
+    pub after: T,
+}
+
+/// How the nodes or relationships of a graph changed between two times
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityDiff<T> {
+    /// Entities absent at the start and present at the end
+    pub created: Vec<T>,
+    /// Entities present at both ends with a different state
+    pub updated: Vec<EntityUpdate<T>>,
+    /// Entities present at the start and absent at the end, in their last state
+    pub deleted: Vec<T>,
+}
+
+impl<T> Default for EntityDiff<T> {
+    fn default() -> Self {
+        Self {
+            created: Vec::new(),
+            updated: Vec::new(),
+            deleted: Vec::new(),
+        }
+    }
+}
+
+impl<T> EntityDiff<T> {
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.created.is_empty() && self.updated.is_empty() && self.deleted.is_empty()
+    }
+}
+
+/// How a graph changed between two transaction times
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GraphDiff {
+    pub nodes: EntityDiff<Node>,
+    pub relationships: EntityDiff<Relationship>,
+}
+
+impl GraphDiff {
+    /// Build a diff from the changes recorded between two times, oldest first
+    pub(crate) fn from_changes(changes: impl IntoIterator<Item = Change>) -> Self {
+        let mut nodes: HashMap<NodeId, (Option<Node>, Option<Node>)> = HashMap::new();
+        let mut relationships: HashMap<RelationshipId, (Option<Relationship>, Option<Relationship>)> =
+            HashMap::new();
+
+        // Keep the first `before` and the last `after` of each entity
+        for change in changes {
+            match change {
+                Change::Node { before, after } => {
+                    let Some(id) = before.as_ref().or(after.as_ref()).map(|n| n.id) else {
+                        continue;
+                    };
+                    nodes.entry(id).or_insert_with(|| (before, None)).1 = after;
+                }
+                Change::Relationship { before, after } => {
+                    let Some(id) = before.as_ref().or(after.as_ref()).map(|r| r.id) else {
+                        continue;
+                    };
+                    relationships.entry(id).or_insert_with(|| (before, None)).1 = after;
+                }
+            }
+        }
+
+        Self {
+            nodes: entity_diff(nodes, |n| n.id.as_internal()),
+            relationships: entity_diff(relationships, |r| r.id.as_internal()),
+        }
+    }
+
+    /// Whether nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.relationships.is_empty()
+    }
+}
+
+/// Classify each entity's start and end state, ordered by ID
+fn entity_diff<K, T: PartialEq>(
+    states: HashMap<K, (Option<T>, Option<T>)>,
+    id: impl Fn(&T) -> u64,
+) -> EntityDiff<T> {
+    let mut diff = EntityDiff::default();
+    for (before, after) in states.into_values() {
+        match (before, after) {
+            (None, Some(after)) => diff.created.push(after),
+            (Some(before), None) => diff.deleted.push(before),
+            (Some(before), Some(after)) if before != after => {
+                diff.updated.push(EntityUpdate { before, after })
+            }
+            // Unchanged, or created and deleted again in between
+            _ => {}
+        }
+    }
+    diff.created.sort_by_key(&id);
+    diff.deleted.sort_by_key(&id);
+    diff.updated.sort_by_key(|u| id(&u.after));
+    diff
+}
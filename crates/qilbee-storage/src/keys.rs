@@ -2,7 +2,7 @@
 //!
 //! Provides efficient binary key encoding for all storage operations.
 
-use qilbee_core::{EntityId, GraphId, NodeId, RelationshipId};
+use qilbee_core::{EntityId, GraphId, NodeId, PropertyValue, RelationshipId};
 
 /// Prefix bytes for different key types
 pub mod prefix {
@@ -15,9 +15,16 @@ pub mod prefix {
     pub const SCHEMA: u8 = 0x07;
     pub const META: u8 = 0x08;
     pub const CONSTRAINT: u8 = 0x09;
+    pub const ADJACENCY_OUT_ORDERED: u8 = 0x0A;
+    pub const ADJACENCY_IN_ORDERED: u8 = 0x0B;
+    pub const LABEL_COUNT: u8 = 0x0C;
+    pub const RELATIONSHIP_TYPE_COUNT: u8 = 0x0D;
+    pub const REL_PROPERTY_INDEX: u8 = 0x0E;
+    pub const HISTORY: u8 = 0x0F;
     pub const MEMORY_EPISODE: u8 = 0x10;
     pub const MEMORY_SEMANTIC: u8 = 0x11;
     pub const MEMORY_TEMPORAL: u8 = 0x12;
+    pub const COMPOSITE_INDEX: u8 = 0x13;
 }
 
 /// Key builder for storage operations
@@ -60,6 +67,14 @@ impl KeyBuilder {
         builder.finish()
     }
 
+    /// Create a relationship prefix for scanning all relationships in a graph
+    pub fn relationship_prefix(graph_id: GraphId) -> Vec<u8> {
+        let mut builder = Self::new(9);
+        builder.push_u8(prefix::RELATIONSHIP);
+        builder.push_u64(graph_id.as_internal());
+        builder.finish()
+    }
+
     /// Create a label index key (label -> node_id)
     pub fn label_index(graph_id: GraphId, label: &str, node_id: NodeId) -> Vec<u8> {
         let mut builder = Self::new(17 + label.len());
@@ -70,6 +85,14 @@ impl KeyBuilder {
         builder.finish()
     }
 
+    /// Create a label index prefix for scanning every label of a graph
+    pub fn label_index_graph_prefix(graph_id: GraphId) -> Vec<u8> {
+        let mut builder = Self::new(9);
+        builder.push_u8(prefix::LABEL_INDEX);
+        builder.push_u64(graph_id.as_internal());
+        builder.finish()
+    }
+
     /// Create a label index prefix for scanning all nodes with a label
     pub fn label_index_prefix(graph_id: GraphId, label: &str) -> Vec<u8> {
         let mut builder = Self::new(9 + label.len());
@@ -95,6 +118,15 @@ impl KeyBuilder {
         builder.finish()
     }
 
+    /// Create an outgoing adjacency prefix for scanning every relationship
+    /// of a graph
+    pub fn adjacency_out_graph_prefix(graph_id: GraphId) -> Vec<u8> {
+        let mut builder = Self::new(9);
+        builder.push_u8(prefix::ADJACENCY_OUT);
+        builder.push_u64(graph_id.as_internal());
+        builder.finish()
+    }
+
     /// Create an outgoing adjacency prefix for scanning
     pub fn adjacency_out_prefix(graph_id: GraphId, source: NodeId) -> Vec<u8> {
         let mut builder = Self::new(17);
@@ -143,6 +175,59 @@ impl KeyBuilder {
         builder.finish()
     }
 
+    /// Create an outgoing adjacency key ordered by a relationship property
+    ///
+    /// `sort_key` comes from [`sortable_property_value`], so a prefix scan
+    /// returns edges in property order.
+    pub fn adjacency_out_ordered(
+        graph_id: GraphId,
+        source: NodeId,
+        sort_key: &[u8],
+        rel_id: RelationshipId,
+    ) -> Vec<u8> {
+        let mut builder = Self::new(25 + sort_key.len());
+        builder.push_u8(prefix::ADJACENCY_OUT_ORDERED);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_u64(source.as_internal());
+        builder.push_bytes(sort_key);
+        builder.push_u64(rel_id.as_internal());
+        builder.finish()
+    }
+
+    /// Create an ordered outgoing adjacency prefix for scanning
+    pub fn adjacency_out_ordered_prefix(graph_id: GraphId, source: NodeId) -> Vec<u8> {
+        let mut builder = Self::new(17);
+        builder.push_u8(prefix::ADJACENCY_OUT_ORDERED);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_u64(source.as_internal());
+        builder.finish()
+    }
+
+    /// Create an incoming adjacency key ordered by a relationship property
+    pub fn adjacency_in_ordered(
+        graph_id: GraphId,
+        target: NodeId,
+        sort_key: &[u8],
+        rel_id: RelationshipId,
+    ) -> Vec<u8> {
+        let mut builder = Self::new(25 + sort_key.len());
+        builder.push_u8(prefix::ADJACENCY_IN_ORDERED);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_u64(target.as_internal());
+        builder.push_bytes(sort_key);
+        builder.push_u64(rel_id.as_internal());
+        builder.finish()
+    }
+
+    /// Create an ordered incoming adjacency prefix for scanning
+    pub fn adjacency_in_ordered_prefix(graph_id: GraphId, target: NodeId) -> Vec<u8> {
+        let mut builder = Self::new(17);
+        builder.push_u8(prefix::ADJACENCY_IN_ORDERED);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_u64(target.as_internal());
+        builder.finish()
+    }
+
     /// Create a property index key
     pub fn property_index(
         graph_id: GraphId,
@@ -191,6 +276,76 @@ impl KeyBuilder {
         builder.finish()
     }
 
+    /// Create a relationship property index key
+    pub fn rel_property_index(
+        graph_id: GraphId,
+        rel_type: &str,
+        property: &str,
+        value_hash: u64,
+        rel_id: RelationshipId,
+    ) -> Vec<u8> {
+        let mut builder = Self::new(33 + rel_type.len() + property.len());
+        builder.push_u8(prefix::REL_PROPERTY_INDEX);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_string(rel_type);
+        builder.push_string(property);
+        builder.push_u64(value_hash);
+        builder.push_u64(rel_id.as_internal());
+        builder.finish()
+    }
+
+    /// Create a relationship property index prefix for scanning all relationships
+    /// with a type+property+value
+    pub fn rel_property_index_value_prefix(
+        graph_id: GraphId,
+        rel_type: &str,
+        property: &str,
+        value_hash: u64,
+    ) -> Vec<u8> {
+        let mut builder = Self::new(25 + rel_type.len() + property.len());
+        builder.push_u8(prefix::REL_PROPERTY_INDEX);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_string(rel_type);
+        builder.push_string(property);
+        builder.push_u64(value_hash);
+        builder.finish()
+    }
+
+    /// Create a composite index key
+    ///
+    /// Holds the hash of each indexed property value in index order, so the
+    /// nodes matching values for the leading properties share a key prefix.
+    pub fn composite_index(
+        graph_id: GraphId,
+        index: &str,
+        value_hashes: &[u64],
+        node_id: NodeId,
+    ) -> Vec<u8> {
+        let mut builder = Self::new(19 + index.len() + 8 * value_hashes.len());
+        builder.push_u8(prefix::COMPOSITE_INDEX);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_string(index);
+        for &hash in value_hashes {
+            builder.push_u64(hash);
+        }
+        builder.push_u64(node_id.as_internal());
+        builder.finish()
+    }
+
+    /// Create a composite index prefix for scanning the nodes matching values
+    /// for the leading properties of the index, or all of its nodes if
+    /// `value_hashes` is empty
+    pub fn composite_index_prefix(graph_id: GraphId, index: &str, value_hashes: &[u64]) -> Vec<u8> {
+        let mut builder = Self::new(11 + index.len() + 8 * value_hashes.len());
+        builder.push_u8(prefix::COMPOSITE_INDEX);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_string(index);
+        for &hash in value_hashes {
+            builder.push_u64(hash);
+        }
+        builder.finish()
+    }
+
     /// Create a schema key
     pub fn schema(graph_id: GraphId, schema_type: &str, name: &str) -> Vec<u8> {
         let mut builder = Self::new(9 + schema_type.len() + name.len());
@@ -201,6 +356,46 @@ impl KeyBuilder {
         builder.finish()
     }
 
+    /// Create a prefix for all schema keys of one type in a graph
+    pub fn schema_prefix(graph_id: GraphId, schema_type: &str) -> Vec<u8> {
+        let mut builder = Self::new(11 + schema_type.len());
+        builder.push_u8(prefix::SCHEMA);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_string(schema_type);
+        builder.finish()
+    }
+
+    /// Create the database-wide schema version key
+    pub fn schema_version() -> Vec<u8> {
+        let mut builder = Self::new(10);
+        builder.push_u8(prefix::SCHEMA);
+        builder.push_string("version");
+        builder.finish()
+    }
+
+    /// Create a change history key
+    ///
+    /// Transaction time orders a graph's history; `sequence` orders changes
+    /// recorded in the same millisecond.
+    pub fn history(graph_id: GraphId, transaction_millis: i64, sequence: u64) -> Vec<u8> {
+        let mut builder = Self::new(25);
+        builder.push_u8(prefix::HISTORY);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_u64(transaction_millis.max(0) as u64);
+        builder.push_u64(sequence);
+        builder.finish()
+    }
+
+    /// Create a change history prefix for scanning a graph's history from a
+    /// transaction time on
+    pub fn history_from(graph_id: GraphId, transaction_millis: i64) -> Vec<u8> {
+        let mut builder = Self::new(17);
+        builder.push_u8(prefix::HISTORY);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_u64(transaction_millis.max(0) as u64);
+        builder.finish()
+    }
+
     /// Create a metadata key
     pub fn meta(key: &str) -> Vec<u8> {
         let mut builder = Self::new(1 + key.len());
@@ -218,6 +413,40 @@ impl KeyBuilder {
         builder.finish()
     }
 
+    /// Create the key holding the number of nodes with a label
+    pub fn label_count(graph_id: GraphId, label: &str) -> Vec<u8> {
+        let mut builder = Self::new(11 + label.len());
+        builder.push_u8(prefix::LABEL_COUNT);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_string(label);
+        builder.finish()
+    }
+
+    /// Create a prefix for the label counts of a graph
+    pub fn label_count_prefix(graph_id: GraphId) -> Vec<u8> {
+        let mut builder = Self::new(9);
+        builder.push_u8(prefix::LABEL_COUNT);
+        builder.push_u64(graph_id.as_internal());
+        builder.finish()
+    }
+
+    /// Create a prefix for the relationship type counts of a graph
+    pub fn relationship_type_count_prefix(graph_id: GraphId) -> Vec<u8> {
+        let mut builder = Self::new(9);
+        builder.push_u8(prefix::RELATIONSHIP_TYPE_COUNT);
+        builder.push_u64(graph_id.as_internal());
+        builder.finish()
+    }
+
+    /// Create the key holding the number of relationships of a type
+    pub fn relationship_type_count(graph_id: GraphId, rel_type: &str) -> Vec<u8> {
+        let mut builder = Self::new(11 + rel_type.len());
+        builder.push_u8(prefix::RELATIONSHIP_TYPE_COUNT);
+        builder.push_u64(graph_id.as_internal());
+        builder.push_string(rel_type);
+        builder.finish()
+    }
+
     /// Create an episodic memory key
     pub fn memory_episode(
         graph_id: GraphId,
@@ -257,19 +486,107 @@ impl KeyBuilder {
         self.buffer.extend_from_slice(&val.to_be_bytes());
     }
 
-    fn push_string(&mut self, s: &str) {
-        // Length-prefixed string
-        let bytes = s.as_bytes();
-        self.buffer
-            .extend_from_slice(&(bytes.len() as u16).to_be_bytes());
+    fn push_bytes(&mut self, bytes: &[u8]) {
         self.buffer.extend_from_slice(bytes);
     }
 
+    fn push_string(&mut self, s: &str) {
+        push_segment(&mut self.buffer, s.as_bytes());
+    }
+
     fn finish(self) -> Vec<u8> {
         self.buffer
     }
 }
 
+/// Length value marking a segment with an 8-byte length
+const EXTENDED_SEGMENT_LEN: u16 = u16::MAX;
+
+/// Append a length-prefixed segment to `buf`
+///
+/// Segments shorter than 65535 bytes get a 2-byte big-endian length; longer
+/// ones get `0xFFFF` followed by an 8-byte length. No byte is reserved as a
+/// separator, so names may contain anything and are never truncated.
+pub fn push_segment(buf: &mut Vec<u8>, bytes: &[u8]) {
+    match u16::try_from(bytes.len()) {
+        Ok(len) if len != EXTENDED_SEGMENT_LEN => buf.extend_from_slice(&len.to_be_bytes()),
+        _ => {
+            buf.extend_from_slice(&EXTENDED_SEGMENT_LEN.to_be_bytes());
+            buf.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+        }
+    }
+    buf.extend_from_slice(bytes);
+}
+
+/// Split the length-prefixed segment at the start of `data` from the rest
+///
+/// Returns `None` if `data` is too short to hold the segment.
+pub fn read_segment(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len = u16::from_be_bytes(data.get(..2)?.try_into().ok()?);
+    let (len, rest) = if len == EXTENDED_SEGMENT_LEN {
+        let len = u64::from_be_bytes(data.get(2..10)?.try_into().ok()?);
+        (usize::try_from(len).ok()?, &data[10..])
+    } else {
+        (len as usize, &data[2..])
+    };
+    (len <= rest.len()).then(|| rest.split_at(len))
+}
+
+/// Encode a property value so that byte order matches value order
+///
+/// Values are grouped by type first (null, booleans, integers, floats,
+/// strings, then temporal types); unsupported types and missing values
+/// sort last.
+pub fn sortable_property_value(value: Option<&PropertyValue>) -> Vec<u8> {
+    const SIGN: u64 = 1 << 63;
+
+    let mut out = Vec::with_capacity(9);
+    match value {
+        Some(PropertyValue::Null) => out.push(0x00),
+        Some(PropertyValue::Boolean(b)) => out.extend_from_slice(&[0x01, *b as u8]),
+        Some(PropertyValue::Integer(i)) => {
+            out.push(0x02);
+            out.extend_from_slice(&(*i as u64 ^ SIGN).to_be_bytes());
+        }
+        Some(PropertyValue::Float(f)) => {
+            // Flip all bits of negatives and only the sign bit of positives
+            let bits = f.to_bits();
+            let ordered = if bits & SIGN != 0 { !bits } else { bits ^ SIGN };
+            out.push(0x03);
+            out.extend_from_slice(&ordered.to_be_bytes());
+        }
+        Some(PropertyValue::String(s)) => {
+            // Escape NUL so the 0x00 0x01 terminator sorts before any content
+            out.push(0x04);
+            for &byte in s.as_bytes() {
+                out.push(byte);
+                if byte == 0x00 {
+                    out.push(0xFF);
+                }
+            }
+            out.extend_from_slice(&[0x00, 0x01]);
+        }
+        Some(PropertyValue::Date(d)) => {
+            out.push(0x05);
+            out.extend_from_slice(&(*d as u32 ^ (1 << 31)).to_be_bytes());
+        }
+        Some(PropertyValue::Time(t)) => {
+            out.push(0x06);
+            out.extend_from_slice(&(*t as u64 ^ SIGN).to_be_bytes());
+        }
+        Some(PropertyValue::DateTime(dt)) => {
+            out.push(0x07);
+            out.extend_from_slice(&(*dt as u64 ^ SIGN).to_be_bytes());
+        }
+        Some(PropertyValue::Duration(d)) => {
+            out.push(0x08);
+            out.extend_from_slice(&(*d as u64 ^ SIGN).to_be_bytes());
+        }
+        _ => out.push(0xFF),
+    }
+    out
+}
+
 /// Key decoder for parsing stored keys
 pub struct KeyDecoder<'a> {
     data: &'a [u8],
@@ -317,20 +634,9 @@ impl<'a> KeyDecoder<'a> {
 
     /// Read a length-prefixed string
     pub fn read_string(&mut self) -> Option<&'a str> {
-        if self.pos + 2 > self.data.len() {
-            return None;
-        }
-
-        let len_bytes: [u8; 2] = self.data[self.pos..self.pos + 2].try_into().ok()?;
-        let len = u16::from_be_bytes(len_bytes) as usize;
-        self.pos += 2;
-
-        if self.pos + len > self.data.len() {
-            return None;
-        }
-
-        let s = std::str::from_utf8(&self.data[self.pos..self.pos + len]).ok()?;
-        self.pos += len;
+        let (segment, rest) = read_segment(&self.data[self.pos..])?;
+        let s = std::str::from_utf8(segment).ok()?;
+        self.pos = self.data.len() - rest.len();
         Some(s)
     }
 
@@ -396,6 +702,95 @@ mod tests {
         assert_eq!(decoder.read_u64(), Some(100));
     }
 
+    #[test]
+    fn test_names_containing_separator_bytes() {
+        let graph_id = GraphId::from_name("test");
+        let node_id = NodeId::from_internal(7);
+        let names = ["Person", "Person\0", "Person\0X", "Per:son", "Per\u{ffff}son", ""];
+
+        for label in names {
+            for property in names {
+                let key = KeyBuilder::property_index(graph_id, label, property, 1, 2);
+                let mut decoder = KeyDecoder::new(&key);
+                assert_eq!(decoder.read_u8(), Some(prefix::PROPERTY_INDEX));
+                assert_eq!(decoder.read_u64(), Some(graph_id.as_internal()));
+                assert_eq!(decoder.read_string(), Some(label));
+                assert_eq!(decoder.read_string(), Some(property));
+                assert_eq!(decoder.read_u64(), Some(1));
+                assert_eq!(decoder.read_u64(), Some(2));
+                assert!(decoder.remaining().is_empty());
+            }
+        }
+
+        // A label's scan prefix never matches keys of a different label
+        for label in names {
+            let prefix = KeyBuilder::label_index_prefix(graph_id, label);
+            for other in names {
+                let key = KeyBuilder::label_index(graph_id, other, node_id);
+                assert_eq!(key.starts_with(&prefix), label == other, "{label:?} vs {other:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_names_are_not_truncated() {
+        let graph_id = GraphId::from_name("test");
+        let node_id = NodeId::from_internal(7);
+
+        for len in [65_534, 65_535, 65_536, 70_000] {
+            let label = "L".repeat(len);
+            let key = KeyBuilder::label_index(graph_id, &label, node_id);
+            let mut decoder = KeyDecoder::new(&key);
+            decoder.read_u8();
+            decoder.read_u64();
+            assert_eq!(decoder.read_string(), Some(label.as_str()));
+            assert_eq!(decoder.read_u64(), Some(7));
+        }
+
+        // A truncated 16-bit length would make this look like a short label
+        let key = KeyBuilder::label_index(graph_id, &"L".repeat(65_538), node_id);
+        assert!(!key.starts_with(&KeyBuilder::label_index_prefix(graph_id, "LL")));
+
+        assert_eq!(read_segment(&[0x00, 0x05, b'a']), None);
+        assert_eq!(read_segment(&[0xFF, 0xFF, 0x00]), None);
+    }
+
+    #[test]
+    fn test_sortable_property_value_order() {
+        let ordered = [
+            Some(PropertyValue::Null),
+            Some(PropertyValue::Boolean(false)),
+            Some(PropertyValue::Boolean(true)),
+            Some(PropertyValue::Integer(i64::MIN)),
+            Some(PropertyValue::Integer(-1)),
+            Some(PropertyValue::Integer(0)),
+            Some(PropertyValue::Integer(42)),
+            Some(PropertyValue::Integer(i64::MAX)),
+            Some(PropertyValue::Float(f64::NEG_INFINITY)),
+            Some(PropertyValue::Float(-2.5)),
+            Some(PropertyValue::Float(-0.0)),
+            Some(PropertyValue::Float(0.5)),
+            Some(PropertyValue::Float(f64::INFINITY)),
+            Some(PropertyValue::String(String::new())),
+            Some(PropertyValue::String("a".to_string())),
+            Some(PropertyValue::String("a\0".to_string())),
+            Some(PropertyValue::String("ab".to_string())),
+            Some(PropertyValue::String("b".to_string())),
+            Some(PropertyValue::DateTime(-1)),
+            Some(PropertyValue::DateTime(1_700_000_000_000)),
+            Some(PropertyValue::Bytes(vec![1])),
+            None,
+        ];
+
+        let encoded: Vec<Vec<u8>> = ordered
+            .iter()
+            .map(|value| sortable_property_value(value.as_ref()))
+            .collect();
+        for pair in encoded.windows(2) {
+            assert!(pair[0] <= pair[1], "{:?} should sort before {:?}", pair[0], pair[1]);
+        }
+    }
+
     #[test]
     fn test_key_prefix_scanning() {
         let graph_id = GraphId::from_name("test");
@@ -9,6 +9,8 @@
 //! - Column families for different data types
 //! - WAL for durability
 //! - Transaction support
+//! - Point-in-time snapshots
+//! - Change history for diffs between transaction times
 //!
 //! # Column Families
 //!
@@ -21,10 +23,16 @@
 //! - `meta` - Database metadata
 
 pub mod engine;
+pub mod history;
 pub mod keys;
+pub mod metrics;
 pub mod options;
+pub mod snapshot;
 pub mod transaction;
 
-pub use engine::StorageEngine;
-pub use options::StorageOptions;
+pub use engine::{CompositeIndex, IndexBuildProgress, StorageEngine, TypeCounts};
+pub use history::{EntityDiff, EntityUpdate, GraphDiff};
+pub use metrics::{ColumnFamilyMetrics, StorageMetrics};
+pub use options::{ColumnFamilyOptions, Compression, StorageOptions};
+pub use snapshot::StorageSnapshot;
 pub use transaction::Transaction;
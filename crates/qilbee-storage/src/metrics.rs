@@ -0,0 +1,118 @@
+//! Typed storage engine metrics
+//!
+//! RocksDB reports its state as named properties, per column family, and
+//! as statistics tickers in one text dump. [`StorageMetrics`] gathers the
+//! ones worth monitoring.
+
+use serde::Serialize;
+
+/// Ticker counting block cache reads served from the cache
+pub(crate) const BLOCK_CACHE_HIT_TICKER: &str = "rocksdb.block.cache.hit";
+
+/// Ticker counting block cache reads that went to disk
+pub(crate) const BLOCK_CACHE_MISS_TICKER: &str = "rocksdb.block.cache.miss";
+
+/// A snapshot of the storage engine's state
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct StorageMetrics {
+    /// One entry per column family, in column family order
+    pub column_families: Vec<ColumnFamilyMetrics>,
+
+    /// Block reads served from the block cache since the database opened
+    pub block_cache_hits: u64,
+
+    /// Block reads that missed the block cache since the database opened
+    pub block_cache_misses: u64,
+}
+
+/// State of one column family
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct ColumnFamilyMetrics {
+    pub name: String,
+
+    /// Estimated number of keys, counting overwritten and deleted keys not
+    /// yet compacted away
+    pub estimated_keys: u64,
+
+    /// Total size of the SST files
+    pub sst_bytes: u64,
+
+    /// Estimated bytes compaction has to rewrite to bring every level
+    /// under its target size
+    pub pending_compaction_bytes: u64,
+}
+
+impl StorageMetrics {
+    /// Total size of the SST files of all column families
+    pub fn total_sst_bytes(&self) -> u64 {
+        self.column_families.iter().map(|cf| cf.sst_bytes).sum()
+    }
+
+    /// Estimated bytes compaction has to rewrite across all column families
+    pub fn pending_compaction_bytes(&self) -> u64 {
+        self.column_families.iter().map(|cf| cf.pending_compaction_bytes).sum()
+    }
+
+    /// Share of block reads served from the block cache, or `None` before
+    /// any block was read
+    pub fn block_cache_hit_rate(&self) -> Option<f64> {
+        let reads = self.block_cache_hits + self.block_cache_misses;
+        (reads > 0).then(|| self.block_cache_hits as f64 / reads as f64)
+    }
+}
+
+/// Count of the ticker `name` in a RocksDB statistics dump, whose ticker
+/// lines read `rocksdb.block.cache.hit COUNT : 42`
+pub(crate) fn ticker_count(statistics: &str, name: &str) -> u64 {
+    statistics
+        .lines()
+        .find_map(|line| {
+            let (ticker, count) = line.split_once(" COUNT : ")?;
+            if ticker.trim() != name {
+                return None;
+            }
+            count.trim().parse().ok()
+        })
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ticker_count() {
+        let statistics = "rocksdb.block.cache.miss COUNT : 7\n\
+                          rocksdb.block.cache.hit COUNT : 21\n\
+                          rocksdb.db.get.micros P50 : 1.5 P95 : 3.0 COUNT : 9 SUM : 20\n";
+        assert_eq!(ticker_count(statistics, BLOCK_CACHE_HIT_TICKER), 21);
+        assert_eq!(ticker_count(statistics, BLOCK_CACHE_MISS_TICKER), 7);
+        assert_eq!(ticker_count(statistics, "rocksdb.block.cache.add"), 0);
+    }
+
+    #[test]
+    fn test_totals() {
+        let metrics = StorageMetrics {
+            column_families: vec![
+                ColumnFamilyMetrics {
+                    name: "nodes".to_string(),
+                    estimated_keys: 10,
+                    sst_bytes: 1000,
+                    pending_compaction_bytes: 50,
+                },
+                ColumnFamilyMetrics {
+                    name: "memory".to_string(),
+                    estimated_keys: 5,
+                    sst_bytes: 500,
+                    pending_compaction_bytes: 0,
+                },
+            ],
+            block_cache_hits: 3,
+            block_cache_misses: 1,
+        };
+        assert_eq!(metrics.total_sst_bytes(), 1500);
+        assert_eq!(metrics.pending_compaction_bytes(), 50);
+        assert_eq!(metrics.block_cache_hit_rate(), Some(0.75));
+        assert_eq!(StorageMetrics::default().block_cache_hit_rate(), None);
+    }
+}
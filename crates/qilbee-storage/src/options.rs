@@ -1,7 +1,43 @@
 //! Storage configuration options
 
+use crate::engine::cf;
+use std::collections::HashMap;
 use std::path::PathBuf;
 
+/// Compression of a column family's data files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Snappy,
+    Lz4,
+    Zstd,
+}
+
+/// Settings of one column family that override the database-wide ones
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ColumnFamilyOptions {
+    /// Compression of the column family's data files (`None` for the default)
+    pub compression: Option<Compression>,
+
+    /// Size in bytes of a block cache of its own (`None` to share the
+    /// database-wide cache)
+    pub block_cache_size: Option<usize>,
+}
+
+impl ColumnFamilyOptions {
+    /// Compress the column family with `compression`
+    pub fn compression(mut self, compression: Compression) -> Self {
+        self.compression = Some(compression);
+        self
+    }
+
+    /// Give the column family a block cache of `size` bytes
+    pub fn block_cache_size(mut self, size: usize) -> Self {
+        self.block_cache_size = Some(size);
+        self
+    }
+}
+
 /// Options for configuring the storage engine
 #[derive(Debug, Clone)]
 pub struct StorageOptions {
@@ -29,7 +65,7 @@ pub struct StorageOptions {
     /// Enable compression
     pub enable_compression: bool,
 
-    /// Block cache size in bytes
+    /// Size in bytes of the block cache shared by the column families
     pub block_cache_size: usize,
 
     /// Enable bloom filters
@@ -42,7 +78,34 @@ pub struct StorageOptions {
     pub enable_wal: bool,
 
     /// Sync WAL on every write (slower but safer)
+    ///
+    /// Has no effect when `enable_wal` is off.
     pub sync_wal: bool,
+
+    /// Relationship property that orders adjacency scans (e.g. a timestamp)
+    ///
+    /// When set, outgoing and incoming relationships are returned sorted by
+    /// this property instead of by relationship ID.
+    pub adjacency_sort_property: Option<String>,
+
+    /// Maximum number of properties on a node or relationship (`None` for unlimited)
+    pub max_properties_per_entity: Option<usize>,
+
+    /// Maximum serialized size of a single property value in bytes (`None` for unlimited)
+    pub max_property_value_bytes: Option<usize>,
+
+    /// Distance within which floats compare equal (`None` for exact comparison)
+    ///
+    /// Applies to property lookups and range scans, and to query predicates
+    /// evaluated over this database. Near-equal floats hash differently, so
+    /// with a tolerance numeric lookups scan a range instead of the hash index.
+    pub float_epsilon: Option<f64>,
+
+    /// Compression and block cache overrides by column family name
+    ///
+    /// Column families without an override use the compression of
+    /// [`Self::compression_for`] and the shared block cache.
+    pub column_families: HashMap<String, ColumnFamilyOptions>,
 }
 
 impl StorageOptions {
@@ -70,6 +133,11 @@ impl StorageOptions {
             bloom_filter_bits_per_key: 10,
             enable_wal: true,
             sync_wal: false, // Faster for tests
+            adjacency_sort_property: None,
+            max_properties_per_entity: None,
+            max_property_value_bytes: None,
+            float_epsilon: None,
+            column_families: HashMap::new(),
         }
     }
 
@@ -89,6 +157,11 @@ impl StorageOptions {
             bloom_filter_bits_per_key: 10,
             enable_wal: true,
             sync_wal: true,
+            adjacency_sort_property: None,
+            max_properties_per_entity: None,
+            max_property_value_bytes: None,
+            float_epsilon: None,
+            column_families: HashMap::new(),
         }
     }
 
@@ -115,6 +188,58 @@ impl StorageOptions {
         self.enable_compression = enabled;
         self
     }
+
+    /// Order adjacency scans by a relationship property
+    pub fn adjacency_sort_property<S: Into<String>>(mut self, property: S) -> Self {
+        self.adjacency_sort_property = Some(property.into());
+        self
+    }
+
+    /// Limit the number of properties on a single node or relationship
+    pub fn max_properties_per_entity(mut self, max: usize) -> Self {
+        self.max_properties_per_entity = Some(max);
+        self
+    }
+
+    /// Limit the serialized size of a single property value
+    pub fn max_property_value_bytes(mut self, max: usize) -> Self {
+        self.max_property_value_bytes = Some(max);
+        self
+    }
+
+    /// Treat floats within `epsilon` of each other as equal in comparisons
+    pub fn float_epsilon(mut self, epsilon: f64) -> Self {
+        self.float_epsilon = Some(epsilon);
+        self
+    }
+
+    /// Override the compression or block cache of one column family
+    pub fn column_family<S: Into<String>>(mut self, name: S, options: ColumnFamilyOptions) -> Self {
+        self.column_families.insert(name.into(), options);
+        self
+    }
+
+    /// Compression of the column family `name`
+    ///
+    /// An override in [`Self::column_families`] wins. Otherwise nothing is
+    /// compressed unless `enable_compression` is set, in which case:
+    /// - `memory` and `history`, large and rarely read, use Zstd
+    /// - `adjacency_out` and `adjacency_in`, hot and holding little data
+    ///   per key, are left uncompressed
+    /// - every other column family uses LZ4
+    pub fn compression_for(&self, name: &str) -> Compression {
+        if let Some(compression) = self.column_families.get(name).and_then(|cf| cf.compression) {
+            return compression;
+        }
+        if !self.enable_compression {
+            return Compression::None;
+        }
+        match name {
+            cf::MEMORY | cf::HISTORY => Compression::Zstd,
+            cf::ADJACENCY_OUT | cf::ADJACENCY_IN => Compression::None,
+            _ => Compression::Lz4,
+        }
+    }
 }
 
 impl Default for StorageOptions {
@@ -133,6 +258,11 @@ impl Default for StorageOptions {
             bloom_filter_bits_per_key: 10,
             enable_wal: true,
             sync_wal: false,
+            adjacency_sort_property: None,
+            max_properties_per_entity: None,
+            max_property_value_bytes: None,
+            float_epsilon: None,
+            column_families: HashMap::new(),
         }
     }
 }
@@ -169,10 +299,29 @@ mod tests {
         let opts = StorageOptions::new("/data")
             .write_buffer_size(128 * 1024 * 1024)
             .sync_wal(true)
-            .compression(false);
+            .compression(false)
+            .adjacency_sort_property("timestamp")
+            .max_properties_per_entity(64)
+            .max_property_value_bytes(1024);
 
         assert_eq!(opts.write_buffer_size, 128 * 1024 * 1024);
         assert!(opts.sync_wal);
         assert!(!opts.enable_compression);
+        assert_eq!(opts.adjacency_sort_property.as_deref(), Some("timestamp"));
+        assert_eq!(opts.max_properties_per_entity, Some(64));
+        assert_eq!(opts.max_property_value_bytes, Some(1024));
+    }
+
+    #[test]
+    fn test_column_family_compression() {
+        let opts = StorageOptions::for_production("/data");
+        assert_eq!(opts.compression_for(cf::MEMORY), Compression::Zstd);
+        assert_eq!(opts.compression_for(cf::ADJACENCY_OUT), Compression::None);
+        assert_eq!(opts.compression_for(cf::NODES), Compression::Lz4);
+
+        let opts = StorageOptions::for_testing("/tmp/test")
+            .column_family(cf::NODES, ColumnFamilyOptions::default().compression(Compression::Snappy));
+        assert_eq!(opts.compression_for(cf::NODES), Compression::Snappy);
+        assert_eq!(opts.compression_for(cf::MEMORY), Compression::None);
     }
 }
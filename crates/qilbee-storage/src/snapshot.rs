@@ -0,0 +1,356 @@
+//! Point-in-time read views of the storage engine
+
+use crate::engine::{cf, StorageEngine};
+use crate::keys::KeyBuilder;
+use qilbee_core::{GraphId, Node, NodeId, PropertyValue, Relationship, RelationshipId, Result};
+use rocksdb::Snapshot;
+
+/// A read-only view of the database as it was when the snapshot was taken
+///
+/// Created by [`StorageEngine::snapshot`]. Every read sees the same committed
+/// state, however many writes land meanwhile, so a relationship and its
+/// endpoints read through one snapshot are always consistent with each other.
+///
+/// The snapshot keeps the database open for as long as it lives, so it can be
+/// stored or sent to another thread like the engine it came from.
+pub struct StorageSnapshot {
+    // Declared first so it is released before the engine it borrows from
+    snapshot: Snapshot<'static>,
+    engine: StorageEngine,
+}
+
+impl StorageSnapshot {
+    pub(crate) fn new(engine: StorageEngine) -> Self {
+        let snapshot = engine.db_snapshot();
+        // SAFETY: the snapshot borrows the database behind the engine's `Arc`,
+        // which does not move and stays alive while `engine` holds a
+        // reference to it. `snapshot` is dropped before `engine`, and the
+        // `'static` lifetime never escapes this struct.
+        let snapshot = unsafe { std::mem::transmute::<Snapshot<'_>, Snapshot<'static>>(snapshot) };
+        Self { snapshot, engine }
+    }
+
+    /// Get a node by ID
+    pub fn get_node(&self, graph_id: GraphId, node_id: NodeId) -> Result<Option<Node>> {
+        self.engine.node_at(Some(&self.snapshot), graph_id, node_id)
+    }
+
+    /// Get all nodes with a specific label
+    pub fn get_nodes_by_label(&self, graph_id: GraphId, label: &str) -> Result<Vec<Node>> {
+        self.engine
+            .nodes_by_label_at(Some(&self.snapshot), graph_id, label)
+    }
+
+    /// Iterate over all nodes of a graph in ID order
+    pub fn nodes(&self, graph_id: GraphId) -> Result<impl Iterator<Item = Result<Node>> + '_> {
+        self.engine
+            .entities_at(Some(&self.snapshot), cf::NODES, KeyBuilder::node_prefix(graph_id))
+    }
+
+    /// Get up to `limit` IDs of the nodes of a graph, or of those with
+    /// `label`, that come after `after`, in ID order
+    ///
+    /// Lets callers page through a graph without holding it in memory.
+    pub fn node_ids_after(
+        &self,
+        graph_id: GraphId,
+        label: Option<&str>,
+        after: Option<NodeId>,
+        limit: usize,
+    ) -> Result<Vec<NodeId>> {
+        self.engine
+            .node_ids_at(Some(&self.snapshot), graph_id, label, after, limit)
+    }
+
+    /// Get nodes by label and property value using the property index
+    pub fn get_nodes_by_property(
+        &self,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
+        value: &PropertyValue,
+    ) -> Result<Vec<Node>> {
+        self.engine
+            .nodes_by_property_at(Some(&self.snapshot), graph_id, label, property, value)
+    }
+
+    /// Get nodes with a label that have a property, whatever its value
+    pub fn get_nodes_with_property(
+        &self,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
+    ) -> Result<Vec<Node>> {
+        self.engine
+            .nodes_with_property_at(Some(&self.snapshot), graph_id, label, property)
+    }
+
+    /// Get nodes with a label whose property lies within `[min, max]`
+    pub fn get_nodes_by_property_range(
+        &self,
+        graph_id: GraphId,
+        label: &str,
+        property: &str,
+        min_value: Option<&PropertyValue>,
+        max_value: Option<&PropertyValue>,
+    ) -> Result<Vec<Node>> {
+        self.engine.nodes_by_property_range_at(
+            Some(&self.snapshot),
+            graph_id,
+            label,
+            property,
+            min_value,
+            max_value,
+        )
+    }
+
+    /// Get nodes by values for the leading properties of a composite index
+    pub fn get_nodes_by_composite_property(
+        &self,
+        graph_id: GraphId,
+        index_name: &str,
+        values: &[PropertyValue],
+    ) -> Result<Vec<Node>> {
+        self.engine
+            .nodes_by_composite_property_at(Some(&self.snapshot), graph_id, index_name, values)
+    }
+
+    /// Iterate over all relationships of a graph in ID order
+    pub fn relationships(
+        &self,
+        graph_id: GraphId,
+    ) -> Result<impl Iterator<Item = Result<Relationship>> + '_> {
+        self.engine.entities_at(
+            Some(&self.snapshot),
+            cf::RELATIONSHIPS,
+            KeyBuilder::relationship_prefix(graph_id),
+        )
+    }
+
+    /// Get a relationship by ID
+    pub fn get_relationship(
+        &self,
+        graph_id: GraphId,
+        rel_id: RelationshipId,
+    ) -> Result<Option<Relationship>> {
+        self.engine
+            .relationship_at(Some(&self.snapshot), graph_id, rel_id)
+    }
+
+    /// Get outgoing relationships from a node
+    pub fn get_outgoing_relationships(
+        &self,
+        graph_id: GraphId,
+        node_id: NodeId,
+    ) -> Result<Vec<Relationship>> {
+        self.engine
+            .outgoing_at(Some(&self.snapshot), graph_id, node_id)
+    }
+
+    /// Get incoming relationships to a node
+    pub fn get_incoming_relationships(
+        &self,
+        graph_id: GraphId,
+        node_id: NodeId,
+    ) -> Result<Vec<Relationship>> {
+        self.engine
+            .incoming_at(Some(&self.snapshot), graph_id, node_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::options::StorageOptions;
+    use qilbee_core::IdGenerator;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use tempfile::TempDir;
+
+    fn create_test_engine() -> (StorageEngine, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let options = StorageOptions::for_testing(temp_dir.path());
+        let engine = StorageEngine::open(options).unwrap();
+        (engine, temp_dir)
+    }
+
+    #[test]
+    fn test_snapshot_ignores_later_writes() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let alice = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &alice).unwrap();
+        let snapshot = engine.snapshot();
+
+        let bob = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &bob).unwrap();
+        let knows = Relationship::new(id_gen.next_relationship_id(), "KNOWS", alice.id, bob.id);
+        engine.put_relationship(graph_id, &knows).unwrap();
+        engine
+            .put_node(graph_id, &Node::with_labels(alice.id, ["Company"]))
+            .unwrap();
+
+        assert_eq!(
+            snapshot.get_node(graph_id, alice.id).unwrap(),
+            Some(alice.clone())
+        );
+        assert!(snapshot.get_node(graph_id, bob.id).unwrap().is_none());
+        assert!(
+            snapshot
+                .get_relationship(graph_id, knows.id)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            snapshot
+                .get_outgoing_relationships(graph_id, alice.id)
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            snapshot.get_nodes_by_label(graph_id, "Person").unwrap(),
+            vec![alice]
+        );
+
+        // A new snapshot sees everything committed so far
+        let latest = engine.snapshot();
+        assert_eq!(
+            latest.get_incoming_relationships(graph_id, bob.id).unwrap(),
+            vec![knows]
+        );
+        assert_eq!(
+            latest
+                .get_nodes_by_label(graph_id, "Company")
+                .unwrap()
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_snapshot_iterates_entities() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let other_graph = GraphId::from_name("other");
+
+        let nodes: Vec<_> = (0..3)
+            .map(|_| Node::with_labels(id_gen.next_node_id(), ["Person"]))
+            .collect();
+        for node in &nodes {
+            engine.put_node(graph_id, node).unwrap();
+        }
+        let knows = Relationship::new(id_gen.next_relationship_id(), "KNOWS", nodes[0].id, nodes[1].id);
+        engine.put_relationship(graph_id, &knows).unwrap();
+        engine
+            .put_node(other_graph, &Node::with_labels(id_gen.next_node_id(), ["Person"]))
+            .unwrap();
+        let snapshot = engine.snapshot();
+
+        engine
+            .put_node(graph_id, &Node::with_labels(id_gen.next_node_id(), ["Person"]))
+            .unwrap();
+        engine.delete_relationship(graph_id, knows.id).unwrap();
+
+        let listed: Vec<Node> = snapshot.nodes(graph_id).unwrap().map(Result::unwrap).collect();
+        assert_eq!(listed, nodes);
+        let listed: Vec<Relationship> = snapshot
+            .relationships(graph_id)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(listed, vec![knows]);
+        assert_eq!(snapshot.relationships(other_graph).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn test_snapshot_outlives_engine_handle() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let nodes: Vec<_> = (0..3)
+            .map(|_| Node::with_labels(id_gen.next_node_id(), ["Person"]))
+            .collect();
+        for node in &nodes {
+            engine.put_node(graph_id, node).unwrap();
+        }
+        let snapshot = std::thread::spawn(move || engine.snapshot()).join().unwrap();
+
+        let ids: Vec<NodeId> = nodes.iter().map(|node| node.id).collect();
+        assert_eq!(snapshot.node_ids_after(graph_id, Some("Person"), None, 10).unwrap(), ids);
+        assert_eq!(snapshot.node_ids_after(graph_id, None, Some(ids[0]), 1).unwrap(), vec![ids[1]]);
+        assert_eq!(snapshot.get_node(graph_id, ids[2]).unwrap(), Some(nodes[2].clone()));
+    }
+
+    #[test]
+    fn test_readers_never_see_torn_relationships() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+        let writing = AtomicBool::new(true);
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                for _ in 0..200 {
+                    // Each pair and its relationship appear and vanish together
+                    let source = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+                    let target = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+                    let rel = Relationship::new(
+                        id_gen.next_relationship_id(),
+                        "KNOWS",
+                        source.id,
+                        target.id,
+                    );
+
+                    let mut tx = engine.begin_transaction(graph_id).unwrap();
+                    tx.put_node(source.clone()).unwrap();
+                    tx.put_node(target.clone()).unwrap();
+                    tx.put_relationship(rel.clone()).unwrap();
+                    tx.commit().unwrap();
+
+                    let mut tx = engine.begin_transaction(graph_id).unwrap();
+                    tx.delete_relationship(rel.id).unwrap();
+                    tx.delete_node(source.id).unwrap();
+                    tx.delete_node(target.id).unwrap();
+                    tx.commit().unwrap();
+                }
+                writing.store(false, Ordering::SeqCst);
+            });
+
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    while writing.load(Ordering::SeqCst) {
+                        let snapshot = engine.snapshot();
+                        for node in snapshot.get_nodes_by_label(graph_id, "Person").unwrap() {
+                            for rel in snapshot
+                                .get_outgoing_relationships(graph_id, node.id)
+                                .unwrap()
+                            {
+                                assert!(
+                                    snapshot.get_node(graph_id, rel.target).unwrap().is_some(),
+                                    "relationship {:?} read without its target",
+                                    rel.id
+                                );
+                            }
+                            for rel in snapshot
+                                .get_incoming_relationships(graph_id, node.id)
+                                .unwrap()
+                            {
+                                assert!(snapshot.get_node(graph_id, rel.source).unwrap().is_some());
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        assert!(
+            engine
+                .get_nodes_by_label(graph_id, "Person")
+                .unwrap()
+                .is_empty()
+        );
+    }
+}
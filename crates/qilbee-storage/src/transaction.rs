@@ -1,7 +1,10 @@
 //! Transaction support for QilbeeDB storage
 
 use crate::engine::StorageEngine;
-use qilbee_core::{Error, GraphId, Node, NodeId, Relationship, RelationshipId, Result};
+use crate::snapshot::StorageSnapshot;
+use qilbee_core::{
+    EntityId, Error, GraphId, Node, NodeId, PropertyValue, Relationship, RelationshipId, Result,
+};
 use std::collections::HashMap;
 use std::sync::atomic::{AtomicU64, Ordering};
 
@@ -34,8 +37,15 @@ pub enum TransactionOperation {
 
 /// A transaction for atomic graph operations
 ///
-/// Provides ACID semantics for a sequence of graph operations.
-/// Changes are only visible after commit.
+/// Writes are buffered until `commit`, which applies all of them in a single
+/// storage batch. Reads through the transaction see its own pending writes;
+/// other readers see none of them until commit, and none at all after
+/// `rollback` or if the transaction is dropped.
+///
+/// Everything else is read from a snapshot taken when the transaction began,
+/// so writes committed by others in the meantime are not seen. Commits are
+/// not checked for conflicts: if two transactions write the same entity, the
+/// last to commit wins.
 pub struct Transaction {
     /// Transaction ID
     id: u64,
@@ -46,12 +56,21 @@ pub struct Transaction {
     /// Reference to the storage engine
     engine: StorageEngine,
 
+    /// Committed state as of the start of the transaction
+    snapshot: StorageSnapshot,
+
     /// Current state
     state: TransactionState,
 
     /// Pending operations
     operations: Vec<TransactionOperation>,
 
+    /// Latest pending version of each written node, `None` once deleted
+    pending_nodes: HashMap<NodeId, Option<Node>>,
+
+    /// Latest pending version of each written relationship, `None` once deleted
+    pending_rels: HashMap<RelationshipId, Option<Relationship>>,
+
     /// Node read cache for this transaction
     node_cache: HashMap<NodeId, Option<Node>>,
 
@@ -65,9 +84,12 @@ impl Transaction {
         Self {
             id: TRANSACTION_COUNTER.fetch_add(1, Ordering::SeqCst),
             graph_id,
+            snapshot: engine.snapshot(),
             engine,
             state: TransactionState::Active,
             operations: Vec::new(),
+            pending_nodes: HashMap::new(),
+            pending_rels: HashMap::new(),
             node_cache: HashMap::new(),
             rel_cache: HashMap::new(),
         }
@@ -103,8 +125,7 @@ impl Transaction {
     pub fn put_node(&mut self, node: Node) -> Result<()> {
         self.check_active()?;
 
-        // Update cache
-        self.node_cache.insert(node.id, Some(node.clone()));
+        self.pending_nodes.insert(node.id, Some(node.clone()));
 
         // Add to pending operations
         self.operations.push(TransactionOperation::PutNode(node));
@@ -119,13 +140,16 @@ impl Transaction {
     pub fn get_node(&mut self, node_id: NodeId) -> Result<Option<Node>> {
         self.check_active()?;
 
-        // Check cache first (includes pending changes)
+        // Pending changes take precedence over the stored version
+        if let Some(pending) = self.pending_nodes.get(&node_id) {
+            return Ok(pending.clone());
+        }
         if let Some(cached) = self.node_cache.get(&node_id) {
             return Ok(cached.clone());
         }
 
-        // Read from storage
-        let node = self.engine.get_node(self.graph_id, node_id)?;
+        // Read from the transaction's snapshot
+        let node = self.snapshot.get_node(self.graph_id, node_id)?;
 
         // Cache the result
         self.node_cache.insert(node_id, node.clone());
@@ -137,8 +161,7 @@ impl Transaction {
     pub fn delete_node(&mut self, node_id: NodeId) -> Result<()> {
         self.check_active()?;
 
-        // Mark as deleted in cache
-        self.node_cache.insert(node_id, None);
+        self.pending_nodes.insert(node_id, None);
 
         // Add to pending operations
         self.operations
@@ -153,8 +176,7 @@ impl Transaction {
     pub fn put_relationship(&mut self, rel: Relationship) -> Result<()> {
         self.check_active()?;
 
-        // Update cache
-        self.rel_cache.insert(rel.id, Some(rel.clone()));
+        self.pending_rels.insert(rel.id, Some(rel.clone()));
 
         // Add to pending operations
         self.operations
@@ -167,13 +189,16 @@ impl Transaction {
     pub fn get_relationship(&mut self, rel_id: RelationshipId) -> Result<Option<Relationship>> {
         self.check_active()?;
 
-        // Check cache first
+        // Pending changes take precedence over the stored version
+        if let Some(pending) = self.pending_rels.get(&rel_id) {
+            return Ok(pending.clone());
+        }
         if let Some(cached) = self.rel_cache.get(&rel_id) {
             return Ok(cached.clone());
         }
 
-        // Read from storage
-        let rel = self.engine.get_relationship(self.graph_id, rel_id)?;
+        // Read from the transaction's snapshot
+        let rel = self.snapshot.get_relationship(self.graph_id, rel_id)?;
 
         // Cache the result
         self.rel_cache.insert(rel_id, rel.clone());
@@ -185,8 +210,7 @@ impl Transaction {
     pub fn delete_relationship(&mut self, rel_id: RelationshipId) -> Result<()> {
         self.check_active()?;
 
-        // Mark as deleted in cache
-        self.rel_cache.insert(rel_id, None);
+        self.pending_rels.insert(rel_id, None);
 
         // Add to pending operations
         self.operations
@@ -195,44 +219,185 @@ impl Transaction {
         Ok(())
     }
 
+    // ========== Lookups ==========
+    //
+    // Lookups read the snapshot and then apply the pending writes: nodes and
+    // relationships written by the transaction replace their stored
+    // versions, and are included if their pending version matches.
+
+    /// Get all nodes with a label
+    pub fn get_nodes_by_label(&mut self, label: &str) -> Result<Vec<Node>> {
+        self.check_active()?;
+        let stored = self.snapshot.get_nodes_by_label(self.graph_id, label)?;
+        Ok(self.overlay_nodes(stored, |node| node.has_label_name(label)))
+    }
+
+    /// Get all nodes
+    pub fn nodes(&mut self) -> Result<Vec<Node>> {
+        self.check_active()?;
+        let stored = self.snapshot.nodes(self.graph_id)?.collect::<Result<Vec<_>>>()?;
+        Ok(self.overlay_nodes(stored, |_| true))
+    }
+
+    /// Get up to `limit` IDs of the nodes, or of the nodes with `label`, that
+    /// come after `after`, in ID order
+    pub fn node_ids_after(&mut self, label: Option<&str>, after: Option<NodeId>, limit: usize) -> Result<Vec<NodeId>> {
+        self.check_active()?;
+        // Each pending write can hide at most one stored ID
+        let window = limit.saturating_add(self.pending_nodes.len());
+        let mut ids = self.snapshot.node_ids_after(self.graph_id, label, after, window)?;
+        ids.retain(|id| !self.pending_nodes.contains_key(id));
+        ids.extend(self.pending_nodes.values().flatten().filter_map(|node| {
+            let after_ok = after.is_none_or(|after| node.id.as_internal() > after.as_internal());
+            let label_ok = label.is_none_or(|label| node.has_label_name(label));
+            (after_ok && label_ok).then_some(node.id)
+        }));
+        ids.sort_by_key(|id| id.as_internal());
+        ids.truncate(limit);
+        Ok(ids)
+    }
+
+    /// Get the nodes with a label whose property equals `value`
+    pub fn get_nodes_by_property(&mut self, label: &str, property: &str, value: &PropertyValue) -> Result<Vec<Node>> {
+        self.check_active()?;
+        let stored = self.snapshot.get_nodes_by_property(self.graph_id, label, property, value)?;
+        let epsilon = self.engine.float_epsilon();
+        Ok(self.overlay_nodes(stored, |node| {
+            node.has_label_name(label)
+                && node.properties.get(property).is_some_and(|actual| actual.equals_within(value, epsilon))
+        }))
+    }
+
+    /// Get the nodes with a label that have a property
+    pub fn get_nodes_with_property(&mut self, label: &str, property: &str) -> Result<Vec<Node>> {
+        self.check_active()?;
+        let stored = self.snapshot.get_nodes_with_property(self.graph_id, label, property)?;
+        Ok(self.overlay_nodes(stored, |node| node.has_label_name(label) && node.properties.contains(property)))
+    }
+
+    /// Get the nodes with a label whose property lies between `min_value`
+    /// and `max_value`, inclusive
+    pub fn get_nodes_by_property_range(
+        &mut self,
+        label: &str,
+        property: &str,
+        min_value: Option<&PropertyValue>,
+        max_value: Option<&PropertyValue>,
+    ) -> Result<Vec<Node>> {
+        self.check_active()?;
+        let stored = self
+            .snapshot
+            .get_nodes_by_property_range(self.graph_id, label, property, min_value, max_value)?;
+        let engine = self.engine.clone();
+        Ok(self.overlay_nodes(stored, |node| {
+            node.has_label_name(label) && engine.property_in_range(node, property, min_value, max_value)
+        }))
+    }
+
+    /// Get the nodes matching values for the leading properties of a
+    /// composite index
+    pub fn get_nodes_by_composite_property(&mut self, index_name: &str, values: &[PropertyValue]) -> Result<Vec<Node>> {
+        self.check_active()?;
+        let stored = self.snapshot.get_nodes_by_composite_property(self.graph_id, index_name, values)?;
+        let index = self
+            .engine
+            .composite_indexes(self.graph_id)?
+            .into_iter()
+            .find(|index| index.name == index_name)
+            .ok_or_else(|| Error::IndexNotFound(index_name.to_string()))?;
+        let epsilon = self.engine.float_epsilon();
+        Ok(self.overlay_nodes(stored, |node| index.matches(node, values, epsilon)))
+    }
+
+    /// Get all relationships
+    pub fn relationships(&mut self) -> Result<Vec<Relationship>> {
+        self.check_active()?;
+        let stored = self.snapshot.relationships(self.graph_id)?.collect::<Result<Vec<_>>>()?;
+        Ok(self.overlay_relationships(stored, |_| true))
+    }
+
+    /// Get the relationships leaving a node
+    pub fn get_outgoing_relationships(&mut self, node_id: NodeId) -> Result<Vec<Relationship>> {
+        self.check_active()?;
+        let stored = self.snapshot.get_outgoing_relationships(self.graph_id, node_id)?;
+        Ok(self.overlay_relationships(stored, |rel| rel.source == node_id))
+    }
+
+    /// Get the relationships entering a node
+    pub fn get_incoming_relationships(&mut self, node_id: NodeId) -> Result<Vec<Relationship>> {
+        self.check_active()?;
+        let stored = self.snapshot.get_incoming_relationships(self.graph_id, node_id)?;
+        Ok(self.overlay_relationships(stored, |rel| rel.target == node_id))
+    }
+
+    /// Replace stored nodes by their pending versions, keeping ID order
+    fn overlay_nodes(&self, stored: Vec<Node>, matches: impl Fn(&Node) -> bool) -> Vec<Node> {
+        let mut nodes: Vec<Node> = stored
+            .into_iter()
+            .filter(|node| !self.pending_nodes.contains_key(&node.id))
+            .collect();
+        nodes.extend(self.pending_nodes.values().flatten().filter(|node| matches(node)).cloned());
+        nodes.sort_by_key(|node| node.id.as_internal());
+        nodes
+    }
+
+    /// Replace stored relationships by their pending versions, keeping ID order
+    fn overlay_relationships(
+        &self,
+        stored: Vec<Relationship>,
+        matches: impl Fn(&Relationship) -> bool,
+    ) -> Vec<Relationship> {
+        let mut rels: Vec<Relationship> = stored
+            .into_iter()
+            .filter(|rel| !self.pending_rels.contains_key(&rel.id))
+            .collect();
+        rels.extend(self.pending_rels.values().flatten().filter(|rel| matches(rel)).cloned());
+        rels.sort_by_key(|rel| rel.id.as_internal());
+        rels
+    }
+
     // ========== Transaction Control ==========
 
     /// Commit the transaction
     ///
-    /// Applies all pending operations atomically.
-    pub fn commit(mut self) -> Result<()> {
+    /// Applies all pending operations atomically: only the last write to
+    /// each entity is stored, and if the batch fails nothing is.
+    pub fn commit(self) -> Result<()> {
+        self.commit_checked(|_, _| Ok(()))
+    }
+
+    /// Like [`Self::commit`], first running `check` on the latest pending
+    /// version of each written node and relationship while holding the
+    /// storage write lock; if it fails nothing is written
+    pub fn commit_checked(
+        mut self,
+        check: impl FnOnce(
+            &HashMap<NodeId, Option<Node>>,
+            &HashMap<RelationshipId, Option<Relationship>>,
+        ) -> Result<()>,
+    ) -> Result<()> {
         self.check_active()?;
 
-        // Apply all operations
-        for op in self.operations.drain(..) {
-            match op {
-                TransactionOperation::PutNode(node) => {
-                    self.engine.put_node(self.graph_id, &node)?;
-                }
-                TransactionOperation::DeleteNode(node_id) => {
-                    self.engine.delete_node(self.graph_id, node_id)?;
-                }
-                TransactionOperation::PutRelationship(rel) => {
-                    self.engine.put_relationship(self.graph_id, &rel)?;
-                }
-                TransactionOperation::DeleteRelationship(rel_id) => {
-                    self.engine.delete_relationship(self.graph_id, rel_id)?;
-                }
-            }
-        }
+        let (nodes, rels) = (&self.pending_nodes, &self.pending_rels);
+        self.engine
+            .apply_changes(self.graph_id, nodes, rels, || check(nodes, rels))?;
 
+        self.operations.clear();
         self.state = TransactionState::Committed;
         Ok(())
     }
 
     /// Rollback the transaction
     ///
-    /// Discards all pending operations.
+    /// Discards all pending operations. Nothing was written to storage, so
+    /// there is nothing to undo.
     pub fn rollback(mut self) -> Result<()> {
         self.check_active()?;
 
         // Clear all pending operations
         self.operations.clear();
+        self.pending_nodes.clear();
+        self.pending_rels.clear();
         self.node_cache.clear();
         self.rel_cache.clear();
 
@@ -250,7 +415,7 @@ impl Transaction {
 mod tests {
     use super::*;
     use crate::options::StorageOptions;
-    use qilbee_core::{EntityId, IdGenerator};
+    use qilbee_core::IdGenerator;
     use tempfile::TempDir;
 
     fn create_test_engine() -> (StorageEngine, TempDir) {
@@ -316,6 +481,138 @@ mod tests {
         assert!(engine.get_node(graph_id, node.id).unwrap().is_none());
     }
 
+    #[test]
+    fn test_rollback_discards_pending_nodes() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let mut tx = engine.begin_transaction(graph_id).unwrap();
+        let alice = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let bob = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        tx.put_node(alice.clone()).unwrap();
+        tx.put_node(bob.clone()).unwrap();
+
+        // The transaction reads its own writes
+        assert_eq!(tx.get_node(alice.id).unwrap(), Some(alice.clone()));
+        assert_eq!(tx.get_node(bob.id).unwrap(), Some(bob.clone()));
+
+        tx.rollback().unwrap();
+
+        assert!(engine.get_node(graph_id, alice.id).unwrap().is_none());
+        assert!(engine.get_node(graph_id, bob.id).unwrap().is_none());
+        assert!(engine.get_nodes_by_label(graph_id, "Person").unwrap().is_empty());
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_commit_writes_final_state() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let stored = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &stored).unwrap();
+
+        let mut tx = engine.begin_transaction(graph_id).unwrap();
+        let temporary = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        tx.put_node(temporary.clone()).unwrap();
+        tx.delete_node(temporary.id).unwrap();
+        tx.put_node(Node::with_labels(stored.id, ["Company"])).unwrap();
+        let rel = Relationship::new(id_gen.next_relationship_id(), "KNOWS", stored.id, stored.id);
+        tx.put_relationship(rel.clone()).unwrap();
+
+        // Reads after a pending delete see the delete, not the stored version
+        assert!(tx.get_node(temporary.id).unwrap().is_none());
+        assert_eq!(tx.get_relationship(rel.id).unwrap(), Some(rel.clone()));
+        assert_eq!(tx.pending_operations(), 4);
+
+        tx.commit().unwrap();
+
+        assert!(engine.get_node(graph_id, temporary.id).unwrap().is_none());
+        assert!(engine.get_nodes_by_label(graph_id, "Person").unwrap().is_empty());
+        assert_eq!(engine.get_nodes_by_label(graph_id, "Company").unwrap().len(), 1);
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 0);
+        assert_eq!(engine.get_outgoing_relationships(graph_id, stored.id).unwrap(), vec![rel]);
+    }
+
+    #[test]
+    fn test_transaction_reads_from_start_snapshot() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let alice = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &alice).unwrap();
+
+        let mut tx = engine.begin_transaction(graph_id).unwrap();
+
+        // Committed after the transaction began, so invisible to it
+        let bob = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &bob).unwrap();
+        assert!(engine.delete_node(graph_id, alice.id).unwrap());
+
+        assert!(tx.get_node(bob.id).unwrap().is_none());
+        assert_eq!(tx.get_node(alice.id).unwrap(), Some(alice));
+    }
+
+    #[test]
+    fn test_failed_commit_writes_nothing() {
+        let temp_dir = TempDir::new().unwrap();
+        let options = StorageOptions::for_testing(temp_dir.path()).max_properties_per_entity(1);
+        let engine = StorageEngine::open(options).unwrap();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let mut tx = engine.begin_transaction(graph_id).unwrap();
+        let valid = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let mut too_large = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        too_large.properties.set("name", "Bob");
+        too_large.properties.set("city", "Paris");
+        tx.put_node(valid.clone()).unwrap();
+        tx.put_node(too_large).unwrap();
+
+        assert!(matches!(tx.commit(), Err(Error::QuotaExceeded(_))));
+        assert!(engine.get_node(graph_id, valid.id).unwrap().is_none());
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 0);
+    }
+
+    #[test]
+    fn test_rollback_leaves_counts_unchanged() {
+        let (engine, _dir) = create_test_engine();
+        let id_gen = IdGenerator::new();
+        let graph_id = GraphId::from_name("test");
+
+        let alice = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        let bob = Node::with_labels(id_gen.next_node_id(), ["Person"]);
+        engine.put_node(graph_id, &alice).unwrap();
+        engine.put_node(graph_id, &bob).unwrap();
+        let knows = Relationship::new(id_gen.next_relationship_id(), "KNOWS", alice.id, bob.id);
+        engine.put_relationship(graph_id, &knows).unwrap();
+
+        let mut tx = Transaction::new(engine.clone(), graph_id);
+        tx.put_node(Node::with_labels(id_gen.next_node_id(), ["Person", "Admin"])).unwrap();
+        tx.delete_node(alice.id).unwrap();
+        tx.delete_relationship(knows.id).unwrap();
+        tx.put_relationship(Relationship::new(id_gen.next_relationship_id(), "LIKES", bob.id, alice.id))
+            .unwrap();
+        tx.rollback().unwrap();
+
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 2);
+        assert_eq!(engine.label_count(graph_id, "Admin").unwrap(), 0);
+        assert_eq!(engine.relationship_type_count(graph_id, "KNOWS").unwrap(), 1);
+        assert_eq!(engine.relationship_type_count(graph_id, "LIKES").unwrap(), 0);
+
+        // The same operations committed do move the counts
+        let mut tx = Transaction::new(engine.clone(), graph_id);
+        tx.delete_node(alice.id).unwrap();
+        tx.delete_relationship(knows.id).unwrap();
+        tx.commit().unwrap();
+
+        assert_eq!(engine.label_count(graph_id, "Person").unwrap(), 1);
+        assert_eq!(engine.relationship_type_count(graph_id, "KNOWS").unwrap(), 0);
+    }
+
     #[test]
     fn test_transaction_delete() {
         let (engine, _dir) = create_test_engine();
@@ -354,7 +651,7 @@ mod tests {
         let tx2 = Transaction::new(engine.clone(), graph_id);
         tx2.commit().unwrap();
 
-        let tx3 = Transaction::new(engine, graph_id);
+        let tx3 = Transaction::new(engine.clone(), graph_id);
         tx3.rollback().unwrap();
     }
 }